@@ -0,0 +1,162 @@
+//! Session banner for shells inside the container. Each launch writes a
+//! short summary of the session to `~/.ai-pod/sessions/<id>.banner` and
+//! mounts it read-only at [`CONTAINER_PATH`]; a snippet added to the home
+//! volume's `~/.bashrc` at seeding prints it once when an interactive shell
+//! starts (`ai-pod run bash`, a shell the agent opens for you, ...).
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::config::{AppConfig, GlobalConfig, NetworkConfig, NotifyConfig};
+use crate::runtime::ContainerRuntime;
+
+/// Where the banner is mounted in the container.
+pub const CONTAINER_PATH: &str = "/etc/ai-pod/banner";
+
+/// First line of the `~/.bashrc` snippet, used to add it only once.
+const MARKER: &str = "# ai-pod session banner";
+
+/// Appended to `~/.bashrc`: print the banner in the first interactive
+/// shell of a session. A missing banner (turned off) prints nothing.
+pub fn profile_snippet() -> String {
+    format!(
+        "{MARKER}\nif [ -n \"$PS1\" ] && [ -z \"$AI_POD_BANNER_SHOWN\" ] && [ -r {CONTAINER_PATH} ]; then\n    cat {CONTAINER_PATH}\n    export AI_POD_BANNER_SHOWN=1\nfi\n"
+    )
+}
+
+/// Shell script that adds [`profile_snippet`] (passed as
+/// `$AI_POD_SNIPPET`) to `~/.bashrc` unless it's there already.
+pub const INSTALL_SCRIPT: &str = r##"grep -qF "# ai-pod session banner" "$HOME/.bashrc" 2>/dev/null || printf '\n%s' "$AI_POD_SNIPPET" >> "$HOME/.bashrc""##;
+
+/// What the banner shows.
+pub struct Info<'a> {
+    pub workspace: &'a Path,
+    pub container: &'a str,
+    pub title: Option<&'a str>,
+    pub notify: &'a NotifyConfig,
+    pub network: &'a NetworkConfig,
+    pub detach_keys: Option<&'a str>,
+    pub host_gateway: &'a str,
+}
+
+fn notify_line(notify: &NotifyConfig) -> String {
+    let mut line = match &notify.command {
+        Some(cmd) if !cmd.is_empty() => format!("via `{}`", cmd[0]),
+        _ => "desktop notifications".to_string(),
+    };
+    if !notify.events.is_empty() {
+        line.push_str(&format!(", only {}", notify.events.join("/")));
+    }
+    if let Some(quiet) = &notify.quiet_hours {
+        line.push_str(&format!(", quiet {}", quiet));
+    }
+    line
+}
+
+fn network_line(network: &NetworkConfig) -> String {
+    if !network.proxied() {
+        return "direct".to_string();
+    }
+    let mut parts = vec!["through the egress proxy".to_string()];
+    if network.audit {
+        parts.push("audited".to_string());
+    }
+    if let Some(bw) = &network.bandwidth {
+        parts.push(format!("capped at {}/s", bw));
+    }
+    if let Some(max) = network.max_connections {
+        parts.push(format!("at most {} connections", max));
+    }
+    parts.join(", ")
+}
+
+pub fn render(info: &Info) -> String {
+    let session = match info.title {
+        Some(title) => format!("{} ({})", title, info.container),
+        None => info.container.to_string(),
+    };
+    let detach = match info.detach_keys {
+        Some("") => "disabled".to_string(),
+        Some(keys) => keys.to_string(),
+        None => "ctrl-p,ctrl-q".to_string(),
+    };
+    [
+        format!("ai-pod session {}", session),
+        format!("  workspace  /app = {}", info.workspace.display()),
+        format!("  notify     {}", notify_line(info.notify)),
+        format!("  network    {}", network_line(info.network)),
+        format!("  detach     {}, then `ai-pod attach` to return", detach),
+        format!("  host       {} ($HOST_GATEWAY)", info.host_gateway),
+        String::new(),
+    ]
+    .join("\n")
+}
+
+/// Write the session's banner and return the `-v` args mounting it, or
+/// nothing when `banner` is turned off.
+pub fn mount_args(config: &AppConfig, global: &GlobalConfig, session_id: &str, info: &Info) -> Result<Vec<String>> {
+    if !global.banner.unwrap_or(true) {
+        return Ok(Vec::new());
+    }
+    let path = config.session_banner_file(session_id);
+    std::fs::create_dir_all(config.sessions_dir())
+        .context("Failed to create ~/.ai-pod/sessions/")?;
+    std::fs::write(&path, render(info)).context("Failed to write session banner")?;
+    Ok(vec![
+        "-v".to_string(),
+        format!("{}:{}:ro,z", path.display(), CONTAINER_PATH),
+    ])
+}
+
+/// Add the banner snippet to the home volume's `~/.bashrc`. Best-effort.
+pub fn install(rt: &ContainerRuntime, volume_mounts: &[String], image: &str) {
+    let _ = rt
+        .command()
+        .args(["run", "--rm"])
+        .args(volume_mounts)
+        .args(["-e", &format!("AI_POD_SNIPPET={}", profile_snippet())])
+        .args([image, "sh", "-c", INSTALL_SCRIPT])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_summarises_the_session() {
+        let notify = NotifyConfig {
+            quiet_hours: Some("22:00-08:00".into()),
+            ..Default::default()
+        };
+        let network = NetworkConfig {
+            audit: true,
+            bandwidth: Some("8mbit".into()),
+            ..Default::default()
+        };
+        let text = render(&Info {
+            workspace: Path::new("/src/api"),
+            container: "ai-pod-3f9c2a1b7d4e-1a2b3c4d",
+            title: Some("fix lint"),
+            notify: &notify,
+            network: &network,
+            detach_keys: None,
+            host_gateway: "host.containers.internal",
+        });
+        assert!(text.starts_with("ai-pod session fix lint (ai-pod-3f9c2a1b7d4e-1a2b3c4d)\n"));
+        assert!(text.contains("  workspace  /app = /src/api\n"));
+        assert!(text.contains("  notify     desktop notifications, quiet 22:00-08:00\n"));
+        assert!(text.contains("  network    through the egress proxy, audited, capped at 8mbit/s\n"));
+        assert!(text.contains("  detach     ctrl-p,ctrl-q, then"));
+        assert!(text.contains("host.containers.internal ($HOST_GATEWAY)"));
+    }
+
+    #[test]
+    fn snippet_starts_with_the_install_marker() {
+        assert!(profile_snippet().starts_with(MARKER));
+        assert!(INSTALL_SCRIPT.contains(MARKER));
+        assert!(profile_snippet().contains(CONTAINER_PATH));
+    }
+}
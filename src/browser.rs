@@ -0,0 +1,211 @@
+//! Opening links from the session in the host browser. Every session
+//! container gets a shim at `/usr/local/bin/xdg-open` and
+//! `/usr/local/bin/open` (and in `$BROWSER`) that posts the URL to the shared
+//! server's `/open_url`. The server opens it with the host's `open` or
+//! `xdg-open` when its host is in the `browser.allow` list, so docs and
+//! preview links the agent opens reach your browser instead of failing in a
+//! container without one.
+//!
+//! `localhost` isn't allowed by default: forwarded container ports (see
+//! [`crate::ports`]) would let the agent serve any page under the host's
+//! localhost origin. Each project may open [`MAX_OPENS`] URLs per minute.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::process::Stdio;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::{AppConfig, BrowserConfig, GlobalConfig};
+
+/// URLs a project may open per [`RATE_WINDOW`].
+const MAX_OPENS: usize = 5;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// When each project last opened URLs, for the rate limit.
+static OPENED: LazyLock<Mutex<HashMap<String, Vec<Instant>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Longest URL the server accepts.
+pub const MAX_URL_LEN: usize = 2048;
+
+/// Stand-in for `xdg-open`, `open` and `$BROWSER`: hand the URL to the
+/// shared server and report its answer.
+const SHIM: &str = r#"#!/bin/sh
+# ai-pod: open URLs in the host browser
+if [ -z "$1" ]; then
+    echo "usage: $(basename "$0") URL" >&2
+    exit 1
+fi
+url=$(printf '%s' "$1" | sed 's/\\/\\\\/g; s/"/\\"/g')
+out=$(curl -sS -X POST -H "X-Api-Key: $AI_POD_API_KEY" -H 'Content-Type: application/json' \
+    -d "{\"project_id\":\"$AI_POD_PROJECT_ID\",\"session_id\":\"$AI_POD_SESSION_ID\",\"url\":\"$url\"}" \
+    -w '\n%{http_code}' "$AI_POD_SERVER_URL/open_url") || exit 1
+[ "$(printf '%s' "$out" | tail -n 1)" = 200 ] && exit 0
+printf '%s\n' "$out" | sed '$d' >&2
+exit 1
+"#;
+
+/// Where the shim is mounted in the container.
+const TARGETS: &[&str] = &["/usr/local/bin/xdg-open", "/usr/local/bin/open"];
+
+/// Write the shim and return the `-v`/`-e` args that install it, unless
+/// `browser.enabled` is off.
+pub fn mount_args(config: &AppConfig, global: &GlobalConfig) -> Result<Vec<String>> {
+    if !global.browser.enabled.unwrap_or(true) {
+        return Ok(Vec::new());
+    }
+    let dir = config.config_dir.join("bin");
+    std::fs::create_dir_all(&dir).context("Failed to create ~/.ai-pod/bin/")?;
+    let shim = dir.join("xdg-open");
+    if std::fs::read_to_string(&shim).ok().as_deref() != Some(SHIM) {
+        std::fs::write(&shim, SHIM).context("Failed to write browser shim")?;
+    }
+    std::fs::set_permissions(&shim, std::fs::Permissions::from_mode(0o755))?;
+    let mut args = Vec::new();
+    for target in TARGETS {
+        args.push("-v".to_string());
+        args.push(format!("{}:{}:ro,z", shim.display(), target));
+    }
+    args.push("-e".to_string());
+    args.push(format!("BROWSER={}", TARGETS[0]));
+    Ok(args)
+}
+
+/// The lower-cased host of an `http`/`https` URL, or `None` for any other
+/// scheme. URLs with credentials (`user@host`) have no host, so a name
+/// before the `@` can't pass for an allowed one.
+fn host(url: &str) -> Option<String> {
+    let lower = url.to_ascii_lowercase();
+    let rest = lower.strip_prefix("https://").or_else(|| lower.strip_prefix("http://"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    if authority.contains('@') {
+        return None;
+    }
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next()?,
+        None => authority.split(':').next()?,
+    };
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// Whether `pattern` from `browser.allow` covers `host`: `*` covers every
+/// host, `*.example.com` the subdomains of `example.com`, anything else only
+/// itself.
+fn matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.')),
+        None => host == pattern,
+    }
+}
+
+/// Check `url` before opening it; the error is shown to the agent.
+pub fn check(url: &str, settings: &BrowserConfig) -> Result<(), String> {
+    if !settings.enabled.unwrap_or(true) {
+        return Err("opening URLs on the host is turned off (browser.enabled)".into());
+    }
+    if url.len() > MAX_URL_LEN || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("malformed URL".into());
+    }
+    let Some(host) = host(url) else {
+        return Err("only http and https URLs without credentials are opened".into());
+    };
+    let allowed = settings.allow.iter().any(|pattern| matches(pattern, &host));
+    if !allowed {
+        return Err(format!(
+            "{} is not in browser.allow; ask the user to open the URL or to allow it with `ai-pod config set browser.allow`",
+            host
+        ));
+    }
+    Ok(())
+}
+
+/// Count an open for `project_id`; `false` when it has opened
+/// [`MAX_OPENS`] URLs within the last minute already.
+pub fn take_slot(project_id: &str, now: Instant) -> bool {
+    let mut opened = OPENED.lock().unwrap_or_else(|e| e.into_inner());
+    opened.retain(|_, times| {
+        times.retain(|t| now.duration_since(*t) < RATE_WINDOW);
+        !times.is_empty()
+    });
+    let times = opened.entry(project_id.to_string()).or_default();
+    if times.len() >= MAX_OPENS {
+        return false;
+    }
+    times.push(now);
+    true
+}
+
+/// Open `url` in the host's default browser. Returns whether the opener
+/// succeeded.
+pub fn open_on_host(url: &str) -> bool {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_allows_listed_hosts_over_http_only() {
+        let settings = BrowserConfig {
+            allow: vec!["docs.rs".into(), "*.github.com".into()],
+            ..Default::default()
+        };
+        assert!(check("http://localhost:5173/", &settings).is_err());
+        assert!(check("http://127.0.0.1:5173/", &settings).is_err());
+        assert!(check("https://docs.rs/serde", &settings).is_ok());
+        assert!(check("https://gist.GitHub.com/x?y#z", &settings).is_ok());
+        assert!(check("https://github.com.evil.io/", &settings).is_err());
+        assert!(check("https://evilgithub.com/", &settings).is_err());
+        assert!(check("https://docs.rs@evil.io/", &settings).is_err());
+        assert!(check("file:///etc/passwd", &settings).is_err());
+        assert!(check("https://docs.rs/a b", &settings).is_err());
+        assert!(
+            check(
+                "https://example.com/",
+                &BrowserConfig {
+                    allow: vec!["*".into()],
+                    ..Default::default()
+                }
+            )
+            .is_ok()
+        );
+        assert!(
+            check(
+                "http://localhost/",
+                &BrowserConfig {
+                    enabled: Some(false),
+                    ..Default::default()
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn opens_are_limited_per_project_and_minute() {
+        let now = Instant::now();
+        for _ in 0..MAX_OPENS {
+            assert!(take_slot("rate-a", now));
+        }
+        assert!(!take_slot("rate-a", now));
+        assert!(take_slot("rate-b", now));
+        assert!(take_slot("rate-a", now + RATE_WINDOW));
+    }
+}
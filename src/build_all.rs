@@ -0,0 +1,220 @@
+//! `ai-pod build --all`: rebuild the images of every known workspace (aliased,
+//! recently launched, or with project state) a few at a time, e.g. after a
+//! base-image update. Each build's output goes to its own log file so
+//! parallel builds don't interleave, and a summary is printed at the end.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Mutex, mpsc};
+use std::time::{Duration, Instant};
+
+use crate::config::AppConfig;
+use crate::events::{self, Event, EventKind};
+use crate::image::{self, DOCKERFILE_NAME};
+use crate::plugins;
+use crate::runtime::ContainerRuntime;
+
+/// Directory under `~/.ai-pod/` holding one log per image.
+const LOG_DIR: &str = "build-logs";
+
+struct Outcome {
+    workspace: PathBuf,
+    image: String,
+    log: PathBuf,
+    elapsed: Duration,
+    result: Result<(), String>,
+}
+
+fn build_one(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    log_dir: &Path,
+    opts: &image::BuildOptions,
+) -> Outcome {
+    let image = image::image_name(workspace);
+    let log = log_dir.join(format!("{}.log", image));
+    let started = Instant::now();
+    let result = (|| -> Result<()> {
+        let dockerfile = workspace.join(DOCKERFILE_NAME);
+        let build_file = crate::provenance::pinned_dockerfile(config, &dockerfile, &image)?;
+        let mut cmd = image::build_command(rt, &build_file, workspace, &image, opts);
+        // Dry runs print the commands instead of logging them.
+        if !rt.dry_run {
+            crate::provenance::verify_base_images(config, &dockerfile, opts.insecure_image)?;
+            let file = std::fs::File::create(&log)
+                .with_context(|| format!("Failed to create {}", log.display()))?;
+            cmd.stdout(file.try_clone()?).stderr(file);
+        }
+        let status = cmd
+            .stdin(Stdio::null())
+            .status()
+            .with_context(|| format!("Failed to run {} build", rt.cmd()))?;
+        if !status.success() {
+            anyhow::bail!("{} build failed", rt.cmd());
+        }
+        if !rt.dry_run {
+            crate::provenance::lock_new_images(rt, &dockerfile)?;
+        }
+        Ok(())
+    })()
+    .map_err(|e| format!("{:#}", e));
+    Outcome {
+        workspace: workspace.to_path_buf(),
+        image,
+        log,
+        elapsed: started.elapsed(),
+        result,
+    }
+}
+
+/// Workspaces that have a Dockerfile to build, and those that are skipped.
+fn partition(workspaces: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    workspaces
+        .into_iter()
+        .partition(|ws| ws.join(DOCKERFILE_NAME).is_file())
+}
+
+/// Build every known workspace's image with at most `jobs` builds at once.
+/// The shared server must already be running (Dockerfiles fetch the agent
+/// installer from it).
+pub fn run(rt: &ContainerRuntime, config: &AppConfig, opts: &image::BuildOptions, jobs: usize) -> Result<()> {
+    let global = crate::config::GlobalConfig::load(config);
+    let opts = &opts.clone().with_config(&global.build).with_network(&global.network);
+    let (buildable, skipped) = partition(crate::registry::known_workspaces(config));
+    for ws in &skipped {
+        eprintln!(
+            "{} no {} in {}; skipped",
+            "Note:".yellow().bold(),
+            DOCKERFILE_NAME,
+            ws.display()
+        );
+    }
+    if buildable.is_empty() {
+        println!("{}", "No workspaces with an ai-pod.Dockerfile found.".yellow());
+        return Ok(());
+    }
+
+    let log_dir = config.config_dir.join(LOG_DIR);
+    std::fs::create_dir_all(&log_dir)
+        .with_context(|| format!("Failed to create {}", log_dir.display()))?;
+
+    let total = buildable.len();
+    let jobs = jobs.clamp(1, total);
+    eprintln!(
+        "{} {} images, {} at a time",
+        "Building".blue().bold(),
+        total,
+        jobs
+    );
+
+    let queue = Mutex::new(buildable.into_iter().collect::<VecDeque<_>>());
+    let (tx, rx) = mpsc::channel();
+    let _keep_alive = image::ServerKeepAlive::start();
+    let mut outcomes = Vec::new();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            let tx = tx.clone();
+            let (queue, log_dir) = (&queue, &log_dir);
+            scope.spawn(move || {
+                loop {
+                    let Some(ws) = queue.lock().unwrap().pop_front() else {
+                        break;
+                    };
+                    if tx.send(build_one(rt, config, &ws, log_dir, opts)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        // Report each build as it finishes, not in queue order.
+        for outcome in rx {
+            report(config, rt, &outcome);
+            outcomes.push(outcome);
+        }
+    });
+
+    let failed: Vec<&Outcome> = outcomes.iter().filter(|o| o.result.is_err()).collect();
+    println!();
+    println!(
+        "{} of {} images built.",
+        total - failed.len(),
+        total
+    );
+    if !failed.is_empty() {
+        for o in &failed {
+            println!("  {} {} (log: {})", "✗".red(), o.workspace.display(), o.log.display());
+        }
+        anyhow::bail!("{} of {} builds failed", failed.len(), total);
+    }
+    Ok(())
+}
+
+fn report(config: &AppConfig, rt: &ContainerRuntime, o: &Outcome) {
+    let elapsed = format!("{}s", o.elapsed.as_secs());
+    match &o.result {
+        Ok(()) => {
+            println!(
+                "  {} {} {} {}",
+                "✓".green(),
+                o.workspace.display(),
+                o.image.dimmed(),
+                elapsed.dimmed()
+            );
+            if !rt.dry_run {
+                let event = Event::new(EventKind::Build, Some(&o.workspace)).image(&o.image);
+                events::record(&config.config_dir, &event);
+                plugins::run_all(&config.config_dir, plugins::Hook::PostBuild, &event);
+            }
+        }
+        Err(e) => println!(
+            "  {} {}: {} {}",
+            "✗".red(),
+            o.workspace.display(),
+            e,
+            elapsed.dimmed()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn partition_skips_workspaces_without_dockerfile() {
+        let dir = TempDir::new().unwrap();
+        let with = dir.path().join("with");
+        let without = dir.path().join("without");
+        std::fs::create_dir_all(&with).unwrap();
+        std::fs::create_dir_all(&without).unwrap();
+        std::fs::write(with.join(DOCKERFILE_NAME), "FROM alpine\n").unwrap();
+        let (buildable, skipped) = partition(vec![with.clone(), without.clone()]);
+        assert_eq!(buildable, vec![with]);
+        assert_eq!(skipped, vec![without]);
+    }
+
+    #[test]
+    #[ignore = "runs the container engine"]
+    fn build_one_reports_failure_with_log_path() {
+        let dir = TempDir::new().unwrap();
+        let ws = dir.path().join("proj");
+        std::fs::create_dir_all(&ws).unwrap();
+        let rt = ContainerRuntime {
+            kind: crate::runtime::RuntimeKind::Podman,
+            dry_run: false,
+        };
+        let config = crate::config::test_config(&dir);
+        let outcome = build_one(&rt, &config, &ws, dir.path(), &image::BuildOptions::default());
+        assert_eq!(outcome.image, image::image_name(&ws));
+        assert_eq!(outcome.log, dir.path().join(format!("{}.log", outcome.image)));
+        // Fails either way: podman may be missing here, and there's no
+        // Dockerfile to build.
+        assert!(outcome.result.is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Compact progress view for image builds. The runtime's output is parsed
+//! into Dockerfile steps, shown as a progress bar with the current step and
+//! a line per finished step with its elapsed time. The full output is kept
+//! and printed if the build fails, so nothing is lost compared to the raw
+//! view (`--verbose`).
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// One Dockerfile instruction as announced in the build output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// 1-based position among the stage's steps.
+    pub index: u64,
+    pub total: u64,
+    pub instruction: String,
+}
+
+/// Recognise a step header in any of the build output formats:
+/// Podman/Buildah `STEP 2/7: RUN ...`, the legacy Docker builder
+/// `Step 2/7 : RUN ...`, and BuildKit plain progress `#6 [2/7] RUN ...`
+/// (also `#6 [stage 2/7] RUN ...` in multi-stage builds).
+pub fn parse_step(line: &str) -> Option<Step> {
+    let line = line.trim();
+    let (counter, instruction) = if let Some(rest) = line.strip_prefix("STEP ") {
+        rest.split_once(": ")?
+    } else if let Some(rest) = line.strip_prefix("Step ") {
+        rest.split_once(" : ")?
+    } else if line.starts_with('#') {
+        let (_, rest) = line.split_once(" [")?;
+        let (bracket, instruction) = rest.split_once("] ")?;
+        (bracket.rsplit(' ').next()?, instruction)
+    } else {
+        return None;
+    };
+    let (index, total) = counter.trim().split_once('/')?;
+    Some(Step {
+        index: index.parse().ok()?,
+        total: total.parse().ok()?,
+        instruction: instruction.trim().to_string(),
+    })
+}
+
+/// Shorten an instruction to fit on one progress line.
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let cut: String = s.chars().take(max.saturating_sub(1)).collect();
+        format!("{}…", cut)
+    }
+}
+
+fn format_elapsed(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+    if secs < 60.0 {
+        format!("{:.1}s", secs)
+    } else {
+        format!("{}m{:02}s", d.as_secs() / 60, d.as_secs() % 60)
+    }
+}
+
+fn forward_lines(reader: impl Read + Send + 'static, tx: mpsc::Sender<String>) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(|l| l.ok()) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Run the build command with the progress view instead of its raw output.
+pub fn run(mut cmd: Command) -> Result<ExitStatus> {
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start image build")?;
+    let (tx, rx) = mpsc::channel();
+    if let Some(out) = child.stdout.take() {
+        forward_lines(out, tx.clone());
+    }
+    if let Some(err) = child.stderr.take() {
+        forward_lines(err, tx);
+    }
+
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner:.blue} [{pos}/{len}] {msg} {elapsed:.dim}")
+            .expect("valid progress template"),
+    );
+    bar.enable_steady_tick(Duration::from_millis(120));
+    bar.set_message("Preparing build context");
+
+    let mut output = Vec::new();
+    let mut current: Option<(Step, Instant)> = None;
+    let finish_step = |bar: &ProgressBar, current: &Option<(Step, Instant)>| {
+        if let Some((step, started)) = current {
+            bar.println(format!(
+                "  {} [{}/{}] {} {}",
+                "✓".green(),
+                step.index,
+                step.total,
+                truncate(&step.instruction, 70),
+                format_elapsed(started.elapsed()).dimmed()
+            ));
+        }
+    };
+    // Both reader threads hang up when the build exits.
+    for line in rx {
+        if let Some(step) = parse_step(&line) {
+            // BuildKit may announce steps out of order or repeat one when it
+            // runs stages in parallel; only move forward on a new step.
+            let is_new = current
+                .as_ref()
+                .is_none_or(|(c, _)| c.index != step.index || c.total != step.total);
+            if is_new {
+                finish_step(&bar, &current);
+                bar.set_length(step.total);
+                bar.set_position(step.index.saturating_sub(1));
+                bar.set_message(truncate(&step.instruction, 60));
+                current = Some((step, Instant::now()));
+            }
+        }
+        output.push(line);
+    }
+    let status = child.wait().context("Failed to wait for image build")?;
+    if status.success() {
+        finish_step(&bar, &current);
+        bar.finish_and_clear();
+    } else {
+        bar.abandon();
+        for line in &output {
+            eprintln!("{}", line);
+        }
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(index: u64, total: u64, instruction: &str) -> Option<Step> {
+        Some(Step {
+            index,
+            total,
+            instruction: instruction.into(),
+        })
+    }
+
+    #[test]
+    fn parse_step_recognises_all_builders() {
+        assert_eq!(parse_step("STEP 2/7: RUN apk add git"), step(2, 7, "RUN apk add git"));
+        assert_eq!(parse_step("Step 1/3 : FROM alpine:3"), step(1, 3, "FROM alpine:3"));
+        assert_eq!(
+            parse_step("#6 [2/5] RUN npm ci"),
+            step(2, 5, "RUN npm ci")
+        );
+        assert_eq!(
+            parse_step("#9 [builder 3/4] COPY . ."),
+            step(3, 4, "COPY . .")
+        );
+    }
+
+    #[test]
+    fn parse_step_ignores_other_output() {
+        assert_eq!(parse_step("--> 3f2a9c1b7e4d"), None);
+        assert_eq!(parse_step("#6 0.512 fetch https://dl-cdn.alpinelinux.org"), None);
+        assert_eq!(parse_step("#6 [internal] load build definition"), None);
+        assert_eq!(parse_step("COMMIT api-3f2a9c"), None);
+    }
+
+    #[test]
+    fn truncate_and_elapsed_formatting() {
+        assert_eq!(truncate("RUN make", 20), "RUN make");
+        assert_eq!(truncate("RUN apk add --no-cache git", 10), "RUN apk a…");
+        assert_eq!(format_elapsed(Duration::from_millis(1500)), "1.5s");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m05s");
+    }
+}
@@ -0,0 +1,186 @@
+//! Host-side `ai-pod cache` subcommand: enable, disable, list and clear the
+//! package-manager cache volumes shared by every workspace's containers.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::config::{AppConfig, GlobalConfig};
+use crate::runtime::ContainerRuntime;
+
+/// A package-manager cache that can be backed by a named volume shared
+/// across all workspaces, so dependency downloads done by the agent in one
+/// project are reused by the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CacheKind {
+    Npm,
+    Cargo,
+    Pip,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 3] = [CacheKind::Npm, CacheKind::Cargo, CacheKind::Pip];
+
+    /// Stable string form, used in `~/.ai-pod/config.json` and volume names.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheKind::Npm => "npm",
+            CacheKind::Cargo => "cargo",
+            CacheKind::Pip => "pip",
+        }
+    }
+
+    /// Parse from a config string. Case-insensitive, trims whitespace;
+    /// returns `None` for anything unrecognized.
+    pub fn from_value(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "npm" => Some(CacheKind::Npm),
+            "cargo" => Some(CacheKind::Cargo),
+            "pip" => Some(CacheKind::Pip),
+            _ => None,
+        }
+    }
+
+    /// Directory the cache volume is mounted on, relative to the container
+    /// user's home.
+    pub fn home_path(self) -> &'static str {
+        match self {
+            CacheKind::Npm => ".npm",
+            CacheKind::Cargo => ".cargo/registry",
+            CacheKind::Pip => ".cache/pip",
+        }
+    }
+
+    /// Directory inside the container the cache volume is mounted on, for
+    /// a user with home `home`.
+    pub fn container_path(self, home: &str) -> String {
+        format!("{}/{}", home, self.home_path())
+    }
+
+    /// Named volume backing this cache. Not workspace-scoped on purpose, so it
+    /// never matches the `ai-pod-{hash}-` prefix that `clean` removes.
+    pub fn volume_name(self) -> String {
+        format!("ai-pod-cache-{}", self.as_str())
+    }
+}
+
+/// Caches enabled in the global config, in config order. Unknown names (from
+/// a hand-edited or newer config) are skipped with a stderr warning.
+pub(crate) fn enabled_caches(global: &GlobalConfig) -> Vec<CacheKind> {
+    let mut out = Vec::new();
+    for name in &global.caches {
+        match CacheKind::from_value(name) {
+            Some(kind) if !out.contains(&kind) => out.push(kind),
+            Some(_) => {}
+            None => eprintln!(
+                "{} unknown cache '{}' in config; skipping",
+                "warning:".yellow().bold(),
+                name
+            ),
+        }
+    }
+    out
+}
+
+pub fn run_list(rt: &ContainerRuntime, config: &AppConfig) -> Result<()> {
+    let enabled = enabled_caches(&GlobalConfig::load(config));
+    for kind in CacheKind::ALL {
+        let state = if enabled.contains(&kind) {
+            "enabled".green().to_string()
+        } else {
+            "disabled".dimmed().to_string()
+        };
+        let vol = kind.volume_name();
+        let present = if crate::container::volume_exists(rt, &vol)? {
+            vol
+        } else {
+            "(no volume yet)".dimmed().to_string()
+        };
+        println!(
+            "{:<8} {:<30} {:<18} {}",
+            kind.as_str(),
+            format!("~/{}", kind.home_path()),
+            state,
+            present
+        );
+    }
+    Ok(())
+}
+
+pub fn run_enable(config: &AppConfig, kind: CacheKind) -> Result<()> {
+    let mut gc = GlobalConfig::load(config);
+    if !gc.enable_cache(kind.as_str()) {
+        println!("Already enabled: {}", kind.as_str());
+        return Ok(());
+    }
+    gc.save(config)?;
+    println!(
+        "{} {} → ~/{} (applies on next launch)",
+        "Enabled cache:".green().bold(),
+        kind.as_str(),
+        kind.home_path()
+    );
+    Ok(())
+}
+
+/// Stop mounting a cache. The volume itself is kept; use `cache clear` to
+/// reclaim the space.
+pub fn run_disable(config: &AppConfig, kind: CacheKind) -> Result<()> {
+    let mut gc = GlobalConfig::load(config);
+    if !gc.disable_cache(kind.as_str()) {
+        println!("Not enabled: {}", kind.as_str());
+        return Ok(());
+    }
+    gc.save(config)?;
+    println!("{} {}", "Disabled cache:".green().bold(), kind.as_str());
+    Ok(())
+}
+
+/// Delete the volume for one cache, or for every known cache when `kind` is
+/// `None`. Enabled caches are re-created empty on the next launch.
+pub fn run_clear(rt: &ContainerRuntime, kind: Option<CacheKind>) -> Result<()> {
+    let kinds: Vec<CacheKind> = match kind {
+        Some(k) => vec![k],
+        None => CacheKind::ALL.to_vec(),
+    };
+    for k in kinds {
+        crate::container::remove_volume(rt, &k.volume_name())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_value_round_trips_as_str() {
+        for kind in CacheKind::ALL {
+            assert_eq!(CacheKind::from_value(kind.as_str()), Some(kind));
+        }
+        assert_eq!(CacheKind::from_value(" NPM "), Some(CacheKind::Npm));
+        assert_eq!(CacheKind::from_value("gradle"), None);
+    }
+
+    #[test]
+    fn volume_names_are_not_workspace_scoped() {
+        assert_eq!(CacheKind::Cargo.volume_name(), "ai-pod-cache-cargo");
+    }
+
+    #[test]
+    fn container_paths_live_under_container_home() {
+        for kind in CacheKind::ALL {
+            assert!(kind.container_path(crate::container::CONTAINER_HOME).starts_with("/home/ai-pod/"));
+            assert!(!kind.home_path().starts_with('/'));
+        }
+    }
+
+    #[test]
+    fn enabled_caches_skips_unknown_and_duplicates() {
+        let gc = GlobalConfig {
+            caches: vec!["pip".into(), "gradle".into(), "npm".into(), "pip".into()],
+            ..Default::default()
+        };
+        assert_eq!(enabled_caches(&gc), vec![CacheKind::Pip, CacheKind::Npm]);
+    }
+}
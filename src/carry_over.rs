@@ -0,0 +1,171 @@
+//! Carrying the agent's environment over a home volume regeneration. When
+//! the image changes, the home volume is removed and seeded again (see
+//! `ensure_home_volume`), which would silently drop shell histories and the
+//! tools the agent installed for itself. Before the old volume goes, its
+//! history files and `.npmrc` are copied into the state volume together
+//! with a manifest of the user's global npm packages and `pip --user`
+//! packages; once the new volume is seeded, the files are put back and the
+//! packages installed again.
+//!
+//! Only packages under the user's home are recorded: whatever the image
+//! installs system-wide comes back with the image.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::process::Stdio;
+
+use crate::container::HomeVolumes;
+use crate::runtime::ContainerRuntime;
+
+/// Where the carried-over files wait in the state volume, relative to the
+/// home directory.
+const DIR: &str = ".claude/ai-pod/carry-over";
+
+/// Files copied from the old home to the new one unless it already has them.
+const FILES: &[&str] = &[
+    ".bash_history",
+    ".zsh_history",
+    ".python_history",
+    ".node_repl_history",
+    ".psql_history",
+    ".sqlite_history",
+    ".npmrc",
+];
+
+/// Run with the home directory, the carry-over directory and [`FILES`] as
+/// arguments, against the old home volume.
+const SAVE: &str = r#"home=$1 out=$2
+shift 2
+rm -rf "$out" && mkdir -p "$out" || exit 1
+for f in "$@"; do
+    [ -s "$home/$f" ] && cp -p "$home/$f" "$out/$f"
+done
+if command -v npm >/dev/null 2>&1; then
+    prefix=$(npm prefix -g 2>/dev/null)
+    case "$prefix" in
+    "$home"/*)
+        npm ls -g --depth=0 --parseable --long 2>/dev/null | tail -n +2 | cut -d: -f2 | grep . > "$out/npm.txt"
+        printf '%s\n' "$prefix" > "$out/npm-prefix"
+        ;;
+    esac
+fi
+python3 -m pip list --user --format=freeze 2>/dev/null | grep '==' > "$out/pip.txt"
+for f in "$out"/*.txt; do
+    [ -s "$f" ] || rm -f "$f"
+done
+exit 0
+"#;
+
+/// Run with the home directory and the carry-over directory as arguments,
+/// against the new home volume. Reports one `file`, `npm`, `pip` or
+/// `failed` line per thing restored (see [`report`]).
+const RESTORE: &str = r#"home=$1 dir=$2
+[ -d "$dir" ] || exit 0
+for f in "$dir"/.[!.]*; do
+    [ -f "$f" ] || continue
+    name=$(basename "$f")
+    [ -e "$home/$name" ] || { cp -p "$f" "$home/$name" && echo "file $name"; }
+done
+if [ -s "$dir/npm.txt" ] && [ -s "$dir/npm-prefix" ]; then
+    if npm install -g --prefix "$(cat "$dir/npm-prefix")" $(cat "$dir/npm.txt") >/dev/null 2>&1; then
+        echo "npm $(tr '\n' ' ' < "$dir/npm.txt")"
+    else
+        echo "failed npm install -g $(tr '\n' ' ' < "$dir/npm.txt")"
+    fi
+fi
+if [ -s "$dir/pip.txt" ]; then
+    if PIP_BREAK_SYSTEM_PACKAGES=1 python3 -m pip install --user -q -r "$dir/pip.txt" >/dev/null 2>&1; then
+        echo "pip $(tr '\n' ' ' < "$dir/pip.txt")"
+    else
+        echo "failed pip install --user $(tr '\n' ' ' < "$dir/pip.txt")"
+    fi
+fi
+rm -rf "$dir"
+"#;
+
+fn run(rt: &ContainerRuntime, volumes: &HomeVolumes, image: &str, script: &str, args: &[&str]) -> Result<String> {
+    let home = volumes.dir();
+    let dir = format!("{}/{}", home, DIR);
+    let out = rt
+        .command()
+        .args(["run", "--rm"])
+        .args(volumes.user.run_args())
+        .args(volumes.mount_args())
+        .args(["--entrypoint", "sh", image, "-c", script, "sh", &home, &dir])
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .context("Failed to run carry-over container")?;
+    if !out.status.success() {
+        anyhow::bail!("carry-over container exited with {}", out.status);
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Record the old home volume's histories and user-installed packages in
+/// the state volume. Runs in `image`, the new one: the old image may be
+/// gone already.
+pub(crate) fn save(rt: &ContainerRuntime, volumes: &HomeVolumes, image: &str) -> Result<()> {
+    run(rt, volumes, image, SAVE, FILES).map(|_| ())
+}
+
+/// Put what [`save`] recorded into the freshly seeded home volume and tell
+/// the user what came back and what didn't.
+pub(crate) fn restore(rt: &ContainerRuntime, volumes: &HomeVolumes, image: &str) {
+    match run(rt, volumes, image, RESTORE, &[]) {
+        Ok(out) => {
+            for line in report(&out) {
+                eprintln!("{}", line);
+            }
+        }
+        Err(e) => eprintln!(
+            "{} could not restore shell history and tools into the new home volume: {}",
+            "warning:".yellow().bold(),
+            e
+        ),
+    }
+}
+
+/// Turn the restore script's output into messages for the user.
+fn report(out: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut lines = Vec::new();
+    for line in out.lines() {
+        let Some((kind, rest)) = line.split_once(' ') else {
+            continue;
+        };
+        let rest = rest.trim();
+        match kind {
+            "file" => files.push(rest),
+            "npm" => lines.push(format!("{} {}", "Reinstalled npm packages:".blue().bold(), rest)),
+            "pip" => lines.push(format!("{} {}", "Reinstalled pip packages:".blue().bold(), rest)),
+            "failed" => lines.push(format!(
+                "{} could not reinstall packages from the old home volume; run `{}` in the session",
+                "warning:".yellow().bold(),
+                rest
+            )),
+            _ => {}
+        }
+    }
+    if !files.is_empty() {
+        lines.insert(0, format!("{} {}", "Carried over:".blue().bold(), files.join(", ")));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_summarises_restore_output() {
+        let out = "file .bash_history\nfile .npmrc\nnpm typescript@5.4.5 \nfailed pip install --user httpie==3.2.2 \n";
+        let lines = report(out);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("Carried over") && lines[0].ends_with(".bash_history, .npmrc"));
+        assert!(lines[1].contains("npm") && lines[1].ends_with("typescript@5.4.5"));
+        assert!(lines[2].contains("`pip install --user httpie==3.2.2`"));
+        assert!(report("").is_empty());
+    }
+}
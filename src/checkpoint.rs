@@ -0,0 +1,376 @@
+//! `ai-pod checkpoint` / `ai-pod resume` (experimental): freeze a running
+//! session with CRIU via `podman container checkpoint --export` and bring it
+//! back later — after a host reboot, or on another machine — with
+//! `podman container restore --import`. Unlike a snapshot, the processes
+//! themselves (the agent mid-conversation) survive, not just the filesystem.
+//!
+//! Each checkpoint is `~/.ai-pod/checkpoints/<name>.tar.gz` (the container,
+//! its memory and its named volumes) plus `<name>.json` describing the
+//! session. The record is written before the checkpoint is taken so the
+//! launching CLI, whose container exits at that moment, knows to keep the
+//! session's state for the resume.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use crate::config::{AppConfig, GlobalConfig, SessionState};
+use crate::hooks::HookContext;
+use crate::runtime::{ContainerRuntime, RuntimeKind};
+use crate::workspace::{container_prefix, session_id_from_container_name, workspace_hash};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub workspace: PathBuf,
+    pub container: String,
+    pub session_id: String,
+    /// Unix seconds.
+    pub created: u64,
+}
+
+fn archive_path(config: &AppConfig, name: &str) -> PathBuf {
+    config.checkpoints_dir().join(format!("{name}.tar.gz"))
+}
+
+fn record_path(config: &AppConfig, name: &str) -> PathBuf {
+    config.checkpoints_dir().join(format!("{name}.json"))
+}
+
+/// All checkpoint records, newest first.
+pub fn list(config: &AppConfig) -> Vec<(String, Checkpoint)> {
+    let Ok(entries) = std::fs::read_dir(config.checkpoints_dir()) else {
+        return Vec::new();
+    };
+    let mut out: Vec<(String, Checkpoint)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            let raw = std::fs::read_to_string(&path).ok()?;
+            Some((name, serde_json::from_str(&raw).ok()?))
+        })
+        .collect();
+    out.sort_by(|a, b| b.1.created.cmp(&a.1.created).then_with(|| a.0.cmp(&b.0)));
+    out
+}
+
+/// Whether `session_id` has been checkpointed; its state must then outlive
+/// the container.
+pub fn is_checkpointed(config: &AppConfig, session_id: &str) -> bool {
+    list(config).iter().any(|(_, c)| c.session_id == session_id)
+}
+
+/// CRIU needs root, so rootless Podman can't checkpoint, and Docker's
+/// checkpoint support is a separate experimental daemon feature.
+fn unsupported_reason(kind: RuntimeKind, rootless: Option<bool>) -> Option<&'static str> {
+    match (kind, rootless) {
+        (RuntimeKind::Docker, _) => Some(
+            "Checkpointing is only supported with Podman. Run ai-pod with --runtime podman.",
+        ),
+        (RuntimeKind::Podman, Some(true)) => Some(
+            "Checkpointing needs rootful Podman: CRIU can't checkpoint rootless containers.\n\
+             Run the session (and this command) as root, e.g. with `sudo ai-pod`.",
+        ),
+        _ => None,
+    }
+}
+
+fn ensure_supported(rt: &ContainerRuntime) -> Result<()> {
+    let rootless = if rt.dry_run || rt.kind != RuntimeKind::Podman {
+        None
+    } else {
+        rt.command()
+            .args(["info", "--format", "{{.Host.Security.Rootless}}"])
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "true")
+    };
+    if let Some(reason) = unsupported_reason(rt.kind, rootless) {
+        anyhow::bail!("{}", reason);
+    }
+    Ok(())
+}
+
+/// Podman's error plus a hint when CRIU itself is the problem.
+fn failure(what: &str, stderr: &str) -> anyhow::Error {
+    let stderr = stderr.trim();
+    let hint = if stderr.to_lowercase().contains("criu") {
+        "\nCheck that CRIU is installed and working: `sudo criu check`."
+    } else {
+        ""
+    };
+    anyhow::anyhow!("{} failed: {}{}", what, stderr, hint)
+}
+
+fn write_record(path: &Path, record: &Checkpoint) -> Result<()> {
+    let json = serde_json::to_string_pretty(record)?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Checkpoint the workspace's running session and stop it. With several
+/// sessions running, the user picks one.
+pub fn create(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path, name: Option<&str>) -> Result<()> {
+    ensure_supported(rt)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let name = match name {
+        Some(n) => n.to_string(),
+        None => crate::snapshot::utc_stamp(now),
+    };
+    crate::snapshot::validate_name(&name)?;
+    if record_path(config, &name).exists() {
+        anyhow::bail!("Checkpoint '{}' already exists. Delete it with `ai-pod checkpoint --rm {}`.", name, name);
+    }
+
+    let containers = crate::container::containers_for_prefix(rt, &container_prefix(workspace), true)?;
+    let container = match containers.len() {
+        0 => anyhow::bail!(
+            "No running session for {}. Launch one with `ai-pod` first.",
+            workspace.display()
+        ),
+        1 => containers[0].clone(),
+        _ => {
+            let sel = dialoguer::Select::new()
+                .with_prompt("Select session to checkpoint")
+                .items(&containers)
+                .default(0)
+                .interact()
+                .context("Selection cancelled")?;
+            containers[sel].clone()
+        }
+    };
+    let session_id = session_id_from_container_name(&container)
+        .with_context(|| format!("{} is not an ai-pod session container", container))?;
+
+    std::fs::create_dir_all(config.checkpoints_dir()).context("Failed to create ~/.ai-pod/checkpoints/")?;
+    let record = Checkpoint {
+        workspace: workspace.to_path_buf(),
+        container: container.clone(),
+        session_id,
+        created: now,
+    };
+    if !rt.dry_run {
+        write_record(&record_path(config, &name), &record)?;
+    }
+
+    eprintln!("{} {} (this stops the session)", "Checkpointing:".blue().bold(), container);
+    // Session containers run with --rm, which Podman only allows to be
+    // checkpointed into an export archive.
+    let archive = archive_path(config, &name);
+    let output = rt
+        .command()
+        .args(["container", "checkpoint", "--tcp-established", "--export"])
+        .arg(&archive)
+        .arg(&container)
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("Failed to run {} container checkpoint", rt.cmd()))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(record_path(config, &name));
+        let _ = std::fs::remove_file(&archive);
+        return Err(failure("Checkpoint", &String::from_utf8_lossy(&output.stderr)));
+    }
+    println!(
+        "{} checkpoint '{}' ({}). Resume with `ai-pod resume {}`.",
+        "Saved".green().bold(),
+        name,
+        archive.display(),
+        name
+    );
+    Ok(())
+}
+
+pub fn remove(config: &AppConfig, name: &str) -> Result<()> {
+    crate::snapshot::validate_name(name)?;
+    let record = record_path(config, name);
+    if !record.exists() {
+        anyhow::bail!("No checkpoint '{}'", name);
+    }
+    let _ = std::fs::remove_file(archive_path(config, name));
+    std::fs::remove_file(&record).with_context(|| format!("Failed to remove {}", record.display()))?;
+    println!("{} {}", "Removed checkpoint:".red().bold(), name);
+    Ok(())
+}
+
+pub fn print_list(config: &AppConfig) {
+    let checkpoints = list(config);
+    if checkpoints.is_empty() {
+        println!("{}", "No checkpoints.".yellow());
+        return;
+    }
+    for (name, c) in checkpoints {
+        println!("{:<24} {}", name, c.workspace.display().to_string().dimmed());
+    }
+}
+
+/// The checkpoint `ai-pod resume` restores: the named one, or one picked
+/// from all checkpoints (newest first).
+pub fn resolve(config: &AppConfig, name: Option<&str>) -> Result<(String, Checkpoint)> {
+    let mut checkpoints = list(config);
+    if let Some(n) = name {
+        crate::snapshot::validate_name(n)?;
+        return checkpoints
+            .into_iter()
+            .find(|(c, _)| c == n)
+            .with_context(|| format!("No checkpoint '{}' in {}", n, config.checkpoints_dir().display()));
+    }
+    if checkpoints.is_empty() {
+        anyhow::bail!("No checkpoints. Create one with `ai-pod checkpoint`.");
+    }
+    let items: Vec<String> = checkpoints
+        .iter()
+        .map(|(n, c)| format!("{:<24} {}", n, c.workspace.display()))
+        .collect();
+    let sel = dialoguer::Select::new()
+        .with_prompt("Select checkpoint to resume")
+        .items(&items)
+        .default(0)
+        .interact()
+        .context("Selection cancelled")?;
+    Ok(checkpoints.swap_remove(sel))
+}
+
+/// Restore a checkpoint and attach to it. The checkpoint is used up: once
+/// restored, its record and archive are removed.
+pub fn resume(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    name: &str,
+    checkpoint: &Checkpoint,
+    detach_keys: Option<&str>,
+) -> Result<()> {
+    ensure_supported(rt)?;
+    // Bind mounts are restored as they were: the workspace must be at the
+    // same path, e.g. on the machine the checkpoint was copied to.
+    if !rt.dry_run && !checkpoint.workspace.is_dir() {
+        anyhow::bail!(
+            "Workspace {} doesn't exist on this machine; it must be at the same path as when the checkpoint was taken.",
+            checkpoint.workspace.display()
+        );
+    }
+    // The session context is bind-mounted read-only; recreate it when the
+    // checkpoint was copied over from another machine.
+    let context_file = config.session_context_file(&checkpoint.session_id);
+    if !rt.dry_run && !context_file.exists() {
+        std::fs::create_dir_all(config.sessions_dir()).context("Failed to create ~/.ai-pod/sessions/")?;
+        std::fs::write(&context_file, "").context("Failed to write session context")?;
+    }
+    SessionState {
+        runtime: rt.kind,
+        tmux: crate::tmux::current_target(),
+        title: None,
+        workspace: Some(checkpoint.workspace.clone()),
+    }
+    .save(config, &checkpoint.session_id)?;
+
+    eprintln!("{} {}", "Resuming:".blue().bold(), name);
+    let output = rt
+        .command()
+        .args(["container", "restore", "--tcp-established", "--import"])
+        .arg(archive_path(config, name))
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("Failed to run {} container restore", rt.cmd()))?;
+    if !output.status.success() {
+        return Err(failure("Restore", &String::from_utf8_lossy(&output.stderr)));
+    }
+    let image = rt
+        .command()
+        .args(["inspect", "--format", "{{.ImageName}}", &checkpoint.container])
+        .stderr(Stdio::null())
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default();
+    if !rt.dry_run {
+        let _ = std::fs::remove_file(archive_path(config, name));
+        let _ = std::fs::remove_file(record_path(config, name));
+    }
+
+    crate::container::attach_to(rt, config, &checkpoint.container, detach_keys)?;
+
+    // Detaching leaves the session running, and a session checkpointed again
+    // keeps its state; otherwise it has ended, so finish it as a regular
+    // launch would.
+    let running = crate::container::containers_for_prefix(rt, &container_prefix(&checkpoint.workspace), true)?;
+    if rt.dry_run || running.contains(&checkpoint.container) || is_checkpointed(config, &checkpoint.session_id) {
+        return Ok(());
+    }
+    crate::service::cleanup_services_for_session(rt, &checkpoint.session_id);
+    let _ = std::fs::remove_file(config.session_state_file(&checkpoint.session_id));
+    let _ = std::fs::remove_file(&context_file);
+    let _ = std::fs::remove_file(config.session_banner_file(&checkpoint.session_id));
+    // `--rm` has removed the container by now, so its exit code is gone.
+    let ctx = HookContext {
+        workspace: &checkpoint.workspace,
+        project_id: &workspace_hash(&checkpoint.workspace),
+        session_id: &checkpoint.session_id,
+        container_name: &checkpoint.container,
+        image: &image,
+        exit_code: None,
+    };
+    crate::container::record_stop(config, &ctx);
+    crate::hooks::run_post_exit(&GlobalConfig::load(config).hooks, &ctx);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use tempfile::TempDir;
+
+    fn record(session_id: &str, created: u64) -> Checkpoint {
+        Checkpoint {
+            workspace: PathBuf::from("/src/api"),
+            container: format!("ai-pod-0123456789ab-{session_id}"),
+            session_id: session_id.to_string(),
+            created,
+        }
+    }
+
+    #[test]
+    fn unsupported_reason_needs_rootful_podman() {
+        assert!(unsupported_reason(RuntimeKind::Docker, None).is_some());
+        assert!(unsupported_reason(RuntimeKind::Podman, Some(true)).is_some());
+        assert!(unsupported_reason(RuntimeKind::Podman, Some(false)).is_none());
+        assert!(unsupported_reason(RuntimeKind::Podman, None).is_none());
+    }
+
+    #[test]
+    fn failure_hints_at_criu() {
+        let e = failure("Checkpoint", "Error: CRIU version check failed\n");
+        assert!(format!("{e}").contains("criu check"));
+        let e = failure("Restore", "Error: no such file\n");
+        assert!(!format!("{e}").contains("criu check"));
+    }
+
+    #[test]
+    fn list_sorts_newest_first_and_marks_sessions() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        std::fs::create_dir_all(config.checkpoints_dir()).unwrap();
+        write_record(&record_path(&config, "old"), &record("aaaa1111", 10)).unwrap();
+        write_record(&record_path(&config, "new"), &record("bbbb2222", 20)).unwrap();
+        std::fs::write(archive_path(&config, "new"), b"").unwrap();
+
+        let names: Vec<String> = list(&config).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["new", "old"]);
+        assert!(is_checkpointed(&config, "aaaa1111"));
+        assert!(!is_checkpointed(&config, "cccc3333"));
+
+        remove(&config, "new").unwrap();
+        assert!(!archive_path(&config, "new").exists());
+        assert!(!is_checkpointed(&config, "bbbb2222"));
+        assert!(remove(&config, "new").is_err());
+    }
+}
@@ -0,0 +1,411 @@
+//! The layers of the session's CLAUDE.md, the file ai-pod mounts at Claude
+//! Code's managed-policy location for each launch (see
+//! `container::session_context_args`). It is rebuilt on every launch from,
+//! in order:
+//!
+//! 1. `preamble`: what ai-pod generates — listening host services, and
+//!    optionally the git branch, recent commits and TODO files;
+//! 2. `global`: the user's fragment, `~/.ai-pod/CLAUDE.md`;
+//! 3. `team`: an organization's fragment at `claude_md.team_file`;
+//! 4. `project`: the workspace's `.ai-pod/CLAUDE.md`.
+//!
+//! `claude_md.disable` turns layers off. Fragments and the string values of
+//! `~/.claude/settings.json` may use `{{placeholders}}` (see
+//! [`TemplateVars`]). Settings are expanded when the home volume is seeded,
+//! so `{{session}}` is only known in fragments.
+
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Component, Path};
+use std::time::Duration;
+
+use crate::config::{AppConfig, ClaudeMdConfig};
+use crate::runtime::ContainerRuntime;
+
+/// The user's CLAUDE.md fragment in `~/.ai-pod`, the `global` layer.
+pub const FRAGMENT_FILE: &str = "CLAUDE.md";
+
+/// The workspace's fragment, the `project` layer.
+pub const PROJECT_FRAGMENT: &str = ".ai-pod/CLAUDE.md";
+
+/// Layer names, in the order they appear.
+pub const LAYERS: &[&str] = &["preamble", "global", "team", "project"];
+
+/// Values for `{{name}}` placeholders: `workspace` (host path), `project`,
+/// `session`, `notify_port`, `host_gateway`, `server_url`, and `env.NAME`
+/// for a host environment variable (empty when unset). Unknown names, and
+/// `session` where there is none, are left as written.
+pub struct TemplateVars<'a> {
+    pub workspace: &'a Path,
+    pub session: Option<&'a str>,
+    pub host_gateway: &'static str,
+    pub server_url: String,
+}
+
+impl<'a> TemplateVars<'a> {
+    pub fn new(rt: &ContainerRuntime, workspace: &'a Path, session: Option<&'a str>) -> Self {
+        TemplateVars {
+            workspace,
+            session,
+            host_gateway: rt.host_gateway(),
+            server_url: rt.server_url(),
+        }
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        match name {
+            "workspace" => Some(self.workspace.display().to_string()),
+            "project" => Some(crate::container::project_display_name(self.workspace)),
+            "session" => self.session.map(str::to_string),
+            "notify_port" => Some(crate::server::lifecycle::server_port().to_string()),
+            "host_gateway" => Some(self.host_gateway.to_string()),
+            "server_url" => Some(self.server_url.clone()),
+            _ => name
+                .strip_prefix("env.")
+                .map(|var| std::env::var(var).unwrap_or_default()),
+        }
+    }
+}
+
+/// Replace the `{{name}}` placeholders in `text` that `vars` knows.
+pub fn expand(text: &str, vars: &TemplateVars) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        match vars.value(after[..end].trim()) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// [`expand`] every string in `value`; object keys are left alone.
+pub fn expand_json(value: &mut serde_json::Value, vars: &TemplateVars) {
+    match value {
+        serde_json::Value::String(s) if s.contains("{{") => *s = expand(s, vars),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|v| expand_json(v, vars)),
+        serde_json::Value::Object(map) => map.values_mut().for_each(|v| expand_json(v, vars)),
+        _ => {}
+    }
+}
+
+/// Ports local dev servers and databases commonly listen on, with what
+/// usually runs there.
+pub const COMMON_PORTS: &[(u16, &str)] = &[
+    (3000, "web dev server (Node, Rails, Grafana)"),
+    (3001, "web dev server"),
+    (3306, "MySQL / MariaDB"),
+    (4200, "Angular dev server"),
+    (4566, "LocalStack"),
+    (5000, "web app (Flask, ASP.NET)"),
+    (5173, "Vite dev server"),
+    (5432, "PostgreSQL"),
+    (5672, "RabbitMQ"),
+    (6379, "Redis"),
+    (8000, "web app (Django, uvicorn)"),
+    (8080, "HTTP server"),
+    (8888, "Jupyter"),
+    (9200, "Elasticsearch"),
+    (11434, "Ollama"),
+    (27017, "MongoDB"),
+];
+
+const PROBE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// TODO files longer than this are cut off.
+const MAX_TODO_BYTES: usize = 8 * 1024;
+
+/// Whether something accepts connections on `port` on the host's loopback.
+fn listening(port: u16) -> bool {
+    TcpStream::connect_timeout(&SocketAddr::from((Ipv4Addr::LOCALHOST, port)), PROBE_TIMEOUT).is_ok()
+}
+
+/// The ports to probe: the common ones plus `extra`, each with a label.
+fn candidate_ports(extra: &[u16]) -> Vec<(u16, &'static str)> {
+    let mut ports: Vec<(u16, &'static str)> = COMMON_PORTS.to_vec();
+    for port in extra {
+        if !ports.iter().any(|(p, _)| p == port) {
+            ports.push((*port, "configured in claude_md.host_ports"));
+        }
+    }
+    ports
+}
+
+/// The "Host services" section for the services in `found`, or `None` when
+/// there are none.
+fn host_services_section(gateway: &str, found: &[(u16, &str)]) -> Option<String> {
+    if found.is_empty() {
+        return None;
+    }
+    let mut md = format!(
+        "## Host services\n\n\
+         These ports were listening on the host when the session started. \
+         Inside this container `localhost` is the container itself; reach them at \
+         `{}` instead:\n\n",
+        gateway
+    );
+    for (port, what) in found {
+        md.push_str(&format!("- `{}:{}`: {}\n", gateway, port, what));
+    }
+    Some(md)
+}
+
+/// Probe the host for the services listed in the config and describe the
+/// ones that answer.
+fn host_services(rt: &ContainerRuntime, config: &ClaudeMdConfig) -> Option<String> {
+    if !config.host_services.unwrap_or(true) || rt.dry_run {
+        return None;
+    }
+    let found: Vec<(u16, &str)> = candidate_ports(&config.host_ports)
+        .into_iter()
+        .filter(|(port, _)| listening(*port))
+        .collect();
+    host_services_section(rt.host_gateway(), &found)
+}
+
+fn git(workspace: &Path, args: &[&str]) -> Option<String> {
+    crate::host_git::output(workspace, args).map(|out| out.trim_end().to_string())
+}
+
+/// The "Project state" section: current branch and recent commit subjects.
+fn project_state(workspace: &Path, config: &ClaudeMdConfig) -> Option<String> {
+    let branch = if config.branch {
+        git(workspace, &["rev-parse", "--abbrev-ref", "HEAD"]).filter(|b| !b.is_empty())
+    } else {
+        None
+    };
+    let commits = if config.commits > 0 {
+        git(
+            workspace,
+            &["log", "--no-color", "--format=%h %s", &format!("-{}", config.commits)],
+        )
+        .filter(|l| !l.is_empty())
+    } else {
+        None
+    };
+    if branch.is_none() && commits.is_none() {
+        return None;
+    }
+    let mut md = String::from("## Project state\n\n");
+    if let Some(branch) = branch {
+        let branch = if branch == "HEAD" { "(detached HEAD)".to_string() } else { format!("`{}`", branch) };
+        md.push_str(&format!("Current branch: {}\n\n", branch));
+    }
+    if let Some(commits) = commits {
+        md.push_str("Recent commits, newest first:\n\n");
+        for line in commits.lines() {
+            md.push_str(&format!("- {}\n", line));
+        }
+    }
+    Some(md)
+}
+
+/// Whether `path` stays inside the workspace.
+pub(crate) fn valid_todo_path(path: &str) -> bool {
+    !path.is_empty() && Path::new(path).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// One section per configured TODO file that exists, headed by its path.
+fn todo_sections(workspace: &Path, config: &ClaudeMdConfig) -> Vec<String> {
+    let mut out = Vec::new();
+    for path in config.todo_files.iter().filter(|p| valid_todo_path(p)) {
+        let Ok(content) = std::fs::read_to_string(workspace.join(path)) else {
+            continue;
+        };
+        let content = content.trim();
+        if content.is_empty() {
+            continue;
+        }
+        let mut md = format!("## {} (at launch)\n\n", path);
+        if content.len() > MAX_TODO_BYTES {
+            let mut end = MAX_TODO_BYTES;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            md.push_str(&content[..end]);
+            md.push_str(&format!("\n\n(cut off; read `/app/{}` for the rest)\n", path));
+        } else {
+            md.push_str(content);
+            md.push('\n');
+        }
+        out.push(md);
+    }
+    out
+}
+
+/// The fragment at `path` with its placeholders filled in, if it exists
+/// and isn't empty.
+fn fragment(path: &Path, vars: &TemplateVars) -> Option<String> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    let text = expand(raw.trim(), vars);
+    (!text.is_empty()).then(|| text + "\n")
+}
+
+/// The team fragment's path, with `~` expanded.
+pub(crate) fn team_file(config: &ClaudeMdConfig, home_dir: &Path) -> Option<std::path::PathBuf> {
+    let file = config.team_file.as_deref()?;
+    Some(crate::mount_cli::normalize_host(file, home_dir).into())
+}
+
+/// The sections for this launch, layer by layer.
+pub fn sections(
+    rt: &ContainerRuntime,
+    app: &AppConfig,
+    workspace: &Path,
+    session_id: &str,
+    config: &ClaudeMdConfig,
+) -> Vec<String> {
+    let enabled = |layer: &str| !config.disable.iter().any(|d| d == layer);
+    let vars = TemplateVars::new(rt, workspace, Some(session_id));
+    let mut out = Vec::new();
+    if enabled("preamble") {
+        out.extend(host_services(rt, config));
+        out.extend(project_state(workspace, config));
+        out.extend(todo_sections(workspace, config));
+    }
+    if enabled("global") {
+        out.extend(fragment(&crate::layout::settings_dir(&app.config_dir).join(FRAGMENT_FILE), &vars));
+    }
+    if enabled("team") {
+        out.extend(team_file(config, &app.home_dir).and_then(|f| fragment(&f, &vars)));
+    }
+    if enabled("project") {
+        out.extend(fragment(&workspace.join(PROJECT_FRAGMENT), &vars));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidate_ports_add_configured_ones_once() {
+        let ports = candidate_ports(&[5432, 4000]);
+        assert_eq!(ports.len(), COMMON_PORTS.len() + 1);
+        assert_eq!(ports.last(), Some(&(4000, "configured in claude_md.host_ports")));
+    }
+
+    #[test]
+    fn host_services_section_names_gateway_addresses() {
+        assert!(host_services_section("host.docker.internal", &[]).is_none());
+        let md = host_services_section("host.docker.internal", &[(5432, "PostgreSQL")]).unwrap();
+        assert!(md.starts_with("## Host services"));
+        assert!(md.contains("- `host.docker.internal:5432`: PostgreSQL"));
+    }
+
+    #[test]
+    fn project_state_lists_branch_and_commits() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ws = dir.path();
+        let run = |args: &[&str]| {
+            let out = std::process::Command::new("git").arg("-C").arg(ws).args(args).output().unwrap();
+            assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        };
+        run(&["init", "-q", "-b", "feature"]);
+        run(&["-c", "user.email=a@b", "-c", "user.name=a", "commit", "-q", "--allow-empty", "-m", "first"]);
+        run(&["-c", "user.email=a@b", "-c", "user.name=a", "commit", "-q", "--allow-empty", "-m", "second"]);
+
+        assert!(project_state(ws, &ClaudeMdConfig::default()).is_none());
+        let config = ClaudeMdConfig {
+            branch: true,
+            commits: 1,
+            ..Default::default()
+        };
+        let md = project_state(ws, &config).unwrap();
+        assert!(md.contains("Current branch: `feature`"));
+        assert!(md.contains(" second\n"));
+        assert!(!md.contains(" first\n"));
+        assert!(project_state(&ws.join("missing"), &config).is_none());
+    }
+
+    #[test]
+    fn todo_sections_include_existing_files_only() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("TODO.md"), "- [ ] ship it\n").unwrap();
+        std::fs::write(dir.path().join("long.md"), "x".repeat(MAX_TODO_BYTES + 10)).unwrap();
+        let config = ClaudeMdConfig {
+            todo_files: vec!["TODO.md".into(), "NOTES.md".into(), "../secret".into(), "long.md".into()],
+            ..Default::default()
+        };
+        let sections = todo_sections(dir.path(), &config);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0], "## TODO.md (at launch)\n\n- [ ] ship it\n");
+        assert!(sections[1].ends_with("(cut off; read `/app/long.md` for the rest)\n"));
+    }
+
+    fn test_vars(session: Option<&str>) -> TemplateVars<'_> {
+        TemplateVars {
+            workspace: Path::new("/home/u/shop"),
+            session,
+            host_gateway: "host.containers.internal",
+            server_url: "http://host.containers.internal:7822".into(),
+        }
+    }
+
+    #[test]
+    fn expand_fills_known_placeholders() {
+        let vars = test_vars(Some("ab12cd34"));
+        assert_eq!(
+            expand("{{project}} at {{ workspace }}, session {{session}}, port {{notify_port}}", &vars),
+            "shop at /home/u/shop, session ab12cd34, port 7822"
+        );
+        assert_eq!(expand("{{env.AI_POD_SURELY_UNSET}}|", &vars), "|");
+        assert_eq!(expand("{{unknown}} {{open", &vars), "{{unknown}} {{open");
+        assert_eq!(expand("{{session}}", &test_vars(None)), "{{session}}");
+    }
+
+    #[test]
+    fn expand_json_touches_values_only() {
+        let mut v = serde_json::json!({"{{project}}": ["{{host_gateway}}", 1], "env": {"URL": "{{server_url}}/x"}});
+        expand_json(&mut v, &test_vars(None));
+        assert_eq!(
+            v,
+            serde_json::json!({"{{project}}": ["host.containers.internal", 1], "env": {"URL": "http://host.containers.internal:7822/x"}})
+        );
+    }
+
+    #[test]
+    fn sections_follow_layer_order_and_skip_disabled_layers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let app = crate::config::test_config(&dir);
+        let ws = dir.path().join("ws");
+        std::fs::create_dir_all(ws.join(".ai-pod")).unwrap();
+        std::fs::write(app.config_dir.join(FRAGMENT_FILE), "global {{session}}").unwrap();
+        std::fs::write(dir.path().join("team.md"), "team").unwrap();
+        std::fs::write(ws.join(PROJECT_FRAGMENT), "project {{project}}").unwrap();
+        std::fs::write(ws.join("TODO.md"), "todo").unwrap();
+        let rt = ContainerRuntime {
+            kind: crate::runtime::RuntimeKind::Podman,
+            dry_run: true,
+        };
+        let mut config = ClaudeMdConfig {
+            todo_files: vec!["TODO.md".into()],
+            team_file: Some("~/team.md".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            sections(&rt, &app, &ws, "s1", &config),
+            vec!["## TODO.md (at launch)\n\ntodo\n", "global s1\n", "team\n", "project ws\n"]
+        );
+        config.disable = vec!["preamble".into(), "team".into()];
+        assert_eq!(sections(&rt, &app, &ws, "s1", &config), vec!["global s1\n", "project ws\n"]);
+    }
+
+    #[test]
+    fn listening_detects_a_bound_port() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(listening(port));
+        drop(listener);
+        assert!(!listening(port));
+    }
+}
@@ -0,0 +1,333 @@
+//! `ai-pod clean --interactive`: list every ai-pod container, volume and
+//! workspace image with its size and when its workspace was last launched,
+//! and remove whatever the user picks in one go.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::config::AppConfig;
+use crate::du::format_bytes;
+use crate::registry::{WorkspaceRegistry, format_age};
+use crate::runtime::ContainerRuntime;
+use crate::workspace::{project_id_from_container_name, workspace_hash};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Container,
+    Volume,
+    Image,
+}
+
+impl Kind {
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Container => "container",
+            Kind::Volume => "volume",
+            Kind::Image => "image",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    kind: Kind,
+    name: String,
+    size: Option<u64>,
+    /// `None` for shared caches and resources of a workspace ai-pod no
+    /// longer knows.
+    workspace: Option<PathBuf>,
+    /// Unix time of the workspace's last launch, if recorded.
+    last_used: Option<u64>,
+    running: bool,
+}
+
+/// Everything found on the runtime, before it's matched to workspaces.
+#[derive(Debug, Default)]
+struct Inventory {
+    containers: Vec<(String, u64)>,
+    running: HashSet<String>,
+    volumes: Vec<(String, Option<u64>)>,
+    images: Vec<(PathBuf, String, u64)>,
+}
+
+/// Attach workspaces and last-launch times, least recently used first;
+/// resources without a known launch come first of all.
+fn build_items(inv: &Inventory, workspaces: &[PathBuf], reg: &WorkspaceRegistry) -> Vec<Item> {
+    let by_hash: HashMap<String, &PathBuf> = workspaces
+        .iter()
+        .map(|ws| (workspace_hash(ws), ws))
+        .collect();
+    let launched: HashMap<&str, u64> = reg
+        .recent
+        .iter()
+        .map(|e| (e.workspace.as_str(), e.last_launched))
+        .collect();
+    let item = |kind, name: &str, size, hash: Option<String>| {
+        let workspace = hash.and_then(|h| by_hash.get(&h).map(|ws| (*ws).clone()));
+        let last_used = workspace
+            .as_ref()
+            .and_then(|ws| launched.get(ws.to_string_lossy().as_ref()).copied());
+        Item {
+            kind,
+            name: name.to_string(),
+            size,
+            workspace,
+            last_used,
+            running: false,
+        }
+    };
+    let mut items = Vec::new();
+    for (name, size) in &inv.containers {
+        let mut it = item(
+            Kind::Container,
+            name,
+            Some(*size),
+            project_id_from_container_name(name),
+        );
+        it.running = inv.running.contains(name);
+        items.push(it);
+    }
+    for (name, size) in &inv.volumes {
+        items.push(item(
+            Kind::Volume,
+            name,
+            *size,
+            project_id_from_container_name(name),
+        ));
+    }
+    for (ws, name, size) in &inv.images {
+        items.push(item(
+            Kind::Image,
+            name,
+            Some(*size),
+            Some(workspace_hash(ws)),
+        ));
+    }
+    items.sort_by(|a, b| {
+        (a.last_used, &a.workspace, a.kind, &a.name).cmp(&(
+            b.last_used,
+            &b.workspace,
+            b.kind,
+            &b.name,
+        ))
+    });
+    items
+}
+
+fn item_line(item: &Item, now: u64) -> String {
+    let size = item.size.map_or_else(|| "-".to_string(), format_bytes);
+    let workspace = match &item.workspace {
+        Some(ws) => ws.display().to_string(),
+        None if item.name.starts_with("ai-pod-cache-") => "(shared cache)".to_string(),
+        None => "(unknown workspace)".to_string(),
+    };
+    let used = match item.last_used {
+        Some(t) => format_age(now, t),
+        None => "never launched".to_string(),
+    };
+    let running = if item.running { " (running)" } else { "" };
+    format!(
+        "{:<9} {:<40} {:>9}  {:<14} {}{}",
+        item.kind.label(),
+        item.name,
+        size,
+        used,
+        workspace,
+        running
+    )
+}
+
+fn running_containers(rt: &ContainerRuntime) -> HashSet<String> {
+    rt.command()
+        .args([
+            "ps",
+            "--filter",
+            "label=managed-by=ai-pod",
+            "--format",
+            "{{.Names}}",
+        ])
+        .output()
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn remove(rt: &ContainerRuntime, item: &Item) -> bool {
+    let args: Vec<&str> = match item.kind {
+        Kind::Container => vec!["rm", "--force", &item.name],
+        Kind::Volume => vec!["volume", "rm", &item.name],
+        Kind::Image => vec!["rmi", &item.name],
+    };
+    rt.command()
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+pub fn run(rt: &ContainerRuntime, config: &AppConfig) -> Result<()> {
+    if !crate::is_stdin_tty() {
+        anyhow::bail!("`ai-pod clean --interactive` needs a terminal");
+    }
+    let workspaces = crate::registry::known_workspaces(config);
+    let (volumes, images) = crate::du::measured_volumes(rt, &workspaces)?;
+    let inv = Inventory {
+        containers: crate::du::container_sizes(rt)?,
+        running: running_containers(rt),
+        volumes,
+        images: images
+            .into_iter()
+            .map(|(ws, (name, size))| (ws, name, size))
+            .collect(),
+    };
+    let items = build_items(&inv, &workspaces, &WorkspaceRegistry::load(config));
+    if items.is_empty() {
+        println!(
+            "{}",
+            "ai-pod has no containers, volumes or images to remove.".yellow()
+        );
+        return Ok(());
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let lines: Vec<String> = items.iter().map(|i| item_line(i, now)).collect();
+    let picked = dialoguer::MultiSelect::new()
+        .with_prompt("Select what to remove (space toggles, enter confirms)")
+        .items(&lines)
+        .interact()
+        .context("Selection cancelled")?;
+    if picked.is_empty() {
+        println!("Nothing selected.");
+        return Ok(());
+    }
+    let mut picked: Vec<&Item> = picked.into_iter().map(|i| &items[i]).collect();
+    let total: u64 = picked.iter().filter_map(|i| i.size).sum();
+    let running = picked.iter().filter(|i| i.running).count();
+    let mut prompt = format!(
+        "Remove {} item(s), about {}?",
+        picked.len(),
+        format_bytes(total)
+    );
+    if running > 0 {
+        prompt = format!("{} {} running session(s) will be stopped.", prompt, running);
+    }
+    if !dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .unwrap_or(false)
+    {
+        println!("{}", "Aborted.".red());
+        return Ok(());
+    }
+    // Containers hold their volumes and images, so they go first.
+    picked.sort_by_key(|i| i.kind);
+    let mut failed = 0;
+    for item in picked {
+        println!(
+            "{} {} {}",
+            "Removing".red().bold(),
+            item.kind.label(),
+            item.name
+        );
+        if !remove(rt, item) {
+            failed += 1;
+            eprintln!(
+                "{} could not remove {}; is it still in use?",
+                "warning:".yellow().bold(),
+                item.name
+            );
+        }
+    }
+    if failed == 0 {
+        println!("{}", "Done.".green());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::RecentEntry;
+
+    #[test]
+    fn build_items_matches_workspaces_and_sorts_by_last_launch() {
+        let old = PathBuf::from("/src/old");
+        let new = PathBuf::from("/src/new");
+        let (h_old, h_new) = (workspace_hash(&old), workspace_hash(&new));
+        let reg = WorkspaceRegistry {
+            recent: vec![
+                RecentEntry {
+                    workspace: "/src/new".into(),
+                    last_launched: 200,
+                },
+                RecentEntry {
+                    workspace: "/src/old".into(),
+                    last_launched: 100,
+                },
+            ],
+            ..Default::default()
+        };
+        let inv = Inventory {
+            containers: vec![(format!("ai-pod-{}-3ba2896f", h_new), 10)],
+            running: HashSet::from([format!("ai-pod-{}-3ba2896f", h_new)]),
+            volumes: vec![
+                (format!("ai-pod-{}-home", h_old), Some(500)),
+                ("ai-pod-cache-npm".into(), None),
+                ("ai-pod-ffffffffffff-home".into(), Some(1)),
+            ],
+            images: vec![(new.clone(), "new-abc123".into(), 900)],
+        };
+        let items = build_items(&inv, &[old.clone(), new.clone()], &reg);
+        let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "ai-pod-cache-npm",
+                "ai-pod-ffffffffffff-home",
+                &format!("ai-pod-{}-home", h_old),
+                &format!("ai-pod-{}-3ba2896f", h_new),
+                "new-abc123",
+            ]
+        );
+        assert_eq!(items[2].workspace.as_ref(), Some(&old));
+        assert_eq!(items[2].last_used, Some(100));
+        assert!(items[3].running);
+        assert_eq!(items[4].kind, Kind::Image);
+    }
+
+    #[test]
+    fn item_line_shows_size_age_and_owner() {
+        let item = Item {
+            kind: Kind::Volume,
+            name: "ai-pod-cache-npm".into(),
+            size: None,
+            workspace: None,
+            last_used: None,
+            running: false,
+        };
+        let line = item_line(&item, 1000);
+        assert!(line.starts_with("volume    ai-pod-cache-npm"));
+        assert!(line.contains("never launched"));
+        assert!(line.ends_with("(shared cache)"));
+        let item = Item {
+            kind: Kind::Container,
+            workspace: Some("/src/api".into()),
+            last_used: Some(10_000 - 7200),
+            size: Some(2048),
+            running: true,
+            ..item
+        };
+        let line = item_line(&item, 10_000);
+        assert!(line.contains("2h ago"));
+        assert!(line.ends_with("/src/api (running)"));
+    }
+}
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::ArgValueCandidates;
 use std::path::PathBuf;
 
 #[derive(ValueEnum, Clone, Debug, PartialEq)]
@@ -26,13 +27,8 @@ pub struct Cli {
     #[arg(long)]
     pub no_credential_check: bool,
 
-    /// Force image rebuild
-    #[arg(long)]
-    pub rebuild: bool,
-
-    /// Build image without Docker/Podman layer cache
-    #[arg(long)]
-    pub no_cache: bool,
+    #[command(flatten)]
+    pub build: BuildFlags,
 
     /// Override workspace directory (default: cwd)
     #[arg(long)]
@@ -45,15 +41,295 @@ pub struct Cli {
     /// Container runtime to use (overrides AI_POD_RUNTIME and autodetect)
     #[arg(long, value_enum)]
     pub runtime: Option<crate::runtime::RuntimeKind>,
+
+    /// Mount an additional directory under /workspaces/<name> (repeatable)
+    #[arg(long = "add-workspace", value_name = "PATH")]
+    pub add_workspace: Vec<PathBuf>,
+
+    /// Watch ai-pod.Dockerfile during the session and relaunch from a rebuilt
+    /// image when it changes: ask after the session ends (prompt, the
+    /// default) or replace the container once the agent is idle (auto)
+    #[arg(long, value_enum, value_name = "MODE", num_args = 0..=1, require_equals = true, default_missing_value = "prompt")]
+    pub watch_dockerfile: Option<crate::dockerfile_watch::WatchMode>,
+
+    /// Start an instance with its own home volume instead of sharing the
+    /// workspace's: a copy of it (clone, the default) or a newly seeded one
+    /// (fresh). The volume is removed when the session ends
+    #[arg(
+        long,
+        value_enum,
+        value_name = "HOME",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "clone",
+        conflicts_with = "rebuild"
+    )]
+    pub new: Option<crate::container::NewInstance>,
+
+    /// Label the session, e.g. in `ai-pod list` and notifications
+    #[arg(long, value_name = "TEXT")]
+    pub title: Option<String>,
+
+    /// Run CMD in place of the image's entrypoint ("" clears it); overrides
+    /// the `launch.entrypoint` setting
+    #[arg(long, value_name = "CMD")]
+    pub entrypoint: Option<String>,
+
+    /// Command to run in place of the image's default, e.g.
+    /// `ai-pod -- tmux new claude`; overrides the `launch.command` setting
+    #[arg(last = true, value_name = "ARGS")]
+    pub launch_command: Vec<String>,
+
+    /// Key sequence to detach from the session (e.g. ctrl-x,ctrl-d; "" disables)
+    #[arg(long, global = true, value_name = "KEYS")]
+    pub detach_keys: Option<String>,
+
+    /// Record every podman/docker command run, with its output, to FILE
+    /// for a bug report (see `ai-pod replay`)
+    #[arg(long, value_name = "FILE", conflicts_with = "dry_run")]
+    pub record: Option<PathBuf>,
+}
+
+/// Image build flags. Accepted before any subcommand that builds or launches
+/// the image, and by `build`, `login`, `run` and `watch` themselves.
+#[derive(Args, Clone, Default)]
+pub struct BuildFlags {
+    /// Force image rebuild
+    #[arg(long)]
+    pub rebuild: bool,
+
+    /// Build image without Docker/Podman layer cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Show the runtime's raw build output instead of the progress view
+    #[arg(long)]
+    pub verbose: bool,
+
+    /// Build even if base images fail the signature or digest checks
+    /// configured under `verify`
+    #[arg(long)]
+    pub insecure_image: bool,
+
+    /// Pull cached build layers from this registry repository (repeatable)
+    #[arg(long, value_name = "REF")]
+    pub cache_from: Vec<String>,
+
+    /// Push the build's layers to this registry repository for later
+    /// --cache-from builds
+    #[arg(long, value_name = "REF")]
+    pub cache_to: Option<String>,
+}
+
+impl BuildFlags {
+    /// Combine the flags given before the subcommand with its own; the
+    /// subcommand's `--cache-to` wins.
+    pub fn merge(&self, own: &BuildFlags) -> BuildFlags {
+        BuildFlags {
+            rebuild: self.rebuild || own.rebuild,
+            no_cache: self.no_cache || own.no_cache,
+            verbose: self.verbose || own.verbose,
+            insecure_image: self.insecure_image || own.insecure_image,
+            cache_from: [self.cache_from.as_slice(), own.cache_from.as_slice()].concat(),
+            cache_to: own.cache_to.clone().or_else(|| self.cache_to.clone()),
+        }
+    }
+
+    fn set_flags(&self) -> Vec<&'static str> {
+        [
+            (self.rebuild, "--rebuild"),
+            (self.no_cache, "--no-cache"),
+            (self.verbose, "--verbose"),
+            (self.insecure_image, "--insecure-image"),
+            (!self.cache_from.is_empty(), "--cache-from"),
+            (self.cache_to.is_some(), "--cache-to"),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+}
+
+const BUILD_FLAGS: &[&str] = &[
+    "--rebuild",
+    "--no-cache",
+    "--verbose",
+    "--insecure-image",
+    "--cache-from",
+    "--cache-to",
+];
+
+const LAUNCH_FLAGS: &[&str] = &[
+    "--no-credential-check",
+    "--add-workspace",
+    "--watch-dockerfile",
+    "--new",
+    "--title",
+    "--entrypoint",
+];
+
+impl Cli {
+    /// Parse the command line and reject top-level flags the chosen
+    /// subcommand would ignore (`ai-pod --rebuild list`).
+    pub fn parse_validated() -> Cli {
+        let matches = Cli::command().get_matches();
+        let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        if let (Some(command), Some(name)) = (&cli.command, matches.subcommand_name())
+            && let Err(msg) = check_top_level_flags(&cli.top_level_flags(), command, name)
+        {
+            Cli::command()
+                .error(clap::error::ErrorKind::ArgumentConflict, msg)
+                .exit();
+        }
+        cli
+    }
+
+    /// The flags set before the subcommand that only some subcommands use.
+    /// `--dry-run`, `--runtime` and `--detach-keys` apply everywhere.
+    fn top_level_flags(&self) -> Vec<&'static str> {
+        let mut set = self.build.set_flags();
+        if self.workdir.is_some() {
+            set.push("--workdir");
+        }
+        if self.no_credential_check {
+            set.push("--no-credential-check");
+        }
+        if !self.add_workspace.is_empty() {
+            set.push("--add-workspace");
+        }
+        if self.watch_dockerfile.is_some() {
+            set.push("--watch-dockerfile");
+        }
+        if self.new.is_some() {
+            set.push("--new");
+        }
+        if self.title.is_some() {
+            set.push("--title");
+        }
+        if self.entrypoint.is_some() {
+            set.push("--entrypoint");
+        }
+        set
+    }
+}
+
+fn check_top_level_flags(flags: &[&str], command: &Command, name: &str) -> Result<(), String> {
+    match flags.iter().find(|flag| !command.accepts(flag)) {
+        Some(flag) => Err(format!("`{}` has no effect on `ai-pod {}`", flag, name)),
+        None => Ok(()),
+    }
+}
+
+impl Command {
+    /// Whether a top-level `flag` (see [`Cli::top_level_flags`]) applies to
+    /// this subcommand.
+    fn accepts(&self, flag: &str) -> bool {
+        let workdir = flag == "--workdir";
+        let build = BUILD_FLAGS.contains(&flag);
+        let launch = LAUNCH_FLAGS.contains(&flag);
+        match self {
+            Command::Open { .. } | Command::Recent { .. } => build || launch,
+            Command::Restore { .. } => workdir || build || launch,
+            Command::Run { .. } | Command::Watch { .. } => {
+                workdir
+                    || build
+                    || (launch && !matches!(flag, "--watch-dockerfile" | "--new" | "--entrypoint"))
+            }
+            Command::Build { .. } | Command::Login { .. } => workdir || build,
+            Command::Env => workdir || (launch && flag != "--watch-dockerfile"),
+            Command::Warm { .. } => workdir || build || flag == "--no-credential-check",
+            Command::Serve { .. }
+            | Command::Control { .. }
+            | Command::RecordExec { .. }
+            | Command::Replay { .. }
+            | Command::Server { .. }
+            | Command::Update
+            | Command::Completions { .. }
+            | Command::Man { .. }
+            | Command::Describe { .. }
+            | Command::Cache { .. }
+            | Command::Attach
+            | Command::List { .. }
+            | Command::Gc { .. }
+            | Command::Du
+            | Command::Stats { .. }
+            | Command::Report { .. }
+            | Command::Top { .. }
+            | Command::Resume { .. } => false,
+            _ => workdir,
+        }
+    }
 }
 
 #[derive(Subcommand)]
 pub enum Command {
     /// Build the container image only
-    Build,
+    Build {
+        /// Workspace path (default: cwd)
+        #[arg(long, conflicts_with = "all")]
+        workdir: Option<PathBuf>,
+        #[command(flatten)]
+        build: BuildFlags,
+        /// Rebuild the images of all known workspaces (aliased, recent or
+        /// previously launched)
+        #[arg(long)]
+        all: bool,
+        /// Builds to run at once with --all
+        #[arg(long, default_value_t = 4, requires = "all")]
+        jobs: usize,
+    },
+
+    /// Resolve the base images' tags again and pin the new digests in
+    /// ai-pod.lock
+    UpdateLock,
+
+    /// Analyze the workspace's built image
+    Image {
+        #[command(subcommand)]
+        action: ImageAction,
+    },
+
+    /// Run, inspect or stop the shared MCP server (normally auto-started)
+    Server {
+        #[command(subcommand)]
+        action: ServerAction,
+    },
+
+    /// Serve a JSON-RPC control socket for editor plugins (launch, status,
+    /// run, clean and the event stream) until interrupted
+    Control {
+        /// Socket path (default: ~/.ai-pod/control.sock)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+    },
+
+    /// Internal: the server process supervised by `ai-pod server run`
+    #[command(hide = true)]
+    Serve {
+        #[arg(long, default_value_t = crate::server::lifecycle::MCP_PORT)]
+        port: u16,
+    },
+
+    /// Internal: runs one runtime command for `--record`
+    #[command(hide = true, name = crate::record::EXEC_SUBCOMMAND)]
+    RecordExec {
+        bundle: PathBuf,
+        #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
+        argv: Vec<String>,
+    },
 
-    /// Start the shared MCP server on port 7822
-    Serve,
+    /// Step through the commands of a `--record` bundle, re-running each
+    /// after asking
+    Replay {
+        /// Bundle written by `--record`
+        bundle: PathBuf,
+        /// Start at this step (see --list)
+        #[arg(long, default_value_t = 1, value_name = "N")]
+        from: usize,
+        /// Print the steps with their recorded exit codes and stop
+        #[arg(long)]
+        list: bool,
+    },
 
     /// Create ai-pod.Dockerfile in the workspace for editing
     Init {
@@ -68,29 +344,172 @@ pub enum Command {
         /// Base image for the container (interactive if omitted)
         #[arg(long, value_enum)]
         image: Option<BaseImage>,
+
+        /// Instantiate from a template: a git URL (optionally `#ref`), a raw
+        /// https URL of a Dockerfile, or a local directory. Defaults to
+        /// `init.from` from the global config
+        #[arg(long, value_name = "SOURCE", conflicts_with_all = ["agent", "image"])]
+        from: Option<String>,
+
+        /// Template directory inside the repository or directory given by --from
+        #[arg(long, value_name = "DIR")]
+        template: Option<String>,
+
+        /// Use the built-in Dockerfile even when `init.from` is configured
+        #[arg(long, conflicts_with = "from")]
+        builtin: bool,
+    },
+
+    /// Package the workspace's Dockerfile, project settings, team-wide global
+    /// settings and MCP servers into a bundle for `ai-pod import-setup`
+    ExportSetup {
+        /// Bundle to write (e.g. setup.tar.gz)
+        bundle: PathBuf,
+    },
+
+    /// Apply a bundle from `ai-pod export-setup` to the workspace, keeping
+    /// any settings and files that already exist
+    ImportSetup {
+        /// Bundle to read
+        bundle: PathBuf,
+
+        /// Apply without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
     },
 
     /// Attach to a running ai-pod container session
     Attach,
 
     /// List all ai-pod containers
-    List,
+    List {
+        /// List the known workspaces instead: project id, last launch,
+        /// aliases and path
+        #[arg(long)]
+        workspaces: bool,
+    },
+
+    /// Add a workspace to the registry without launching it, so `open`,
+    /// `build --all` and `du` know it
+    Register {
+        /// Workspace path (default: cwd)
+        path: Option<PathBuf>,
+        /// Talk to the shared server instance on this port instead of 7822,
+        /// started on demand like the default one (7822 goes back to it)
+        #[arg(long, value_name = "PORT")]
+        server_port: Option<u16>,
+    },
+
+    /// Remove a workspace from the registry, the recent list and its aliases
+    Unregister {
+        /// Workspace path (default: cwd); it may no longer exist
+        path: Option<PathBuf>,
+    },
+
+    /// Remove the containers, volumes, image and state of known workspaces
+    /// whose directory no longer exists
+    Gc {
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
 
     /// Remove the container for current/specified workspace
     Clean {
         /// Workspace path (default: cwd)
         #[arg(long)]
         workdir: Option<PathBuf>,
+        /// Only remove the state volume (`~/.claude`: sign-in, sessions,
+        /// shell history); keep containers, the home volume and the image
+        #[arg(long, conflicts_with_all = ["keep_volume", "volume_only", "image"])]
+        state_only: bool,
+        /// Remove the containers but keep the home and state volumes, so
+        /// sign-in and history survive
+        #[arg(long, conflicts_with = "volume_only")]
+        keep_volume: bool,
+        /// Remove only the volumes; leave containers alone
+        #[arg(long, conflicts_with = "image")]
+        volume_only: bool,
+        /// Also remove the workspace's image
+        #[arg(long)]
+        image: bool,
+        /// Pick from every ai-pod container, volume and image, across all
+        /// workspaces, and remove the selection
+        #[arg(
+            short,
+            long,
+            conflicts_with_all = ["workdir", "state_only", "keep_volume", "volume_only", "image"]
+        )]
+        interactive: bool,
     },
 
-    /// Run a command in the container, overriding the default
+    /// Run a command in the container, overriding the default. Use it as a
+    /// project task runner: `ai-pod run -e CI=1 make test` runs in the
+    /// workspace's session if one is up, otherwise in a one-off container,
+    /// and exits with the command's exit code
     Run {
-        /// Command to run (e.g. bash, claude)
-        command: String,
+        /// Workspace path (default: cwd)
+        #[arg(long)]
+        workdir: Option<PathBuf>,
+        /// Set an environment variable for the command; KEY alone passes the
+        /// host's value through (repeatable)
+        #[arg(short, long = "env", value_name = "KEY[=VALUE]", value_parser = parse_env)]
+        env: Vec<String>,
+        /// Never allocate a pseudo-TTY, even on a terminal
+        #[arg(long)]
+        no_tty: bool,
+        #[command(flatten)]
+        build: BuildFlags,
+        /// Run as this user inside the container (name or uid[:gid])
+        #[arg(short, long)]
+        user: Option<String>,
+        /// Label the session (default: the first words of the prompt of
+        /// `claude -p` or `opencode run`)
+        #[arg(long, value_name = "TEXT")]
+        title: Option<String>,
+        /// Command to run (e.g. bash, claude) and its arguments. Everything
+        /// after the command is passed through unchanged, including `--` and
+        /// flags ai-pod also knows
+        #[arg(
+            value_name = "COMMAND",
+            required = true,
+            num_args = 1..,
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        argv: Vec<String>,
+    },
+
+    /// Re-run a headless agent job whenever matching workspace files change
+    /// (e.g. `ai-pod watch --glob 'src/**' --prompt "make the tests pass"`)
+    Watch {
+        /// Workspace path (default: cwd)
+        #[arg(long)]
+        workdir: Option<PathBuf>,
+
+        #[command(flatten)]
+        build: BuildFlags,
+
+        /// Workspace-relative glob to watch (repeatable; default: watch.globs
+        /// from the config, or every file)
+        #[arg(long = "glob", value_name = "GLOB")]
+        globs: Vec<String>,
+
+        /// Run the agent headless with this prompt
+        #[arg(long, conflicts_with = "command")]
+        prompt: Option<String>,
+
+        /// Agent used for --prompt
+        #[arg(long, value_enum, default_value = "claude")]
+        agent: Agent,
 
-        /// Arguments to pass to the command
-        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
-        args: Vec<String>,
+        /// Label the jobs' sessions (default: the first words of --prompt)
+        #[arg(long, value_name = "TEXT")]
+        title: Option<String>,
+
+        /// Command to run instead of a prompt (after `--`)
+        #[arg(last = true)]
+        command: Vec<String>,
     },
 
     /// View and manage host commands for the current workspace
@@ -145,16 +564,306 @@ pub enum Command {
         workdir: Option<PathBuf>,
     },
 
-    /// Manage host-path bind mounts applied to every ai-pod container.
+    /// Hide a workspace path from the container. Directories are overlaid with
+    /// an empty tmpfs and files with /dev/null, so they stay on the host but
+    /// are invisible to the agent.
+    Exclude {
+        /// Path relative to the workspace root (e.g. secrets/, data/raw)
+        path: String,
+        /// Workspace path (default: cwd)
+        #[arg(long)]
+        workdir: Option<PathBuf>,
+    },
+
+    /// Stop hiding a previously excluded workspace path.
+    Unexclude {
+        /// Path relative to the workspace root
+        path: String,
+        /// Workspace path (default: cwd)
+        #[arg(long)]
+        workdir: Option<PathBuf>,
+    },
+
+    /// Manage bind mounts and named volumes applied to ai-pod containers,
+    /// globally or per workspace.
     Mount {
         #[command(subcommand)]
         action: MountAction,
     },
 
+    /// View and edit ai-pod configuration, globally or for this workspace.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Manage package-manager cache volumes (npm, cargo, pip) shared across
+    /// all workspaces.
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Show or relax the container hardening flags for a workspace.
+    Security {
+        #[command(subcommand)]
+        action: SecurityAction,
+    },
+
+    /// Set up the workspace's next session ahead of time: build the image,
+    /// seed the volumes and create a stopped container that the next
+    /// `ai-pod` starts at once
+    Warm {
+        #[arg(long)]
+        workdir: Option<PathBuf>,
+        /// Remove the standby container instead
+        #[arg(long)]
+        rm: bool,
+    },
+
+    /// Print the environment, mounts, network and security options the next
+    /// launch would use, and where each one comes from
+    Env,
+
+    /// Sign the agent in from inside the container: opens the OAuth page in
+    /// the host browser and stores the credentials in the home volume.
+    Login {
+        /// Agent to sign in (default: claude)
+        #[arg(long, value_enum, default_value = "claude")]
+        agent: Agent,
+        /// Workspace path (default: cwd)
+        #[arg(long)]
+        workdir: Option<PathBuf>,
+        #[command(flatten)]
+        build: BuildFlags,
+    },
+
+    /// Commit the workspace's running container (runtime-installed tools,
+    /// files outside the workspace) to a snapshot image
+    Snapshot {
+        /// Snapshot name (default: the current UTC time)
+        #[arg(conflicts_with_all = ["list", "rm"])]
+        name: Option<String>,
+        /// List this workspace's snapshots
+        #[arg(long)]
+        list: bool,
+        /// Delete the named snapshot
+        #[arg(long, value_name = "NAME")]
+        rm: Option<String>,
+    },
+
+    /// Launch a new session from a snapshot (picked interactively if no name
+    /// is given)
+    Restore {
+        name: Option<String>,
+    },
+
+    /// Experimental: freeze the workspace's running session with CRIU (rootful
+    /// Podman only) so it can be resumed after a reboot or on another machine
+    Checkpoint {
+        /// Checkpoint name (default: the current UTC time)
+        #[arg(conflicts_with_all = ["list", "rm"])]
+        name: Option<String>,
+        /// List all checkpoints
+        #[arg(long)]
+        list: bool,
+        /// Delete the named checkpoint
+        #[arg(long, value_name = "NAME")]
+        rm: Option<String>,
+    },
+
+    /// Experimental: restore a checkpointed session and attach to it (picked
+    /// interactively if no name is given)
+    Resume {
+        name: Option<String>,
+    },
+
+    /// Launch a workspace by alias (see `ai-pod alias`) or path, without
+    /// having to `cd` into it first.
+    Open {
+        /// Registered alias or workspace path
+        #[arg(add = ArgValueCandidates::new(crate::completions::complete_open_target))]
+        target: String,
+    },
+
+    /// Show recent agent notifications for this workspace
+    Notifications {
+        /// Include every workspace, labelled by project
+        #[arg(long)]
+        all: bool,
+        /// Number of notifications to show
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Hold back this workspace's notifications for a while, without
+    /// stopping its sessions
+    Snooze {
+        /// How long, e.g. 30m, 2h or 1h30m; `off` ends the snooze and any `ack`
+        #[arg(default_value = crate::snooze::DEFAULT_DURATION)]
+        duration: String,
+    },
+
+    /// Silence the sessions running in this workspace; sessions launched
+    /// afterwards notify as usual
+    Ack,
+
+    /// Show the destinations this workspace's containers contacted through
+    /// the egress audit proxy (`network.audit`)
+    Egress {
+        /// Session to show (default: the most recent one)
+        #[arg(long, conflicts_with = "all")]
+        session: Option<String>,
+        /// Sum over every recorded session
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Pick a recently launched workspace and launch it again.
+    Recent {
+        /// Only print the list, don't prompt
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Manage workspace aliases used by `ai-pod open`.
+    Alias {
+        #[command(subcommand)]
+        action: AliasAction,
+    },
+
+    /// Show disk usage per workspace (image, home volume, masks, containers)
+    /// and of the shared caches, with what `clean` would free
+    Du,
+
+    /// Launches, builds and agent time per workspace, recorded locally when
+    /// `stats.enabled` is set
+    Stats {
+        /// Print the totals as JSON
+        #[arg(long)]
+        json: bool,
+        /// Delete the recorded statistics
+        #[arg(long, conflicts_with = "json")]
+        reset: bool,
+    },
+
+    /// Summarize agent activity across workspaces as Markdown
+    Report {
+        #[command(subcommand)]
+        action: ReportAction,
+    },
+
+    /// Live CPU, memory and process counts of running ai-pod containers,
+    /// highlighting those over the `top` thresholds in config.json
+    Top {
+        /// Seconds between refreshes
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+        /// Print one snapshot and exit
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Review the workspace's uncommitted changes hunk by hunk: accept
+    /// (stage), revert, or open each one in `editor.command`
+    Review,
+
+    /// List the files changed in the workspace (per git) with their first
+    /// changed line, and optionally open them with `editor.command`
+    Changes {
+        /// Open the changed files in the configured editor
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Print the shell completion script (e.g. `source <(ai-pod completions bash)`)
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Print the man page, or write one page per subcommand with --out-dir
+    Man {
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Describe the commands, flags and config keys (with defaults), for
+    /// GUIs and wrappers
+    Describe {
+        /// Print the description as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Update ai-pod to the latest release
     Update,
 }
 
+#[derive(Subcommand)]
+pub enum ReportAction {
+    /// Sessions, agent time, changed files, branches and pull requests per
+    /// project for one week (Monday to Sunday)
+    Weekly {
+        /// Report on an earlier week: 1 is last week
+        #[arg(long, default_value_t = 0)]
+        weeks_ago: u32,
+        /// Write the report to this file instead of stdout
+        #[arg(long, short = 'o', value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ImageAction {
+    /// Break the image down by layer and suggest how to shrink large ones
+    Inspect {
+        /// Also list empty layers (ENV, CMD, ...)
+        #[arg(long)]
+        all: bool,
+    },
+    /// Search the image's layers and config for baked-in secrets; exits
+    /// non-zero if any are found
+    Scan,
+}
+
+/// `--env` value: `KEY` or `KEY=VALUE` with a non-empty, `=`-free key.
+fn parse_env(s: &str) -> Result<String, String> {
+    let key = s.split_once('=').map_or(s, |(k, _)| k);
+    if key.is_empty() || key.chars().any(char::is_whitespace) {
+        return Err(format!("expected KEY or KEY=VALUE, got `{}`", s));
+    }
+    Ok(s.to_string())
+}
+
+#[derive(Subcommand)]
+pub enum ServerAction {
+    /// Start the shared server on port 7822, restarting it if it crashes
+    Run {
+        /// Stay attached to the terminal and log to it (the default)
+        #[arg(long, conflicts_with = "daemonize")]
+        foreground: bool,
+        /// Start in the background, logging to ~/.ai-pod/server.log
+        #[arg(long)]
+        daemonize: bool,
+        /// Run another instance on this port (see `ai-pod register --server-port`)
+        #[arg(long, default_value_t = crate::server::lifecycle::MCP_PORT)]
+        port: u16,
+    },
+    /// Show whether the server is running, its PIDs and bound port, for
+    /// every instance
+    Status,
+    /// Stop the server
+    Stop {
+        /// Stop the instance on this port
+        #[arg(long, default_value_t = crate::server::lifecycle::MCP_PORT, conflicts_with = "all")]
+        port: u16,
+        /// Stop every instance
+        #[arg(long)]
+        all: bool,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum CommandsAction {
     /// Plain list (one row per command)
@@ -172,13 +881,13 @@ pub enum CommandsAction {
     Kill {
         command_id: String,
         /// Session id (optional; resolved from list if omitted)
-        #[arg(long)]
+        #[arg(long, add = ArgValueCandidates::new(crate::completions::complete_session))]
         session: Option<String>,
     },
     /// Print stdout/stderr/exit for a command
     Logs {
         command_id: String,
-        #[arg(long)]
+        #[arg(long, add = ArgValueCandidates::new(crate::completions::complete_session))]
         session: Option<String>,
     },
 }
@@ -191,7 +900,7 @@ pub enum ServicesAction {
     Logs {
         name: String,
         /// Session id (optional; resolved from the workspace if exactly one session owns the name)
-        #[arg(long)]
+        #[arg(long, add = ArgValueCandidates::new(crate::completions::complete_session))]
         session: Option<String>,
         /// Number of trailing log lines to print
         #[arg(long, default_value_t = 50)]
@@ -201,7 +910,7 @@ pub enum ServicesAction {
     Stop {
         name: String,
         /// Session id (optional; resolved from the workspace if exactly one session owns the name)
-        #[arg(long)]
+        #[arg(long, add = ArgValueCandidates::new(crate::completions::complete_session))]
         session: Option<String>,
     },
 }
@@ -232,6 +941,40 @@ pub enum EnvFilesAction {
     },
 }
 
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Show all settings with their effective values
+    List {
+        /// Show the current workspace's settings instead of the global ones
+        #[arg(long)]
+        project: bool,
+    },
+    /// Print one setting (dotted key, e.g. security.read_only)
+    Get {
+        key: String,
+        #[arg(long)]
+        project: bool,
+    },
+    /// Change a setting. Values are parsed as JSON when possible
+    /// (true, 3, ["npm"]), otherwise taken as a string.
+    Set {
+        key: String,
+        value: String,
+        #[arg(long)]
+        project: bool,
+    },
+    /// Remove a setting so its default applies again
+    Unset {
+        key: String,
+        #[arg(long)]
+        project: bool,
+    },
+    /// Open the global config in $EDITOR and validate it on save
+    Edit,
+    /// Validate the global and workspace configuration and report problems
+    Check,
+}
+
 #[derive(Subcommand)]
 pub enum MountAction {
     /// List configured global mounts
@@ -249,11 +992,77 @@ pub enum MountAction {
         /// warn-list (credentials, system paths, ai-pod's own state, etc.).
         #[arg(long, short = 'y')]
         yes: bool,
+        /// Treat the spec as `volume:container` and mount a named volume
+        #[arg(long)]
+        volume: bool,
+        /// Store the mount for the current workspace only (see --workdir)
+        #[arg(long)]
+        project: bool,
     },
     /// Remove a mount by host path (use the exact host path from `mount list`).
     Remove {
-        /// Host path of the mount to remove
+        /// Host path (or volume name) of the mount to remove
         host: String,
+        /// Remove from the current workspace's mounts instead of the global list
+        #[arg(long)]
+        project: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// List known caches, whether they are enabled and whether a volume exists
+    List,
+    /// Mount a cache volume into every container from the next launch on
+    Enable {
+        #[arg(value_enum)]
+        name: crate::cache_cli::CacheKind,
+    },
+    /// Stop mounting a cache volume (the volume is kept; see `cache clear`)
+    Disable {
+        #[arg(value_enum)]
+        name: crate::cache_cli::CacheKind,
+    },
+    /// Delete a cache volume, or all of them if no name is given
+    Clear {
+        #[arg(value_enum)]
+        name: Option<crate::cache_cli::CacheKind>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AliasAction {
+    /// List registered aliases
+    List,
+    /// Register an alias for a workspace (default: current workspace)
+    Add {
+        name: String,
+        /// Workspace path (default: --workdir or cwd)
+        path: Option<PathBuf>,
+    },
+    /// Remove an alias
+    Remove {
+        #[arg(add = ArgValueCandidates::new(crate::completions::complete_alias))]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SecurityAction {
+    /// Print the hardening flags the next launch will apply
+    Show {
+        #[arg(long)]
+        workdir: Option<PathBuf>,
+    },
+    /// Skip cap-drop, no-new-privileges and seccomp/AppArmor for this workspace
+    Relax {
+        #[arg(long)]
+        workdir: Option<PathBuf>,
+    },
+    /// Re-apply the global security config to this workspace
+    Enforce {
+        #[arg(long)]
+        workdir: Option<PathBuf>,
     },
 }
 
@@ -277,3 +1086,65 @@ pub enum AllowedAction {
         workdir: Option<PathBuf>,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(args: &[&str]) -> Result<(), String> {
+        let cli = Cli::try_parse_from(args).unwrap();
+        let name = args.last().unwrap();
+        check_top_level_flags(&cli.top_level_flags(), cli.command.as_ref().unwrap(), name)
+    }
+
+    #[test]
+    fn top_level_flags_are_checked_against_the_subcommand() {
+        assert_eq!(
+            check(&["ai-pod", "--rebuild", "list"]),
+            Err("`--rebuild` has no effect on `ai-pod list`".to_string())
+        );
+        assert!(check(&["ai-pod", "--workdir", "/tmp", "du"]).is_err());
+        assert!(check(&["ai-pod", "--add-workspace", "/tmp", "build"]).is_err());
+        assert!(check(&["ai-pod", "--new", "run", "make"]).is_err());
+        assert!(check(&["ai-pod", "--new=fresh", "open", "api"]).is_ok());
+        assert!(check(&["ai-pod", "--title", "nightly", "run", "make"]).is_ok());
+        assert!(check(&["ai-pod", "--title", "nightly", "list"]).is_err());
+        assert!(check(&["ai-pod", "--rebuild", "watch"]).is_ok());
+        assert!(check(&["ai-pod", "--new", "--add-workspace", "/tmp", "env"]).is_ok());
+        assert!(check(&["ai-pod", "--watch-dockerfile", "env"]).is_err());
+        assert!(check(&["ai-pod", "--workdir", "/tmp", "snapshot"]).is_ok());
+        assert!(check(&["ai-pod", "--dry-run", "list"]).is_ok());
+    }
+
+    #[test]
+    fn subcommands_own_their_build_flags() {
+        let cli = Cli::try_parse_from(["ai-pod", "--cache-from", "a", "run", "--rebuild", "--cache-from", "b", "make", "--rebuild"]).unwrap();
+        let Some(Command::Run { build, argv, .. }) = &cli.command else {
+            panic!("expected run");
+        };
+        assert_eq!(argv, &["make", "--rebuild"]);
+        let merged = cli.build.merge(build);
+        assert!(merged.rebuild);
+        assert_eq!(merged.cache_from, vec!["a", "b"]);
+        assert!(Cli::try_parse_from(["ai-pod", "list", "--rebuild"]).is_err());
+        assert!(Cli::try_parse_from(["ai-pod", "build", "--all", "--workdir", "/tmp"]).is_err());
+    }
+
+    #[test]
+    fn run_argv_after_separator_is_passed_through() {
+        let cli = Cli::try_parse_from(["ai-pod", "run", "--no-tty", "--", "--rebuild", "--", "x"]).unwrap();
+        let Some(Command::Run { build, argv, .. }) = &cli.command else {
+            panic!("expected run");
+        };
+        assert_eq!(argv, &["--rebuild", "--", "x"]);
+        assert!(!build.rebuild);
+    }
+
+    #[test]
+    fn clean_flags_conflict_where_they_contradict() {
+        assert!(Cli::try_parse_from(["ai-pod", "clean", "--keep-volume", "--image"]).is_ok());
+        assert!(Cli::try_parse_from(["ai-pod", "clean", "--keep-volume", "--volume-only"]).is_err());
+        assert!(Cli::try_parse_from(["ai-pod", "clean", "--volume-only", "--image"]).is_err());
+        assert!(Cli::try_parse_from(["ai-pod", "clean", "--state-only", "--keep-volume"]).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! Guard against two workspaces sharing a project id. Container, volume and
+//! state-file names embed only a 6-byte hash of the workspace path, so two
+//! paths can in principle map to the same names, and a resource left behind
+//! under a name says nothing about where it came from. Containers and
+//! per-workspace volumes are labelled with the full workspace path, and
+//! ai-pod refuses to reuse one whose label names another path.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+use crate::registry::WorkspaceRegistry;
+use crate::runtime::ContainerRuntime;
+use crate::workspace::workspace_hash;
+
+/// Label holding the full workspace path.
+pub const LABEL: &str = "ai-pod.workspace";
+
+/// `--label ai-pod.workspace=<path>`.
+pub fn label_args(workspace: &Path) -> Vec<String> {
+    vec![
+        "--label".to_string(),
+        format!("{}={}", LABEL, workspace.to_string_lossy()),
+    ]
+}
+
+/// The label's value from `inspect --format` output. Resources created
+/// before the label existed print nothing (or `<no value>`).
+pub(crate) fn parse_owner(output: &str) -> Option<String> {
+    let owner = output.trim();
+    (!owner.is_empty() && owner != "<no value>").then(|| owner.to_string())
+}
+
+/// Error unless `owner` is unknown or is `workspace` itself.
+fn check_owner(what: &str, name: &str, owner: Option<&str>, workspace: &Path) -> Result<()> {
+    let ws = workspace.to_string_lossy();
+    match owner {
+        Some(owner) if owner != ws => anyhow::bail!(
+            "{} {} belongs to {}, not {}: both paths map to project id {}. \
+             ai-pod won't reuse it. Remove it if {} is gone, or rename one of the workspace directories.",
+            what,
+            name,
+            owner,
+            ws,
+            workspace_hash(workspace),
+            owner
+        ),
+        _ => Ok(()),
+    }
+}
+
+fn label_of(rt: &ContainerRuntime, inspect: &[&str], format: &str, name: &str) -> Option<String> {
+    let output = rt
+        .command()
+        .args(inspect)
+        .args(["--format", format, name])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_owner(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `volume inspect --format` template printing the label.
+pub(crate) fn volume_label_format() -> String {
+    format!("{{{{ index .Labels \"{}\" }}}}", LABEL)
+}
+
+/// Refuse to reuse volume `name` if it was created for another workspace.
+/// A missing volume is fine.
+pub fn check_volume(rt: &ContainerRuntime, name: &str, workspace: &Path) -> Result<()> {
+    if rt.dry_run {
+        return Ok(());
+    }
+    let owner = crate::prefetch::volume_owner(name)
+        .unwrap_or_else(|| label_of(rt, &["volume", "inspect"], &volume_label_format(), name));
+    check_owner("Volume", name, owner.as_deref(), workspace)
+        .map_err(|e| e.context(format!("`{} volume rm {}` removes it", rt.cmd(), name)))
+}
+
+/// Refuse to reuse container `name` if it was started for another workspace.
+pub fn check_container(rt: &ContainerRuntime, name: &str, workspace: &Path) -> Result<()> {
+    if rt.dry_run {
+        return Ok(());
+    }
+    let format = format!("{{{{ index .Config.Labels \"{}\" }}}}", LABEL);
+    let owner = label_of(rt, &["inspect"], &format, name);
+    check_owner("Container", name, owner.as_deref(), workspace)
+}
+
+/// Refuse to take over a project state file written for another workspace.
+pub fn check_state(state_file: &Path, recorded: &str, workspace: &Path) -> Result<()> {
+    let owner = (!recorded.is_empty()).then_some(recorded);
+    check_owner(
+        "Project state",
+        &state_file.display().to_string(),
+        owner,
+        workspace,
+    )
+}
+
+/// Registered workspaces other than `workspace` with the same project id.
+pub fn sharing_project_id(reg: &WorkspaceRegistry, workspace: &Path) -> Vec<String> {
+    let ws = workspace.to_string_lossy();
+    let id = workspace_hash(workspace);
+    reg.workspaces
+        .iter()
+        .filter(|(path, known)| known.project_id == id && **path != ws)
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// Warn when a registered workspace shares `workspace`'s project id.
+pub fn warn_if_shared(reg: &WorkspaceRegistry, workspace: &Path) {
+    for other in sharing_project_id(reg, workspace) {
+        eprintln!(
+            "{} {} and {} share project id {}; their containers, volumes and state would be mixed up. \
+             Rename one of the directories, or `ai-pod unregister {}` if it's gone.",
+            "warning:".yellow().bold(),
+            workspace.display(),
+            other,
+            workspace_hash(workspace),
+            other
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::KnownWorkspace;
+
+    #[test]
+    fn parse_owner_ignores_unlabelled_resources() {
+        assert_eq!(parse_owner("/src/api\n").as_deref(), Some("/src/api"));
+        assert_eq!(parse_owner("\n"), None);
+        assert_eq!(parse_owner("<no value>\n"), None);
+    }
+
+    #[test]
+    fn check_owner_refuses_another_workspace() {
+        let ws = Path::new("/src/api");
+        assert!(check_owner("Volume", "v", None, ws).is_ok());
+        assert!(check_owner("Volume", "v", Some("/src/api"), ws).is_ok());
+        let err = check_owner("Volume", "v", Some("/src/web"), ws)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("belongs to /src/web, not /src/api"), "{err}");
+        assert!(err.contains(&workspace_hash(ws)), "{err}");
+        assert!(check_state(Path::new("/s.json"), "", ws).is_ok());
+        assert!(check_state(Path::new("/s.json"), "/src/web", ws).is_err());
+    }
+
+    #[test]
+    fn sharing_project_id_finds_other_paths_with_the_same_hash() {
+        let ws = Path::new("/src/api");
+        let mut reg = WorkspaceRegistry::default();
+        reg.register(ws, 1);
+        reg.register(Path::new("/src/web"), 1);
+        assert!(sharing_project_id(&reg, ws).is_empty());
+        // A real collision is vanishingly rare; fake one.
+        reg.workspaces.insert(
+            "/elsewhere/api".into(),
+            KnownWorkspace {
+                project_id: workspace_hash(ws),
+                registered: 2,
+                server_port: None,
+            },
+        );
+        assert_eq!(sharing_project_id(&reg, ws), vec!["/elsewhere/api"]);
+    }
+}
@@ -23,10 +23,11 @@ use ratatui::{
 use serde::{Deserialize, Serialize};
 
 use crate::config::AppConfig;
-use crate::server::lifecycle::{MCP_PORT, ProjectState};
+use crate::server::lifecycle::{ProjectState, server_port};
 use crate::workspace::workspace_hash;
 
-const SERVER_BASE: &str = "http://127.0.0.1";
+/// `localhost` rather than `127.0.0.1`: the server may listen on IPv6 only.
+const SERVER_BASE: &str = "http://localhost";
 
 #[derive(Serialize)]
 struct RunReq<'a> {
@@ -92,6 +93,7 @@ fn load_ctx(config: &AppConfig, workspace: &Path) -> Result<Ctx> {
             "No project state for this workspace. Launch `ai-pod` first to initialise it."
         );
     }
+    crate::server::lifecycle::select_port(crate::registry::server_port(config, workspace));
     Ok(Ctx {
         project_id,
         api_key: state.api_key,
@@ -100,7 +102,7 @@ fn load_ctx(config: &AppConfig, workspace: &Path) -> Result<Ctx> {
 }
 
 fn url(path: &str) -> String {
-    format!("{}:{}{}", SERVER_BASE, MCP_PORT, path)
+    format!("{}:{}{}", SERVER_BASE, server_port(), path)
 }
 
 async fn fetch_list(ctx: &Ctx, all: bool) -> Result<Vec<CommandSummary>> {
@@ -0,0 +1,163 @@
+//! `ai-pod completions <shell>` and `ai-pod man`: shell completion scripts
+//! and manual pages generated from the clap definitions. Completions are
+//! dynamic — the shell calls back into `ai-pod` (`COMPLETE=<shell>`), so
+//! workspace aliases and session ids are offered as they exist right now.
+
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use clap_complete::CompletionCandidate;
+use clap_complete::env::Shells;
+use std::io::Write;
+use std::path::Path;
+
+use crate::cli::Cli;
+use crate::config::AppConfig;
+use crate::registry::WorkspaceRegistry;
+
+/// Environment variable the registration script sets when asking for
+/// completions; handled by `clap_complete::CompleteEnv` at startup.
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+const BIN_NAME: &str = "ai-pod";
+
+/// Answer a completion request from the shell and exit, if this process is
+/// one. Must run before anything else writes to stdout.
+pub fn handle_env() {
+    clap_complete::CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_VAR)
+        .complete();
+}
+
+pub fn run_completions(shell: clap_complete::Shell) -> Result<()> {
+    let name = shell.to_string();
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&name)
+        .with_context(|| format!("Completions are not supported for {}", name))?;
+    let mut out = std::io::stdout().lock();
+    completer
+        .write_registration(COMPLETE_VAR, BIN_NAME, BIN_NAME, BIN_NAME, &mut out)
+        .context("Failed to write completion script")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Print the top-level man page, or write one page per subcommand into
+/// `out_dir` (`ai-pod.1`, `ai-pod-open.1`, ...).
+pub fn run_man(out_dir: Option<&Path>) -> Result<()> {
+    match out_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            clap_mangen::generate_to(Cli::command(), dir)
+                .with_context(|| format!("Failed to write man pages to {}", dir.display()))?;
+            eprintln!("Man pages written to {}", dir.display());
+        }
+        None => {
+            let mut out = std::io::stdout().lock();
+            clap_mangen::Man::new(Cli::command())
+                .render(&mut out)
+                .context("Failed to render man page")?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn alias_names(config: &AppConfig) -> Vec<CompletionCandidate> {
+    WorkspaceRegistry::load(config)
+        .aliases
+        .into_iter()
+        .map(|(alias, ws)| CompletionCandidate::new(alias).help(Some(ws.into())))
+        .collect()
+}
+
+/// Aliases first, then recently launched workspace paths.
+fn open_targets(config: &AppConfig) -> Vec<CompletionCandidate> {
+    let mut out = alias_names(config);
+    for entry in WorkspaceRegistry::load(config).recent {
+        out.push(CompletionCandidate::new(entry.workspace).tag(Some("recent".into())));
+    }
+    out
+}
+
+/// Session ids with a state file under `~/.ai-pod/sessions/`.
+fn session_ids(config: &AppConfig) -> Vec<CompletionCandidate> {
+    let Ok(entries) = std::fs::read_dir(config.sessions_dir()) else {
+        return vec![];
+    };
+    let mut ids: Vec<String> = entries
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            (path.extension()? == "json").then(|| path.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+    ids.sort();
+    ids.into_iter().map(CompletionCandidate::new).collect()
+}
+
+fn with_config(f: fn(&AppConfig) -> Vec<CompletionCandidate>) -> Vec<CompletionCandidate> {
+    AppConfig::new().map(|c| f(&c)).unwrap_or_default()
+}
+
+/// Candidates for `ai-pod open <target>`.
+pub fn complete_open_target() -> Vec<CompletionCandidate> {
+    with_config(open_targets)
+}
+
+/// Candidates for `ai-pod alias remove <name>`.
+pub fn complete_alias() -> Vec<CompletionCandidate> {
+    with_config(alias_names)
+}
+
+/// Candidates for `--session`.
+pub fn complete_session() -> Vec<CompletionCandidate> {
+    with_config(session_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use tempfile::TempDir;
+
+    fn values(c: &[CompletionCandidate]) -> Vec<String> {
+        c.iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn open_targets_list_aliases_then_recent() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let mut reg = WorkspaceRegistry::default();
+        reg.set_alias("api", Path::new("/src/api"));
+        reg.record_launch(Path::new("/src/web"), 10);
+        reg.save(&config).unwrap();
+        assert_eq!(values(&open_targets(&config)), vec!["api", "/src/web"]);
+        assert_eq!(values(&alias_names(&config)), vec!["api"]);
+    }
+
+    #[test]
+    fn session_ids_come_from_state_files() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        assert!(session_ids(&config).is_empty());
+        std::fs::create_dir_all(config.sessions_dir()).unwrap();
+        std::fs::write(config.session_state_file("b2"), "{}").unwrap();
+        std::fs::write(config.session_state_file("a1"), "{}").unwrap();
+        std::fs::write(config.sessions_dir().join("notes.txt"), "").unwrap();
+        assert_eq!(values(&session_ids(&config)), vec!["a1", "b2"]);
+    }
+
+    #[test]
+    fn cli_renders_man_page() {
+        let mut buf = Vec::new();
+        clap_mangen::Man::new(Cli::command()).render(&mut buf).unwrap();
+        let page = String::from_utf8(buf).unwrap();
+        assert!(page.contains("ai\\-pod"));
+        assert!(page.contains("completions"));
+    }
+}
@@ -14,20 +14,45 @@ pub struct AppConfig {
 /// A user-configured host-to-container bind mount applied to every ai-pod
 /// container launch. Stored as part of [`GlobalConfig`] in
 /// `~/.ai-pod/config.json`.
+///
+/// `source`, `target` and `rw` are accepted as aliases of `host`, `container`
+/// and `writable` when the file is edited by hand.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct MountSpec {
     /// Tilde-expanded absolute host path, as the user supplied it. Symlinks
     /// are intentionally NOT resolved so users can mount things like a
-    /// `~/.claude/skills` directory that is itself a symlink.
+    /// `~/.claude/skills` directory that is itself a symlink. For
+    /// [`MountType::Volume`] this is the named volume instead.
+    #[serde(alias = "source")]
     pub host: String,
     /// Explicit container target path, or `None` to mirror under
     /// `/home/ai-pod`. When `None`, the host path must be under the user's
     /// `$HOME` directory.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(default, alias = "target", skip_serializing_if = "Option::is_none")]
     pub container: Option<String>,
     /// Read-only by default. Set true via `--writable` on `mount add`.
-    #[serde(default)]
+    #[serde(default, alias = "rw")]
     pub writable: bool,
+    #[serde(default, rename = "type", skip_serializing_if = "MountType::is_bind")]
+    pub kind: MountType,
+}
+
+/// What [`MountSpec::host`] refers to.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MountType {
+    /// A host file or directory.
+    #[default]
+    Bind,
+    /// A named volume, created by the runtime on first use. Handy for data
+    /// that should outlive containers but never touch the host filesystem.
+    Volume,
+}
+
+impl MountType {
+    pub fn is_bind(&self) -> bool {
+        *self == MountType::Bind
+    }
 }
 
 /// Global ai-pod configuration shared across all workspaces. Persists to
@@ -36,18 +61,808 @@ pub struct MountSpec {
 pub struct GlobalConfig {
     #[serde(default)]
     pub mounts: Vec<MountSpec>,
+    /// Package-manager caches (`npm`, `cargo`, `pip`) whose named volumes are
+    /// mounted into every container. Kept as plain strings so an unknown
+    /// name in a hand-edited file is skipped at launch instead of making the
+    /// whole config unparseable.
+    #[serde(default)]
+    pub caches: Vec<String>,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub userns: UsernsMode,
+    /// User the agent runs as; a workspace's own `user` setting wins.
+    #[serde(default, skip_serializing_if = "UserConfig::is_empty")]
+    pub user: UserConfig,
+    /// Default detach sequence for `run`/`attach` (e.g. `"ctrl-x,ctrl-d"`;
+    /// `""` disables detaching). Unset keeps the runtime's ctrl-p,ctrl-q.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detach_keys: Option<String>,
+    /// Container runtime binary, for hosts where it isn't on the PATH of
+    /// GUI-spawned shells (`"/opt/homebrew/bin/podman"`, `"podman-remote"`).
+    /// `AI_POD_RUNTIME` overrides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_path: Option<String>,
+    /// Host commands run before launch and after the container exits.
+    #[serde(default, skip_serializing_if = "crate::hooks::HooksConfig::is_empty")]
+    pub hooks: crate::hooks::HooksConfig,
+    /// Dotfiles repository cloned into every home volume at init.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dotfiles: Option<DotfilesConfig>,
+    /// How agent notifications are delivered on the host.
+    #[serde(default, skip_serializing_if = "NotifyConfig::is_empty")]
+    pub notify: NotifyConfig,
+    /// Set the terminal title to `ai-pod: <project> (<status>)` while
+    /// attached. Unset means enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal_title: Option<bool>,
+    /// Show the session banner in interactive shells inside the container.
+    /// Unset means enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banner: Option<bool>,
+    /// What to do in tmux when an agent in a tmux-launched session notifies.
+    #[serde(default, skip_serializing_if = "TmuxConfig::is_empty")]
+    pub tmux: TmuxConfig,
+    /// Host editor used to open files the agent changed.
+    #[serde(default, skip_serializing_if = "EditorConfig::is_empty")]
+    pub editor: EditorConfig,
+    /// Thresholds above which `ai-pod top` highlights a container.
+    #[serde(default, skip_serializing_if = "TopConfig::is_empty")]
+    pub top: TopConfig,
+    /// Files `ai-pod watch` reacts to.
+    #[serde(default, skip_serializing_if = "WatchConfig::is_empty")]
+    pub watch: WatchConfig,
+    /// Startup probe and container healthcheck of launched sessions.
+    #[serde(default, skip_serializing_if = "HealthcheckConfig::is_empty")]
+    pub healthcheck: HealthcheckConfig,
+    /// How the shared server listens.
+    #[serde(default, skip_serializing_if = "ServerConfig::is_empty")]
+    pub server: ServerConfig,
+    /// Defaults for `ai-pod init`.
+    #[serde(default, skip_serializing_if = "InitConfig::is_empty")]
+    pub init: InitConfig,
+    /// Signature verification of base images before a build.
+    #[serde(default, skip_serializing_if = "VerifyConfig::is_empty")]
+    pub verify: VerifyConfig,
+    /// Image build settings.
+    #[serde(default, skip_serializing_if = "BuildConfig::is_empty")]
+    pub build: BuildConfig,
+    /// Container network settings.
+    #[serde(default, skip_serializing_if = "NetworkConfig::is_empty")]
+    pub network: NetworkConfig,
+    /// What ai-pod writes into the session's CLAUDE.md.
+    #[serde(default, skip_serializing_if = "ClaudeMdConfig::is_empty")]
+    pub claude_md: ClaudeMdConfig,
+    /// Opening links from the session in the host browser.
+    #[serde(default, skip_serializing_if = "BrowserConfig::is_empty")]
+    pub browser: BrowserConfig,
+    /// Forwarding of ports the agent starts listening on.
+    #[serde(default, skip_serializing_if = "PortsConfig::is_empty")]
+    pub ports: PortsConfig,
+    /// Entrypoint and command of the interactive session; a workspace's
+    /// own `launch` setting wins.
+    #[serde(default, skip_serializing_if = "LaunchConfig::is_empty")]
+    pub launch: LaunchConfig,
+    /// Keep shell histories, `.npmrc` and user-installed npm/pip packages
+    /// when the home volume is regenerated. Unset means enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carry_over: Option<bool>,
+    /// Variables passed in from the workspace's `.envrc`.
+    #[serde(default, skip_serializing_if = "DirenvConfig::is_empty")]
+    pub direnv: DirenvConfig,
+    /// What a workspace's new state volume is seeded with from the host.
+    #[serde(default, skip_serializing_if = "SeedConfig::is_empty")]
+    pub seed: SeedConfig,
+    /// Local usage statistics (see [`crate::stats`]).
+    #[serde(default, skip_serializing_if = "StatsConfig::is_empty")]
+    pub stats: StatsConfig,
+    /// Spending caps for API-billed agents.
+    #[serde(default, skip_serializing_if = "BudgetConfig::is_empty")]
+    pub budget: BudgetConfig,
+    /// Syncing the workspace into a volume instead of bind-mounting it; a
+    /// workspace's own `sync` setting wins.
+    #[serde(default, skip_serializing_if = "SyncConfig::is_empty")]
+    pub sync: SyncConfig,
+}
+
+/// Host browser opener (see [`crate::browser`]), stored as `browser` in
+/// `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct BrowserConfig {
+    /// Install the opener shim in session containers. Unset means enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Hosts whose URLs are opened besides `localhost` and `127.0.0.1`:
+    /// `docs.rs`, `*.github.com` (subdomains), or `*` for any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+}
+
+impl BrowserConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == BrowserConfig::default()
+    }
+}
+
+/// Port forwarding (see [`crate::ports`]), stored as `ports` in
+/// `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct PortsConfig {
+    /// Forward ports that start listening in the container to the host.
+    /// Unset means enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward: Option<bool>,
+    /// Container ports never forwarded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<u16>,
+}
+
+impl PortsConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == PortsConfig::default()
+    }
+}
+
+/// What the interactive session runs instead of the image's `ENTRYPOINT`
+/// and `CMD`, stored as `launch` in `~/.ai-pod/config.json` or a
+/// workspace's project settings. `--entrypoint` and a command after `--`
+/// override it for one launch.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct LaunchConfig {
+    /// Executable run in place of the image's `ENTRYPOINT`; `""` clears it
+    /// so the command runs on its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<String>,
+    /// Arguments in place of the image's `CMD`, e.g.
+    /// `["tmux", "new", "claude"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+}
+
+impl LaunchConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == LaunchConfig::default()
+    }
+}
+
+/// `.envrc` evaluation (see [`crate::direnv`]), stored as `direnv` in
+/// `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct DirenvConfig {
+    /// Names of the variables passed into the container; `PREFIX_*`
+    /// matches a prefix. Empty leaves the `.envrc` alone.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+}
+
+impl DirenvConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == DirenvConfig::default()
+    }
+}
+
+/// History import (see [`crate::history_import`]), stored as `seed` in
+/// `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct SeedConfig {
+    /// Copy the host's Claude Code conversation history into a new state
+    /// volume. Unset means off: only settings and sign-in are copied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<bool>,
+    /// Ask before copying more history than this many MB (default 200).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm_mb: Option<u64>,
+}
+
+impl SeedConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == SeedConfig::default()
+    }
+}
+
+/// Usage statistics (see [`crate::stats`]), stored as `stats` in
+/// `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct StatsConfig {
+    /// Record launches, builds and session time per workspace in
+    /// `~/.ai-pod/stats.json`. Unset means off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+impl StatsConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == StatsConfig::default()
+    }
+}
+
+/// Spending caps (see [`crate::server::budget`]), stored as `budget` in
+/// `~/.ai-pod/config.json`. Amounts are the agent's own cost estimate in USD.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct BudgetConfig {
+    /// Estimated spend per day, across all workspaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_usd: Option<f64>,
+    /// Estimated spend per session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_usd: Option<f64>,
+    /// What happens to a session over a cap, besides the notification.
+    /// Unset means `warn`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<BudgetAction>,
+}
+
+impl BudgetConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == BudgetConfig::default()
+    }
+
+    /// Whether any cap is set, so spend has to be tracked.
+    pub fn enabled(&self) -> bool {
+        self.daily_usd.is_some() || self.session_usd.is_some()
+    }
+}
+
+/// Workspace sync (see [`crate::sync`]), stored as `sync` in
+/// `~/.ai-pod/config.json` or a workspace's project settings.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct SyncConfig {
+    /// Keep /app in a volume synced with mutagen instead of bind-mounting
+    /// the workspace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Extra mutagen ignore patterns. Masked and excluded paths are never
+    /// synced.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+}
+
+impl SyncConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == SyncConfig::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BudgetAction {
+    /// Only notify.
+    #[default]
+    Warn,
+    /// `pause` the container; `unpause` resumes it where it was.
+    Pause,
+    /// `stop` the container.
+    Stop,
+}
+
+/// Generated CLAUDE.md sections (see [`crate::claude_md`]), stored as
+/// `claude_md` in `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct ClaudeMdConfig {
+    /// List the dev services listening on the host. Unset means enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_services: Option<bool>,
+    /// Ports to check besides the common ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host_ports: Vec<u16>,
+    /// Name the workspace's current git branch.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub branch: bool,
+    /// Number of recent commit subjects to list; 0 lists none.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub commits: usize,
+    /// Workspace files (e.g. `TODO.md`) whose contents are included.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub todo_files: Vec<String>,
+    /// An organization's shared fragment, the `team` layer (`~` is
+    /// expanded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_file: Option<String>,
+    /// Layers left out: `preamble`, `global`, `team` or `project`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disable: Vec<String>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl ClaudeMdConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == ClaudeMdConfig::default()
+    }
+}
+
+/// Container network settings, stored as `network` in
+/// `~/.ai-pod/config.json`. Auditing and the limits work through the shared
+/// server's egress proxy; setting any of them routes container HTTP(S)
+/// traffic through it. Host entries and DNS servers are passed to the
+/// runtime.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct NetworkConfig {
+    /// Record the destinations contacted, for `ai-pod egress`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub audit: bool,
+    /// Throughput cap per session, both directions together, e.g. `"8mbit"`
+    /// or `"500KB"` (per second).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bandwidth: Option<String>,
+    /// Connections a session may have open at once; further ones wait.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+    /// Destinations (`host` or `host:port`) the egress proxy may reach even
+    /// though they are loopback, link-local or cloud metadata addresses.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub local_destinations: Vec<String>,
+    /// Extra `/etc/hosts` entries, each `"name=ip"` (or `"name:ip"`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
+    /// DNS servers to use instead of the runtime's default resolver.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns: Vec<String>,
+    /// Search domains tried for unqualified host names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns_search: Vec<String>,
+}
+
+impl NetworkConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == NetworkConfig::default()
+    }
+
+    /// Whether containers should use the egress proxy.
+    pub fn proxied(&self) -> bool {
+        self.audit || self.bandwidth.is_some() || self.max_connections.is_some()
+    }
+}
+
+/// Image build settings, stored as `build` in `~/.ai-pod/config.json`. The
+/// cache options are defaults for `--cache-from` / `--cache-to`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct BuildConfig {
+    /// Registries to pull cached layers from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cache_from: Vec<String>,
+    /// Registry to push the build's layers to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_to: Option<String>,
+    /// Scan every newly built image for baked-in secrets.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub scan_secrets: bool,
+}
+
+impl BuildConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == BuildConfig::default()
+    }
+}
+
+/// Cosign verification of the Dockerfile's base images, stored as `verify`
+/// in `~/.ai-pod/config.json`. Set `key` for public-key signatures, or
+/// `identity` and `issuer` for keyless ones; with neither, images aren't
+/// verified.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct VerifyConfig {
+    /// Path of the cosign public key (`~` is expanded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Regular expression the signing certificate's identity must match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// OIDC issuer of the signing certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// Base images that must be signed, as globs like `ghcr.io/acme/*`.
+    /// Empty means every base image.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+impl VerifyConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == VerifyConfig::default()
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.key.is_some() || self.identity.is_some()
+    }
+}
+
+/// `ai-pod init` defaults, stored as `init` in `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct InitConfig {
+    /// Template source used when `--from` is omitted, e.g. the
+    /// organization's template repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+}
+
+impl InitConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == InitConfig::default()
+    }
+}
+
+/// Shared server settings, stored as `server` in `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct ServerConfig {
+    #[serde(default)]
+    pub listen: ListenMode,
+}
+
+impl ServerConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == ServerConfig::default()
+    }
+}
+
+/// Address families the shared server binds. Read when the server starts.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ListenMode {
+    /// IPv6 and IPv4, falling back to IPv4 when the host has no IPv6.
+    #[default]
+    Dual,
+    /// `0.0.0.0` only.
+    Ipv4,
+    /// `[::]` only.
+    Ipv6,
+}
+
+/// `ai-pod watch` defaults, stored as `watch` in `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct WatchConfig {
+    /// Workspace-relative globs (`*`, `?`, `**`) used when `--glob` isn't
+    /// given. Empty means every file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub globs: Vec<String>,
+}
+
+impl WatchConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == WatchConfig::default()
+    }
+}
+
+/// Session healthcheck, stored as `healthcheck` in `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct HealthcheckConfig {
+    /// Start sessions detached and wait for the probe to pass before
+    /// attaching. On by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Shell command run in the container as the probe; exit 0 is healthy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Seconds to wait for the first passing probe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl HealthcheckConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == HealthcheckConfig::default()
+    }
+}
+
+/// Opener for changed files, stored as `editor` in `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct EditorConfig {
+    /// Argv of the opener. With `{file}` (and optionally `{line}`) it runs
+    /// once per file, otherwise the absolute paths are appended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    /// Open the changed files automatically when the agent stops.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_on_stop: Option<bool>,
+}
+
+impl EditorConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == EditorConfig::default()
+    }
+}
+
+/// tmux notification actions, stored as `tmux` in `~/.ai-pod/config.json`.
+/// They apply only to sessions launched inside tmux.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct TmuxConfig {
+    /// Set to `false` to skip tmux entirely. Default on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Ring the pane's bell so tmux flags the window. Default on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bell: Option<bool>,
+    /// Show the message with `display-message` on the pane. Default on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_message: Option<bool>,
+    /// Rename the window to `<project> *`. Default off, since tmux keeps the
+    /// name until you rename it again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rename_window: Option<bool>,
+}
+
+impl TmuxConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == TmuxConfig::default()
+    }
+}
+
+/// `ai-pod top` highlight thresholds, stored as `top` in
+/// `~/.ai-pod/config.json`. Unset values use the defaults in `top.rs`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct TopConfig {
+    /// CPU usage in percent of one core (400 = four cores busy).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_percent: Option<f64>,
+    /// Memory usage in percent of the container's limit (or host memory).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_percent: Option<f64>,
+    /// Number of processes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids: Option<u64>,
+}
+
+impl TopConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == TopConfig::default()
+    }
+}
+
+/// Notification delivery, stored as `notify` in `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct NotifyConfig {
+    /// Argv run instead of the desktop notification, e.g.
+    /// `["my-script", "{title}", "{message}"]`. `{title}`, `{message}`,
+    /// `{workspace}` and `{project_id}` are substituted in every argument.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    /// Local time range with no notifications, e.g. `"22:00-08:00"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<String>,
+    /// Only these kinds are delivered (`stop`, `permission`, `message`);
+    /// empty means all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    /// Skip notifications from sessions launched less than this many seconds ago.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_session_secs: Option<u64>,
+    /// Seconds to wait for more notifications of the same kind from the same
+    /// workspace before showing one summary; 0 shows each right away.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debounce_secs: Option<u64>,
+    /// Notification text per kind (`stop`, `permission`, `message`), with
+    /// `default` applying to kinds that have none.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub templates: std::collections::BTreeMap<String, NotifyTemplate>,
+}
+
+/// Title and body of a notification, with `{project}`, `{session_title}`,
+/// `{event}`, `{message}`, `{duration}`, `{count}`, `{workspace}` and
+/// `{project_id}` placeholders. Unset fields fall back to the `default` template, then to
+/// the built-in text.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct NotifyTemplate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Body when several notifications were folded into one (see
+    /// `debounce_secs`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+impl NotifyConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == NotifyConfig::default()
+    }
+}
+
+/// A dotfiles repository installed into each container's home, like the dev
+/// containers dotfiles feature. Stored as `dotfiles` in
+/// `~/.ai-pod/config.json`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DotfilesConfig {
+    /// Git URL, or `owner/repo` as shorthand for a GitHub repository.
+    pub repository: String,
+    /// Command run from the checkout. When unset, the first existing of the
+    /// usual `install.sh`/`bootstrap.sh`/`setup.sh` scripts is run; if there
+    /// is none, top-level dotfiles are symlinked into `$HOME`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub install_command: Option<String>,
+    /// Checkout location inside the container; `~/` means the container home.
+    #[serde(default = "default_dotfiles_target")]
+    pub target_path: String,
+}
+
+fn default_dotfiles_target() -> String {
+    "~/dotfiles".to_string()
+}
+
+impl DotfilesConfig {
+    /// Clone URL, expanding the `owner/repo` GitHub shorthand.
+    pub fn clone_url(&self) -> String {
+        let repo = self.repository.trim();
+        let is_shorthand = !repo.contains(':')
+            && !repo.starts_with('/')
+            && repo.split('/').count() == 2
+            && repo.split('/').all(|p| !p.is_empty());
+        if is_shorthand {
+            format!("https://github.com/{}.git", repo)
+        } else {
+            repo.to_string()
+        }
+    }
+}
+
+/// The user the agent runs as, stored as `user` in `~/.ai-pod/config.json`
+/// or a workspace's project settings. Unset runs the image's `USER`, the
+/// `ai-pod` user of the Dockerfile template.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct UserConfig {
+    /// `--user` value: a user name, `uid` or `uid:gid`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Home directory in the container. Defaults to `/root` for root,
+    /// `/home/<name>` for a user name and `/home/ai-pod` for a uid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub home: Option<String>,
+}
+
+impl UserConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == UserConfig::default()
+    }
+
+    fn user(&self) -> Option<&str> {
+        let name = self.name.as_deref()?;
+        name.split(':').next().filter(|u| !u.is_empty())
+    }
+
+    /// The home directory the home volume is mounted on.
+    pub fn home_dir(&self) -> String {
+        if let Some(home) = self.home.as_deref().map(|h| h.trim_end_matches('/')).filter(|h| !h.is_empty()) {
+            return home.to_string();
+        }
+        match self.user() {
+            Some("root" | "0") => "/root".to_string(),
+            Some(user) if !user.chars().all(|c| c.is_ascii_digit()) => format!("/home/{}", user),
+            _ => crate::container::CONTAINER_HOME.to_string(),
+        }
+    }
+
+    /// `chown` owner of directories the user writes to: the configured
+    /// `uid:gid`, a uid's own group, a name's login group, or the template's
+    /// `ai-pod` user.
+    pub fn owner(&self) -> String {
+        match self.name.as_deref() {
+            None => "ai-pod:ai-pod".to_string(),
+            Some(name) if name.contains(':') => name.to_string(),
+            Some(uid) if uid.chars().all(|c| c.is_ascii_digit()) => format!("{}:{}", uid, uid),
+            Some(name) => format!("{}:", name),
+        }
+    }
+
+    /// `--user` and `HOME` for containers that run as this user; none for
+    /// the image's default user.
+    pub fn run_args(&self) -> Vec<String> {
+        match &self.name {
+            Some(name) => vec![
+                format!("--user={}", name),
+                "-e".to_string(),
+                format!("HOME={}", self.home_dir()),
+            ],
+            None => Vec::new(),
+        }
+    }
+
+    /// Why `name` or `home` can't be used, if so.
+    pub fn problem(&self) -> Option<String> {
+        if let Some(name) = &self.name {
+            let valid = !name.is_empty()
+                && name.split(':').count() <= 2
+                && name.split(':').all(|p| {
+                    !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                });
+            if !valid {
+                return Some(format!("user.name '{}' must be a user name, uid or uid:gid", name));
+            }
+        }
+        if let Some(home) = &self.home
+            && !home.starts_with('/')
+        {
+            return Some(format!("user.home '{}' must be an absolute path", home));
+        }
+        None
+    }
+}
+
+/// User-namespace mapping for the agent container, stored as `userns` in
+/// `~/.ai-pod/config.json`. Only affects rootless Podman; Docker ignores it.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UsernsMode {
+    /// Podman's default mapping: the host user is container UID 0, so files
+    /// the agent creates under `/app` land on a sub-UID on the host.
+    #[default]
+    Default,
+    /// `--userns=keep-id`, mapping the host user onto the image's `ai-pod`
+    /// user so workspace files keep the host user's ownership.
+    KeepId,
+}
+
+/// Container hardening options applied to every agent container launch.
+/// Stored under `security` in `~/.ai-pod/config.json`. The filesystem options
+/// default to off so Dockerfiles that write outside `/app` and `$HOME` keep
+/// working; capabilities are dropped and privilege escalation is blocked by
+/// default since the agent runs as the unprivileged `ai-pod` user anyway.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SecurityConfig {
+    /// Run with `--read-only`. The home volume, `/app`, user mounts, masks,
+    /// caches and a tmpfs `/tmp` remain the only writable locations.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Mount a tmpfs on `/tmp` so scratch files never outlive the container.
+    /// Implied by `read_only`.
+    #[serde(default)]
+    pub tmpfs_tmp: bool,
+    /// Capabilities passed to `--cap-drop`. `["ALL"]` unless overridden.
+    #[serde(default = "default_cap_drop")]
+    pub cap_drop: Vec<String>,
+    /// Capabilities re-added with `--cap-add` after the drop.
+    #[serde(default)]
+    pub cap_add: Vec<String>,
+    /// Pass `--security-opt no-new-privileges` so setuid binaries in the
+    /// image can't regain what `cap_drop` removed.
+    #[serde(default = "default_true")]
+    pub no_new_privileges: bool,
+    /// Host path of a custom seccomp JSON profile (`~` is expanded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seccomp_profile: Option<String>,
+    /// Name of an AppArmor profile already loaded on the host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apparmor_profile: Option<String>,
+}
+
+fn default_cap_drop() -> Vec<String> {
+    vec!["ALL".to_string()]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            tmpfs_tmp: false,
+            cap_drop: default_cap_drop(),
+            cap_add: Vec::new(),
+            no_new_privileges: true,
+            seccomp_profile: None,
+            apparmor_profile: None,
+        }
+    }
 }
 
 impl GlobalConfig {
     pub fn path(config: &AppConfig) -> PathBuf {
-        config.config_dir.join("config.json")
+        crate::layout::settings_dir(&config.config_dir).join("config.json")
     }
 
     /// Load `~/.ai-pod/config.json`. Returns default if missing or malformed
     /// (with a stderr warning in the malformed case) so a corrupt file never
     /// blocks a launch.
     pub fn load(config: &AppConfig) -> Self {
-        let path = Self::path(config);
+        Self::load_from_dir(&config.config_dir)
+    }
+
+    /// [`GlobalConfig::load`] for callers that only have the config dir,
+    /// like the shared server.
+    pub fn load_from_dir(config_dir: &Path) -> Self {
+        let path = crate::layout::settings_dir(config_dir).join("config.json");
         let raw = match std::fs::read_to_string(&path) {
             Ok(s) => s,
             Err(_) => return Self::default(),
@@ -67,6 +882,9 @@ impl GlobalConfig {
 
     pub fn save(&self, config: &AppConfig) -> Result<()> {
         let path = Self::path(config);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create the settings directory")?;
+        }
         let json = serde_json::to_string_pretty(self)?;
         let tmp = path.with_extension("tmp");
         let mut file = OpenOptions::new()
@@ -102,6 +920,22 @@ impl GlobalConfig {
         self.mounts.retain(|m| m.host != host);
         before != self.mounts.len()
     }
+
+    /// Returns false if the cache is already enabled.
+    pub fn enable_cache(&mut self, name: &str) -> bool {
+        if self.caches.iter().any(|c| c == name) {
+            return false;
+        }
+        self.caches.push(name.to_string());
+        true
+    }
+
+    /// Returns true if the cache was enabled before.
+    pub fn disable_cache(&mut self, name: &str) -> bool {
+        let before = self.caches.len();
+        self.caches.retain(|c| c != name);
+        before != self.caches.len()
+    }
 }
 
 /// Path to a per-session record, relative to the config dir. Kept in a
@@ -118,6 +952,15 @@ pub fn session_state_path(config_dir: &Path, session_id: &str) -> PathBuf {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SessionState {
     pub runtime: crate::runtime::RuntimeKind,
+    /// The tmux pane the session was launched from, for notifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux: Option<crate::tmux::TmuxTarget>,
+    /// The session's label (`--title` or the start of its prompt).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The workspace the session was launched in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<PathBuf>,
 }
 
 impl SessionState {
@@ -129,6 +972,16 @@ impl SessionState {
         serde_json::from_str(&raw).ok()
     }
 
+    /// [`Self::load_from_dir`] for a session id a container sent: `None`
+    /// unless the session belongs to `workspace`, so one project's agent
+    /// can't reach another project's session.
+    pub fn load_for(config_dir: &Path, session_id: &str, workspace: &Path) -> Option<Self> {
+        if session_id.is_empty() || !session_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        Self::load_from_dir(config_dir, session_id).filter(|s| s.workspace.as_deref() == Some(workspace))
+    }
+
     /// Persist this record to `~/.ai-pod/sessions/{session_id}.json` at 0o600,
     /// creating the `sessions/` directory if needed. Atomic via temp + rename.
     pub fn save(&self, config: &AppConfig, session_id: &str) -> Result<()> {
@@ -156,7 +1009,7 @@ impl SessionState {
 impl AppConfig {
     pub fn new() -> Result<Self> {
         let home_dir = dirs::home_dir().context("Could not determine home directory")?;
-        let config_dir = home_dir.join(".ai-pod");
+        let config_dir = crate::layout::state_dir(&home_dir);
 
         Ok(Self {
             runtime_settings: config_dir.join("runtime-settings.json"),
@@ -167,7 +1020,7 @@ impl AppConfig {
 
     pub fn init(&self) -> Result<()> {
         std::fs::create_dir_all(&self.config_dir).context("Failed to create ~/.ai-pod/")?;
-        Ok(())
+        crate::migrate::run(self)
     }
 
     /// Returns path to the per-project state file: ~/.ai-pod/{hash}.json
@@ -185,11 +1038,29 @@ impl AppConfig {
         self.config_dir.join("sessions")
     }
 
+    /// Directory holding session checkpoints (see `checkpoint`):
+    /// ~/.ai-pod/checkpoints/
+    pub fn checkpoints_dir(&self) -> PathBuf {
+        self.config_dir.join("checkpoints")
+    }
+
     /// Returns path to a per-session record: ~/.ai-pod/sessions/{session_id}.json
     pub fn session_state_file(&self, session_id: &str) -> PathBuf {
         session_state_path(&self.config_dir, session_id)
     }
 
+    /// Per-session context appended to the agent's instructions (see
+    /// `container::session_context_args`): ~/.ai-pod/sessions/{session_id}.md
+    pub fn session_context_file(&self, session_id: &str) -> PathBuf {
+        self.sessions_dir().join(format!("{session_id}.md"))
+    }
+
+    /// Per-session banner for shells in the container (see
+    /// `crate::banner`): ~/.ai-pod/sessions/{session_id}.banner
+    pub fn session_banner_file(&self, session_id: &str) -> PathBuf {
+        self.sessions_dir().join(format!("{session_id}.banner"))
+    }
+
     pub fn claude_settings_path(&self) -> PathBuf {
         self.home_dir.join(".claude").join("settings.json")
     }
@@ -221,39 +1092,43 @@ impl AppConfig {
     }
 }
 
+/// An `AppConfig` for tests: the temp dir is the home directory, with an
+/// (existing) `.ai-pod` config dir inside it.
+#[cfg(test)]
+pub(crate) fn test_config(dir: &tempfile::TempDir) -> AppConfig {
+    let home = dir.path().to_path_buf();
+    let config_dir = home.join(".ai-pod");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    AppConfig {
+        runtime_settings: config_dir.join("runtime-settings.json"),
+        config_dir,
+        home_dir: home,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
 
-    fn temp_config(dir: &TempDir) -> AppConfig {
-        let home = dir.path().to_path_buf();
-        let config_dir = home.join(".ai-pod");
-        AppConfig {
-            runtime_settings: config_dir.join("runtime-settings.json"),
-            config_dir,
-            home_dir: home,
-        }
-    }
-
     #[test]
     fn all_paths_are_under_config_dir() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         assert!(config.runtime_settings.starts_with(&config.config_dir));
     }
 
     #[test]
     fn config_dir_is_under_home() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         assert!(config.config_dir.starts_with(&config.home_dir));
     }
 
     #[test]
     fn project_state_file_is_under_config_dir() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let p = config.project_state_file("abc123def456");
         assert!(p.starts_with(&config.config_dir));
         assert!(p.to_string_lossy().ends_with(".json"));
@@ -263,7 +1138,7 @@ mod tests {
     #[test]
     fn server_state_file_is_under_config_dir() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let p = config.server_state_file();
         assert!(p.starts_with(&config.config_dir));
         assert!(p.to_string_lossy().ends_with("server.json"));
@@ -272,7 +1147,7 @@ mod tests {
     #[test]
     fn session_state_file_is_under_sessions_subdir() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let p = config.session_state_file("abc123");
         assert!(p.starts_with(config.sessions_dir()));
         assert!(p.to_string_lossy().ends_with("abc123.json"));
@@ -286,11 +1161,14 @@ mod tests {
         use crate::runtime::RuntimeKind;
         use std::os::unix::fs::PermissionsExt;
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         config.init().unwrap();
 
         SessionState {
             runtime: RuntimeKind::Docker,
+            tmux: None,
+            title: None,
+            workspace: Some("/p/a".into()),
         }
         .save(&config, "sess0001")
         .unwrap();
@@ -301,19 +1179,22 @@ mod tests {
 
         let loaded = SessionState::load_from_dir(&config.config_dir, "sess0001").unwrap();
         assert_eq!(loaded.runtime, RuntimeKind::Docker);
+        assert!(SessionState::load_for(&config.config_dir, "sess0001", Path::new("/p/a")).is_some());
+        assert!(SessionState::load_for(&config.config_dir, "sess0001", Path::new("/p/b")).is_none());
+        assert!(SessionState::load_for(&config.config_dir, "../sess0001", Path::new("/p/a")).is_none());
     }
 
     #[test]
     fn session_state_load_missing_returns_none() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         assert!(SessionState::load_from_dir(&config.config_dir, "nope").is_none());
     }
 
     #[test]
     fn claude_settings_path_points_to_settings_json() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let p = config.claude_settings_path();
         assert!(p.ends_with("settings.json"));
         assert!(p.to_string_lossy().contains(".claude"));
@@ -322,7 +1203,7 @@ mod tests {
     #[test]
     fn claude_md_path_points_to_claude_md() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let p = config.claude_md_path();
         assert!(p.ends_with("CLAUDE.md"));
         assert!(p.to_string_lossy().contains(".claude"));
@@ -331,8 +1212,8 @@ mod tests {
     #[test]
     fn init_creates_config_dir() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
-        assert!(!config.config_dir.exists());
+        let config = test_config(&dir);
+        std::fs::remove_dir(&config.config_dir).unwrap();
         config.init().unwrap();
         assert!(config.config_dir.exists());
     }
@@ -340,7 +1221,7 @@ mod tests {
     #[test]
     fn global_config_load_missing_returns_default() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let loaded = GlobalConfig::load(&config);
         assert!(loaded.mounts.is_empty());
     }
@@ -349,7 +1230,7 @@ mod tests {
     fn global_config_round_trips() {
         use std::os::unix::fs::PermissionsExt;
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         config.init().unwrap();
 
         let mut gc = GlobalConfig::default();
@@ -357,11 +1238,13 @@ mod tests {
             host: "/home/user/.claude/skills".into(),
             container: None,
             writable: false,
+            kind: MountType::Bind,
         }));
         assert!(gc.add(MountSpec {
             host: "/etc/secret.pem".into(),
             container: Some("/run/secrets/secret.pem".into()),
             writable: true,
+            kind: MountType::Bind,
         }));
         gc.save(&config).unwrap();
 
@@ -393,6 +1276,7 @@ mod tests {
             host: "/foo".into(),
             container: None,
             writable: false,
+            kind: MountType::Bind,
         };
         assert!(gc.add(spec.clone()));
         assert!(!gc.add(spec));
@@ -406,16 +1290,121 @@ mod tests {
             host: "/foo".into(),
             container: None,
             writable: false,
+            kind: MountType::Bind,
         });
         assert!(gc.remove("/foo"));
         assert!(!gc.remove("/foo"));
         assert!(gc.mounts.is_empty());
     }
 
+    #[test]
+    fn global_config_cache_toggle_round_trips() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        config.init().unwrap();
+
+        let mut gc = GlobalConfig::default();
+        assert!(gc.enable_cache("npm"));
+        assert!(!gc.enable_cache("npm"));
+        assert!(gc.enable_cache("cargo"));
+        assert!(gc.disable_cache("npm"));
+        assert!(!gc.disable_cache("npm"));
+        gc.save(&config).unwrap();
+
+        let loaded = GlobalConfig::load(&config);
+        assert_eq!(loaded.caches, vec!["cargo".to_string()]);
+    }
+
+    #[test]
+    fn global_config_without_caches_key_still_loads() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        config.init().unwrap();
+        std::fs::write(GlobalConfig::path(&config), r#"{"mounts":[]}"#).unwrap();
+        let loaded = GlobalConfig::load(&config);
+        assert!(loaded.caches.is_empty());
+    }
+
+    #[test]
+    fn global_config_security_defaults() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        config.init().unwrap();
+        std::fs::write(
+            GlobalConfig::path(&config),
+            r#"{"security":{"read_only":true}}"#,
+        )
+        .unwrap();
+        let loaded = GlobalConfig::load(&config);
+        assert!(loaded.security.read_only);
+        assert!(!loaded.security.tmpfs_tmp);
+        assert_eq!(loaded.security.cap_drop, vec!["ALL".to_string()]);
+        assert!(loaded.security.no_new_privileges);
+        assert_eq!(GlobalConfig::default().security, SecurityConfig::default());
+    }
+
+    #[test]
+    fn global_config_userns_parses_kebab_case() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        config.init().unwrap();
+        std::fs::write(GlobalConfig::path(&config), r#"{"userns":"keep-id"}"#).unwrap();
+        assert_eq!(GlobalConfig::load(&config).userns, UsernsMode::KeepId);
+        assert_eq!(GlobalConfig::default().userns, UsernsMode::Default);
+    }
+
+    #[test]
+    fn user_config_derives_home_and_owner() {
+        let user = |name: &str| UserConfig {
+            name: Some(name.into()),
+            home: None,
+        };
+        assert_eq!(UserConfig::default().home_dir(), "/home/ai-pod");
+        assert_eq!(UserConfig::default().owner(), "ai-pod:ai-pod");
+        assert!(UserConfig::default().run_args().is_empty());
+        assert_eq!(user("node").home_dir(), "/home/node");
+        assert_eq!(user("node").owner(), "node:");
+        assert_eq!(user("root").home_dir(), "/root");
+        assert_eq!(user("1001").home_dir(), "/home/ai-pod");
+        assert_eq!(user("1001").owner(), "1001:1001");
+        assert_eq!(user("1001:100").owner(), "1001:100");
+        let custom = UserConfig {
+            home: Some("/srv/dev/".into()),
+            ..user("dev")
+        };
+        assert_eq!(custom.run_args(), vec!["--user=dev", "-e", "HOME=/srv/dev"]);
+        assert!(user("dev;rm").problem().is_some());
+        assert!(user("1:2:3").problem().is_some());
+        assert!(UserConfig { home: Some("srv".into()), ..user("dev") }.problem().is_some());
+        assert!(custom.problem().is_none());
+    }
+
+    #[test]
+    fn mount_spec_accepts_source_target_aliases_and_type() {
+        let spec: MountSpec = serde_json::from_str(
+            r#"{"source":"datasets","target":"/data","rw":true,"type":"volume"}"#,
+        )
+        .unwrap();
+        assert_eq!(spec.host, "datasets");
+        assert_eq!(spec.container.as_deref(), Some("/data"));
+        assert!(spec.writable);
+        assert_eq!(spec.kind, MountType::Volume);
+
+        // Bind is the default and is not written out, keeping existing files
+        // byte-identical.
+        let bind = MountSpec {
+            host: "/x".into(),
+            container: None,
+            writable: false,
+            kind: MountType::Bind,
+        };
+        assert!(!serde_json::to_string(&bind).unwrap().contains("type"));
+    }
+
     #[test]
     fn global_config_load_malformed_returns_default() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         config.init().unwrap();
         std::fs::write(GlobalConfig::path(&config), "{not valid json").unwrap();
         let loaded = GlobalConfig::load(&config);
@@ -429,7 +1418,7 @@ mod tests {
         // effect on O_CREAT, so without the explicit set_permissions after
         // rename the final file would inherit the looser mode.
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         config.init().unwrap();
         let tmp = GlobalConfig::path(&config).with_extension("tmp");
         std::fs::write(&tmp, "stale").unwrap();
@@ -445,4 +1434,21 @@ mod tests {
             & 0o777;
         assert_eq!(mode, 0o600, "save must enforce 0o600 even over a stale tmp");
     }
+
+    #[test]
+    fn dotfiles_config_defaults_and_shorthand() {
+        let gc: GlobalConfig =
+            serde_json::from_str(r#"{"dotfiles": {"repository": "octo/dotfiles"}}"#).unwrap();
+        let d = gc.dotfiles.unwrap();
+        assert_eq!(d.target_path, "~/dotfiles");
+        assert_eq!(d.install_command, None);
+        assert_eq!(d.clone_url(), "https://github.com/octo/dotfiles.git");
+
+        let full = DotfilesConfig {
+            repository: "git@github.com:octo/dotfiles.git".into(),
+            install_command: None,
+            target_path: default_dotfiles_target(),
+        };
+        assert_eq!(full.clone_url(), "git@github.com:octo/dotfiles.git");
+    }
 }
@@ -0,0 +1,382 @@
+//! `ai-pod config check`: validate the global config and the current
+//! workspace's settings together and report every problem at once, instead
+//! of failing one at a time deep inside a runtime call at launch.
+
+use colored::Colorize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::{AppConfig, GlobalConfig, MountSpec, UsernsMode};
+use crate::runtime::RuntimeKind;
+use crate::server::lifecycle::ProjectState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The launch would fail or the setting is rejected outright.
+    Error,
+    /// The setting is ignored or skipped at launch.
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Where the problem is: `config.json`, `project`, or a workspace file.
+    pub source: &'static str,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(source: &'static str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            source,
+            message: message.into(),
+        }
+    }
+
+    fn warning(source: &'static str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            source,
+            message: message.into(),
+        }
+    }
+}
+
+/// Read a JSON file into a typed value, reporting parse errors and unknown
+/// keys. `None` when the file is missing or unusable.
+fn load_checked<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    source: &'static str,
+    out: &mut Vec<Diagnostic>,
+) -> Option<T> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    let value: Value = match serde_json::from_str(&raw) {
+        Ok(v) => v,
+        Err(e) => {
+            out.push(Diagnostic::error(
+                source,
+                format!("{} is not valid JSON ({}); it is ignored at launch", path.display(), e),
+            ));
+            return None;
+        }
+    };
+    match crate::config_cli::from_value_strict::<T>(value) {
+        Ok((parsed, unknown)) => {
+            for key in unknown {
+                out.push(Diagnostic::warning(
+                    source,
+                    format!("unknown key '{}' is ignored", key),
+                ));
+            }
+            Some(parsed)
+        }
+        Err(e) => {
+            out.push(Diagnostic::error(
+                source,
+                format!("{:#}; the file is ignored at launch", e),
+            ));
+            None
+        }
+    }
+}
+
+fn check_mounts(
+    mounts: &[MountSpec],
+    source: &'static str,
+    home_dir: &Path,
+    out: &mut Vec<Diagnostic>,
+) -> Vec<String> {
+    let mut targets = Vec::new();
+    for spec in mounts {
+        match crate::mount_cli::validate_spec(spec, home_dir) {
+            Ok(_) => {
+                if let Ok(t) = crate::container::resolve_container_target(spec, home_dir) {
+                    targets.push(t);
+                }
+            }
+            Err(e) => out.push(Diagnostic::error(
+                source,
+                format!("mount '{}': {:#}", spec.host, e),
+            )),
+        }
+    }
+    targets
+}
+
+fn check_global(gc: &GlobalConfig, home_dir: &Path, runtime: RuntimeKind, out: &mut Vec<Diagnostic>) {
+    const SRC: &str = "config.json";
+    for name in &gc.caches {
+        if crate::cache_cli::CacheKind::from_value(name).is_none() {
+            out.push(Diagnostic::warning(
+                SRC,
+                format!("unknown cache '{}' is skipped (expected npm, cargo or pip)", name),
+            ));
+        }
+    }
+    if let Some(keys) = &gc.detach_keys
+        && let Err(e) = crate::runtime::validate_detach_keys(keys)
+    {
+        out.push(Diagnostic::error(SRC, format!("detach_keys: {:#}", e)));
+    }
+    if let Some(profile) = &gc.security.seccomp_profile {
+        let path = crate::mount_cli::normalize_host(profile, home_dir);
+        if !Path::new(&path).is_file() {
+            out.push(Diagnostic::warning(
+                SRC,
+                format!("security.seccomp_profile {} does not exist and is skipped", path),
+            ));
+        }
+    }
+    if let Some(key) = &gc.verify.key {
+        let path = crate::mount_cli::normalize_host(key, home_dir);
+        if !Path::new(&path).is_file() {
+            out.push(Diagnostic::error(
+                SRC,
+                format!("verify.key {} does not exist; builds will fail", path),
+            ));
+        }
+    }
+    if gc.verify.identity.is_some() != gc.verify.issuer.is_some() {
+        out.push(Diagnostic::error(
+            SRC,
+            "verify.identity and verify.issuer must be set together for keyless verification",
+        ));
+    }
+    if let Some(rate) = &gc.network.bandwidth
+        && let Err(e) = crate::server::egress::parse_rate(rate)
+    {
+        out.push(Diagnostic::error(SRC, format!("network.bandwidth: {}", e)));
+    }
+    if gc.network.max_connections == Some(0) {
+        out.push(Diagnostic::error(SRC, "network.max_connections must be at least 1"));
+    }
+    if let Some(problem) = gc.user.problem() {
+        out.push(Diagnostic::error(SRC, problem));
+    }
+    if let Some(file) = crate::claude_md::team_file(&gc.claude_md, home_dir)
+        && !file.is_file()
+    {
+        out.push(Diagnostic::warning(
+            SRC,
+            format!("claude_md.team_file {} does not exist and is skipped", file.display()),
+        ));
+    }
+    for layer in gc.claude_md.disable.iter().filter(|l| !crate::claude_md::LAYERS.contains(&l.as_str())) {
+        out.push(Diagnostic::warning(
+            SRC,
+            format!("unknown claude_md.disable layer '{}' is ignored", layer),
+        ));
+    }
+    for entry in &gc.network.hosts {
+        if let Err(e) = crate::container::parse_host_entry(entry) {
+            out.push(Diagnostic::warning(SRC, format!("network.hosts: {} (skipped)", e)));
+        }
+    }
+    for server in gc.network.dns.iter().filter(|s| s.parse::<std::net::IpAddr>().is_err()) {
+        out.push(Diagnostic::warning(
+            SRC,
+            format!("network.dns entry '{}' is not an IP address and is skipped", server),
+        ));
+    }
+    if runtime == RuntimeKind::Docker {
+        if gc.userns == UsernsMode::KeepId {
+            out.push(Diagnostic::warning(
+                SRC,
+                "userns \"keep-id\" only applies to Podman and is ignored on Docker",
+            ));
+        }
+        if gc.detach_keys.as_deref() == Some("") {
+            out.push(Diagnostic::warning(
+                SRC,
+                "detach_keys \"\" (disable) is not supported by Docker; its default is used",
+            ));
+        }
+    }
+    for cmd in gc.hooks.pre_launch.iter().chain(&gc.hooks.post_exit) {
+        if cmd.trim().is_empty() {
+            out.push(Diagnostic::warning(SRC, "hooks contain an empty command"));
+        }
+    }
+    if let Some(d) = &gc.dotfiles
+        && d.repository.trim().is_empty()
+    {
+        out.push(Diagnostic::error(SRC, "dotfiles.repository is empty"));
+    }
+    if gc.notify.command.as_ref().is_some_and(|c| c.is_empty()) {
+        out.push(Diagnostic::warning(
+            SRC,
+            "notify.command is empty; desktop notifications are used",
+        ));
+    }
+    if let Some(Err(e)) = gc.notify.quiet_hours.as_deref().map(crate::server::notify::parse_quiet_hours) {
+        out.push(Diagnostic::error(SRC, format!("{}; quiet hours are ignored", e)));
+    }
+    for kind in &gc.notify.events {
+        if !crate::server::notify::EVENT_KINDS.contains(&kind.as_str()) {
+            out.push(Diagnostic::error(
+                SRC,
+                format!("notify.events: unknown kind '{}' (expected stop, permission, message or error)", kind),
+            ));
+        }
+    }
+    for e in crate::server::notify::template_errors(&gc.notify) {
+        out.push(Diagnostic::error(SRC, format!("{}; the built-in text is used", e)));
+    }
+}
+
+/// Run every check. `workspace` adds the project-level checks; `runtime`
+/// enables the runtime-specific ones.
+pub fn diagnose(
+    config: &AppConfig,
+    workspace: Option<&Path>,
+    runtime: RuntimeKind,
+) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let home_dir = &config.home_dir;
+
+    let global: GlobalConfig =
+        load_checked(&GlobalConfig::path(config), "config.json", &mut out).unwrap_or_default();
+    check_global(&global, home_dir, runtime, &mut out);
+    let mut targets = check_mounts(&global.mounts, "config.json", home_dir, &mut out);
+
+    if let Some(ws) = workspace {
+        let state_path = config.project_state_file(&crate::workspace::workspace_hash(ws));
+        if let Some(state) = load_checked::<ProjectState>(&state_path, "project", &mut out) {
+            targets.extend(check_mounts(&state.mounts, "project", home_dir, &mut out));
+            for p in &state.excluded_paths {
+                if let Err(e) = crate::workspace::normalize_exclude_path(p) {
+                    out.push(Diagnostic::warning("project", format!("excluded path {}", e)));
+                }
+            }
+        }
+        if let Err(e) = crate::post_create::load(ws) {
+            out.push(Diagnostic::warning(
+                "devcontainer.json",
+                format!("{}; post-create setup is skipped", e),
+            ));
+        }
+        if !ws.join(crate::image::DOCKERFILE_NAME).exists() {
+            out.push(Diagnostic::error(
+                "workspace",
+                format!(
+                    "no {} in {}; run `ai-pod init`",
+                    crate::image::DOCKERFILE_NAME,
+                    ws.display()
+                ),
+            ));
+        }
+    }
+
+    // Two mounts on one container path: the later one silently wins.
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for t in &targets {
+        *seen.entry(t.as_str()).or_default() += 1;
+    }
+    let mut dupes: Vec<&str> = seen.into_iter().filter(|(_, n)| *n > 1).map(|(t, _)| t).collect();
+    dupes.sort();
+    for t in dupes {
+        out.push(Diagnostic::error(
+            "mounts",
+            format!("several mounts target {}", t),
+        ));
+    }
+    out
+}
+
+/// Print diagnostics to stderr. Returns true if any is an error.
+pub fn print_diagnostics(diags: &[Diagnostic]) -> bool {
+    for d in diags {
+        let label = match d.severity {
+            Severity::Error => "error:".red().bold(),
+            Severity::Warning => "warning:".yellow().bold(),
+        };
+        eprintln!("{} [{}] {}", label, d.source, d.message);
+    }
+    diags.iter().any(|d| d.severity == Severity::Error)
+}
+
+pub fn run_check(config: &AppConfig, workspace: &Path, runtime: RuntimeKind) -> anyhow::Result<()> {
+    let diags = diagnose(config, Some(workspace), runtime);
+    if diags.is_empty() {
+        println!("{}", "Configuration OK.".green());
+        return Ok(());
+    }
+    if print_diagnostics(&diags) {
+        anyhow::bail!("Configuration has errors");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use tempfile::TempDir;
+
+    fn messages(diags: &[Diagnostic]) -> Vec<String> {
+        diags.iter().map(|d| d.message.clone()).collect()
+    }
+
+    #[test]
+    fn clean_setup_has_no_diagnostics() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let ws = dir.path().join("ws");
+        std::fs::create_dir_all(&ws).unwrap();
+        std::fs::write(ws.join(crate::image::DOCKERFILE_NAME), "FROM x\n").unwrap();
+        assert!(diagnose(&config, Some(&ws), RuntimeKind::Podman).is_empty());
+    }
+
+    #[test]
+    fn reports_unknown_keys_bad_values_and_missing_files() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        std::fs::write(
+            GlobalConfig::path(&config),
+            r#"{
+                "caches": ["gradle"],
+                "detach_keys": "ctrl-xy",
+                "userns": "keep-id",
+                "security": {"seccomp_profile": "~/missing.json", "typo": true}
+            }"#,
+        )
+        .unwrap();
+        let diags = diagnose(&config, None, RuntimeKind::Docker);
+        let msgs = messages(&diags).join("\n");
+        assert!(msgs.contains("unknown key 'security.typo'"), "{msgs}");
+        assert!(msgs.contains("unknown cache 'gradle'"), "{msgs}");
+        assert!(msgs.contains("detach_keys"), "{msgs}");
+        assert!(msgs.contains("missing.json does not exist"), "{msgs}");
+        assert!(msgs.contains("ignored on Docker"), "{msgs}");
+        assert!(diags.iter().any(|d| d.severity == Severity::Error));
+    }
+
+    #[test]
+    fn reports_malformed_config_and_duplicate_targets() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        std::fs::write(GlobalConfig::path(&config), "{ nope").unwrap();
+        let diags = diagnose(&config, None, RuntimeKind::Podman);
+        assert!(messages(&diags)[0].contains("is not valid JSON"));
+
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::create_dir_all(&a).unwrap();
+        std::fs::create_dir_all(&b).unwrap();
+        let gc = GlobalConfig {
+            mounts: vec![
+                crate::mount_cli::parse_spec(&format!("{}:/data", a.display()), false, dir.path())
+                    .unwrap(),
+                crate::mount_cli::parse_spec(&format!("{}:/data", b.display()), false, dir.path())
+                    .unwrap(),
+            ],
+            ..Default::default()
+        };
+        gc.save(&config).unwrap();
+        let diags = diagnose(&config, None, RuntimeKind::Podman);
+        assert_eq!(messages(&diags), vec!["several mounts target /data"]);
+    }
+}
@@ -0,0 +1,531 @@
+//! Host-side `ai-pod config` subcommand: list, get, set, unset and edit the
+//! global config (`~/.ai-pod/config.json`) or a workspace's project settings,
+//! validating every change before it is written.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::config::{AppConfig, GlobalConfig};
+use crate::server::lifecycle::ProjectState;
+
+/// Project-state keys users may touch. The rest (`workspace`, `api_key`,
+/// `post_create_done`) are ai-pod bookkeeping; `api_key` is also a secret.
+pub(crate) const PROJECT_KEYS: &[&str] = &[
+    "allowed_commands",
+    "allowed_services",
+    "excluded_paths",
+    "ignored_credential_files",
+    "launch",
+    "masked_directories",
+    "mounts",
+    "relax_security",
+    "sync",
+    "user",
+];
+
+/// Which file a `config` command operates on.
+pub enum ConfigScope<'a> {
+    Global,
+    Project(&'a Path),
+}
+
+/// Parse a value given on the command line: valid JSON is taken as-is
+/// (`true`, `3`, `["a"]`, `{"k": 1}`), anything else as a plain string.
+pub(crate) fn parse_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Split a dotted key into segments, rejecting empty ones (`a..b`, `.a`).
+fn key_path(key: &str) -> Result<Vec<&str>> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        anyhow::bail!("Invalid config key '{}'", key);
+    }
+    Ok(parts)
+}
+
+fn get_path<'v>(value: &'v Value, path: &[&str]) -> Option<&'v Value> {
+    path.iter().try_fold(value, |v, seg| match v {
+        Value::Object(map) => map.get(*seg),
+        Value::Array(items) => seg.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+/// Set `path` to `new`, creating intermediate objects as needed.
+fn set_path(value: &mut Value, path: &[&str], new: Value) -> Result<()> {
+    let (last, parents) = path.split_last().expect("key_path never returns empty");
+    let mut cur = value;
+    for seg in parents {
+        cur = match cur {
+            Value::Object(map) => map
+                .entry(seg.to_string())
+                .or_insert_with(|| Value::Object(Map::new())),
+            Value::Array(items) => {
+                let i: usize = seg
+                    .parse()
+                    .with_context(|| format!("'{}' is a list; use a numeric index", seg))?;
+                items
+                    .get_mut(i)
+                    .with_context(|| format!("Index {} is out of range", i))?
+            }
+            _ => anyhow::bail!("'{}' is not an object", seg),
+        };
+    }
+    match cur {
+        Value::Object(map) => {
+            map.insert(last.to_string(), new);
+        }
+        Value::Array(items) => {
+            let i: usize = last
+                .parse()
+                .with_context(|| format!("'{}' is a list; use a numeric index", last))?;
+            *items
+                .get_mut(i)
+                .with_context(|| format!("Index {} is out of range", i))? = new;
+        }
+        _ => anyhow::bail!("Cannot set '{}' inside a non-object value", last),
+    }
+    Ok(())
+}
+
+/// Remove `path`. Returns false if it wasn't set.
+fn remove_path(value: &mut Value, path: &[&str]) -> bool {
+    let (last, parents) = path.split_last().expect("key_path never returns empty");
+    let mut cur = value;
+    for seg in parents {
+        cur = match cur {
+            Value::Object(map) => match map.get_mut(*seg) {
+                Some(v) => v,
+                None => return false,
+            },
+            _ => return false,
+        };
+    }
+    match cur {
+        Value::Object(map) => map.remove(*last).is_some(),
+        _ => false,
+    }
+}
+
+/// Deserialize `value`, collecting the dotted paths of keys the target type
+/// doesn't know — serde would otherwise ignore them silently, so a typo like
+/// `secutiry` looks saved but never takes effect.
+pub(crate) fn from_value_strict<T: serde::de::DeserializeOwned>(
+    value: Value,
+) -> Result<(T, Vec<String>)> {
+    let mut unknown = Vec::new();
+    let parsed = serde_ignored::deserialize(value, |path| unknown.push(path.to_string()))
+        .context("Invalid configuration value")?;
+    Ok((parsed, unknown))
+}
+
+/// Checks beyond what serde enforces, for values that would otherwise only
+/// fail (or be silently skipped) at launch.
+pub(crate) fn validate_global(gc: &GlobalConfig, home_dir: &Path) -> Result<()> {
+    for name in &gc.caches {
+        if crate::cache_cli::CacheKind::from_value(name).is_none() {
+            anyhow::bail!("Unknown cache '{}' (expected npm, cargo or pip)", name);
+        }
+    }
+    if let Some(keys) = &gc.detach_keys {
+        crate::runtime::validate_detach_keys(keys)?;
+    }
+    if gc.notify.command.as_ref().is_some_and(|c| c.is_empty()) {
+        anyhow::bail!("notify.command must name a program");
+    }
+    if let Some(range) = &gc.notify.quiet_hours {
+        crate::server::notify::parse_quiet_hours(range).map_err(anyhow::Error::msg)?;
+    }
+    if let Some(kind) = gc
+        .notify
+        .events
+        .iter()
+        .find(|e| !crate::server::notify::EVENT_KINDS.contains(&e.as_str()))
+    {
+        anyhow::bail!("Unknown notify.events entry '{}' (expected stop, permission, message or error)", kind);
+    }
+    if let Some(e) = crate::server::notify::template_errors(&gc.notify).into_iter().next() {
+        anyhow::bail!(e);
+    }
+    if gc.verify.key.is_some() && gc.verify.identity.is_some() {
+        anyhow::bail!("Set either verify.key or verify.identity, not both");
+    }
+    if gc.verify.identity.is_some() != gc.verify.issuer.is_some() {
+        anyhow::bail!("verify.identity and verify.issuer must be set together");
+    }
+    if let Some(rate) = &gc.network.bandwidth {
+        crate::server::egress::parse_rate(rate)
+            .map_err(anyhow::Error::msg)
+            .context("Invalid network.bandwidth")?;
+    }
+    if gc.network.max_connections == Some(0) {
+        anyhow::bail!("network.max_connections must be at least 1");
+    }
+    if let Some(layer) = gc
+        .claude_md
+        .disable
+        .iter()
+        .find(|l| !crate::claude_md::LAYERS.contains(&l.as_str()))
+    {
+        anyhow::bail!(
+            "Unknown claude_md.disable layer '{}' (expected preamble, global, team or project)",
+            layer
+        );
+    }
+    if let Some(path) = gc.claude_md.todo_files.iter().find(|p| !crate::claude_md::valid_todo_path(p)) {
+        anyhow::bail!("claude_md.todo_files entry '{}' must be a path inside the workspace", path);
+    }
+    for entry in &gc.network.hosts {
+        crate::container::parse_host_entry(entry).map_err(anyhow::Error::msg)?;
+    }
+    if let Some(server) = gc.network.dns.iter().find(|s| s.parse::<std::net::IpAddr>().is_err()) {
+        anyhow::bail!("network.dns entry '{}' is not an IP address", server);
+    }
+    for spec in &gc.mounts {
+        crate::mount_cli::validate_spec(spec, home_dir)
+            .with_context(|| format!("Invalid mount '{}'", spec.host))?;
+    }
+    if let Some(problem) = gc.user.problem() {
+        anyhow::bail!(problem);
+    }
+    Ok(())
+}
+
+fn validate_project(state: &ProjectState, home_dir: &Path) -> Result<()> {
+    for spec in &state.mounts {
+        crate::mount_cli::validate_spec(spec, home_dir)
+            .with_context(|| format!("Invalid mount '{}'", spec.host))?;
+    }
+    for p in &state.excluded_paths {
+        crate::workspace::normalize_exclude_path(p).map_err(anyhow::Error::msg)?;
+    }
+    if let Some(problem) = state.user.problem() {
+        anyhow::bail!(problem);
+    }
+    Ok(())
+}
+
+/// Read the raw global config. Unlike [`GlobalConfig::load`], a malformed
+/// file is an error: editing on top of a silently-defaulted config would
+/// throw the user's settings away.
+pub(crate) fn read_global(config: &AppConfig) -> Result<Value> {
+    let path = GlobalConfig::path(config);
+    match std::fs::read_to_string(&path) {
+        Ok(raw) => serde_json::from_str(&raw).with_context(|| {
+            format!(
+                "{} is not valid JSON; fix it with `ai-pod config edit`",
+                path.display()
+            )
+        }),
+        Err(_) => Ok(Value::Object(Map::new())),
+    }
+}
+
+/// Validate a complete global config value and turn it into the typed form.
+pub(crate) fn check_global(value: Value, home_dir: &Path) -> Result<GlobalConfig> {
+    let (gc, unknown): (GlobalConfig, _) = from_value_strict(value)?;
+    if !unknown.is_empty() {
+        anyhow::bail!("Unknown config key(s): {}", unknown.join(", "));
+    }
+    validate_global(&gc, home_dir)?;
+    Ok(gc)
+}
+
+pub(crate) fn project_view(state: &ProjectState) -> Result<Value> {
+    let mut value = serde_json::to_value(state)?;
+    if let Value::Object(map) = &mut value {
+        map.retain(|k, _| PROJECT_KEYS.contains(&k.as_str()));
+    }
+    Ok(value)
+}
+
+fn check_project_key(path: &[&str]) -> Result<()> {
+    if !PROJECT_KEYS.contains(&path[0]) {
+        anyhow::bail!(
+            "Unknown project key '{}' (expected one of: {})",
+            path[0],
+            PROJECT_KEYS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Apply `edit` to the scope's settings, validate the result and save it.
+fn modify(
+    config: &AppConfig,
+    scope: &ConfigScope,
+    edit: impl FnOnce(&mut Value) -> Result<()>,
+) -> Result<()> {
+    match scope {
+        ConfigScope::Global => {
+            let mut value = read_global(config)?;
+            edit(&mut value)?;
+            check_global(value, &config.home_dir)?.save(config)
+        }
+        ConfigScope::Project(ws) => {
+            let state = crate::server::lifecycle::get_or_create_project_state(config, ws)?;
+            let mut value = project_view(&state)?;
+            edit(&mut value)?;
+            // Re-attach the bookkeeping fields before parsing the full state.
+            let mut full = serde_json::to_value(&state)?;
+            if let (Value::Object(f), Value::Object(v)) = (&mut full, value) {
+                f.extend(v);
+            }
+            let (updated, unknown): (ProjectState, _) = from_value_strict(full)?;
+            if !unknown.is_empty() {
+                anyhow::bail!("Unknown config key(s): {}", unknown.join(", "));
+            }
+            validate_project(&updated, &config.home_dir)?;
+            updated.save(&config.project_state_file(&crate::workspace::workspace_hash(ws)))
+        }
+    }
+}
+
+fn scope_value(config: &AppConfig, scope: &ConfigScope) -> Result<Value> {
+    match scope {
+        ConfigScope::Global => {
+            // Show effective values, defaults included.
+            let gc: GlobalConfig = serde_json::from_value(read_global(config)?)
+                .context("Invalid configuration; fix it with `ai-pod config edit`")?;
+            Ok(serde_json::to_value(gc)?)
+        }
+        ConfigScope::Project(ws) => {
+            let path = config.project_state_file(&crate::workspace::workspace_hash(ws));
+            project_view(&ProjectState::load(&path))
+        }
+    }
+}
+
+/// Flatten nested objects into `a.b = <json>` lines; lists stay inline.
+fn flatten(value: &Value, prefix: &str, out: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() || prefix.is_empty() => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten(v, &key, out);
+            }
+        }
+        other => out.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+pub fn run_list(config: &AppConfig, scope: ConfigScope) -> Result<()> {
+    let mut lines = Vec::new();
+    flatten(&scope_value(config, &scope)?, "", &mut lines);
+    for (k, v) in lines {
+        println!("{} = {}", k.bold(), v);
+    }
+    Ok(())
+}
+
+pub fn run_get(config: &AppConfig, scope: ConfigScope, key: &str) -> Result<()> {
+    let path = key_path(key)?;
+    if let ConfigScope::Project(_) = scope {
+        check_project_key(&path)?;
+    }
+    let value = scope_value(config, &scope)?;
+    let found = get_path(&value, &path).with_context(|| format!("'{}' is not set", key))?;
+    match found {
+        Value::String(s) => println!("{}", s),
+        other => println!("{}", serde_json::to_string_pretty(other)?),
+    }
+    Ok(())
+}
+
+pub fn run_set(config: &AppConfig, scope: ConfigScope, key: &str, raw: &str) -> Result<()> {
+    let path = key_path(key)?;
+    if let ConfigScope::Project(_) = scope {
+        check_project_key(&path)?;
+    }
+    let value = parse_value(raw);
+    let shown = value.to_string();
+    modify(config, &scope, |v| set_path(v, &path, value))
+        .with_context(|| format!("Could not set '{}'", key))?;
+    println!("{} {} = {}", "Set:".green().bold(), key, shown);
+    Ok(())
+}
+
+pub fn run_unset(config: &AppConfig, scope: ConfigScope, key: &str) -> Result<()> {
+    let path = key_path(key)?;
+    if let ConfigScope::Project(_) = scope {
+        check_project_key(&path)?;
+    }
+    let mut removed = false;
+    modify(config, &scope, |v| {
+        removed = remove_path(v, &path);
+        Ok(())
+    })?;
+    if removed {
+        println!("{} {} (back to default)", "Unset:".green().bold(), key);
+    } else {
+        println!("Not set: {}", key);
+    }
+    Ok(())
+}
+
+/// Open the global config in `$VISUAL`/`$EDITOR` (default `vi`) on a scratch
+/// copy, and only replace the real file once the result validates.
+pub fn run_edit(config: &AppConfig) -> Result<()> {
+    let path = GlobalConfig::path(config);
+    let scratch = path.with_extension("edit.json");
+    let initial = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(_) => serde_json::to_string_pretty(&GlobalConfig::default())?,
+    };
+    std::fs::write(&scratch, initial).context("Failed to create scratch config")?;
+    std::fs::set_permissions(
+        &scratch,
+        std::os::unix::fs::PermissionsExt::from_mode(0o600),
+    )?;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let result = loop {
+        // Through `sh` so EDITOR values with arguments ("code --wait") work.
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(&scratch)
+            .status()
+            .with_context(|| format!("Failed to start editor `{}`", editor))?;
+        if !status.success() {
+            break Err(anyhow::anyhow!("Editor exited with non-zero status; config unchanged"));
+        }
+        let checked = std::fs::read_to_string(&scratch)
+            .context("Failed to read edited config")
+            .and_then(|raw| serde_json::from_str(&raw).context("Not valid JSON"))
+            .and_then(|v| check_global(v, &config.home_dir));
+        match checked {
+            Ok(gc) => break gc.save(config),
+            Err(e) => {
+                eprintln!("{} {:#}", "error:".red().bold(), e);
+                let again = crate::is_stdin_tty()
+                    && dialoguer::Confirm::new()
+                        .with_prompt("Re-open the editor?")
+                        .default(true)
+                        .interact()
+                        .unwrap_or(false);
+                if !again {
+                    break Err(anyhow::anyhow!("Config unchanged"));
+                }
+            }
+        }
+    };
+    let _ = std::fs::remove_file(&scratch);
+    result?;
+    println!("{} {}", "Saved:".green().bold(), path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parse_value_falls_back_to_string() {
+        assert_eq!(parse_value("true"), Value::Bool(true));
+        assert_eq!(parse_value("[\"npm\"]"), serde_json::json!(["npm"]));
+        assert_eq!(parse_value("keep-id"), Value::String("keep-id".into()));
+        assert_eq!(parse_value(""), Value::String(String::new()));
+    }
+
+    #[test]
+    fn set_get_and_remove_nested_paths() {
+        let mut v = serde_json::json!({});
+        set_path(&mut v, &["security", "read_only"], Value::Bool(true)).unwrap();
+        assert_eq!(get_path(&v, &["security", "read_only"]), Some(&Value::Bool(true)));
+        set_path(&mut v, &["caches"], serde_json::json!(["npm"])).unwrap();
+        assert_eq!(get_path(&v, &["caches", "0"]), Some(&serde_json::json!("npm")));
+        assert!(remove_path(&mut v, &["security", "read_only"]));
+        assert!(!remove_path(&mut v, &["security", "read_only"]));
+        assert!(key_path("a..b").is_err());
+    }
+
+    #[test]
+    fn set_validates_and_rejects_unknown_keys() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+
+        run_set(&config, ConfigScope::Global, "userns", "keep-id").unwrap();
+        run_set(&config, ConfigScope::Global, "security.read_only", "true").unwrap();
+        let gc = GlobalConfig::load(&config);
+        assert_eq!(gc.userns, crate::config::UsernsMode::KeepId);
+        assert!(gc.security.read_only);
+
+        assert!(run_set(&config, ConfigScope::Global, "userns", "bogus").is_err());
+        assert!(run_set(&config, ConfigScope::Global, "secutiry.read_only", "true").is_err());
+        assert!(run_set(&config, ConfigScope::Global, "caches", "[\"gradle\"]").is_err());
+        assert!(run_set(&config, ConfigScope::Global, "detach_keys", "ctrl-xy").is_err());
+        // Rejected writes leave the file alone.
+        assert_eq!(GlobalConfig::load(&config).userns, crate::config::UsernsMode::KeepId);
+
+        run_unset(&config, ConfigScope::Global, "userns").unwrap();
+        assert_eq!(GlobalConfig::load(&config).userns, crate::config::UsernsMode::Default);
+    }
+
+    #[test]
+    fn set_refuses_to_overwrite_malformed_config() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        std::fs::write(GlobalConfig::path(&config), "{ not json").unwrap();
+        assert!(run_set(&config, ConfigScope::Global, "userns", "keep-id").is_err());
+        assert_eq!(
+            std::fs::read_to_string(GlobalConfig::path(&config)).unwrap(),
+            "{ not json"
+        );
+    }
+
+    #[test]
+    fn project_scope_hides_bookkeeping_keys() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let ws = dir.path().join("ws");
+        std::fs::create_dir_all(&ws).unwrap();
+
+        run_set(&config, ConfigScope::Project(&ws), "relax_security", "true").unwrap();
+        let state = ProjectState::load(
+            &config.project_state_file(&crate::workspace::workspace_hash(&ws)),
+        );
+        assert!(state.relax_security);
+        assert!(!state.api_key.is_empty(), "api key must survive config writes");
+
+        assert!(run_set(&config, ConfigScope::Project(&ws), "api_key", "x").is_err());
+        assert!(run_get(&config, ConfigScope::Project(&ws), "api_key").is_err());
+        let view = project_view(&state).unwrap();
+        assert!(view.get("api_key").is_none());
+    }
+
+    #[test]
+    fn from_value_strict_reports_nested_unknown_keys() {
+        let written = serde_json::json!({
+            "security": {"read_only": true, "typo": 1},
+            "mounts": [{"source": "/x", "type": "bind", "bogus": 1}],
+            "hooks": {"pre_launch": []},
+            "x": 2
+        });
+        let (_, mut unknown): (GlobalConfig, _) = from_value_strict(written).unwrap();
+        unknown.sort();
+        assert_eq!(unknown, vec!["mounts.0.bogus", "security.typo", "x"]);
+    }
+
+    #[test]
+    fn flatten_expands_objects_only() {
+        let mut out = Vec::new();
+        flatten(&serde_json::json!({"a": {"b": 1}, "c": [1, 2]}), "", &mut out);
+        assert_eq!(
+            out,
+            vec![("a.b".into(), "1".into()), ("c".into(), "[1,2]".into())]
+        );
+    }
+}
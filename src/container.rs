@@ -1,26 +1,168 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
 use dialoguer;
-use std::path::Path;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
-use crate::config::{AppConfig, GlobalConfig, MountSpec};
+use crate::cache_cli::{CacheKind, enabled_caches};
+use crate::claude_md::TemplateVars;
+use crate::config::{
+    AppConfig, GlobalConfig, LaunchConfig, MountSpec, MountType, SecurityConfig, UserConfig, UsernsMode,
+};
+use crate::dockerfile_watch::{DockerfileWatch, WatchMode};
+use crate::events::{self, Event, EventKind};
+use crate::hooks::{self, HookContext};
+use crate::plugins;
+use crate::podman_api;
 use crate::runtime::ContainerRuntime;
 use crate::server::lifecycle::ProjectState;
+use crate::term_title::TitleGuard;
 use crate::workspace::{
     container_name_for, container_prefix, mask_volume_name, new_session_id,
+    session_id_from_container_name, state_volume_name, sync_volume_name,
     volume_name as gen_volume_name, workspace_hash,
 };
 
 /// Home directory of the `ai-pod` user inside every container image.
 /// The Dockerfile template creates this user with this home path, so the
-/// runtime does not need to probe the image.
-const CONTAINER_HOME: &str = "/home/ai-pod";
+/// runtime does not need to probe the image. A configured `user` may live
+/// elsewhere (see [`UserConfig::home_dir`]).
+pub(crate) const CONTAINER_HOME: &str = "/home/ai-pod";
+
+/// The two volumes behind a container's home. `home` holds what the image
+/// and seeding put into `$HOME` and is regenerated when the image changes;
+/// `state` is mounted over `~/.claude` (sign-in, sessions, settings, shell
+/// history) and survives that.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HomeVolumes {
+    pub home: String,
+    pub state: String,
+    /// Who the containers mounting them run as.
+    pub user: UserConfig,
+}
+
+impl HomeVolumes {
+    pub(crate) fn for_workspace(workspace: &Path) -> Self {
+        HomeVolumes {
+            home: gen_volume_name(workspace),
+            state: state_volume_name(workspace),
+            user: UserConfig::default(),
+        }
+    }
+
+    /// The volumes of an `ai-pod --new` instance, removed with its session.
+    pub(crate) fn for_instance(workspace: &Path, session_id: &str) -> Self {
+        HomeVolumes {
+            home: format!("{}-{}", gen_volume_name(workspace), session_id),
+            state: format!("{}-{}", state_volume_name(workspace), session_id),
+            user: UserConfig::default(),
+        }
+    }
+
+    pub(crate) fn with_user(self, user: UserConfig) -> Self {
+        HomeVolumes { user, ..self }
+    }
+
+    /// Where the home volume is mounted: the user's home.
+    pub(crate) fn dir(&self) -> String {
+        self.user.home_dir()
+    }
+
+    /// Best-effort removal of both volumes.
+    pub(crate) fn remove(&self, rt: &ContainerRuntime) {
+        let _ = remove_volume(rt, &self.home);
+        let _ = remove_volume(rt, &self.state);
+    }
+
+    /// `-v` arguments mounting both volumes.
+    pub(crate) fn mount_args(&self) -> Vec<String> {
+        let dir = self.dir();
+        vec![
+            "-v".to_string(),
+            format!("{}:{}:z", self.home, dir),
+            "-v".to_string(),
+            format!("{}:{}/.claude:z", self.state, dir),
+        ]
+    }
+}
+
+/// Launch-time options that come from CLI flags rather than persisted config.
+/// Grouped so new flags don't keep widening the launch signatures.
+#[derive(Default, Debug, Clone)]
+pub struct LaunchOptions {
+    /// Remove existing containers for the workspace and reseed the home volume.
+    pub rebuild: bool,
+    /// Additional host directories (canonicalized) mounted under
+    /// `/workspaces/<name>` for multi-repo tasks (`--add-workspace`).
+    pub extra_workspaces: Vec<PathBuf>,
+    /// Detach sequence (`--detach-keys` / config); `None` = runtime default.
+    pub detach_keys: Option<String>,
+    /// Watch `ai-pod.Dockerfile` during the session (`--watch-dockerfile`).
+    pub watch_dockerfile: Option<WatchMode>,
+    /// Give the session its own home volume instead of the workspace's
+    /// (`--new`).
+    pub new_instance: Option<NewInstance>,
+    /// Label for the session (see [`crate::session_title`]).
+    pub title: Option<String>,
+    /// Create the session's container as a standby instead of running it
+    /// (`ai-pod warm`, see [`crate::warm`]).
+    pub warm: bool,
+    /// Executable run in place of the image's entrypoint (`--entrypoint`).
+    pub entrypoint: Option<String>,
+    /// Command run in place of the image's default one (after `--`).
+    pub command: Vec<String>,
+}
+
+/// Home volume of an instance started with `--new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum NewInstance {
+    /// A copy of the workspace's home volume (sign-in, history, tools)
+    Clone,
+    /// A newly seeded volume, as on a first launch
+    Fresh,
+}
+
+/// Per-command options of `ai-pod run`, applied to both a one-off container
+/// and an `exec` into the running session.
+#[derive(Default)]
+pub struct RunOptions {
+    /// `KEY=VALUE`, or `KEY` to pass the host's value through (`--env`).
+    pub env: Vec<String>,
+    /// User to run the command as (`--user`).
+    pub user: Option<String>,
+}
+
+impl RunOptions {
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for var in &self.env {
+            args.push("-e".to_string());
+            args.push(var.clone());
+        }
+        if let Some(user) = &self.user {
+            args.push(format!("--user={}", user));
+        }
+        args
+    }
+}
 
 pub fn containers_for_prefix(
     rt: &ContainerRuntime,
     prefix: &str,
     running_only: bool,
+) -> Result<Vec<String>> {
+    if running_only && let Some(names) = crate::prefetch::running_containers(prefix) {
+        return Ok(names);
+    }
+    query_containers(rt, prefix, running_only)
+}
+
+/// [`containers_for_prefix`], always asking the runtime.
+pub(crate) fn query_containers(
+    rt: &ContainerRuntime,
+    prefix: &str,
+    running_only: bool,
 ) -> Result<Vec<String>> {
     let filter = format!("name=^{}-", prefix);
     let mut cmd = rt.command();
@@ -46,6 +188,14 @@ pub fn containers_for_prefix(
 }
 
 pub fn volume_exists(rt: &ContainerRuntime, name: &str) -> Result<bool> {
+    if let Some(exists) = crate::prefetch::volume_exists(name) {
+        return Ok(exists);
+    }
+    if let Some(api) = podman_api::client(rt)
+        && let Ok(exists) = api.volume_exists(name)
+    {
+        return Ok(exists);
+    }
     let status = rt
         .command()
         .args(["volume", "inspect", name])
@@ -56,9 +206,94 @@ pub fn volume_exists(rt: &ContainerRuntime, name: &str) -> Result<bool> {
     Ok(status.success())
 }
 
-/// Create a fresh mask volume and chown its root to the container's `ai-pod` user
+/// Create a named volume, through the Podman API when available. `what`
+/// names the volume's role in the error message; `owner` is the workspace a
+/// per-workspace volume is labelled with.
+pub(crate) fn create_volume(rt: &ContainerRuntime, name: &str, what: &str, owner: Option<&Path>) -> Result<()> {
+    let labels: Vec<(&str, String)> = owner
+        .map(|ws| (crate::collision::LABEL, ws.to_string_lossy().to_string()))
+        .into_iter()
+        .collect();
+    if let Some(api) = podman_api::client(rt) {
+        match api.volume_create(name, &labels) {
+            Ok(()) => return Ok(()),
+            Err(e @ podman_api::ApiError::Status { .. }) => {
+                anyhow::bail!("Failed to create {} {}: {}", what, name, e)
+            }
+            Err(podman_api::ApiError::Transport(_)) => {}
+        }
+    }
+    let status = rt
+        .command()
+        .args(["volume", "create"])
+        .args(labels.iter().flat_map(|(k, v)| ["--label".to_string(), format!("{}={}", k, v)]))
+        .arg(name)
+        .status()
+        .with_context(|| format!("Failed to create {}", what))?;
+    if !status.success() {
+        anyhow::bail!("Failed to create {} {}", what, name);
+    }
+    Ok(())
+}
+
+/// Copy a host file into a (possibly stopped) container, like `podman cp`:
+/// `dest` ending in `/` keeps the file name. Uses the Podman API when
+/// available and falls back to the CLI.
+fn copy_to_container(rt: &ContainerRuntime, src: &Path, container: &str, dest: &str) -> Result<()> {
+    if let Some(api) = podman_api::client(rt) {
+        match api.copy_into(container, src, dest) {
+            Ok(()) => return Ok(()),
+            Err(e @ podman_api::ApiError::Status { .. }) => {
+                anyhow::bail!("Failed to copy {} into {}: {}", src.display(), container, e)
+            }
+            Err(podman_api::ApiError::Transport(_)) => {}
+        }
+    }
+    let status = rt
+        .command()
+        .arg("cp")
+        .arg(src)
+        .arg(format!("{}:{}", container, dest))
+        .status()
+        .context("Failed to copy into container")?;
+    if !status.success() {
+        anyhow::bail!("Failed to copy {} into {}", src.display(), container);
+    }
+    Ok(())
+}
+
+/// Copy a file out of a container to `dest` on the host. Errors when the
+/// file doesn't exist in the container.
+fn copy_from_container(rt: &ContainerRuntime, container: &str, src: &str, dest: &Path) -> Result<()> {
+    if let Some(api) = podman_api::client(rt) {
+        match api.copy_out(container, src) {
+            Ok(Some(contents)) => {
+                return std::fs::write(dest, contents)
+                    .with_context(|| format!("Failed to write {}", dest.display()));
+            }
+            Ok(None) => anyhow::bail!("{} not found in {}", src, container),
+            Err(e @ podman_api::ApiError::Status { .. }) => {
+                anyhow::bail!("Failed to copy {} out of {}: {}", src, container, e)
+            }
+            Err(podman_api::ApiError::Transport(_)) => {}
+        }
+    }
+    let status = rt
+        .command()
+        .arg("cp")
+        .arg(format!("{}:{}", container, src))
+        .arg(dest)
+        .status()
+        .context("Failed to copy out of container")?;
+    if !status.success() {
+        anyhow::bail!("Failed to copy {} out of {}", src, container);
+    }
+    Ok(())
+}
+
+/// Create a fresh mask volume and chown its root to the container's user
 /// so the unprivileged in-container user can write under /app/<dir>.
-fn seed_mask_volume(rt: &ContainerRuntime, image: &str, vol: &str, dir: &str) -> Result<()> {
+fn seed_mask_volume(rt: &ContainerRuntime, image: &str, vol: &str, dir: &str, user: &UserConfig) -> Result<()> {
     let mount_path = format!("/app/{}", dir);
     let status = rt
         .command()
@@ -72,7 +307,7 @@ fn seed_mask_volume(rt: &ContainerRuntime, image: &str, vol: &str, dir: &str) ->
             "--entrypoint",
             "chown",
             image,
-            "ai-pod:ai-pod",
+            &user.owner(),
             &mount_path,
         ])
         .status()
@@ -90,19 +325,14 @@ fn ensure_mask_volume(
     workspace: &Path,
     image: &str,
     dir: &str,
+    user: &UserConfig,
 ) -> Result<String> {
     let vol = mask_volume_name(workspace, dir);
+    crate::collision::check_volume(rt, &vol, workspace)?;
     if !volume_exists(rt, &vol)? {
         eprintln!("{} {}", "Creating mask volume:".blue().bold(), vol);
-        let status = rt
-            .command()
-            .args(["volume", "create", &vol])
-            .status()
-            .context("Failed to create mask volume")?;
-        if !status.success() {
-            anyhow::bail!("Failed to create mask volume {}", vol);
-        }
-        seed_mask_volume(rt, image, &vol, dir)?;
+        create_volume(rt, &vol, "mask volume", Some(workspace))?;
+        seed_mask_volume(rt, image, &vol, dir, user)?;
     }
     Ok(vol)
 }
@@ -116,16 +346,216 @@ fn mask_mount_args(
     workspace: &Path,
     image: &str,
     masks: &[String],
+    user: &UserConfig,
 ) -> Result<Vec<String>> {
     let mut out = Vec::with_capacity(masks.len() * 2);
     for dir in masks {
-        let vol = ensure_mask_volume(rt, workspace, image, dir)?;
+        let vol = ensure_mask_volume(rt, workspace, image, dir, user)?;
         out.push("-v".to_string());
         out.push(format!("{}:/app/{}:Z", vol, dir));
     }
     Ok(out)
 }
 
+/// Ensure a shared package-manager cache volume exists, creating it and
+/// chowning its root to the container's user on first use. Uses the
+/// shared `:z` label since several containers may mount it at once.
+fn ensure_cache_volume(rt: &ContainerRuntime, image: &str, kind: CacheKind, user: &UserConfig) -> Result<String> {
+    let vol = kind.volume_name();
+    if !volume_exists(rt, &vol)? {
+        eprintln!("{} {}", "Creating cache volume:".blue().bold(), vol);
+        create_volume(rt, &vol, "cache volume", None)?;
+        let mount_path = kind.container_path(&user.home_dir());
+        let status = rt
+            .command()
+            .args([
+                "run",
+                "--rm",
+                "--user",
+                "0",
+                "-v",
+                &format!("{}:{}:z", vol, mount_path),
+                "--entrypoint",
+                "chown",
+                image,
+                &user.owner(),
+                &mount_path,
+            ])
+            .status()
+            .context("Failed to seed cache volume")?;
+        if !status.success() {
+            anyhow::bail!("Failed to chown cache volume {}", vol);
+        }
+    }
+    Ok(vol)
+}
+
+/// Build `-v` arg pairs for the package-manager caches enabled in the global
+/// config (see `ai-pod cache`). Spliced in after the home volume so each cache
+/// shadows its sub-path of the per-workspace home.
+fn cache_mount_args(
+    rt: &ContainerRuntime,
+    image: &str,
+    caches: &[CacheKind],
+    user: &UserConfig,
+) -> Result<Vec<String>> {
+    let mut out = Vec::with_capacity(caches.len() * 2);
+    for &kind in caches {
+        let vol = ensure_cache_volume(rt, image, kind, user)?;
+        out.push("-v".to_string());
+        out.push(format!("{}:{}:z", vol, kind.container_path(&user.home_dir())));
+    }
+    Ok(out)
+}
+
+/// Build the args that hide excluded workspace paths inside the container:
+/// an empty tmpfs over directories, `/dev/null` over files. Paths that don't
+/// exist on the host are skipped — there is nothing to hide, and the runtime
+/// would otherwise create them in the host workspace as mountpoints. Must be
+/// spliced in after the workspace bind and masks.
+pub(crate) fn exclude_args(workspace: &Path, excluded: &[String]) -> Vec<String> {
+    let mut out = Vec::with_capacity(excluded.len() * 2);
+    for raw in excluded {
+        let rel = match crate::workspace::normalize_exclude_path(raw) {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("{} {}; skipping", "warning:".yellow().bold(), e);
+                continue;
+            }
+        };
+        let target = format!("/app/{}", rel);
+        match std::fs::symlink_metadata(workspace.join(&rel)) {
+            Ok(meta) if meta.is_dir() => {
+                out.push("--tmpfs".to_string());
+                out.push(format!("{}:rw,nosuid,nodev,mode=1777", target));
+            }
+            Ok(_) => {
+                out.push("-v".to_string());
+                out.push(format!("/dev/null:{}:ro", target));
+            }
+            Err(_) => {}
+        }
+    }
+    out
+}
+
+/// `-v` args for the `--add-workspace` roots. Uses the shared `:z` label
+/// rather than `/app`'s private `:Z`, since an extra root is often another
+/// workspace's primary root with its own session running.
+fn extra_workspace_args(roots: &[PathBuf]) -> Vec<String> {
+    let targets = crate::workspace::extra_workspace_targets(roots);
+    let mut out = Vec::with_capacity(roots.len() * 2);
+    for (root, target) in roots.iter().zip(targets) {
+        out.push("-v".to_string());
+        out.push(format!("{}:{}:z", root.display(), target));
+    }
+    out
+}
+
+/// Host variables forwarded into the container so clocks, encodings and
+/// full-screen TUIs match the host. `TERM`/`COLORTERM` only make sense with
+/// a pseudo-TTY, so they are dropped when `tty` is false.
+const FORWARDED_ENV: [&str; 4] = ["TZ", "LANG", "TERM", "COLORTERM"];
+
+/// `-e` args for [`FORWARDED_ENV`]. When `TZ` is unset, the zone name is
+/// derived from the `/etc/localtime` symlink (e.g. `Europe/Berlin`), since
+/// most hosts configure the timezone that way rather than via `TZ`.
+fn host_env_args(
+    get: impl Fn(&str) -> Option<String>,
+    localtime: Option<PathBuf>,
+    tty: bool,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    for key in FORWARDED_ENV {
+        if !tty && (key == "TERM" || key == "COLORTERM") {
+            continue;
+        }
+        let value = get(key).filter(|v| !v.is_empty()).or_else(|| {
+            if key == "TZ" {
+                localtime.as_deref().and_then(zone_from_localtime)
+            } else {
+                None
+            }
+        });
+        if let Some(v) = value {
+            out.push("-e".to_string());
+            out.push(format!("{}={}", key, v));
+        }
+    }
+    out
+}
+
+/// `/usr/share/zoneinfo/Europe/Berlin` → `Europe/Berlin`. Also covers macOS's
+/// `/var/db/timezone/zoneinfo/...` layout.
+fn zone_from_localtime(target: &Path) -> Option<String> {
+    let s = target.to_str()?;
+    let (_, zone) = s.rsplit_once("zoneinfo/")?;
+    if zone.is_empty() {
+        return None;
+    }
+    Some(zone.to_string())
+}
+
+pub(crate) fn host_env_args_from_env(tty: bool) -> Vec<String> {
+    host_env_args(
+        |k| std::env::var(k).ok(),
+        std::fs::read_link("/etc/localtime").ok(),
+        tty,
+    )
+}
+
+/// Session-specific notes for the agent, followed by the generated
+/// `sections` (see [`crate::claude_md`]), or `None` when there is nothing
+/// beyond the defaults to say.
+fn session_context(roots: &[PathBuf], sections: &[String]) -> Option<String> {
+    if roots.is_empty() && sections.is_empty() {
+        return None;
+    }
+    let mut md = String::from("# ai-pod session\n\n");
+    if !roots.is_empty() {
+        md.push_str(
+            "This session spans several repositories. The primary workspace is \
+             mounted at `/app`; the others are mounted at:\n\n",
+        );
+        for (root, target) in roots
+            .iter()
+            .zip(crate::workspace::extra_workspace_targets(roots))
+        {
+            md.push_str(&format!("- `{}` (host: `{}`)\n", target, root.display()));
+        }
+    }
+    for section in sections {
+        if !md.ends_with("\n\n") {
+            md.push('\n');
+        }
+        md.push_str(section);
+    }
+    Some(md)
+}
+
+/// Write this session's context file and return the `-v` args that mount it
+/// read-only at Claude Code's managed-policy location. That file is loaded in
+/// addition to the user's own `~/.claude/CLAUDE.md`, so nothing in the home
+/// volume is rewritten per launch.
+fn session_context_args(
+    config: &AppConfig,
+    session_id: &str,
+    roots: &[PathBuf],
+    sections: &[String],
+) -> Result<Vec<String>> {
+    let Some(md) = session_context(roots, sections) else {
+        return Ok(Vec::new());
+    };
+    let path = config.session_context_file(session_id);
+    std::fs::create_dir_all(config.sessions_dir())
+        .context("Failed to create ~/.ai-pod/sessions/")?;
+    std::fs::write(&path, md).context("Failed to write session context")?;
+    Ok(vec![
+        "-v".to_string(),
+        format!("{}:/etc/claude-code/CLAUDE.md:ro,z", path.display()),
+    ])
+}
+
 /// Resolve the in-container target path for a user-defined mount.
 ///
 /// - If `spec.container` is set, returns it verbatim (already validated at `mount add`).
@@ -135,6 +565,9 @@ pub(crate) fn resolve_container_target(spec: &MountSpec, home_dir: &Path) -> Res
     if let Some(c) = &spec.container {
         return Ok(c.clone());
     }
+    if spec.kind == MountType::Volume {
+        anyhow::bail!("volume mount {} needs an explicit container path", spec.host);
+    }
     let host = Path::new(&spec.host);
     let rel = host.strip_prefix(home_dir).map_err(|_| {
         anyhow::anyhow!(
@@ -162,7 +595,10 @@ pub(crate) fn resolve_container_target(spec: &MountSpec, home_dir: &Path) -> Res
 /// Uses the `:z` SELinux label (shared) to match the home-volume mount, so the
 /// host user retains access to e.g. `~/.claude/skills` after the container
 /// touches it. Read-only mounts get `:z,ro`.
-pub(crate) fn build_mount_args(home_dir: &Path, mounts: &[MountSpec]) -> Result<Vec<String>> {
+///
+/// Targets derived from `$HOME` land in `container_home`, the container
+/// user's home.
+pub(crate) fn build_mount_args(home_dir: &Path, container_home: &str, mounts: &[MountSpec]) -> Result<Vec<String>> {
     let mut out = Vec::with_capacity(mounts.len() * 2);
     for m in mounts {
         // Re-validate against the current $HOME and the current rule set so
@@ -183,8 +619,9 @@ pub(crate) fn build_mount_args(home_dir: &Path, mounts: &[MountSpec]) -> Result<
         };
         // symlink_metadata so a dangling symlink (target temporarily missing)
         // still counts as present — `MountSpec`'s doc comment says symlinks
-        // are intentionally not resolved.
-        if Path::new(&m.host).symlink_metadata().is_err() {
+        // are intentionally not resolved. Named volumes are created by the
+        // runtime on first use, so there is nothing to check.
+        if m.kind == MountType::Bind && Path::new(&m.host).symlink_metadata().is_err() {
             eprintln!(
                 "{} mount source {} does not exist; skipping",
                 "warning:".yellow().bold(),
@@ -194,23 +631,190 @@ pub(crate) fn build_mount_args(home_dir: &Path, mounts: &[MountSpec]) -> Result<
         }
         let opts = if m.writable { "z" } else { "z,ro" };
         out.push("-v".to_string());
+        let target = match (&m.container, target.strip_prefix(CONTAINER_HOME)) {
+            (None, Some(rest)) => format!("{}{}", container_home, rest),
+            _ => target,
+        };
         out.push(format!("{}:{}:{}", m.host, target, opts));
     }
     Ok(out)
 }
 
+/// Hardening flags derived from the global `security` config. The tmpfs is
+/// mounted `exec` because build tools routinely run scripts out of `/tmp`.
+/// `relaxed` is the per-project escape hatch (`ai-pod security relax`): it
+/// drops the capability and security-opt flags but keeps the filesystem ones,
+/// which the user opted into explicitly.
+pub fn security_args(security: &SecurityConfig, home_dir: &Path, relaxed: bool) -> Vec<String> {
+    let mut out = Vec::new();
+    if security.read_only {
+        out.push("--read-only".to_string());
+    }
+    if security.read_only || security.tmpfs_tmp {
+        out.push("--tmpfs".to_string());
+        out.push("/tmp:rw,exec,nosuid,nodev,mode=1777".to_string());
+    }
+    if relaxed {
+        return out;
+    }
+    for cap in &security.cap_drop {
+        out.push("--cap-drop".to_string());
+        out.push(cap.clone());
+    }
+    for cap in &security.cap_add {
+        out.push("--cap-add".to_string());
+        out.push(cap.clone());
+    }
+    if security.no_new_privileges {
+        out.push("--security-opt".to_string());
+        out.push("no-new-privileges".to_string());
+    }
+    if let Some(profile) = &security.seccomp_profile {
+        let path = crate::mount_cli::normalize_host(profile, home_dir);
+        if Path::new(&path).is_file() {
+            out.push("--security-opt".to_string());
+            out.push(format!("seccomp={}", path));
+        } else {
+            eprintln!(
+                "{} seccomp profile {} does not exist; skipping",
+                "warning:".yellow().bold(),
+                path
+            );
+        }
+    }
+    if let Some(profile) = &security.apparmor_profile {
+        out.push("--security-opt".to_string());
+        out.push(format!("apparmor={}", profile));
+    }
+    out
+}
+
+/// `--userns` flags for the configured mode, probing the container user's
+/// ids only when keep-id is actually going to be used.
+fn userns_args(rt: &ContainerRuntime, userns: UsernsMode, image: &str, user: &UserConfig) -> Vec<String> {
+    if userns != UsernsMode::KeepId || rt.kind != crate::runtime::RuntimeKind::Podman {
+        return rt.userns_args(userns, None);
+    }
+    rt.userns_args(userns, crate::image::image_user_ids(rt, image, &user.run_args()))
+}
+
+/// Split a `network.hosts` entry, `name=ip` or `name:ip`, into the name and
+/// address. The address may also be `host-gateway`.
+pub(crate) fn parse_host_entry(entry: &str) -> Result<(&str, &str), String> {
+    let (name, addr) = match (entry.find('='), entry.find(':')) {
+        (Some(i), _) => (&entry[..i], &entry[i + 1..]),
+        (None, Some(i)) => (&entry[..i], &entry[i + 1..]),
+        (None, None) => return Err(format!("host entry '{}' must be name=ip", entry)),
+    };
+    let (name, addr) = (name.trim(), addr.trim());
+    let valid_name =
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'));
+    if !valid_name {
+        return Err(format!("'{}' is not a valid host name", name));
+    }
+    if addr != "host-gateway" && addr.parse::<std::net::IpAddr>().is_err() {
+        return Err(format!("'{}' is not an IP address", addr));
+    }
+    Ok((name, addr))
+}
+
+/// `--add-host`, `--dns` and `--dns-search` flags for the `network` config.
+/// Invalid entries are skipped; `ai-pod config check` reports them.
+pub(crate) fn resolver_args(network: &crate::config::NetworkConfig) -> Vec<String> {
+    let mut out = Vec::new();
+    for entry in &network.hosts {
+        if let Ok((name, addr)) = parse_host_entry(entry) {
+            out.push(format!("--add-host={}:{}", name, addr));
+        }
+    }
+    for server in &network.dns {
+        if server.parse::<std::net::IpAddr>().is_ok() {
+            out.push(format!("--dns={}", server));
+        }
+    }
+    for domain in &network.dns_search {
+        out.push(format!("--dns-search={}", domain));
+    }
+    out
+}
+
+/// `-e` flags sending the container's HTTP(S) traffic through the shared
+/// server's egress proxy when auditing or a limit is configured. The proxy
+/// credentials name the session so traffic is recorded against it; the host
+/// gateway (the shared server itself) and loopback stay direct.
+fn egress_proxy_args(
+    rt: &ContainerRuntime,
+    network: &crate::config::NetworkConfig,
+    project_id: &str,
+    session_id: &str,
+    api_key: &str,
+) -> Vec<String> {
+    if !network.proxied() {
+        return Vec::new();
+    }
+    let proxy = format!(
+        "http://{}.{}:{}@{}:{}",
+        project_id,
+        session_id,
+        api_key,
+        rt.host_gateway(),
+        crate::server::egress::EGRESS_PORT
+    );
+    let no_proxy = format!("localhost,127.0.0.1,::1,{}", rt.host_gateway());
+    let mut out = Vec::new();
+    for (name, value) in [
+        ("HTTP_PROXY", &proxy),
+        ("HTTPS_PROXY", &proxy),
+        ("http_proxy", &proxy),
+        ("https_proxy", &proxy),
+        ("NO_PROXY", &no_proxy),
+        ("no_proxy", &no_proxy),
+    ] {
+        out.push("-e".to_string());
+        out.push(format!("{}={}", name, value));
+    }
+    // Node's built-in fetch only honours the variables when asked to.
+    out.push("-e".to_string());
+    out.push("NODE_USE_ENV_PROXY=1".to_string());
+    out
+}
+
 /// Best-effort removal of a single mask volume. Prints a message on success and
 /// a warning if the volume is in use (e.g. another container still mounts it).
 pub fn remove_mask_volume(rt: &ContainerRuntime, workspace: &Path, dir: &str) -> Result<()> {
-    let vol = mask_volume_name(workspace, dir);
-    if !volume_exists(rt, &vol)? {
+    remove_volume(rt, &mask_volume_name(workspace, dir))
+}
+
+/// Best-effort removal of a named volume. No-op when it doesn't exist; warns
+/// instead of failing when the runtime refuses (typically because a running
+/// container still mounts it).
+pub fn remove_volume(rt: &ContainerRuntime, vol: &str) -> Result<()> {
+    if !volume_exists(rt, vol)? {
         return Ok(());
     }
+    if let Some(api) = podman_api::client(rt) {
+        match api.volume_remove(vol) {
+            Ok(()) => {
+                eprintln!("{} {}", "Removed volume:".red().bold(), vol);
+                return Ok(());
+            }
+            Err(e @ podman_api::ApiError::Status { .. }) => {
+                eprintln!(
+                    "{} could not remove {} ({})",
+                    "Warning:".yellow().bold(),
+                    vol,
+                    e
+                );
+                return Ok(());
+            }
+            Err(podman_api::ApiError::Transport(_)) => {}
+        }
+    }
     let output = rt
         .command()
-        .args(["volume", "rm", &vol])
+        .args(["volume", "rm", vol])
         .output()
-        .context("Failed to remove mask volume")?;
+        .context("Failed to remove volume")?;
     if output.status.success() {
         eprintln!("{} {}", "Removed volume:".red().bold(), vol);
     } else {
@@ -225,12 +829,142 @@ pub fn remove_mask_volume(rt: &ContainerRuntime, workspace: &Path, dir: &str) ->
     Ok(())
 }
 
+/// Forget that the post-create setup ran, so it runs again against a fresh
+/// or reseeded home volume.
+fn reset_post_create(config: &AppConfig, workspace: &Path) {
+    let path = config.project_state_file(&workspace_hash(workspace));
+    let mut state = ProjectState::load(&path);
+    if state.post_create_done.take().is_some() {
+        let _ = state.save(&path);
+    }
+}
+
+/// Run the workspace's devcontainer `postCreateCommand` in a throwaway
+/// container with the session's mounts, unless the same commands already
+/// succeeded against this home volume. A failure only warns: the launch goes
+/// ahead and the setup is retried next time.
+fn run_post_create(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    image: &str,
+    mount_args: &[String],
+) -> Result<()> {
+    let post_create = match crate::post_create::load(workspace) {
+        Ok(Some(pc)) => pc,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            eprintln!("{} {}; skipping post-create setup", "warning:".yellow().bold(), e);
+            return Ok(());
+        }
+    };
+    let fingerprint = post_create.fingerprint();
+    let state_path = config.project_state_file(&workspace_hash(workspace));
+    if ProjectState::load(&state_path).post_create_done.as_deref() == Some(fingerprint.as_str()) {
+        return Ok(());
+    }
+
+    let stdio_flag = if crate::is_stdin_tty() { "-it" } else { "-i" };
+    let resolver = resolver_args(&GlobalConfig::load(config).network);
+    for argv in &post_create.commands {
+        eprintln!("{} {}", "Post-create:".blue().bold(), argv.join(" "));
+        let status = rt
+            .command()
+            .args(["run", "--rm", stdio_flag, "-w", "/app"])
+            .args(mount_args)
+            .args(&resolver)
+            .args(["--entrypoint", &argv[0], image])
+            .args(&argv[1..])
+            .stdin(Stdio::inherit())
+            // Setup output isn't the session's: keep it out of a piped
+            // `ai-pod run ... | tee log`.
+            .stdout(std::io::stderr())
+            .stderr(Stdio::inherit())
+            .status()
+            .context("Failed to run post-create command")?;
+        if !status.success() {
+            eprintln!(
+                "{} post-create command failed; it will run again on the next launch",
+                "warning:".yellow().bold()
+            );
+            return Ok(());
+        }
+    }
+
+    if !rt.dry_run {
+        let mut state = ProjectState::load(&state_path);
+        state.post_create_done = Some(fingerprint);
+        state.save(&state_path)?;
+    }
+    Ok(())
+}
+
 fn load_project_state(config: &AppConfig, workspace: &Path) -> ProjectState {
     let hash = workspace_hash(workspace);
     ProjectState::load(&config.project_state_file(&hash))
 }
 
-fn generate_runtime_settings(config: &AppConfig) -> Result<()> {
+/// The user `workspace`'s containers run as: its own `user` setting, else
+/// the global one.
+pub(crate) fn container_user(config: &AppConfig, workspace: &Path) -> Result<UserConfig> {
+    let project = load_project_state(config, workspace).user;
+    let user = if project.is_empty() { GlobalConfig::load(config).user } else { project };
+    if let Some(problem) = user.problem() {
+        anyhow::bail!("{}; fix it with `ai-pod config`", problem);
+    }
+    Ok(user)
+}
+
+/// The entrypoint and command a session runs, each with where it's set;
+/// `None` keeps the image's.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct SessionCommand {
+    entrypoint: Option<(String, &'static str)>,
+    command: Option<(Vec<String>, &'static str)>,
+}
+
+impl SessionCommand {
+    /// The flags in `opts`, else the workspace's `launch` setting, else the
+    /// global one, separately for the entrypoint and the command.
+    fn resolve(opts: &LaunchOptions, project: &LaunchConfig, global: &LaunchConfig) -> Self {
+        let flags = LaunchConfig {
+            entrypoint: opts.entrypoint.clone(),
+            command: opts.command.clone(),
+        };
+        let layers = [(&flags, "flag"), (project, "project"), (global, "global")];
+        SessionCommand {
+            entrypoint: layers
+                .iter()
+                .find_map(|(l, source)| l.entrypoint.clone().map(|e| (e, *source))),
+            command: layers
+                .iter()
+                .find(|(l, _)| !l.command.is_empty())
+                .map(|(l, source)| (l.command.clone(), *source)),
+        }
+    }
+
+    /// Run args placed before the image.
+    fn entrypoint_args(&self) -> Vec<String> {
+        self.entrypoint
+            .iter()
+            .map(|(e, _)| format!("--entrypoint={}", e))
+            .collect()
+    }
+
+    /// Args placed after the image.
+    fn command_args(&self) -> &[String] {
+        self.command.as_ref().map_or(&[], |(c, _)| c.as_slice())
+    }
+}
+
+/// Posts the hooks' notifications, queueing them in the home volume while
+/// the server is unreachable.
+const NOTIFY_HOOK_SH: &str = include_str!("../templates/notify-hook.sh");
+
+/// Where [`NOTIFY_HOOK_SH`] is seeded, relative to the container's home.
+const NOTIFY_HOOK_PATH: &str = ".ai-pod/notify.sh";
+
+fn generate_runtime_settings(config: &AppConfig, vars: &TemplateVars) -> Result<()> {
     let mut settings: serde_json::Value = if config.claude_settings_path().exists() {
         let raw = std::fs::read_to_string(config.claude_settings_path())
             .context("Failed to read settings.json")?;
@@ -238,19 +972,15 @@ fn generate_runtime_settings(config: &AppConfig) -> Result<()> {
     } else {
         serde_json::json!({})
     };
+    crate::claude_md::expand_json(&mut settings, vars);
 
-    let notify_curl = |msg: &str| {
-        format!(
-            "curl -fsS -X POST -H \"X-Api-Key: $AI_POD_API_KEY\" -H 'Content-Type: application/json' -d '{{\"project_id\":\"'\"$AI_POD_PROJECT_ID\"'\",\"message\":\"{}\"}}' \"$AI_POD_SERVER_URL/notify_user\" >/dev/null || true",
-            msg
-        )
-    };
+    let notify_hook = |event: &str, msg: &str| format!("sh \"$HOME/{}\" {} '{}'", NOTIFY_HOOK_PATH, event, msg);
 
     let stop_hook = serde_json::json!([{
         "matcher": "*",
         "hooks": [{
             "type": "command",
-            "command": notify_curl("Task completed"),
+            "command": notify_hook("stop", "Task completed"),
         }]
     }]);
 
@@ -258,7 +988,7 @@ fn generate_runtime_settings(config: &AppConfig) -> Result<()> {
         "matcher": "*",
         "hooks": [{
             "type": "command",
-            "command": notify_curl("Claude needs your approval"),
+            "command": notify_hook("permission", "Claude needs your approval"),
         }]
     }]);
 
@@ -270,6 +1000,20 @@ fn generate_runtime_settings(config: &AppConfig) -> Result<()> {
     let hooks_obj = hooks.as_object_mut().context("hooks is not an object")?;
     hooks_obj.insert("Stop".to_string(), stop_hook);
     hooks_obj.insert("PermissionRequest".to_string(), permission_hook);
+    // Sends what the hooks queued while the server was down, next to any
+    // prompt hooks of the user's own.
+    let flush_hook = serde_json::json!({
+        "hooks": [{
+            "type": "command",
+            "command": format!("sh \"$HOME/{}\" --flush", NOTIFY_HOOK_PATH),
+        }]
+    });
+    match hooks_obj.get_mut("UserPromptSubmit").and_then(|h| h.as_array_mut()) {
+        Some(existing) => existing.push(flush_hook),
+        None => {
+            hooks_obj.insert("UserPromptSubmit".to_string(), serde_json::json!([flush_hook]));
+        }
+    }
 
     // Set default permission mode — no per-tool prompts in TUI
     let permissions = obj
@@ -342,6 +1086,7 @@ fn write_gitconfig_to_volume(
     rt: &ContainerRuntime,
     config: &AppConfig,
     init_container: &str,
+    home: &str,
 ) -> Result<()> {
     let name = read_git_global("user.name");
     let email = read_git_global("user.email");
@@ -359,132 +1104,358 @@ fn write_gitconfig_to_volume(
 
     let tmp = config.config_dir.join("gitconfig.tmp");
     std::fs::write(&tmp, lines.join("\n") + "\n")?;
-    let _ = rt
-        .command()
-        .args([
-            "cp",
-            tmp.to_str().unwrap(),
-            &format!("{}:{}/.gitconfig", init_container, CONTAINER_HOME),
-        ])
-        .status();
+    seed_file(rt, &tmp, init_container, &format!("{}/.gitconfig", home));
     Ok(())
 }
 
-/// Populate a home volume via a temporary stopped container.
-/// Handles directory creation, runtime config, skill file, opencode config, and git identity.
-/// Set `copy_claude_json` to copy `~/.claude.json` (first-time init only; skipped on reseed).
-///
-/// Note: the `mcpServers.ai-pod` entry is *not* written here — `refresh_claude_mcp_in_volume`
-/// handles that on every launch with the current session id baked in.
-fn seed_home_volume(
-    rt: &ContainerRuntime,
+/// The stopped helper container files are copied into a home volume
+/// through. It's removed when dropped, so a failed seeding step doesn't
+/// leave it behind to block the next launch's `create` with a name
+/// conflict; one left by a killed launch is removed by
+/// [`remove_stale_init_containers`].
+struct InitContainer<'a> {
+    rt: &'a ContainerRuntime,
+    name: String,
+}
+
+impl<'a> InitContainer<'a> {
+    fn create(
+        rt: &'a ContainerRuntime,
+        workspace: &Path,
+        volumes: &HomeVolumes,
+        container_name: &str,
+        image: &str,
+    ) -> Result<Self> {
+        let name = format!("{}-init", container_name);
+        remove_container_quietly(rt, &name);
+        let status = rt
+            .command()
+            .args(["create", "--name", &name])
+            .args(crate::collision::label_args(workspace))
+            .args(volumes.user.run_args())
+            .args(volumes.mount_args())
+            .args([image, "true"])
+            .status()
+            .context("Failed to create init container")?;
+        if !status.success() {
+            anyhow::bail!("Failed to create init container");
+        }
+        Ok(InitContainer { rt, name })
+    }
+}
+
+impl Drop for InitContainer<'_> {
+    fn drop(&mut self) {
+        remove_container_quietly(self.rt, &self.name);
+    }
+}
+
+fn remove_container_quietly(rt: &ContainerRuntime, name: &str) {
+    let _ = rt
+        .command()
+        .args(["rm", "--force", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Whether `name` is a home volume's init container: `<prefix>-init`, or
+/// `<prefix>-<session id>-init` for a `--new` instance.
+fn is_init_container(prefix: &str, name: &str) -> bool {
+    match name.strip_prefix(prefix).and_then(|rest| rest.strip_suffix("-init")) {
+        Some("") => true,
+        Some(instance) => is_session_container("", instance),
+        None => false,
+    }
+}
+
+/// Remove init containers of `workspace` that a launch killed while seeding
+/// left behind. Runs under the workspace's setup lock, so no seeding is
+/// using them.
+fn remove_stale_init_containers(rt: &ContainerRuntime, workspace: &Path) {
+    if rt.dry_run {
+        return;
+    }
+    let prefix = container_prefix(workspace);
+    let Ok(out) = rt
+        .command()
+        .args(["ps", "-a", "--filter", &format!("name=^{}-", prefix), "--format", "{{.Names}}"])
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return;
+    };
+    for name in String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter(|name| is_init_container(&prefix, name))
+    {
+        eprintln!("{} {}", "Removing leftover init container:".blue().bold(), name);
+        remove_container_quietly(rt, name);
+    }
+}
+
+/// Populate a home volume via a temporary stopped container.
+/// Handles directory creation, runtime config, skill file, opencode config, and git identity.
+/// Set `copy_claude_json` to copy `~/.claude.json` (first-time init only; skipped on reseed).
+///
+/// Note: the `mcpServers.ai-pod` entry is *not* written here — `refresh_claude_mcp_in_volume`
+/// handles that on every launch with the current session id baked in.
+fn seed_home_volume(
+    rt: &ContainerRuntime,
     config: &AppConfig,
-    volume_name: &str,
+    workspace: &Path,
+    volumes: &HomeVolumes,
     container_name: &str,
     image: &str,
     copy_claude_json: bool,
 ) -> Result<()> {
-    let init_container = format!("{}-init", container_name);
-    let status = rt
-        .command()
-        .args([
-            "create",
-            "--name",
-            &init_container,
-            "-v",
-            &format!("{}:{}", volume_name, CONTAINER_HOME),
-            image,
-            "true",
-        ])
-        .status()
-        .context("Failed to create init container")?;
-    if !status.success() {
-        anyhow::bail!("Failed to create init container");
+    let init_container = InitContainer::create(rt, workspace, volumes, container_name, image)?;
+    let vars = TemplateVars::new(rt, workspace, None);
+    populate_home_volume(rt, config, &vars, volumes, &init_container.name, image, copy_claude_json)?;
+    drop(init_container);
+
+    if let Some(dotfiles) = GlobalConfig::load(config).dotfiles {
+        install_dotfiles(rt, &dotfiles, volumes, image);
+    }
+
+    Ok(())
+}
+
+/// Copy a file into the init container, warning when that fails: a missing
+/// settings file shouldn't stop the launch, but shouldn't go unnoticed
+/// either. Returns whether the copy succeeded.
+fn seed_file(rt: &ContainerRuntime, src: &Path, init_container: &str, dest: &str) -> bool {
+    match copy_to_container(rt, src, init_container, dest) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!(
+                "{} {:#}; the container starts without it",
+                "warning:".yellow().bold(),
+                e
+            );
+            false
+        }
     }
+}
 
+/// The seeding steps that go through the init container.
+fn populate_home_volume(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    vars: &TemplateVars,
+    volumes: &HomeVolumes,
+    init_container: &str,
+    image: &str,
+    copy_claude_json: bool,
+) -> Result<()> {
+    let home = volumes.dir();
     if copy_claude_json {
         let host_claude_json = config.home_dir.join(".claude.json");
         if host_claude_json.exists() {
-            let _ = rt
-                .command()
-                .args([
-                    "cp",
-                    &host_claude_json.to_string_lossy(),
-                    &format!("{}:{}/", init_container, CONTAINER_HOME),
-                ])
-                .status();
+            seed_file(rt, &host_claude_json, init_container, &format!("{}/", home));
         }
     }
 
     let _ = rt
         .command()
+        .args(["run", "--rm"])
+        .args(volumes.user.run_args())
+        .args(volumes.mount_args())
         .args([
-            "run",
-            "--rm",
-            "-v",
-            &format!("{}:{}:z", volume_name, CONTAINER_HOME),
             image,
             "mkdir",
             "-p",
-            &format!("{}/.claude", CONTAINER_HOME),
-            &format!("{}/.config", CONTAINER_HOME),
-            &format!("{}/.config/opencode/plugins", CONTAINER_HOME),
+            &format!("{}/.claude", home),
+            &format!("{}/.config", home),
+            &format!("{}/.config/opencode/plugins", home),
+            &format!("{}/.ai-pod", home),
         ])
         .status();
 
-    generate_runtime_settings(config)?;
+    generate_runtime_settings(config, vars)?;
 
-    let _ = rt
-        .command()
-        .args([
-            "cp",
-            &config.runtime_settings.to_string_lossy(),
-            &format!("{}:{}/.claude/settings.json", init_container, CONTAINER_HOME),
-        ])
-        .status();
+    seed_file(
+        rt,
+        &config.runtime_settings,
+        init_container,
+        &format!("{}/.claude/settings.json", home),
+    );
+
+    let notify_hook = config.config_dir.join("notify-hook.sh");
+    std::fs::write(&notify_hook, NOTIFY_HOOK_SH).context("Failed to write notify-hook.sh")?;
+    seed_file(rt, &notify_hook, init_container, &format!("{}/{}", home, NOTIFY_HOOK_PATH));
+
+    if copy_claude_json {
+        seed_keychain_credentials(rt, config, init_container, &home)?;
+    }
 
     // Copy the host's personal CLAUDE.md into the container (no ai-pod preamble)
     let host_claude_md = config.claude_md_path();
     if host_claude_md.exists() {
-        let _ = rt
-            .command()
-            .args([
-                "cp",
-                &host_claude_md.to_string_lossy(),
-                &format!("{}:{}/.claude/CLAUDE.md", init_container, CONTAINER_HOME),
-            ])
-            .status();
+        seed_file(
+            rt,
+            &host_claude_md,
+            init_container,
+            &format!("{}/.claude/CLAUDE.md", home),
+        );
     }
 
     let opencode_plugin = config.config_dir.join("opencode-plugin.js");
     if opencode_plugin.exists() {
-        let _ = rt
-            .command()
-            .args([
-                "cp",
-                &opencode_plugin.to_string_lossy(),
-                &format!(
-                    "{}:{}/.config/opencode/plugins/ai-pod.js",
-                    init_container, CONTAINER_HOME
-                ),
-            ])
-            .status();
+        seed_file(
+            rt,
+            &opencode_plugin,
+            init_container,
+            &format!("{}/.config/opencode/plugins/ai-pod.js", home),
+        );
     }
 
-    write_gitconfig_to_volume(rt, config, &init_container)?;
+    write_gitconfig_to_volume(rt, config, init_container, &home)?;
+    crate::banner::install(rt, &[volumes.user.run_args(), volumes.mount_args()].concat(), image);
 
-    let _ = rt.command().args(["rm", &init_container]).status();
+    Ok(())
+}
+
+/// Clone (or fast-forward) the dotfiles checkout, then run the install
+/// command, the first conventional install script, or — failing both —
+/// symlink the top-level dotfiles into `$HOME`. Repo and command travel as
+/// env vars so nothing user-supplied is spliced into the script.
+const DOTFILES_SCRIPT: &str = r#"set -e
+command -v git >/dev/null || { echo "git is not installed in the image" >&2; exit 1; }
+case "$DOTFILES_TARGET" in "~/"*) DOTFILES_TARGET="$HOME/${DOTFILES_TARGET#\~/}" ;; esac
+if [ -d "$DOTFILES_TARGET/.git" ]; then
+  git -C "$DOTFILES_TARGET" pull --ff-only -q
+else
+  git clone -q --depth 1 "$DOTFILES_REPO" "$DOTFILES_TARGET"
+fi
+cd "$DOTFILES_TARGET"
+if [ -n "$DOTFILES_INSTALL" ]; then
+  sh -c "$DOTFILES_INSTALL"
+  exit
+fi
+for f in install.sh install bootstrap.sh bootstrap script/bootstrap setup.sh setup script/setup; do
+  if [ -f "$f" ]; then chmod +x "$f"; "./$f"; exit; fi
+done
+for f in .[!.]*; do
+  [ "$f" = .git ] || [ ! -e "$f" ] || ln -sfn "$DOTFILES_TARGET/$f" "$HOME/$f"
+done
+"#;
+
+fn dotfiles_args(
+    dotfiles: &crate::config::DotfilesConfig,
+    volumes: &HomeVolumes,
+    image: &str,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec!["run".into(), "--rm".into()];
+    args.extend(volumes.user.run_args());
+    args.extend(volumes.mount_args());
+    args.extend([
+        "-e".into(),
+        format!("DOTFILES_REPO={}", dotfiles.clone_url()),
+        "-e".into(),
+        format!("DOTFILES_TARGET={}", dotfiles.target_path),
+        "-e".into(),
+        format!(
+            "DOTFILES_INSTALL={}",
+            dotfiles.install_command.as_deref().unwrap_or("")
+        ),
+        "--entrypoint".into(),
+        "sh".into(),
+        image.into(),
+        "-c".into(),
+        DOTFILES_SCRIPT.into(),
+    ]);
+    args
+}
 
+/// Best-effort: a broken dotfiles repo should never block a launch.
+fn install_dotfiles(
+    rt: &ContainerRuntime,
+    dotfiles: &crate::config::DotfilesConfig,
+    volumes: &HomeVolumes,
+    image: &str,
+) {
+    eprintln!("{} {}", "Installing dotfiles:".blue().bold(), dotfiles.clone_url());
+    let ok = rt
+        .command()
+        .args(dotfiles_args(dotfiles, volumes, image))
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if !ok {
+        eprintln!(
+            "{} dotfiles installation failed; continuing without them",
+            "warning:".yellow().bold()
+        );
+    }
+}
+
+/// On macOS, offer to copy Claude Code's OAuth credentials from the keychain
+/// into the new volume's `~/.claude/.credentials.json`, where the Linux build
+/// inside the container looks for them. Declining (or no terminal to ask on)
+/// leaves the container signed out, with a pointer to `ai-pod login`.
+fn seed_keychain_credentials(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    init_container: &str,
+    home: &str,
+) -> Result<()> {
+    if !cfg!(target_os = "macos") {
+        return Ok(());
+    }
+    let consent = crate::is_stdin_tty()
+        && dialoguer::Confirm::new()
+            .with_prompt("Copy your Claude Code login from the macOS keychain into the container?")
+            .default(true)
+            .interact()
+            .unwrap_or(false);
+    let creds = if consent {
+        crate::keychain::claude_credentials()
+    } else {
+        None
+    };
+    let Some(creds) = creds else {
+        signed_out_note();
+        return Ok(());
+    };
+
+    let tmp = config.config_dir.join("credentials-seed.json");
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp)
+        .context("Failed to stage credentials")?;
+    std::io::Write::write_all(&mut file, creds.as_bytes())?;
+    drop(file);
+    let seeded = seed_file(
+        rt,
+        &tmp,
+        init_container,
+        &format!("{}/.claude/.credentials.json", home),
+    );
+    let _ = std::fs::remove_file(&tmp);
+    if !seeded {
+        signed_out_note();
+    }
     Ok(())
 }
 
+fn signed_out_note() {
+    eprintln!(
+        "{} the container starts signed out; run `ai-pod login` to sign in.",
+        "Note:".yellow().bold()
+    );
+}
+
 /// Update the `mcpServers.ai-pod` entry in the volume's `~/.claude.json`
 /// with literal api_key + session_id values. Runs on every launch so the
 /// in-volume config matches the env the agent will see.
 fn refresh_claude_mcp_in_volume(
     rt: &ContainerRuntime,
     config: &AppConfig,
-    volume_name: &str,
+    volumes: &HomeVolumes,
     container_name: &str,
     image: &str,
     server_url: &str,
@@ -494,15 +1465,10 @@ fn refresh_claude_mcp_in_volume(
     let init_container = format!("{}-mcp", container_name);
     let status = rt
         .command()
-        .args([
-            "create",
-            "--name",
-            &init_container,
-            "-v",
-            &format!("{}:{}", volume_name, CONTAINER_HOME),
-            image,
-            "true",
-        ])
+        .args(["create", "--name", &init_container])
+        .args(volumes.user.run_args())
+        .args(volumes.mount_args())
+        .args([image, "true"])
         .status()
         .context("Failed to create mcp-refresh container")?;
     if !status.success() {
@@ -512,14 +1478,7 @@ fn refresh_claude_mcp_in_volume(
     // Pull the existing .claude.json out of the volume (may not exist yet).
     let tmp_in = config.config_dir.join("claude-in.json");
     let _ = std::fs::remove_file(&tmp_in);
-    let _ = rt
-        .command()
-        .args([
-            "cp",
-            &format!("{}:{}/.claude.json", init_container, CONTAINER_HOME),
-            tmp_in.to_str().unwrap(),
-        ])
-        .status();
+    let _ = copy_from_container(rt, &init_container, &format!("{}/.claude.json", volumes.dir()), &tmp_in);
 
     let mut value: serde_json::Value = std::fs::read_to_string(&tmp_in)
         .ok()
@@ -541,14 +1500,12 @@ fn refresh_claude_mcp_in_volume(
 
     let tmp_out = config.config_dir.join("claude-out.json");
     std::fs::write(&tmp_out, serde_json::to_string_pretty(&value)?)?;
-    let _ = rt
-        .command()
-        .args([
-            "cp",
-            tmp_out.to_str().unwrap(),
-            &format!("{}:{}/.claude.json", init_container, CONTAINER_HOME),
-        ])
-        .status();
+    let _ = copy_to_container(
+        rt,
+        &tmp_out,
+        &init_container,
+        &format!("{}/.claude.json", volumes.dir()),
+    );
 
     let _ = rt.command().args(["rm", &init_container]).status();
     let _ = std::fs::remove_file(&tmp_in);
@@ -556,84 +1513,484 @@ fn refresh_claude_mcp_in_volume(
     Ok(())
 }
 
-/// Initialize a named home volume for the first time.
+/// Record (or clear) a home volume as being initialised; see
+/// `ProjectState::home_volume_pending`.
+fn set_home_volume_pending(config: &AppConfig, workspace: &Path, volume: Option<&str>) -> Result<()> {
+    let path = config.project_state_file(&workspace_hash(workspace));
+    let mut state = ProjectState::load(&path);
+    if state.home_volume_pending.as_deref() != volume {
+        state.home_volume_pending = volume.map(str::to_string);
+        state.save(&path)?;
+    }
+    Ok(())
+}
+
+/// Record (or clear) the image a home volume was seeded from; see
+/// `ProjectState::home_volume_image`.
+fn set_home_volume_image(config: &AppConfig, workspace: &Path, image_id: Option<String>) -> Result<()> {
+    let path = config.project_state_file(&workspace_hash(workspace));
+    let mut state = ProjectState::load(&path);
+    if state.home_volume_image != image_id {
+        state.home_volume_image = image_id;
+        state.save(&path)?;
+    }
+    Ok(())
+}
+
+/// Create and seed the workspace's home volumes if they don't exist yet.
+/// The home volume is recreated when an earlier initialisation never
+/// finished, and regenerated when the image changed since it was seeded;
+/// the state volume is kept either way.
+pub(crate) fn ensure_home_volume(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    image: &str,
+) -> Result<HomeVolumes> {
+    let volumes = HomeVolumes::for_workspace(workspace).with_user(container_user(config, workspace)?);
+    let volume_name = &volumes.home;
+    crate::collision::check_volume(rt, &volumes.state, workspace)?;
+    crate::collision::check_volume(rt, volume_name, workspace)?;
+    ensure_state_volume(rt, config, workspace, &volumes, image)?;
+    let mut exists = volume_exists(rt, volume_name)?;
+    let state = load_project_state(config, workspace);
+    let image_id = crate::image::image_id(rt, image);
+    let mut carried = false;
+    if exists && state.home_volume_pending.as_deref() == Some(volume_name.as_str()) {
+        eprintln!(
+            "{} home volume {} was left half-initialised by an interrupted launch; recreating it",
+            "warning:".yellow().bold(),
+            volume_name
+        );
+        remove_volume(rt, volume_name)?;
+        exists = volume_exists(rt, volume_name)?;
+        if exists {
+            anyhow::bail!(
+                "Could not remove the half-initialised volume {}. Remove it with `{} volume rm {}` and try again.",
+                volume_name,
+                rt.cmd(),
+                volume_name
+            );
+        }
+    } else if exists
+        && image_id.is_some()
+        && state.home_volume_image.is_some()
+        && state.home_volume_image != image_id
+    {
+        eprintln!(
+            "{} the image changed since {} was seeded; regenerating it ({} is kept)",
+            "Note:".yellow().bold(),
+            volume_name,
+            volumes.state
+        );
+        if GlobalConfig::load(config).carry_over.unwrap_or(true) && !rt.dry_run {
+            carried = match crate::carry_over::save(rt, &volumes, image) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!(
+                        "{} could not record shell history and tools from {}: {}",
+                        "warning:".yellow().bold(),
+                        volume_name,
+                        e
+                    );
+                    false
+                }
+            };
+        }
+        // A running session still mounts it: keep using it until a later launch.
+        remove_volume(rt, volume_name)?;
+        exists = volume_exists(rt, volume_name)?;
+        if exists {
+            return Ok(volumes);
+        }
+    }
+    if !exists {
+        reset_post_create(config, workspace);
+        init_home_volume(rt, config, workspace, &volumes, image)?;
+        if carried {
+            crate::carry_over::restore(rt, &volumes, image);
+        }
+    }
+    if !rt.dry_run {
+        set_home_volume_image(config, workspace, image_id)?;
+    }
+    Ok(volumes)
+}
+
+/// Create the state volume if it's missing. A home volume from before the
+/// split still holds `~/.claude`; its contents move over once. Otherwise
+/// the host's history may be imported into it (`seed.history`).
+fn ensure_state_volume(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    volumes: &HomeVolumes,
+    image: &str,
+) -> Result<()> {
+    if volume_exists(rt, &volumes.state)? {
+        return Ok(());
+    }
+    create_volume(rt, &volumes.state, "state volume", Some(workspace))?;
+    let from = if volume_exists(rt, &volumes.home)? {
+        eprintln!(
+            "{} {} -> {}",
+            "Moving ~/.claude to the state volume:".blue().bold(),
+            volumes.home,
+            volumes.state
+        );
+        Some((volumes.home.as_str(), ".claude"))
+    } else {
+        None
+    };
+    if let Err(e) = fill_volume(rt, from, &volumes.state, image, &volumes.user) {
+        let _ = remove_volume(rt, &volumes.state);
+        return Err(e.context(format!("Failed to initialise state volume {}", volumes.state)));
+    }
+    if from.is_none() {
+        crate::history_import::import(rt, config, workspace, &GlobalConfig::load(config).seed, volumes, image);
+    }
+    Ok(())
+}
+
+/// Initialize a named home volume for the first time. The volume only
+/// counts as ready once every step succeeded: it's marked pending until
+/// then, and removed again when seeding fails.
 fn init_home_volume(
     rt: &ContainerRuntime,
     config: &AppConfig,
-    volume_name: &str,
-    container_name: &str,
+    workspace: &Path,
+    volumes: &HomeVolumes,
     image: &str,
-    project_id: &str,
-    api_key: &str,
 ) -> Result<()> {
+    let volume_name = volumes.home.as_str();
     eprintln!(
         "{} {}",
         "Initialising home volume:".blue().bold(),
         volume_name
     );
 
-    let status = rt
-        .command()
-        .args(["volume", "create", volume_name])
-        .status()
-        .context("Failed to create volume")?;
-    if !status.success() {
-        anyhow::bail!("Failed to create volume {}", volume_name);
+    if !rt.dry_run {
+        set_home_volume_pending(config, workspace, Some(volume_name))?;
     }
+    create_volume(rt, volume_name, "volume", Some(workspace))?;
 
-    seed_home_volume(rt, config, volume_name, container_name, image, true)?;
-
-    let _ = (project_id, api_key); // used via env vars at runtime
+    if let Err(e) = seed_home_volume(rt, config, workspace, volumes, &container_prefix(workspace), image, true) {
+        let _ = remove_volume(rt, volume_name);
+        if !volume_exists(rt, volume_name).unwrap_or(true) {
+            let _ = set_home_volume_pending(config, workspace, None);
+        }
+        return Err(e.context(format!("Failed to initialise home volume {}", volume_name)));
+    }
+    if !rt.dry_run {
+        set_home_volume_pending(config, workspace, None)?;
+    }
 
     eprintln!("{}", "Home volume initialised.".green());
 
     Ok(())
 }
 
+/// Create the volumes of a `--new` instance: copies of the workspace's, or
+/// newly seeded ones. Removed again if that fails.
+fn instance_home_volume(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    session_id: &str,
+    image: &str,
+    mode: NewInstance,
+) -> Result<HomeVolumes> {
+    let volumes = HomeVolumes::for_instance(workspace, session_id).with_user(container_user(config, workspace)?);
+    eprintln!(
+        "{} {} ({})",
+        "Creating instance home volume:".blue().bold(),
+        volumes.home,
+        match mode {
+            NewInstance::Clone => "copy of the workspace's",
+            NewInstance::Fresh => "fresh",
+        }
+    );
+    if let Err(e) = fill_instance_volumes(rt, config, workspace, session_id, image, mode, &volumes) {
+        volumes.remove(rt);
+        return Err(e.context(format!("Failed to create instance volume {}", volumes.home)));
+    }
+    Ok(volumes)
+}
+
+fn fill_instance_volumes(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    session_id: &str,
+    image: &str,
+    mode: NewInstance,
+    volumes: &HomeVolumes,
+) -> Result<()> {
+    create_volume(rt, &volumes.home, "instance volume", Some(workspace))?;
+    create_volume(rt, &volumes.state, "instance volume", Some(workspace))?;
+    match mode {
+        NewInstance::Clone => {
+            let shared = HomeVolumes::for_workspace(workspace);
+            fill_volume(rt, Some((&shared.home, ".")), &volumes.home, image, &volumes.user)?;
+            fill_volume(rt, Some((&shared.state, ".")), &volumes.state, image, &volumes.user)
+        }
+        NewInstance::Fresh => {
+            fill_volume(rt, None, &volumes.state, image, &volumes.user)?;
+            seed_home_volume(
+                rt,
+                config,
+                workspace,
+                volumes,
+                &container_name_for(workspace, session_id),
+                image,
+                true,
+            )
+        }
+    }
+}
+
+/// Copy directory `dir` of volume `from` (`.` for all of it; skipped when
+/// missing) into volume `to`, keeping owners and modes, then hand `to`'s top
+/// directory to the container user: the configured one, else the owner of
+/// the image's home. Runs as root: a new volume's top directory belongs to
+/// root.
+fn fill_volume(
+    rt: &ContainerRuntime,
+    from: Option<(&str, &str)>,
+    to: &str,
+    image: &str,
+    user: &UserConfig,
+) -> Result<()> {
+    let mut cmd = rt.command();
+    cmd.args(["run", "--rm", "--user", "0"]);
+    if let Some((volume, _)) = from {
+        cmd.args(["-v", &format!("{}:/from:ro,z", volume)]);
+    }
+    let script = format!(
+        r#"[ ! -d "/from/$1" ] || cp -a "/from/$1/." /to/; chown "${{2:-$(stat -c %u:%g {})}}" /to"#,
+        CONTAINER_HOME
+    );
+    let owner = if user.name.is_some() { user.owner() } else { String::new() };
+    let status = cmd
+        .args(["-v", &format!("{}:/to:z", to), "--entrypoint", "sh", image, "-c", &script, "sh"])
+        .arg(from.map_or(".", |(_, dir)| dir))
+        .arg(owner)
+        .status()
+        .context("Failed to fill volume")?;
+    if !status.success() {
+        anyhow::bail!("Failed to fill volume {}", to);
+    }
+    Ok(())
+}
+
 /// Re-apply runtime config after a rebuild.
 /// Does NOT wipe the volume — auth state is preserved.
 fn reseed_home_volume(
     rt: &ContainerRuntime,
     config: &AppConfig,
-    volume_name: &str,
+    workspace: &Path,
+    volumes: &HomeVolumes,
     container_name: &str,
     image: &str,
-    project_id: &str,
-    api_key: &str,
 ) -> Result<()> {
     eprintln!(
         "{} {}",
         "Refreshing home volume config:".blue().bold(),
-        volume_name
+        volumes.home
     );
 
-    seed_home_volume(rt, config, volume_name, container_name, image, false)?;
-
-    let _ = (project_id, api_key); // used via env vars at runtime
+    seed_home_volume(rt, config, workspace, volumes, container_name, image, false)?;
 
     eprintln!("{}", "Home volume reseeded.".green());
 
     Ok(())
 }
 
+/// What the next launch of a workspace would pass to the runtime, for
+/// `ai-pod env`. Each entry is paired with where it comes from. Nothing is
+/// created: cache and mask volumes are listed by name.
+#[derive(Debug, Default)]
+pub struct LaunchPreview {
+    pub image: String,
+    pub env: Vec<(String, &'static str)>,
+    pub mounts: Vec<(String, &'static str)>,
+    pub network: Vec<(String, &'static str)>,
+    pub security: Vec<(String, &'static str)>,
+    pub command: Vec<(String, &'static str)>,
+    pub relaxed: bool,
+}
+
+/// Values of `flag` in a flat arg list (`["-v", "a", "-v", "b"]` → `a`, `b`).
+fn flag_values(args: &[String], flag: &str) -> Vec<String> {
+    args.windows(2)
+        .filter(|w| w[0] == flag)
+        .map(|w| w[1].clone())
+        .collect()
+}
+
+/// Pair up `--flag value` args for display; `--flag=value` stays as is.
+fn flag_pairs(args: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for arg in args {
+        match out.last_mut() {
+            Some(last) if !arg.starts_with('-') && last.starts_with('-') && !last.contains([' ', '=']) => {
+                last.push(' ');
+                last.push_str(arg);
+            }
+            _ => out.push(arg.clone()),
+        }
+    }
+    out
+}
+
+/// Work out the next launch's environment, mounts, network and security
+/// options from the global config, the project state and `opts`. The
+/// session id and API key are shown as placeholders.
+pub fn launch_preview(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    image: &str,
+    opts: &LaunchOptions,
+) -> Result<LaunchPreview> {
+    let global = GlobalConfig::load(config);
+    let project_state = load_project_state(config, workspace);
+    let user = container_user(config, workspace)?;
+    let project_id = workspace_hash(workspace);
+    let (session_id, api_key) = ("<session id>", "<api key>");
+    let mut p = LaunchPreview {
+        image: image.to_string(),
+        relaxed: project_state.relax_security,
+        ..Default::default()
+    };
+
+    let env = |args: &[String], source| {
+        flag_values(args, "-e")
+            .into_iter()
+            .map(move |e| (e, source))
+    };
+    p.env.extend(env(&host_env_args_from_env(crate::is_stdin_tty()), "host"));
+    p.env.extend(env(&crate::direnv::env_args(&global.direnv, workspace), "direnv"));
+    for var in [
+        format!("HOST_GATEWAY={}", rt.host_gateway()),
+        format!("AI_POD_PROJECT_ID={}", project_id),
+        format!("AI_POD_API_KEY={}", api_key),
+        format!("AI_POD_SESSION_ID={}", session_id),
+        format!("AI_POD_SERVER_URL={}", rt.server_url()),
+        histfile_env(&user.home_dir()),
+        "OPENCODE_CONFIG_CONTENT=<generated per session>".to_string(),
+    ] {
+        p.env.push((var, "ai-pod"));
+    }
+    let proxy = egress_proxy_args(rt, &global.network, &project_id, session_id, api_key);
+    p.env.extend(env(&proxy, "network"));
+    let budget = crate::server::budget::env_args(&global.budget, &rt.server_url(), &project_id, session_id, api_key);
+    p.env.extend(env(&budget, "budget"));
+
+    let volumes = match opts.new_instance {
+        Some(_) => HomeVolumes::for_instance(workspace, session_id),
+        None => HomeVolumes::for_workspace(workspace),
+    }
+    .with_user(user.clone());
+    let source = if opts.new_instance.is_some() { "--new" } else { "home" };
+    for m in flag_values(&volumes.mount_args(), "-v") {
+        p.mounts.push((m, source));
+    }
+    let sync = crate::sync::enabled(&global.sync, &project_state.sync);
+    p.mounts.push((app_mount(workspace, &global, &project_state), if sync { "sync" } else { "workspace" }));
+    for (mounts, source) in [(&global.mounts, "global"), (&project_state.mounts, "project")] {
+        for m in flag_values(&build_mount_args(&config.home_dir, &user.home_dir(), mounts)?, "-v") {
+            p.mounts.push((m, source));
+        }
+    }
+    for kind in enabled_caches(&global) {
+        p.mounts.push((format!("{}:{}:z", kind.volume_name(), kind.container_path(&user.home_dir())), "cache"));
+    }
+    for dir in &project_state.masked_directories {
+        p.mounts.push((format!("{}:/app/{}:Z", mask_volume_name(workspace, dir), dir), "mask"));
+    }
+    for m in flag_pairs(&exclude_args(workspace, &project_state.excluded_paths)) {
+        p.mounts.push((m, "exclude"));
+    }
+    for m in flag_values(&extra_workspace_args(&opts.extra_workspaces), "-v") {
+        p.mounts.push((m, "--add-workspace"));
+    }
+
+    p.network.push((format!("--network {}", crate::workspace::service_network_name(workspace)), "ai-pod"));
+    p.network.push((rt.add_host_arg(), "ai-pod"));
+    for arg in flag_pairs(&resolver_args(&global.network)) {
+        p.network.push((arg, "network"));
+    }
+    if global.network.proxied() {
+        p.network.push(("egress proxy".to_string(), "network"));
+    }
+
+    let hardening = security_args(&global.security, &config.home_dir, project_state.relax_security);
+    for arg in flag_pairs(&hardening) {
+        p.security.push((arg, "security"));
+    }
+    for arg in flag_pairs(&user.run_args()) {
+        p.security.push((arg, "user"));
+    }
+    for arg in flag_pairs(&userns_args(rt, global.userns, image, &user)) {
+        p.security.push((arg, "userns"));
+    }
+    if global.healthcheck.enabled() {
+        for arg in flag_pairs(&crate::healthcheck::run_args(&global.healthcheck)) {
+            p.security.push((arg, "healthcheck"));
+        }
+    }
+
+    let session = SessionCommand::resolve(opts, &project_state.launch, &global.launch);
+    if let Some((entrypoint, source)) = &session.entrypoint {
+        p.command.push((format!("--entrypoint={}", entrypoint), source));
+    }
+    if let Some((command, source)) = &session.command {
+        p.command.push((command.join(" "), source));
+    }
+    Ok(p)
+}
+
+/// Run an interactive session. Returns `true` when the Dockerfile changed
+/// under `--watch-dockerfile` and the session should be relaunched from a
+/// rebuilt image.
 pub fn launch_container(
     rt: &ContainerRuntime,
     config: &AppConfig,
     workspace: &Path,
-    rebuild: bool,
     image: &str,
     project_id: &str,
     api_key: &str,
-) -> Result<()> {
+    opts: &LaunchOptions,
+) -> Result<bool> {
     let prefix = container_prefix(workspace);
-    let volume_name = gen_volume_name(workspace);
+    let volumes = HomeVolumes::for_workspace(workspace).with_user(container_user(config, workspace)?);
     let workspace_str = workspace.to_string_lossy();
+    let global = GlobalConfig::load(config);
 
-    rt.warn_if_rootless_userns_mismatch();
+    rt.warn_if_rootless_userns_mismatch(global.userns);
+    crate::vm::check_workspace(rt, config, workspace);
+
+    // Volume init, reseeding and post-create must not run twice at once.
+    let mut setup_lock = crate::launch_lock::acquire(config, workspace)?;
+    remove_stale_init_containers(rt, workspace);
+
+    // A standby from `ai-pod warm` is set up already; otherwise a new
+    // standby replaces the old one.
+    let standby = crate::warm::take(rt, config, workspace, image, opts);
+    if opts.warm {
+        crate::warm::discard(rt, config, workspace);
+    }
 
     // On rebuild: stop all existing containers for this workspace and reseed the volume
-    if rebuild {
-        for name in containers_for_prefix(rt, &prefix, false)? {
+    if opts.rebuild {
+        let existing = containers_for_prefix(rt, &prefix, false)?;
+        for name in &existing {
+            crate::collision::check_container(rt, name, workspace)?;
+        }
+        crate::collision::check_volume(rt, &volumes.home, workspace)?;
+        crate::collision::check_volume(rt, &volumes.state, workspace)?;
+        for name in existing {
             eprintln!(
                 "{} {}",
                 "Removing container for rebuild:".blue().bold(),
@@ -641,44 +1998,75 @@ pub fn launch_container(
             );
             let _ = rt.command().args(["rm", "--force", &name]).status();
         }
-        if volume_exists(rt, &volume_name)? {
-            reseed_home_volume(
-                rt,
-                config,
-                &volume_name,
-                &prefix,
-                image,
-                project_id,
-                api_key,
-            )?;
+        reset_post_create(config, workspace);
+        if volume_exists(rt, &volumes.home)? {
+            ensure_state_volume(rt, config, workspace, &volumes, image)?;
+            reseed_home_volume(rt, config, workspace, &volumes, &prefix, image)?;
         }
     }
 
-    // Init home volume if it doesn't exist
-    if !volume_exists(rt, &volume_name)? {
-        init_home_volume(
-            rt,
-            config,
-            &volume_name,
-            &prefix,
-            image,
-            project_id,
-            api_key,
-        )?;
+    // Init home volume if it doesn't exist (or never finished initialising).
+    // A fresh instance doesn't need it.
+    if standby.is_none() && opts.new_instance != Some(NewInstance::Fresh) {
+        ensure_home_volume(rt, config, workspace, image)?;
     }
 
-    let session_id = new_session_id();
-    let container_name = container_name_for(workspace, &session_id);
-    eprintln!("{} {}", "Starting container:".blue().bold(), container_name);
+    let (session_id, container_name) = match &standby {
+        Some(standby) => (standby.session_id.clone(), standby.container.clone()),
+        None => {
+            let session_id = new_session_id();
+            let container_name = container_name_for(workspace, &session_id);
+            (session_id, container_name)
+        }
+    };
+    let action = match (&standby, opts.warm) {
+        (_, true) => "Creating standby container:",
+        (Some(_), false) => "Starting standby container:",
+        (None, false) => "Starting container:",
+    };
+    eprintln!("{} {}", action.blue().bold(), container_name);
+
+    let mut hook_ctx = HookContext {
+        workspace,
+        project_id,
+        session_id: &session_id,
+        container_name: &container_name,
+        image,
+        exit_code: None,
+    };
+    if !rt.dry_run && !opts.warm {
+        // Hooks and plugins run unlocked: one may launch ai-pod for this
+        // workspace itself, and a slow one mustn't hold up other launches.
+        drop(setup_lock);
+        hooks::run_pre_launch(&global.hooks, &hook_ctx)?;
+        let mut event = Event::new(EventKind::Launch, Some(workspace))
+            .session(&session_id, &container_name)
+            .image(image);
+        event.title = opts.title.clone();
+        plugins::run_blocking(&config.config_dir, plugins::Hook::PreLaunch, &event)?;
+        events::record(&config.config_dir, &event);
+        setup_lock = crate::launch_lock::acquire(config, workspace)?;
+    }
 
     // Record the runtime for this session before the container starts, so the
     // shared server runs service containers on the same runtime.
-    crate::config::SessionState { runtime: rt.kind }.save(config, &session_id)?;
+    crate::config::SessionState {
+        runtime: rt.kind,
+        tmux: crate::tmux::current_target(),
+        title: opts.title.clone(),
+        workspace: Some(workspace.to_path_buf()),
+    }
+    .save(config, &session_id)?;
+
+    let volumes = match opts.new_instance {
+        Some(mode) => instance_home_volume(rt, config, workspace, &session_id, image, mode)?,
+        None => volumes,
+    };
 
     refresh_claude_mcp_in_volume(
         rt,
         config,
-        &volume_name,
+        &volumes,
         &prefix,
         image,
         &rt.server_url(),
@@ -689,15 +2077,58 @@ pub fn launch_container(
     let add_host = rt.add_host_arg();
     let host_gw_env = format!("HOST_GATEWAY={}", rt.host_gateway());
     let server_url_env = format!("AI_POD_SERVER_URL={}", rt.server_url());
+    let histfile_env = histfile_env(&volumes.dir());
     let opencode_config_env = format!(
         "OPENCODE_CONFIG_CONTENT={}",
         opencode_config_content(&rt.server_url(), api_key, &session_id)
     );
 
     let project_state = load_project_state(config, workspace);
-    let mask_args = mask_mount_args(rt, workspace, image, &project_state.masked_directories)?;
-    let global = GlobalConfig::load(config);
-    let user_mount_args = build_mount_args(&config.home_dir, &global.mounts)?;
+    let mask_args = mask_mount_args(rt, workspace, image, &project_state.masked_directories, &volumes.user)?;
+    let excluded_args = exclude_args(workspace, &project_state.excluded_paths);
+    let extra_root_args = extra_workspace_args(&opts.extra_workspaces);
+    let mut context_args = session_context_args(
+        config,
+        &session_id,
+        &opts.extra_workspaces,
+        &crate::claude_md::sections(rt, config, workspace, &session_id, &global.claude_md),
+    )?;
+    context_args.extend(crate::banner::mount_args(
+        config,
+        &global,
+        &session_id,
+        &crate::banner::Info {
+            workspace,
+            container: &container_name,
+            title: opts.title.as_deref(),
+            notify: &global.notify,
+            network: &global.network,
+            detach_keys: opts.detach_keys.as_deref(),
+            host_gateway: rt.host_gateway(),
+        },
+    )?);
+    context_args.extend(crate::browser::mount_args(config, &global)?);
+    let user_mount_args = build_mount_args(
+        &config.home_dir,
+        &volumes.dir(),
+        &[global.mounts.as_slice(), project_state.mounts.as_slice()].concat(),
+    )?;
+    let cache_args = cache_mount_args(rt, image, &enabled_caches(&global), &volumes.user)?;
+    let hardening_args =
+        security_args(&global.security, &config.home_dir, project_state.relax_security);
+    let userns_args = userns_args(rt, global.userns, image, &volumes.user);
+    let session = SessionCommand::resolve(opts, &project_state.launch, &global.launch);
+    let mut env_args = host_env_args_from_env(true);
+    env_args.extend(crate::direnv::env_args(&global.direnv, workspace));
+    env_args.extend(crate::server::budget::env_args(
+        &global.budget,
+        &rt.server_url(),
+        project_id,
+        &session_id,
+        api_key,
+    ));
+    let proxy_args = egress_proxy_args(rt, &global.network, project_id, &session_id, api_key);
+    let resolver = resolver_args(&global.network);
 
     // Create the per-workspace service network up front and attach the main
     // container to it at launch. Lazy attach via `podman network connect` after
@@ -707,8 +2138,35 @@ pub fn launch_container(
     // runtime without restarting the session.
     let service_net = crate::service::ensure_service_network(rt, workspace)?;
 
+    let setup_mounts = [
+        volumes.user.run_args(),
+        volumes.mount_args(),
+        vec!["-v".to_string(), format!("{}:/app:Z", workspace_str)],
+        user_mount_args.clone(),
+        cache_args.clone(),
+        hardening_args.clone(),
+        userns_args.clone(),
+        mask_args.clone(),
+        excluded_args.clone(),
+    ]
+    .concat();
+    run_post_create(rt, config, workspace, image, &setup_mounts)?;
+
+    // With the startup probe the container runs detached and is attached to
+    // once it's ready; it's kept after an early exit so its logs can be shown.
+    // A standby is only created here, to be started by a later launch.
+    let probe = global.healthcheck.enabled() && !rt.dry_run;
     let mut run_cmd = rt.command();
-    run_cmd.args(["run", "--rm", "-it"]);
+    match (opts.warm, probe) {
+        (false, true) => run_cmd.args(["run", "-d", "-it"]),
+        (false, false) => run_cmd.args(["run", "--rm", "-it"]),
+        (true, true) => run_cmd.args(["create", "-it"]),
+        (true, false) => run_cmd.args(["create", "--rm", "-it"]),
+    };
+    if probe {
+        run_cmd.args(crate::healthcheck::run_args(&global.healthcheck));
+    }
+    run_cmd.args(rt.detach_keys_args(opts.detach_keys.as_deref()));
     run_cmd.args([
         "--name",
         &container_name,
@@ -716,17 +2174,45 @@ pub fn launch_container(
         "managed-by=ai-pod",
         "--network",
         &service_net,
-        "-v",
-        &format!("{}:{}:z", volume_name, CONTAINER_HOME),
-        "-v",
-        &format!("{}:/app:Z", workspace_str),
     ]);
+    run_cmd.args(crate::collision::label_args(workspace));
+    run_cmd.args(crate::session_title::label_args(opts.title.as_deref()));
+    run_cmd.args(volumes.user.run_args());
+    run_cmd.args(volumes.mount_args());
+    run_cmd.args(["-v", &app_mount(workspace, &global, &project_state)]);
     for arg in &user_mount_args {
         run_cmd.arg(arg);
     }
+    for arg in &cache_args {
+        run_cmd.arg(arg);
+    }
+    for arg in &hardening_args {
+        run_cmd.arg(arg);
+    }
+    for arg in &userns_args {
+        run_cmd.arg(arg);
+    }
     for arg in &mask_args {
         run_cmd.arg(arg);
     }
+    for arg in &excluded_args {
+        run_cmd.arg(arg);
+    }
+    for arg in &extra_root_args {
+        run_cmd.arg(arg);
+    }
+    for arg in &context_args {
+        run_cmd.arg(arg);
+    }
+    for arg in &env_args {
+        run_cmd.arg(arg);
+    }
+    for arg in &proxy_args {
+        run_cmd.arg(arg);
+    }
+    for arg in &resolver {
+        run_cmd.arg(arg);
+    }
     run_cmd.args([
         &add_host,
         "-e",
@@ -740,26 +2226,216 @@ pub fn launch_container(
         "-e",
         &server_url_env,
         "-e",
+        &histfile_env,
+        "-e",
         &opencode_config_env,
     ]);
+    run_cmd.args(session.entrypoint_args());
     run_cmd.arg(image);
-    let run_status = run_cmd
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status()
-        .context("Failed to run container")?;
+    run_cmd.args(session.command_args());
+    if opts.warm {
+        return crate::warm::create(rt, config, workspace, image, run_cmd, &session_id, &container_name);
+    }
+    if standby.is_some() {
+        // The standby was created from these same arguments (its fingerprint
+        // still matches); only the session files written above are new.
+        run_cmd = rt.command();
+        if probe {
+            run_cmd.arg("start");
+        } else {
+            run_cmd.args(["start", "--attach", "--interactive"]);
+            run_cmd.args(rt.detach_keys_args(opts.detach_keys.as_deref()));
+        }
+        run_cmd.arg(&container_name);
+    }
+    let sync = start_sync(rt, workspace, image, &global, &project_state, &volumes, &userns_args)?;
+    let title = TitleGuard::set(
+        global.terminal_title.unwrap_or(true),
+        &project_display_name(workspace),
+        "running",
+    );
+    drop(setup_lock);
+    let watch = opts.watch_dockerfile.filter(|_| !rt.dry_run).map(|mode| {
+        DockerfileWatch::start(
+            rt,
+            &config.config_dir,
+            &workspace.join(crate::image::DOCKERFILE_NAME),
+            &session_id,
+            &container_name,
+            mode,
+        )
+    });
+    let ports = if rt.dry_run {
+        None
+    } else {
+        crate::ports::PortWatch::start(rt, &config.config_dir, workspace, &container_name, &global.ports)
+    };
+    let run_status = if probe {
+        crate::healthcheck::start_and_attach(
+            rt,
+            run_cmd,
+            &container_name,
+            &global.healthcheck,
+            opts.detach_keys.as_deref(),
+        )
+    } else {
+        run_cmd
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+            .context("Failed to run container")
+    };
+    drop(title);
+    if let Some(ports) = ports {
+        ports.finish();
+    }
+    let run_status = match run_status {
+        Ok(status) => status,
+        Err(e) => {
+            crate::service::cleanup_services_for_session(rt, &session_id);
+            let _ = std::fs::remove_file(config.session_state_file(&session_id));
+            let _ = std::fs::remove_file(config.session_context_file(&session_id));
+            let _ = std::fs::remove_file(config.session_banner_file(&session_id));
+            if opts.new_instance.is_some() {
+                volumes.remove(rt);
+            }
+            if let Some(sync) = sync {
+                sync.finish();
+            }
+            return Err(e);
+        }
+    };
 
     // Main container has exited (cleanly or otherwise); tear down anything the
     // agent started for this session. Best-effort: this is also covered by the
     // server's periodic orphan sweep if the CLI was killed.
+    //
+    // A checkpointed session only looks like it exited: `ai-pod resume`
+    // brings it back and needs its state (and its services) intact.
+    if !rt.dry_run && crate::checkpoint::is_checkpointed(config, &session_id) {
+        eprintln!(
+            "{} session checkpointed; continue it with `ai-pod resume`.",
+            "Note:".yellow().bold()
+        );
+        return Ok(false);
+    }
     crate::service::cleanup_services_for_session(rt, &session_id);
     let _ = std::fs::remove_file(config.session_state_file(&session_id));
-    let _ = run_status;
+    let _ = std::fs::remove_file(config.session_context_file(&session_id));
+    let _ = std::fs::remove_file(config.session_banner_file(&session_id));
+    if opts.new_instance.is_some() {
+        volumes.remove(rt);
+    }
+    if let Some(sync) = sync {
+        sync.finish();
+    }
 
-    Ok(())
+    if !rt.dry_run {
+        hook_ctx.exit_code = run_status.code();
+        record_stop(config, &hook_ctx);
+        hooks::run_post_exit(&global.hooks, &hook_ctx);
+        if crate::editor::changed_files(workspace).is_ok_and(|f| !f.is_empty()) {
+            eprintln!(
+                "{} the workspace has uncommitted changes; review them with `ai-pod review`.",
+                "Note:".yellow().bold()
+            );
+        }
+    }
+
+    Ok(watch.is_some_and(DockerfileWatch::finish))
+}
+
+/// Keep shell history in the state volume, so it outlives the home volume.
+fn histfile_env(home: &str) -> String {
+    format!("HISTFILE={}/.claude/shell_history", home)
+}
+
+/// The `-v` value for /app: the workspace itself, or its sync volume when
+/// `sync.enabled` is on (see [`crate::sync`]).
+fn app_mount(workspace: &Path, global: &GlobalConfig, project: &ProjectState) -> String {
+    if crate::sync::enabled(&global.sync, &project.sync) {
+        crate::sync::mount_arg(workspace)
+    } else {
+        format!("{}:/app:Z", workspace.display())
+    }
+}
+
+/// Start syncing the workspace when `sync.enabled` is on; the session
+/// containers run as `volumes.user` with `userns_args`.
+fn start_sync<'a>(
+    rt: &'a ContainerRuntime,
+    workspace: &'a Path,
+    image: &str,
+    global: &GlobalConfig,
+    project: &ProjectState,
+    volumes: &HomeVolumes,
+    userns_args: &[String],
+) -> Result<Option<crate::sync::SyncSession<'a>>> {
+    if !crate::sync::enabled(&global.sync, &project.sync) {
+        return Ok(None);
+    }
+    let ignores = crate::sync::ignores(&global.sync, &project.sync, &project.masked_directories, &project.excluded_paths);
+    let container_args = [volumes.user.run_args(), volumes.mount_args(), userns_args.to_vec()].concat();
+    crate::sync::start(rt, workspace, image, &volumes.user.owner(), &container_args, &ignores).map(Some)
+}
+
+/// Whether `name` is a session container (`<prefix>-<session id>`). Helper
+/// containers (`-init`, `-mcp`, services) share the prefix but not the shape.
+fn is_session_container(prefix: &str, name: &str) -> bool {
+    name.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .is_some_and(|id| id.len() == 8 && id.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The workspace's running session container, if any.
+pub(crate) fn running_session(rt: &ContainerRuntime, workspace: &Path) -> Result<Option<String>> {
+    let prefix = container_prefix(workspace);
+    Ok(containers_for_prefix(rt, &prefix, true)?
+        .into_iter()
+        .find(|name| is_session_container(&prefix, name)))
 }
 
+/// `exec` `command` in a running session container. It already has the
+/// session's mounts and environment; only the host's terminal settings are
+/// passed along.
+fn exec_in_session(
+    rt: &ContainerRuntime,
+    container: &str,
+    command: &str,
+    args: &[String],
+    interactive: bool,
+    detach_keys: Option<&str>,
+    run_opts: &RunOptions,
+) -> Result<std::process::ExitStatus> {
+    eprintln!(
+        "{} {} {}",
+        "Running in session container:".blue().bold(),
+        container,
+        command
+    );
+    let mut cmd = rt.command();
+    cmd.arg("exec").arg(if interactive { "-it" } else { "-i" });
+    if interactive {
+        cmd.args(rt.detach_keys_args(detach_keys));
+    }
+    cmd.args(host_env_args_from_env(interactive))
+        .args(run_opts.args())
+        .arg(container)
+        .arg(command)
+        .args(args);
+    cmd.stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .context("Failed to exec in session container")
+}
+
+/// Run `command` and return its exit status: inside the workspace's running
+/// session when there is one (a second container would contend for the home
+/// volume), otherwise in a one-off container. `interactive` allocates a
+/// pseudo-TTY (and enables detach keys); pass it only when both stdin and
+/// stdout are terminals.
 pub fn run_in_container(
     rt: &ContainerRuntime,
     config: &AppConfig,
@@ -770,35 +2446,73 @@ pub fn run_in_container(
     command: &str,
     args: &[String],
     interactive: bool,
-) -> Result<()> {
+    opts: &LaunchOptions,
+    run_opts: &RunOptions,
+) -> Result<std::process::ExitStatus> {
+    if let Some(container) = running_session(rt, workspace)? {
+        crate::collision::check_container(rt, &container, workspace)?;
+        let _title = TitleGuard::set(
+            GlobalConfig::load(config).terminal_title.unwrap_or(true) && interactive,
+            &project_display_name(workspace),
+            command,
+        );
+        return exec_in_session(
+            rt,
+            &container,
+            command,
+            args,
+            interactive,
+            opts.detach_keys.as_deref(),
+            run_opts,
+        );
+    }
+
     let session_id = new_session_id();
     let container_name = container_name_for(workspace, &session_id);
-    let volume_name = gen_volume_name(workspace);
     let workspace_str = workspace.to_string_lossy();
+    let global = GlobalConfig::load(config);
+
+    rt.warn_if_rootless_userns_mismatch(global.userns);
+    crate::vm::check_workspace(rt, config, workspace);
 
-    rt.warn_if_rootless_userns_mismatch();
+    let mut hook_ctx = HookContext {
+        workspace,
+        project_id,
+        session_id: &session_id,
+        container_name: &container_name,
+        image,
+        exit_code: None,
+    };
+    if !rt.dry_run {
+        hooks::run_pre_launch(&global.hooks, &hook_ctx)?;
+        let mut event = Event::new(EventKind::Launch, Some(workspace))
+            .session(&session_id, &container_name)
+            .image(image);
+        event.title = opts.title.clone();
+        plugins::run_blocking(&config.config_dir, plugins::Hook::PreLaunch, &event)?;
+        events::record(&config.config_dir, &event);
+    }
+    // Taken after the hooks, which may run ai-pod for this workspace.
+    let setup_lock = crate::launch_lock::acquire(config, workspace)?;
+    remove_stale_init_containers(rt, workspace);
 
     // Record the runtime for this session before the container starts, so the
     // shared server runs service containers on the same runtime.
-    crate::config::SessionState { runtime: rt.kind }.save(config, &session_id)?;
-
-    // Init home volume if it doesn't exist
-    if !volume_exists(rt, &volume_name)? {
-        init_home_volume(
-            rt,
-            config,
-            &volume_name,
-            &container_name,
-            image,
-            project_id,
-            api_key,
-        )?;
+    crate::config::SessionState {
+        runtime: rt.kind,
+        tmux: crate::tmux::current_target(),
+        title: opts.title.clone(),
+        workspace: Some(workspace.to_path_buf()),
     }
+    .save(config, &session_id)?;
+
+    // Init home volume if it doesn't exist (or never finished initialising)
+    let volumes = ensure_home_volume(rt, config, workspace, image)?;
 
     refresh_claude_mcp_in_volume(
         rt,
         config,
-        &volume_name,
+        &volumes,
         &container_name,
         image,
         &rt.server_url(),
@@ -814,36 +2528,104 @@ pub fn run_in_container(
     );
 
     let project_state = load_project_state(config, workspace);
-    let mask_args = mask_mount_args(rt, workspace, image, &project_state.masked_directories)?;
-    let global = GlobalConfig::load(config);
-    let user_mount_args = build_mount_args(&config.home_dir, &global.mounts)?;
+    let mask_args = mask_mount_args(rt, workspace, image, &project_state.masked_directories, &volumes.user)?;
+    let excluded_args = exclude_args(workspace, &project_state.excluded_paths);
+    let extra_root_args = extra_workspace_args(&opts.extra_workspaces);
+    let mut context_args = session_context_args(
+        config,
+        &session_id,
+        &opts.extra_workspaces,
+        &crate::claude_md::sections(rt, config, workspace, &session_id, &global.claude_md),
+    )?;
+    context_args.extend(crate::banner::mount_args(
+        config,
+        &global,
+        &session_id,
+        &crate::banner::Info {
+            workspace,
+            container: &container_name,
+            title: opts.title.as_deref(),
+            notify: &global.notify,
+            network: &global.network,
+            detach_keys: opts.detach_keys.as_deref(),
+            host_gateway: rt.host_gateway(),
+        },
+    )?);
+    context_args.extend(crate::browser::mount_args(config, &global)?);
+    let user_mount_args = build_mount_args(
+        &config.home_dir,
+        &volumes.dir(),
+        &[global.mounts.as_slice(), project_state.mounts.as_slice()].concat(),
+    )?;
+    let cache_args = cache_mount_args(rt, image, &enabled_caches(&global), &volumes.user)?;
+    let hardening_args =
+        security_args(&global.security, &config.home_dir, project_state.relax_security);
+    let userns_args = userns_args(rt, global.userns, image, &volumes.user);
+    let mut env_args = host_env_args_from_env(interactive);
+    env_args.extend(crate::direnv::env_args(&global.direnv, workspace));
 
     // See the matching comment in launch_container — main goes on the
     // per-workspace service network at launch so service containers can be
     // attached later on rootless podman.
     let service_net = crate::service::ensure_service_network(rt, workspace)?;
 
+    let setup_mounts = [
+        volumes.user.run_args(),
+        volumes.mount_args(),
+        vec!["-v".to_string(), format!("{}:/app:Z", workspace_str)],
+        user_mount_args.clone(),
+        cache_args.clone(),
+        hardening_args.clone(),
+        userns_args.clone(),
+        mask_args.clone(),
+        excluded_args.clone(),
+    ]
+    .concat();
+    run_post_create(rt, config, workspace, image, &setup_mounts)?;
+
     // Without a tty on stdin (e.g. an IDE driving ai-pod over stdio for
-    // ACP), `-t` would allocate a pseudo-TTY that mangles the JSON-RPC
-    // byte stream the agent emits. Keep `-i` so stdin stays attached.
+    // ACP) or stdout (a pipeline), `-t` would allocate a pseudo-TTY that
+    // mangles the byte stream. Keep `-i` so stdin stays attached.
     let stdio_flag = if interactive { "-it" } else { "-i" };
     let mut run_args: Vec<String> = vec![
         "run".into(),
         "--rm".into(),
         stdio_flag.into(),
     ];
+    if interactive {
+        run_args.extend(rt.detach_keys_args(opts.detach_keys.as_deref()));
+    }
     run_args.extend_from_slice(&[
         "--label".into(),
         "managed-by=ai-pod".into(),
         "--network".into(),
         service_net,
-        "-v".into(),
-        format!("{}:{}:z", volume_name, CONTAINER_HOME),
-        "-v".into(),
-        format!("{}:/app:Z", workspace_str),
     ]);
+    run_args.extend(crate::collision::label_args(workspace));
+    run_args.extend(crate::session_title::label_args(opts.title.as_deref()));
+    run_args.extend(volumes.user.run_args());
+    run_args.extend(volumes.mount_args());
+    run_args.extend_from_slice(&["-v".into(), app_mount(workspace, &global, &project_state)]);
     run_args.extend(user_mount_args);
+    run_args.extend(cache_args);
+    run_args.extend(hardening_args);
+    let sync_userns_args = userns_args.clone();
+    run_args.extend(userns_args);
     run_args.extend(mask_args);
+    run_args.extend(excluded_args);
+    run_args.extend(extra_root_args);
+    run_args.extend(context_args);
+    run_args.extend(env_args);
+    run_args.extend(run_opts.args());
+    run_args.extend(egress_proxy_args(rt, &global.network, project_id, &session_id, api_key));
+    run_args.extend(crate::server::budget::env_args(
+        &global.budget,
+        &rt.server_url(),
+        project_id,
+        &session_id,
+        api_key,
+    ));
+    run_args.extend(resolver_args(&global.network));
     run_args.extend_from_slice(&[
         rt.add_host_arg(),
         "-e".into(),
@@ -857,6 +2639,8 @@ pub fn run_in_container(
         "-e".into(),
         format!("AI_POD_SERVER_URL={}", rt.server_url()),
         "-e".into(),
+        histfile_env(&volumes.dir()),
+        "-e".into(),
         format!(
             "OPENCODE_CONFIG_CONTENT={}",
             opencode_config_content(&rt.server_url(), api_key, &session_id)
@@ -867,6 +2651,13 @@ pub fn run_in_container(
     ]);
     run_args.extend_from_slice(args);
 
+    let sync = start_sync(rt, workspace, image, &global, &project_state, &volumes, &sync_userns_args)?;
+    drop(setup_lock);
+    let title = TitleGuard::set(
+        global.terminal_title.unwrap_or(true) && interactive,
+        &project_display_name(workspace),
+        command,
+    );
     let status = rt
         .command()
         .args(&run_args)
@@ -874,19 +2665,38 @@ pub fn run_in_container(
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .status()
-        .context("Failed to run command in container")?;
+        .context("Failed to run command in container");
+    drop(title);
+    if let Some(sync) = sync {
+        sync.finish();
+    }
+    let status = status?;
 
     crate::service::cleanup_services_for_session(rt, &session_id);
     let _ = std::fs::remove_file(config.session_state_file(&session_id));
+    let _ = std::fs::remove_file(config.session_context_file(&session_id));
+    let _ = std::fs::remove_file(config.session_banner_file(&session_id));
 
-    if !status.success() {
-        anyhow::bail!("Command exited with non-zero status");
+    if !rt.dry_run {
+        hook_ctx.exit_code = status.code();
+        record_stop(config, &hook_ctx);
+        hooks::run_post_exit(&global.hooks, &hook_ctx);
     }
 
-    Ok(())
+    Ok(status)
 }
 
-pub fn list_containers(rt: &ContainerRuntime) -> Result<()> {
+/// Shell-style exit code for a child's status: its own code, or 128 plus the
+/// signal number when it was killed by a signal.
+pub fn exit_code(status: std::process::ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    status
+        .code()
+        .or_else(|| status.signal().map(|s| 128 + s))
+        .unwrap_or(1)
+}
+
+pub fn list_containers(rt: &ContainerRuntime, config: &AppConfig) -> Result<()> {
     let output = rt
         .command()
         .args([
@@ -904,15 +2714,49 @@ pub fn list_containers(rt: &ContainerRuntime) -> Result<()> {
         println!("{}", "No ai-pod containers found.".yellow());
     } else {
         println!("{}", "ai-pod containers:".blue().bold());
-        println!("{:<20} {:<30} {}", "NAME", "STATUS", "CREATED");
-        println!("{}", "-".repeat(80));
-        print!("{}", String::from_utf8_lossy(&output.stdout));
+        println!("{:<32} {:<24} {:<32} {}", "NAME", "STATUS", "CREATED", "TITLE");
+        println!("{}", "-".repeat(100));
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (name, status, created) = (
+                fields.next().unwrap_or_default(),
+                fields.next().unwrap_or_default(),
+                fields.next().unwrap_or_default(),
+            );
+            // Session containers are `ai-pod-<project>-<session>`.
+            let title = name
+                .rsplit_once('-')
+                .and_then(|(_, sid)| crate::config::SessionState::load_from_dir(&config.config_dir, sid))
+                .and_then(|s| s.title)
+                .unwrap_or_default();
+            println!("{:<32} {:<24} {:<32} {}", name, status, created, title);
+        }
     }
 
     Ok(())
 }
 
-pub fn attach_container(rt: &ContainerRuntime) -> Result<()> {
+/// The workspace's directory name, as shown in the terminal title.
+pub(crate) fn project_display_name(workspace: &Path) -> String {
+    workspace
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| workspace.display().to_string())
+}
+
+pub(crate) fn record_stop(config: &AppConfig, ctx: &HookContext<'_>) {
+    let mut event = Event::new(EventKind::Stop, Some(ctx.workspace))
+        .session(ctx.session_id, ctx.container_name)
+        .image(ctx.image);
+    event.exit_code = ctx.exit_code;
+    events::record(&config.config_dir, &event);
+}
+
+pub fn attach_container(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    detach_keys: Option<&str>,
+) -> Result<()> {
     // List all running ai-pod containers with their start times
     let output = rt
         .command()
@@ -958,61 +2802,209 @@ pub fn attach_container(rt: &ContainerRuntime) -> Result<()> {
         entries[selection].0.clone()
     };
 
+    attach_to(rt, config, &container_name, detach_keys)
+}
+
+/// Attach the terminal to a running session container until it exits or the
+/// user detaches.
+pub fn attach_to(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    container_name: &str,
+    detach_keys: Option<&str>,
+) -> Result<()> {
+    let container_name = container_name.to_string();
     println!("{} {}", "Attaching to:".green(), container_name);
-    rt.command()
-        .args(["attach", "--detach-keys=ctrl-p,ctrl-q", &container_name])
+    if !rt.dry_run {
+        // `ai-pod-{project_id}-{session_id}`; the workspace path itself isn't
+        // recoverable from the name.
+        let mut event = Event::new(EventKind::Attach, None);
+        event.session_id = session_id_from_container_name(&container_name);
+        event.project_id = crate::workspace::project_id_from_container_name(&container_name);
+        event.container = Some(container_name.clone());
+        events::record(&config.config_dir, &event);
+    }
+    // The workspace is only recoverable through the known-workspace list.
+    let project = crate::workspace::project_id_from_container_name(&container_name)
+        .and_then(|id| {
+            crate::registry::known_workspaces(config)
+                .into_iter()
+                .find(|ws| crate::workspace::workspace_hash(ws) == id)
+        })
+        .map(|ws| project_display_name(&ws))
+        .unwrap_or_else(|| container_name.clone());
+    let _title = TitleGuard::set(
+        GlobalConfig::load(config).terminal_title.unwrap_or(true),
+        &project,
+        "attached",
+    );
+    attach_child(rt, &container_name, detach_keys)?;
+    Ok(())
+}
+
+/// Attach the terminal to `container_name` and wait until it exits or the
+/// user detaches.
+pub(crate) fn attach_child(
+    rt: &ContainerRuntime,
+    container_name: &str,
+    detach_keys: Option<&str>,
+) -> Result<std::process::ExitStatus> {
+    let mut child = rt
+        .command()
+        .arg("attach")
+        .args(rt.detach_keys_args(detach_keys))
+        .arg(container_name)
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
-        .status()
+        .spawn()
         .context("Failed to attach to container")?;
 
+    // The container's pty keeps the size of whichever terminal last drove it,
+    // and the attach client only pushes a resize on SIGWINCH. Nudge it once
+    // it has connected so TUIs redraw at this terminal's size straight away.
+    if crate::is_stdin_tty() {
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        // Safety: signalling our own child by pid; a stale pid is harmless
+        // since we still hold the un-reaped child.
+        unsafe {
+            libc::kill(child.id() as libc::pid_t, libc::SIGWINCH);
+        }
+    }
+    child.wait().context("Failed to attach to container")
+}
+
+/// `ai-pod clean --state-only`: remove just the state volume (sign-in,
+/// sessions, shell history). Containers, the home volume and the image stay.
+pub fn clean_state(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path) -> Result<()> {
+    let volume = state_volume_name(workspace);
+    if !volume_exists(rt, &volume)? {
+        println!("{}", "No state volume found for this workspace.".yellow());
+        return Ok(());
+    }
+    if running_session(rt, workspace)?.is_some() {
+        anyhow::bail!(
+            "A session of this workspace is running and uses {}; end it first",
+            volume
+        );
+    }
+    println!("{} {}", "Removing volume:".red().bold(), volume);
+    remove_volume(rt, &volume)?;
+    if !rt.dry_run {
+        events::record(&config.config_dir, &Event::new(EventKind::Clean, Some(workspace)));
+    }
     Ok(())
 }
 
+/// What `ai-pod clean` removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CleanOptions {
+    /// Containers and the service network (off with `--volume-only`).
+    pub containers: bool,
+    /// Home, state, instance, mask and sync volumes (off with `--keep-volume`).
+    pub volumes: bool,
+    /// The workspace's image (`--image`).
+    pub image: bool,
+}
+
 pub fn clean_container(
     rt: &ContainerRuntime,
     config: &AppConfig,
     workspace: &Path,
+    opts: CleanOptions,
 ) -> Result<()> {
     let prefix = container_prefix(workspace);
     let volume_name = gen_volume_name(workspace);
 
-    let containers = containers_for_prefix(rt, &prefix, false)?;
-
-    if containers.is_empty() {
-        println!("{}", "No containers found for this workspace.".yellow());
-    } else {
-        for name in &containers {
-            println!("{} {}", "Removing container:".red().bold(), name);
-            let _ = rt.command().args(["rm", "--force", name]).status();
+    if opts.containers {
+        // Stop syncing before the sync container goes.
+        crate::sync::stop(rt, workspace);
+        let containers = containers_for_prefix(rt, &prefix, false)?;
+        if containers.is_empty() {
+            println!("{}", "No containers found for this workspace.".yellow());
+        } else {
+            for name in &containers {
+                println!("{} {}", "Removing container:".red().bold(), name);
+                let _ = rt.command().args(["rm", "--force", name]).status();
+            }
+            println!("{}", "Containers removed.".green());
         }
-        println!("{}", "Containers removed.".green());
+    } else if opts.volumes && running_session(rt, workspace)?.is_some() {
+        anyhow::bail!(
+            "A session of this workspace is running and uses its volumes; end it first, or drop --volume-only"
+        );
     }
 
-    // Remove named home volume
-    if volume_exists(rt, &volume_name)? {
-        println!("{} {}", "Removing volume:".red().bold(), volume_name);
-        let status = rt
-            .command()
-            .args(["volume", "rm", &volume_name])
-            .status()
-            .context("Failed to remove volume")?;
-        if status.success() {
-            println!("{}", "Volume removed.".green());
+    if opts.volumes {
+        // Remove named home volume
+        if volume_exists(rt, &volume_name)? {
+            println!("{} {}", "Removing volume:".red().bold(), volume_name);
+            let status = rt
+                .command()
+                .args(["volume", "rm", &volume_name])
+                .status()
+                .context("Failed to remove volume")?;
+            if status.success() {
+                println!("{}", "Volume removed.".green());
+            }
+        }
+
+        // The state volume, and the volumes of `--new` instances that didn't
+        // get to clean up.
+        let state_volume = state_volume_name(workspace);
+        let instance_prefixes = [format!("{}-", volume_name), format!("{}-", state_volume)];
+        for volume in crate::du::list_volumes(rt)? {
+            if volume == state_volume || instance_prefixes.iter().any(|p| volume.starts_with(p)) {
+                println!("{} {}", "Removing volume:".red().bold(), volume);
+                let _ = remove_volume(rt, &volume);
+            }
+        }
+
+        // Remove per-mask volumes recorded in this workspace's state. Config (the
+        // list of masked dirs) is preserved so the volumes are re-created on next
+        // launch — `clean` resets runtime state, not user config.
+        let state = load_project_state(config, workspace);
+        for dir in &state.masked_directories {
+            let _ = remove_mask_volume(rt, workspace, dir);
+        }
+        let sync_volume = sync_volume_name(workspace);
+        if volume_exists(rt, &sync_volume)? {
+            println!("{} {}", "Removing volume:".red().bold(), sync_volume);
+            let _ = remove_volume(rt, &sync_volume);
         }
+    } else {
+        println!("{}", "Keeping the home and state volumes.".dimmed());
     }
 
-    // Remove per-mask volumes recorded in this workspace's state. Config (the
-    // list of masked dirs) is preserved so the volumes are re-created on next
-    // launch — `clean` resets runtime state, not user config.
-    let state = load_project_state(config, workspace);
-    for dir in &state.masked_directories {
-        let _ = remove_mask_volume(rt, workspace, dir);
+    if opts.containers {
+        // Remove the per-workspace service-container network if it exists.
+        crate::service::remove_service_network(rt, workspace);
+    }
+
+    if opts.image {
+        let image = crate::image::image_name(workspace);
+        if crate::image::image_exists(rt, &image)? {
+            println!("{} {}", "Removing image:".red().bold(), image);
+            let status = rt
+                .command()
+                .args(["rmi", &image])
+                .status()
+                .context("Failed to remove image")?;
+            if !status.success() {
+                eprintln!(
+                    "{} could not remove {}; is a container still using it?",
+                    "warning:".yellow().bold(),
+                    image
+                );
+            }
+        } else {
+            println!("{}", "No image found for this workspace.".yellow());
+        }
     }
 
-    // Remove the per-workspace service-container network if it exists.
-    crate::service::remove_service_network(rt, workspace);
+    if !rt.dry_run {
+        events::record(&config.config_dir, &Event::new(EventKind::Clean, Some(workspace)));
+    }
 
     Ok(())
 }
@@ -1020,24 +3012,180 @@ pub fn clean_container(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::test_config;
+
+    #[test]
+    fn dotfiles_args_pass_settings_as_env() {
+        let d = crate::config::DotfilesConfig {
+            repository: "octo/dots".into(),
+            install_command: Some("make install".into()),
+            target_path: "~/.dotfiles".into(),
+        };
+        let volumes = HomeVolumes {
+            home: "ai-pod-abc-home".into(),
+            state: "ai-pod-abc-state".into(),
+            user: UserConfig {
+                name: Some("node".into()),
+                home: None,
+            },
+        };
+        let args = dotfiles_args(&d, &volumes, "img");
+        assert!(args.contains(&"DOTFILES_REPO=https://github.com/octo/dots.git".to_string()));
+        assert!(args.contains(&"DOTFILES_TARGET=~/.dotfiles".to_string()));
+        assert!(args.contains(&"DOTFILES_INSTALL=make install".to_string()));
+        assert!(args.contains(&"ai-pod-abc-home:/home/node:z".to_string()));
+        assert!(args.contains(&"ai-pod-abc-state:/home/node/.claude:z".to_string()));
+        assert!(args.contains(&"--user=node".to_string()));
+        assert!(args.contains(&"HOME=/home/node".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some(DOTFILES_SCRIPT));
+    }
+
+    #[test]
+    fn home_volumes_mount_state_over_claude_dir() {
+        let p = Path::new("/home/user/myproject");
+        let shared = HomeVolumes::for_workspace(p);
+        assert_eq!(shared.home, gen_volume_name(p));
+        assert_eq!(shared.state, state_volume_name(p));
+        assert_eq!(
+            shared.mount_args(),
+            vec![
+                "-v".to_string(),
+                format!("{}:/home/ai-pod:z", shared.home),
+                "-v".to_string(),
+                format!("{}:/home/ai-pod/.claude:z", shared.state),
+            ]
+        );
+        let instance = HomeVolumes::for_instance(p, "3ba2896f");
+        assert_eq!(instance.home, format!("{}-3ba2896f", shared.home));
+        assert_eq!(instance.state, format!("{}-3ba2896f", shared.state));
+    }
+
+    #[test]
+    fn host_env_args_forwards_set_vars_and_derives_tz() {
+        let get = |k: &str| match k {
+            "LANG" => Some("en_US.UTF-8".to_string()),
+            "TERM" => Some("xterm-256color".to_string()),
+            "COLORTERM" => Some(String::new()),
+            _ => None,
+        };
+        let localtime = Some(PathBuf::from("/usr/share/zoneinfo/Europe/Berlin"));
+        assert_eq!(
+            host_env_args(get, localtime.clone(), true),
+            vec![
+                "-e",
+                "TZ=Europe/Berlin",
+                "-e",
+                "LANG=en_US.UTF-8",
+                "-e",
+                "TERM=xterm-256color",
+            ]
+        );
+        // No tty: terminal vars are dropped.
+        assert_eq!(
+            host_env_args(get, localtime, false),
+            vec!["-e", "TZ=Europe/Berlin", "-e", "LANG=en_US.UTF-8"]
+        );
+    }
+
+    #[test]
+    fn host_env_args_prefers_explicit_tz() {
+        let get = |k: &str| (k == "TZ").then(|| "UTC".to_string());
+        let localtime = Some(PathBuf::from("/usr/share/zoneinfo/Europe/Berlin"));
+        assert_eq!(host_env_args(get, localtime, false), vec!["-e", "TZ=UTC"]);
+    }
+
+    #[test]
+    fn home_volume_pending_round_trips_and_keeps_other_state() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let ws = Path::new("/src/api");
+        let path = config.project_state_file(&workspace_hash(ws));
+        let mut state = ProjectState::load(&path);
+        state.post_create_done = Some("abc".into());
+        state.save(&path).unwrap();
+
+        set_home_volume_pending(&config, ws, Some("ai-pod-x-home")).unwrap();
+        let state = load_project_state(&config, ws);
+        assert_eq!(state.home_volume_pending.as_deref(), Some("ai-pod-x-home"));
+        assert_eq!(state.post_create_done.as_deref(), Some("abc"));
+
+        set_home_volume_pending(&config, ws, None).unwrap();
+        assert!(load_project_state(&config, ws).home_volume_pending.is_none());
+    }
+
+    #[test]
+    fn is_session_container_skips_helpers() {
+        let prefix = "ai-pod-0123456789ab";
+        assert!(is_session_container(prefix, "ai-pod-0123456789ab-3ba2896f"));
+        assert!(!is_session_container(prefix, "ai-pod-0123456789ab-3ba2896f-mcp"));
+        assert!(!is_session_container(prefix, "ai-pod-0123456789ab-init"));
+        assert!(!is_session_container(prefix, "ai-pod-ffffffffffff-3ba2896f"));
+    }
+
+    #[test]
+    fn is_init_container_matches_workspace_and_instance_seeding() {
+        let prefix = "ai-pod-0123456789ab";
+        assert!(is_init_container(prefix, "ai-pod-0123456789ab-init"));
+        assert!(is_init_container(prefix, "ai-pod-0123456789ab-3ba2896f-init"));
+        assert!(!is_init_container(prefix, "ai-pod-0123456789ab-3ba2896f"));
+        assert!(!is_init_container(prefix, "ai-pod-0123456789ab-3ba2896f-mcp"));
+        assert!(!is_init_container(prefix, "ai-pod-0123456789ab-db-init"));
+        assert!(!is_init_container(prefix, "ai-pod-ffffffffffff-init"));
+    }
+
+    #[test]
+    fn exit_code_passes_codes_and_maps_signals() {
+        use std::os::unix::process::ExitStatusExt;
+        assert_eq!(exit_code(std::process::ExitStatus::from_raw(0)), 0);
+        assert_eq!(exit_code(std::process::ExitStatus::from_raw(2 << 8)), 2);
+        // Killed by SIGINT (2).
+        assert_eq!(exit_code(std::process::ExitStatus::from_raw(2)), 130);
+    }
+
+    #[test]
+    fn zone_from_localtime_handles_linux_and_macos() {
+        assert_eq!(
+            zone_from_localtime(Path::new("/usr/share/zoneinfo/America/New_York")).as_deref(),
+            Some("America/New_York")
+        );
+        assert_eq!(
+            zone_from_localtime(Path::new("/var/db/timezone/zoneinfo/Asia/Tokyo")).as_deref(),
+            Some("Asia/Tokyo")
+        );
+        assert_eq!(zone_from_localtime(Path::new("/etc/some-file")), None);
+    }
     use tempfile::TempDir;
 
-    fn make_test_config(dir: &TempDir) -> AppConfig {
-        let home = dir.path().to_path_buf();
-        let config_dir = home.join(".ai-pod");
-        std::fs::create_dir_all(&config_dir).unwrap();
-        AppConfig {
-            runtime_settings: config_dir.join("runtime-settings.json"),
-            config_dir,
-            home_dir: home,
-        }
+    fn test_vars() -> TemplateVars<'static> {
+        let rt = ContainerRuntime {
+            kind: crate::runtime::RuntimeKind::Podman,
+            dry_run: true,
+        };
+        TemplateVars::new(&rt, Path::new("/home/u/proj"), None)
+    }
+
+    #[test]
+    fn runtime_settings_expand_placeholders() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        std::fs::create_dir_all(dir.path().join(".claude")).unwrap();
+        std::fs::write(
+            config.claude_settings_path(),
+            r#"{"env": {"PROJECT": "{{project}}", "NOTIFY": "{{notify_port}}"}}"#,
+        )
+        .unwrap();
+        generate_runtime_settings(&config, &test_vars()).unwrap();
+        let v: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&config.runtime_settings).unwrap()).unwrap();
+        assert_eq!(v["env"]["PROJECT"], "proj");
+        assert_eq!(v["env"]["NOTIFY"], "7822");
     }
 
     #[test]
     fn runtime_settings_contains_stop_hook() {
         let dir = TempDir::new().unwrap();
-        let config = make_test_config(&dir);
-        generate_runtime_settings(&config).unwrap();
+        let config = test_config(&dir);
+        generate_runtime_settings(&config, &test_vars()).unwrap();
 
         let content = std::fs::read_to_string(&config.runtime_settings).unwrap();
         let json: serde_json::Value = serde_json::from_str(&content).unwrap();
@@ -1045,29 +3193,99 @@ mod tests {
         let stop = &json["hooks"]["Stop"];
         assert!(stop.is_array(), "hooks.Stop should be an array");
         let cmd = stop[0]["hooks"][0]["command"].as_str().unwrap();
-        assert!(cmd.contains("notify_user"));
-        assert!(cmd.contains("$AI_POD_SERVER_URL"));
+        assert_eq!(cmd, "sh \"$HOME/.ai-pod/notify.sh\" stop 'Task completed'");
+        assert!(NOTIFY_HOOK_SH.contains("\"$AI_POD_SERVER_URL/notify_user\""));
     }
 
     #[test]
-    fn runtime_settings_stop_hook_uses_curl() {
+    fn runtime_settings_flush_hook_joins_existing_prompt_hooks() {
         let dir = TempDir::new().unwrap();
-        let config = make_test_config(&dir);
-        generate_runtime_settings(&config).unwrap();
+        let config = test_config(&dir);
+        std::fs::create_dir_all(config.home_dir.join(".claude")).unwrap();
+        let existing = serde_json::json!({"hooks": {"UserPromptSubmit": [{"hooks": [{"type": "command", "command": "mine"}]}]}});
+        std::fs::write(config.claude_settings_path(), existing.to_string()).unwrap();
+        generate_runtime_settings(&config, &test_vars()).unwrap();
 
         let content = std::fs::read_to_string(&config.runtime_settings).unwrap();
         let json: serde_json::Value = serde_json::from_str(&content).unwrap();
-        let cmd = json["hooks"]["Stop"][0]["hooks"][0]["command"]
-            .as_str()
-            .unwrap();
-        assert!(cmd.starts_with("curl"));
+        let prompt = json["hooks"]["UserPromptSubmit"].as_array().unwrap();
+        assert_eq!(prompt.len(), 2);
+        assert_eq!(prompt[0]["hooks"][0]["command"], "mine");
+        assert_eq!(prompt[1]["hooks"][0]["command"], "sh \"$HOME/.ai-pod/notify.sh\" --flush");
+    }
+
+    #[test]
+    fn notify_hook_queues_while_the_server_is_down() {
+        use std::io::{BufRead, BufReader, Write};
+        let home = TempDir::new().unwrap();
+        let script = home.path().join("notify.sh");
+        std::fs::write(&script, NOTIFY_HOOK_SH).unwrap();
+        let queue = home.path().join(".ai-pod/notify-queue");
+        let hook = |url: &str, args: &[&str]| {
+            let status = std::process::Command::new("sh")
+                .arg(&script)
+                .args(args)
+                .env("HOME", home.path())
+                .env("AI_POD_SERVER_URL", url)
+                .env("AI_POD_PROJECT_ID", "p1")
+                .env("AI_POD_SESSION_ID", "s1")
+                .env("AI_POD_API_KEY", "k")
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+
+        // Nothing listens on a port we just released.
+        let down = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        hook(&format!("http://{}", down), &["stop", "Task completed"]);
+        hook(&format!("http://{}", down), &["permission", "Claude needs your approval"]);
+        let queued = std::fs::read_to_string(&queue).unwrap();
+        assert_eq!(queued.lines().count(), 2);
+        let first: serde_json::Value = serde_json::from_str(queued.lines().next().unwrap()).unwrap();
+        assert_eq!((first["event"].as_str(), first["session_id"].as_str()), (Some("stop"), Some("s1")));
+        assert!(first["ts"].as_u64().is_some());
+
+        // Once the server answers, a flush sends the queue in order.
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        let received = std::thread::spawn(move || {
+            let mut bodies = Vec::new();
+            for _ in 0..2 {
+                let (stream, _) = server.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(n) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = n.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                std::io::Read::read_exact(&mut reader, &mut body).unwrap();
+                bodies.push(String::from_utf8(body).unwrap());
+                reader
+                    .get_mut()
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .unwrap();
+            }
+            bodies
+        });
+        hook(&url, &["--flush"]);
+        let bodies = received.join().unwrap();
+        assert!(bodies[0].contains("\"event\":\"stop\""));
+        assert!(bodies[1].contains("\"event\":\"permission\""));
+        assert!(!queue.exists() || std::fs::read_to_string(&queue).unwrap().is_empty());
     }
 
     #[test]
     fn runtime_settings_contains_default_mode_bypass_permissions() {
         let dir = TempDir::new().unwrap();
-        let config = make_test_config(&dir);
-        generate_runtime_settings(&config).unwrap();
+        let config = test_config(&dir);
+        generate_runtime_settings(&config, &test_vars()).unwrap();
 
         let content = std::fs::read_to_string(&config.runtime_settings).unwrap();
         let json: serde_json::Value = serde_json::from_str(&content).unwrap();
@@ -1077,8 +3295,8 @@ mod tests {
     #[test]
     fn runtime_settings_does_not_contain_mcp_servers() {
         let dir = TempDir::new().unwrap();
-        let config = make_test_config(&dir);
-        generate_runtime_settings(&config).unwrap();
+        let config = test_config(&dir);
+        generate_runtime_settings(&config, &test_vars()).unwrap();
 
         let content = std::fs::read_to_string(&config.runtime_settings).unwrap();
         let json: serde_json::Value = serde_json::from_str(&content).unwrap();
@@ -1088,7 +3306,7 @@ mod tests {
     #[test]
     fn runtime_settings_preserves_existing_keys() {
         let dir = TempDir::new().unwrap();
-        let config = make_test_config(&dir);
+        let config = test_config(&dir);
 
         let claude_dir = config.home_dir.join(".claude");
         std::fs::create_dir_all(&claude_dir).unwrap();
@@ -1099,7 +3317,7 @@ mod tests {
         )
         .unwrap();
 
-        generate_runtime_settings(&config).unwrap();
+        generate_runtime_settings(&config, &test_vars()).unwrap();
 
         let content = std::fs::read_to_string(&config.runtime_settings).unwrap();
         let json: serde_json::Value = serde_json::from_str(&content).unwrap();
@@ -1116,6 +3334,19 @@ mod tests {
         assert_eq!(entry["headers"]["X-Ai-Pod-Session-Id"], "s2");
     }
 
+    #[test]
+    fn flag_helpers_pair_flags_with_their_values() {
+        let args: Vec<String> = ["-v", "a:/a", "--read-only", "--tmpfs", "/tmp:rw", "--cap-drop=ALL", "-v", "b:/b"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(flag_values(&args, "-v"), vec!["a:/a", "b:/b"]);
+        assert_eq!(
+            flag_pairs(&args),
+            vec!["-v a:/a", "--read-only", "--tmpfs /tmp:rw", "--cap-drop=ALL", "-v b:/b"]
+        );
+    }
+
     #[test]
     fn opencode_config_content_bakes_literal_values() {
         let s = opencode_config_content("http://host.containers.internal:7822", "k1", "s2");
@@ -1136,6 +3367,7 @@ mod tests {
             host: "/whatever".into(),
             container: Some("/run/secrets/key".into()),
             writable: false,
+            kind: MountType::Bind,
         };
         let t = resolve_container_target(&spec, Path::new("/home/user")).unwrap();
         assert_eq!(t, "/run/secrets/key");
@@ -1147,6 +3379,7 @@ mod tests {
             host: "/home/user/.claude/skills".into(),
             container: None,
             writable: false,
+            kind: MountType::Bind,
         };
         let t = resolve_container_target(&spec, Path::new("/home/user")).unwrap();
         assert_eq!(t, "/home/ai-pod/.claude/skills");
@@ -1158,6 +3391,7 @@ mod tests {
             host: "/etc/foo".into(),
             container: None,
             writable: false,
+            kind: MountType::Bind,
         };
         let err = resolve_container_target(&spec, Path::new("/home/user")).unwrap_err();
         assert!(err.to_string().contains("outside $HOME"), "got: {err}");
@@ -1173,8 +3407,9 @@ mod tests {
             host: host_str.clone(),
             container: Some("/home/ai-pod/.claude/skills".into()),
             writable: false,
+            kind: MountType::Bind,
         }];
-        let args = build_mount_args(dir.path(), &mounts).unwrap();
+        let args = build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap();
         assert_eq!(
             args,
             vec![
@@ -1194,8 +3429,9 @@ mod tests {
             host: host_str.clone(),
             container: Some("/home/ai-pod/.claude/skills".into()),
             writable: true,
+            kind: MountType::Bind,
         }];
-        let args = build_mount_args(dir.path(), &mounts).unwrap();
+        let args = build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap();
         assert_eq!(
             args,
             vec![
@@ -1215,8 +3451,9 @@ mod tests {
             host: host_str.clone(),
             container: None,
             writable: false,
+            kind: MountType::Bind,
         }];
-        let args = build_mount_args(dir.path(), &mounts).unwrap();
+        let args = build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap();
         assert_eq!(
             args,
             vec![
@@ -1234,8 +3471,9 @@ mod tests {
             host: missing.to_string_lossy().to_string(),
             container: Some("/home/ai-pod/x".into()),
             writable: false,
+            kind: MountType::Bind,
         }];
-        let args = build_mount_args(dir.path(), &mounts).unwrap();
+        let args = build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap();
         assert!(args.is_empty(), "missing host path should be skipped");
     }
 
@@ -1247,6 +3485,7 @@ mod tests {
             host: "/home/user".into(),
             container: None,
             writable: false,
+            kind: MountType::Bind,
         };
         let err = resolve_container_target(&spec, Path::new("/home/user")).unwrap_err();
         assert!(err.to_string().contains("home volume root"), "got: {err}");
@@ -1264,8 +3503,9 @@ mod tests {
             host: outside.display().to_string(),
             container: None,
             writable: false,
+            kind: MountType::Bind,
         }];
-        let args = build_mount_args(dir.path(), &mounts).unwrap();
+        let args = build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap();
         assert!(args.is_empty(), "invalid stored mount should be skipped");
     }
 
@@ -1279,11 +3519,164 @@ mod tests {
             host: "/".into(),
             container: Some("/home/ai-pod/exploit".into()),
             writable: false,
+            kind: MountType::Bind,
         }];
-        let args = build_mount_args(dir.path(), &mounts).unwrap();
+        let args = build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap();
         assert!(args.is_empty(), "stored invalid host should be warn-skipped");
     }
 
+    #[test]
+    fn security_args_defaults_drop_caps_and_block_escalation() {
+        let args = security_args(&SecurityConfig::default(), Path::new("/home/user"), false);
+        assert_eq!(
+            args,
+            vec!["--cap-drop", "ALL", "--security-opt", "no-new-privileges"]
+        );
+    }
+
+    #[test]
+    fn security_args_relaxed_keeps_only_filesystem_flags() {
+        let sec = SecurityConfig {
+            read_only: true,
+            ..Default::default()
+        };
+        let args = security_args(&sec, Path::new("/home/user"), true);
+        assert_eq!(args[0], "--read-only");
+        assert_eq!(args[1], "--tmpfs");
+        assert!(args[2].starts_with("/tmp:"));
+        assert_eq!(args.len(), 3);
+    }
+
+    #[test]
+    fn security_args_tmpfs_without_read_only() {
+        let sec = SecurityConfig {
+            tmpfs_tmp: true,
+            ..Default::default()
+        };
+        let args = security_args(&sec, Path::new("/home/user"), true);
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[0], "--tmpfs");
+    }
+
+    #[test]
+    fn security_args_applies_profiles_and_cap_add() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("seccomp.json"), "{}").unwrap();
+        let sec = SecurityConfig {
+            cap_add: vec!["NET_BIND_SERVICE".into()],
+            seccomp_profile: Some("~/seccomp.json".into()),
+            apparmor_profile: Some("ai-pod".into()),
+            ..Default::default()
+        };
+        let args = security_args(&sec, dir.path(), false);
+        assert!(args.windows(2).any(|w| w == ["--cap-add", "NET_BIND_SERVICE"]));
+        let seccomp = format!("seccomp={}", dir.path().join("seccomp.json").display());
+        assert!(args.contains(&seccomp), "got: {args:?}");
+        assert!(args.contains(&"apparmor=ai-pod".to_string()));
+    }
+
+    #[test]
+    fn security_args_skips_missing_seccomp_profile() {
+        let dir = TempDir::new().unwrap();
+        let sec = SecurityConfig {
+            seccomp_profile: Some("~/missing.json".into()),
+            ..Default::default()
+        };
+        let args = security_args(&sec, dir.path(), false);
+        assert!(!args.iter().any(|a| a.starts_with("seccomp=")));
+    }
+
+    #[test]
+    fn build_mount_args_emits_named_volume_without_host_check() {
+        let dir = TempDir::new().unwrap();
+        let mounts = vec![MountSpec {
+            host: "datasets".into(),
+            container: Some("/data".into()),
+            writable: false,
+            kind: MountType::Volume,
+        }];
+        let args = build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap();
+        assert_eq!(args, vec!["-v".to_string(), "datasets:/data:z,ro".to_string()]);
+    }
+
+    #[test]
+    fn build_mount_args_skips_ai_pod_managed_volumes() {
+        let dir = TempDir::new().unwrap();
+        let mounts = vec![MountSpec {
+            host: "ai-pod-0123456789ab-home".into(),
+            container: Some("/data".into()),
+            writable: false,
+            kind: MountType::Volume,
+        }];
+        assert!(build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap().is_empty());
+    }
+
+    #[test]
+    fn exclude_args_uses_tmpfs_for_dirs_and_dev_null_for_files() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("data/raw")).unwrap();
+        std::fs::write(dir.path().join("secrets.json"), "{}").unwrap();
+        let args = exclude_args(
+            dir.path(),
+            &["data/raw".into(), "secrets.json".into(), "missing".into()],
+        );
+        assert_eq!(
+            args,
+            vec![
+                "--tmpfs".to_string(),
+                "/app/data/raw:rw,nosuid,nodev,mode=1777".to_string(),
+                "-v".to_string(),
+                "/dev/null:/app/secrets.json:ro".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn exclude_args_skips_invalid_stored_paths() {
+        let dir = TempDir::new().unwrap();
+        assert!(exclude_args(dir.path(), &["../etc".into()]).is_empty());
+    }
+
+    #[test]
+    fn extra_workspace_args_mount_under_workspaces() {
+        let roots = vec![PathBuf::from("/home/u/api"), PathBuf::from("/srv/api")];
+        assert_eq!(
+            extra_workspace_args(&roots),
+            vec![
+                "-v",
+                "/home/u/api:/workspaces/api:z",
+                "-v",
+                "/srv/api:/workspaces/api-2:z",
+            ]
+        );
+    }
+
+    #[test]
+    fn session_context_lists_extra_roots() {
+        assert!(session_context(&[], &[]).is_none());
+        let md = session_context(&[PathBuf::from("/home/u/api")], &[]).unwrap();
+        assert!(md.contains("`/app`"));
+        assert!(md.contains("`/workspaces/api` (host: `/home/u/api`)"));
+        let md = session_context(&[], &["## Host services\n".to_string()]).unwrap();
+        assert_eq!(md, "# ai-pod session\n\n## Host services\n");
+    }
+
+    #[test]
+    fn session_context_args_writes_file_and_mounts_read_only() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        assert!(session_context_args(&config, "abcd1234", &[], &[]).unwrap().is_empty());
+
+        let args =
+            session_context_args(&config, "abcd1234", &[PathBuf::from("/home/u/api")], &[]).unwrap();
+        let file = config.session_context_file("abcd1234");
+        assert!(file.exists());
+        assert_eq!(
+            args[1],
+            format!("{}:/etc/claude-code/CLAUDE.md:ro,z", file.display())
+        );
+    }
+
     #[test]
     fn build_mount_args_keeps_dangling_symlinks() {
         // MountSpec doc explicitly says symlinks are not resolved, so a
@@ -1297,9 +3690,79 @@ mod tests {
             host: host_str.clone(),
             container: Some("/home/ai-pod/.claude/skills".into()),
             writable: false,
+            kind: MountType::Bind,
         }];
-        let args = build_mount_args(dir.path(), &mounts).unwrap();
+        let args = build_mount_args(dir.path(), CONTAINER_HOME, &mounts).unwrap();
         assert_eq!(args.len(), 2, "dangling symlink should still mount");
         assert!(args[1].starts_with(&host_str));
     }
+
+    #[test]
+    fn parse_host_entry_accepts_both_separators() {
+        assert_eq!(parse_host_entry("staging.corp=10.0.0.5"), Ok(("staging.corp", "10.0.0.5")));
+        assert_eq!(parse_host_entry("git.corp:fd00::1"), Ok(("git.corp", "fd00::1")));
+        assert_eq!(parse_host_entry("gw=host-gateway"), Ok(("gw", "host-gateway")));
+        assert!(parse_host_entry("staging.corp").is_err());
+        assert!(parse_host_entry("staging.corp=not-an-ip").is_err());
+        assert!(parse_host_entry("bad name=10.0.0.5").is_err());
+    }
+
+    #[test]
+    fn run_options_args() {
+        assert!(RunOptions::default().args().is_empty());
+        let opts = RunOptions {
+            env: vec!["CI=1".into(), "NPM_TOKEN".into()],
+            user: Some("1000:1000".into()),
+        };
+        assert_eq!(
+            opts.args(),
+            vec!["-e", "CI=1", "-e", "NPM_TOKEN", "--user=1000:1000"]
+        );
+    }
+
+    #[test]
+    fn resolver_args_skip_invalid_entries() {
+        let network = crate::config::NetworkConfig {
+            hosts: vec!["staging.corp=10.0.0.5".into(), "broken".into()],
+            dns: vec!["10.0.0.53".into(), "dns.corp".into()],
+            dns_search: vec!["corp.example".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            resolver_args(&network),
+            vec![
+                "--add-host=staging.corp:10.0.0.5",
+                "--dns=10.0.0.53",
+                "--dns-search=corp.example"
+            ]
+        );
+    }
+
+    #[test]
+    fn session_command_prefers_flags_then_project_then_global() {
+        let global = LaunchConfig {
+            entrypoint: Some("/usr/local/bin/wrap".into()),
+            command: vec!["tmux".into(), "new".into(), "claude".into()],
+        };
+        let project = LaunchConfig {
+            command: vec!["zsh".into()],
+            ..Default::default()
+        };
+        let session = SessionCommand::resolve(&LaunchOptions::default(), &project, &global);
+        assert_eq!(session.entrypoint_args(), vec!["--entrypoint=/usr/local/bin/wrap"]);
+        assert_eq!(session.command_args(), ["zsh"]);
+        assert_eq!(session.command.as_ref().unwrap().1, "project");
+
+        let opts = LaunchOptions {
+            entrypoint: Some(String::new()),
+            ..Default::default()
+        };
+        let session = SessionCommand::resolve(&opts, &project, &global);
+        assert_eq!(session.entrypoint_args(), vec!["--entrypoint="]);
+        assert_eq!(session.entrypoint.as_ref().unwrap().1, "flag");
+
+        let none = SessionCommand::resolve(&LaunchOptions::default(), &Default::default(), &Default::default());
+        assert_eq!(none, SessionCommand::default());
+        assert!(none.entrypoint_args().is_empty() && none.command_args().is_empty());
+    }
 }
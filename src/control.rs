@@ -0,0 +1,454 @@
+//! Control socket for editor plugins (`ai-pod control`): JSON-RPC 2.0 over
+//! a Unix socket at `~/.ai-pod/control.sock`, one JSON message per line, so
+//! a VS Code or Neovim plugin can drive ai-pod and follow what it does
+//! without scraping colored terminal output.
+//!
+//! Methods, as of schema version [`SCHEMA`]:
+//!
+//! - `version`: `{version, schema}`.
+//! - `status` `{workspace?}`: `{sessions: [{name, workspace, project_id,
+//!   session_id, status, created, title}]}`.
+//! - `launch` `{workspace}`: sets the next session up like `ai-pod warm`
+//!   and returns `{command}`, the argv to run in the editor's terminal; the
+//!   agent is interactive and needs one.
+//! - `run` `{workspace, argv, env?}`: `ai-pod run`. Its output arrives as
+//!   `output` notifications `{id, stream, line}`; the result is
+//!   `{exit_code}`.
+//! - `clean` `{workspace, keep_volumes?, image?}`: `ai-pod clean`.
+//! - `events.subscribe` `{workspace?, since?}`: every event-log entry from
+//!   now on (or from unix time `since`) arrives as an `event` notification
+//!   shaped like a line of `events.log`.
+//!
+//! `launch`, `run` and `clean` run this binary's own subcommands, so they
+//! behave exactly like the CLI; what they print is only passed on in error
+//! messages, never part of the schema.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::{Value, json};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::config::AppConfig;
+use crate::events::{self, Event};
+use crate::registry::WorkspaceRegistry;
+use crate::runtime::ContainerRuntime;
+
+/// Bumped whenever a method or a result changes incompatibly.
+pub const SCHEMA: u32 = 1;
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The operation ran and failed; `data` has its exit code and output.
+const FAILED: i64 = -32000;
+
+/// How often `events.subscribe` looks for new events.
+const EVENT_POLL: Duration = Duration::from_millis(500);
+
+pub fn socket_path(config: &AppConfig) -> PathBuf {
+    config.config_dir.join("control.sock")
+}
+
+/// `ai-pod control`: serve the socket until interrupted.
+pub async fn serve(rt: &ContainerRuntime, config: &AppConfig, socket: Option<PathBuf>) -> Result<()> {
+    let path = socket.unwrap_or_else(|| socket_path(config));
+    if path.exists() {
+        if UnixStream::connect(&path).await.is_ok() {
+            anyhow::bail!("Another `ai-pod control` is already listening on {}", path.display());
+        }
+        std::fs::remove_file(&path).with_context(|| format!("Failed to remove stale {}", path.display()))?;
+    }
+    let listener = UnixListener::bind(&path).with_context(|| format!("Failed to bind {}", path.display()))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    eprintln!("{} {}", "Control socket listening on".blue().bold(), path.display());
+
+    let accept = async {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let (rt, config_dir) = (rt.clone(), config.config_dir.clone());
+                    tokio::spawn(async move { connection(stream, rt, config_dir).await });
+                }
+                Err(e) => return Err::<(), _>(e),
+            }
+        }
+    };
+    let result = tokio::select! {
+        r = accept => r.context("Control socket failed"),
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    };
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Read requests off one client, answering each in its own task so a long
+/// `run` doesn't hold up the others.
+async fn connection(stream: UnixStream, rt: ContainerRuntime, config_dir: PathBuf) {
+    let (read, mut write) = stream.into_split();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Value>();
+    tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            let line = format!("{}\n", msg);
+            if write.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut lines = BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = tx.send(error(Value::Null, PARSE_ERROR, &e.to_string(), None));
+                continue;
+            }
+        };
+        let (tx, rt, config_dir) = (tx.clone(), rt.clone(), config_dir.clone());
+        tokio::spawn(async move {
+            let id = request.get("id").cloned().unwrap_or(Value::Null);
+            let method = request.get("method").and_then(Value::as_str).unwrap_or_default().to_string();
+            let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+            let reply = match dispatch(&method, &params, &id, &rt, &config_dir, &tx).await {
+                Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                Err(e) => error(id, e.code, &e.message, e.data),
+            };
+            // Notifications (no id) get no response.
+            if request.get("id").is_some() {
+                let _ = tx.send(reply);
+            }
+        });
+    }
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn params(message: impl Into<String>) -> Self {
+        RpcError {
+            code: INVALID_PARAMS,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+fn error(id: Value, code: i64, message: &str, data: Option<Value>) -> Value {
+    let mut err = json!({"code": code, "message": message});
+    if let Some(data) = data {
+        err["data"] = data;
+    }
+    json!({"jsonrpc": "2.0", "id": id, "error": err})
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({"jsonrpc": "2.0", "method": method, "params": params})
+}
+
+async fn dispatch(
+    method: &str,
+    params: &Value,
+    id: &Value,
+    rt: &ContainerRuntime,
+    config_dir: &Path,
+    tx: &UnboundedSender<Value>,
+) -> Result<Value, RpcError> {
+    match method {
+        "version" => Ok(json!({"version": env!("CARGO_PKG_VERSION"), "schema": SCHEMA})),
+        "status" => status(rt, config_dir, optional_workspace(params)?.as_deref()).await,
+        "launch" => {
+            let workspace = workspace(params)?;
+            let ws = workspace.to_string_lossy().to_string();
+            run_cli(rt, &["--workdir", &ws, "warm"], None).await?;
+            Ok(json!({"command": ["ai-pod", "--workdir", ws]}))
+        }
+        "run" => {
+            let workspace = workspace(params)?;
+            let argv = strings(params, "argv")?;
+            if argv.is_empty() {
+                return Err(RpcError::params("argv must name the command to run"));
+            }
+            let mut args = vec!["run".to_string(), "--no-tty".into(), "--workdir".into()];
+            args.push(workspace.to_string_lossy().to_string());
+            for var in strings(params, "env")? {
+                args.extend(["-e".to_string(), var]);
+            }
+            // Keep an argv that starts with a flag from being read as ai-pod's own.
+            args.push("--".into());
+            args.extend(argv);
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let code = run_cli(rt, &args, Some((id, tx))).await?;
+            Ok(json!({"exit_code": code}))
+        }
+        "clean" => {
+            let workspace = workspace(params)?;
+            let mut args = vec!["clean".to_string(), "--workdir".into(), workspace.to_string_lossy().to_string()];
+            if flag(params, "keep_volumes") {
+                args.push("--keep-volume".into());
+            }
+            if flag(params, "image") {
+                args.push("--image".into());
+            }
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            run_cli(rt, &args, None).await?;
+            Ok(json!({}))
+        }
+        "events.subscribe" => {
+            let project_id = optional_workspace(params)?.map(|w| crate::workspace::workspace_hash(&w));
+            let since = params.get("since").and_then(Value::as_u64);
+            tokio::spawn(follow_events(config_dir.to_path_buf(), project_id, since, tx.clone()));
+            Ok(json!({"subscribed": true}))
+        }
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("unknown method '{}'", method),
+            data: None,
+        }),
+    }
+}
+
+fn optional_workspace(params: &Value) -> Result<Option<PathBuf>, RpcError> {
+    match params.get("workspace") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(ws)) => std::fs::canonicalize(ws)
+            .map(Some)
+            .map_err(|e| RpcError::params(format!("workspace {}: {}", ws, e))),
+        Some(_) => Err(RpcError::params("workspace must be a path")),
+    }
+}
+
+fn workspace(params: &Value) -> Result<PathBuf, RpcError> {
+    optional_workspace(params)?.ok_or_else(|| RpcError::params("workspace is required"))
+}
+
+fn strings(params: &Value, key: &str) -> Result<Vec<String>, RpcError> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| v.as_str().map(str::to_string))
+            .collect::<Option<_>>()
+            .ok_or_else(|| RpcError::params(format!("{} must be a list of strings", key))),
+        Some(_) => Err(RpcError::params(format!("{} must be a list of strings", key))),
+    }
+}
+
+fn flag(params: &Value, key: &str) -> bool {
+    params.get(key).and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// The sessions in `ps` output (`name\tstatus\tcreated` per line), with
+/// their workspace from the registry. Service and init containers are left
+/// out.
+fn parse_sessions(out: &str, reg: &WorkspaceRegistry, config_dir: &Path, project_id: Option<&str>) -> Vec<Value> {
+    out.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (name, status, created) = (fields.next()?, fields.next()?, fields.next().unwrap_or_default());
+            let project = crate::workspace::project_id_from_container_name(name)?;
+            let session = crate::workspace::session_id_from_container_name(name)?;
+            if name != format!("ai-pod-{}-{}", project, session) || project_id.is_some_and(|p| p != project) {
+                return None;
+            }
+            let workspace = reg
+                .workspaces
+                .iter()
+                .find(|(_, e)| e.project_id == project)
+                .map(|(ws, _)| ws.clone());
+            let title = crate::config::SessionState::load_from_dir(config_dir, &session).and_then(|s| s.title);
+            Some(json!({
+                "name": name,
+                "workspace": workspace,
+                "project_id": project,
+                "session_id": session,
+                "status": status,
+                "created": created,
+                "title": title,
+            }))
+        })
+        .collect()
+}
+
+async fn status(rt: &ContainerRuntime, config_dir: &Path, workspace: Option<&Path>) -> Result<Value, RpcError> {
+    let out = rt
+        .async_command()
+        .args([
+            "ps",
+            "-a",
+            "--filter",
+            "label=managed-by=ai-pod",
+            "--format",
+            "{{.Names}}\t{{.Status}}\t{{.CreatedAt}}",
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| RpcError {
+            code: FAILED,
+            message: format!("failed to list containers: {}", e),
+            data: None,
+        })?;
+    let project_id = workspace.map(crate::workspace::workspace_hash);
+    let sessions = parse_sessions(
+        &String::from_utf8_lossy(&out.stdout),
+        &WorkspaceRegistry::load_from_dir(config_dir),
+        config_dir,
+        project_id.as_deref(),
+    );
+    Ok(json!({"sessions": sessions}))
+}
+
+/// Run `ai-pod <args>` with colors off and wait for it. With `stream`, each
+/// output line is sent as an `output` notification for that request and
+/// the exit code is returned whatever it is; otherwise a failure is an
+/// error carrying the output.
+async fn run_cli(
+    rt: &ContainerRuntime,
+    args: &[&str],
+    stream: Option<(&Value, &UnboundedSender<Value>)>,
+) -> Result<i32, RpcError> {
+    let failed = |message: String, data: Option<Value>| RpcError {
+        code: FAILED,
+        message,
+        data,
+    };
+    let exe = std::env::current_exe().map_err(|e| failed(e.to_string(), None))?;
+    let mut cmd = tokio::process::Command::new(exe);
+    cmd.args(["--runtime", rt.kind.as_str()]);
+    if rt.dry_run {
+        cmd.arg("--dry-run");
+    }
+    let mut child = cmd
+        .args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| failed(format!("failed to start ai-pod: {}", e), None))?;
+    let stdout = collect(child.stdout.take(), "stdout", stream);
+    let stderr = collect(child.stderr.take(), "stderr", stream);
+    let (status, stdout, stderr) = tokio::join!(child.wait(), stdout, stderr);
+    let code = status.map(crate::container::exit_code).unwrap_or(-1);
+    if stream.is_some() || code == 0 {
+        return Ok(code);
+    }
+    let reason = stderr
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("ai-pod failed")
+        .trim()
+        .to_string();
+    Err(failed(
+        reason,
+        Some(json!({"exit_code": code, "stdout": stdout, "stderr": stderr})),
+    ))
+}
+
+/// Read `pipe` to the end, forwarding its lines when streaming.
+async fn collect<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    name: &str,
+    stream: Option<(&Value, &UnboundedSender<Value>)>,
+) -> String {
+    let Some(pipe) = pipe else {
+        return String::new();
+    };
+    let mut all = String::new();
+    let mut lines = BufReader::new(pipe).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        match stream {
+            Some((id, tx)) => {
+                let _ = tx.send(notification("output", json!({"id": id, "stream": name, "line": line})));
+            }
+            None => {
+                all.push_str(&line);
+                all.push('\n');
+            }
+        }
+    }
+    all
+}
+
+/// Send events appended to the log as `event` notifications until the
+/// client goes away. With `since`, earlier events from that time on are sent
+/// first.
+async fn follow_events(config_dir: PathBuf, project_id: Option<String>, since: Option<u64>, tx: UnboundedSender<Value>) {
+    let path = events::events_file(&config_dir);
+    let len = |p: &Path| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+    let mut offset = if since.is_some() { 0 } else { len(&path) };
+    let mut partial = String::new();
+    loop {
+        let size = len(&path);
+        if size < offset {
+            // Rotated: start over on the fresh file.
+            offset = 0;
+            partial.clear();
+        }
+        if size > offset {
+            let Ok(bytes) = std::fs::read(&path) else {
+                continue;
+            };
+            let end = (size as usize).min(bytes.len());
+            partial.push_str(&String::from_utf8_lossy(&bytes[offset as usize..end]));
+            offset = end as u64;
+            while let Some(nl) = partial.find('\n') {
+                let line: String = partial.drain(..=nl).collect();
+                let Ok(event) = serde_json::from_str::<Event>(&line) else {
+                    continue;
+                };
+                if since.is_some_and(|s| event.ts < s) || (project_id.is_some() && event.project_id != project_id) {
+                    continue;
+                }
+                let params = serde_json::to_value(&event).unwrap_or(Value::Null);
+                if tx.send(notification("event", params)).is_err() {
+                    return;
+                }
+            }
+        }
+        if tx.is_closed() {
+            return;
+        }
+        tokio::time::sleep(EVENT_POLL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sessions_keeps_session_containers_of_the_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut reg = WorkspaceRegistry::default();
+        reg.register(Path::new("/src/api"), 1);
+        let api = crate::workspace::workspace_hash(Path::new("/src/api"));
+        let out = format!(
+            "ai-pod-{api}-0a1b2c3d\tUp 5 minutes\t2026-01-01 10:00\n\
+             ai-pod-{api}-0a1b2c3d-svc-db\tUp 5 minutes\t2026-01-01 10:00\n\
+             ai-pod-{api}-init\tCreated\t2026-01-01 10:00\n\
+             ai-pod-abcdefabcdef-11112222\tExited (0)\t2026-01-01 09:00\n"
+        );
+        let all = parse_sessions(&out, &reg, dir.path(), None);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0]["workspace"], "/src/api");
+        assert_eq!(all[0]["session_id"], "0a1b2c3d");
+        assert_eq!(all[0]["status"], "Up 5 minutes");
+        assert_eq!(all[1]["workspace"], Value::Null);
+        assert_eq!(parse_sessions(&out, &reg, dir.path(), Some(&api)).len(), 1);
+    }
+}
@@ -55,7 +55,7 @@ const CREDENTIAL_DIR_PATTERNS: &[&str] = &[
     ".gnupg/",
 ];
 
-fn is_credential_file(path: &Path) -> bool {
+pub(crate) fn is_credential_file(path: &Path) -> bool {
     let file_name = match path.file_name().and_then(|n| n.to_str()) {
         Some(n) => n,
         None => return false,
@@ -567,19 +567,10 @@ mod tests_scan {
 #[cfg(test)]
 mod tests_env_files_management {
     use super::*;
+    use crate::config::test_config;
     use crate::server::lifecycle::ProjectState;
     use tempfile::TempDir;
 
-    fn temp_config(home: &Path) -> AppConfig {
-        let config_dir = home.join(".ai-pod");
-        std::fs::create_dir_all(&config_dir).unwrap();
-        AppConfig {
-            runtime_settings: config_dir.join("runtime-settings.json"),
-            config_dir,
-            home_dir: home.to_path_buf(),
-        }
-    }
-
     #[test]
     fn remove_ignored_credential_removes_entry() {
         let mut state = ProjectState::default();
@@ -595,7 +586,7 @@ mod tests_env_files_management {
     fn hide_file_moves_and_symlinks() {
         let home = TempDir::new().unwrap();
         let workspace = TempDir::new().unwrap();
-        let config = temp_config(home.path());
+        let config = test_config(&home);
 
         let src = workspace.path().join(".env");
         std::fs::write(&src, "SECRET=123").unwrap();
@@ -614,7 +605,7 @@ mod tests_env_files_management {
     fn hide_file_refuses_already_hidden_file() {
         let home = TempDir::new().unwrap();
         let workspace = TempDir::new().unwrap();
-        let config = temp_config(home.path());
+        let config = test_config(&home);
 
         let src = workspace.path().join(".env");
         std::fs::write(&src, "SECRET=123").unwrap();
@@ -628,7 +619,7 @@ mod tests_env_files_management {
     fn unhide_file_restores_real_file() {
         let home = TempDir::new().unwrap();
         let workspace = TempDir::new().unwrap();
-        let config = temp_config(home.path());
+        let config = test_config(&home);
 
         let src = workspace.path().join(".env");
         std::fs::write(&src, "SECRET=123").unwrap();
@@ -655,7 +646,7 @@ mod tests_env_files_management {
     fn list_env_files_classifies_all_three_states() {
         let home = TempDir::new().unwrap();
         let workspace = TempDir::new().unwrap();
-        let config = temp_config(home.path());
+        let config = test_config(&home);
 
         // Exposed file
         std::fs::write(workspace.path().join(".env"), "A=1").unwrap();
@@ -690,7 +681,7 @@ mod tests_env_files_management {
         // subdirectories must NOT collide in ~/.env-files/<slug>/.
         let home = TempDir::new().unwrap();
         let workspace = TempDir::new().unwrap();
-        let config = temp_config(home.path());
+        let config = test_config(&home);
 
         let a_dir = workspace.path().join("packages").join("a");
         let b_dir = workspace.path().join("packages").join("b");
@@ -721,7 +712,7 @@ mod tests_env_files_management {
     fn hide_file_rejects_path_traversal() {
         let home = TempDir::new().unwrap();
         let workspace = TempDir::new().unwrap();
-        let config = temp_config(home.path());
+        let config = test_config(&home);
         std::fs::write(workspace.path().join(".env"), "X=1").unwrap();
         let err = hide_file(workspace.path(), &config, "../escape/.env").unwrap_err();
         assert!(err.to_string().contains("Invalid"), "got: {}", err);
@@ -0,0 +1,475 @@
+//! `ai-pod describe`: the CLI surface, the config schema and its defaults,
+//! generated from the binary itself, for GUIs and wrappers that want to stay
+//! in sync with the installed version. `--json` is the stable form; without
+//! it, a plain overview is printed.
+//!
+//! The commands come from the clap definitions. The config schema is read
+//! off the config types' `Deserialize` impls by deserializing them from a
+//! [`Probe`] that records what each field asks for, so it can't drift from
+//! what `config.json` actually accepts.
+
+use anyhow::Result;
+use clap::{ArgAction, CommandFactory};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::cli::Cli;
+use crate::config::GlobalConfig;
+use crate::server::lifecycle::ProjectState;
+
+/// Bumped whenever the JSON layout changes incompatibly.
+pub const SCHEMA: u32 = 1;
+
+/// `ai-pod describe [--json]`.
+pub fn run(json: bool) -> Result<()> {
+    let doc = describe();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+        return Ok(());
+    }
+    println!("ai-pod {}", env!("CARGO_PKG_VERSION"));
+    println!("\nCommands:");
+    for cmd in doc["cli"]["subcommands"].as_array().into_iter().flatten() {
+        println!(
+            "  {:<16} {}",
+            cmd["name"].as_str().unwrap_or_default(),
+            cmd["about"].as_str().unwrap_or_default()
+        );
+    }
+    println!("\nSettings (`ai-pod config set KEY VALUE`):");
+    let mut keys = Vec::new();
+    flatten_keys(&doc["config"]["global"], "", &mut keys);
+    for (key, ty, default) in keys {
+        match default {
+            Some(default) => println!("  {:<32} {:<10} default {}", key, ty, default),
+            None => println!("  {:<32} {}", key, ty),
+        }
+    }
+    Ok(())
+}
+
+/// The whole description, as printed by `--json`.
+pub fn describe() -> Value {
+    let mut global = schema_of::<GlobalConfig>();
+    add_defaults(&mut global, &serde_json::to_value(GlobalConfig::default()).unwrap_or_default());
+    let mut project = schema_of::<ProjectState>();
+    if let Some(Value::Object(fields)) = project.get_mut("fields") {
+        fields.retain(|k, _| crate::config_cli::PROJECT_KEYS.contains(&k.as_str()));
+    }
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "schema": SCHEMA,
+        "cli": command(&Cli::command()),
+        "config": {"global": global, "project": project},
+    })
+}
+
+fn command(cmd: &clap::Command) -> Value {
+    json!({
+        "name": cmd.get_name(),
+        "about": cmd.get_about().map(|s| s.to_string()),
+        "args": cmd
+            .get_arguments()
+            .filter(|a| !a.is_hide_set() && !matches!(a.get_action(), ArgAction::Help | ArgAction::Version))
+            .map(arg)
+            .collect::<Vec<_>>(),
+        "subcommands": cmd
+            .get_subcommands()
+            .filter(|c| !c.is_hide_set())
+            .map(command)
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn arg(a: &clap::Arg) -> Value {
+    let takes_value = a.get_action().takes_values();
+    let multiple = matches!(a.get_action(), ArgAction::Append | ArgAction::Count)
+        || a.get_num_args().is_some_and(|n| n.max_values() > 1);
+    let mut out = json!({
+        "name": a.get_id().as_str(),
+        "long": a.get_long(),
+        "short": a.get_short().map(String::from),
+        "help": a.get_help().map(|s| s.to_string()),
+        "positional": a.is_positional(),
+        "takes_value": takes_value,
+        "required": a.is_required_set(),
+        "multiple": multiple,
+        "global": a.is_global_set(),
+    });
+    if takes_value {
+        out["value_name"] = json!(a.get_value_names().and_then(|v| v.first()).map(|s| s.to_string()));
+        let defaults: Vec<String> = a.get_default_values().iter().map(|v| v.to_string_lossy().into_owned()).collect();
+        if !defaults.is_empty() {
+            out["default"] = json!(defaults);
+        }
+        let values: Vec<String> = a
+            .get_possible_values()
+            .iter()
+            .filter(|v| !v.is_hide_set())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        if !values.is_empty() {
+            out["possible_values"] = json!(values);
+        }
+    }
+    out
+}
+
+/// Fill in `default` on the leaves of `schema` that `defaults` (a
+/// serialized default config) has a value for.
+fn add_defaults(schema: &mut Value, defaults: &Value) {
+    let Some(Value::Object(fields)) = schema.get_mut("fields") else {
+        return;
+    };
+    for (key, field) in fields {
+        let Some(default) = defaults.get(key) else {
+            continue;
+        };
+        if field.get("fields").is_some() {
+            add_defaults(field, default);
+        } else {
+            field["default"] = default.clone();
+        }
+    }
+}
+
+/// `(dotted key, type, default)` for every leaf of `schema`.
+fn flatten_keys(schema: &Value, prefix: &str, out: &mut Vec<(String, String, Option<String>)>) {
+    let Some(Value::Object(fields)) = schema.get("fields") else {
+        return;
+    };
+    for (key, field) in fields {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        if field.get("fields").is_some() {
+            flatten_keys(field, &path, out);
+            continue;
+        }
+        let ty = match (field["type"].as_str(), field.get("enum")) {
+            (_, Some(Value::Array(variants))) => variants
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("|"),
+            (Some("array"), _) => format!("[{}]", field["items"]["type"].as_str().unwrap_or("any")),
+            (ty, _) => ty.unwrap_or("any").to_string(),
+        };
+        out.push((path, ty, field.get("default").map(Value::to_string)));
+    }
+}
+
+/// The schema of `T`: `{type, fields?, items?, values?, enum?, nullable?}`.
+/// A field whose type can't be probed is described as `any`; it is left out
+/// of the next attempt so the fields after it are still seen.
+fn schema_of<T: DeserializeOwned>() -> Value {
+    let run = ProbeRun::default();
+    for _ in 0..64 {
+        let slot = Slot::default();
+        let probe = Probe {
+            slot: slot.clone(),
+            path: String::new(),
+            run: &run,
+        };
+        let result = T::deserialize(probe);
+        let failed = run.failed.borrow_mut().take();
+        match (result, failed) {
+            (Err(_), Some(path)) if !run.skip.borrow().contains_key(&path) => {
+                let partial = run.last_field.borrow().clone();
+                run.skip.borrow_mut().insert(path, partial);
+            }
+            _ => return slot.take(),
+        }
+    }
+    Value::Null
+}
+
+type Slot = Rc<RefCell<Value>>;
+
+#[derive(Default)]
+struct ProbeRun {
+    /// Fields to leave out, with what was recorded for them.
+    skip: RefCell<BTreeMap<String, Value>>,
+    /// The first (innermost) field that failed in this attempt.
+    failed: RefCell<Option<String>>,
+    /// Schema recorded for that field.
+    last_field: RefCell<Value>,
+}
+
+#[derive(Debug)]
+struct ProbeError(String);
+
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ProbeError {}
+
+impl de::Error for ProbeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ProbeError(msg.to_string())
+    }
+}
+
+/// A deserializer that records the shape each `deserialize_*` call asks for
+/// in `slot` and answers with a placeholder (`false`, `0`, `""`, one
+/// element) so the type's `Deserialize` impl carries on into its fields.
+struct Probe<'a> {
+    slot: Slot,
+    path: String,
+    run: &'a ProbeRun,
+}
+
+impl<'a> Probe<'a> {
+    fn set(&self, schema: Value) {
+        *self.slot.borrow_mut() = schema;
+    }
+
+    fn child(&self, key: &str) -> Probe<'a> {
+        Probe {
+            slot: Slot::default(),
+            path: if self.path.is_empty() { key.to_string() } else { format!("{}.{}", self.path, key) },
+            run: self.run,
+        }
+    }
+}
+
+macro_rules! probe_as {
+    ($($method:ident => $ty:literal, $visit:ident($($value:expr)?);)*) => {
+        $(fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+            self.set(json!({"type": $ty}));
+            visitor.$visit($($value)?)
+        })*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Probe<'_> {
+    type Error = ProbeError;
+
+    probe_as! {
+        deserialize_bool => "boolean", visit_bool(false);
+        deserialize_i8 => "integer", visit_i64(0);
+        deserialize_i16 => "integer", visit_i64(0);
+        deserialize_i32 => "integer", visit_i64(0);
+        deserialize_i64 => "integer", visit_i64(0);
+        deserialize_u8 => "integer", visit_u64(0);
+        deserialize_u16 => "integer", visit_u64(0);
+        deserialize_u32 => "integer", visit_u64(0);
+        deserialize_u64 => "integer", visit_u64(0);
+        deserialize_f32 => "number", visit_f64(0.0);
+        deserialize_f64 => "number", visit_f64(0.0);
+        deserialize_char => "string", visit_char('a');
+        deserialize_str => "string", visit_str("");
+        deserialize_string => "string", visit_str("");
+        deserialize_bytes => "string", visit_str("");
+        deserialize_byte_buf => "string", visit_str("");
+        deserialize_unit => "null", visit_unit();
+        deserialize_ignored_any => "any", visit_unit();
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+        self.set(json!({"type": "any"}));
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+        let slot = self.slot.clone();
+        let result = visitor.visit_some(Probe {
+            slot: slot.clone(),
+            path: self.path.clone(),
+            run: self.run,
+        });
+        if let Value::Object(map) = &mut *slot.borrow_mut() {
+            map.insert("nullable".into(), Value::Bool(true));
+        }
+        result
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, ProbeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, ProbeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+        self.deserialize_tuple(1, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, ProbeError> {
+        let item = self.child("[]");
+        let items = item.slot.clone();
+        let result = visitor.visit_seq(Elements { probe: Some(item), left: len });
+        self.set(json!({"type": "array", "items": items.take()}));
+        result
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ProbeError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+        let value = self.child("*");
+        let values = value.slot.clone();
+        let result = visitor.visit_map(Entry { probe: Some(value) });
+        self.set(json!({"type": "object", "values": values.take()}));
+        result
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ProbeError> {
+        self.set(json!({"type": "object", "fields": {}}));
+        visitor.visit_map(Fields {
+            probe: &self,
+            fields: fields.iter(),
+            current: None,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ProbeError> {
+        self.set(json!({"type": "string", "enum": variants}));
+        let first = variants.first().copied().unwrap_or_default();
+        visitor.visit_enum(first.into_deserializer())
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+        self.deserialize_str(visitor)
+    }
+}
+
+/// The placeholder sequence: `left` elements, the first one probed.
+struct Elements<'a> {
+    probe: Option<Probe<'a>>,
+    left: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for Elements<'_> {
+    type Error = ProbeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, ProbeError> {
+        if self.left == 0 {
+            return Ok(None);
+        }
+        self.left -= 1;
+        match self.probe.take() {
+            Some(probe) => seed.deserialize(probe).map(Some),
+            None => Err(de::Error::custom("only the first element is probed")),
+        }
+    }
+}
+
+/// The placeholder map: one entry with an empty key.
+struct Entry<'a> {
+    probe: Option<Probe<'a>>,
+}
+
+impl<'de> de::MapAccess<'de> for Entry<'_> {
+    type Error = ProbeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, ProbeError> {
+        if self.probe.is_none() {
+            return Ok(None);
+        }
+        seed.deserialize("".into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, ProbeError> {
+        seed.deserialize(self.probe.take().expect("value follows its key"))
+    }
+}
+
+/// A struct's fields, each probed into its own entry under `fields`.
+struct Fields<'p, 'a> {
+    probe: &'p Probe<'a>,
+    fields: std::slice::Iter<'static, &'static str>,
+    current: Option<&'static str>,
+}
+
+/// Set `parent`'s schema for its field `name`.
+fn record(parent: &Probe, name: &str, schema: Value) {
+    if let Some(Value::Object(fields)) = parent.slot.borrow_mut().get_mut("fields") {
+        fields.insert(name.to_string(), schema);
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Fields<'_, '_> {
+    type Error = ProbeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, ProbeError> {
+        for name in self.fields.by_ref() {
+            let path = self.probe.child(name).path;
+            let skipped = self.probe.run.skip.borrow().get(&path).cloned();
+            if let Some(schema) = skipped {
+                record(self.probe, name, if schema.is_null() { json!({"type": "any"}) } else { schema });
+                continue;
+            }
+            self.current = Some(name);
+            return seed.deserialize(name.into_deserializer()).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, ProbeError> {
+        let name = self.current.take().expect("value follows its key");
+        let child = self.probe.child(name);
+        let (slot, path) = (child.slot.clone(), child.path.clone());
+        let result = seed.deserialize(child);
+        let schema = slot.take();
+        record(self.probe, name, schema.clone());
+        if result.is_err() && self.probe.run.failed.borrow().is_none() {
+            *self.probe.run.failed.borrow_mut() = Some(path);
+            *self.probe.run.last_field.borrow_mut() = schema;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_covers_commands_config_and_defaults() {
+        let doc = describe();
+        let commands: Vec<&str> = doc["cli"]["subcommands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|c| c["name"].as_str())
+            .collect();
+        assert!(commands.contains(&"describe") && commands.contains(&"config"));
+        assert!(!commands.contains(&"serve"), "hidden commands are left out");
+
+        let global = &doc["config"]["global"]["fields"];
+        assert_eq!(global["notify"]["fields"]["quiet_hours"]["type"], "string");
+        assert_eq!(global["notify"]["fields"]["quiet_hours"]["nullable"], true);
+        assert_eq!(global["mounts"]["type"], "array");
+        assert_eq!(global["mounts"]["items"]["type"], "object");
+        assert_eq!(global["userns"]["type"], "string");
+        assert!(global["userns"]["enum"].as_array().is_some_and(|v| !v.is_empty()));
+        assert!(global["userns"].get("default").is_some());
+        assert_eq!(global["notify"]["fields"]["templates"]["values"]["type"], "object");
+
+        let project = doc["config"]["project"]["fields"].as_object().unwrap();
+        assert!(project.contains_key("relax_security") && !project.contains_key("api_key"));
+    }
+}
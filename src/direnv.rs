@@ -0,0 +1,183 @@
+//! Environment presets from the workspace's `.envrc`. With `direnv.allow`
+//! set, each launch evaluates the `.envrc` on the host with
+//! `direnv exec`, keeps the variables it sets whose names are on the
+//! allowlist, and passes them into the container. The agent gets the
+//! project's `DATABASE_URL`-style settings without `.env` files being
+//! mounted or the whole host environment being copied.
+//!
+//! `direnv exec` runs against an emptied environment, so only what the
+//! `.envrc` sets is picked up, not what the host shell happens to export.
+//! The `.envrc` must have been approved with `direnv allow` first.
+
+use colored::Colorize;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::DirenvConfig;
+
+/// How long the `.envrc` may take; a `use nix` or `use flake` that builds
+/// anything should be warmed up in a host shell first.
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Host variables `direnv` needs to find its config and its `allow`
+/// database. They are passed in, and dropped from the result unless the
+/// `.envrc` changed them.
+const BASE_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "XDG_CONFIG_HOME",
+    "XDG_DATA_HOME",
+    "XDG_CACHE_HOME",
+];
+
+/// Variables ai-pod or the container set itself; never taken from the
+/// `.envrc`, whatever the allowlist says.
+const RESERVED: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "PWD", "OLDPWD", "SHLVL", "TERM", "TZ", "HISTFILE",
+    "BROWSER", "HOST_GATEWAY", "OPENCODE_CONFIG_CONTENT",
+];
+
+/// Whether `name` is on the `allow` list, where `PREFIX_*` covers every
+/// name starting with `PREFIX_`.
+fn allowed(name: &str, allow: &[String]) -> bool {
+    allow.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    })
+}
+
+fn reserved(name: &str) -> bool {
+    RESERVED.contains(&name) || name.starts_with("AI_POD_") || name.starts_with("DIRENV_")
+}
+
+/// The allowed variables in `env -0` output, leaving out [`BASE_ENV`]
+/// variables `base` still has the same value for.
+fn filter(raw: &[u8], base: &[(String, String)], allow: &[String]) -> Vec<(String, String)> {
+    raw.split(|b| *b == 0)
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (name, value) = entry.split_once('=')?;
+            let inherited = base.iter().any(|(k, v)| k == name && v == value);
+            (!name.is_empty() && !inherited && !reserved(name) && allowed(name, allow))
+                .then(|| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Run `direnv exec` in `workspace` and return its environment, or why not.
+fn evaluate(workspace: &Path, base: &[(String, String)]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new("direnv")
+        .arg("exec")
+        .arg(workspace)
+        .args(["env", "-0"])
+        .current_dir(workspace)
+        .env_clear()
+        .envs(base.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => "direnv is not installed".to_string(),
+            _ => format!("failed to run direnv: {}", e),
+        })?;
+    let mut stdout = child.stdout.take().expect("piped");
+    let mut stderr = child.stderr.take().expect("piped");
+    let out = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+    let err = std::thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = stderr.read_to_string(&mut buf);
+        buf
+    });
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("the .envrc took longer than {}s", TIMEOUT.as_secs()));
+            }
+        }
+    };
+    let (out, err) = (out.join().unwrap_or_default(), err.join().unwrap_or_default());
+    if !status.success() {
+        let reason = err
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("direnv failed")
+            .trim_start_matches("direnv: ")
+            .to_string();
+        return Err(reason);
+    }
+    Ok(out)
+}
+
+/// The allowed variables the workspace's `.envrc` sets, as `NAME=value`.
+/// Empty when `direnv.allow` is unset or there's no `.envrc`; a `.envrc`
+/// that can't be evaluated is reported and skipped.
+fn env_vars(settings: &DirenvConfig, workspace: &Path) -> Vec<String> {
+    if settings.allow.is_empty() || !workspace.join(".envrc").is_file() {
+        return Vec::new();
+    }
+    let base: Vec<(String, String)> = BASE_ENV
+        .iter()
+        .filter_map(|k| std::env::var(k).ok().map(|v| (k.to_string(), v)))
+        .collect();
+    match evaluate(workspace, &base) {
+        Ok(raw) => filter(&raw, &base, &settings.allow)
+            .into_iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect(),
+        Err(reason) => {
+            eprintln!(
+                "{} not passing variables from .envrc: {} (run `direnv allow` in the workspace if it's blocked)",
+                "warning:".yellow().bold(),
+                reason
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// `-e` args for [`env_vars`].
+pub fn env_args(settings: &DirenvConfig, workspace: &Path) -> Vec<String> {
+    env_vars(settings, workspace)
+        .into_iter()
+        .flat_map(|var| ["-e".to_string(), var])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_keeps_allowed_variables_set_by_the_envrc() {
+        let raw = b"PATH=/nix/store/x/bin:/usr/bin\0HOME=/home/me\0DATABASE_URL=postgres://db/app\0\
+DATABASE_POOL=5\0AWS_SECRET_ACCESS_KEY=hunter2\0DIRENV_DIR=-/src/app\0AI_POD_API_KEY=x\0MULTI=a\nb\0";
+        let base = vec![("HOME".to_string(), "/home/me".to_string()), ("PATH".to_string(), "/usr/bin".to_string())];
+        let allow = vec!["DATABASE_*".to_string(), "MULTI".to_string(), "PATH".to_string(), "AI_POD_*".to_string()];
+        assert_eq!(
+            filter(raw, &base, &allow),
+            vec![
+                ("DATABASE_URL".to_string(), "postgres://db/app".to_string()),
+                ("DATABASE_POOL".to_string(), "5".to_string()),
+                ("MULTI".to_string(), "a\nb".to_string()),
+            ]
+        );
+        assert!(filter(raw, &base, &[]).is_empty());
+    }
+}
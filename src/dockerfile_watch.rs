@@ -0,0 +1,180 @@
+//! `--watch-dockerfile`: watch `ai-pod.Dockerfile` while a session runs and
+//! replace the container with one from a rebuilt image once it changes. The
+//! home volume is kept, so the agent's login and history carry over.
+//!
+//! The swap happens at a safe point: in `auto` mode, the next time the agent
+//! notifies (it finished a turn or waits for permission, so nothing is
+//! mid-edit); in `prompt` mode, after the session ends and the user agrees.
+
+use clap::ValueEnum;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::events::{self, EventKind};
+use crate::runtime::ContainerRuntime;
+
+const POLL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WatchMode {
+    /// Ask after the session ends
+    Prompt,
+    /// Replace the container as soon as the agent is idle
+    Auto,
+}
+
+/// What the watcher thread reports back to the launch.
+#[derive(Default)]
+struct Shared {
+    /// Unix seconds of the first change; 0 while unchanged.
+    changed_at: AtomicU64,
+    /// Set in `auto` mode once the container was stopped for a relaunch.
+    replaced: AtomicBool,
+    done: AtomicBool,
+}
+
+pub struct DockerfileWatch {
+    shared: Arc<Shared>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+/// Whether the agent notified for `session_id` at or after `since`, i.e.
+/// went idle after the Dockerfile changed.
+fn idle_since(config_dir: &Path, session_id: &str, since: u64) -> bool {
+    events::load(config_dir).iter().rev().any(|e| {
+        e.event == EventKind::Notification && e.ts >= since && e.session_id.as_deref() == Some(session_id)
+    })
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Stop the session container so `ai-pod` relaunches it; `run --rm -it`
+/// (or the `attach` after a startup probe) returns once it has stopped.
+fn stop_container(rt: &ContainerRuntime, container: &str) {
+    let _ = rt
+        .command()
+        .args(["stop", "-t", "10", container])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+}
+
+impl DockerfileWatch {
+    pub fn start(
+        rt: &ContainerRuntime,
+        config_dir: &Path,
+        dockerfile: &Path,
+        session_id: &str,
+        container: &str,
+        mode: WatchMode,
+    ) -> Self {
+        let shared = Arc::new(Shared::default());
+        let initial = std::fs::read(dockerfile).ok();
+        let (rt, config_dir, dockerfile) = (rt.clone(), config_dir.to_path_buf(), dockerfile.to_path_buf());
+        let (session_id, container) = (session_id.to_string(), container.to_string());
+        let thread_shared = Arc::clone(&shared);
+        let handle = std::thread::spawn(move || {
+            let shared = thread_shared;
+            while !shared.done.load(Ordering::Relaxed) {
+                std::thread::sleep(POLL);
+                let changed_at = shared.changed_at.load(Ordering::Relaxed);
+                if changed_at == 0 {
+                    // Editors save by rename; a missing file is mid-save.
+                    match std::fs::read(&dockerfile) {
+                        Ok(now_bytes) if Some(&now_bytes) != initial.as_ref() => {
+                            shared.changed_at.store(now(), Ordering::Relaxed);
+                            announce(&config_dir, &dockerfile, mode);
+                        }
+                        _ => {}
+                    }
+                } else if mode == WatchMode::Auto && idle_since(&config_dir, &session_id, changed_at) {
+                    shared.replaced.store(true, Ordering::Relaxed);
+                    stop_container(&rt, &container);
+                    return;
+                }
+            }
+        });
+        DockerfileWatch {
+            shared,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop watching once the session has exited. Returns whether to rebuild
+    /// and relaunch: always after an `auto` replacement, otherwise when the
+    /// Dockerfile changed and the user confirms.
+    pub fn finish(mut self) -> bool {
+        self.shared.done.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        if self.shared.replaced.load(Ordering::Relaxed) {
+            eprintln!(
+                "{}",
+                "ai-pod.Dockerfile changed: rebuilding and relaunching the session.".blue().bold()
+            );
+            return true;
+        }
+        if self.shared.changed_at.load(Ordering::Relaxed) == 0 || !crate::is_stdin_tty() {
+            return false;
+        }
+        dialoguer::Confirm::new()
+            .with_prompt("ai-pod.Dockerfile changed during the session. Rebuild the image and relaunch?")
+            .default(true)
+            .interact()
+            .unwrap_or(false)
+    }
+}
+
+/// Let the user know the Dockerfile changed and what happens next.
+fn announce(config_dir: &Path, dockerfile: &Path, mode: WatchMode) {
+    let workspace: PathBuf = dockerfile.parent().map(Path::to_path_buf).unwrap_or_default();
+    let message = match mode {
+        WatchMode::Auto => "ai-pod.Dockerfile changed; the session will be relaunched with a rebuilt image when the agent is idle.",
+        WatchMode::Prompt => "ai-pod.Dockerfile changed; exit the session to rebuild and relaunch.",
+    };
+    crate::server::notify::announce(config_dir, &workspace, "message", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Event;
+    use tempfile::TempDir;
+
+    #[test]
+    fn idle_since_matches_later_notifications_for_the_session() {
+        let dir = TempDir::new().unwrap();
+        let ws = Path::new("/src/api");
+        let mut early = Event::new(EventKind::Notification, Some(ws));
+        early.ts = 100;
+        early.session_id = Some("aaaa1111".into());
+        events::record(dir.path(), &early);
+        assert!(!idle_since(dir.path(), "aaaa1111", 200));
+
+        let mut other = early.clone();
+        other.ts = 250;
+        other.session_id = Some("bbbb2222".into());
+        events::record(dir.path(), &other);
+        assert!(!idle_since(dir.path(), "aaaa1111", 200));
+
+        let mut stop = Event::new(EventKind::Stop, Some(ws));
+        stop.ts = 260;
+        stop.session_id = Some("aaaa1111".into());
+        events::record(dir.path(), &stop);
+        assert!(!idle_since(dir.path(), "aaaa1111", 200));
+
+        let mut later = early.clone();
+        later.ts = 300;
+        events::record(dir.path(), &later);
+        assert!(idle_since(dir.path(), "aaaa1111", 200));
+    }
+}
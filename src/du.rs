@@ -0,0 +1,441 @@
+//! `ai-pod du`: disk usage per workspace (image, home volume, mask volumes,
+//! container writable layers) plus the shared cache volumes, with hints on
+//! what `ai-pod clean` and friends would free.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use crate::config::AppConfig;
+use crate::runtime::ContainerRuntime;
+use crate::workspace::workspace_hash;
+
+/// What an `ai-pod-*` volume belongs to, going by its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VolumeKind {
+    Home(String),
+    Mask(String),
+    Cache(String),
+    Other,
+}
+
+fn classify_volume(name: &str) -> VolumeKind {
+    let Some(rest) = name.strip_prefix("ai-pod-") else {
+        return VolumeKind::Other;
+    };
+    if let Some(kind) = rest.strip_prefix("cache-") {
+        return VolumeKind::Cache(kind.to_string());
+    }
+    let Some((hash, tail)) = rest.split_once('-') else {
+        return VolumeKind::Other;
+    };
+    if hash.len() != 12 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return VolumeKind::Other;
+    }
+    if ["home", "state"].contains(&tail) || tail.starts_with("home-") || tail.starts_with("state-") {
+        VolumeKind::Home(hash.to_string())
+    } else if tail.starts_with("mask-") {
+        VolumeKind::Mask(hash.to_string())
+    } else {
+        VolumeKind::Other
+    }
+}
+
+/// Parse the human sizes `ps --size` prints (`12.3kB`, `1.5GB (virtual
+/// 2GB)`, `0B`). Decimal units, as both runtimes use; binary ones are
+/// accepted too.
+fn parse_human_size(s: &str) -> Option<u64> {
+    let s = s.split('(').next()?.trim();
+    let split = s.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (num, unit) = s.split_at(split);
+    let num: f64 = num.parse().ok()?;
+    let factor: f64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "b" => 1.0,
+        "kb" | "k" => 1e3,
+        "mb" | "m" => 1e6,
+        "gb" | "g" => 1e9,
+        "tb" | "t" => 1e12,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((num * factor) as u64)
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn format_opt(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(0) | None => "-".to_string(),
+        Some(b) => format_bytes(b),
+    }
+}
+
+/// `du -sk` output (`<KiB>\t/du/<volume>`) to bytes per volume.
+fn parse_du_output(out: &str) -> HashMap<String, u64> {
+    out.lines()
+        .filter_map(|line| {
+            let (kib, path) = line.split_once(char::is_whitespace)?;
+            let name = path.trim().strip_prefix("/du/")?;
+            Some((name.to_string(), kib.trim().parse::<u64>().ok()? * 1024))
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Row {
+    /// `None` for volumes/containers of a workspace ai-pod no longer knows.
+    workspace: Option<PathBuf>,
+    image_name: Option<String>,
+    image: Option<u64>,
+    home: Option<u64>,
+    masks: u64,
+    containers: u64,
+    /// Volume names, for the removal hint of unknown workspaces.
+    volumes: Vec<String>,
+}
+
+impl Row {
+    fn total(&self) -> u64 {
+        self.image.unwrap_or(0) + self.home.unwrap_or(0) + self.masks + self.containers
+    }
+
+    /// What `ai-pod clean` removes: containers, the home volume and masks.
+    fn cleanable(&self) -> u64 {
+        self.home.unwrap_or(0) + self.masks + self.containers
+    }
+}
+
+#[derive(Debug, Default)]
+struct Report {
+    rows: Vec<Row>,
+    caches: BTreeMap<String, u64>,
+}
+
+/// Group sizes by workspace hash. `volumes` are `(name, size)`; `None` size
+/// means it couldn't be measured.
+fn build_report(
+    workspaces: &[PathBuf],
+    images: &HashMap<PathBuf, (String, u64)>,
+    volumes: &[(String, Option<u64>)],
+    containers: &[(String, u64)],
+) -> Report {
+    let mut rows: BTreeMap<String, Row> = BTreeMap::new();
+    for ws in workspaces {
+        let row = rows.entry(workspace_hash(ws)).or_default();
+        row.workspace = Some(ws.clone());
+        if let Some((name, size)) = images.get(ws) {
+            row.image_name = Some(name.clone());
+            row.image = Some(*size);
+        }
+    }
+    let mut caches = BTreeMap::new();
+    for (name, size) in volumes {
+        match classify_volume(name) {
+            VolumeKind::Home(hash) => {
+                let row = rows.entry(hash).or_default();
+                row.home = Some(row.home.unwrap_or(0) + size.unwrap_or(0));
+                row.volumes.push(name.clone());
+            }
+            VolumeKind::Mask(hash) => {
+                let row = rows.entry(hash).or_default();
+                row.masks += size.unwrap_or(0);
+                row.volumes.push(name.clone());
+            }
+            VolumeKind::Cache(kind) => {
+                caches.insert(kind, size.unwrap_or(0));
+            }
+            VolumeKind::Other => {}
+        }
+    }
+    for (name, size) in containers {
+        if let Some(hash) = crate::workspace::project_id_from_container_name(name) {
+            rows.entry(hash).or_default().containers += size;
+        }
+    }
+    // Workspaces that have nothing on disk are noise.
+    let mut rows: Vec<Row> = rows.into_values().filter(|r| r.total() > 0).collect();
+    rows.sort_by_key(|r| std::cmp::Reverse(r.total()));
+    Report { rows, caches }
+}
+
+pub(crate) fn list_volumes(rt: &ContainerRuntime) -> Result<Vec<String>> {
+    let output = rt
+        .command()
+        .args(["volume", "ls", "--format", "{{.Name}}"])
+        .output()
+        .context("Failed to list volumes")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|n| n.starts_with("ai-pod-"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Measure all volumes with one throwaway container running `du`. Needs
+/// some local image with `du`; any workspace image will do.
+fn measure_volumes(rt: &ContainerRuntime, volumes: &[String], image: &str) -> HashMap<String, u64> {
+    if volumes.is_empty() {
+        return HashMap::new();
+    }
+    let mut args: Vec<String> = vec![
+        "run".into(),
+        "--rm".into(),
+        "--user".into(),
+        "0".into(),
+        "--network".into(),
+        "none".into(),
+    ];
+    for v in volumes {
+        args.push("-v".into());
+        args.push(format!("{}:/du/{}:ro", v, v));
+    }
+    args.extend(["--entrypoint".into(), "du".into(), image.into(), "-sk".into()]);
+    args.extend(volumes.iter().map(|v| format!("/du/{}", v)));
+    let Ok(output) = rt.command().args(&args).stderr(Stdio::null()).output() else {
+        return HashMap::new();
+    };
+    parse_du_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+pub(crate) fn container_sizes(rt: &ContainerRuntime) -> Result<Vec<(String, u64)>> {
+    let output = rt
+        .command()
+        .args([
+            "ps",
+            "-a",
+            "--size",
+            "--filter",
+            "label=managed-by=ai-pod",
+            "--format",
+            "{{.Names}}\t{{.Size}}",
+        ])
+        .output()
+        .context("Failed to list containers")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (name, size) = line.split_once('\t')?;
+            Some((name.to_string(), parse_human_size(size)?))
+        })
+        .collect())
+}
+
+fn image_size(rt: &ContainerRuntime, image: &str) -> Option<u64> {
+    let output = rt
+        .command()
+        .args(["image", "inspect", "--format", "{{.Size}}", image])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Workspace images with their sizes, and every ai-pod volume with its
+/// size when it could be measured.
+pub(crate) type Measured = (Vec<(String, Option<u64>)>, HashMap<PathBuf, (String, u64)>);
+
+pub(crate) fn measured_volumes(rt: &ContainerRuntime, workspaces: &[PathBuf]) -> Result<Measured> {
+    let mut images = HashMap::new();
+    for ws in workspaces {
+        let name = crate::image::image_name(ws);
+        if let Some(size) = image_size(rt, &name) {
+            images.insert(ws.clone(), (name, size));
+        }
+    }
+    let volume_names = list_volumes(rt)?;
+    let measured = match images.values().next() {
+        Some((image, _)) => measure_volumes(rt, &volume_names, image),
+        None => HashMap::new(),
+    };
+    if !volume_names.is_empty() && measured.is_empty() {
+        eprintln!(
+            "{} couldn't measure volume sizes (no workspace image to run `du` in); they show as -",
+            "Note:".yellow().bold()
+        );
+    }
+    let volumes: Vec<(String, Option<u64>)> = volume_names
+        .into_iter()
+        .map(|v| {
+            let size = measured.get(&v).copied();
+            (v, size)
+        })
+        .collect();
+    Ok((volumes, images))
+}
+
+pub fn run(rt: &ContainerRuntime, config: &AppConfig) -> Result<()> {
+    let workspaces = crate::registry::known_workspaces(config);
+    let (volumes, images) = measured_volumes(rt, &workspaces)?;
+    let report = build_report(&workspaces, &images, &volumes, &container_sizes(rt)?);
+    print_report(rt, &report);
+    Ok(())
+}
+
+fn print_report(rt: &ContainerRuntime, report: &Report) {
+    if report.rows.is_empty() && report.caches.is_empty() {
+        println!("{}", "ai-pod isn't using any disk space yet.".yellow());
+        return;
+    }
+    println!(
+        "{:<40} {:>9} {:>9} {:>9} {:>11} {:>9}",
+        "WORKSPACE", "IMAGE", "HOME", "MASKS", "CONTAINERS", "TOTAL"
+    );
+    let mut total = 0;
+    for row in &report.rows {
+        let label = match &row.workspace {
+            Some(ws) => ws.display().to_string(),
+            None => "(unknown workspace)".to_string(),
+        };
+        println!(
+            "{:<40} {:>9} {:>9} {:>9} {:>11} {:>9}",
+            label,
+            format_opt(row.image),
+            format_opt(row.home),
+            format_opt(Some(row.masks)),
+            format_opt(Some(row.containers)),
+            format_bytes(row.total()).bold()
+        );
+        total += row.total();
+    }
+    if !report.caches.is_empty() {
+        let caches: Vec<String> = report
+            .caches
+            .iter()
+            .map(|(k, v)| format!("{} {}", k, format_opt(Some(*v))))
+            .collect();
+        println!("{:<40} {}", "Caches", caches.join(", "));
+        total += report.caches.values().sum::<u64>();
+    }
+    println!("{:<40} {}", "Total".bold(), format_bytes(total).bold());
+
+    let mut hints = Vec::new();
+    for row in &report.rows {
+        match &row.workspace {
+            Some(ws) if row.cleanable() > 0 => hints.push((
+                format!("ai-pod clean --workdir {}", ws.display()),
+                row.cleanable(),
+                "home volume, masks and containers",
+            )),
+            None if !row.volumes.is_empty() => hints.push((
+                format!("{} volume rm {}", rt.cmd(), row.volumes.join(" ")),
+                row.cleanable(),
+                "workspace no longer known",
+            )),
+            _ => {}
+        }
+        if let (Some(name), Some(size)) = (&row.image_name, row.image) {
+            hints.push((format!("{} rmi {}", rt.cmd(), name), size, "rebuilt on next launch"));
+        }
+    }
+    let cache_total: u64 = report.caches.values().sum();
+    if cache_total > 0 {
+        hints.push(("ai-pod cache clear".to_string(), cache_total, "refilled on demand"));
+    }
+    if !hints.is_empty() {
+        println!();
+        println!("{}", "Reclaimable:".blue().bold());
+        for (cmd, size, why) in hints {
+            println!("  {:<60} {:>9}  {}", cmd, format_bytes(size), why.dimmed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn parse_human_size_handles_runtime_formats() {
+        assert_eq!(parse_human_size("0B"), Some(0));
+        assert_eq!(parse_human_size("12.3kB (virtual 800MB)"), Some(12_300));
+        assert_eq!(parse_human_size("1.5GB"), Some(1_500_000_000));
+        assert_eq!(parse_human_size("2 MiB"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_human_size("lots"), None);
+    }
+
+    #[test]
+    fn format_bytes_uses_decimal_units() {
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(12_300), "12.3 kB");
+        assert_eq!(format_bytes(1_500_000_000), "1.5 GB");
+    }
+
+    #[test]
+    fn classify_volume_by_name() {
+        assert_eq!(
+            classify_volume("ai-pod-3f2a9c1b7e4d-home"),
+            VolumeKind::Home("3f2a9c1b7e4d".into())
+        );
+        assert_eq!(
+            classify_volume("ai-pod-3f2a9c1b7e4d-home-3ba2896f"),
+            VolumeKind::Home("3f2a9c1b7e4d".into())
+        );
+        assert_eq!(
+            classify_volume("ai-pod-3f2a9c1b7e4d-state"),
+            VolumeKind::Home("3f2a9c1b7e4d".into())
+        );
+        assert_eq!(
+            classify_volume("ai-pod-3f2a9c1b7e4d-mask-node_modules"),
+            VolumeKind::Mask("3f2a9c1b7e4d".into())
+        );
+        assert_eq!(classify_volume("ai-pod-cache-npm"), VolumeKind::Cache("npm".into()));
+        assert_eq!(classify_volume("ai-pod-other-home"), VolumeKind::Other);
+        assert_eq!(classify_volume("pgdata"), VolumeKind::Other);
+    }
+
+    #[test]
+    fn parse_du_output_maps_volumes_to_bytes() {
+        let out = "4\t/du/ai-pod-cache-npm\n300\t/du/ai-pod-3f2a9c1b7e4d-home\ngarbage\n";
+        let sizes = parse_du_output(out);
+        assert_eq!(sizes.get("ai-pod-cache-npm"), Some(&4096));
+        assert_eq!(sizes.get("ai-pod-3f2a9c1b7e4d-home"), Some(&307_200));
+        assert_eq!(sizes.len(), 2);
+    }
+
+    #[test]
+    fn build_report_groups_by_workspace_and_flags_unknown() {
+        let ws = PathBuf::from("/src/api");
+        let hash = workspace_hash(&ws);
+        let images = HashMap::from([(ws.clone(), ("api-abc123".to_string(), 1_000))]);
+        let volumes = vec![
+            (format!("ai-pod-{}-home", hash), Some(300)),
+            (format!("ai-pod-{}-mask-node_modules", hash), Some(50)),
+            ("ai-pod-0123456789ab-home".to_string(), Some(70)),
+            ("ai-pod-cache-cargo".to_string(), Some(9)),
+        ];
+        let containers = vec![
+            (format!("ai-pod-{}-a1b2c3d4", hash), 5),
+            ("ai-pod-svc-db".to_string(), 100),
+        ];
+        let report = build_report(std::slice::from_ref(&ws), &images, &volumes, &containers);
+        assert_eq!(report.rows.len(), 2);
+        let api = &report.rows[0];
+        assert_eq!(api.workspace.as_deref(), Some(Path::new("/src/api")));
+        assert_eq!((api.image, api.home, api.masks, api.containers), (Some(1_000), Some(300), 50, 5));
+        assert_eq!(api.cleanable(), 355);
+        let orphan = &report.rows[1];
+        assert_eq!(orphan.workspace, None);
+        assert_eq!(orphan.volumes, vec!["ai-pod-0123456789ab-home"]);
+        assert_eq!(report.caches.get("cargo"), Some(&9));
+    }
+}
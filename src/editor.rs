@@ -0,0 +1,242 @@
+//! Open the files an agent changed in the host editor. Changes are read from
+//! git in the workspace (tracked modifications plus untracked files), each
+//! with the first changed line, and handed to `editor.command` from
+//! `~/.ai-pod/config.json` — e.g. `["code", "--goto", "{file}:{line}"]` or
+//! `["nvim", "--server", "/tmp/nvim.sock", "--remote"]`. Used when the agent
+//! stops (with `editor.open_on_stop`) and by `ai-pod changes --open`.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::config::{EditorConfig, GlobalConfig};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedFile {
+    /// Path relative to the workspace.
+    pub path: String,
+    /// First changed line (1 for new files).
+    pub line: u32,
+}
+
+/// Paths from `git status --porcelain -z`, skipping deletions and
+/// `.ai-pod/`. Renames report the new path (the old one follows as a
+/// separate entry).
+fn parse_status(out: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut entries = out.split('\0').filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = entry.split_at(3);
+        if code.starts_with('R') || code.starts_with('C') {
+            entries.next();
+        }
+        // ai-pod's own command output isn't the agent's work.
+        if code.contains('D') || path.ends_with('/') || path.starts_with(".ai-pod/") {
+            continue;
+        }
+        files.push(path.to_string());
+    }
+    files
+}
+
+/// First line of the new side from `git diff -U0` hunk headers
+/// (`@@ -a,b +c,d @@`), per file.
+fn parse_first_lines(diff: &str) -> Vec<(String, u32)> {
+    let mut out: Vec<(String, u32)> = Vec::new();
+    let mut current: Option<String> = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ b/") {
+            current = Some(path.to_string());
+        } else if let Some(rest) = line.strip_prefix("@@ ")
+            && let Some(path) = current.take()
+        {
+            let start = rest
+                .split_whitespace()
+                .find_map(|f| f.strip_prefix('+'))
+                .and_then(|f| f.split(',').next())
+                .and_then(|n| n.parse::<u32>().ok())
+                .unwrap_or(1);
+            out.push((path, start.max(1)));
+        }
+    }
+    out
+}
+
+fn git(workspace: &Path, args: &[&str]) -> Result<String> {
+    crate::host_git::output(workspace, args)
+        .ok_or_else(|| anyhow::anyhow!("{} is not a git repository", workspace.display()))
+}
+
+/// Files changed in the workspace relative to `HEAD`, including untracked
+/// ones, sorted by path.
+pub fn changed_files(workspace: &Path) -> Result<Vec<ChangedFile>> {
+    let paths = parse_status(&git(
+        workspace,
+        &["status", "--porcelain=v1", "-z", "--untracked-files=all", "--ignore-submodules=all"],
+    )?);
+    // An unborn HEAD (fresh repo) has nothing to diff against.
+    let diff = git(
+        workspace,
+        &["diff", "-U0", "--no-color", "--no-ext-diff", "--no-textconv", "--ignore-submodules=all", "HEAD"],
+    )
+    .unwrap_or_default();
+    let lines = parse_first_lines(&diff);
+    let mut files: Vec<ChangedFile> = paths
+        .into_iter()
+        .map(|path| {
+            let line = lines.iter().find(|(p, _)| *p == path).map(|(_, l)| *l).unwrap_or(1);
+            ChangedFile { path, line }
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup();
+    Ok(files)
+}
+
+/// Argv lists to run for `files`. With `{file}`/`{line}` in the template the
+/// command runs once per file; otherwise all paths are appended to one call.
+pub fn opener_commands(template: &[String], workspace: &Path, files: &[ChangedFile]) -> Vec<Vec<String>> {
+    if template.is_empty() || files.is_empty() {
+        return Vec::new();
+    }
+    let abs = |f: &ChangedFile| workspace.join(&f.path).to_string_lossy().to_string();
+    if template.iter().any(|a| a.contains("{file}")) {
+        files
+            .iter()
+            .map(|f| {
+                template
+                    .iter()
+                    .map(|a| a.replace("{file}", &abs(f)).replace("{line}", &f.line.to_string()))
+                    .collect()
+            })
+            .collect()
+    } else {
+        let mut argv = template.to_vec();
+        argv.extend(files.iter().map(abs));
+        vec![argv]
+    }
+}
+
+/// Run the opener for `files`. Errors when `editor.command` isn't set.
+pub fn open(config: &EditorConfig, workspace: &Path, files: &[ChangedFile]) -> Result<()> {
+    let Some(template) = config.command.as_ref().filter(|c| !c.is_empty()) else {
+        anyhow::bail!(
+            "No editor configured. Set editor.command in ~/.ai-pod/config.json, e.g.\n  \
+             ai-pod config set editor.command '[\"code\", \"--goto\", \"{{file}}:{{line}}\"]'"
+        );
+    };
+    for argv in opener_commands(template, workspace, files) {
+        let status = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .status()
+            .with_context(|| format!("Failed to run {}", argv[0]))?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {}", argv[0], status);
+        }
+    }
+    Ok(())
+}
+
+/// Called by the server when an agent stops: with `editor.open_on_stop`,
+/// collect the changed files and open them. Returns the changed paths for
+/// the event log; `None` when it's off or outside a git repository.
+pub fn on_stop(config_dir: &Path, workspace: &Path) -> Option<Vec<String>> {
+    let editor = GlobalConfig::load_from_dir(config_dir).editor;
+    // Any agent can post a stop, so host git only runs when asked for.
+    if !editor.open_on_stop.unwrap_or(false) {
+        return None;
+    }
+    let files = changed_files(workspace).ok()?;
+    if !files.is_empty()
+        && let Err(e) = open(&editor, workspace, &files)
+    {
+        eprintln!("[editor] {e:#}");
+    }
+    Some(files.into_iter().map(|f| f.path).collect())
+}
+
+/// `ai-pod changes [--open]`.
+pub fn run(config_dir: &Path, workspace: &Path, open_files: bool) -> Result<()> {
+    let files = changed_files(workspace)?;
+    if files.is_empty() {
+        println!("{}", "No changes.".yellow());
+        return Ok(());
+    }
+    for f in &files {
+        println!("{}:{}", f.path, f.line.to_string().dimmed());
+    }
+    if open_files {
+        open(&GlobalConfig::load_from_dir(config_dir).editor, workspace, &files)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_status_skips_deletions_and_follows_renames() {
+        let out = " M src/main.rs\0?? notes.md\0?? .ai-pod/commands/s1/c1/stdout\0 D gone.rs\0R  new.rs\0old.rs\0A  added.rs\0";
+        assert_eq!(parse_status(out), vec!["src/main.rs", "notes.md", "new.rs", "added.rs"]);
+    }
+
+    #[test]
+    fn parse_first_lines_reads_hunk_headers() {
+        let diff = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -10,2 +12,3 @@ fn x()\n+x\n@@ -40 +41 @@\n+y\n\
+                    diff --git a/b.rs b/b.rs\n--- a/b.rs\n+++ b/b.rs\n@@ -1 +0,0 @@\n-z\n";
+        assert_eq!(
+            parse_first_lines(diff),
+            vec![("src/a.rs".to_string(), 12), ("b.rs".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn opener_commands_per_file_or_appended() {
+        let files = vec![
+            ChangedFile { path: "a.rs".into(), line: 3 },
+            ChangedFile { path: "b.rs".into(), line: 1 },
+        ];
+        let ws = Path::new("/src/api");
+        let per_file: Vec<String> = vec!["code".into(), "--goto".into(), "{file}:{line}".into()];
+        assert_eq!(
+            opener_commands(&per_file, ws, &files),
+            vec![
+                vec!["code", "--goto", "/src/api/a.rs:3"],
+                vec!["code", "--goto", "/src/api/b.rs:1"],
+            ]
+        );
+        let appended: Vec<String> = vec!["nvim".into(), "--remote".into()];
+        assert_eq!(
+            opener_commands(&appended, ws, &files),
+            vec![vec!["nvim", "--remote", "/src/api/a.rs", "/src/api/b.rs"]]
+        );
+    }
+
+    #[test]
+    fn changed_files_reads_a_real_repo() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ws = dir.path();
+        let git = |args: &[&str]| {
+            assert!(Command::new("git").arg("-C").arg(ws).args(args).output().unwrap().status.success());
+        };
+        git(&["init", "-q"]);
+        std::fs::write(ws.join("a.txt"), "1\n2\n3\n").unwrap();
+        git(&["add", "."]);
+        git(&["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-qm", "init"]);
+        std::fs::write(ws.join("a.txt"), "1\n2\nthree\n").unwrap();
+        std::fs::write(ws.join("new.txt"), "x\n").unwrap();
+        assert_eq!(
+            changed_files(ws).unwrap(),
+            vec![
+                ChangedFile { path: "a.txt".into(), line: 3 },
+                ChangedFile { path: "new.txt".into(), line: 1 },
+            ]
+        );
+    }
+}
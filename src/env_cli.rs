@@ -0,0 +1,72 @@
+//! Host-side `ai-pod env`: print what the next launch of a workspace would
+//! use — environment, mounts, network, security options and any entrypoint
+//! or command override — with where each entry comes from, to untangle
+//! global config, project settings and flags.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+use crate::config::AppConfig;
+use crate::container::{LaunchOptions, LaunchPreview};
+use crate::runtime::ContainerRuntime;
+
+fn section(out: &mut Vec<String>, title: &str, entries: &[(String, &str)]) {
+    out.push(format!("{}", title.blue().bold()));
+    if entries.is_empty() {
+        out.push(format!("  {}", "(none)".dimmed()));
+    }
+    for (value, source) in entries {
+        out.push(format!("  {:<60} {}", value, source.dimmed()));
+    }
+}
+
+fn lines(preview: &LaunchPreview) -> Vec<String> {
+    let mut out = vec![format!("{} {}", "Image:".blue().bold(), preview.image)];
+    section(&mut out, "Environment", &preview.env);
+    section(&mut out, "Mounts", &preview.mounts);
+    section(&mut out, "Network", &preview.network);
+    section(&mut out, "Security", &preview.security);
+    if !preview.command.is_empty() {
+        section(&mut out, "Command", &preview.command);
+    }
+    if preview.relaxed {
+        out.push(format!(
+            "{} security is relaxed for this workspace (`ai-pod security enforce` undoes it)",
+            "Note:".yellow().bold()
+        ));
+    }
+    out
+}
+
+pub fn run(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path, opts: &LaunchOptions) -> Result<()> {
+    let image = crate::image::image_name(workspace);
+    let preview = crate::container::launch_preview(rt, config, workspace, &image, opts)?;
+    for line in lines(&preview) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_group_entries_with_their_source() {
+        let preview = LaunchPreview {
+            image: "api-3f9c2a".into(),
+            env: vec![("TZ=Europe/Berlin".into(), "host")],
+            mounts: vec![("/src/api:/app:Z".into(), "workspace")],
+            relaxed: true,
+            ..Default::default()
+        };
+        let out = lines(&preview);
+        assert!(out[0].ends_with("api-3f9c2a"));
+        assert!(out[1].contains("Environment"));
+        assert!(out[2].starts_with("  TZ=Europe/Berlin ") && out[2].contains("host"));
+        assert!(out[5].contains("Network"));
+        assert!(out[6].contains("(none)"));
+        assert!(out.last().unwrap().contains("relaxed"));
+    }
+}
@@ -0,0 +1,244 @@
+//! Machine-readable lifecycle event log: one JSON object per line in
+//! `~/.ai-pod/events.log`, appended when images are built, containers are
+//! launched, attached to, stopped or cleaned, and when an agent sends a
+//! notification. Meant for external tooling (dashboards, time trackers);
+//! writes are best-effort and never fail the command that triggered them.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+pub const EVENTS_FILE: &str = "events.log";
+
+/// Once the log grows past this, it is moved to `events.log.1` (replacing
+/// the previous one) and a fresh file is started.
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Build,
+    Launch,
+    Attach,
+    Stop,
+    Clean,
+    Notification,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Event {
+    /// Unix time in seconds.
+    pub ts: u64,
+    pub event: EventKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Workspace hash, as used in container and volume names.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Container exit code on `stop`; absent when it was killed by a signal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Notification text on `notification`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Workspace files changed (per git) when the agent stopped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<String>>,
+    /// The session's label on `launch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl Event {
+    pub fn new(event: EventKind, workspace: Option<&Path>) -> Self {
+        Event {
+            ts: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            event,
+            workspace: workspace.map(|w| w.to_string_lossy().to_string()),
+            project_id: workspace.map(crate::workspace::workspace_hash),
+            session_id: None,
+            container: None,
+            image: None,
+            exit_code: None,
+            message: None,
+            files: None,
+            title: None,
+        }
+    }
+
+    pub fn session(mut self, session_id: &str, container: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self.container = Some(container.to_string());
+        self
+    }
+
+    pub fn image(mut self, image: &str) -> Self {
+        self.image = Some(image.to_string());
+        self
+    }
+}
+
+pub fn events_file(config_dir: &Path) -> PathBuf {
+    config_dir.join(EVENTS_FILE)
+}
+
+/// Append `event` to the log, and to the usage statistics if they are on.
+/// Errors are swallowed: the log is a side channel and must not break a
+/// launch or a notification.
+pub fn record(config_dir: &Path, event: &Event) {
+    let _ = append(&events_file(config_dir), event);
+    crate::stats::record(config_dir, event);
+}
+
+/// Events in the current log, oldest first; unreadable lines are skipped.
+pub fn load(config_dir: &Path) -> Vec<Event> {
+    std::fs::read_to_string(events_file(config_dir))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+/// Events in the rotated log followed by the current one, oldest first, for
+/// reports that look further back than the current file.
+pub fn load_all(config_dir: &Path) -> Vec<Event> {
+    let mut rotated = events_file(config_dir).into_os_string();
+    rotated.push(".1");
+    std::fs::read_to_string(rotated)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .chain(load(config_dir))
+        .collect()
+}
+
+/// Notifications in `events`, newest first, limited to `workspace` if given.
+pub fn notifications<'a>(events: &'a [Event], workspace: Option<&Path>) -> Vec<&'a Event> {
+    let project_id = workspace.map(crate::workspace::workspace_hash);
+    events
+        .iter()
+        .rev()
+        .filter(|e| e.event == EventKind::Notification)
+        .filter(|e| project_id.is_none() || e.project_id == project_id)
+        .collect()
+}
+
+/// `ai-pod notifications`: recent agent notifications, for one workspace or
+/// (`workspace` = `None`) all of them with the project each came from.
+pub fn print_notifications(config_dir: &Path, workspace: Option<&Path>, limit: usize) {
+    use colored::Colorize;
+
+    let all = load(config_dir);
+    let shown: Vec<&Event> = notifications(&all, workspace).into_iter().take(limit).collect();
+    if shown.is_empty() {
+        println!("No notifications.");
+        return;
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    for e in shown.into_iter().rev() {
+        let project = match (workspace, &e.workspace) {
+            (None, Some(ws)) => format!("{:<20} ", crate::registry::workspace_label(config_dir, Path::new(ws))),
+            _ => String::new(),
+        };
+        println!(
+            "{:>10}  {}{}",
+            crate::registry::format_age(now, e.ts).dimmed(),
+            project.blue(),
+            e.message.as_deref().unwrap_or_default()
+        );
+    }
+}
+
+fn append(path: &Path, event: &Event) -> std::io::Result<()> {
+    append_json_line(path, event)
+}
+
+/// Append `value` as one JSON line to the log at `path`, rotating it to
+/// `<path>.1` first once it has reached `MAX_LOG_BYTES`. Shared with the
+/// server's access log.
+pub(crate) fn append_json_line<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    if std::fs::metadata(path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+        let mut rotated = path.as_os_str().to_owned();
+        rotated.push(".1");
+        std::fs::rename(path, rotated)?;
+    }
+    let mut line = serde_json::to_string(value)?;
+    line.push('\n');
+    // A single write of one line with O_APPEND keeps concurrent writers (the
+    // CLI and the shared server) from interleaving within a line.
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn record_appends_one_json_line_per_event() {
+        let dir = TempDir::new().unwrap();
+        let ws = Path::new("/home/u/proj");
+        record(dir.path(), &Event::new(EventKind::Build, Some(ws)).image("proj-abc"));
+        let mut stop = Event::new(EventKind::Stop, Some(ws)).session("s1", "ai-pod-proj-s1");
+        stop.exit_code = Some(0);
+        record(dir.path(), &stop);
+
+        let raw = std::fs::read_to_string(events_file(dir.path())).unwrap();
+        let lines: Vec<&str> = raw.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["event"], "build");
+        assert_eq!(first["workspace"], "/home/u/proj");
+        assert_eq!(first["project_id"], crate::workspace::workspace_hash(ws));
+        assert!(first.get("session_id").is_none());
+        let second: Event = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(second, stop);
+    }
+
+    #[test]
+    fn append_rotates_large_log() {
+        let dir = TempDir::new().unwrap();
+        let path = events_file(dir.path());
+        std::fs::write(&path, vec![b'x'; MAX_LOG_BYTES as usize]).unwrap();
+        append(&path, &Event::new(EventKind::Clean, None)).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        assert!(dir.path().join("events.log.1").exists());
+    }
+
+    #[test]
+    fn notifications_filter_by_workspace_newest_first() {
+        let (api, web) = (Path::new("/src/api"), Path::new("/src/web"));
+        let note = |ws, msg: &str| {
+            let mut e = Event::new(EventKind::Notification, Some(ws));
+            e.message = Some(msg.into());
+            e
+        };
+        let events = vec![
+            note(api, "first"),
+            Event::new(EventKind::Launch, Some(api)),
+            note(web, "web"),
+            note(api, "second"),
+        ];
+        let msgs = |v: Vec<&Event>| v.iter().map(|e| e.message.clone().unwrap()).collect::<Vec<_>>();
+        assert_eq!(msgs(notifications(&events, Some(api))), vec!["second", "first"]);
+        assert_eq!(msgs(notifications(&events, None)), vec!["second", "web", "first"]);
+    }
+}
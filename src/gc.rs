@@ -0,0 +1,57 @@
+//! `ai-pod gc`: remove what ai-pod keeps for workspaces whose directory no
+//! longer exists (deleted or moved checkouts): their containers, volumes,
+//! image, project state and registry entry.
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::config::AppConfig;
+use crate::container::{CleanOptions, clean_container};
+use crate::registry::{WorkspaceRegistry, missing_workspaces};
+use crate::runtime::ContainerRuntime;
+
+pub fn run(rt: &ContainerRuntime, config: &AppConfig, yes: bool) -> Result<()> {
+    let missing = missing_workspaces(config);
+    if missing.is_empty() {
+        println!("{}", "Every known workspace still exists; nothing to collect.".green());
+        return Ok(());
+    }
+    println!("{}", "Workspaces that no longer exist:".blue().bold());
+    for ws in &missing {
+        println!("  {}", ws.display());
+    }
+    if !yes {
+        if !crate::is_stdin_tty() {
+            anyhow::bail!("Pass --yes to remove them without a prompt");
+        }
+        let confirmed = dialoguer::Confirm::new()
+            .with_prompt("Remove their containers, volumes, images and state?")
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+        if !confirmed {
+            println!("{}", "Aborted.".red());
+            return Ok(());
+        }
+    }
+    for ws in &missing {
+        println!("{} {}", "Collecting:".blue().bold(), ws.display());
+        let opts = CleanOptions {
+            containers: true,
+            volumes: true,
+            image: true,
+        };
+        if let Err(e) = clean_container(rt, config, ws, opts) {
+            eprintln!("{} {:#}; keeping its registry entry", "warning:".yellow().bold(), e);
+            continue;
+        }
+        if rt.dry_run {
+            continue;
+        }
+        let _ = std::fs::remove_file(config.project_state_file(&crate::workspace::workspace_hash(ws)));
+        let mut reg = WorkspaceRegistry::load(config);
+        reg.unregister(ws);
+        reg.save(config)?;
+    }
+    Ok(())
+}
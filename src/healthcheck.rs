@@ -0,0 +1,220 @@
+//! Startup probe for launched sessions. The session container is started
+//! detached with a healthcheck, ai-pod waits until the probe passes inside
+//! it, and only then attaches the terminal. An image whose entrypoint dies
+//! straight away then fails with its exit code and last log lines instead
+//! of a terminal that closes without a word.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::HealthcheckConfig;
+use crate::runtime::ContainerRuntime;
+
+/// Passes once the workspace is mounted and an agent is on the `PATH`.
+pub const DEFAULT_COMMAND: &str = "test -d /app && { command -v claude || command -v opencode; } >/dev/null";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// How often the probe runs while waiting.
+const POLL: Duration = Duration::from_millis(250);
+
+/// Log lines shown when the container exits before it's ready.
+const LOG_LINES: &str = "20";
+
+impl HealthcheckConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn probe(&self) -> &str {
+        self.command.as_deref().unwrap_or(DEFAULT_COMMAND)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+}
+
+/// `--health-*` flags, so `ps` and `inspect` report the session's health
+/// for as long as it runs.
+pub fn run_args(config: &HealthcheckConfig) -> Vec<String> {
+    [
+        "--health-cmd",
+        config.probe(),
+        "--health-interval",
+        "30s",
+        "--health-retries",
+        "3",
+        "--health-start-period",
+        "5s",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// What `inspect` says about a container that isn't ready yet.
+#[derive(Debug, PartialEq, Eq)]
+enum State {
+    Running,
+    Starting,
+    Exited(Option<i32>),
+}
+
+/// Parse `{{.State.Status}} {{.State.ExitCode}}`.
+fn parse_state(output: &str) -> State {
+    let mut fields = output.split_whitespace();
+    match fields.next() {
+        Some("running") => State::Running,
+        Some("created") | Some("configured") | Some("initialized") => State::Starting,
+        _ => State::Exited(fields.next().and_then(|c| c.parse().ok())),
+    }
+}
+
+fn state(rt: &ContainerRuntime, container: &str) -> State {
+    rt.command()
+        .args(["inspect", "--format", "{{.State.Status}} {{.State.ExitCode}}", container])
+        .stderr(Stdio::null())
+        .output()
+        .map(|o| parse_state(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or(State::Exited(None))
+}
+
+fn probe_passes(rt: &ContainerRuntime, container: &str, probe: &str) -> bool {
+    rt.command()
+        .args(["exec", container, "sh", "-c", probe])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+fn last_logs(rt: &ContainerRuntime, container: &str) -> String {
+    rt.command()
+        .args(["logs", "--tail", LOG_LINES, container])
+        .output()
+        .map(|o| {
+            let mut logs = String::from_utf8_lossy(&o.stdout).into_owned();
+            logs.push_str(&String::from_utf8_lossy(&o.stderr));
+            logs.trim_end().to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// Wait until `probe` passes in `container`, erroring if the container
+/// exits first or the timeout runs out.
+fn wait_ready(rt: &ContainerRuntime, container: &str, config: &HealthcheckConfig) -> Result<()> {
+    let deadline = Instant::now() + config.timeout();
+    loop {
+        match state(rt, container) {
+            State::Exited(code) => {
+                let code = code.map_or_else(|| "unknown".to_string(), |c| c.to_string());
+                let logs = last_logs(rt, container);
+                anyhow::bail!(
+                    "The container exited (code {}) before it was ready; the image's ENTRYPOINT or CMD is probably broken.{}",
+                    code,
+                    if logs.is_empty() {
+                        String::new()
+                    } else {
+                        format!("\nLast output:\n{}", logs)
+                    }
+                );
+            }
+            State::Running if probe_passes(rt, container, config.probe()) => return Ok(()),
+            _ => {}
+        }
+        if Instant::now() >= deadline {
+            anyhow::bail!(
+                "The container didn't pass its startup probe within {}s (`{}`). Raise `healthcheck.timeout_secs`, change `healthcheck.command`, or set `healthcheck.enabled` to false.",
+                config.timeout().as_secs(),
+                config.probe()
+            );
+        }
+        std::thread::sleep(POLL);
+    }
+}
+
+/// Start the session with `run_cmd` (a detached `run` without `--rm`),
+/// wait for it to become ready, then attach until it exits. The container
+/// is removed afterwards unless the user detached from it.
+pub fn start_and_attach(
+    rt: &ContainerRuntime,
+    mut run_cmd: Command,
+    container: &str,
+    config: &HealthcheckConfig,
+    detach_keys: Option<&str>,
+) -> Result<ExitStatus> {
+    let output = run_cmd
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to run container")?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to start container {}", container);
+    }
+    let started = Instant::now();
+    let ready = wait_ready(rt, container, config);
+    if let Err(e) = ready {
+        remove(rt, container);
+        return Err(e);
+    }
+    if started.elapsed() > Duration::from_secs(3) {
+        eprintln!(
+            "{} {} ready after {}s",
+            "Started:".blue().bold(),
+            container,
+            started.elapsed().as_secs()
+        );
+    }
+    let status = crate::container::attach_child(rt, container, detach_keys);
+    if state(rt, container) == State::Running {
+        eprintln!(
+            "{} detached from {}; it keeps running. `ai-pod attach` returns to it.",
+            "Note:".yellow().bold(),
+            container
+        );
+    } else {
+        remove(rt, container);
+    }
+    status
+}
+
+fn remove(rt: &ContainerRuntime, container: &str) {
+    let _ = rt
+        .command()
+        .args(["rm", "--force", container])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_state_reads_status_and_exit_code() {
+        assert_eq!(parse_state("running 0\n"), State::Running);
+        assert_eq!(parse_state("created 0"), State::Starting);
+        assert_eq!(parse_state("exited 127\n"), State::Exited(Some(127)));
+        assert_eq!(parse_state(""), State::Exited(None));
+    }
+
+    #[test]
+    fn defaults_apply_until_configured() {
+        let config = HealthcheckConfig::default();
+        assert!(config.enabled());
+        assert_eq!(config.probe(), DEFAULT_COMMAND);
+        assert_eq!(config.timeout(), Duration::from_secs(30));
+        let config = HealthcheckConfig {
+            enabled: Some(false),
+            command: Some("claude --version".into()),
+            timeout_secs: Some(5),
+        };
+        assert!(!config.enabled());
+        let args = run_args(&config);
+        assert_eq!(&args[..2], &["--health-cmd", "claude --version"]);
+    }
+}
@@ -0,0 +1,220 @@
+//! Optional import of the host's Claude Code history into a new state
+//! volume. Seeding copies only settings and sign-in into the container (see
+//! `populate_home_volume`). With `seed.history` on, a workspace's first
+//! state volume also gets the conversation history from the host's
+//! `~/.claude`, so `claude --resume` and the prompt history carry on inside
+//! the container. The workspace's own sessions are filed under `/app`,
+//! where the container sees it.
+//!
+//! The history can run to hundreds of MB. Its size is shown up front and,
+//! above `seed.confirm_mb`, has to be confirmed. The copy is streamed into
+//! the volume as a tar archive behind a progress bar, and retried once when
+//! it fails; a failed import never blocks the launch.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use crate::config::{AppConfig, SeedConfig};
+use crate::container::HomeVolumes;
+use crate::runtime::ContainerRuntime;
+
+/// What makes up the history under `~/.claude`.
+const ENTRIES: &[&str] = &["history.jsonl", "projects", "todos", "file-history", "plans"];
+
+/// Size above which the import is confirmed first, unless `seed.confirm_mb`
+/// says otherwise.
+const DEFAULT_CONFIRM_MB: u64 = 200;
+
+const ATTEMPTS: usize = 2;
+
+/// Claude Code's directory name under `projects/` for sessions in `dir`.
+fn project_key(dir: &str) -> String {
+    dir.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect()
+}
+
+/// Where `rel` goes in the state volume: the workspace's sessions move from
+/// its host path's project directory to `/app`'s.
+fn target(rel: &Path, workspace: &Path) -> PathBuf {
+    let host = Path::new("projects").join(project_key(&workspace.to_string_lossy()));
+    match rel.strip_prefix(&host) {
+        Ok(rest) => Path::new("projects").join(project_key("/app")).join(rest),
+        Err(_) => rel.to_path_buf(),
+    }
+}
+
+/// Regular files of the history under `claude_dir`, relative to it, and
+/// their total size. Symlinks are left out: they may point anywhere on the
+/// host.
+fn collect(claude_dir: &Path) -> (Vec<PathBuf>, u64) {
+    let mut files = Vec::new();
+    let mut total = 0;
+    for entry in ENTRIES {
+        for file in walkdir::WalkDir::new(claude_dir.join(entry))
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            let (Ok(rel), Ok(meta)) = (file.path().strip_prefix(claude_dir), file.metadata()) else {
+                continue;
+            };
+            total += meta.len();
+            files.push(rel.to_path_buf());
+        }
+    }
+    (files, total)
+}
+
+/// Stream `files` into the state volume as a tar archive.
+fn copy(
+    rt: &ContainerRuntime,
+    claude_dir: &Path,
+    workspace: &Path,
+    files: &[PathBuf],
+    total: u64,
+    volumes: &HomeVolumes,
+    image: &str,
+) -> Result<()> {
+    let mut child = rt
+        .command()
+        .args(["run", "--rm", "-i"])
+        .args(volumes.user.run_args())
+        .args(["-v", &format!("{}:/to:z", volumes.state)])
+        .args(["--entrypoint", "tar", image, "-x", "-f", "-", "-C", "/to"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start the import container")?;
+    let mut stderr = child.stderr.take().context("no stderr")?;
+    let err = std::thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = stderr.read_to_string(&mut buf);
+        buf
+    });
+
+    let bar = ProgressBar::new(total);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner:.blue} [{bytes}/{total_bytes}] {wide_bar} {eta}")
+            .expect("valid progress template"),
+    );
+    bar.enable_steady_tick(Duration::from_millis(120));
+    let stdin = child.stdin.take().context("no stdin")?;
+    let mut archive = tar::Builder::new(bar.wrap_write(stdin));
+    archive.follow_symlinks(false);
+    let written = files
+        .iter()
+        .try_for_each(|rel| archive.append_path_with_name(claude_dir.join(rel), target(rel, workspace)))
+        .and_then(|()| archive.into_inner().map(drop));
+    bar.finish_and_clear();
+
+    let status = child.wait().context("Failed to wait for the import container")?;
+    let err = err.join().unwrap_or_default();
+    if !status.success() {
+        let reason = err.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("tar failed");
+        anyhow::bail!("{}", reason.trim());
+    }
+    written.context("Failed to send the history")
+}
+
+/// Copy the host's history into the newly created state volume, if
+/// `seed.history` is on. Best-effort: problems are reported, not returned.
+pub(crate) fn import(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    settings: &SeedConfig,
+    volumes: &HomeVolumes,
+    image: &str,
+) {
+    if !settings.history.unwrap_or(false) || rt.dry_run {
+        return;
+    }
+    let claude_dir = config.home_dir.join(".claude");
+    let (files, total) = collect(&claude_dir);
+    if files.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{} {} in {} files",
+        "Copying Claude Code history:".blue().bold(),
+        crate::du::format_bytes(total),
+        files.len()
+    );
+    let limit = settings.confirm_mb.unwrap_or(DEFAULT_CONFIRM_MB).saturating_mul(1_000_000);
+    if total > limit {
+        let confirmed = crate::is_stdin_tty()
+            && dialoguer::Confirm::new()
+                .with_prompt(format!(
+                    "Copy {} of history into the container?",
+                    crate::du::format_bytes(total)
+                ))
+                .default(true)
+                .interact()
+                .unwrap_or(false);
+        if !confirmed {
+            eprintln!(
+                "{} skipped the history; raise seed.confirm_mb or run interactively to copy it.",
+                "Note:".yellow().bold()
+            );
+            return;
+        }
+    }
+    for attempt in 1..=ATTEMPTS {
+        match copy(rt, &claude_dir, workspace, &files, total, volumes, image) {
+            Ok(()) => {
+                eprintln!("{}", "History copied.".green());
+                return;
+            }
+            Err(e) if attempt < ATTEMPTS => {
+                eprintln!("{} copying the history failed ({:#}); retrying", "warning:".yellow().bold(), e);
+            }
+            Err(e) => {
+                eprintln!(
+                    "{} copying the history failed ({:#}); the container starts without it",
+                    "warning:".yellow().bold(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_files_are_collected_and_filed_under_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let claude = dir.path();
+        std::fs::create_dir_all(claude.join("projects/-app")).unwrap();
+        std::fs::write(claude.join("projects/-app/a.jsonl"), "0123456789").unwrap();
+        std::fs::write(claude.join("history.jsonl"), "abc").unwrap();
+        std::fs::write(claude.join("settings.json"), "{}").unwrap();
+        std::fs::write(claude.join(".credentials.json"), "secret").unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", claude.join("projects/link")).unwrap();
+
+        let (mut files, total) = collect(claude);
+        files.sort();
+        assert_eq!(
+            files,
+            vec![PathBuf::from("history.jsonl"), PathBuf::from("projects/-app/a.jsonl")]
+        );
+        assert_eq!(total, 13);
+
+        let ws = Path::new("/home/me/src/my.app");
+        assert_eq!(
+            target(Path::new("projects/-home-me-src-my-app/s1.jsonl"), ws),
+            PathBuf::from("projects/-app/s1.jsonl")
+        );
+        assert_eq!(
+            target(Path::new("projects/-home-me-src-other/s1.jsonl"), ws),
+            PathBuf::from("projects/-home-me-src-other/s1.jsonl")
+        );
+    }
+}
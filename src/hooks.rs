@@ -0,0 +1,150 @@
+//! Host-side lifecycle hooks: shell commands from `hooks` in
+//! `~/.ai-pod/config.json` that run before the agent container starts and
+//! after it exits.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct HooksConfig {
+    /// Run in order before the container starts. A failing command aborts
+    /// the launch.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_launch: Vec<String>,
+    /// Run in order after the container exits. Failures only warn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_exit: Vec<String>,
+}
+
+impl HooksConfig {
+    pub fn is_empty(&self) -> bool {
+        self.pre_launch.is_empty() && self.post_exit.is_empty()
+    }
+}
+
+/// Describes the session to a hook through `AI_POD_*` environment variables.
+pub struct HookContext<'a> {
+    pub workspace: &'a Path,
+    pub project_id: &'a str,
+    pub session_id: &'a str,
+    pub container_name: &'a str,
+    pub image: &'a str,
+    /// Container exit code; only set for post-exit hooks. `None` there means
+    /// the container was killed by a signal.
+    pub exit_code: Option<i32>,
+}
+
+impl HookContext<'_> {
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("AI_POD_WORKSPACE", self.workspace.to_string_lossy().to_string()),
+            ("AI_POD_PROJECT_ID", self.project_id.to_string()),
+            ("AI_POD_SESSION_ID", self.session_id.to_string()),
+            ("AI_POD_CONTAINER", self.container_name.to_string()),
+            ("AI_POD_IMAGE", self.image.to_string()),
+        ];
+        if let Some(code) = self.exit_code {
+            env.push(("AI_POD_EXIT_CODE", code.to_string()));
+        }
+        env
+    }
+}
+
+fn run_one(cmd: &str, ctx: &HookContext<'_>) -> Result<bool> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .current_dir(ctx.workspace)
+        .envs(ctx.env())
+        .status()
+        .with_context(|| format!("Failed to run hook `{}`", cmd))?;
+    Ok(status.success())
+}
+
+/// Run the pre-launch hooks; the first failure aborts the launch.
+pub fn run_pre_launch(hooks: &HooksConfig, ctx: &HookContext<'_>) -> Result<()> {
+    for cmd in &hooks.pre_launch {
+        eprintln!("{} {}", "Pre-launch hook:".blue(), cmd);
+        if !run_one(cmd, ctx)? {
+            anyhow::bail!("Pre-launch hook failed: {}", cmd);
+        }
+    }
+    Ok(())
+}
+
+/// Run every post-exit hook, warning on failures — the session is already
+/// over, so there is nothing left to abort.
+pub fn run_post_exit(hooks: &HooksConfig, ctx: &HookContext<'_>) {
+    for cmd in &hooks.post_exit {
+        eprintln!("{} {}", "Post-exit hook:".blue(), cmd);
+        match run_one(cmd, ctx) {
+            Ok(true) => {}
+            Ok(false) => eprintln!(
+                "{} post-exit hook failed: {}",
+                "warning:".yellow().bold(),
+                cmd
+            ),
+            Err(e) => eprintln!("{} {:#}", "warning:".yellow().bold(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn ctx<'a>(workspace: &'a Path, exit_code: Option<i32>) -> HookContext<'a> {
+        HookContext {
+            workspace,
+            project_id: "abc123",
+            session_id: "s1",
+            container_name: "ai-pod-abc123-s1",
+            image: "ai-pod-abc123",
+            exit_code,
+        }
+    }
+
+    #[test]
+    fn hooks_see_session_env_and_run_in_workspace() {
+        let dir = TempDir::new().unwrap();
+        let hooks = HooksConfig {
+            pre_launch: vec!["echo \"$AI_POD_SESSION_ID $(pwd)\" > pre.txt".into()],
+            post_exit: vec!["echo \"$AI_POD_EXIT_CODE\" > post.txt".into()],
+        };
+        run_pre_launch(&hooks, &ctx(dir.path(), None)).unwrap();
+        run_post_exit(&hooks, &ctx(dir.path(), Some(3)));
+
+        let pre = std::fs::read_to_string(dir.path().join("pre.txt")).unwrap();
+        let cwd = std::fs::canonicalize(dir.path()).unwrap();
+        assert_eq!(pre.trim(), format!("s1 {}", cwd.display()));
+        let post = std::fs::read_to_string(dir.path().join("post.txt")).unwrap();
+        assert_eq!(post.trim(), "3");
+    }
+
+    #[test]
+    fn failing_pre_launch_hook_stops_the_chain() {
+        let dir = TempDir::new().unwrap();
+        let hooks = HooksConfig {
+            pre_launch: vec!["exit 1".into(), "touch ran".into()],
+            post_exit: vec![],
+        };
+        assert!(run_pre_launch(&hooks, &ctx(dir.path(), None)).is_err());
+        assert!(!dir.path().join("ran").exists());
+    }
+
+    #[test]
+    fn hooks_config_parses_and_omits_empty_lists() {
+        let hooks: HooksConfig =
+            serde_json::from_str(r#"{"pre_launch": ["make db-up"]}"#).unwrap();
+        assert_eq!(hooks.pre_launch, vec!["make db-up"]);
+        assert!(hooks.post_exit.is_empty());
+        assert_eq!(
+            serde_json::to_string(&hooks).unwrap(),
+            r#"{"pre_launch":["make db-up"]}"#
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Git on the host, in directories the agent can write to. A repository's
+//! own config can make git run commands (`core.fsmonitor`, hooks, filter
+//! drivers, signature checks), so an agent that edits `.git/config` could
+//! otherwise run anything on the host the next time ai-pod reads its
+//! changes. Every host git call goes through [`command`], which switches
+//! those off. Diffs also need `--no-ext-diff` and `--no-textconv`.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Filter drivers defined in the repository's own config (`.git/config`
+/// and what it includes), by name.
+fn repo_filters(dir: &Path) -> Vec<String> {
+    let Ok(output) = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["config", "--show-scope", "--name-only", "--get-regexp", r"^filter\."])
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .filter(|(scope, _)| matches!(*scope, "local" | "worktree"))
+        .filter_map(|(_, key)| key.strip_prefix("filter.")?.rsplit_once('.'))
+        .map(|(name, _)| name.to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// `git -C <dir>` with the repository's command-running config switched
+/// off and stdin closed.
+pub(crate) fn command(dir: &Path) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(dir).args([
+        "-c",
+        "core.fsmonitor=false",
+        "-c",
+        "core.hooksPath=/dev/null",
+        "-c",
+        "log.showSignature=false",
+    ]);
+    for name in repo_filters(dir) {
+        for key in ["clean", "smudge", "process"] {
+            cmd.arg("-c").arg(format!("filter.{}.{}=", name, key));
+        }
+        cmd.arg("-c").arg(format!("filter.{}.required=false", name));
+    }
+    cmd.env("GIT_CONFIG_NOSYSTEM", "1").stdin(Stdio::null());
+    cmd
+}
+
+/// Stdout of a git command in `dir`; `None` when it fails, e.g. outside a
+/// repository.
+pub(crate) fn output(dir: &Path, args: &[&str]) -> Option<String> {
+    let out = command(dir).args(args).stderr(Stdio::null()).output().ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repo_config_cannot_run_commands() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ws = dir.path();
+        let git = |args: &[&str]| {
+            assert!(Command::new("git").arg("-C").arg(ws).args(args).output().unwrap().status.success());
+        };
+        git(&["init", "-q"]);
+        std::fs::write(ws.join("a.txt"), "1\n").unwrap();
+        git(&["add", "."]);
+        git(&["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-qm", "init"]);
+        let pwned = ws.join("pwned");
+        let touch = format!("touch {}", pwned.display());
+        git(&["config", "core.fsmonitor", &touch]);
+        git(&["config", "filter.x.clean", &touch]);
+        git(&["config", "filter.x.required", "true"]);
+        git(&["config", "diff.x.textconv", &touch]);
+        git(&["config", "diff.external", &touch]);
+        git(&["config", "log.showSignature", "true"]);
+        git(&["config", "gpg.program", &touch]);
+        std::fs::write(ws.join(".gitattributes"), "*.txt filter=x diff=x\n").unwrap();
+        std::fs::write(ws.join("a.txt"), "2\n").unwrap();
+        assert_eq!(repo_filters(ws), vec!["x"]);
+
+        let status = output(ws, &["status", "--porcelain"]).unwrap();
+        assert!(status.contains("a.txt"), "{}", status);
+        output(ws, &["diff", "--no-ext-diff", "--no-textconv", "HEAD"]).unwrap();
+        output(ws, &["log", "--format=%s"]).unwrap();
+        assert!(!pwned.exists());
+    }
+}
@@ -1,8 +1,12 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
 use sha2::{Digest, Sha256};
+use std::io::IsTerminal;
 use std::path::Path;
 
+use crate::config::AppConfig;
+use crate::events::{self, Event, EventKind};
+use crate::plugins;
 use crate::runtime::ContainerRuntime;
 
 pub const DOCKERFILE_NAME: &str = "ai-pod.Dockerfile";
@@ -37,7 +41,15 @@ pub fn image_name(workspace: &Path) -> String {
     format!("{}-{}", label, short_hash)
 }
 
-fn image_exists(rt: &ContainerRuntime, image: &str) -> Result<bool> {
+pub(crate) fn image_exists(rt: &ContainerRuntime, image: &str) -> Result<bool> {
+    if let Some(exists) = crate::prefetch::image_exists(image) {
+        return Ok(exists);
+    }
+    if let Some(api) = crate::podman_api::client(rt)
+        && let Ok(exists) = api.image_exists(image)
+    {
+        return Ok(exists);
+    }
     let status = rt
         .command()
         .args(["image", "exists", image])
@@ -46,6 +58,48 @@ fn image_exists(rt: &ContainerRuntime, image: &str) -> Result<bool> {
     Ok(status.success())
 }
 
+/// The `(uid, gid)` the image runs as with `user_args` (none for its default
+/// user), read by running `id` in a throwaway container. `None` if the probe
+/// fails or under `--dry-run`, where the echoed command line can't be parsed.
+pub fn image_user_ids(rt: &ContainerRuntime, image: &str, user_args: &[String]) -> Option<(u32, u32)> {
+    let output = rt
+        .command()
+        .args(["run", "--rm"])
+        .args(user_args)
+        .args(["--entrypoint", "sh", image, "-c", "id -u; id -g"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    parse_user_ids(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_user_ids(out: &str) -> Option<(u32, u32)> {
+    let mut lines = out.lines().map(str::trim);
+    let uid = lines.next()?.parse().ok()?;
+    let gid = lines.next()?.parse().ok()?;
+    Some((uid, gid))
+}
+
+/// The image's ID; `None` when it doesn't exist or under `--dry-run`.
+pub fn image_id(rt: &ContainerRuntime, image: &str) -> Option<String> {
+    if rt.dry_run {
+        return None;
+    }
+    if let Some(id) = crate::prefetch::image_id(image) {
+        return id;
+    }
+    let output = rt
+        .command()
+        .args(["image", "inspect", "--format", "{{.Id}}", image])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!id.is_empty()).then_some(id)
+}
+
 pub fn needs_build(rt: &ContainerRuntime, image: &str, force: bool) -> Result<bool> {
     if force {
         return Ok(true);
@@ -53,21 +107,110 @@ pub fn needs_build(rt: &ContainerRuntime, image: &str, force: bool) -> Result<bo
     Ok(!image_exists(rt, image)?)
 }
 
-pub fn build_image(rt: &ContainerRuntime, dockerfile: &Path, image: &str, no_cache: bool) -> Result<()> {
-    eprintln!("{}", "Building container image...".blue().bold());
+/// How `ensure_image` builds, from the global `--rebuild`, `--no-cache`,
+/// `--verbose`, `--insecure-image` and cache flags.
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Build even if the image exists.
+    pub force: bool,
+    pub no_cache: bool,
+    /// Stream the runtime's raw output instead of the progress view.
+    pub verbose: bool,
+    /// Skip base image verification (see [`crate::provenance`]).
+    pub insecure_image: bool,
+    /// Registry repositories to import cached layers from.
+    pub cache_from: Vec<String>,
+    /// Registry repository to export the build's layers to.
+    pub cache_to: Option<String>,
+    /// `--add-host` entries from `network.hosts`, so build steps can reach
+    /// internal mirrors.
+    pub add_hosts: Vec<String>,
+}
+
+impl BuildOptions {
+    pub fn from_flags(flags: &crate::cli::BuildFlags) -> Self {
+        BuildOptions {
+            force: flags.rebuild,
+            no_cache: flags.no_cache,
+            verbose: flags.verbose,
+            insecure_image: flags.insecure_image,
+            cache_from: flags.cache_from.clone(),
+            cache_to: flags.cache_to.clone(),
+            add_hosts: Vec::new(),
+        }
+    }
+
+    /// Fill in the cache registries from the `build` config when no flag
+    /// set them.
+    pub fn with_config(mut self, build: &crate::config::BuildConfig) -> Self {
+        if self.cache_from.is_empty() {
+            self.cache_from = build.cache_from.clone();
+        }
+        if self.cache_to.is_none() {
+            self.cache_to = build.cache_to.clone();
+        }
+        self
+    }
+
+    /// Take the extra host entries from the `network` config.
+    pub fn with_network(mut self, network: &crate::config::NetworkConfig) -> Self {
+        self.add_hosts = crate::container::resolver_args(network)
+            .into_iter()
+            .filter(|a| a.starts_with("--add-host="))
+            .collect();
+        self
+    }
+}
 
+/// `--cache-from` / `--cache-to` arguments. Podman takes a repository as is;
+/// for Docker a plain reference becomes a BuildKit registry cache (exporting
+/// one needs a `docker-container` buildx builder), while a value with `=` is
+/// passed through as a full cache spec.
+fn cache_args(kind: crate::runtime::RuntimeKind, from: &[String], to: Option<&str>) -> Vec<String> {
+    let docker = kind == crate::runtime::RuntimeKind::Docker;
+    let spec = |r: &str, extra: &str| {
+        if docker && !r.contains('=') {
+            format!("type=registry,ref={}{}", r, extra)
+        } else {
+            r.to_string()
+        }
+    };
+    let mut args = Vec::new();
+    for r in from {
+        args.push("--cache-from".to_string());
+        args.push(spec(r, ""));
+    }
+    if let Some(r) = to {
+        args.push("--cache-to".to_string());
+        args.push(spec(r, ",mode=max"));
+    }
+    args
+}
+
+/// The `build` invocation for `image`, without stdio configuration.
+/// `dockerfile` may live outside `context` (a pinned copy, see
+/// [`crate::provenance`]).
+pub fn build_command(
+    rt: &ContainerRuntime,
+    dockerfile: &Path,
+    context: &Path,
+    image: &str,
+    opts: &BuildOptions,
+) -> std::process::Command {
     let version_arg = format!("AI_POD_VERSION={}", env!("CARGO_PKG_VERSION"));
     let gateway_arg = format!("HOST_GATEWAY={}", rt.host_gateway());
     let mut cmd = rt.command();
     cmd.arg("build");
-    if no_cache {
+    if opts.no_cache {
         cmd.arg("--no-cache");
     }
+    cmd.args(cache_args(rt.kind, &opts.cache_from, opts.cache_to.as_deref()));
     // For Docker, host.docker.internal is not automatically available in build
     // containers — we need to inject it explicitly.
     if rt.kind == crate::runtime::RuntimeKind::Docker {
-        cmd.args(["--add-host", &format!("{}:host-gateway", rt.host_gateway())]);
+        cmd.args(["--add-host", &format!("{}:{}", rt.host_gateway(), rt.host_gateway_address())]);
     }
+    cmd.args(&opts.add_hosts);
     cmd.args([
         "--build-arg",
         &version_arg,
@@ -77,37 +220,89 @@ pub fn build_image(rt: &ContainerRuntime, dockerfile: &Path, image: &str, no_cac
         image,
         "-f",
         &dockerfile.to_string_lossy(),
-        &dockerfile.parent().unwrap_or(Path::new(".")).to_string_lossy(),
+        &context.to_string_lossy(),
     ]);
+    // Only passed for another instance: Dockerfiles from before
+    // `AI_POD_SERVER_PORT` don't declare it, and Docker warns about
+    // build args nothing uses.
+    let port = crate::server::lifecycle::server_port();
+    if port != crate::server::lifecycle::MCP_PORT {
+        cmd.args(["--build-arg", &format!("AI_POD_SERVER_PORT={}", port)]);
+    }
+    cmd
+}
 
-    // Keep the shared server alive during the build. The server auto-shuts-down
-    // after 30 s of inactivity with no containers running. POST /keep-alive
-    // immediately so the timer is bumped before the build's first long step,
-    // then re-bump every 10 s for safety margin.
-    let (stop_tx, stop_rx) = std::sync::mpsc::channel::<()>();
-    let keepalive_thread = std::thread::spawn(move || {
-        let client = reqwest::blocking::Client::new();
-        let url = format!(
-            "http://127.0.0.1:{}/keep-alive",
-            crate::server::lifecycle::MCP_PORT
-        );
-        let _ = client.post(&url).send();
-        loop {
-            match stop_rx.recv_timeout(std::time::Duration::from_secs(10)) {
-                Ok(_) | Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                    let _ = client.post(&url).send();
+/// Keeps the shared server alive while builds run. The server auto-shuts-down
+/// after 30 s of inactivity with no containers running. POST /keep-alive
+/// immediately so the timer is bumped before the build's first long step,
+/// then re-bump every 10 s for safety margin, until dropped.
+pub struct ServerKeepAlive {
+    stop_tx: Option<std::sync::mpsc::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl ServerKeepAlive {
+    pub fn start() -> Self {
+        let (stop_tx, stop_rx) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            let client = reqwest::blocking::Client::new();
+            let url = format!(
+                "http://localhost:{}/keep-alive",
+                crate::server::lifecycle::server_port()
+            );
+            let _ = client.post(&url).send();
+            loop {
+                match stop_rx.recv_timeout(std::time::Duration::from_secs(10)) {
+                    Ok(_) | Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                        let _ = client.post(&url).send();
+                    }
                 }
             }
+        });
+        ServerKeepAlive {
+            stop_tx: Some(stop_tx),
+            thread: Some(thread),
         }
-    });
+    }
+}
 
-    let status = cmd
-        .status()
-        .context(format!("Failed to run {} build", rt.cmd()));
+impl Drop for ServerKeepAlive {
+    fn drop(&mut self) {
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+pub fn build_image(
+    rt: &ContainerRuntime,
+    dockerfile: &Path,
+    context: &Path,
+    image: &str,
+    opts: &BuildOptions,
+) -> Result<()> {
+    eprintln!("{}", "Building container image...".blue().bold());
+
+    let mut cmd = build_command(rt, dockerfile, context, image, opts);
+    let keep_alive = ServerKeepAlive::start();
 
-    let _ = stop_tx.send(());
-    let _ = keepalive_thread.join();
+    // The progress view needs a terminal to draw on; piped or logged output
+    // and dry runs get the raw stream.
+    let progress = !opts.verbose && !rt.dry_run && std::io::stderr().is_terminal();
+    let status = if progress {
+        // Make BuildKit print step lines instead of its own tty display.
+        cmd.env("BUILDKIT_PROGRESS", "plain");
+        crate::build_progress::run(cmd)
+    } else {
+        cmd.status()
+            .context(format!("Failed to run {} build", rt.cmd()))
+    };
+
+    drop(keep_alive);
 
     if !status?.success() {
         anyhow::bail!("{} build failed", rt.cmd());
@@ -117,9 +312,43 @@ pub fn build_image(rt: &ContainerRuntime, dockerfile: &Path, image: &str, no_cac
     Ok(())
 }
 
-pub fn ensure_image(rt: &ContainerRuntime, dockerfile: &Path, image: &str, force: bool, no_cache: bool) -> Result<()> {
-    if needs_build(rt, image, force)? {
-        build_image(rt, dockerfile, image, no_cache)?;
+pub fn ensure_image(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    dockerfile: &Path,
+    image: &str,
+    opts: &BuildOptions,
+) -> Result<()> {
+    // Another ai-pod may be building this workspace's image; once it's done
+    // the image exists and there's nothing left to do.
+    let _lock = dockerfile
+        .parent()
+        .map(|ws| crate::launch_lock::acquire(config, ws))
+        .transpose()?;
+    if needs_build(rt, image, opts.force)? {
+        crate::prefetch::forget_image(image);
+        if !rt.dry_run {
+            crate::provenance::verify_base_images(config, dockerfile, opts.insecure_image)?;
+        }
+        let workspace = dockerfile.parent().unwrap_or(Path::new("."));
+        let build_file = crate::provenance::pinned_dockerfile(config, dockerfile, image)?;
+        let global = crate::config::GlobalConfig::load(config);
+        let build = global.build;
+        let opts = opts.clone().with_config(&build).with_network(&global.network);
+        build_image(rt, &build_file, workspace, image, &opts)?;
+        if !rt.dry_run {
+            crate::provenance::lock_new_images(rt, dockerfile)?;
+            if build.scan_secrets {
+                // Best-effort: a failed scan shouldn't block the launch.
+                match crate::image_secrets::scan_image(rt, image) {
+                    Ok(findings) => crate::image_secrets::print_findings(&findings),
+                    Err(e) => eprintln!("{} secret scan failed: {:#}", "warning:".yellow().bold(), e),
+                }
+            }
+            let event = Event::new(EventKind::Build, dockerfile.parent()).image(image);
+            events::record(&config.config_dir, &event);
+            plugins::run_all(&config.config_dir, plugins::Hook::PostBuild, &event);
+        }
     } else {
         eprintln!("{}", "Container image is up to date.".green());
     }
@@ -131,6 +360,54 @@ mod tests {
     use super::*;
     use std::path::Path;
 
+    #[test]
+    fn cache_args_per_runtime() {
+        use crate::runtime::RuntimeKind;
+        let from = vec!["ghcr.io/acme/cache".to_string()];
+        assert_eq!(
+            cache_args(RuntimeKind::Podman, &from, Some("ghcr.io/acme/cache")),
+            vec!["--cache-from", "ghcr.io/acme/cache", "--cache-to", "ghcr.io/acme/cache"]
+        );
+        assert_eq!(
+            cache_args(RuntimeKind::Docker, &from, Some("ghcr.io/acme/cache")),
+            vec![
+                "--cache-from",
+                "type=registry,ref=ghcr.io/acme/cache",
+                "--cache-to",
+                "type=registry,ref=ghcr.io/acme/cache,mode=max"
+            ]
+        );
+        assert_eq!(
+            cache_args(RuntimeKind::Docker, &["type=gha".to_string()], None),
+            vec!["--cache-from", "type=gha"]
+        );
+        assert!(cache_args(RuntimeKind::Podman, &[], None).is_empty());
+    }
+
+    #[test]
+    fn build_options_take_cache_defaults_from_config() {
+        let build = crate::config::BuildConfig {
+            cache_from: vec!["ghcr.io/acme/cache".into()],
+            cache_to: None,
+            scan_secrets: false,
+        };
+        let opts = BuildOptions::default().with_config(&build);
+        assert_eq!(opts.cache_from, build.cache_from);
+        let flagged = BuildOptions {
+            cache_from: vec!["local/cache".into()],
+            ..Default::default()
+        }
+        .with_config(&build);
+        assert_eq!(flagged.cache_from, vec!["local/cache"]);
+    }
+
+    #[test]
+    fn parse_user_ids_reads_two_lines() {
+        assert_eq!(parse_user_ids("1000\n1001\n"), Some((1000, 1001)));
+        assert_eq!(parse_user_ids("1000\n"), None);
+        assert_eq!(parse_user_ids("podman run --rm ...\n"), None);
+    }
+
     #[test]
     fn image_name_uses_last_path_component() {
         let name = image_name(Path::new("/home/user/myproject"));
@@ -0,0 +1,229 @@
+//! `ai-pod image inspect`: break the workspace's built image down by layer
+//! (the instruction that created it and its size) and flag common bloat —
+//! package-manager caches left in a layer — with the fix to put in
+//! `ai-pod.Dockerfile`.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::du::format_bytes;
+use crate::runtime::ContainerRuntime;
+
+/// Layers smaller than this aren't worth a suggestion.
+const BLOAT_THRESHOLD: u64 = 10_000_000;
+
+/// Width of the instruction column.
+const INSTRUCTION_WIDTH: usize = 70;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Layer {
+    pub size: u64,
+    /// The Dockerfile instruction, as recorded by the build.
+    pub instruction: String,
+}
+
+/// Tidy a history `CreatedBy` entry into the instruction it came from:
+/// drop the `/bin/sh -c #(nop)` wrapper of classic builds, BuildKit's
+/// `|N ARG=...` prefix and `# buildkit` suffix.
+pub(crate) fn instruction_of(created_by: &str) -> String {
+    let mut s = created_by.trim();
+    s = s.strip_suffix("# buildkit").unwrap_or(s).trim_end();
+    if let Some(rest) = s.strip_prefix('|') {
+        // `|2 A=1 B=2 /bin/sh -c ...`: skip the count and the build args.
+        let count: usize = rest.split_whitespace().next().and_then(|n| n.parse().ok()).unwrap_or(0);
+        let mut words = rest.splitn(count + 2, ' ');
+        s = words.nth(count + 1).unwrap_or(s);
+    }
+    if let Some(rest) = s.strip_prefix("/bin/sh -c #(nop)") {
+        return rest.trim().to_string();
+    }
+    if let Some(rest) = s.strip_prefix("/bin/sh -c ").or_else(|| s.strip_prefix("RUN /bin/sh -c ")) {
+        return format!("RUN {}", rest.trim());
+    }
+    s.to_string()
+}
+
+/// Parse `history --human=false --format '{{.Size}}\t{{.CreatedBy}}'`,
+/// which lists the newest layer first, into layers in build order.
+pub fn parse_history(out: &str) -> Vec<Layer> {
+    let mut layers: Vec<Layer> = out
+        .lines()
+        .filter_map(|line| {
+            let (size, created_by) = line.split_once('\t')?;
+            Some(Layer {
+                size: size.trim().parse().ok()?,
+                instruction: instruction_of(created_by),
+            })
+        })
+        .collect();
+    layers.reverse();
+    layers
+}
+
+/// Suggestions for a layer that keeps package-manager caches around.
+pub fn suggestions(layer: &Layer) -> Vec<&'static str> {
+    if layer.size < BLOAT_THRESHOLD {
+        return Vec::new();
+    }
+    let i = layer.instruction.as_str();
+    let cache_mount = i.contains("--mount=type=cache");
+    let mut out = Vec::new();
+    if i.contains("apt-get install") && !i.contains("/var/lib/apt/lists") && !cache_mount {
+        out.push("apt lists stay in the layer: end the RUN with `&& rm -rf /var/lib/apt/lists/*`");
+    }
+    if i.contains("apt-get install") && !i.contains("--no-install-recommends") {
+        out.push("add `--no-install-recommends` to `apt-get install`");
+    }
+    if i.contains("apk add") && !i.contains("--no-cache") {
+        out.push("use `apk add --no-cache`");
+    }
+    if (i.contains("npm install") || i.contains("npm ci") || i.contains("npm i "))
+        && !i.contains("npm cache clean")
+        && !cache_mount
+    {
+        out.push("the npm cache stays in the layer: end the RUN with `&& npm cache clean --force`");
+    }
+    if i.contains("pip install") && !i.contains("--no-cache-dir") && !cache_mount {
+        out.push("use `pip install --no-cache-dir`");
+    }
+    if i.contains("cargo install") && !i.contains("registry") && !cache_mount {
+        out.push("the cargo registry stays in the layer: end the RUN with `&& rm -rf $CARGO_HOME/registry`");
+    }
+    out
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        format!("{}…", s.chars().take(width - 1).collect::<String>())
+    }
+}
+
+fn history(rt: &ContainerRuntime, image: &str) -> Result<Vec<Layer>> {
+    let out = rt
+        .command()
+        .args([
+            "history",
+            "--no-trunc",
+            "--human=false",
+            "--format",
+            "{{.Size}}\t{{.CreatedBy}}",
+            image,
+        ])
+        .output()
+        .with_context(|| format!("Failed to run {} history", rt.cmd()))?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "Image {} not found; build it with `ai-pod build`",
+            image
+        );
+    }
+    Ok(parse_history(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// Print the layer breakdown of `image`. Empty layers (metadata like `ENV`)
+/// are hidden unless `all` is set.
+pub fn run(rt: &ContainerRuntime, image: &str, all: bool) -> Result<()> {
+    let layers = history(rt, image)?;
+    if rt.dry_run {
+        return Ok(());
+    }
+    let total: u64 = layers.iter().map(|l| l.size).sum();
+    println!("{} {} ({})", "Image:".blue().bold(), image, format_bytes(total));
+    println!();
+    println!("{:>10}  {}", "SIZE".bold(), "INSTRUCTION".bold());
+    let mut hidden = 0;
+    let mut flagged = 0;
+    for layer in &layers {
+        if layer.size == 0 && !all {
+            hidden += 1;
+            continue;
+        }
+        let size = format!("{:>10}", format_bytes(layer.size));
+        let share = (layer.size * 100).checked_div(total).unwrap_or(0);
+        let size = if share >= 25 { size.yellow().bold().to_string() } else { size };
+        println!("{}  {}", size, truncate(&layer.instruction, INSTRUCTION_WIDTH));
+        for tip in suggestions(layer) {
+            println!("{:>10}  {} {}", "", "↳".yellow(), tip);
+            flagged += 1;
+        }
+    }
+    if hidden > 0 {
+        println!("{}", format!("({} empty layers hidden; --all shows them)", hidden).dimmed());
+    }
+    if flagged > 0 {
+        println!();
+        println!(
+            "{} {} suggestion(s) above could shrink the image; rebuild with `ai-pod --rebuild` after editing ai-pod.Dockerfile.",
+            "Note:".yellow().bold(),
+            flagged
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instruction_of_handles_classic_and_buildkit_history() {
+        assert_eq!(
+            instruction_of("/bin/sh -c #(nop)  ENV PATH=/usr/bin"),
+            "ENV PATH=/usr/bin"
+        );
+        assert_eq!(
+            instruction_of("/bin/sh -c apt-get update && apt-get install -y git"),
+            "RUN apt-get update && apt-get install -y git"
+        );
+        assert_eq!(
+            instruction_of("RUN /bin/sh -c npm ci # buildkit"),
+            "RUN npm ci"
+        );
+        assert_eq!(
+            instruction_of("|2 AI_POD_VERSION=1.0 HOST_GATEWAY=host.containers.internal /bin/sh -c curl -fsSL x | sh"),
+            "RUN curl -fsSL x | sh"
+        );
+        assert_eq!(instruction_of("COPY file:abc in /app"), "COPY file:abc in /app");
+    }
+
+    #[test]
+    fn parse_history_returns_build_order() {
+        let out = "0\t/bin/sh -c #(nop)  CMD [\"bash\"]\n\
+                   120000000\t/bin/sh -c apt-get install -y git\n\
+                   80000000\t/bin/sh -c #(nop) ADD file:abc in / \n\
+                   garbage line\n";
+        let layers = parse_history(out);
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[0].size, 80_000_000);
+        assert_eq!(layers[0].instruction, "ADD file:abc in /");
+        assert_eq!(layers[2].instruction, "CMD [\"bash\"]");
+    }
+
+    #[test]
+    fn suggestions_flag_caches_in_large_layers() {
+        let layer = |size, instruction: &str| Layer {
+            size,
+            instruction: instruction.to_string(),
+        };
+        let apt = layer(200_000_000, "RUN apt-get update && apt-get install -y git");
+        assert_eq!(suggestions(&apt).len(), 2);
+        let tidy = layer(
+            200_000_000,
+            "RUN apt-get update && apt-get install -y --no-install-recommends git && rm -rf /var/lib/apt/lists/*",
+        );
+        assert!(suggestions(&tidy).is_empty());
+        assert_eq!(suggestions(&layer(50_000_000, "RUN npm ci")).len(), 1);
+        assert!(suggestions(&layer(50_000_000, "RUN --mount=type=cache,target=/root/.npm npm ci")).is_empty());
+        assert_eq!(suggestions(&layer(50_000_000, "RUN pip install requests")).len(), 1);
+        assert_eq!(suggestions(&layer(50_000_000, "RUN apk add git")).len(), 1);
+        assert!(suggestions(&layer(1_000, "RUN npm ci")).is_empty());
+    }
+
+    #[test]
+    fn truncate_marks_cut_text() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdefghijkl", 5), "abcd…");
+    }
+}
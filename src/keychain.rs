@@ -0,0 +1,47 @@
+//! Reading Claude Code's OAuth credentials from the macOS keychain. On macOS
+//! Claude Code keeps them there instead of `~/.claude/.credentials.json`,
+//! so copying `~/.claude.json` alone seeds a signed-out container.
+
+use std::process::Command;
+
+/// Keychain item (service name) Claude Code stores its credentials under.
+const KEYCHAIN_SERVICE: &str = "Claude Code-credentials";
+
+/// The credentials JSON from the login keychain, or `None` when not on macOS,
+/// the item doesn't exist, or access was denied. macOS shows its own
+/// keychain access prompt on top of the consent asked by the caller.
+pub fn claude_credentials() -> Option<String> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    let output = Command::new("security")
+        .args(["find-generic-password", "-s", KEYCHAIN_SERVICE, "-w"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    validate_credentials(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+/// Accept only a JSON object carrying an OAuth entry, so a stray keychain item
+/// with the same service name never ends up as the container's credentials.
+fn validate_credentials(raw: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(raw).ok()?;
+    value.get("claudeAiOauth")?.as_object()?;
+    Some(raw.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_credentials_requires_oauth_object() {
+        let ok = r#"{"claudeAiOauth": {"accessToken": "t", "refreshToken": "r"}}"#;
+        assert_eq!(validate_credentials(ok).as_deref(), Some(ok));
+        assert_eq!(validate_credentials("not json"), None);
+        assert_eq!(validate_credentials(r#"{"other": 1}"#), None);
+        assert_eq!(validate_credentials(r#"{"claudeAiOauth": "x"}"#), None);
+    }
+}
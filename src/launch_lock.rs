@@ -0,0 +1,125 @@
+//! Per-workspace lock around the setup steps of a launch (image build, home
+//! volume init, post-create). Two `ai-pod` invocations for the same workspace
+//! would otherwise both build the image or seed the volume; the second one
+//! now waits and then finds everything in place. The lock is released before
+//! the session itself starts, so concurrent sessions still work.
+//!
+//! Locks are `flock`s on `~/.ai-pod/locks/<workspace hash>.lock`, released
+//! by the kernel when the process exits, so a crashed launch never leaves a
+//! stale lock behind.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+
+/// Held while setting up a workspace; unlocks on drop.
+pub struct LaunchLock {
+    _file: File,
+}
+
+fn lock_path(config: &AppConfig, workspace: &Path) -> PathBuf {
+    config
+        .config_dir
+        .join("locks")
+        .join(format!("{}.lock", crate::workspace::workspace_hash(workspace)))
+}
+
+pub(crate) fn flock(file: &File, flags: libc::c_int) -> std::io::Result<()> {
+    // Safety: flock only operates on the descriptor we own.
+    if unsafe { libc::flock(file.as_raw_fd(), flags) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Lock `workspace`, waiting (with a message naming the other process) when
+/// another ai-pod is setting it up.
+pub fn acquire(config: &AppConfig, workspace: &Path) -> Result<LaunchLock> {
+    let path = lock_path(config, workspace);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).context("Failed to create ~/.ai-pod/locks/")?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    if let Err(e) = flock(&file, libc::LOCK_EX | libc::LOCK_NB) {
+        if e.raw_os_error() != Some(libc::EWOULDBLOCK) {
+            return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+        }
+        let mut holder = String::new();
+        let _ = file.read_to_string(&mut holder);
+        let holder = holder.trim();
+        eprintln!(
+            "{} another ai-pod{} is starting this workspace; waiting for it to finish setting up...",
+            "Note:".yellow().bold(),
+            if holder.is_empty() {
+                String::new()
+            } else {
+                format!(" (pid {})", holder)
+            }
+        );
+        flock(&file, libc::LOCK_EX).with_context(|| format!("Failed to lock {}", path.display()))?;
+    }
+
+    // Runtime lookups made before another process last set this workspace
+    // up may be out of date.
+    let mut holder = String::new();
+    let _ = file.rewind();
+    let _ = file.read_to_string(&mut holder);
+    if holder.trim() != std::process::id().to_string()
+        && let Ok(modified) = file.metadata().and_then(|m| m.modified())
+    {
+        crate::prefetch::discard_if_before(modified);
+    }
+
+    // Record our pid for the message above; best-effort.
+    let _ = file.set_len(0);
+    let _ = file.rewind();
+    let _ = write!(file, "{}", std::process::id());
+    Ok(LaunchLock { _file: file })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use tempfile::TempDir;
+
+    #[test]
+    fn lock_excludes_other_descriptors_until_dropped() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let ws = Path::new("/src/api");
+        let lock = acquire(&config, ws).unwrap();
+
+        let path = lock_path(&config, ws);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            std::process::id().to_string()
+        );
+        let other = File::open(&path).unwrap();
+        assert!(flock(&other, libc::LOCK_EX | libc::LOCK_NB).is_err());
+
+        drop(lock);
+        assert!(flock(&other, libc::LOCK_EX | libc::LOCK_NB).is_ok());
+    }
+
+    #[test]
+    fn workspaces_lock_independently() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let _a = acquire(&config, Path::new("/src/a")).unwrap();
+        let _b = acquire(&config, Path::new("/src/b")).unwrap();
+    }
+}
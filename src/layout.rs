@@ -0,0 +1,285 @@
+//! Where ai-pod keeps its files. What you write yourself — [`SETTINGS`] —
+//! goes to `$XDG_CONFIG_HOME/ai-pod` and everything else — project and server
+//! state, sessions, logs, locks — to `$XDG_STATE_HOME/ai-pod`, defaulting
+//! to `~/.config/ai-pod` and `~/.local/state/ai-pod`. `AI_POD_HOME` puts
+//! everything in one directory instead.
+//!
+//! An existing `~/.ai-pod` is moved into place the first time a release
+//! with this layout runs, unless the shared server is still running from it;
+//! it then stays in use until the server has stopped.
+
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming a single directory for all of ai-pod's files.
+pub const HOME_ENV: &str = "AI_POD_HOME";
+
+/// Entries of the settings directory: the global config, the global
+/// CLAUDE.md fragment and the plugin directory.
+pub const SETTINGS: &[&str] = &["config.json", "CLAUDE.md", "plugins"];
+
+/// The directory ai-pod used before the XDG layout.
+pub fn legacy_dir(home: &Path) -> PathBuf {
+    home.join(".ai-pod")
+}
+
+/// The XDG settings and state directories for `home`, given the
+/// environment lookup `get`. Relative XDG values are ignored, as the spec
+/// asks.
+pub fn xdg_dirs(home: &Path, get: impl Fn(&str) -> Option<String>) -> (PathBuf, PathBuf) {
+    let base = |var: &str, default: &str| {
+        get(var)
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .unwrap_or_else(|| home.join(default))
+            .join("ai-pod")
+    };
+    (base("XDG_CONFIG_HOME", ".config"), base("XDG_STATE_HOME", ".local/state"))
+}
+
+fn env(var: &str) -> Option<String> {
+    std::env::var(var).ok().filter(|v| !v.is_empty())
+}
+
+/// Where [`SETTINGS`] live when `config_dir` is the state directory:
+/// the XDG settings directory for the XDG state directory, else
+/// `config_dir` itself (`AI_POD_HOME`, `~/.ai-pod`, tests).
+pub fn settings_dir(config_dir: &Path) -> PathBuf {
+    if let Some(home) = dirs::home_dir() {
+        let (settings, state) = xdg_dirs(&home, env);
+        if config_dir == state {
+            return settings;
+        }
+    }
+    config_dir.to_path_buf()
+}
+
+/// Every directory ai-pod may keep files in for `home`, for guards
+/// against mounting them into a container.
+pub fn own_dirs(home: &Path) -> Vec<PathBuf> {
+    let (settings, state) = xdg_dirs(home, env);
+    let mut dirs = vec![legacy_dir(home), settings, state];
+    dirs.extend(env(HOME_ENV).map(PathBuf::from));
+    dirs
+}
+
+/// What to do with a `~/.ai-pod` from an earlier release.
+#[derive(Debug, PartialEq, Eq)]
+enum Legacy {
+    /// Nothing to move.
+    None,
+    /// Move it to the XDG directories now.
+    Move,
+    /// Keep using it until the shared server running from it has stopped.
+    Busy,
+    /// Leave it be: the XDG state directory is in use already.
+    Ignore,
+}
+
+fn legacy_action(legacy: &Path, state: &Path) -> Legacy {
+    if !legacy.is_dir() {
+        return Legacy::None;
+    }
+    if state.exists() {
+        return Legacy::Ignore;
+    }
+    let server = crate::server::lifecycle::ServerState::load(&legacy.join("server.json"));
+    if server.is_alive() {
+        Legacy::Busy
+    } else {
+        Legacy::Move
+    }
+}
+
+/// Copy a file or directory tree, keeping symlinks as they are.
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+    let meta = std::fs::symlink_metadata(from)?;
+    if meta.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        std::fs::set_permissions(to, meta.permissions())
+    } else if meta.file_type().is_symlink() {
+        std::os::unix::fs::symlink(std::fs::read_link(from)?, to)
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// Rename `from` to `to`, or copy and remove it when they're on different
+/// filesystems (e.g. `XDG_CONFIG_HOME` on another mount).
+fn move_entry(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if std::fs::symlink_metadata(to).is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    if let Err(e) = copy_tree(from, to) {
+        let _ = std::fs::remove_dir_all(to).or_else(|_| std::fs::remove_file(to));
+        return Err(e);
+    }
+    if from.is_dir() {
+        std::fs::remove_dir_all(from)
+    } else {
+        std::fs::remove_file(from)
+    }
+}
+
+/// Move `legacy` to `state`, and its [`SETTINGS`] on to `settings`. On
+/// failure everything is moved back, so `legacy` stays complete.
+fn move_legacy(legacy: &Path, settings: &Path, state: &Path) -> std::io::Result<()> {
+    if let Some(parent) = state.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(legacy, state)?;
+    let mut moved = Vec::new();
+    let result = SETTINGS.iter().try_for_each(|name| {
+        let from = state.join(name);
+        if !from.exists() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(settings)?;
+        move_entry(&from, &settings.join(name))?;
+        moved.push(*name);
+        Ok(())
+    });
+    if result.is_err() {
+        for name in moved {
+            let _ = move_entry(&settings.join(name), &state.join(name));
+        }
+        let _ = std::fs::rename(state, legacy);
+    }
+    result
+}
+
+/// The state directory (`AppConfig::config_dir`) for `home`, moving an old
+/// `~/.ai-pod` into the XDG layout on the way.
+pub fn state_dir(home: &Path) -> PathBuf {
+    if let Some(dir) = env(HOME_ENV) {
+        return PathBuf::from(dir);
+    }
+    let legacy = legacy_dir(home);
+    let (settings, state) = xdg_dirs(home, env);
+    match legacy_action(&legacy, &state) {
+        Legacy::None | Legacy::Ignore => state,
+        Legacy::Busy => {
+            static NOTED: std::sync::Once = std::sync::Once::new();
+            NOTED.call_once(|| {
+                eprintln!(
+                    "{} {} moves to {} once the shared server stops (`ai-pod server stop`).",
+                    "Note:".yellow().bold(),
+                    legacy.display(),
+                    state.display()
+                );
+            });
+            legacy
+        }
+        Legacy::Move => match move_legacy(&legacy, &settings, &state) {
+            Ok(()) => {
+                eprintln!(
+                    "{} {} to {} (settings in {})",
+                    "Moved:".blue().bold(),
+                    legacy.display(),
+                    state.display(),
+                    settings.display()
+                );
+                state
+            }
+            Err(e) => {
+                eprintln!(
+                    "{} could not move {} to {}: {}; still using it",
+                    "warning:".yellow().bold(),
+                    legacy.display(),
+                    state.display(),
+                    e
+                );
+                // The rollback failed too: use the files where they are.
+                if legacy.is_dir() { legacy } else { state }
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn xdg_dirs_default_under_home_and_ignore_relative_values() {
+        let home = Path::new("/home/me");
+        let (settings, state) = xdg_dirs(home, |_| None);
+        assert_eq!(settings, Path::new("/home/me/.config/ai-pod"));
+        assert_eq!(state, Path::new("/home/me/.local/state/ai-pod"));
+        let (settings, state) = xdg_dirs(home, |var| match var {
+            "XDG_CONFIG_HOME" => Some("/etc/me".into()),
+            _ => Some("relative".into()),
+        });
+        assert_eq!(settings, Path::new("/etc/me/ai-pod"));
+        assert_eq!(state, Path::new("/home/me/.local/state/ai-pod"));
+    }
+
+    #[test]
+    fn legacy_directory_moves_with_its_config_split_off() {
+        let dir = TempDir::new().unwrap();
+        let legacy = legacy_dir(dir.path());
+        std::fs::create_dir_all(legacy.join("sessions")).unwrap();
+        std::fs::write(legacy.join("config.json"), "{}").unwrap();
+        std::fs::create_dir_all(legacy.join("plugins")).unwrap();
+        std::fs::write(legacy.join("3f9c2a1b7d4e.json"), "{}").unwrap();
+        let (settings, state) = xdg_dirs(dir.path(), |_| None);
+
+        assert_eq!(legacy_action(&legacy, &state), Legacy::Move);
+        move_legacy(&legacy, &settings, &state).unwrap();
+        assert!(!legacy.exists());
+        assert!(state.join("sessions").is_dir());
+        assert!(state.join("3f9c2a1b7d4e.json").is_file());
+        assert!(!state.join("config.json").exists());
+        assert!(settings.join("config.json").is_file());
+        assert!(settings.join("plugins").is_dir());
+        assert_eq!(legacy_action(&legacy, &state), Legacy::None);
+
+        std::fs::create_dir_all(&legacy).unwrap();
+        assert_eq!(legacy_action(&legacy, &state), Legacy::Ignore);
+    }
+
+    #[test]
+    fn failed_settings_move_puts_the_legacy_directory_back() {
+        let dir = TempDir::new().unwrap();
+        let legacy = legacy_dir(dir.path());
+        std::fs::create_dir_all(legacy.join("plugins")).unwrap();
+        std::fs::write(legacy.join("config.json"), "{}").unwrap();
+        std::fs::write(legacy.join("3f9c2a1b7d4e.json"), "{}").unwrap();
+        let (settings, state) = xdg_dirs(dir.path(), |_| None);
+        // Something in the way of the plugins directory: that move fails
+        // after config.json has moved, and must leave it alone.
+        std::fs::create_dir_all(settings.join("plugins/x")).unwrap();
+
+        assert!(move_legacy(&legacy, &settings, &state).is_err());
+        assert!(legacy.join("config.json").is_file());
+        assert!(legacy.join("plugins").is_dir());
+        assert!(legacy.join("3f9c2a1b7d4e.json").is_file());
+        assert!(!state.exists());
+        assert!(!settings.join("config.json").exists());
+        assert!(settings.join("plugins/x").is_dir());
+    }
+
+    #[test]
+    fn copy_tree_keeps_symlinks() {
+        let dir = TempDir::new().unwrap();
+        let from = dir.path().join("plugins");
+        std::fs::create_dir_all(from.join("notify")).unwrap();
+        std::fs::write(from.join("notify/run.sh"), "echo").unwrap();
+        std::os::unix::fs::symlink("run.sh", from.join("notify/link")).unwrap();
+        let to = dir.path().join("copy");
+        copy_tree(&from, &to).unwrap();
+        assert_eq!(std::fs::read_to_string(to.join("notify/run.sh")).unwrap(), "echo");
+        assert_eq!(std::fs::read_link(to.join("notify/link")).unwrap(), Path::new("run.sh"));
+    }
+}
@@ -1,16 +1,74 @@
+pub mod banner;
+pub mod browser;
+pub mod build_all;
+pub mod build_progress;
+pub mod cache_cli;
+pub mod carry_over;
+pub mod checkpoint;
+pub mod claude_md;
+pub mod clean_picker;
 pub mod cli;
+pub mod collision;
 pub mod commands_cli;
+pub mod completions;
 pub mod config;
+pub mod config_check;
+pub mod config_cli;
 pub mod container;
+pub mod control;
 pub mod credentials;
+pub mod describe;
+pub mod direnv;
+pub mod dockerfile_watch;
+pub mod du;
+pub mod editor;
+pub mod env_cli;
 pub mod env_files_cli;
+pub mod events;
+pub mod gc;
+pub mod healthcheck;
+pub mod history_import;
+pub mod hooks;
+pub mod host_git;
 pub mod image;
+pub mod image_inspect;
+pub mod image_secrets;
+pub mod launch_lock;
+pub mod layout;
+pub mod local_time;
+pub mod keychain;
+pub mod login;
+pub mod migrate;
 pub mod mount_cli;
+pub mod plugins;
+pub mod podman_api;
+pub mod ports;
+pub mod post_create;
+pub mod prefetch;
+pub mod provenance;
+pub mod record;
+pub mod registry;
+pub mod report;
+pub mod review;
 pub mod runtime;
+pub mod runtime_check;
 pub mod server;
 pub mod service;
 pub mod services_cli;
+pub mod session_title;
+pub mod setup_bundle;
+pub mod snapshot;
+pub mod snooze;
+pub mod stats;
+pub mod sync;
+pub mod template;
+pub mod term_title;
+pub mod tmux;
+pub mod top;
 pub mod update;
+pub mod vm;
+pub mod warm;
+pub mod watch;
 pub mod workspace;
 
 /// Returns true if stdin is connected to a terminal. When false, ai-pod
@@ -21,3 +79,11 @@ pub fn is_stdin_tty() -> bool {
     // Safety: isatty just reads the fd's terminal state, no aliasing concerns.
     unsafe { libc::isatty(0) == 1 }
 }
+
+/// Returns true if stdout is connected to a terminal. When false, output is
+/// piped or redirected (`ai-pod run tsc | tee log`) and must not go through
+/// a pseudo-TTY, which would add carriage returns and escape sequences.
+pub fn is_stdout_tty() -> bool {
+    // Safety: as above.
+    unsafe { libc::isatty(1) == 1 }
+}
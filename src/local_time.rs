@@ -0,0 +1,39 @@
+//! Calendar time in the host's local time zone, for dates and times of day
+//! shown to (or configured by) the user.
+
+/// Current Unix time in seconds.
+pub fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// The local calendar time of the Unix time `ts`; `None` when it can't be
+/// represented.
+pub fn tm(ts: i64) -> Option<libc::tm> {
+    let ts = ts as libc::time_t;
+    // Safety: localtime_r writes only to the `tm` we pass in.
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        (!libc::localtime_r(&ts, &mut tm).is_null()).then_some(tm)
+    }
+}
+
+/// `YYYY-MM-DD` of the Unix time `ts`; empty when it can't be represented.
+pub fn date(ts: i64) -> String {
+    tm(ts)
+        .map(|tm| format!("{:04}-{:02}-{:02}", tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_is_a_calendar_day_around_ts() {
+        // 2024-06-15 12:00 UTC is that day in every zone from -11:59 to +11:59.
+        assert_eq!(date(1_718_452_800), "2024-06-15");
+    }
+}
@@ -0,0 +1,205 @@
+//! Host-side `ai-pod login` subcommand: run the agent's interactive login in
+//! a one-off container so the OAuth flow works end to end. The container's
+//! browser opener is replaced by a shim that hands URLs to the host, which
+//! opens them in the default browser, and on Linux the container shares the
+//! host network so the browser's redirect to `localhost:<port>` reaches the
+//! agent's callback listener.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::cli::Agent;
+use crate::config::AppConfig;
+use crate::container::HomeVolumes;
+use crate::runtime::ContainerRuntime;
+
+/// Directory inside the login container shared with the host for URLs.
+const BRIDGE_DIR: &str = "/run/ai-pod-login";
+
+/// Stand-in for `xdg-open`/`$BROWSER`: append the URL to the bridge file
+/// instead of trying to start a browser that doesn't exist in the container.
+const OPENER_SHIM: &str = "#!/bin/sh\nprintf '%s\\n' \"$1\" >> /run/ai-pod-login/urls\n";
+
+/// Entrypoint and args that start the agent's interactive login.
+fn login_command(agent: &Agent) -> (&'static str, &'static [&'static str]) {
+    match agent {
+        Agent::Claude => ("claude", &["/login"]),
+        Agent::Opencode => ("opencode", &["auth", "login"]),
+    }
+}
+
+/// Only plain https URLs are opened on the host; anything else the container
+/// writes to the bridge file is ignored.
+fn is_openable(url: &str) -> bool {
+    url.starts_with("https://") && !url.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+fn open_on_host(url: &str) {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    let opened = std::process::Command::new(opener)
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if !opened {
+        eprintln!(
+            "\r\n{} could not open a browser; visit the URL shown above manually\r",
+            "warning:".yellow().bold()
+        );
+    }
+}
+
+/// Poll the bridge file and open each new line, until `done` is set.
+fn watch_urls(urls_file: &Path, done: &AtomicBool) {
+    let mut seen = 0;
+    while !done.load(Ordering::Relaxed) {
+        if let Ok(contents) = std::fs::read_to_string(urls_file) {
+            let lines: Vec<&str> = contents.lines().collect();
+            for url in lines.iter().skip(seen) {
+                if is_openable(url.trim()) {
+                    open_on_host(url.trim());
+                }
+            }
+            seen = seen.max(lines.len());
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+fn login_args(
+    rt: &ContainerRuntime,
+    volumes: &HomeVolumes,
+    bridge: &Path,
+    image: &str,
+    agent: &Agent,
+) -> Vec<String> {
+    let shim = format!("{}/xdg-open", BRIDGE_DIR);
+    let mut args: Vec<String> = vec!["run".into(), "--rm".into(), "-it".into()];
+    args.extend(volumes.user.run_args());
+    args.extend(volumes.mount_args());
+    args.extend([
+        "-v".into(),
+        format!("{}:{}:z", bridge.display(), BRIDGE_DIR),
+        "-v".into(),
+        format!("{}/xdg-open:/usr/local/bin/xdg-open:ro,z", bridge.display()),
+        "-e".into(),
+        format!("BROWSER={}", shim),
+    ]);
+    // Docker Desktop and podman machine run containers in a VM, where host
+    // networking is the VM's, not the Mac's; there the agent falls back to
+    // asking for the code shown in the browser.
+    if !cfg!(target_os = "macos") {
+        args.push("--network".into());
+        args.push("host".into());
+    }
+    args.push(rt.add_host_arg());
+    let (entrypoint, rest) = login_command(agent);
+    args.push("--entrypoint".into());
+    args.push(entrypoint.into());
+    args.push(image.into());
+    args.extend(rest.iter().map(|s| s.to_string()));
+    args
+}
+
+pub fn run(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    image: &str,
+    agent: &Agent,
+) -> Result<()> {
+    if !crate::is_stdin_tty() {
+        anyhow::bail!("`ai-pod login` is interactive and needs a terminal");
+    }
+    let volumes = crate::container::ensure_home_volume(rt, config, workspace, image)?;
+
+    let bridge = tempfile::Builder::new()
+        .prefix("ai-pod-login-")
+        .tempdir_in(&config.config_dir)
+        .context("Failed to create login bridge directory")?;
+    let shim = bridge.path().join("xdg-open");
+    std::fs::write(&shim, OPENER_SHIM).context("Failed to write browser shim")?;
+    std::fs::set_permissions(&shim, std::fs::Permissions::from_mode(0o755))?;
+    // The container user may be a different (sub-)uid; let it append.
+    std::fs::set_permissions(bridge.path(), std::fs::Permissions::from_mode(0o777))?;
+
+    eprintln!(
+        "{} the sign-in page opens in your browser automatically",
+        "Login:".blue().bold()
+    );
+
+    let done = Arc::new(AtomicBool::new(false));
+    let watcher = {
+        let done = Arc::clone(&done);
+        let urls_file = bridge.path().join("urls");
+        std::thread::spawn(move || watch_urls(&urls_file, &done))
+    };
+
+    let status = rt
+        .command()
+        .args(login_args(rt, &volumes, bridge.path(), image, agent))
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .context("Failed to run login container");
+
+    done.store(true, Ordering::Relaxed);
+    let _ = watcher.join();
+
+    if !status?.success() {
+        anyhow::bail!("Login exited with non-zero status");
+    }
+    eprintln!("{}", "Credentials saved to the workspace's home volume.".green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::RuntimeKind;
+
+    #[test]
+    fn is_openable_accepts_only_https() {
+        assert!(is_openable("https://claude.ai/oauth/authorize?code=true&x=1"));
+        assert!(!is_openable("http://example.com"));
+        assert!(!is_openable("file:///etc/passwd"));
+        assert!(!is_openable("https://a.b/ c"));
+    }
+
+    #[test]
+    fn login_args_install_shim_and_run_agent_login() {
+        let rt = ContainerRuntime {
+            kind: RuntimeKind::Podman,
+            dry_run: false,
+        };
+        let volumes = HomeVolumes {
+            home: "ai-pod-abc-home".into(),
+            state: "ai-pod-abc-state".into(),
+            user: Default::default(),
+        };
+        let args = login_args(&rt, &volumes, Path::new("/tmp/b"), "img", &Agent::Claude);
+        assert!(args.contains(&"ai-pod-abc-state:/home/ai-pod/.claude:z".to_string()));
+        assert!(args.contains(&"/tmp/b/xdg-open:/usr/local/bin/xdg-open:ro,z".to_string()));
+        assert!(args.contains(&"BROWSER=/run/ai-pod-login/xdg-open".to_string()));
+        let tail: Vec<&str> = args[args.len() - 4..].iter().map(String::as_str).collect();
+        assert_eq!(tail, vec!["--entrypoint", "claude", "img", "/login"]);
+    }
+
+    #[test]
+    fn opener_shim_targets_bridge_file() {
+        assert!(OPENER_SHIM.contains(&format!("{}/urls", BRIDGE_DIR)));
+    }
+}
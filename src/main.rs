@@ -1,14 +1,18 @@
 use ai_pod::{
-    cli, commands_cli, config, container, credentials, env_files_cli, image, mount_cli, runtime,
-    server, services_cli, update, workspace,
+    build_all, cache_cli, checkpoint, cli, commands_cli, completions, config, config_check, config_cli,
+    container, credentials, du, editor, env_files_cli, events, image, image_inspect, image_secrets, login, mount_cli, registry,
+    provenance, review, runtime, runtime_check, server, services_cli, setup_bundle, snapshot, snooze, stats, template,
+    top, update, watch, workspace,
 };
 
 use anyhow::{Context, Result};
-use clap::Parser;
 use colored::Colorize;
 use std::path::Path;
 
-use cli::{AllowedAction, Cli, Command, CommandsAction, EnvFilesAction, MountAction, ServicesAction};
+use cli::{
+    AliasAction, AllowedAction, CacheAction, Cli, Command, CommandsAction, ConfigAction,
+    EnvFilesAction, ImageAction, MountAction, ReportAction, SecurityAction, ServerAction, ServicesAction,
+};
 use config::AppConfig;
 use runtime::ContainerRuntime;
 
@@ -46,6 +50,51 @@ fn resolve_workspace(workdir: &Option<std::path::PathBuf>) -> Result<std::path::
     }
 }
 
+/// Canonicalize the `--add-workspace` roots, rejecting anything that isn't a
+/// directory or that duplicates the primary workspace.
+fn resolve_extra_workspaces(
+    primary: &Path,
+    extra: &[std::path::PathBuf],
+) -> Result<Vec<std::path::PathBuf>> {
+    let mut out = Vec::with_capacity(extra.len());
+    for p in extra {
+        let root = std::fs::canonicalize(p)
+            .with_context(|| format!("Invalid --add-workspace path {}", p.display()))?;
+        if !root.is_dir() {
+            anyhow::bail!("--add-workspace {} is not a directory", root.display());
+        }
+        if root == primary || out.contains(&root) {
+            continue;
+        }
+        out.push(root);
+    }
+    Ok(out)
+}
+
+/// Resolve the runtime preference: --runtime flag > AI_POD_RUNTIME env >
+/// runtime_path in config.json > autodetect (podman preferred, docker
+/// fallback). Binary paths from the env, the config and $PODMAN are
+/// checked here, before anything runs the runtime.
+fn resolve_runtime(cli: &Cli) -> Result<ContainerRuntime> {
+    let runtime_path = AppConfig::new()
+        .ok()
+        .and_then(|config| config::GlobalConfig::load(&config).runtime_path);
+    let runtime_pref = runtime::configure(
+        cli.runtime,
+        std::env::var("AI_POD_RUNTIME").ok().as_deref(),
+        runtime_path.as_deref(),
+        std::env::var("PODMAN").ok().as_deref(),
+        cli.dry_run,
+    )?;
+    ContainerRuntime::detect(runtime_pref, cli.dry_run)
+}
+
+/// `--detach-keys`, else `detach_keys` from the global config, validated.
+fn resolve_detach_keys(cli: &Cli, config: &AppConfig) -> Result<Option<String>> {
+    let global = config::GlobalConfig::load(config);
+    runtime::resolve_detach_keys(cli.detach_keys.as_deref(), global.detach_keys.as_deref())
+}
+
 fn resolve_agent(agent: Option<cli::Agent>) -> Result<cli::Agent> {
     match agent {
         Some(a) => Ok(a),
@@ -223,12 +272,51 @@ fn maybe_prompt_gitignore(workspace: &Path) -> Result<()> {
     Ok(())
 }
 
-async fn launch_flow(cli: &Cli, rt: &ContainerRuntime) -> Result<()> {
+/// Commands that talk to the container engine, and so probe it first for a
+/// targeted error. State-only commands (mask, exclude, ...) and the server
+/// (which reports runtime errors per request) skip the probe.
+fn needs_engine(command: &Option<Command>) -> bool {
+    matches!(
+        command,
+        None | Some(
+            Command::Open { .. }
+                | Command::Recent { .. }
+                | Command::Build { .. }
+                | Command::UpdateLock
+                | Command::Image { .. }
+                | Command::Login { .. }
+                | Command::Run { .. }
+                | Command::Watch { .. }
+                | Command::Attach
+                | Command::List { .. }
+                | Command::Gc { .. }
+                | Command::Du
+                | Command::Top { .. }
+                | Command::Snapshot { .. }
+                | Command::Restore { .. }
+                | Command::Checkpoint { .. }
+                | Command::Resume { .. }
+                | Command::Clean { .. }
+                | Command::Cache { .. }
+                | Command::Warm { .. }
+        )
+    )
+}
+
+/// `snapshot` launches that snapshot image (see `ai-pod restore`) instead of
+/// building the workspace image.
+async fn launch_flow(
+    cli: &Cli,
+    rt: &ContainerRuntime,
+    workspace: &Path,
+    snapshot: Option<&str>,
+    warm: bool,
+) -> Result<()> {
     let config = AppConfig::new()?;
     config.init()?;
 
-    // 1. Resolve workspace
-    let workspace = resolve_workspace(&cli.workdir)?;
+    // 1. Workspace (resolved by the caller: --workdir/cwd, alias or picker)
+    let workspace = workspace.to_path_buf();
     eprintln!("{} {}", "Workspace:".blue(), workspace.display());
 
     // 2. Locate Dockerfile
@@ -241,24 +329,50 @@ async fn launch_flow(cli: &Cli, rt: &ContainerRuntime) -> Result<()> {
         );
     }
 
-    // 3. Credential scan
+    // Surface config problems up front; the launch itself still applies the
+    // usual skip-and-warn fallbacks.
+    config_check::print_diagnostics(&config_check::diagnose(&config, Some(&workspace), rt.kind));
+
+    let extra_workspaces = resolve_extra_workspaces(&workspace, &cli.add_workspace)?;
+    let detach_keys = resolve_detach_keys(cli, &config)?;
+    for root in &extra_workspaces {
+        eprintln!("{} {}", "Also mounting:".blue(), root.display());
+    }
+
+    // Ask the runtime what the setup steps below need to know while the
+    // credential scan and the server start-up run.
+    let launch_image = snapshot.map_or_else(|| image::image_name(&workspace), str::to_string);
+    ai_pod::prefetch::start(rt, &config, &workspace, &launch_image);
+
+    // 3. Credential scan (every mounted root, not just the primary one)
     if !cli.no_credential_check {
-        if !credentials::check_credentials(&workspace, &config)? {
-            eprintln!("{}", "Aborted.".red());
-            return Ok(());
+        for root in std::iter::once(&workspace).chain(&extra_workspaces) {
+            if !credentials::check_credentials(root, &config)? {
+                eprintln!("{}", "Aborted.".red());
+                return Ok(());
+            }
         }
     }
 
     // 4. Ensure shared server is running (must be up before image build so the
-    //    Dockerfile can fetch /install/{agent}.sh from http://{gateway}:7822)
+    //    Dockerfile can fetch /install/{agent}.sh from http://{gateway}:7822),
+    //    on the workspace's own port if the registry assigns it one
+    server::lifecycle::select_port(registry::server_port(&config, &workspace));
     server::lifecycle::ensure_shared_server(&config).await?;
 
     // Prune .ai-pod/commands/ entries for sessions whose container is gone.
     clean_stale_sessions(rt, &workspace);
 
     // 5. Build image if needed
-    let image = image::image_name(&workspace);
-    image::ensure_image(rt, &dockerfile, &image, cli.rebuild, cli.no_cache)?;
+    let image = match snapshot {
+        Some(image) => image.to_string(),
+        None => {
+            let image = image::image_name(&workspace);
+            let build_opts = image::BuildOptions::from_flags(&cli.build);
+            image::ensure_image(rt, &config, &dockerfile, &image, &build_opts)?;
+            image
+        }
+    };
 
     // Bridge the gap between build completion and the first authenticated
     // request: re-arm the inactivity timer so the server doesn't shut down
@@ -269,35 +383,198 @@ async fn launch_flow(cli: &Cli, rt: &ContainerRuntime) -> Result<()> {
     server::lifecycle::check_server_version().await?;
 
     // 7. Get or create project state (stable api_key)
+    ai_pod::collision::warn_if_shared(&registry::WorkspaceRegistry::load(&config), &workspace);
     let project_id = workspace::workspace_hash(&workspace);
     let state = server::lifecycle::get_or_create_project_state(&config, &workspace)?;
 
     // 8. Reload server config so it picks up the updated project file
     server::lifecycle::reload_config().await?;
 
-    // 9. Launch container
-    container::launch_container(
+    registry::record_launch(&config, &workspace);
+
+    // 9. Launch container, and again from a rebuilt image each time the
+    //    Dockerfile changes under --watch-dockerfile. A snapshot isn't built
+    //    from the Dockerfile, so there's nothing to watch.
+    let watch_dockerfile = cli.watch_dockerfile.filter(|_| snapshot.is_none());
+    if cli.watch_dockerfile.is_some() && snapshot.is_some() {
+        eprintln!(
+            "{} --watch-dockerfile is ignored when launching from a snapshot.",
+            "warning:".yellow().bold()
+        );
+    }
+    let mut opts = container::LaunchOptions {
+        rebuild: cli.build.rebuild,
+        extra_workspaces,
+        detach_keys,
+        watch_dockerfile,
+        new_instance: cli.new,
+        title: cli.title.as_deref().and_then(ai_pod::session_title::normalize),
+        warm,
+        entrypoint: cli.entrypoint.clone(),
+        command: cli.launch_command.clone(),
+    };
+    while container::launch_container(
         rt,
         &config,
         &workspace,
-        cli.rebuild,
         &image,
         &project_id,
         &state.api_key,
-    )?;
+        &opts,
+    )? {
+        // Keep the home volume: only the image is rebuilt.
+        opts.rebuild = false;
+        let build_opts = image::BuildOptions {
+            force: true,
+            ..image::BuildOptions::from_flags(&cli.build)
+        };
+        image::ensure_image(rt, &config, &dockerfile, &image, &build_opts)?;
+    }
 
     Ok(())
 }
 
+/// Everything `run_in_container` needs, prepared once for `ai-pod run` and
+/// for every job of `ai-pod watch`.
+struct RunContext {
+    config: AppConfig,
+    workspace: std::path::PathBuf,
+    image: String,
+    project_id: String,
+    api_key: String,
+    opts: container::LaunchOptions,
+}
+
+impl RunContext {
+    fn run(
+        &self,
+        rt: &ContainerRuntime,
+        command: &str,
+        args: &[String],
+        interactive: bool,
+        run_opts: &container::RunOptions,
+    ) -> Result<std::process::ExitStatus> {
+        container::run_in_container(
+            rt,
+            &self.config,
+            &self.workspace,
+            &self.image,
+            &self.project_id,
+            &self.api_key,
+            command,
+            args,
+            interactive,
+            &self.opts,
+            run_opts,
+        )
+    }
+}
+
+/// Credential triage, shared server and image for a one-off command.
+/// `None` when the user aborted the triage. `workdir` and `build` are the
+/// subcommand's own flags, combined with those given before it.
+async fn prepare_run(
+    cli: &Cli,
+    rt: &ContainerRuntime,
+    workdir: &Option<std::path::PathBuf>,
+    build: &cli::BuildFlags,
+    interactive: bool,
+) -> Result<Option<RunContext>> {
+    let config = AppConfig::new()?;
+    config.init()?;
+    let workspace = resolve_workspace(&workdir.clone().or_else(|| cli.workdir.clone()))?;
+    let build = cli.build.merge(build);
+    let dockerfile = workspace.join(image::DOCKERFILE_NAME);
+    if !dockerfile.exists() {
+        anyhow::bail!(
+            "No {} found in {}.\nRun `ai-pod init` to create one.",
+            image::DOCKERFILE_NAME,
+            workspace.display()
+        );
+    }
+    let extra_workspaces = resolve_extra_workspaces(&workspace, &cli.add_workspace)?;
+    let detach_keys = resolve_detach_keys(cli, &config)?;
+    ai_pod::prefetch::start(rt, &config, &workspace, &image::image_name(&workspace));
+    if !cli.no_credential_check {
+        for root in std::iter::once(&workspace).chain(&extra_workspaces) {
+            // Without a tty we cannot run the dialoguer-based triage. Run
+            // the silent scan instead: succeed if nothing is pending, else
+            // emit a clear error pointing the user at the interactive flow.
+            if !interactive {
+                let hash = workspace::workspace_hash(root);
+                let state = server::lifecycle::ProjectState::load(
+                    &config.project_state_file(&hash),
+                );
+                let pending = credentials::pending_credentials(root, &state);
+                if !pending.is_empty() {
+                    anyhow::bail!(
+                        "{} has {} un-triaged sensitive file(s). Run `ai-pod --workdir {}` interactively to review them, or pass `--no-credential-check`.",
+                        root.display(),
+                        pending.len(),
+                        root.display()
+                    );
+                }
+            } else if !credentials::check_credentials(root, &config)? {
+                eprintln!("{}", "Aborted.".red());
+                return Ok(None);
+            }
+        }
+    }
+    server::lifecycle::select_port(registry::server_port(&config, &workspace));
+    server::lifecycle::ensure_shared_server(&config).await?;
+    let image = image::image_name(&workspace);
+    let build_opts = image::BuildOptions::from_flags(&build);
+    image::ensure_image(rt, &config, &dockerfile, &image, &build_opts)?;
+    server::lifecycle::bump_keep_alive().await;
+    server::lifecycle::check_server_version().await?;
+    let project_id = workspace::workspace_hash(&workspace);
+    let state = server::lifecycle::get_or_create_project_state(&config, &workspace)?;
+    server::lifecycle::reload_config().await?;
+
+    Ok(Some(RunContext {
+        config,
+        workspace,
+        image,
+        project_id,
+        api_key: state.api_key,
+        opts: container::LaunchOptions {
+            rebuild: build.rebuild,
+            extra_workspaces,
+            detach_keys,
+            watch_dockerfile: None,
+            new_instance: None,
+            title: None,
+            warm: false,
+            entrypoint: None,
+            command: Vec::new(),
+        },
+    }))
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    completions::handle_env();
+    let cli = Cli::parse_validated();
+    if let Some(Command::RecordExec { bundle, argv }) = &cli.command {
+        ai_pod::record::forward_signals();
+        std::process::exit(ai_pod::record::exec(bundle, argv)?);
+    }
 
     // Show the cached update notification — a pure local read, no network wait.
     // The cache is refreshed in the background by the shared server. Skipped for
     // internal/daemon commands and when stdin isn't a tty (we're being driven by
     // another program, e.g. an IDE speaking ACP, where it would just be noise).
-    if !matches!(&cli.command, Some(Command::Serve) | Some(Command::Update))
+    if !matches!(
+        &cli.command,
+        Some(Command::Serve { .. })
+            | Some(Command::Control { .. })
+            | Some(Command::Server { .. })
+            | Some(Command::Replay { .. })
+            | Some(Command::Update)
+            | Some(Command::Completions { .. })
+            | Some(Command::Man { .. })
+            | Some(Command::Describe { .. })
+    )
         && ai_pod::is_stdin_tty()
         && let Ok(config) = AppConfig::new()
     {
@@ -306,15 +583,76 @@ async fn main() -> Result<()> {
 
     // Commands that don't need a container runtime
     match &cli.command {
-        Some(Command::Init { workdir, agent, image }) => {
+        Some(Command::Replay { bundle, from, list }) => {
+            return ai_pod::record::replay(bundle, *from, *list);
+        }
+        Some(Command::Init {
+            workdir,
+            agent,
+            image,
+            from,
+            template,
+            builtin,
+        }) => {
             let workspace = resolve_workspace(workdir)?;
-            init_project(&workspace, agent.clone(), image.clone())?;
+            let config = AppConfig::new()?;
+            let from = match from {
+                Some(f) => Some(f.clone()),
+                None if *builtin || agent.is_some() || image.is_some() => None,
+                None => config::GlobalConfig::load(&config).init.from,
+            };
+            match from {
+                Some(from) => {
+                    config.init()?;
+                    template::init_from(&config, &workspace, &from, template.as_deref()).await?;
+                    maybe_prompt_gitignore(&workspace)?;
+                }
+                None if template.is_some() => anyhow::bail!("--template requires --from"),
+                None => init_project(&workspace, agent.clone(), image.clone())?,
+            }
+            return Ok(());
+        }
+        Some(Command::ExportSetup { bundle }) => {
+            let config = AppConfig::new()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            setup_bundle::export(&config, &workspace, bundle)?;
+            return Ok(());
+        }
+        Some(Command::ImportSetup { bundle, yes }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            setup_bundle::import(&config, &workspace, bundle, *yes)?;
             return Ok(());
         }
         Some(Command::Update) => {
             update::run_update().await?;
             return Ok(());
         }
+        Some(Command::Completions { shell }) => {
+            completions::run_completions(*shell)?;
+            return Ok(());
+        }
+        Some(Command::Man { out_dir }) => {
+            completions::run_man(out_dir.as_deref())?;
+            return Ok(());
+        }
+        Some(Command::Describe { json }) => {
+            ai_pod::describe::run(*json)?;
+            return Ok(());
+        }
+        Some(Command::Review) => {
+            let config = AppConfig::new()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            review::run(&config.config_dir, &workspace)?;
+            return Ok(());
+        }
+        Some(Command::Changes { open }) => {
+            let config = AppConfig::new()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            editor::run(&config.config_dir, &workspace, *open)?;
+            return Ok(());
+        }
         Some(Command::EnvFiles { action, workdir }) => {
             let config = AppConfig::new()?;
             config.init()?;
@@ -341,12 +679,204 @@ async fn main() -> Result<()> {
         Some(Command::Mount { action }) => {
             let config = AppConfig::new()?;
             config.init()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            let scope = |project: bool| {
+                if project {
+                    mount_cli::MountScope::Project(&workspace)
+                } else {
+                    mount_cli::MountScope::Global
+                }
+            };
+            match action {
+                MountAction::List => mount_cli::run_list(&config, Some(&workspace))?,
+                MountAction::Add {
+                    spec,
+                    writable,
+                    yes,
+                    volume,
+                    project,
+                } => mount_cli::run_add(&config, scope(*project), spec, *writable, *volume, *yes)?,
+                MountAction::Remove { host, project } => {
+                    mount_cli::run_remove(&config, scope(*project), host)?
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Cache {
+            action: CacheAction::Enable { name },
+        }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            cache_cli::run_enable(&config, *name)?;
+            return Ok(());
+        }
+        Some(Command::Cache {
+            action: CacheAction::Disable { name },
+        }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            cache_cli::run_disable(&config, *name)?;
+            return Ok(());
+        }
+        Some(Command::Config { action }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            let scope = |project: bool| {
+                if project {
+                    config_cli::ConfigScope::Project(&workspace)
+                } else {
+                    config_cli::ConfigScope::Global
+                }
+            };
+            match action {
+                ConfigAction::List { project } => config_cli::run_list(&config, scope(*project))?,
+                ConfigAction::Get { key, project } => {
+                    config_cli::run_get(&config, scope(*project), key)?
+                }
+                ConfigAction::Set {
+                    key,
+                    value,
+                    project,
+                } => config_cli::run_set(&config, scope(*project), key, value)?,
+                ConfigAction::Unset { key, project } => {
+                    config_cli::run_unset(&config, scope(*project), key)?
+                }
+                ConfigAction::Edit => config_cli::run_edit(&config)?,
+                ConfigAction::Check => {
+                    let rt = resolve_runtime(&cli)?;
+                    config_check::run_check(&config, &workspace, rt.kind)?
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Alias { action }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            match action {
+                AliasAction::List => registry::run_alias_list(&config)?,
+                AliasAction::Add { name, path } => {
+                    let workspace = resolve_workspace(&path.clone().or(cli.workdir.clone()))?;
+                    registry::run_alias_add(&config, name, &workspace)?;
+                }
+                AliasAction::Remove { name } => registry::run_alias_remove(&config, name)?,
+            }
+            return Ok(());
+        }
+        Some(Command::Notifications { all, limit }) => {
+            let config = AppConfig::new()?;
+            let workspace = if *all {
+                None
+            } else {
+                Some(resolve_workspace(&cli.workdir)?)
+            };
+            events::print_notifications(&config.config_dir, workspace.as_deref(), *limit);
+            return Ok(());
+        }
+        Some(Command::Snooze { duration }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            snooze::run_snooze(&config.config_dir, &resolve_workspace(&cli.workdir)?, duration)?;
+            return Ok(());
+        }
+        Some(Command::Ack) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            snooze::run_ack(&config.config_dir, &resolve_workspace(&cli.workdir)?)?;
+            return Ok(());
+        }
+        Some(Command::Report {
+            action: ReportAction::Weekly { weeks_ago, out },
+        }) => {
+            let config = AppConfig::new()?;
+            ai_pod::report::run_weekly(&config.config_dir, *weeks_ago, out.as_ref())?;
+            return Ok(());
+        }
+        Some(Command::Stats { json, reset }) => {
+            let config = AppConfig::new()?;
+            if *reset {
+                stats::reset(&config.config_dir)?;
+            } else {
+                stats::run(&config.config_dir, *json)?;
+            }
+            return Ok(());
+        }
+        Some(Command::Egress { session, all }) => {
+            let config = AppConfig::new()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            server::egress::print_report(&config.config_dir, &workspace, session.as_deref(), *all)?;
+            return Ok(());
+        }
+        Some(Command::List { workspaces: true }) => {
+            let config = AppConfig::new()?;
+            registry::run_workspace_list(&config)?;
+            return Ok(());
+        }
+        Some(Command::Register { path, server_port }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            let workspace = resolve_workspace(&path.clone().or(cli.workdir.clone()))?;
+            registry::run_register(&config, &workspace, *server_port)?;
+            return Ok(());
+        }
+        Some(Command::Unregister { path }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            // A deleted checkout can't be canonicalized; take the path as given.
+            let workspace = match path.clone().or(cli.workdir.clone()) {
+                Some(p) => std::fs::canonicalize(&p).or_else(|_| std::path::absolute(&p))?,
+                None => std::env::current_dir().context("Failed to get current directory")?,
+            };
+            registry::run_unregister(&config, &workspace)?;
+            return Ok(());
+        }
+        Some(Command::Recent { list }) if *list || !ai_pod::is_stdin_tty() => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            registry::run_recent_list(&config)?;
+            return Ok(());
+        }
+        Some(Command::Security { action }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            let (SecurityAction::Show { workdir }
+            | SecurityAction::Relax { workdir }
+            | SecurityAction::Enforce { workdir }) = action;
+            let ws = workdir.clone().or_else(|| cli.workdir.clone());
+            let workspace = resolve_workspace(&ws)?;
+            let hash = workspace::workspace_hash(&workspace);
+            let state_path = config.project_state_file(&hash);
+            let mut state = server::lifecycle::ProjectState::load(&state_path);
             match action {
-                MountAction::List => mount_cli::run_list(&config)?,
-                MountAction::Add { spec, writable, yes } => {
-                    mount_cli::run_add(&config, spec, *writable, *yes)?
+                SecurityAction::Show { .. } => {
+                    let global = config::GlobalConfig::load(&config);
+                    let args = container::security_args(
+                        &global.security,
+                        &config.home_dir,
+                        state.relax_security,
+                    );
+                    if state.relax_security {
+                        println!(
+                            "{} security is relaxed for this workspace",
+                            "Note:".yellow().bold()
+                        );
+                    }
+                    if args.is_empty() {
+                        println!("{}", "No hardening flags.".dimmed());
+                    } else {
+                        println!("{}", args.join(" "));
+                    }
+                }
+                SecurityAction::Relax { .. } => {
+                    state.relax_security = true;
+                    state.save(&state_path)?;
+                    println!("{} {}", "Relaxed:".yellow().bold(), workspace.display());
+                }
+                SecurityAction::Enforce { .. } => {
+                    state.relax_security = false;
+                    state.save(&state_path)?;
+                    println!("{} {}", "Enforced:".green().bold(), workspace.display());
                 }
-                MountAction::Remove { host } => mount_cli::run_remove(&config, host)?,
             }
             return Ok(());
         }
@@ -395,20 +925,73 @@ async fn main() -> Result<()> {
         _ => {}
     }
 
-    // Resolve the runtime preference: --runtime flag > AI_POD_RUNTIME env >
-    // autodetect (podman preferred, docker fallback).
-    let runtime_pref = cli.runtime.or_else(|| {
-        std::env::var("AI_POD_RUNTIME")
-            .ok()
-            .and_then(|v| crate::runtime::RuntimeKind::from_value(&v))
-    });
-    let rt = ContainerRuntime::detect(runtime_pref, cli.dry_run)?;
+    let rt = resolve_runtime(&cli)?;
+    ai_pod::vm::configure(rt.kind);
+    let _recording = cli.record.as_deref().map(ai_pod::record::start).transpose()?;
+    if needs_engine(&cli.command) {
+        runtime_check::ensure_working(&rt)?;
+        runtime_check::warn_missing_subuid(&rt);
+    }
 
     match &cli.command {
-        Some(Command::Build) => {
+        Some(Command::Build {
+            all: true,
+            jobs,
+            build,
+            ..
+        }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            server::lifecycle::ensure_shared_server(&config).await?;
+            build_all::run(&rt, &config, &image::BuildOptions::from_flags(&cli.build.merge(build)), *jobs)?;
+        }
+        Some(Command::Build { workdir, build, .. }) => {
             let config = AppConfig::new()?;
             config.init()?;
+            let workspace = resolve_workspace(&workdir.clone().or_else(|| cli.workdir.clone()))?;
+            let dockerfile = workspace.join(image::DOCKERFILE_NAME);
+            if !dockerfile.exists() {
+                anyhow::bail!(
+                    "No {} found in {}.\nRun `ai-pod init` to create one.",
+                    image::DOCKERFILE_NAME,
+                    workspace.display()
+                );
+            }
+            server::lifecycle::select_port(registry::server_port(&config, &workspace));
+            server::lifecycle::ensure_shared_server(&config).await?;
+            let image = image::image_name(&workspace);
+            let build_opts = image::BuildOptions::from_flags(&cli.build.merge(build));
+            image::ensure_image(&rt, &config, &dockerfile, &image, &build_opts)?;
+        }
+        Some(Command::UpdateLock) => {
+            let config = AppConfig::new()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            provenance::update_lock(&rt, &config, &workspace)?;
+        }
+        Some(Command::Image {
+            action: ImageAction::Inspect { all },
+        }) => {
             let workspace = resolve_workspace(&cli.workdir)?;
+            image_inspect::run(&rt, &image::image_name(&workspace), *all)?;
+        }
+        Some(Command::Image {
+            action: ImageAction::Scan,
+        }) => {
+            let workspace = resolve_workspace(&cli.workdir)?;
+            let findings = image_secrets::scan_image(&rt, &image::image_name(&workspace))?;
+            image_secrets::print_findings(&findings);
+            if !findings.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Login {
+            agent,
+            workdir,
+            build,
+        }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            let workspace = resolve_workspace(&workdir.clone().or_else(|| cli.workdir.clone()))?;
             let dockerfile = workspace.join(image::DOCKERFILE_NAME);
             if !dockerfile.exists() {
                 anyhow::bail!(
@@ -417,26 +1000,108 @@ async fn main() -> Result<()> {
                     workspace.display()
                 );
             }
+            server::lifecycle::select_port(registry::server_port(&config, &workspace));
             server::lifecycle::ensure_shared_server(&config).await?;
             let image = image::image_name(&workspace);
-            image::ensure_image(&rt, &dockerfile, &image, cli.rebuild, cli.no_cache)?;
+            let build_opts = image::BuildOptions::from_flags(&cli.build.merge(build));
+            image::ensure_image(&rt, &config, &dockerfile, &image, &build_opts)?;
+            login::run(&rt, &config, &workspace, &image, agent)?;
+        }
+        Some(Command::Serve { port }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            server::lifecycle::select_port(*port);
+            server::run_server(*port, config, rt).await?;
+        }
+        Some(Command::Control { socket }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            ai_pod::control::serve(&rt, &config, socket.clone()).await?;
         }
-        Some(Command::Serve) => {
+        Some(Command::Server { action }) => {
             let config = AppConfig::new()?;
             config.init()?;
-            server::run_server(server::lifecycle::MCP_PORT, config, rt).await?;
+            match action {
+                ServerAction::Run {
+                    daemonize: true, port, ..
+                } => {
+                    server::lifecycle::select_port(*port);
+                    if server::lifecycle::ServerState::load(&server::lifecycle::server_state_file(&config)).is_alive() {
+                        println!("{}", "Shared server is already running.".yellow());
+                    } else {
+                        server::lifecycle::daemonize(&config)?;
+                    }
+                }
+                ServerAction::Run { port, .. } => {
+                    server::lifecycle::select_port(*port);
+                    server::lifecycle::supervise(&config)?
+                }
+                ServerAction::Status => server::lifecycle::print_status(&config),
+                ServerAction::Stop { port, all } => {
+                    server::lifecycle::select_port(*port);
+                    server::lifecycle::stop_server(&config, *all)?
+                }
+            }
         }
         Some(Command::Attach) => {
-            container::attach_container(&rt)?;
+            let config = AppConfig::new()?;
+            let keys = resolve_detach_keys(&cli, &config)?;
+            container::attach_container(&rt, &config, keys.as_deref())?;
+        }
+        Some(Command::List { .. }) => {
+            container::list_containers(&rt, &AppConfig::new()?)?;
+        }
+        Some(Command::Gc { yes }) => {
+            let config = AppConfig::new()?;
+            ai_pod::gc::run(&rt, &config, *yes)?;
+        }
+        Some(Command::Du) => {
+            let config = AppConfig::new()?;
+            du::run(&rt, &config)?;
+        }
+        Some(Command::Env) => {
+            let config = AppConfig::new()?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            server::lifecycle::select_port(registry::server_port(&config, &workspace));
+            let opts = container::LaunchOptions {
+                extra_workspaces: resolve_extra_workspaces(&workspace, &cli.add_workspace)?,
+                detach_keys: resolve_detach_keys(&cli, &config)?,
+                new_instance: cli.new,
+                title: cli.title.as_deref().and_then(ai_pod::session_title::normalize),
+                entrypoint: cli.entrypoint.clone(),
+                command: cli.launch_command.clone(),
+                ..Default::default()
+            };
+            ai_pod::env_cli::run(&rt, &config, &workspace, &opts)?;
         }
-        Some(Command::List) => {
-            container::list_containers(&rt)?;
+        Some(Command::Top { interval, once }) => {
+            let config = AppConfig::new()?;
+            top::run(&rt, &config, *interval, *once)?;
         }
-        Some(Command::Clean { workdir }) => {
+        Some(Command::Clean {
+            workdir,
+            state_only,
+            keep_volume,
+            volume_only,
+            image,
+            interactive,
+        }) => {
             let config = AppConfig::new()?;
+            if *interactive {
+                return ai_pod::clean_picker::run(&rt, &config);
+            }
             let ws = workdir.clone().or_else(|| cli.workdir.clone());
             let workspace = resolve_workspace(&ws)?;
-            container::clean_container(&rt, &config, &workspace)?;
+            if *state_only {
+                container::clean_state(&rt, &config, &workspace)?;
+            } else {
+                let opts = container::CleanOptions {
+                    containers: !volume_only,
+                    volumes: !keep_volume,
+                    image: *image,
+                };
+                container::clean_container(&rt, &config, &workspace, opts)?;
+            }
         }
         Some(Command::Mask { dir, workdir }) => {
             let config = AppConfig::new()?;
@@ -495,60 +1160,119 @@ async fn main() -> Result<()> {
             }
             println!("{} {}", "Unmasked:".green().bold(), dir);
         }
-        Some(Command::Run { command, args }) => {
+        Some(Command::Exclude { path, workdir }) => {
             let config = AppConfig::new()?;
             config.init()?;
-            let workspace = resolve_workspace(&cli.workdir)?;
-            let dockerfile = workspace.join(image::DOCKERFILE_NAME);
-            if !dockerfile.exists() {
-                anyhow::bail!(
-                    "No {} found in {}.\nRun `ai-pod init` to create one.",
-                    image::DOCKERFILE_NAME,
-                    workspace.display()
+            let ws = workdir.clone().or_else(|| cli.workdir.clone());
+            let workspace = resolve_workspace(&ws)?;
+            let rel = workspace::normalize_exclude_path(path).map_err(anyhow::Error::msg)?;
+
+            let hash = workspace::workspace_hash(&workspace);
+            let state_path = config.project_state_file(&hash);
+            let mut state = server::lifecycle::ProjectState::load(&state_path);
+            if state.is_excluded(&rel) {
+                println!("Already excluded: {}", rel);
+                return Ok(());
+            }
+            state.add_excluded(&rel);
+            state.save(&state_path)?;
+            println!("{} {}", "Excluded:".green().bold(), rel);
+
+            let prefix = workspace::container_prefix(&workspace);
+            if !container::containers_for_prefix(&rt, &prefix, true)?.is_empty() {
+                println!(
+                    "{} a container is running for this workspace; the path stays visible to it until the next launch.",
+                    "Note:".yellow().bold()
                 );
             }
-            let interactive = ai_pod::is_stdin_tty();
-            if !cli.no_credential_check {
-                // Without a tty we cannot run the dialoguer-based triage. Run
-                // the silent scan instead: succeed if nothing is pending, else
-                // emit a clear error pointing the user at the interactive flow.
-                if !interactive {
-                    let hash = workspace::workspace_hash(&workspace);
-                    let state = server::lifecycle::ProjectState::load(
-                        &config.project_state_file(&hash),
-                    );
-                    let pending = credentials::pending_credentials(&workspace, &state);
-                    if !pending.is_empty() {
-                        anyhow::bail!(
-                            "Workspace has {} un-triaged sensitive file(s). Run `ai-pod` interactively to review them, or pass `--no-credential-check`.",
-                            pending.len()
-                        );
-                    }
-                } else if !credentials::check_credentials(&workspace, &config)? {
-                    eprintln!("{}", "Aborted.".red());
-                    return Ok(());
+        }
+        Some(Command::Unexclude { path, workdir }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            let ws = workdir.clone().or_else(|| cli.workdir.clone());
+            let workspace = resolve_workspace(&ws)?;
+            let rel = workspace::normalize_exclude_path(path).map_err(anyhow::Error::msg)?;
+
+            let hash = workspace::workspace_hash(&workspace);
+            let state_path = config.project_state_file(&hash);
+            let mut state = server::lifecycle::ProjectState::load(&state_path);
+            if !state.is_excluded(&rel) {
+                println!("Not excluded: {}", rel);
+                return Ok(());
+            }
+            state.remove_excluded(&rel);
+            state.save(&state_path)?;
+            println!("{} {}", "Unexcluded:".green().bold(), rel);
+        }
+        Some(Command::Run {
+            workdir,
+            env,
+            no_tty,
+            user,
+            title,
+            build,
+            argv,
+        }) => {
+            let Some(mut ctx) = prepare_run(&cli, &rt, workdir, build, ai_pod::is_stdin_tty()).await? else {
+                return Ok(());
+            };
+            let title = title.as_deref().or(cli.title.as_deref());
+            ctx.opts.title = ai_pod::session_title::resolve(title, &argv[0], &argv[1..]);
+            // A pseudo-TTY only when both ends are a terminal, so `ai-pod run
+            // tsc | tee log` gets clean output.
+            let tty = !no_tty && ai_pod::is_stdin_tty() && ai_pod::is_stdout_tty();
+            let run_opts = container::RunOptions {
+                env: env.clone(),
+                user: user.clone(),
+            };
+            let status = ctx.run(&rt, &argv[0], &argv[1..], tty, &run_opts)?;
+            // Pass the command's exit code through for scripts and Makefiles.
+            if !status.success() {
+                std::process::exit(container::exit_code(status));
+            }
+        }
+        Some(Command::Watch {
+            workdir,
+            build,
+            globs,
+            prompt,
+            agent,
+            title,
+            command,
+        }) => {
+            let (job, job_args) = watch::job_command(agent, prompt.as_deref(), command)?;
+            let Some(mut ctx) = prepare_run(&cli, &rt, workdir, build, false).await? else {
+                return Ok(());
+            };
+            let title = title.as_deref().or(cli.title.as_deref());
+            ctx.opts.title = ai_pod::session_title::resolve(title, &job, &job_args);
+            let globs = if !globs.is_empty() {
+                globs.clone()
+            } else {
+                let configured = config::GlobalConfig::load(&ctx.config).watch.globs;
+                if configured.is_empty() {
+                    watch::DEFAULT_GLOBS.iter().map(|g| g.to_string()).collect()
+                } else {
+                    configured
+                }
+            };
+            eprintln!("{} {} ({})", "Watching:".blue().bold(), ctx.workspace.display(), globs.join(", "));
+            loop {
+                // The shared server shuts down when idle; bring it back for
+                // a job that follows a long wait.
+                server::lifecycle::ensure_shared_server(&ctx.config).await?;
+                server::lifecycle::reload_config().await?;
+                match ctx.run(&rt, &job, &job_args, false, &container::RunOptions::default()) {
+                    Ok(status) if !status.success() => eprintln!(
+                        "{} job exited with status {}",
+                        "warning:".yellow().bold(),
+                        container::exit_code(status)
+                    ),
+                    Ok(_) => {}
+                    Err(e) => eprintln!("{} {:#}", "warning:".yellow().bold(), e),
                 }
+                watch::wait_for_change(&ctx.workspace, &globs);
             }
-            server::lifecycle::ensure_shared_server(&config).await?;
-            let image = image::image_name(&workspace);
-            image::ensure_image(&rt, &dockerfile, &image, cli.rebuild, cli.no_cache)?;
-            server::lifecycle::bump_keep_alive().await;
-            server::lifecycle::check_server_version().await?;
-            let project_id = workspace::workspace_hash(&workspace);
-            let state = server::lifecycle::get_or_create_project_state(&config, &workspace)?;
-            server::lifecycle::reload_config().await?;
-
-            container::run_in_container(
-                &rt,
-                &config,
-                &workspace,
-                &image,
-                &project_id,
-                &state.api_key,
-                command,
-                args,
-                interactive,
-            )?;
         }
         Some(Command::Commands { action }) => {
             let config = AppConfig::new()?;
@@ -588,8 +1312,78 @@ async fn main() -> Result<()> {
                 }
             }
         }
+        Some(Command::Cache { action }) => match action {
+            CacheAction::List => {
+                let config = AppConfig::new()?;
+                cache_cli::run_list(&rt, &config)?;
+            }
+            CacheAction::Clear { name } => cache_cli::run_clear(&rt, *name)?,
+            _ => unreachable!(),
+        },
+        Some(Command::Snapshot { name, list, rm }) => {
+            let workspace = resolve_workspace(&cli.workdir)?;
+            if *list {
+                snapshot::print_list(&rt, &workspace)?;
+            } else if let Some(name) = rm {
+                snapshot::remove(&rt, &workspace, name)?;
+            } else {
+                snapshot::create(&rt, &workspace, name.as_deref())?;
+            }
+        }
+        Some(Command::Restore { name }) => {
+            let workspace = resolve_workspace(&cli.workdir)?;
+            let image = snapshot::resolve_restore(&rt, &workspace, name.as_deref())?;
+            launch_flow(&cli, &rt, &workspace, Some(&image), false).await?;
+        }
+        Some(Command::Checkpoint { name, list, rm }) => {
+            let config = AppConfig::new()?;
+            if *list {
+                checkpoint::print_list(&config);
+            } else if let Some(name) = rm {
+                checkpoint::remove(&config, name)?;
+            } else {
+                let workspace = resolve_workspace(&cli.workdir)?;
+                checkpoint::create(&rt, &config, &workspace, name.as_deref())?;
+            }
+        }
+        Some(Command::Resume { name }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            let (name, cp) = checkpoint::resolve(&config, name.as_deref())?;
+            // The restored agent talks to the shared server with its original
+            // API key, so the server must be up with the project loaded.
+            server::lifecycle::select_port(registry::server_port(&config, &cp.workspace));
+            server::lifecycle::ensure_shared_server(&config).await?;
+            server::lifecycle::get_or_create_project_state(&config, &cp.workspace)?;
+            server::lifecycle::reload_config().await?;
+            let detach_keys = resolve_detach_keys(&cli, &config)?;
+            checkpoint::resume(&rt, &config, &name, &cp, detach_keys.as_deref())?;
+        }
+        Some(Command::Open { target }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            let workspace = registry::resolve_open_target(&config, target)?;
+            launch_flow(&cli, &rt, &workspace, None, false).await?;
+        }
+        Some(Command::Recent { .. }) => {
+            let config = AppConfig::new()?;
+            config.init()?;
+            match registry::pick_recent(&config)? {
+                Some(workspace) => launch_flow(&cli, &rt, &workspace, None, false).await?,
+                None => println!("No recently launched workspaces."),
+            }
+        }
+        Some(Command::Warm { workdir, rm }) => {
+            let workspace = resolve_workspace(&workdir.clone().or_else(|| cli.workdir.clone()))?;
+            if *rm {
+                ai_pod::warm::run_rm(&rt, &AppConfig::new()?, &workspace);
+            } else {
+                launch_flow(&cli, &rt, &workspace, None, true).await?;
+            }
+        }
         None => {
-            launch_flow(&cli, &rt).await?;
+            let workspace = resolve_workspace(&cli.workdir)?;
+            launch_flow(&cli, &rt, &workspace, None, false).await?;
         }
         _ => unreachable!(),
     }
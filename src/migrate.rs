@@ -0,0 +1,263 @@
+//! Schema version of `~/.ai-pod/` and the migrations between versions.
+//! `~/.ai-pod/schema-version` holds the version the directory was last
+//! brought up to. When a newer ai-pod finds an older version it backs up the
+//! JSON files to `~/.ai-pod/backups/<time>-v<old>/`, runs the pending
+//! migrations in order and bumps the marker. A directory written by a newer
+//! release is left alone, with a warning.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+
+/// The version this release writes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const MARKER: &str = "schema-version";
+
+struct Migration {
+    /// Version the directory is at after this migration.
+    to: u32,
+    description: &'static str,
+    apply: fn(&Path) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 1,
+    description: "spell mount keys `host`, `container` and `writable`",
+    apply: canonical_mount_keys,
+}];
+
+fn marker_path(dir: &Path) -> PathBuf {
+    dir.join(MARKER)
+}
+
+/// The directory's schema version: the marker's, else 0 for a directory
+/// from before versioning, or `None` for a fresh one with nothing to
+/// migrate.
+fn current_version(dir: &Path) -> Option<u32> {
+    if let Ok(raw) = std::fs::read_to_string(marker_path(dir)) {
+        return Some(raw.trim().parse().unwrap_or(0));
+    }
+    (!json_files(dir).is_empty()).then_some(0)
+}
+
+/// Top-level `*.json` files: project and server state, the workspace
+/// registry, and the global config, which may live in the separate settings
+/// directory.
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "json"))
+        .collect();
+    files.sort();
+    let settings = crate::layout::settings_dir(dir).join("config.json");
+    if !files.contains(&settings) && settings.is_file() {
+        files.push(settings);
+    }
+    files
+}
+
+fn write_marker(dir: &Path, version: u32) -> Result<()> {
+    let path = marker_path(dir);
+    let tmp = path.with_extension("tmp");
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp)
+        .context("Failed to write schema version")?;
+    writeln!(file, "{}", version)?;
+    std::fs::rename(&tmp, &path).context("Failed to write schema version")
+}
+
+/// Copy the JSON files to a fresh backup directory and return it.
+fn backup(dir: &Path, from: u32, now: u64) -> Result<PathBuf> {
+    let target = dir.join("backups").join(format!("{}-v{}", now, from));
+    std::fs::create_dir_all(&target)
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+    for file in json_files(dir) {
+        let name = file.file_name().expect("read_dir entries have names");
+        std::fs::copy(&file, target.join(name))
+            .with_context(|| format!("Failed to back up {}", file.display()))?;
+    }
+    Ok(target)
+}
+
+/// Bring `dir` up to [`SCHEMA_VERSION`]. Returns the backup directory when
+/// migrations ran.
+fn migrate_dir(dir: &Path, now: u64) -> Result<Option<PathBuf>> {
+    let Some(from) = current_version(dir) else {
+        write_marker(dir, SCHEMA_VERSION)?;
+        return Ok(None);
+    };
+    if from > SCHEMA_VERSION {
+        eprintln!(
+            "{} {} was last used by a newer ai-pod (schema {}, this release knows {}); settings it added may be ignored.",
+            "warning:".yellow().bold(),
+            dir.display(),
+            from,
+            SCHEMA_VERSION
+        );
+        return Ok(None);
+    }
+    if from == SCHEMA_VERSION {
+        return Ok(None);
+    }
+    let saved = backup(dir, from, now)?;
+    for step in MIGRATIONS.iter().filter(|m| m.to > from) {
+        (step.apply)(dir).with_context(|| {
+            format!(
+                "Migration to schema {} ({}) failed; the files before it are in {}",
+                step.to,
+                step.description,
+                saved.display()
+            )
+        })?;
+        write_marker(dir, step.to)?;
+    }
+    Ok(Some(saved))
+}
+
+/// Run pending migrations of `~/.ai-pod/`, once per process, under a lock
+/// so concurrent ai-pod invocations don't migrate twice.
+pub fn run(config: &AppConfig) -> Result<()> {
+    static DONE: std::sync::OnceLock<()> = std::sync::OnceLock::new();
+    if DONE.get().is_some() {
+        return Ok(());
+    }
+    let dir = &config.config_dir;
+    if current_version(dir) == Some(SCHEMA_VERSION) {
+        let _ = DONE.set(());
+        return Ok(());
+    }
+    let locks = dir.join("locks");
+    std::fs::create_dir_all(&locks).context("Failed to create ~/.ai-pod/locks/")?;
+    let lock = File::create(locks.join("migrate.lock")).context("Failed to open migration lock")?;
+    crate::launch_lock::flock(&lock, libc::LOCK_EX).context("Failed to lock ~/.ai-pod for migration")?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if let Some(saved) = migrate_dir(dir, now)? {
+        eprintln!(
+            "{} {} to schema {} (previous files in {})",
+            "Migrated:".blue().bold(),
+            dir.display(),
+            SCHEMA_VERSION,
+            saved.display()
+        );
+    }
+    let _ = DONE.set(());
+    Ok(())
+}
+
+/// Rewrite a JSON file through `edit`, saving it only when `edit` reports
+/// a change. Files that don't parse are left for the loaders to report.
+fn rewrite_json(path: &Path, edit: impl Fn(&mut Value) -> bool) -> Result<()> {
+    let Ok(raw) = std::fs::read_to_string(path) else {
+        return Ok(());
+    };
+    let Ok(mut value) = serde_json::from_str::<Value>(&raw) else {
+        return Ok(());
+    };
+    if !edit(&mut value) {
+        return Ok(());
+    }
+    let tmp = path.with_extension("tmp");
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    file.write_all(serde_json::to_string_pretty(&value)?.as_bytes())?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Schema 1: mounts added by hand may use `source`, `target` and `rw`,
+/// which are still read but no longer written. Rename them in the global
+/// config and every project state file.
+fn canonical_mount_keys(dir: &Path) -> Result<()> {
+    for path in json_files(dir) {
+        rewrite_json(&path, |value| {
+            let Some(mounts) = value.get_mut("mounts").and_then(Value::as_array_mut) else {
+                return false;
+            };
+            let mut changed = false;
+            for mount in mounts.iter_mut().filter_map(Value::as_object_mut) {
+                for (old, new) in [("source", "host"), ("target", "container"), ("rw", "writable")] {
+                    if !mount.contains_key(new)
+                        && let Some(v) = mount.remove(old)
+                    {
+                        mount.insert(new.to_string(), v);
+                        changed = true;
+                    }
+                }
+            }
+            changed
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn fresh_directory_is_stamped_without_a_backup() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(migrate_dir(dir.path(), 1).unwrap(), None);
+        assert_eq!(current_version(dir.path()), Some(SCHEMA_VERSION));
+        assert!(!dir.path().join("backups").exists());
+    }
+
+    #[test]
+    fn old_directory_is_backed_up_and_migrated() {
+        let dir = TempDir::new().unwrap();
+        let config = r#"{"mounts": [{"source": "/data", "target": "/data", "rw": true}]}"#;
+        std::fs::write(dir.path().join("config.json"), config).unwrap();
+        std::fs::write(
+            dir.path().join("3f9c2a1b7d4e.json"),
+            r#"{"workspace": "/src/api", "mounts": [{"host": "/x", "source": "/y"}]}"#,
+        )
+        .unwrap();
+
+        let saved = migrate_dir(dir.path(), 42).unwrap().unwrap();
+        assert_eq!(saved, dir.path().join("backups/42-v0"));
+        assert_eq!(std::fs::read_to_string(saved.join("config.json")).unwrap(), config);
+        assert_eq!(current_version(dir.path()), Some(1));
+
+        let migrated: Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("config.json")).unwrap()).unwrap();
+        assert_eq!(
+            migrated["mounts"][0],
+            serde_json::json!({"host": "/data", "container": "/data", "writable": true})
+        );
+        // An explicit `host` wins; the stray alias is left for the loader.
+        let project: Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("3f9c2a1b7d4e.json")).unwrap()).unwrap();
+        assert_eq!(project["mounts"][0], serde_json::json!({"host": "/x", "source": "/y"}));
+        assert_eq!(migrate_dir(dir.path(), 43).unwrap(), None);
+    }
+
+    #[test]
+    fn newer_schema_is_left_alone() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(MARKER), format!("{}\n", SCHEMA_VERSION + 1)).unwrap();
+        assert_eq!(migrate_dir(dir.path(), 1).unwrap(), None);
+        assert_eq!(current_version(dir.path()), Some(SCHEMA_VERSION + 1));
+    }
+}
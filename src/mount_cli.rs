@@ -6,7 +6,8 @@ use colored::Colorize;
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 
-use crate::config::{AppConfig, GlobalConfig, MountSpec};
+use crate::config::{AppConfig, GlobalConfig, MountSpec, MountType};
+use crate::server::lifecycle::ProjectState;
 
 const CONTAINER_HOME: &str = "/home/ai-pod";
 
@@ -42,11 +43,51 @@ pub(crate) fn parse_spec(s: &str, writable: bool, home_dir: &Path) -> Result<Mou
         host,
         container,
         writable,
+        kind: MountType::Bind,
     };
     validate_spec(&spec, home_dir)?;
     Ok(spec)
 }
 
+/// Parse a `volume:container` spec for a named-volume mount. Unlike bind
+/// mounts the container path is mandatory — there is no host path to mirror.
+pub(crate) fn parse_volume_spec(s: &str, writable: bool, home_dir: &Path) -> Result<MountSpec> {
+    let Some((name, container)) = s.split_once(':') else {
+        anyhow::bail!("Volume mounts need an explicit container path: <volume>:<container-path>");
+    };
+    let spec = MountSpec {
+        host: name.to_string(),
+        container: Some(normalize_container(container)),
+        writable,
+        kind: MountType::Volume,
+    };
+    validate_spec(&spec, home_dir)?;
+    Ok(spec)
+}
+
+/// Named volumes must be a plain runtime volume name, and may not reference
+/// ai-pod's own `ai-pod-*` volumes — those hold other workspaces' home
+/// directories (and their agent credentials).
+pub(crate) fn validate_volume_name(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        anyhow::bail!("Volume name must not be empty");
+    };
+    if !first.is_ascii_alphanumeric()
+        || !chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+    {
+        anyhow::bail!(
+            "Volume name {} may only contain ASCII letters, digits, '_', '.' or '-' \
+             and must start with a letter or digit",
+            name
+        );
+    }
+    if name.starts_with("ai-pod-") {
+        anyhow::bail!("Volume {} is managed by ai-pod and cannot be mounted", name);
+    }
+    Ok(())
+}
+
 /// Expand a leading `~` / `~/` against `home_dir` and strip any trailing
 /// slashes. Used as the single normalization point for both `mount add` and
 /// `mount remove` so they look at exactly the same string.
@@ -182,6 +223,12 @@ pub(crate) fn canonical_host(host: &str) -> Option<String> {
 /// `container::build_mount_args` so that a hand-edited `~/.ai-pod/config.json`
 /// can't bypass the security and footgun checks.
 pub(crate) fn validate_spec(spec: &MountSpec, home_dir: &Path) -> Result<String> {
+    if spec.kind == MountType::Volume {
+        validate_volume_name(&spec.host)?;
+        let target = crate::container::resolve_container_target(spec, home_dir)?;
+        validate_container_path(&target)?;
+        return Ok(target);
+    }
     validate_host_path(&spec.host)?;
     // If the host source resolves through symlinks to a different path,
     // re-run the host-side string checks against the *resolved* target so
@@ -313,17 +360,20 @@ fn collect_host_warnings(host: &str, target: &str, home_dir: &Path) -> Vec<Strin
         }
     }
 
-    // 3. ai-pod's own config dir. A writable mount here lets a compromised
+    // 3. ai-pod's own config dirs. A writable mount here lets a compromised
     //    container rewrite the global mount list for the next launch — a
     //    confused-deputy escalation that compounds with all other risks.
-    let ai_pod = home_dir.join(".ai-pod").display().to_string();
-    if host == ai_pod || host.starts_with(&format!("{}/", ai_pod)) {
-        out.push(format!(
-            "{} is ai-pod's own config directory. Mounting it (especially \
-             writable) lets a container modify the global mount list and \
-             escalate to other host paths on the next launch.",
-            host
-        ));
+    for dir in crate::layout::own_dirs(home_dir) {
+        let ai_pod = dir.display().to_string();
+        if host == ai_pod || host.starts_with(&format!("{}/", ai_pod)) {
+            out.push(format!(
+                "{} is ai-pod's own config directory. Mounting it (especially \
+                 writable) lets a container modify the global mount list and \
+                 escalate to other host paths on the next launch.",
+                host
+            ));
+            break;
+        }
     }
 
     // 4. Container target overrides files that ai-pod itself seeds. The
@@ -396,23 +446,82 @@ fn prompt_risky_mount(spec: &MountSpec, target: &str) -> bool {
         .unwrap_or(false)
 }
 
-pub fn run_add(config: &AppConfig, spec_str: &str, writable: bool, assume_yes: bool) -> Result<()> {
-    run_add_with_confirm(config, spec_str, writable, assume_yes, prompt_risky_mount)
+/// Where a `mount add`/`mount remove` is stored: the global config, or the
+/// per-project state of one workspace.
+pub enum MountScope<'a> {
+    Global,
+    Project(&'a Path),
+}
+
+fn load_scope(config: &AppConfig, scope: &MountScope) -> Vec<MountSpec> {
+    match scope {
+        MountScope::Global => GlobalConfig::load(config).mounts,
+        MountScope::Project(ws) => project_state(config, ws).mounts,
+    }
+}
+
+fn save_scope(config: &AppConfig, scope: &MountScope, mounts: Vec<MountSpec>) -> Result<()> {
+    match scope {
+        MountScope::Global => {
+            let mut gc = GlobalConfig::load(config);
+            gc.mounts = mounts;
+            gc.save(config)
+        }
+        MountScope::Project(ws) => {
+            let path = config.project_state_file(&crate::workspace::workspace_hash(ws));
+            let mut state = ProjectState::load(&path);
+            state.mounts = mounts;
+            state.save(&path)
+        }
+    }
+}
+
+fn project_state(config: &AppConfig, workspace: &Path) -> ProjectState {
+    ProjectState::load(&config.project_state_file(&crate::workspace::workspace_hash(workspace)))
+}
+
+pub fn run_add(
+    config: &AppConfig,
+    scope: MountScope,
+    spec_str: &str,
+    writable: bool,
+    volume: bool,
+    assume_yes: bool,
+) -> Result<()> {
+    run_add_with_confirm(
+        config,
+        scope,
+        spec_str,
+        writable,
+        volume,
+        assume_yes,
+        prompt_risky_mount,
+    )
 }
 
 /// Core of [`run_add`], with the risky-mount confirmation injected so tests can
 /// drive the decision without touching `dialoguer` (which would block on a TTY).
 fn run_add_with_confirm(
     config: &AppConfig,
+    scope: MountScope,
     spec_str: &str,
     writable: bool,
+    volume: bool,
     assume_yes: bool,
     confirm: impl FnOnce(&MountSpec, &str) -> bool,
 ) -> Result<()> {
-    let spec = parse_spec(spec_str, writable, &config.home_dir)?;
+    let spec = if volume {
+        parse_volume_spec(spec_str, writable, &config.home_dir)?
+    } else {
+        parse_spec(spec_str, writable, &config.home_dir)?
+    };
     let target = crate::container::resolve_container_target(&spec, &config.home_dir)?;
 
-    let warnings = warn_for_spec(&spec, &target, &config.home_dir);
+    let warnings = if spec.kind == MountType::Bind {
+        warn_for_spec(&spec, &target, &config.home_dir)
+    } else {
+        Vec::new()
+    };
     if !warnings.is_empty() {
         eprintln!(
             "{} this mount is on ai-pod's risky-path warn-list:",
@@ -426,9 +535,9 @@ fn run_add_with_confirm(
         }
     }
 
-    let mut gc = GlobalConfig::load(config);
+    let mut mounts = load_scope(config, &scope);
 
-    if let Some(existing) = gc.mounts.iter().find(|m| m.host == spec.host) {
+    if let Some(existing) = mounts.iter().find(|m| m.host == spec.host) {
         if existing.writable != spec.writable {
             anyhow::bail!(
                 "{} is already mounted as {}. Run `ai-pod mount remove {}` first, \
@@ -443,7 +552,7 @@ fn run_add_with_confirm(
         return Ok(());
     }
 
-    for existing in &gc.mounts {
+    for existing in &mounts {
         let existing_target = match crate::container::resolve_container_target(
             existing,
             &config.home_dir,
@@ -462,8 +571,8 @@ fn run_add_with_confirm(
         }
     }
 
-    gc.add(spec.clone());
-    gc.save(config)?;
+    mounts.push(spec.clone());
+    save_scope(config, &scope, mounts)?;
 
     println!(
         "{} {} → {} ({})",
@@ -473,44 +582,62 @@ fn run_add_with_confirm(
         if spec.writable { "rw" } else { "ro" }
     );
 
-    warn_if_unreadable(&spec);
+    if spec.kind == MountType::Bind {
+        warn_if_unreadable(&spec);
+    }
     Ok(())
 }
 
-pub fn run_remove(config: &AppConfig, host: &str) -> Result<()> {
+pub fn run_remove(config: &AppConfig, scope: MountScope, host: &str) -> Result<()> {
     let normalized = normalize_host(host, &config.home_dir);
-    let mut gc = GlobalConfig::load(config);
-    if !gc.remove(&normalized) {
+    let mut mounts = load_scope(config, &scope);
+    let before = mounts.len();
+    // Volume entries store a bare name, which normalize_host leaves as-is.
+    mounts.retain(|m| m.host != normalized);
+    if mounts.len() == before {
         println!("{} {}", "Not mounted:".yellow(), normalized);
         return Ok(());
     }
-    gc.save(config)?;
+    save_scope(config, &scope, mounts)?;
     println!("{} {}", "Unmounted:".green().bold(), normalized);
     Ok(())
 }
 
-pub fn run_list(config: &AppConfig) -> Result<()> {
+pub fn run_list(config: &AppConfig, workspace: Option<&Path>) -> Result<()> {
     let gc = GlobalConfig::load(config);
-    if gc.mounts.is_empty() {
+    let project = workspace
+        .map(|ws| project_state(config, ws).mounts)
+        .unwrap_or_default();
+    if gc.mounts.is_empty() && project.is_empty() {
         println!(
             "{}",
-            "No global mounts configured. Use `ai-pod mount add <host>[:<container>]`."
+            "No mounts configured. Use `ai-pod mount add <host>[:<container>]`."
                 .dimmed()
         );
         return Ok(());
     }
-    for m in &gc.mounts {
+    print_mounts(config, &gc.mounts);
+    if !project.is_empty() {
+        println!("{}", "Project mounts:".blue().bold());
+        print_mounts(config, &project);
+    }
+    Ok(())
+}
+
+fn print_mounts(config: &AppConfig, mounts: &[MountSpec]) {
+    for m in mounts {
         let target = crate::container::resolve_container_target(m, &config.home_dir)
             .unwrap_or_else(|_| "(invalid)".to_string());
         let mode = if m.writable { "rw" } else { "ro" };
-        let exists = if Path::new(&m.host).symlink_metadata().is_ok() {
+        let exists = if m.kind == MountType::Volume {
+            "  (volume)"
+        } else if Path::new(&m.host).symlink_metadata().is_ok() {
             ""
         } else {
             "  (missing — will be skipped at launch)"
         };
         println!("{:<50} → {:<40} [{}]{}", m.host, target, mode, exists);
     }
-    Ok(())
 }
 
 /// One-line warning for `mount add` when the host file is mode-restricted in
@@ -542,18 +669,9 @@ fn warn_if_unreadable(spec: &MountSpec) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::test_config;
     use tempfile::TempDir;
 
-    fn make_config(home: &Path) -> AppConfig {
-        let config_dir = home.join(".ai-pod");
-        std::fs::create_dir_all(&config_dir).unwrap();
-        AppConfig {
-            runtime_settings: config_dir.join("runtime-settings.json"),
-            config_dir,
-            home_dir: home.to_path_buf(),
-        }
-    }
-
     #[test]
     fn parse_spec_host_only() {
         let dir = TempDir::new().unwrap();
@@ -752,18 +870,19 @@ mod tests {
     #[test]
     fn run_add_rejects_no_container_outside_home() {
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
-        let err = run_add(&config, "/etc/foo", false, true).unwrap_err();
+        let config = test_config(&dir);
+        let err =
+            run_add(&config, MountScope::Global, "/etc/foo", false, false, true).unwrap_err();
         assert!(err.to_string().contains("outside $HOME"));
     }
 
     #[test]
     fn run_add_and_remove_round_trip() {
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
+        let config = test_config(&dir);
         std::fs::create_dir_all(dir.path().join(".claude/skills")).unwrap();
 
-        run_add(&config, "~/.claude/skills", false, true).unwrap();
+        run_add(&config, MountScope::Global, "~/.claude/skills", false, false, true).unwrap();
         let gc = GlobalConfig::load(&config);
         assert_eq!(gc.mounts.len(), 1);
         assert_eq!(
@@ -773,6 +892,7 @@ mod tests {
 
         run_remove(
             &config,
+            MountScope::Global,
             &dir.path().join(".claude/skills").display().to_string(),
         )
         .unwrap();
@@ -785,11 +905,11 @@ mod tests {
         // Symmetric with parse_spec normalization: a user who types
         // `~/x/` for `mount remove` should find the entry stored as `~/x`.
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
+        let config = test_config(&dir);
         std::fs::create_dir_all(dir.path().join(".claude/skills")).unwrap();
-        run_add(&config, "~/.claude/skills", false, true).unwrap();
+        run_add(&config, MountScope::Global, "~/.claude/skills", false, false, true).unwrap();
 
-        run_remove(&config, "~/.claude/skills/").unwrap();
+        run_remove(&config, MountScope::Global, "~/.claude/skills/").unwrap();
         let gc = GlobalConfig::load(&config);
         assert!(gc.mounts.is_empty(), "remove should find the entry");
     }
@@ -797,11 +917,11 @@ mod tests {
     #[test]
     fn run_add_dedups_by_host_when_writable_matches() {
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
+        let config = test_config(&dir);
         std::fs::create_dir_all(dir.path().join(".claude/skills")).unwrap();
 
-        run_add(&config, "~/.claude/skills", false, true).unwrap();
-        run_add(&config, "~/.claude/skills", false, true).unwrap();
+        run_add(&config, MountScope::Global, "~/.claude/skills", false, false, true).unwrap();
+        run_add(&config, MountScope::Global, "~/.claude/skills", false, false, true).unwrap();
         let gc = GlobalConfig::load(&config);
         assert_eq!(gc.mounts.len(), 1, "duplicate host should not be added");
         assert!(!gc.mounts[0].writable);
@@ -810,11 +930,12 @@ mod tests {
     #[test]
     fn run_add_errors_on_writable_mismatch() {
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
+        let config = test_config(&dir);
         std::fs::create_dir_all(dir.path().join(".claude/skills")).unwrap();
 
-        run_add(&config, "~/.claude/skills", false, true).unwrap();
-        let err = run_add(&config, "~/.claude/skills", true, true).unwrap_err();
+        run_add(&config, MountScope::Global, "~/.claude/skills", false, false, true).unwrap();
+        let err = run_add(&config, MountScope::Global, "~/.claude/skills", true, false, true)
+            .unwrap_err();
         let msg = err.to_string();
         assert!(msg.contains("already mounted"), "got: {msg}");
         assert!(msg.contains("remove"), "should hint to remove first: {msg}");
@@ -829,6 +950,7 @@ mod tests {
             host: host.to_string(),
             container: container.map(|c| c.to_string()),
             writable: false,
+            kind: MountType::Bind,
         }
     }
 
@@ -928,6 +1050,7 @@ mod tests {
             host: link.display().to_string(),
             container: Some("/home/ai-pod/innocent".into()),
             writable: false,
+            kind: MountType::Bind,
         };
         let err = validate_spec(&spec, dir.path()).unwrap_err();
         assert!(
@@ -950,6 +1073,7 @@ mod tests {
             host: link.display().to_string(),
             container: Some("/home/ai-pod/notes".into()),
             writable: false,
+            kind: MountType::Bind,
         };
         let w = warn_for_spec(&spec, "/home/ai-pod/notes", dir.path());
         assert!(
@@ -978,13 +1102,19 @@ mod tests {
         // `run_add_with_confirm` so the test never reaches the live
         // `dialoguer` prompt — which would block forever on a TTY.
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
+        let config = test_config(&dir);
         let ssh = dir.path().join(".ssh");
         std::fs::create_dir_all(&ssh).unwrap();
 
-        let err = run_add_with_confirm(&config, &ssh.display().to_string(), false, false, |_, _| {
-            false
-        })
+        let err = run_add_with_confirm(
+            &config,
+            MountScope::Global,
+            &ssh.display().to_string(),
+            false,
+            false,
+            false,
+            |_, _| false,
+        )
         .unwrap_err();
         assert!(
             err.to_string().contains("cancelled"),
@@ -1000,12 +1130,20 @@ mod tests {
         // The complement of the abort case: when the user confirms at the
         // prompt, the risky mount is stored. Again injected to avoid the TTY.
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
+        let config = test_config(&dir);
         let ssh = dir.path().join(".ssh");
         std::fs::create_dir_all(&ssh).unwrap();
 
-        run_add_with_confirm(&config, &ssh.display().to_string(), false, false, |_, _| true)
-            .unwrap();
+        run_add_with_confirm(
+            &config,
+            MountScope::Global,
+            &ssh.display().to_string(),
+            false,
+            false,
+            false,
+            |_, _| true,
+        )
+        .unwrap();
         let gc = GlobalConfig::load(&config);
         assert_eq!(gc.mounts.len(), 1, "confirmed risky mount must be stored");
     }
@@ -1013,26 +1151,56 @@ mod tests {
     #[test]
     fn run_add_allows_risky_mount_with_yes() {
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
+        let config = test_config(&dir);
         let ssh = dir.path().join(".ssh");
         std::fs::create_dir_all(&ssh).unwrap();
 
-        run_add(&config, &ssh.display().to_string(), false, true).unwrap();
+        run_add(&config, MountScope::Global, &ssh.display().to_string(), false, false, true)
+            .unwrap();
         let gc = GlobalConfig::load(&config);
         assert_eq!(gc.mounts.len(), 1);
     }
 
+    #[test]
+    fn parse_volume_spec_requires_container_path() {
+        let dir = TempDir::new().unwrap();
+        let spec = parse_volume_spec("datasets:/data/", false, dir.path()).unwrap();
+        assert_eq!(spec.kind, MountType::Volume);
+        assert_eq!(spec.container.as_deref(), Some("/data"));
+        assert!(parse_volume_spec("datasets", false, dir.path()).is_err());
+        assert!(parse_volume_spec("ai-pod-cache-npm:/data", false, dir.path()).is_err());
+        assert!(parse_volume_spec("../x:/data", false, dir.path()).is_err());
+    }
+
+    #[test]
+    fn project_scope_stores_in_project_state() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let ws = dir.path().join("ws");
+        std::fs::create_dir_all(&ws).unwrap();
+
+        let scope = MountScope::Project(&ws);
+        run_add(&config, scope, "datasets:/data", false, true, true).unwrap();
+        assert!(GlobalConfig::load(&config).mounts.is_empty());
+        assert_eq!(project_state(&config, &ws).mounts.len(), 1);
+
+        run_remove(&config, MountScope::Project(&ws), "datasets").unwrap();
+        assert!(project_state(&config, &ws).mounts.is_empty());
+    }
+
     #[test]
     fn run_add_rejects_colliding_container_target() {
         let dir = TempDir::new().unwrap();
-        let config = make_config(dir.path());
+        let config = test_config(&dir);
         std::fs::create_dir_all(dir.path().join("a")).unwrap();
         std::fs::create_dir_all(dir.path().join("b")).unwrap();
         let a = dir.path().join("a").display().to_string();
         let b = dir.path().join("b").display().to_string();
 
-        run_add(&config, &format!("{}:/home/ai-pod/shared", a), false, true).unwrap();
-        let err = run_add(&config, &format!("{}:/home/ai-pod/shared", b), false, true).unwrap_err();
+        let spec_a = format!("{}:/home/ai-pod/shared", a);
+        let spec_b = format!("{}:/home/ai-pod/shared", b);
+        run_add(&config, MountScope::Global, &spec_a, false, false, true).unwrap();
+        let err = run_add(&config, MountScope::Global, &spec_b, false, false, true).unwrap_err();
         let msg = err.to_string();
         assert!(msg.contains("already used"), "got: {msg}");
 
@@ -0,0 +1,209 @@
+//! External plugins: executables in `~/.ai-pod/plugins/` run at fixed points
+//! (before launch, after an image build, when an agent sends a notification)
+//! to add scanners, team policies or notification channels without patching
+//! ai-pod. Each plugin gets the hook name as its only argument and the event
+//! as one JSON object on stdin (the same shape as lines in `events.log`).
+//! A plugin that runs longer than [`TIMEOUT`] is killed and counts as failed.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::events::Event;
+
+pub const PLUGINS_DIR: &str = "plugins";
+
+/// How long a plugin may run, so a hung one can't block a launch or the
+/// server's notifications forever.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    /// Before the container starts; a failing plugin aborts the launch.
+    PreLaunch,
+    /// After the image was (re)built.
+    PostBuild,
+    /// After an agent's `notify_user` was shown.
+    Notification,
+}
+
+impl Hook {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Hook::PreLaunch => "pre-launch",
+            Hook::PostBuild => "post-build",
+            Hook::Notification => "notification",
+        }
+    }
+}
+
+pub fn plugins_dir(config_dir: &Path) -> PathBuf {
+    crate::layout::settings_dir(config_dir).join(PLUGINS_DIR)
+}
+
+/// Executable regular files in the plugin directory, in name order. Hidden
+/// files and files without an execute bit are ignored, so a plugin can be
+/// disabled with `chmod -x`.
+pub fn discover(config_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(plugins_dir(config_dir)) else {
+        return vec![];
+    };
+    let mut plugins: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            !p.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+                && std::fs::metadata(p)
+                    .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        })
+        .collect();
+    plugins.sort();
+    plugins
+}
+
+fn plugin_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Run one plugin; `Ok(false)` when it exits non-zero. It is killed after
+/// `timeout`.
+fn run_one(plugin: &Path, hook: Hook, payload: &str, timeout: Duration) -> Result<bool> {
+    let mut child = Command::new(plugin)
+        .arg(hook.as_str())
+        .env("AI_POD_PLUGIN_HOOK", hook.as_str())
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run plugin {}", plugin.display()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // On its own thread, so a plugin that never reads its input can't
+        // block past the deadline; the write fails once the plugin is gone.
+        let payload = payload.to_string();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(payload.as_bytes());
+        });
+    }
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                anyhow::bail!("Plugin {} took longer than {}s", plugin_name(plugin), timeout.as_secs());
+            }
+        }
+    };
+    Ok(status.success())
+}
+
+/// Run the plugins for `hook`, stopping at the first failure, which is
+/// returned as an error.
+pub fn run_blocking(config_dir: &Path, hook: Hook, event: &Event) -> Result<()> {
+    let plugins = discover(config_dir);
+    if plugins.is_empty() {
+        return Ok(());
+    }
+    let payload = serde_json::to_string(event)?;
+    for plugin in plugins {
+        if !run_one(&plugin, hook, &payload, TIMEOUT)? {
+            anyhow::bail!("Plugin {} failed on {}", plugin_name(&plugin), hook.as_str());
+        }
+    }
+    Ok(())
+}
+
+/// Run every plugin for `hook`, only warning on failures.
+pub fn run_all(config_dir: &Path, hook: Hook, event: &Event) {
+    let plugins = discover(config_dir);
+    if plugins.is_empty() {
+        return;
+    }
+    let Ok(payload) = serde_json::to_string(event) else {
+        return;
+    };
+    for plugin in plugins {
+        let failure = match run_one(&plugin, hook, &payload, TIMEOUT) {
+            Ok(true) => continue,
+            Ok(false) => "exited with non-zero status".to_string(),
+            Err(e) => format!("{:#}", e),
+        };
+        eprintln!(
+            "{} plugin {} ({}) {}",
+            "warning:".yellow().bold(),
+            plugin_name(&plugin),
+            hook.as_str(),
+            failure
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventKind;
+    use tempfile::TempDir;
+
+    fn write_plugin(dir: &Path, name: &str, body: &str, mode: u32) {
+        let path = plugins_dir(dir).join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, body).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn discover_lists_executables_in_name_order() {
+        let dir = TempDir::new().unwrap();
+        assert!(discover(dir.path()).is_empty());
+        write_plugin(dir.path(), "b-policy", "#!/bin/sh\n", 0o755);
+        write_plugin(dir.path(), "a-scan", "#!/bin/sh\n", 0o700);
+        write_plugin(dir.path(), "disabled", "#!/bin/sh\n", 0o644);
+        write_plugin(dir.path(), ".hidden", "#!/bin/sh\n", 0o755);
+        let names: Vec<String> = discover(dir.path()).iter().map(|p| plugin_name(p)).collect();
+        assert_eq!(names, vec!["a-scan", "b-policy"]);
+    }
+
+    #[test]
+    fn run_blocking_passes_hook_and_event_and_stops_on_failure() {
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().join("out");
+        write_plugin(
+            dir.path(),
+            "10-record",
+            &format!("#!/bin/sh\necho \"$1\" > {0}\ncat >> {0}\n", out.display()),
+            0o755,
+        );
+        let event = Event::new(EventKind::Launch, Some(Path::new("/src/api")));
+        run_blocking(dir.path(), Hook::PreLaunch, &event).unwrap();
+        let recorded = std::fs::read_to_string(&out).unwrap();
+        let (hook, json) = recorded.split_once('\n').unwrap();
+        assert_eq!(hook, "pre-launch");
+        assert_eq!(serde_json::from_str::<Event>(json).unwrap(), event);
+
+        write_plugin(dir.path(), "20-deny", "#!/bin/sh\nexit 3\n", 0o755);
+        let err = run_blocking(dir.path(), Hook::PreLaunch, &event).unwrap_err();
+        assert!(err.to_string().contains("20-deny"), "{err}");
+    }
+
+    #[test]
+    fn run_one_kills_a_plugin_past_its_deadline() {
+        let dir = TempDir::new().unwrap();
+        write_plugin(dir.path(), "slow", "#!/bin/sh
+exec sleep 30
+", 0o755);
+        let started = Instant::now();
+        // More than a pipe buffer, which the plugin never reads.
+        let payload = "x".repeat(1 << 20);
+        let err = run_one(&plugins_dir(dir.path()).join("slow"), Hook::Notification, &payload, Duration::from_millis(200))
+            .unwrap_err();
+        assert!(err.to_string().contains("took longer"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
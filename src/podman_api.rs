@@ -0,0 +1,447 @@
+//! Minimal client for Podman's REST API (libpod) on its unix socket. Used for
+//! the calls ai-pod makes many times per launch — image/volume existence
+//! checks, volume create/remove and single-file copies in and out of
+//! containers — which are much faster than spawning `podman` each time and
+//! return the engine's error message instead of an exit code.
+//!
+//! Every caller falls back to the `podman` CLI when the socket isn't there
+//! (no `podman.socket` service, podman machine on macOS, Docker) or the
+//! request fails in transport. Plain HTTP/1.1 over a `UnixStream` keeps this
+//! synchronous and independent of the tokio runtime the CLI runs on.
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::runtime::{ContainerRuntime, RuntimeKind};
+
+/// API version prefix; v4 endpoints are served by Podman 4 and 5.
+const API_PREFIX: &str = "/v4.0.0/libpod";
+
+/// Set to disable the API client and always use the CLI.
+pub const DISABLE_ENV: &str = "AI_POD_NO_PODMAN_API";
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The socket couldn't be reached or the response was unreadable.
+    Transport(std::io::Error),
+    /// The engine answered with an error status.
+    Status { code: u16, message: String },
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::Transport(e) => write!(f, "podman API unavailable: {}", e),
+            ApiError::Status { code, message } => write!(f, "{} (HTTP {})", message, code),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<std::io::Error> for ApiError {
+    fn from(e: std::io::Error) -> Self {
+        ApiError::Transport(e)
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// Turn an unexpected status into an error carrying the engine's message
+    /// (libpod errors are `{"cause": ..., "message": ..., "response": ...}`).
+    fn error(self) -> ApiError {
+        let message = serde_json::from_slice::<serde_json::Value>(&self.body)
+            .ok()
+            .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&self.body).trim().to_string());
+        ApiError::Status {
+            code: self.status,
+            message,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PodmanApi {
+    socket: PathBuf,
+}
+
+/// The shared client for `rt`, or `None` when the API can't be used: not
+/// Podman, `--dry-run` (commands must be printed), `--record` (commands
+/// must be recorded), disabled via [`DISABLE_ENV`], or no responding socket.
+/// Probed once per process.
+pub fn client(rt: &ContainerRuntime) -> Option<&'static PodmanApi> {
+    static CLIENT: OnceLock<Option<PodmanApi>> = OnceLock::new();
+    if rt.kind != RuntimeKind::Podman
+        || rt.dry_run
+        || crate::record::bundle().is_some()
+        || std::env::var_os(DISABLE_ENV).is_some()
+    {
+        return None;
+    }
+    CLIENT
+        .get_or_init(|| {
+            let socket = socket_path(
+                std::env::var("CONTAINER_HOST").ok().as_deref(),
+                std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from),
+                // Safety: geteuid has no preconditions.
+                unsafe { libc::geteuid() } == 0,
+            )?;
+            let api = PodmanApi { socket };
+            api.ping().then_some(api)
+        })
+        .as_ref()
+}
+
+/// Where the Podman service listens: `CONTAINER_HOST` when it is a
+/// `unix://` URL (any other scheme means a remote engine the CLI handles),
+/// otherwise the rootless or rootful default socket, if it exists.
+fn socket_path(container_host: Option<&str>, runtime_dir: Option<PathBuf>, root: bool) -> Option<PathBuf> {
+    if let Some(host) = container_host.filter(|h| !h.is_empty()) {
+        return host.strip_prefix("unix://").map(PathBuf::from);
+    }
+    let path = if root {
+        PathBuf::from("/run/podman/podman.sock")
+    } else {
+        runtime_dir?.join("podman/podman.sock")
+    };
+    path.exists().then_some(path)
+}
+
+/// Percent-encode a query value, keeping `/` readable.
+fn encode_query(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~' | b'/') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// Split a raw HTTP/1.1 response into status and (de-chunked) body.
+fn parse_response(raw: &[u8]) -> std::io::Result<Response> {
+    let invalid = |what: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, what.to_string());
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| invalid("incomplete HTTP response"))?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let mut lines = head.lines();
+    let status: u16 = lines
+        .next()
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid("malformed HTTP status line"))?;
+    let chunked = lines.any(|l| {
+        let l = l.to_ascii_lowercase();
+        l.starts_with("transfer-encoding:") && l.contains("chunked")
+    });
+    let body = &raw[split + 4..];
+    let body = if chunked {
+        decode_chunked(body).ok_or_else(|| invalid("malformed chunked body"))?
+    } else {
+        body.to_vec()
+    };
+    Ok(Response { status, body })
+}
+
+fn decode_chunked(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+        let size_str = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size = usize::from_str_radix(size_str.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(out);
+        }
+        out.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+/// A ustar archive holding one regular file, the format the archive
+/// endpoints exchange. Ownership is left to the engine, which chowns to the
+/// container's user like `podman cp` does.
+fn single_file_tar(name: &str, contents: &[u8], mode: u32) -> std::io::Result<Vec<u8>> {
+    if name.is_empty() || name.len() > 100 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "file name doesn't fit a tar header",
+        ));
+    }
+    let mut header = [0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    let octal = |field: &mut [u8], value: u64| {
+        let s = format!("{:0width$o}\0", value, width = field.len() - 1);
+        field.copy_from_slice(s.as_bytes());
+    };
+    octal(&mut header[100..108], u64::from(mode & 0o7777));
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    octal(&mut header[124..136], contents.len() as u64);
+    let mtime = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    // The checksum is computed with its own field set to spaces.
+    header[148..156].copy_from_slice(b"        ");
+    let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+
+    let mut out = header.to_vec();
+    out.extend_from_slice(contents);
+    out.resize(out.len().div_ceil(512) * 512, 0);
+    out.extend_from_slice(&[0u8; 1024]);
+    Ok(out)
+}
+
+/// Contents of the first regular file in a tar archive.
+fn first_file_in_tar(tar: &[u8]) -> Option<Vec<u8>> {
+    let mut offset = 0;
+    while offset + 512 <= tar.len() {
+        let header = &tar[offset..offset + 512];
+        if header.iter().all(|&b| b == 0) {
+            return None;
+        }
+        let size_field = std::str::from_utf8(&header[124..136]).ok()?;
+        let size = u64::from_str_radix(size_field.trim_matches(|c: char| c == '\0' || c == ' '), 8)
+            .ok()? as usize;
+        let data_start = offset + 512;
+        if matches!(header[156], b'0' | 0) {
+            return tar.get(data_start..data_start + size).map(<[u8]>::to_vec);
+        }
+        // Directories, PAX headers and the like: skip their data blocks.
+        offset = data_start + size.div_ceil(512) * 512;
+    }
+    None
+}
+
+impl PodmanApi {
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<(&str, &[u8])>,
+        timeout: Duration,
+    ) -> std::io::Result<Response> {
+        let mut stream = UnixStream::connect(&self.socket)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        let mut req = format!(
+            "{} {}{} HTTP/1.1\r\nHost: d\r\nConnection: close\r\n",
+            method, API_PREFIX, path
+        );
+        let (content_type, payload) = body.unwrap_or(("application/json", &[]));
+        if body.is_some() {
+            req.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        req.push_str(&format!("Content-Length: {}\r\n\r\n", payload.len()));
+        stream.write_all(req.as_bytes())?;
+        stream.write_all(payload)?;
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+        parse_response(&raw)
+    }
+
+    fn get(&self, path: &str) -> std::io::Result<Response> {
+        self.request("GET", path, None, Duration::from_secs(30))
+    }
+
+    fn ping(&self) -> bool {
+        self.request("GET", "/_ping", None, Duration::from_secs(2))
+            .is_ok_and(|r| r.status == 200)
+    }
+
+    fn exists(&self, path: &str) -> Result<bool, ApiError> {
+        let resp = self.get(path)?;
+        match resp.status {
+            204 => Ok(true),
+            404 => Ok(false),
+            _ => Err(resp.error()),
+        }
+    }
+
+    pub fn image_exists(&self, name: &str) -> Result<bool, ApiError> {
+        self.exists(&format!("/images/{}/exists", encode_query(name)))
+    }
+
+    pub fn volume_exists(&self, name: &str) -> Result<bool, ApiError> {
+        self.exists(&format!("/volumes/{}/exists", encode_query(name)))
+    }
+
+    pub fn volume_create(&self, name: &str, labels: &[(&str, String)]) -> Result<(), ApiError> {
+        let labels: serde_json::Map<String, serde_json::Value> = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone().into()))
+            .collect();
+        let body = serde_json::json!({ "Name": name, "Labels": labels }).to_string();
+        let resp = self.request(
+            "POST",
+            "/volumes/create",
+            Some(("application/json", body.as_bytes())),
+            Duration::from_secs(30),
+        )?;
+        match resp.status {
+            200 | 201 => Ok(()),
+            _ => Err(resp.error()),
+        }
+    }
+
+    pub fn volume_remove(&self, name: &str) -> Result<(), ApiError> {
+        let resp = self.request(
+            "DELETE",
+            &format!("/volumes/{}", encode_query(name)),
+            None,
+            Duration::from_secs(30),
+        )?;
+        match resp.status {
+            200 | 204 => Ok(()),
+            _ => Err(resp.error()),
+        }
+    }
+
+    /// Copy a host file into a container. `dest` is the full target path, or
+    /// a directory ending in `/` to keep the source's file name — like the
+    /// destination of `podman cp`.
+    pub fn copy_into(&self, container: &str, src: &Path, dest: &str) -> Result<(), ApiError> {
+        let (dir, name) = match dest.strip_suffix('/') {
+            Some(dir) => (
+                dir.to_string(),
+                src.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            ),
+            None => match dest.rsplit_once('/') {
+                Some((dir, name)) => (dir.to_string(), name.to_string()),
+                None => (".".to_string(), dest.to_string()),
+            },
+        };
+        let contents = std::fs::read(src)?;
+        let mode = std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(src)?.permissions());
+        let tar = single_file_tar(&name, &contents, mode)?;
+        let dir = if dir.is_empty() { "/".to_string() } else { dir };
+        let resp = self.request(
+            "PUT",
+            &format!(
+                "/containers/{}/archive?path={}",
+                encode_query(container),
+                encode_query(&dir)
+            ),
+            Some(("application/x-tar", &tar)),
+            Duration::from_secs(60),
+        )?;
+        match resp.status {
+            200 => Ok(()),
+            _ => Err(resp.error()),
+        }
+    }
+
+    /// Read a file out of a container. `Ok(None)` when it doesn't exist.
+    pub fn copy_out(&self, container: &str, path: &str) -> Result<Option<Vec<u8>>, ApiError> {
+        let resp = self.get(&format!(
+            "/containers/{}/archive?path={}",
+            encode_query(container),
+            encode_query(path)
+        ))?;
+        match resp.status {
+            200 => Ok(first_file_in_tar(&resp.body)),
+            404 => Ok(None),
+            _ => Err(resp.error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use tempfile::TempDir;
+
+    #[test]
+    fn socket_path_prefers_container_host() {
+        assert_eq!(
+            socket_path(Some("unix:///tmp/p.sock"), None, false),
+            Some(PathBuf::from("/tmp/p.sock"))
+        );
+        assert_eq!(socket_path(Some("ssh://core@localhost:22/run/podman.sock"), None, false), None);
+        let dir = TempDir::new().unwrap();
+        assert_eq!(socket_path(None, Some(dir.path().to_path_buf()), false), None);
+        std::fs::create_dir_all(dir.path().join("podman")).unwrap();
+        std::fs::write(dir.path().join("podman/podman.sock"), "").unwrap();
+        assert_eq!(
+            socket_path(None, Some(dir.path().to_path_buf()), false),
+            Some(dir.path().join("podman/podman.sock"))
+        );
+    }
+
+    #[test]
+    fn parse_response_handles_chunked_bodies() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n";
+        let resp = parse_response(raw).unwrap();
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.body, b"Wikipedia");
+        let raw = b"HTTP/1.1 409 Conflict\r\nContent-Length: 44\r\n\r\n{\"cause\":\"in use\",\"message\":\"volume in use\"}";
+        let err = parse_response(raw).unwrap().error();
+        assert_eq!(err.to_string(), "volume in use (HTTP 409)");
+    }
+
+    #[test]
+    fn tar_round_trips_a_single_file() {
+        let tar = single_file_tar("settings.json", b"{\"a\":1}", 0o600).unwrap();
+        assert_eq!(tar.len() % 512, 0);
+        assert_eq!(&tar[257..262], b"ustar");
+        assert_eq!(first_file_in_tar(&tar), Some(b"{\"a\":1}".to_vec()));
+        assert!(single_file_tar(&"x".repeat(101), b"", 0o644).is_err());
+    }
+
+    #[test]
+    fn encode_query_keeps_slashes() {
+        assert_eq!(encode_query("/home/ai-pod/.claude"), "/home/ai-pod/.claude");
+        assert_eq!(encode_query("a b&c"), "a%20b%26c");
+    }
+
+    #[test]
+    fn requests_go_to_libpod_paths_over_the_socket() {
+        let dir = TempDir::new().unwrap();
+        let socket = dir.path().join("podman.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in ["204 No Content", "404 Not Found"] {
+                let (mut conn, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 1024];
+                let n = conn.read(&mut buf).unwrap();
+                let req = String::from_utf8_lossy(&buf[..n]).to_string();
+                requests.push(req.lines().next().unwrap().to_string());
+                write!(conn, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+            }
+            requests
+        });
+        let api = PodmanApi { socket };
+        assert!(api.volume_exists("ai-pod-abc-home").unwrap());
+        assert!(!api.image_exists("localhost/api-3f2a9c").unwrap());
+        assert_eq!(
+            server.join().unwrap(),
+            vec![
+                "GET /v4.0.0/libpod/volumes/ai-pod-abc-home/exists HTTP/1.1",
+                "GET /v4.0.0/libpod/images/localhost/api-3f2a9c/exists HTTP/1.1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Forwarding of ports the agent opens. While a session runs, ai-pod polls
+//! the container's `/proc/net/tcp` and `/proc/net/tcp6` for listening
+//! sockets. When a port starts listening that wasn't at session start — a
+//! dev server the agent spun up, say — it is forwarded to the same port on
+//! the host's `127.0.0.1` (or a free one if that's taken) and a notification
+//! gives the URL. The forward goes away when the port closes or the session
+//! ends.
+//!
+//! Connections are relayed through `exec -i` into the container, with
+//! `socat` if the image has it and bash's `/dev/tcp` otherwise, so it works
+//! without publishing ports and on every runtime and network setup.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::config::PortsConfig;
+use crate::server::notify::announce;
+use crate::runtime::ContainerRuntime;
+
+const POLL: Duration = Duration::from_secs(2);
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+/// TCP state `LISTEN` in `/proc/net/tcp`.
+const LISTEN: &str = "0A";
+
+/// Run in the container by `sh -c` with the `socat` address, host and port
+/// as `$0`, `$1` and `$2`: copies stdin to the port and the port to stdout.
+const RELAY: &str = r#"if command -v socat >/dev/null 2>&1; then exec socat - "TCP:$0"; fi
+exec bash -c 'exec 3<>"/dev/tcp/$0/$1" || exit 1; cat <&3 & cat >&3; wait' "$1" "$2""#;
+
+/// An address from `/proc/net/tcp{,6}`: the kernel prints each 32-bit word
+/// of the network-order address as a native (little-endian) integer.
+fn parse_addr(hex: &str) -> Option<IpAddr> {
+    let word = |i: usize| u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok();
+    match hex.len() {
+        8 => Some(IpAddr::V4(Ipv4Addr::from(word(0)?.to_le_bytes()))),
+        32 => {
+            let mut bytes = [0u8; 16];
+            for i in 0..4 {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&word(i)?.to_le_bytes());
+            }
+            Some(IpAddr::V6(Ipv6Addr::from(bytes)))
+        }
+        _ => None,
+    }
+}
+
+/// Listening ports in `/proc/net/tcp{,6}` output, with the address to
+/// connect to inside the container: loopback for wildcard listeners, else
+/// the bound address. An IPv4 listener wins over an IPv6 one on the same
+/// port.
+pub fn listening(proc_net: &str) -> BTreeMap<u16, IpAddr> {
+    let mut ports = BTreeMap::new();
+    for line in proc_net.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 || fields[3] != LISTEN {
+            continue;
+        }
+        let Some((addr, port)) = fields[1].split_once(':') else {
+            continue;
+        };
+        let (Some(addr), Ok(port)) = (parse_addr(addr), u16::from_str_radix(port, 16)) else {
+            continue;
+        };
+        let target = match addr {
+            IpAddr::V4(a) if a.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(a) if a.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            other => other,
+        };
+        let entry = ports.entry(port).or_insert(target);
+        if target.is_ipv4() && entry.is_ipv6() {
+            *entry = target;
+        }
+    }
+    ports
+}
+
+fn poll(rt: &ContainerRuntime, container: &str) -> Option<BTreeMap<u16, IpAddr>> {
+    // `tcp6` is missing with IPv6 disabled; `cat` still prints `tcp`.
+    let out = rt
+        .command()
+        .args(["exec", container, "cat", "/proc/net/tcp", "/proc/net/tcp6"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let raw = String::from_utf8_lossy(&out.stdout);
+    (!raw.trim().is_empty()).then(|| listening(&raw))
+}
+
+/// Copy `stream` to `target` inside the container and back until either
+/// side closes.
+fn relay(rt: &ContainerRuntime, container: &str, target: IpAddr, port: u16, mut stream: TcpStream) {
+    let socat = match target {
+        IpAddr::V4(a) => format!("{}:{}", a, port),
+        IpAddr::V6(a) => format!("[{}]:{}", a, port),
+    };
+    let Ok(mut child) = rt
+        .command()
+        .args(["exec", "-i", container, "sh", "-c", RELAY, &socat, &target.to_string(), &port.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return;
+    };
+    let (Some(mut stdin), Some(mut stdout), Ok(mut upstream)) =
+        (child.stdin.take(), child.stdout.take(), stream.try_clone())
+    else {
+        let _ = child.kill();
+        let _ = child.wait();
+        return;
+    };
+    // Dropping `stdin` once the client is done closes the relay's input.
+    let to_container = std::thread::spawn(move || {
+        let _ = std::io::copy(&mut upstream, &mut stdin);
+    });
+    let _ = std::io::copy(&mut stdout, &mut stream);
+    let _ = stream.shutdown(Shutdown::Both);
+    let _ = to_container.join();
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// A host listener relaying to a container port; stops when dropped.
+struct Forward {
+    host_port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for Forward {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Listen on `127.0.0.1:port`, or a free port when it's taken, and relay
+/// each connection to `target:port` in the container.
+fn forward(rt: &ContainerRuntime, container: &str, port: u16, target: IpAddr) -> std::io::Result<Forward> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .or_else(|_| TcpListener::bind((Ipv4Addr::LOCALHOST, 0)))?;
+    listener.set_nonblocking(true)?;
+    let host_port = listener.local_addr()?.port();
+    let stop = Arc::new(AtomicBool::new(false));
+    let (rt, container, thread_stop) = (rt.clone(), container.to_string(), Arc::clone(&stop));
+    std::thread::spawn(move || {
+        while !thread_stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if stream.set_nonblocking(false).is_err() {
+                        continue;
+                    }
+                    let (rt, container) = (rt.clone(), container.clone());
+                    std::thread::spawn(move || relay(&rt, &container, target, port, stream));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL),
+                Err(_) => return,
+            }
+        }
+    });
+    Ok(Forward { host_port, stop })
+}
+
+pub struct PortWatch {
+    done: Arc<AtomicBool>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl PortWatch {
+    /// Watch `container` for new listening ports, unless `ports.forward`
+    /// is off.
+    pub fn start(
+        rt: &ContainerRuntime,
+        config_dir: &Path,
+        workspace: &Path,
+        container: &str,
+        settings: &PortsConfig,
+    ) -> Option<Self> {
+        if !settings.forward.unwrap_or(true) {
+            return None;
+        }
+        let done = Arc::new(AtomicBool::new(false));
+        let (rt, config_dir, workspace): (_, PathBuf, PathBuf) =
+            (rt.clone(), config_dir.to_path_buf(), workspace.to_path_buf());
+        let (container, ignore) = (container.to_string(), settings.ignore.clone());
+        let thread_done = Arc::clone(&done);
+        let handle = std::thread::spawn(move || {
+            // Ports open at session start belong to the image, not the agent.
+            let mut baseline: Option<BTreeSet<u16>> = None;
+            let mut forwards: BTreeMap<u16, Option<Forward>> = BTreeMap::new();
+            while !thread_done.load(Ordering::Relaxed) {
+                std::thread::sleep(POLL);
+                let Some(open) = poll(&rt, &container) else {
+                    continue;
+                };
+                let baseline = baseline.get_or_insert_with(|| open.keys().copied().collect());
+                baseline.retain(|port| open.contains_key(port));
+                forwards.retain(|port, _| open.contains_key(port));
+                for (&port, &target) in &open {
+                    if baseline.contains(&port) || ignore.contains(&port) || forwards.contains_key(&port) {
+                        continue;
+                    }
+                    match forward(&rt, &container, port, target) {
+                        Ok(fwd) => {
+                            announce(
+                                &config_dir,
+                                &workspace,
+                                "message",
+                                &format!(
+                                    "Port {} in the container is forwarded to http://localhost:{}",
+                                    port, fwd.host_port
+                                ),
+                            );
+                            forwards.insert(port, Some(fwd));
+                        }
+                        Err(e) => {
+                            announce(
+                                &config_dir,
+                                &workspace,
+                                "error",
+                                &format!("Port {} in the container is listening but can't be forwarded: {}", port, e),
+                            );
+                            forwards.insert(port, None);
+                        }
+                    }
+                }
+            }
+        });
+        Some(PortWatch {
+            done,
+            handle: Some(handle),
+        })
+    }
+
+    /// Stop watching and close every forward once the session has exited.
+    pub fn finish(mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listening_reads_listen_sockets_from_proc_net_tcp() {
+        let raw = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1435 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1234
+   1: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1235
+   2: 0100007F:1F90 0100007F:D2F0 01 00000000:00000000 00:00000000 00000000  1000        0 1236
+  sl  local_address                         remote_address                        st tx_queue rx_queue
+   0: 00000000000000000000000000000000:1435 00000000000000000000000000000000:0000 0A 00000000:00000000
+   1: 00000000000000000000000001000000:0BB8 00000000000000000000000000000000:0000 0A 00000000:00000000
+";
+        let ports = listening(raw);
+        assert_eq!(ports.len(), 3);
+        assert_eq!(ports[&5173], IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(ports[&8080], IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(ports[&3000], IpAddr::V6(Ipv6Addr::LOCALHOST));
+    }
+}
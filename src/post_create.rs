@@ -0,0 +1,217 @@
+//! In-container setup commands that run once per home volume, read from the
+//! `postCreateCommand` of the workspace's `.devcontainer/devcontainer.json`
+//! so projects that already describe their setup for dev containers need no
+//! extra config.
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+pub const DEVCONTAINER_PATH: &str = ".devcontainer/devcontainer.json";
+
+/// Setup commands as argv vectors, run in order inside the container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostCreate {
+    pub commands: Vec<Vec<String>>,
+}
+
+impl PostCreate {
+    /// Stable digest of the commands, stored in the project state once they
+    /// succeed so edits to `postCreateCommand` trigger a re-run.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for argv in &self.commands {
+            for arg in argv {
+                hasher.update(arg.as_bytes());
+                hasher.update([0]);
+            }
+            hasher.update([1]);
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+pub fn devcontainer_file(workspace: &Path) -> PathBuf {
+    workspace.join(DEVCONTAINER_PATH)
+}
+
+/// Read `postCreateCommand` from the workspace. `Ok(None)` when there is no
+/// devcontainer.json or it has no (non-empty) post-create command.
+pub fn load(workspace: &Path) -> Result<Option<PostCreate>, String> {
+    let path = devcontainer_file(workspace);
+    let Ok(raw) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let value: Value = serde_json::from_str(&strip_jsonc(&raw))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    match value.get("postCreateCommand") {
+        None | Some(Value::Null) => Ok(None),
+        Some(cmd) => {
+            let commands = parse_command(cmd)
+                .map_err(|e| format!("{}: postCreateCommand {}", path.display(), e))?;
+            Ok((!commands.is_empty()).then_some(PostCreate { commands }))
+        }
+    }
+}
+
+/// Devcontainer command forms: a string runs through `sh -c`, an array is an
+/// argv, and an object maps names to either form (run in key order here
+/// rather than in parallel, to keep output readable).
+fn parse_command(cmd: &Value) -> Result<Vec<Vec<String>>, String> {
+    match cmd {
+        Value::String(s) if s.trim().is_empty() => Ok(vec![]),
+        Value::String(s) => Ok(vec![vec!["sh".into(), "-c".into(), s.clone()]]),
+        Value::Array(items) => {
+            let argv = items
+                .iter()
+                .map(|v| v.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or("array entries must be strings")?;
+            Ok(if argv.is_empty() { vec![] } else { vec![argv] })
+        }
+        Value::Object(map) => {
+            let mut out = Vec::new();
+            for v in map.values() {
+                if matches!(v, Value::Object(_)) {
+                    return Err("object entries must be strings or arrays".into());
+                }
+                out.extend(parse_command(v)?);
+            }
+            Ok(out)
+        }
+        _ => Err("must be a string, array or object".into()),
+    }
+}
+
+/// Remove `//` and `/* */` comments and trailing commas so devcontainer.json
+/// (JSON with comments) parses with serde_json. String contents are kept
+/// verbatim.
+fn strip_jsonc(src: &str) -> String {
+    strip_trailing_commas(&strip_comments(src))
+}
+
+/// Copy `src`, letting `skip` drop text outside of string literals: it gets
+/// the remaining input and returns how many chars to drop (0 = keep one).
+fn walk_outside_strings(src: &str, skip: impl Fn(&[char]) -> usize) -> String {
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = String::with_capacity(src.len());
+    let mut i = 0;
+    let mut in_string = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                out.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        if c == '"' {
+            in_string = true;
+            out.push(c);
+            i += 1;
+            continue;
+        }
+        match skip(&chars[i..]) {
+            0 => {
+                out.push(c);
+                i += 1;
+            }
+            n => i += n,
+        }
+    }
+    out
+}
+
+fn strip_comments(src: &str) -> String {
+    walk_outside_strings(src, |rest| match rest {
+        ['/', '/', ..] => rest.iter().position(|&c| c == '\n').unwrap_or(rest.len()),
+        ['/', '*', ..] => rest[2..]
+            .windows(2)
+            .position(|w| w == ['*', '/'])
+            .map_or(rest.len(), |p| p + 4),
+        _ => 0,
+    })
+}
+
+fn strip_trailing_commas(src: &str) -> String {
+    walk_outside_strings(src, |rest| {
+        if rest[0] != ',' {
+            return 0;
+        }
+        match rest[1..].iter().find(|c| !c.is_whitespace()) {
+            Some('}') | Some(']') => 1,
+            _ => 0,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_devcontainer(dir: &TempDir, body: &str) {
+        let path = devcontainer_file(dir.path());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+
+    #[test]
+    fn load_returns_none_without_devcontainer() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(load(dir.path()).unwrap(), None);
+        write_devcontainer(&dir, r#"{"image": "x"}"#);
+        assert_eq!(load(dir.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn load_parses_jsonc_string_command() {
+        let dir = TempDir::new().unwrap();
+        write_devcontainer(
+            &dir,
+            r#"{
+                // install deps once
+                "postCreateCommand": "npm ci // not a comment", /* trailing */
+            }"#,
+        );
+        let pc = load(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            pc.commands,
+            vec![vec!["sh".to_string(), "-c".into(), "npm ci // not a comment".into()]]
+        );
+    }
+
+    #[test]
+    fn parse_command_handles_array_and_object_forms() {
+        let arr = serde_json::json!(["pip", "install", "-e", "."]);
+        assert_eq!(parse_command(&arr).unwrap(), vec![vec!["pip", "install", "-e", "."]]);
+
+        let obj = serde_json::json!({"deps": "npm ci", "tools": ["cargo", "fetch"]});
+        assert_eq!(
+            parse_command(&obj).unwrap(),
+            vec![
+                vec!["sh".to_string(), "-c".into(), "npm ci".into()],
+                vec!["cargo".to_string(), "fetch".into()],
+            ]
+        );
+        assert!(parse_command(&serde_json::json!(42)).is_err());
+        assert!(parse_command(&serde_json::json!([1])).is_err());
+    }
+
+    #[test]
+    fn fingerprint_changes_with_commands() {
+        let a = PostCreate {
+            commands: vec![vec!["a".into(), "b".into()]],
+        };
+        let b = PostCreate {
+            commands: vec![vec!["ab".into()]],
+        };
+        assert_ne!(a.fingerprint(), b.fingerprint());
+        assert_eq!(a.fingerprint(), a.clone().fingerprint());
+    }
+}
@@ -0,0 +1,207 @@
+//! Launch-path runtime lookups, issued concurrently. Before the session
+//! starts, a launch asks the runtime whether the image exists and what its
+//! id is, which session containers are running, and whether each of the
+//! workspace's volumes exists and which workspace it belongs to. Each of
+//! those is a separate `podman`/`docker` process, and they used to run one
+//! after the other. [`start`] issues them all at once on background threads
+//! at the beginning of a launch, overlapping the credential scan and the
+//! server start-up; the helpers that would ask the runtime take the answer
+//! from here instead.
+//!
+//! Each answer is used once, so a second lookup — after ai-pod created or
+//! removed the volume, say — goes to the runtime again. Answers are dropped
+//! when another ai-pod set the workspace up in the meantime (see
+//! [`discard_if_before`]).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::SystemTime;
+
+use crate::config::AppConfig;
+use crate::container::HomeVolumes;
+use crate::runtime::ContainerRuntime;
+
+#[derive(Debug, Default)]
+struct Answers {
+    started: Option<SystemTime>,
+    images: HashMap<String, bool>,
+    /// Image id by image name; `None` when the image doesn't exist.
+    image_ids: HashMap<String, Option<String>>,
+    /// Running containers by name prefix.
+    running: HashMap<String, Vec<String>>,
+    volumes: HashMap<String, bool>,
+    /// Workspace label of existing volumes. Kept across lookups: ai-pod only
+    /// ever labels a volume with the workspace that checked it.
+    owners: HashMap<String, Option<String>>,
+}
+
+enum Slot {
+    Idle,
+    Pending(SystemTime, JoinHandle<Answers>),
+    Ready(Box<Answers>),
+}
+
+static SLOT: Mutex<Slot> = Mutex::new(Slot::Idle);
+
+/// Run `f` on the answers, waiting for the lookups if they're still running.
+fn with_answers<T>(f: impl FnOnce(&mut Answers) -> Option<T>) -> Option<T> {
+    let mut slot = SLOT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Slot::Pending(..) = *slot
+        && let Slot::Pending(started, handle) = std::mem::replace(&mut *slot, Slot::Idle)
+    {
+        let mut answers = handle.join().unwrap_or_default();
+        answers.started = Some(started);
+        *slot = Slot::Ready(Box::new(answers));
+    }
+    match &mut *slot {
+        Slot::Ready(answers) => f(answers),
+        _ => None,
+    }
+}
+
+fn inspect(rt: &ContainerRuntime, args: &[&str]) -> Option<String> {
+    rt.command()
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+}
+
+/// Look up everything a launch of `workspace` from `image` asks the runtime
+/// before the session starts, in the background. Does nothing under
+/// `--dry-run`, where every lookup is just echoed, or `--record`, which
+/// records commands in the order they run.
+pub fn start(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path, image: &str) {
+    if rt.dry_run || crate::record::bundle().is_some() {
+        return;
+    }
+    let volumes = HomeVolumes::for_workspace(workspace);
+    let masks = crate::server::lifecycle::ProjectState::load(
+        &config.project_state_file(&crate::workspace::workspace_hash(workspace)),
+    )
+    .masked_directories;
+    let mut names = vec![volumes.state, volumes.home];
+    names.extend(masks.iter().map(|dir| crate::workspace::mask_volume_name(workspace, dir)));
+    let prefix = crate::workspace::container_prefix(workspace);
+    let (rt, image) = (rt.clone(), image.to_string());
+
+    let started = SystemTime::now();
+    let handle = std::thread::spawn(move || {
+        let format = crate::collision::volume_label_format();
+        std::thread::scope(|s| {
+            let image_id = s.spawn(|| inspect(&rt, &["image", "inspect", "--format", "{{.Id}}", &image]));
+            let running = s.spawn(|| crate::container::query_containers(&rt, &prefix, true).ok());
+            let volumes: Vec<_> = names
+                .iter()
+                .map(|name| {
+                    let (rt, format) = (&rt, &format);
+                    (name, s.spawn(move || inspect(rt, &["volume", "inspect", "--format", format, name])))
+                })
+                .collect();
+
+            let mut answers = Answers::default();
+            let image_id = image_id.join().ok().flatten();
+            answers.images.insert(image.clone(), image_id.is_some());
+            answers.image_ids.insert(image.clone(), image_id.filter(|id| !id.is_empty()));
+            if let Ok(Some(names)) = running.join() {
+                answers.running.insert(prefix.clone(), names);
+            }
+            for (name, handle) in volumes {
+                let label = handle.join().ok().flatten();
+                answers.volumes.insert(name.clone(), label.is_some());
+                if let Some(label) = label {
+                    answers.owners.insert(name.clone(), crate::collision::parse_owner(&label));
+                }
+            }
+            answers
+        })
+    });
+    *SLOT.lock().unwrap_or_else(|e| e.into_inner()) = Slot::Pending(started, handle);
+}
+
+/// Whether `image` exists, if looked up.
+pub fn image_exists(image: &str) -> Option<bool> {
+    with_answers(|a| a.images.remove(image))
+}
+
+/// `image`'s id, if looked up; `Some(None)` when it doesn't exist.
+pub fn image_id(image: &str) -> Option<Option<String>> {
+    with_answers(|a| a.image_ids.remove(image))
+}
+
+/// Forget what was looked up about `image`, e.g. once it's been rebuilt.
+pub fn forget_image(image: &str) {
+    with_answers(|a| {
+        a.images.remove(image);
+        a.image_ids.remove(image);
+        None::<()>
+    });
+}
+
+/// Running containers named `<prefix>-...`, if looked up.
+pub fn running_containers(prefix: &str) -> Option<Vec<String>> {
+    with_answers(|a| a.running.remove(prefix))
+}
+
+pub fn volume_exists(name: &str) -> Option<bool> {
+    with_answers(|a| a.volumes.remove(name))
+}
+
+/// The workspace volume `name` is labelled with, if looked up; `Some(None)`
+/// for a missing or unlabelled volume.
+pub fn volume_owner(name: &str) -> Option<Option<String>> {
+    with_answers(|a| match a.volumes.get(name) {
+        Some(false) => Some(None),
+        _ => a.owners.get(name).cloned(),
+    })
+}
+
+/// Drop the answers if they were looked up before `time`, when another
+/// process last held the workspace's launch lock: it may have created or
+/// removed what they describe.
+pub fn discard_if_before(time: SystemTime) {
+    let mut slot = SLOT.lock().unwrap_or_else(|e| e.into_inner());
+    let started = match &*slot {
+        Slot::Pending(started, _) => Some(*started),
+        Slot::Ready(answers) => answers.started,
+        Slot::Idle => None,
+    };
+    if started.is_some_and(|started| started <= time) {
+        *slot = Slot::Idle;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn answers_are_used_once_and_dropped_when_stale() {
+        let started = SystemTime::now();
+        let mut answers = Answers {
+            started: Some(started),
+            ..Default::default()
+        };
+        answers.volumes.insert("prefetch-test-home".into(), true);
+        answers.volumes.insert("prefetch-test-state".into(), false);
+        answers.owners.insert("prefetch-test-home".into(), Some("/src/api".into()));
+        *SLOT.lock().unwrap() = Slot::Ready(Box::new(answers));
+
+        assert_eq!(volume_owner("prefetch-test-state"), Some(None));
+        assert_eq!(volume_exists("prefetch-test-home"), Some(true));
+        assert_eq!(volume_exists("prefetch-test-home"), None);
+        assert_eq!(volume_owner("prefetch-test-home"), Some(Some("/src/api".into())));
+
+        discard_if_before(started - Duration::from_secs(1));
+        assert_eq!(volume_exists("prefetch-test-state"), Some(false));
+        discard_if_before(started + Duration::from_secs(1));
+        assert_eq!(volume_owner("prefetch-test-home"), None);
+    }
+}
@@ -0,0 +1,476 @@
+//! Provenance of the registry images an `ai-pod.Dockerfile` builds on.
+//!
+//! `ai-pod.lock`, next to the Dockerfile, pins the digest of every `FROM`
+//! image. The first build records them; later builds use a copy of the
+//! Dockerfile with `FROM image@digest`, so a moved tag changes nothing until
+//! `ai-pod update-lock` resolves the tags again. Committing the lockfile
+//! gives the whole team the same base images.
+//!
+//! With `verify` set in the global config, every `FROM` image is also checked
+//! with `cosign verify` (public-key or keyless) before a build. A build
+//! refuses to start when an image is unsigned or its digest no longer matches
+//! the lockfile, unless `--insecure-image` is passed.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{AppConfig, GlobalConfig, VerifyConfig};
+use crate::runtime::ContainerRuntime;
+
+pub const LOCKFILE: &str = "ai-pod.lock";
+
+/// `ai-pod.lock`: the digest each base image resolved to when it was
+/// pinned, keyed by the reference in the Dockerfile.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub images: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    pub fn path(workspace: &Path) -> PathBuf {
+        workspace.join(LOCKFILE)
+    }
+
+    pub fn load(workspace: &Path) -> Result<Self> {
+        let path = Self::path(workspace);
+        match std::fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).with_context(|| format!("{} is not valid JSON", path.display())),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, workspace: &Path) -> Result<()> {
+        let path = Self::path(workspace);
+        std::fs::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// The images named by the Dockerfile's `FROM` lines, skipping `scratch`
+/// and references to earlier build stages.
+pub fn base_images(dockerfile: &str) -> Vec<String> {
+    let mut stages: Vec<String> = Vec::new();
+    let mut images = Vec::new();
+    for line in dockerfile.lines() {
+        let mut words = line.split_whitespace();
+        if !words.next().is_some_and(|w| w.eq_ignore_ascii_case("FROM")) {
+            continue;
+        }
+        let mut words = words.skip_while(|w| w.starts_with("--"));
+        let Some(image) = words.next() else {
+            continue;
+        };
+        if image != "scratch" && !stages.iter().any(|s| s.eq_ignore_ascii_case(image)) && !images.iter().any(|i| i == image) {
+            images.push(image.to_string());
+        }
+        if words.next().is_some_and(|w| w.eq_ignore_ascii_case("AS"))
+            && let Some(name) = words.next()
+        {
+            stages.push(name.to_string());
+        }
+    }
+    images
+}
+
+/// The image reference of a `FROM` line, with its byte range in `line`.
+fn from_image(line: &str) -> Option<(usize, &str)> {
+    let mut words = line.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("FROM") {
+        return None;
+    }
+    let image = words.find(|w| !w.starts_with("--"))?;
+    // Safe: `image` is a subslice of `line`.
+    let start = image.as_ptr() as usize - line.as_ptr() as usize;
+    Some((start, image))
+}
+
+/// Whether `image` can be pinned: not already by digest and not built from
+/// a build argument.
+fn pinnable(image: &str) -> bool {
+    !image.contains('@') && !image.contains('$')
+}
+
+/// `dockerfile` with every locked `FROM` image replaced by `image@digest`.
+pub fn pin(dockerfile: &str, lock: &Lockfile) -> String {
+    let mut out = String::with_capacity(dockerfile.len());
+    for line in dockerfile.split_inclusive('\n') {
+        match from_image(line) {
+            Some((start, image)) if pinnable(image) && lock.images.contains_key(image) => {
+                out.push_str(&line[..start + image.len()]);
+                out.push('@');
+                out.push_str(&lock.images[image]);
+                out.push_str(&line[start + image.len()..]);
+            }
+            _ => out.push_str(line),
+        }
+    }
+    out
+}
+
+/// The Dockerfile to build for `image`: `dockerfile` itself when
+/// `ai-pod.lock` pins nothing, otherwise a pinned copy under
+/// `~/.ai-pod/pinned/`.
+pub fn pinned_dockerfile(config: &AppConfig, dockerfile: &Path, image: &str) -> Result<PathBuf> {
+    let workspace = dockerfile.parent().unwrap_or(Path::new("."));
+    let lock = Lockfile::load(workspace)?;
+    if lock.images.is_empty() {
+        return Ok(dockerfile.to_path_buf());
+    }
+    let content = std::fs::read_to_string(dockerfile)
+        .with_context(|| format!("Failed to read {}", dockerfile.display()))?;
+    let pinned = pin(&content, &lock);
+    if pinned == content {
+        return Ok(dockerfile.to_path_buf());
+    }
+    let dir = config.config_dir.join("pinned");
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}.Dockerfile", image));
+    std::fs::write(&path, pinned).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// The registry digest of the local copy of `image`, from its first
+/// `RepoDigests` entry. `None` for images that never came from a registry.
+fn local_digest(rt: &ContainerRuntime, image: &str) -> Option<String> {
+    let out = rt
+        .command()
+        .args(["image", "inspect", "--format", "{{json .RepoDigests}}", image])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let digests: Vec<String> = serde_json::from_slice(&out.stdout).ok()?;
+    digests
+        .first()
+        .and_then(|d| d.split_once('@'))
+        .map(|(_, digest)| digest.to_string())
+}
+
+/// After a build, pin the base images `ai-pod.lock` doesn't know yet to the
+/// digests the build pulled.
+pub fn lock_new_images(rt: &ContainerRuntime, dockerfile: &Path) -> Result<()> {
+    let workspace = dockerfile.parent().unwrap_or(Path::new("."));
+    let content = std::fs::read_to_string(dockerfile)
+        .with_context(|| format!("Failed to read {}", dockerfile.display()))?;
+    let mut lock = Lockfile::load(workspace)?;
+    let mut changed = false;
+    for image in base_images(&content) {
+        if !pinnable(&image) || lock.images.contains_key(&image) {
+            continue;
+        }
+        if let Some(digest) = local_digest(rt, &image) {
+            eprintln!("{} {}@{}", "Pinned:".green().bold(), image, digest);
+            lock.images.insert(image, digest);
+            changed = true;
+        }
+    }
+    if changed {
+        lock.save(workspace)?;
+    }
+    Ok(())
+}
+
+/// `ai-pod update-lock`: resolve every base image's tag again (pulling it,
+/// or through cosign when it must be verified) and rewrite `ai-pod.lock`.
+pub fn update_lock(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path) -> Result<()> {
+    let dockerfile = workspace.join(crate::image::DOCKERFILE_NAME);
+    let content = std::fs::read_to_string(&dockerfile)
+        .with_context(|| format!("No {} found in {}", crate::image::DOCKERFILE_NAME, workspace.display()))?;
+    let verify = GlobalConfig::load(config).verify;
+    let old = Lockfile::load(workspace)?;
+    let mut new = Lockfile::default();
+    for image in base_images(&content).into_iter().filter(|i| pinnable(i)) {
+        let digest = if verify.enabled() && selected(&verify, &image) {
+            eprintln!("{} {}", "Verifying:".blue().bold(), image);
+            cosign_verify(&verify, &image, &config.home_dir)?
+        } else {
+            eprintln!("{} {}", "Pulling:".blue().bold(), image);
+            let status = rt
+                .command()
+                .args(["pull", "--quiet", &image])
+                .stdout(std::process::Stdio::null())
+                .status()
+                .with_context(|| format!("Failed to run {} pull", rt.cmd()))?;
+            if !status.success() {
+                anyhow::bail!("Failed to pull {}", image);
+            }
+            if rt.dry_run {
+                continue;
+            }
+            local_digest(rt, &image).with_context(|| format!("{} reported no registry digest", image))?
+        };
+        match old.images.get(&image) {
+            Some(d) if *d == digest => println!("  {} {}", image, "unchanged".dimmed()),
+            Some(d) => println!("  {} {} → {}", image, d.dimmed(), digest),
+            None => println!("  {} {}", image, digest),
+        }
+        new.images.insert(image, digest);
+    }
+    for image in old.images.keys().filter(|i| !new.images.contains_key(*i)) {
+        println!("  {} {}", image, "removed".dimmed());
+    }
+    if rt.dry_run {
+        return Ok(());
+    }
+    if new == old {
+        println!("{}", "ai-pod.lock is up to date.".green());
+    } else {
+        new.save(workspace)?;
+        println!(
+            "{} {}. Rebuild with `ai-pod --rebuild` to use the new images.",
+            "Updated:".green().bold(),
+            Lockfile::path(workspace).display()
+        );
+    }
+    Ok(())
+}
+
+/// Whether `verify.images` selects `image` (all images when it's empty).
+fn selected(verify: &VerifyConfig, image: &str) -> bool {
+    verify.images.is_empty() || verify.images.iter().any(|g| crate::watch::glob_match(g, image))
+}
+
+/// Arguments for `cosign verify` of `image`.
+fn cosign_args(verify: &VerifyConfig, image: &str, home_dir: &Path) -> Vec<String> {
+    let mut args = vec!["verify".to_string()];
+    if let Some(key) = &verify.key {
+        args.push("--key".into());
+        args.push(crate::mount_cli::normalize_host(key, home_dir));
+    } else {
+        if let Some(identity) = &verify.identity {
+            args.push("--certificate-identity-regexp".into());
+            args.push(identity.clone());
+        }
+        if let Some(issuer) = &verify.issuer {
+            args.push("--certificate-oidc-issuer".into());
+            args.push(issuer.clone());
+        }
+    }
+    args.push(image.to_string());
+    args
+}
+
+/// The manifest digest from `cosign verify`'s JSON output; every verified
+/// signature names the same one.
+fn verified_digest(stdout: &str) -> Option<String> {
+    let payloads: Vec<serde_json::Value> = serde_json::from_str(stdout.trim()).ok()?;
+    payloads.iter().find_map(|p| {
+        p.pointer("/critical/image/docker-manifest-digest")
+            .and_then(|d| d.as_str())
+            .map(str::to_string)
+    })
+}
+
+fn cosign_verify(verify: &VerifyConfig, image: &str, home_dir: &Path) -> Result<String> {
+    let out = Command::new("cosign")
+        .args(cosign_args(verify, image, home_dir))
+        .output()
+        .context("Failed to run cosign (install it from https://docs.sigstore.dev, or pass --insecure-image)")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "{} has no valid signature: {}\nPass --insecure-image to build from it anyway.",
+            image,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    verified_digest(&String::from_utf8_lossy(&out.stdout))
+        .with_context(|| format!("cosign verified {} but reported no digest", image))
+}
+
+/// Compare `digest` against the lockfile entry for `image`, adding one if
+/// missing. Returns whether the lockfile changed.
+fn check_pin(lock: &mut Lockfile, image: &str, digest: &str) -> Result<bool> {
+    match lock.images.get(image) {
+        Some(pinned) if pinned == digest => Ok(false),
+        Some(pinned) => anyhow::bail!(
+            "{} changed since it was pinned in {} (was {}, now {}).\nIf the new image is expected, run `ai-pod update-lock`, or pass --insecure-image.",
+            image,
+            LOCKFILE,
+            pinned,
+            digest
+        ),
+        None => {
+            lock.images.insert(image.to_string(), digest.to_string());
+            Ok(true)
+        }
+    }
+}
+
+/// Verify the base images of `dockerfile` as configured under `verify`.
+/// A no-op when verification isn't configured; with `insecure` it only warns.
+pub fn verify_base_images(config: &AppConfig, dockerfile: &Path, insecure: bool) -> Result<()> {
+    let verify = GlobalConfig::load(config).verify;
+    if !verify.enabled() {
+        return Ok(());
+    }
+    if insecure {
+        eprintln!(
+            "{} --insecure-image: base images are not verified",
+            "warning:".yellow().bold()
+        );
+        return Ok(());
+    }
+    let workspace = dockerfile.parent().unwrap_or(Path::new("."));
+    let content = std::fs::read_to_string(dockerfile)
+        .with_context(|| format!("Failed to read {}", dockerfile.display()))?;
+    let mut lock = Lockfile::load(workspace)?;
+    let mut changed = false;
+    for image in base_images(&content).iter().filter(|i| selected(&verify, i)) {
+        if image.contains('$') {
+            anyhow::bail!(
+                "Cannot verify base image {}: it depends on a build argument.\nPass --insecure-image to build anyway.",
+                image
+            );
+        }
+        // A pinned image is verified at its digest, so a moved tag only
+        // matters after `ai-pod update-lock`.
+        let target = match lock.images.get(image) {
+            Some(digest) if pinnable(image) => format!("{}@{}", image, digest),
+            _ => image.clone(),
+        };
+        eprintln!("{} {}", "Verifying:".blue().bold(), target);
+        let digest = cosign_verify(&verify, &target, &config.home_dir)?;
+        if check_pin(&mut lock, image, &digest)? {
+            eprintln!("{} {}@{}", "Pinned:".green().bold(), image, digest);
+            changed = true;
+        }
+    }
+    if changed {
+        lock.save(workspace)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+
+    #[test]
+    fn base_images_skips_stages_and_scratch() {
+        let dockerfile = "\
+FROM --platform=$BUILDPLATFORM rust:1.85 AS build
+RUN cargo build
+from node:22-slim as web
+FROM scratch
+FROM build
+COPY --from=web /app /app
+FROM ghcr.io/acme/base:1
+FROM node:22-slim
+";
+        assert_eq!(
+            base_images(dockerfile),
+            vec!["rust:1.85", "node:22-slim", "ghcr.io/acme/base:1"]
+        );
+    }
+
+    #[test]
+    fn pin_rewrites_locked_from_lines_only() {
+        let mut lock = Lockfile::default();
+        lock.images.insert("node:22".into(), "sha256:aaa".into());
+        lock.images.insert("rust:1.85".into(), "sha256:bbb".into());
+        let dockerfile = "\
+FROM --platform=linux/amd64 rust:1.85 AS build
+RUN echo FROM node:22
+FROM node:22
+FROM alpine:3
+FROM node:22@sha256:ccc
+";
+        assert_eq!(
+            pin(dockerfile, &lock),
+            "\
+FROM --platform=linux/amd64 rust:1.85@sha256:bbb AS build
+RUN echo FROM node:22
+FROM node:22@sha256:aaa
+FROM alpine:3
+FROM node:22@sha256:ccc
+"
+        );
+    }
+
+    #[test]
+    fn pinned_dockerfile_only_when_something_is_pinned() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let dockerfile = dir.path().join("ai-pod.Dockerfile");
+        std::fs::write(&dockerfile, "FROM node:22\n").unwrap();
+        assert_eq!(pinned_dockerfile(&config, &dockerfile, "ws-abc123").unwrap(), dockerfile);
+
+        let mut lock = Lockfile::default();
+        lock.images.insert("node:22".into(), "sha256:aaa".into());
+        lock.save(dir.path()).unwrap();
+        let pinned = pinned_dockerfile(&config, &dockerfile, "ws-abc123").unwrap();
+        assert_eq!(pinned, config.config_dir.join("pinned/ws-abc123.Dockerfile"));
+        assert_eq!(std::fs::read_to_string(pinned).unwrap(), "FROM node:22@sha256:aaa\n");
+    }
+
+    #[test]
+    fn selected_uses_globs() {
+        let mut verify = VerifyConfig::default();
+        assert!(selected(&verify, "node:22"));
+        verify.images = vec!["ghcr.io/acme/*".into()];
+        assert!(selected(&verify, "ghcr.io/acme/base:1"));
+        assert!(!selected(&verify, "node:22"));
+    }
+
+    #[test]
+    fn cosign_args_for_key_and_keyless() {
+        let home = Path::new("/home/u");
+        let key = VerifyConfig {
+            key: Some("~/cosign.pub".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            cosign_args(&key, "node:22", home),
+            vec!["verify", "--key", "/home/u/cosign.pub", "node:22"]
+        );
+        let keyless = VerifyConfig {
+            identity: Some("https://github.com/acme/.*".into()),
+            issuer: Some("https://token.actions.githubusercontent.com".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            cosign_args(&keyless, "node:22", home),
+            vec![
+                "verify",
+                "--certificate-identity-regexp",
+                "https://github.com/acme/.*",
+                "--certificate-oidc-issuer",
+                "https://token.actions.githubusercontent.com",
+                "node:22"
+            ]
+        );
+    }
+
+    #[test]
+    fn verified_digest_reads_cosign_output() {
+        let out = r#"[{"critical":{"identity":{"docker-reference":"ghcr.io/acme/base"},"image":{"docker-manifest-digest":"sha256:abc"},"type":"cosign container image signature"},"optional":null}]"#;
+        assert_eq!(verified_digest(out).as_deref(), Some("sha256:abc"));
+        assert_eq!(verified_digest("not json"), None);
+    }
+
+    #[test]
+    fn check_pin_records_then_rejects_changes() {
+        let mut lock = Lockfile::default();
+        assert!(check_pin(&mut lock, "node:22", "sha256:aaa").unwrap());
+        assert!(!check_pin(&mut lock, "node:22", "sha256:aaa").unwrap());
+        let err = check_pin(&mut lock, "node:22", "sha256:bbb").unwrap_err();
+        assert!(err.to_string().contains("changed since it was pinned"));
+        assert_eq!(lock.images["node:22"], "sha256:aaa");
+    }
+
+    #[test]
+    fn verify_is_a_no_op_unless_configured() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let dockerfile = dir.path().join("ai-pod.Dockerfile");
+        std::fs::write(&dockerfile, "FROM node:22\n").unwrap();
+        verify_base_images(&config, &dockerfile, false).unwrap();
+        assert!(!Lockfile::path(dir.path()).exists());
+    }
+}
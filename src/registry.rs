@@ -0,0 +1,699 @@
+//! Workspace registry: every workspace ai-pod has launched or that was added
+//! with `ai-pod register`, user-defined aliases and a most-recently-launched
+//! list, persisted to `~/.ai-pod/workspaces.json`. Backs `ai-pod open`,
+//! `ai-pod alias`, `ai-pod recent`, `ai-pod list --workspaces`, `ai-pod gc`
+//! and everything that maps a workspace hash back to its directory.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+
+/// How many entries `ai-pod recent` remembers.
+const MAX_RECENT: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecentEntry {
+    pub workspace: String,
+    /// Unix timestamp (seconds) of the last launch.
+    pub last_launched: u64,
+}
+
+/// A registered workspace, keyed by its canonical path.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KnownWorkspace {
+    /// Workspace hash, as used in container, volume and state file names.
+    pub project_id: String,
+    /// Unix timestamp (seconds) of when it was registered.
+    pub registered: u64,
+    /// Port of the shared server instance its sessions talk to, when not
+    /// the default one. Set with `ai-pod register --server-port`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_port: Option<u16>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct WorkspaceRegistry {
+    /// Canonical workspace path → its entry. Filled on launch and by
+    /// `ai-pod register`.
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, KnownWorkspace>,
+    /// Alias → canonical workspace path.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    /// Most recent first, one entry per workspace.
+    #[serde(default)]
+    pub recent: Vec<RecentEntry>,
+}
+
+impl WorkspaceRegistry {
+    pub fn path(config: &AppConfig) -> PathBuf {
+        config.config_dir.join("workspaces.json")
+    }
+
+    /// Load the registry. Missing or malformed files yield an empty registry
+    /// so a corrupt file never blocks a launch.
+    pub fn load(config: &AppConfig) -> Self {
+        Self::load_from_dir(&config.config_dir)
+    }
+
+    /// Like `load`, for the shared server, which only knows the config dir.
+    pub fn load_from_dir(config_dir: &Path) -> Self {
+        std::fs::read_to_string(config_dir.join("workspaces.json"))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, config: &AppConfig) -> Result<()> {
+        let path = Self::path(config);
+        let json = serde_json::to_string_pretty(self)?;
+        let tmp = path.with_extension("tmp");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)
+            .context("Failed to write workspace registry")?;
+        file.write_all(json.as_bytes())
+            .context("Failed to write workspace registry contents")?;
+        std::fs::rename(&tmp, &path).context("Failed to rename workspace registry")?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+            .context("Failed to set permissions on workspace registry")?;
+        Ok(())
+    }
+
+    /// Insert or replace an alias. Returns the previous target, if any.
+    pub fn set_alias(&mut self, alias: &str, workspace: &Path) -> Option<String> {
+        self.aliases
+            .insert(alias.to_string(), workspace.to_string_lossy().to_string())
+    }
+
+    /// Returns true if the alias existed.
+    pub fn remove_alias(&mut self, alias: &str) -> bool {
+        self.aliases.remove(alias).is_some()
+    }
+
+    /// Add `workspace` unless it's registered already. Returns true if it
+    /// was added.
+    pub fn register(&mut self, workspace: &Path, now: u64) -> bool {
+        let ws = workspace.to_string_lossy().to_string();
+        if self.workspaces.contains_key(&ws) {
+            return false;
+        }
+        let entry = KnownWorkspace {
+            project_id: crate::workspace::workspace_hash(workspace),
+            registered: now,
+            server_port: None,
+        };
+        self.workspaces.insert(ws, entry);
+        true
+    }
+
+    /// Forget `workspace`: its entry, its place in the recent list and the
+    /// aliases pointing at it. Returns true if ai-pod knew it.
+    pub fn unregister(&mut self, workspace: &Path) -> bool {
+        let ws = workspace.to_string_lossy().to_string();
+        let recent = self.recent.len();
+        self.recent.retain(|e| e.workspace != ws);
+        let aliases = self.aliases.len();
+        self.aliases.retain(|_, w| *w != ws);
+        self.workspaces.remove(&ws).is_some() || recent != self.recent.len() || aliases != self.aliases.len()
+    }
+
+    /// Move `workspace` to the front of the recent list, stamped with `now`,
+    /// registering it if needed.
+    pub fn record_launch(&mut self, workspace: &Path, now: u64) {
+        self.register(workspace, now);
+        let ws = workspace.to_string_lossy().to_string();
+        self.recent.retain(|e| e.workspace != ws);
+        self.recent.insert(
+            0,
+            RecentEntry {
+                workspace: ws,
+                last_launched: now,
+            },
+        );
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    /// Aliases pointing at `workspace`, for display next to recent entries.
+    pub fn aliases_for(&self, workspace: &str) -> Vec<&str> {
+        self.aliases
+            .iter()
+            .filter(|(_, w)| w.as_str() == workspace)
+            .map(|(a, _)| a.as_str())
+            .collect()
+    }
+}
+
+/// Validate an alias: 1..=40 chars of ASCII letters, digits, `-` or `_`.
+/// Kept strict so an alias can never be mistaken for a path by `ai-pod open`.
+pub fn validate_alias(alias: &str) -> Result<()> {
+    if alias.is_empty() || alias.len() > 40 {
+        anyhow::bail!("Alias must be 1-40 characters");
+    }
+    if !alias
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!("Alias may only contain ASCII letters, digits, '-' or '_'");
+    }
+    Ok(())
+}
+
+/// Best-effort: note a launch of `workspace` in the recent list. Failures are
+/// ignored — the registry is a convenience, never a reason to abort.
+pub fn record_launch(config: &AppConfig, workspace: &Path) {
+    let mut reg = WorkspaceRegistry::load(config);
+    reg.record_launch(workspace, now_secs());
+    let _ = reg.save(config);
+}
+
+/// Resolve the argument to `ai-pod open`: a registered alias wins, then an
+/// existing path, then the one registered workspace with that directory
+/// name.
+pub fn resolve_open_target(config: &AppConfig, target: &str) -> Result<PathBuf> {
+    let reg = WorkspaceRegistry::load(config);
+    if let Some(ws) = reg.aliases.get(target) {
+        let path = PathBuf::from(ws);
+        if !path.is_dir() {
+            anyhow::bail!(
+                "Alias '{}' points to {}, which no longer exists. Update it with `ai-pod alias add {} <path>`.",
+                target,
+                ws,
+                target
+            );
+        }
+        return Ok(path);
+    }
+    if let Ok(path) = std::fs::canonicalize(target) {
+        return Ok(path);
+    }
+    match by_dir_name(&reg, target).as_slice() {
+        [ws] => Ok(PathBuf::from(ws)),
+        [] => anyhow::bail!(
+            "'{}' is neither a registered alias, an existing path nor a registered workspace's name \
+             (see `ai-pod alias list` and `ai-pod list --workspaces`)",
+            target
+        ),
+        several => anyhow::bail!(
+            "'{}' matches several registered workspaces ({}); give the path or add an alias",
+            target,
+            several.join(", ")
+        ),
+    }
+}
+
+/// Registered workspaces whose directory is named `name` and still exists.
+fn by_dir_name(reg: &WorkspaceRegistry, name: &str) -> Vec<String> {
+    reg.workspaces
+        .keys()
+        .filter(|ws| Path::new(ws).file_name().is_some_and(|n| n == name) && Path::new(ws).is_dir())
+        .cloned()
+        .collect()
+}
+
+/// Every workspace ai-pod knows about: registered, aliased, recently
+/// launched, or with a project state file (from before the registry).
+/// Sorted and deduplicated; includes directories that no longer exist.
+fn all_workspaces(config: &AppConfig) -> Vec<PathBuf> {
+    let reg = WorkspaceRegistry::load(config);
+    let mut all: Vec<String> = reg.workspaces.into_keys().collect();
+    all.extend(reg.aliases.into_values());
+    all.extend(reg.recent.into_iter().map(|e| e.workspace));
+    for (_, path) in crate::server::lifecycle::project_state_files(&config.config_dir) {
+        let state = crate::server::lifecycle::ProjectState::load(&path);
+        if !state.workspace.is_empty() {
+            all.push(state.workspace);
+        }
+    }
+    all.sort();
+    all.dedup();
+    all.into_iter().map(PathBuf::from).collect()
+}
+
+/// [`all_workspaces`] limited to directories that still exist.
+pub fn known_workspaces(config: &AppConfig) -> Vec<PathBuf> {
+    all_workspaces(config).into_iter().filter(|p| p.is_dir()).collect()
+}
+
+/// Short name for `workspace` in notification titles and history: its alias
+/// if it has one, otherwise the directory name. When another known workspace
+/// has the same directory name, the parent directory is added (`work/api`).
+pub fn workspace_label(config_dir: &Path, workspace: &Path) -> String {
+    let reg = WorkspaceRegistry::load_from_dir(config_dir);
+    let mut known: Vec<String> = reg.workspaces.keys().cloned().collect();
+    known.extend(reg.recent.iter().map(|e| e.workspace.clone()));
+    known.extend(reg.aliases.values().cloned());
+    for (_, path) in crate::server::lifecycle::project_state_files(config_dir) {
+        known.push(crate::server::lifecycle::ProjectState::load(&path).workspace);
+    }
+    label_among(&reg, &known, workspace)
+}
+
+fn label_among(reg: &WorkspaceRegistry, known: &[String], workspace: &Path) -> String {
+    let ws = workspace.to_string_lossy();
+    if let Some(alias) = reg.aliases_for(&ws).first() {
+        return alias.to_string();
+    }
+    let name = |p: &Path| p.file_name().map(|n| n.to_string_lossy().to_string());
+    let Some(base) = name(workspace) else {
+        return ws.to_string();
+    };
+    let clash = known
+        .iter()
+        .any(|k| *k != ws && name(Path::new(k)).as_deref() == Some(base.as_str()));
+    match workspace.parent().and_then(name) {
+        Some(parent) if clash => format!("{}/{}", parent, base),
+        _ => base,
+    }
+}
+
+/// Human-readable "time ago" for the recent list.
+pub fn format_age(now: u64, then: u64) -> String {
+    let secs = now.saturating_sub(then);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn run_alias_list(config: &AppConfig) -> Result<()> {
+    let reg = WorkspaceRegistry::load(config);
+    if reg.aliases.is_empty() {
+        println!("No aliases. Add one with `ai-pod alias add <name> [path]`.");
+        return Ok(());
+    }
+    for (alias, ws) in &reg.aliases {
+        let missing = if Path::new(ws).is_dir() {
+            String::new()
+        } else {
+            format!(" {}", "(missing)".red())
+        };
+        println!("{:<20} {}{}", alias, ws, missing);
+    }
+    Ok(())
+}
+
+pub fn run_alias_add(config: &AppConfig, alias: &str, workspace: &Path) -> Result<()> {
+    validate_alias(alias)?;
+    if !workspace.is_dir() {
+        anyhow::bail!("{} is not a directory", workspace.display());
+    }
+    let mut reg = WorkspaceRegistry::load(config);
+    let previous = reg.set_alias(alias, workspace);
+    reg.save(config)?;
+    match previous {
+        Some(old) if old != workspace.to_string_lossy() => println!(
+            "{} {} → {} (was {})",
+            "Updated alias:".green().bold(),
+            alias,
+            workspace.display(),
+            old
+        ),
+        _ => println!(
+            "{} {} → {}",
+            "Added alias:".green().bold(),
+            alias,
+            workspace.display()
+        ),
+    }
+    Ok(())
+}
+
+pub fn run_alias_remove(config: &AppConfig, alias: &str) -> Result<()> {
+    let mut reg = WorkspaceRegistry::load(config);
+    if !reg.remove_alias(alias) {
+        anyhow::bail!("No alias named '{}'", alias);
+    }
+    reg.save(config)?;
+    println!("{} {}", "Removed alias:".green().bold(), alias);
+    Ok(())
+}
+
+pub fn run_register(config: &AppConfig, workspace: &Path, server_port: Option<u16>) -> Result<()> {
+    if !workspace.is_dir() {
+        anyhow::bail!("{} is not a directory", workspace.display());
+    }
+    let mut reg = WorkspaceRegistry::load(config);
+    let added = reg.register(workspace, now_secs());
+    if let Some(port) = server_port {
+        if port == 0 {
+            anyhow::bail!("--server-port must be a port number above 0");
+        }
+        let default = crate::server::lifecycle::MCP_PORT;
+        if let Some(entry) = reg.workspaces.get_mut(&*workspace.to_string_lossy()) {
+            entry.server_port = (port != default).then_some(port);
+        }
+        reg.save(config)?;
+        println!(
+            "{} {} talks to the shared server on port {}{}",
+            if added { "Registered:" } else { "Updated:" }.green().bold(),
+            workspace.display(),
+            port,
+            if port == default { " (the default)" } else { "" }
+        );
+        println!("Sessions launched from now on use it; running ones stay on their server.");
+        return Ok(());
+    }
+    if !added {
+        println!("{} is already registered.", workspace.display());
+        return Ok(());
+    }
+    reg.save(config)?;
+    println!("{} {}", "Registered:".green().bold(), workspace.display());
+    Ok(())
+}
+
+/// Port of the shared server instance `workspace` is assigned to, or the
+/// default one.
+pub fn server_port(config: &AppConfig, workspace: &Path) -> u16 {
+    WorkspaceRegistry::load(config)
+        .workspaces
+        .get(&*workspace.to_string_lossy())
+        .and_then(|e| e.server_port)
+        .unwrap_or(crate::server::lifecycle::MCP_PORT)
+}
+
+pub fn run_unregister(config: &AppConfig, workspace: &Path) -> Result<()> {
+    let mut reg = WorkspaceRegistry::load(config);
+    if !reg.unregister(workspace) {
+        anyhow::bail!("{} is not registered (see `ai-pod list --workspaces`)", workspace.display());
+    }
+    reg.save(config)?;
+    println!("{} {}", "Unregistered:".green().bold(), workspace.display());
+    println!(
+        "Its containers, volumes and image are kept; `ai-pod clean --workdir {} --image` removes them.",
+        workspace.display()
+    );
+    Ok(())
+}
+
+/// One display line per known workspace: path, project id, aliases and
+/// when it was last launched.
+fn workspace_lines(reg: &WorkspaceRegistry, all: &[PathBuf], now: u64) -> Vec<String> {
+    all.iter()
+        .map(|ws| {
+            let path = ws.to_string_lossy();
+            let launched = reg
+                .recent
+                .iter()
+                .find(|e| e.workspace == path)
+                .map_or_else(|| "-".to_string(), |e| format_age(now, e.last_launched));
+            let aliases = reg.aliases_for(&path).join(", ");
+            let missing = if ws.is_dir() {
+                String::new()
+            } else {
+                format!(" {}", "(missing)".red())
+            };
+            let port = match reg.workspaces.get(&*path).and_then(|e| e.server_port) {
+                Some(port) => format!(" (server port {})", port),
+                None => String::new(),
+            };
+            format!(
+                "{:<12}  {:<12}  {:<16}  {}{}{}",
+                crate::workspace::workspace_hash(ws),
+                launched,
+                aliases,
+                path,
+                port,
+                missing
+            )
+        })
+        .collect()
+}
+
+/// `ai-pod list --workspaces`.
+pub fn run_workspace_list(config: &AppConfig) -> Result<()> {
+    let all = all_workspaces(config);
+    if all.is_empty() {
+        println!("No known workspaces. Launch one, or add it with `ai-pod register [path]`.");
+        return Ok(());
+    }
+    println!(
+        "{:<12}  {:<12}  {:<16}  WORKSPACE",
+        "PROJECT ID", "LAUNCHED", "ALIASES"
+    );
+    for line in workspace_lines(&WorkspaceRegistry::load(config), &all, now_secs()) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Known workspaces whose directory no longer exists, for `ai-pod gc`.
+pub fn missing_workspaces(config: &AppConfig) -> Vec<PathBuf> {
+    all_workspaces(config).into_iter().filter(|p| !p.is_dir()).collect()
+}
+
+/// One display line per recent workspace: path, aliases and age.
+fn recent_lines(reg: &WorkspaceRegistry, now: u64) -> Vec<String> {
+    reg.recent
+        .iter()
+        .map(|e| {
+            let aliases = reg.aliases_for(&e.workspace);
+            let alias = if aliases.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", aliases.join(", "))
+            };
+            format!(
+                "{}{}  ({})",
+                e.workspace,
+                alias,
+                format_age(now, e.last_launched)
+            )
+        })
+        .collect()
+}
+
+pub fn run_recent_list(config: &AppConfig) -> Result<()> {
+    let reg = WorkspaceRegistry::load(config);
+    if reg.recent.is_empty() {
+        println!("No recently launched workspaces.");
+        return Ok(());
+    }
+    for line in recent_lines(&reg, now_secs()) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Interactive picker over the recent list. Entries whose directory has
+/// since disappeared are left out. Returns `None` if there is nothing to pick.
+pub fn pick_recent(config: &AppConfig) -> Result<Option<PathBuf>> {
+    let mut reg = WorkspaceRegistry::load(config);
+    reg.recent.retain(|e| Path::new(&e.workspace).is_dir());
+    if reg.recent.is_empty() {
+        return Ok(None);
+    }
+    let items = recent_lines(&reg, now_secs());
+    let sel = dialoguer::Select::new()
+        .with_prompt("Select workspace")
+        .items(&items)
+        .default(0)
+        .interact()
+        .context("Selection cancelled")?;
+    Ok(Some(PathBuf::from(&reg.recent[sel].workspace)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use tempfile::TempDir;
+
+    #[test]
+    fn known_workspaces_merges_registry_and_project_states() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let ws: Vec<PathBuf> = ["a", "b", "c"].iter().map(|n| dir.path().join(n)).collect();
+        for w in &ws {
+            std::fs::create_dir_all(w).unwrap();
+        }
+        let mut reg = WorkspaceRegistry::default();
+        reg.set_alias("a", &ws[0]);
+        reg.record_launch(&ws[0], 1);
+        reg.record_launch(&ws[1], 2);
+        reg.record_launch(&dir.path().join("gone"), 3);
+        reg.save(&config).unwrap();
+        crate::server::lifecycle::get_or_create_project_state(&config, &ws[2]).unwrap();
+        assert_eq!(known_workspaces(&config), ws);
+    }
+
+    #[test]
+    fn label_prefers_alias_then_disambiguates_by_parent() {
+        let mut reg = WorkspaceRegistry::default();
+        let known = vec!["/src/work/api".to_string(), "/src/home/api".to_string(), "/src/web".to_string()];
+        assert_eq!(label_among(&reg, &known, Path::new("/src/web")), "web");
+        assert_eq!(label_among(&reg, &known, Path::new("/src/work/api")), "work/api");
+        assert_eq!(label_among(&reg, &known[..1], Path::new("/src/work/api")), "api");
+        reg.set_alias("backend", Path::new("/src/work/api"));
+        assert_eq!(label_among(&reg, &known, Path::new("/src/work/api")), "backend");
+        assert_eq!(label_among(&reg, &known, Path::new("/")), "/");
+    }
+
+    #[test]
+    fn launch_registers_and_unregister_forgets_everywhere() {
+        let mut reg = WorkspaceRegistry::default();
+        reg.record_launch(Path::new("/src/api"), 5);
+        assert_eq!(
+            reg.workspaces["/src/api"],
+            KnownWorkspace {
+                project_id: crate::workspace::workspace_hash(Path::new("/src/api")),
+                registered: 5,
+                server_port: None,
+            }
+        );
+        reg.record_launch(Path::new("/src/api"), 9);
+        assert_eq!(reg.workspaces["/src/api"].registered, 5);
+        assert!(!reg.register(Path::new("/src/api"), 10));
+
+        reg.set_alias("api", Path::new("/src/api"));
+        reg.set_alias("web", Path::new("/src/web"));
+        assert!(reg.unregister(Path::new("/src/api")));
+        assert!(reg.workspaces.is_empty() && reg.recent.is_empty());
+        assert_eq!(reg.aliases.keys().collect::<Vec<_>>(), vec!["web"]);
+        assert!(!reg.unregister(Path::new("/src/api")));
+    }
+
+    #[test]
+    fn open_target_falls_back_to_a_unique_registered_dir_name() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let (work, home) = (dir.path().join("work/api"), dir.path().join("home/api"));
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::create_dir_all(&home).unwrap();
+        let mut reg = WorkspaceRegistry::default();
+        reg.register(&work, 1);
+        reg.save(&config).unwrap();
+        assert_eq!(resolve_open_target(&config, "api").unwrap(), work);
+        reg.register(&home, 2);
+        reg.save(&config).unwrap();
+        let err = resolve_open_target(&config, "api").unwrap_err().to_string();
+        assert!(err.contains("matches several registered workspaces"), "{err}");
+        assert!(resolve_open_target(&config, "nope").is_err());
+    }
+
+    #[test]
+    fn missing_workspaces_come_from_every_source() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let live = dir.path().join("live");
+        std::fs::create_dir_all(&live).unwrap();
+        let mut reg = WorkspaceRegistry::default();
+        reg.register(&live, 1);
+        reg.register(&dir.path().join("moved"), 1);
+        reg.set_alias("old", &dir.path().join("deleted"));
+        reg.save(&config).unwrap();
+        assert_eq!(
+            missing_workspaces(&config),
+            vec![dir.path().join("deleted"), dir.path().join("moved")]
+        );
+        let lines = workspace_lines(&reg, std::slice::from_ref(&live), 100);
+        assert!(lines[0].starts_with(&crate::workspace::workspace_hash(&live)));
+        assert!(lines[0].ends_with(&live.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn record_launch_moves_to_front_and_dedupes() {
+        let mut reg = WorkspaceRegistry::default();
+        reg.record_launch(Path::new("/a"), 1);
+        reg.record_launch(Path::new("/b"), 2);
+        reg.record_launch(Path::new("/a"), 3);
+        let order: Vec<&str> = reg.recent.iter().map(|e| e.workspace.as_str()).collect();
+        assert_eq!(order, vec!["/a", "/b"]);
+        assert_eq!(reg.recent[0].last_launched, 3);
+    }
+
+    #[test]
+    fn record_launch_caps_history() {
+        let mut reg = WorkspaceRegistry::default();
+        for i in 0..(MAX_RECENT as u64 + 5) {
+            reg.record_launch(Path::new(&format!("/p{i}")), i);
+        }
+        assert_eq!(reg.recent.len(), MAX_RECENT);
+    }
+
+    #[test]
+    fn registry_round_trips_at_0o600() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let mut reg = WorkspaceRegistry::default();
+        assert!(reg.set_alias("api", Path::new("/home/u/api")).is_none());
+        reg.record_launch(Path::new("/home/u/api"), 42);
+        reg.save(&config).unwrap();
+
+        let mode = std::fs::metadata(WorkspaceRegistry::path(&config))
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o777;
+        assert_eq!(mode, 0o600);
+
+        let loaded = WorkspaceRegistry::load(&config);
+        assert_eq!(loaded.aliases.get("api").map(String::as_str), Some("/home/u/api"));
+        assert_eq!(loaded.aliases_for("/home/u/api"), vec!["api"]);
+        assert_eq!(loaded.recent.len(), 1);
+    }
+
+    #[test]
+    fn resolve_open_target_prefers_alias_then_path() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let ws = dir.path().join("proj");
+        std::fs::create_dir_all(&ws).unwrap();
+        let mut reg = WorkspaceRegistry::default();
+        reg.set_alias("proj", &ws);
+        reg.save(&config).unwrap();
+
+        assert_eq!(resolve_open_target(&config, "proj").unwrap(), ws);
+        let by_path = resolve_open_target(&config, &ws.display().to_string()).unwrap();
+        assert_eq!(by_path, std::fs::canonicalize(&ws).unwrap());
+        assert!(resolve_open_target(&config, "nope-not-here").is_err());
+    }
+
+    #[test]
+    fn validate_alias_rules() {
+        assert!(validate_alias("my-api_2").is_ok());
+        assert!(validate_alias("").is_err());
+        assert!(validate_alias("a/b").is_err());
+        assert!(validate_alias(&"x".repeat(41)).is_err());
+    }
+
+    #[test]
+    fn recent_lines_show_aliases() {
+        let mut reg = WorkspaceRegistry::default();
+        reg.set_alias("api", Path::new("/w/api"));
+        reg.record_launch(Path::new("/w/web"), 0);
+        reg.record_launch(Path::new("/w/api"), 0);
+        assert_eq!(
+            recent_lines(&reg, 30),
+            vec!["/w/api [api]  (just now)", "/w/web  (just now)"]
+        );
+    }
+
+    #[test]
+    fn format_age_buckets() {
+        assert_eq!(format_age(100, 90), "just now");
+        assert_eq!(format_age(1000, 100), "15m ago");
+        assert_eq!(format_age(10_000, 0), "2h ago");
+        assert_eq!(format_age(200_000, 0), "2d ago");
+    }
+}
@@ -0,0 +1,266 @@
+//! `ai-pod report weekly`: a Markdown summary of a week of agent work, for
+//! time sheets and status reports. Per project it lists the sessions and
+//! the time they ran, what they were about, the files the agent changed,
+//! and the branches and pull requests that saw commits that week.
+//!
+//! Sessions, titles and changed files come from the event log (including
+//! its rotated predecessor), so the report reaches back as far as the log
+//! does. Branches and pull requests come from the workspace's git history:
+//! a commit subject ending in `(#123)` or a `Merge pull request #123`
+//! counts as a pull request.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::events::{Event, EventKind};
+
+/// Changed files listed per project; the rest are only counted.
+const MAX_FILES: usize = 15;
+
+/// Local midnight at the start of the Monday `weeks_ago` weeks back, and a
+/// week after it, as Unix times.
+fn week_bounds(now: i64, weeks_ago: u32) -> (u64, u64) {
+    let Some(mut tm) = crate::local_time::tm(now) else {
+        return (0, 0);
+    };
+    tm.tm_hour = 0;
+    tm.tm_min = 0;
+    tm.tm_sec = 0;
+    tm.tm_isdst = -1;
+    tm.tm_mday -= (tm.tm_wday + 6) % 7 + 7 * weeks_ago as i32;
+    let mut end = tm;
+    end.tm_mday += 7;
+    // Safety: mktime only touches the `tm` we pass in.
+    let (start, end) = unsafe { (libc::mktime(&mut tm), libc::mktime(&mut end)) };
+    (start.max(0) as u64, end.max(0) as u64)
+}
+
+#[derive(Default, Debug)]
+struct ProjectWeek {
+    sessions: usize,
+    /// Seconds of the sessions that stopped, counted within the week.
+    secs: u64,
+    /// Sessions launched that week that haven't stopped (as far as the log
+    /// knows).
+    open: usize,
+    titles: Vec<String>,
+    files: BTreeSet<String>,
+    builds: usize,
+}
+
+/// Activity per workspace path between `start` and `end`.
+fn collect(events: &[Event], start: u64, end: u64) -> BTreeMap<String, ProjectWeek> {
+    let mut out: BTreeMap<String, ProjectWeek> = BTreeMap::new();
+    let mut launched: BTreeMap<&str, (u64, &str)> = BTreeMap::new();
+    for e in events.iter().filter(|e| e.ts < end) {
+        let Some(ws) = e.workspace.as_deref() else {
+            continue;
+        };
+        match e.event {
+            EventKind::Launch => {
+                if let Some(id) = e.session_id.as_deref() {
+                    launched.insert(id, (e.ts, ws));
+                }
+                if e.ts >= start {
+                    let p = out.entry(ws.to_string()).or_default();
+                    p.sessions += 1;
+                    p.open += 1;
+                    if let Some(title) = &e.title {
+                        p.titles.push(title.clone());
+                    }
+                }
+            }
+            EventKind::Stop if e.ts >= start => {
+                let Some((started, _)) = e.session_id.as_deref().and_then(|id| launched.remove(id)) else {
+                    continue;
+                };
+                let p = out.entry(ws.to_string()).or_default();
+                p.secs += e.ts - started.max(start);
+                if started >= start {
+                    p.open = p.open.saturating_sub(1);
+                } else {
+                    // Launched the week before: it still ran this week.
+                    p.sessions += 1;
+                }
+            }
+            EventKind::Notification if e.ts >= start => {
+                if let Some(files) = &e.files {
+                    out.entry(ws.to_string()).or_default().files.extend(files.iter().cloned());
+                }
+            }
+            EventKind::Build if e.ts >= start => out.entry(ws.to_string()).or_default().builds += 1,
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Branches with commits between `start` and `end`, with their number of
+/// commits in that window, and the pull requests among the commit subjects.
+fn git_activity(workspace: &Path, start: u64, end: u64) -> (Vec<(String, usize)>, Vec<String>) {
+    let since = format!("--since=@{}", start);
+    let until = format!("--until=@{}", end);
+    let mut branches = Vec::new();
+    let refs = crate::host_git::output(
+        workspace,
+        &["for-each-ref", "--sort=-committerdate", "--format=%(refname:short)%09%(committerdate:unix)", "refs/heads"],
+    )
+    .unwrap_or_default();
+    for line in refs.lines() {
+        let Some((name, ts)) = line.split_once('\t') else {
+            continue;
+        };
+        if ts.parse::<u64>().is_ok_and(|ts| ts < start) {
+            continue;
+        }
+        let count = crate::host_git::output(workspace, &["rev-list", "--count", &since, &until, name])
+            .and_then(|n| n.trim().parse().ok())
+            .unwrap_or(0);
+        if count > 0 {
+            branches.push((name.to_string(), count));
+        }
+    }
+    let pr = regex::Regex::new(r"\(#\d+\)\s*$|^Merge pull request #\d+").expect("valid regex");
+    let prs = crate::host_git::output(workspace, &["log", "--branches", "--no-color", "--format=%s", &since, &until])
+        .unwrap_or_default()
+        .lines()
+        .filter(|s| pr.is_match(s))
+        .map(str::to_string)
+        .collect();
+    (branches, prs)
+}
+
+/// The Markdown report for the week starting at `start`.
+fn render(config_dir: &Path, projects: &BTreeMap<String, ProjectWeek>, start: u64, end: u64) -> String {
+    let fmt = crate::server::notify::format_duration;
+    let mut md = format!(
+        "# ai-pod weekly report: {} to {}\n\n",
+        crate::local_time::date(start as i64),
+        crate::local_time::date(end.saturating_sub(1) as i64)
+    );
+    if projects.is_empty() {
+        md.push_str("No agent sessions this week.\n");
+        return md;
+    }
+    let mut rows: Vec<(&String, &ProjectWeek, String)> = projects
+        .iter()
+        .map(|(ws, p)| (ws, p, crate::registry::workspace_label(config_dir, Path::new(ws))))
+        .collect();
+    rows.sort_by(|a, b| b.1.secs.cmp(&a.1.secs).then(a.2.cmp(&b.2)));
+
+    md.push_str("| Project | Sessions | Agent time | Files changed |\n|---|---:|---:|---:|\n");
+    for (_, p, label) in &rows {
+        let _ = writeln!(md, "| {} | {} | {} | {} |", label, p.sessions, fmt(p.secs), p.files.len());
+    }
+    let (sessions, secs) = rows.iter().fold((0, 0), |(n, s), (_, p, _)| (n + p.sessions, s + p.secs));
+    let _ = writeln!(md, "| **Total** | **{}** | **{}** | |", sessions, fmt(secs));
+
+    for (ws, p, label) in &rows {
+        let _ = write!(md, "\n## {}\n\n`{}`\n\n", label, ws);
+        let running = if p.open > 0 {
+            format!(", {} still running or not stopped cleanly", p.open)
+        } else {
+            String::new()
+        };
+        let _ = writeln!(md, "- Sessions: {} ({}{})", p.sessions, fmt(p.secs), running);
+        if p.builds > 0 {
+            let _ = writeln!(md, "- Image builds: {}", p.builds);
+        }
+        if !p.titles.is_empty() {
+            md.push_str("- Tasks:\n");
+            for title in &p.titles {
+                let _ = writeln!(md, "  - {}", title);
+            }
+        }
+        if !p.files.is_empty() {
+            let _ = writeln!(md, "- Files changed ({}):", p.files.len());
+            for file in p.files.iter().take(MAX_FILES) {
+                let _ = writeln!(md, "  - `{}`", file);
+            }
+            if p.files.len() > MAX_FILES {
+                let _ = writeln!(md, "  - and {} more", p.files.len() - MAX_FILES);
+            }
+        }
+        let (branches, prs) = git_activity(Path::new(ws), start, end);
+        if !branches.is_empty() {
+            let list: Vec<String> = branches.iter().map(|(b, n)| format!("`{}` ({})", b, n)).collect();
+            let _ = writeln!(md, "- Branches with commits: {}", list.join(", "));
+        }
+        if !prs.is_empty() {
+            md.push_str("- Pull requests:\n");
+            for pr in &prs {
+                let _ = writeln!(md, "  - {}", pr);
+            }
+        }
+    }
+    md
+}
+
+/// `ai-pod report weekly [--weeks-ago N] [--out FILE]`.
+pub fn run_weekly(config_dir: &Path, weeks_ago: u32, out: Option<&PathBuf>) -> Result<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (start, end) = week_bounds(now, weeks_ago);
+    let events = crate::events::load_all(config_dir);
+    let md = render(config_dir, &collect(&events, start, end), start, end);
+    match out {
+        Some(path) => {
+            std::fs::write(path, &md).with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Wrote {}", path.display());
+        }
+        None => print!("{}", md),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ts: u64, kind: EventKind, session: Option<&str>) -> Event {
+        let mut e = Event::new(kind, Some(Path::new("/p/a")));
+        e.ts = ts;
+        if let Some(id) = session {
+            e = e.session(id, "c");
+        }
+        e
+    }
+
+    #[test]
+    fn week_is_summed_per_project_within_its_bounds() {
+        let (start, end) = week_bounds(1_760_000_000, 0);
+        assert_eq!(end - start, 7 * 86_400, "no DST change in that week");
+        assert!(start <= 1_760_000_000 && 1_760_000_000 < end);
+        assert_eq!(week_bounds(1_760_000_000, 1).1, start);
+
+        let mut titled = at(start + 100, EventKind::Launch, Some("s2"));
+        titled.title = Some("fix login".into());
+        let mut stop_note = at(start + 400, EventKind::Notification, Some("s2"));
+        stop_note.files = Some(vec!["src/a.rs".into(), "src/b.rs".into()]);
+        let events = vec![
+            at(start - 100, EventKind::Launch, Some("s1")),
+            at(start + 50, EventKind::Stop, Some("s1")),
+            titled,
+            stop_note,
+            at(start + 600, EventKind::Stop, Some("s2")),
+            at(start + 700, EventKind::Build, None),
+            at(start + 800, EventKind::Launch, Some("s3")),
+            at(end + 10, EventKind::Launch, Some("s4")),
+        ];
+        let week = collect(&events, start, end);
+        let p = &week["/p/a"];
+        assert_eq!((p.sessions, p.open, p.builds), (3, 1, 1));
+        assert_eq!(p.secs, 50 + 500);
+        assert_eq!(p.titles, vec!["fix login".to_string()]);
+        assert_eq!(p.files.len(), 2);
+
+        let md = render(Path::new("/nonexistent"), &week, start, end);
+        assert!(md.contains("| a | 3 | 9m | 2 |"), "{}", md);
+        assert!(md.contains("  - `src/a.rs`"));
+        assert!(md.contains("1 still running"));
+    }
+}
@@ -0,0 +1,354 @@
+//! `ai-pod review`: walk through the agent's uncommitted changes hunk by hunk,
+//! like `git add -p`. Accepting a hunk stages it, reverting removes it from
+//! the working tree, and opening jumps to it in `editor.command`. Untracked
+//! files are reviewed as a whole. Accepted changes end up in the index, so a
+//! second run only shows what's still undecided.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::config::GlobalConfig;
+use crate::editor::ChangedFile;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hunk {
+    text: String,
+    /// First line of the hunk in the working-tree file.
+    new_start: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FilePatch {
+    path: String,
+    /// `diff --git` through the `+++` line, prepended to every hunk.
+    header: String,
+    hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Accept,
+    Revert,
+    Open,
+    Skip,
+    Quit,
+}
+
+fn parse_action(input: &str) -> Option<Action> {
+    match input.trim() {
+        "a" | "y" => Some(Action::Accept),
+        "r" | "d" => Some(Action::Revert),
+        "o" | "e" => Some(Action::Open),
+        "s" | "n" | "" => Some(Action::Skip),
+        "q" => Some(Action::Quit),
+        _ => None,
+    }
+}
+
+/// Split `git diff` output into files and hunks.
+fn parse_diff(diff: &str) -> Vec<FilePatch> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    for line in diff.split_inclusive('\n') {
+        if line.starts_with("diff --git ") {
+            files.push(FilePatch {
+                path: String::new(),
+                header: String::new(),
+                hunks: Vec::new(),
+            });
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if let Some(rest) = line.strip_prefix("@@ ") {
+            let new_start = rest
+                .split_whitespace()
+                .find_map(|f| f.strip_prefix('+'))
+                .and_then(|f| f.split(',').next())
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(1);
+            file.hunks.push(Hunk {
+                text: line.to_string(),
+                new_start,
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.text.push_str(line);
+        } else {
+            if let Some(p) = line.strip_prefix("+++ b/") {
+                file.path = p.trim_end().to_string();
+            } else if let Some(p) = line.strip_prefix("--- a/")
+                && file.path.is_empty()
+            {
+                file.path = p.trim_end().to_string();
+            }
+            file.header.push_str(line);
+        }
+    }
+    files
+}
+
+fn git(workspace: &Path) -> Command {
+    crate::host_git::command(workspace)
+}
+
+/// `git apply` a patch from stdin with extra flags (`--cached`, `-R`).
+fn apply(workspace: &Path, patch: &str, flags: &[&str]) -> Result<()> {
+    let mut child = git(workspace)
+        .arg("apply")
+        .args(flags)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run git apply")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(patch.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git apply failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn git_ok(workspace: &Path, args: &[&str]) -> Result<()> {
+    let status = git(workspace).args(args).status().context("Failed to run git")?;
+    if !status.success() {
+        anyhow::bail!("git {} failed", args.join(" "));
+    }
+    Ok(())
+}
+
+fn print_hunk(text: &str) {
+    for line in text.lines() {
+        let colored = if line.starts_with("@@") {
+            line.cyan()
+        } else if line.starts_with('+') {
+            line.green()
+        } else if line.starts_with('-') {
+            line.red()
+        } else {
+            line.normal()
+        };
+        println!("{}", colored);
+    }
+}
+
+fn prompt(input: &mut impl BufRead, question: &str) -> Result<Action> {
+    loop {
+        print!("{} [a]ccept, [r]evert, [o]pen, [s]kip, [q]uit? ", question.blue().bold());
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Action::Quit);
+        }
+        match parse_action(&line) {
+            Some(a) => return Ok(a),
+            None => println!("Unknown choice '{}'", line.trim()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    accepted: usize,
+    reverted: usize,
+    skipped: usize,
+}
+
+fn open_at(workspace: &Path, config_dir: &Path, path: &str, line: u32) {
+    let file = ChangedFile {
+        path: path.to_string(),
+        line,
+    };
+    let editor = GlobalConfig::load_from_dir(config_dir).editor;
+    if let Err(e) = crate::editor::open(&editor, workspace, &[file]) {
+        eprintln!("{} {:#}", "warning:".yellow().bold(), e);
+    }
+}
+
+pub fn run(config_dir: &Path, workspace: &Path) -> Result<()> {
+    let diff = git(workspace)
+        .args(["diff", "--no-color", "--no-ext-diff", "--no-textconv"])
+        .stderr(Stdio::null())
+        .output()
+        .context("Failed to run git diff")?;
+    if !diff.status.success() {
+        anyhow::bail!("{} is not a git repository", workspace.display());
+    }
+    let files = parse_diff(&String::from_utf8_lossy(&diff.stdout));
+    let untracked: Vec<String> = String::from_utf8_lossy(
+        &git(workspace)
+            .args(["ls-files", "--others", "--exclude-standard", "-z"])
+            .output()
+            .context("Failed to list untracked files")?
+            .stdout,
+    )
+    .split('\0')
+    .filter(|p| !p.is_empty() && !p.starts_with(".ai-pod/"))
+    .map(str::to_string)
+    .collect();
+
+    if files.is_empty() && untracked.is_empty() {
+        println!("{}", "Nothing to review.".yellow());
+        return Ok(());
+    }
+
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let mut tally = Tally::default();
+    'files: for file in &files {
+        // Binary or mode-only changes have no hunks; decide on the whole file.
+        if file.hunks.is_empty() {
+            println!("\n{}", file.header.trim_end().bold());
+            loop {
+                match prompt(&mut input, &format!("{}:", file.path))? {
+                    Action::Accept => {
+                        git_ok(workspace, &["add", "--", &file.path])?;
+                        tally.accepted += 1;
+                    }
+                    Action::Revert => {
+                        git_ok(workspace, &["checkout", "--", &file.path])?;
+                        tally.reverted += 1;
+                    }
+                    Action::Open => {
+                        open_at(workspace, config_dir, &file.path, 1);
+                        continue;
+                    }
+                    Action::Skip => tally.skipped += 1,
+                    Action::Quit => break 'files,
+                }
+                break;
+            }
+            continue;
+        }
+        for (i, hunk) in file.hunks.iter().enumerate() {
+            println!("\n{}", file.path.bold());
+            print_hunk(&hunk.text);
+            let patch = format!("{}{}", file.header, hunk.text);
+            loop {
+                let question = format!("Hunk {}/{}", i + 1, file.hunks.len());
+                match prompt(&mut input, &question)? {
+                    Action::Accept => match apply(workspace, &patch, &["--cached"]) {
+                        Ok(()) => tally.accepted += 1,
+                        Err(e) => eprintln!("{} {:#}", "warning:".yellow().bold(), e),
+                    },
+                    Action::Revert => match apply(workspace, &patch, &["-R"]) {
+                        Ok(()) => tally.reverted += 1,
+                        Err(e) => eprintln!("{} {:#}", "warning:".yellow().bold(), e),
+                    },
+                    Action::Open => {
+                        open_at(workspace, config_dir, &file.path, hunk.new_start);
+                        continue;
+                    }
+                    Action::Skip => tally.skipped += 1,
+                    Action::Quit => break 'files,
+                }
+                break;
+            }
+        }
+    }
+
+    for path in &untracked {
+        println!("\n{} {}", "New file:".bold(), path);
+        loop {
+            match prompt(&mut input, &format!("{}:", path))? {
+                Action::Accept => {
+                    git_ok(workspace, &["add", "--", path])?;
+                    tally.accepted += 1;
+                }
+                Action::Revert => {
+                    std::fs::remove_file(workspace.join(path))
+                        .with_context(|| format!("Failed to remove {}", path))?;
+                    tally.reverted += 1;
+                }
+                Action::Open => {
+                    open_at(workspace, config_dir, path, 1);
+                    continue;
+                }
+                Action::Skip => tally.skipped += 1,
+                Action::Quit => break,
+            }
+            break;
+        }
+    }
+
+    println!(
+        "\n{} accepted (staged), {} reverted, {} skipped.",
+        tally.accepted, tally.reverted, tally.skipped
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/a.rs b/src/a.rs\n\
+index 1111111..2222222 100644\n\
+--- a/src/a.rs\n\
++++ b/src/a.rs\n\
+@@ -1,2 +1,2 @@\n\
+ fn a() {}\n\
+-fn b() {}\n\
++fn b() { todo!() }\n\
+@@ -10 +10,2 @@ impl X\n\
+ x\n\
++y\n\
+diff --git a/logo.png b/logo.png\n\
+index 3333333..4444444 100644\n\
+Binary files a/logo.png and b/logo.png differ\n";
+
+    #[test]
+    fn parse_diff_splits_files_and_hunks() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/a.rs");
+        assert!(files[0].header.ends_with("+++ b/src/a.rs\n"));
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(files[0].hunks[1].new_start, 10);
+        assert!(files[0].hunks[0].text.contains("+fn b() { todo!() }\n"));
+        assert!(files[1].hunks.is_empty());
+    }
+
+    #[test]
+    fn parse_action_accepts_git_add_p_keys() {
+        assert_eq!(parse_action("a\n"), Some(Action::Accept));
+        assert_eq!(parse_action("y"), Some(Action::Accept));
+        assert_eq!(parse_action("r"), Some(Action::Revert));
+        assert_eq!(parse_action(""), Some(Action::Skip));
+        assert_eq!(parse_action("q"), Some(Action::Quit));
+        assert_eq!(parse_action("x"), None);
+    }
+
+    #[test]
+    fn single_hunks_stage_and_revert_independently() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ws = dir.path();
+        let run = |args: &[&str]| assert!(git(ws).args(args).output().unwrap().status.success());
+        run(&["init", "-q"]);
+        let original: String = (1..=20).map(|i| format!("line{}\n", i)).collect();
+        std::fs::write(ws.join("f.txt"), &original).unwrap();
+        run(&["add", "."]);
+        run(&["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-qm", "init"]);
+        let changed = original.replace("line2\n", "two\n").replace("line19\n", "nineteen\n");
+        std::fs::write(ws.join("f.txt"), changed).unwrap();
+
+        let out = git(ws).args(["diff", "--no-color"]).output().unwrap().stdout;
+        let files = parse_diff(&String::from_utf8_lossy(&out));
+        assert_eq!(files[0].hunks.len(), 2);
+        let patch = |i: usize| format!("{}{}", files[0].header, files[0].hunks[i].text);
+        apply(ws, &patch(0), &["--cached"]).unwrap();
+        apply(ws, &patch(1), &["-R"]).unwrap();
+
+        let staged = git(ws).args(["diff", "--cached", "--no-color"]).output().unwrap().stdout;
+        assert!(String::from_utf8_lossy(&staged).contains("+two"));
+        let now = std::fs::read_to_string(ws.join("f.txt")).unwrap();
+        assert!(now.contains("two\n") && now.contains("line19\n"));
+    }
+}
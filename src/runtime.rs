@@ -1,10 +1,14 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::ValueEnum;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::process::Command;
 use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::config::UsernsMode;
+use crate::runtime_check::{self, Platform};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -34,9 +38,24 @@ impl RuntimeKind {
         }
     }
 
-    /// Whether this runtime's binary is present and runnable on PATH.
+    fn binary_override(self) -> &'static OnceLock<String> {
+        static PODMAN: OnceLock<String> = OnceLock::new();
+        static DOCKER: OnceLock<String> = OnceLock::new();
+        match self {
+            RuntimeKind::Podman => &PODMAN,
+            RuntimeKind::Docker => &DOCKER,
+        }
+    }
+
+    /// The program to run: the path set by [`configure`], else the plain
+    /// binary name looked up on PATH.
+    pub fn binary(self) -> &'static str {
+        self.binary_override().get().map_or(self.as_str(), String::as_str)
+    }
+
+    /// Whether this runtime's binary is present and runnable.
     pub fn is_available(self) -> bool {
-        Command::new(self.as_str())
+        Command::new(self.binary())
             .arg("--version")
             .output()
             .is_ok_and(|o| o.status.success())
@@ -67,11 +86,7 @@ impl ContainerRuntime {
             if dry_run || kind.is_available() {
                 return Ok(Self { kind, dry_run });
             }
-            anyhow::bail!(
-                "Requested container runtime `{}` is not available on PATH. \
-                 Install it or choose the other runtime.",
-                kind.as_str()
-            );
+            anyhow::bail!(runtime_check::not_found_message(Some(kind), Platform::current()));
         }
         if RuntimeKind::Podman.is_available() {
             return Ok(Self {
@@ -85,9 +100,7 @@ impl ContainerRuntime {
                 dry_run,
             });
         }
-        anyhow::bail!(
-            "Neither podman nor docker found. Install one of them and ensure it is on your PATH."
-        )
+        anyhow::bail!(runtime_check::not_found_message(None, Platform::current()))
     }
 
     /// The binary name: "podman" or "docker"
@@ -97,45 +110,61 @@ impl ContainerRuntime {
 
     /// Returns a std::process::Command with the runtime binary.
     /// When `dry_run` is set, returns an `echo` command prefixed with the
-    /// runtime name so the intended invocation is printed instead of run.
+    /// runtime binary so the intended invocation is printed instead of run.
+    /// Under `--record`, the runtime runs through the recording shim.
     pub fn command(&self) -> Command {
+        let binary = self.kind.binary();
         if self.dry_run {
             let mut cmd = Command::new("echo");
-            cmd.arg(self.cmd());
+            cmd.arg(binary);
             cmd
         } else {
-            Command::new(self.cmd())
+            crate::record::wrap(binary).unwrap_or_else(|| Command::new(binary))
         }
     }
 
     /// Returns a tokio::process::Command with the runtime binary.
-    /// Honors `dry_run` the same way as `command()`.
+    /// Honors `dry_run` and `--record` the same way as `command()`.
     pub fn async_command(&self) -> tokio::process::Command {
+        let binary = self.kind.binary();
         if self.dry_run {
             let mut cmd = tokio::process::Command::new("echo");
-            cmd.arg(self.cmd());
+            cmd.arg(binary);
             cmd
+        } else if let Some(cmd) = crate::record::wrap(binary) {
+            cmd.into()
         } else {
-            tokio::process::Command::new(self.cmd())
+            tokio::process::Command::new(binary)
         }
     }
 
     /// The hostname that resolves to the host from inside a container.
     pub fn host_gateway(&self) -> &'static str {
+        if let Some((name, _)) = crate::vm::provider().host_gateway() {
+            return name;
+        }
         match self.kind {
             RuntimeKind::Podman => "host.containers.internal",
             RuntimeKind::Docker => "host.docker.internal",
         }
     }
 
+    /// What `--add-host` maps [`Self::host_gateway`] to: the runtime's
+    /// `host-gateway`, unless the VM puts the host elsewhere (see
+    /// [`crate::vm`]).
+    pub fn host_gateway_address(&self) -> &'static str {
+        crate::vm::provider().host_gateway().map_or("host-gateway", |(_, address)| address)
+    }
+
     /// The --add-host flag value for host gateway resolution.
     pub fn add_host_arg(&self) -> String {
-        format!("--add-host={}:host-gateway", self.host_gateway())
+        format!("--add-host={}:{}", self.host_gateway(), self.host_gateway_address())
     }
 
-    /// The server URL using the correct gateway hostname.
+    /// The server URL using the correct gateway hostname and the port of
+    /// the selected server instance.
     pub fn server_url(&self) -> String {
-        format!("http://{}:7822", self.host_gateway())
+        format!("http://{}:{}", self.host_gateway(), crate::server::lifecycle::server_port())
     }
 
     /// Display name for the runtime (e.g. in generated docs).
@@ -159,10 +188,13 @@ impl ContainerRuntime {
     /// and `/etc/subuid` actually has a sub-UID range configured for the current
     /// user (the precondition for rootless UID remapping — this avoids a false
     /// positive for rootful Podman invoked by a non-root user).
-    pub fn warn_if_rootless_userns_mismatch(&self) {
+    pub fn warn_if_rootless_userns_mismatch(&self, userns: UsernsMode) {
         if self.kind != RuntimeKind::Podman || self.dry_run {
             return;
         }
+        if userns == UsernsMode::KeepId {
+            return;
+        }
         if env::var_os("PODMAN_USERNS").is_some() {
             return;
         }
@@ -179,12 +211,183 @@ impl ContainerRuntime {
         eprintln!(
             "{} workspace files may appear root-owned inside the container \
              (rootless Podman's default UID mapping).\n  \
-             Set {} before running ai-pod to fix this, e.g. `{}`.",
+             Set {} in ~/.ai-pod/config.json to fix this.",
             "warning:".yellow().bold(),
-            "PODMAN_USERNS=keep-id".bold(),
-            "PODMAN_USERNS=keep-id ai-pod".bold(),
+            "\"userns\": \"keep-id\"".bold(),
         );
     }
+
+    /// `--userns` flags for the agent container. Only rootless-relevant on
+    /// Podman, and skipped when `PODMAN_USERNS` is set so an explicit env
+    /// choice still wins. `ids` is the image user's `(uid, gid)`; when known,
+    /// the host user is mapped onto it so `$HOME` ownership inside the image
+    /// keeps matching, otherwise Podman's plain keep-id is used.
+    pub fn userns_args(&self, userns: UsernsMode, ids: Option<(u32, u32)>) -> Vec<String> {
+        if userns != UsernsMode::KeepId {
+            return Vec::new();
+        }
+        if self.kind != RuntimeKind::Podman {
+            eprintln!(
+                "{} \"userns\": \"keep-id\" only applies to Podman; ignoring",
+                "warning:".yellow().bold()
+            );
+            return Vec::new();
+        }
+        if env::var_os("PODMAN_USERNS").is_some() {
+            return Vec::new();
+        }
+        match ids {
+            Some((uid, gid)) => vec![format!("--userns=keep-id:uid={uid},gid={gid}")],
+            None => vec!["--userns=keep-id".to_string()],
+        }
+    }
+
+    /// `--detach-keys` flag for `run`/`attach`. `None` keeps the runtime's
+    /// default (ctrl-p,ctrl-q). An empty sequence disables detaching, which
+    /// only Podman supports; Docker treats it as "use the default", so warn
+    /// rather than pretend it worked.
+    pub fn detach_keys_args(&self, keys: Option<&str>) -> Vec<String> {
+        match keys {
+            None => Vec::new(),
+            Some("") if self.kind == RuntimeKind::Docker => {
+                eprintln!(
+                    "{} Docker cannot disable the detach sequence; using its default",
+                    "warning:".yellow().bold()
+                );
+                Vec::new()
+            }
+            Some(k) => vec![format!("--detach-keys={k}")],
+        }
+    }
+}
+
+/// Which runtime a binary is, from its `--version` output: `podman version
+/// 5.2.0`, `podman-remote version 5.2.0` or `Docker version 27.3.1, ...`.
+fn kind_from_version(output: &str) -> Option<RuntimeKind> {
+    let lower = output.trim_start().to_ascii_lowercase();
+    if lower.starts_with("podman") {
+        Some(RuntimeKind::Podman)
+    } else if lower.starts_with("docker") {
+        Some(RuntimeKind::Docker)
+    } else {
+        None
+    }
+}
+
+/// Run `binary --version` and tell which runtime it is. Under `dry_run`
+/// nothing is run and the file name decides.
+fn probe_binary(binary: &str, dry_run: bool) -> Result<RuntimeKind> {
+    if dry_run {
+        let name = std::path::Path::new(binary)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        return Ok(if name.contains("docker") {
+            RuntimeKind::Docker
+        } else {
+            RuntimeKind::Podman
+        });
+    }
+    let output = Command::new(binary)
+        .arg("--version")
+        .output()
+        .with_context(|| format!("Failed to run `{} --version`", binary))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`{} --version` failed: {}",
+            binary,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    kind_from_version(&stdout).with_context(|| {
+        format!(
+            "`{}` is neither podman nor docker (`--version` printed \"{}\")",
+            binary,
+            stdout.trim()
+        )
+    })
+}
+
+/// Resolve the runtime preference and binary overrides at startup:
+///
+/// - `--runtime` (`flag`) picks a runtime by name.
+/// - `AI_POD_RUNTIME` (`env_value`) is a runtime name or a binary, either a
+///   path (`/opt/homebrew/bin/podman`) or a name on PATH (`podman-remote`).
+/// - `runtime_path` in the global config is a binary like the above.
+/// - `$PODMAN` (`podman_env`) is the podman binary to use.
+///
+/// Every binary is checked with `--version`, which also tells its runtime.
+/// Returns the preferred runtime, `None` to autodetect. Call once, before
+/// [`ContainerRuntime::detect`].
+pub fn configure(
+    flag: Option<RuntimeKind>,
+    env_value: Option<&str>,
+    config_path: Option<&str>,
+    podman_env: Option<&str>,
+    dry_run: bool,
+) -> Result<Option<RuntimeKind>> {
+    let env_value = env_value.map(str::trim).filter(|v| !v.is_empty());
+    let env_kind = env_value.and_then(RuntimeKind::from_value);
+    let binary = match (env_value, config_path) {
+        (Some(v), _) if env_kind.is_none() => Some((v, "AI_POD_RUNTIME")),
+        (_, Some(p)) if !p.is_empty() => Some((p, "runtime_path in config.json")),
+        _ => None,
+    };
+    let mut path_kind = None;
+    if let Some((binary, source)) = binary {
+        let kind = probe_binary(binary, dry_run)
+            .with_context(|| format!("Invalid container runtime in {}", source))?;
+        let _ = kind.binary_override().set(binary.to_string());
+        path_kind = Some(kind);
+    }
+    if let Some(podman) = podman_env.filter(|p| !p.is_empty())
+        && path_kind != Some(RuntimeKind::Podman)
+    {
+        let kind = probe_binary(podman, dry_run).context("Invalid podman binary in $PODMAN")?;
+        if kind != RuntimeKind::Podman {
+            anyhow::bail!("$PODMAN is {}, not podman", podman);
+        }
+        let _ = kind.binary_override().set(podman.to_string());
+    }
+    Ok(flag.or(env_kind).or(path_kind))
+}
+
+/// Pick the detach sequence: `--detach-keys` wins over `detach_keys` in
+/// `~/.ai-pod/config.json`; `None` means the runtime default.
+pub fn resolve_detach_keys(cli: Option<&str>, config: Option<&str>) -> Result<Option<String>> {
+    let Some(keys) = cli.or(config) else {
+        return Ok(None);
+    };
+    validate_detach_keys(keys)?;
+    Ok(Some(keys.to_string()))
+}
+
+/// Validate a detach sequence in the runtime's syntax: comma-separated keys,
+/// each either a single character or `ctrl-<c>` where `<c>` is a letter or
+/// one of `@ ^ [ \ ] _`. The empty string (disable) is accepted.
+pub fn validate_detach_keys(keys: &str) -> Result<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    for key in keys.split(',') {
+        let ok = match key.strip_prefix("ctrl-") {
+            Some(c) => {
+                c.len() == 1
+                    && c.chars()
+                        .all(|c| c.is_ascii_lowercase() || "@^[\\]_".contains(c))
+            }
+            None => key.chars().count() == 1,
+        };
+        if !ok {
+            anyhow::bail!(
+                "Invalid detach key '{}' in '{}'. Use e.g. \"ctrl-x,ctrl-d\", or \"\" to disable.",
+                key,
+                keys
+            );
+        }
+    }
+    Ok(())
 }
 
 /// Whether `/etc/subuid` configures a sub-UID range for the current user,
@@ -192,7 +395,7 @@ impl ContainerRuntime {
 /// `subuid(5)`). A missing/unreadable file (`None`) defaults to `true` so the
 /// hint is surfaced rather than silently swallowed on the rootless-Podman hosts
 /// this targets.
-fn subuid_range_configured(username: Option<&str>, uid: u32, subuid: Option<&str>) -> bool {
+pub(crate) fn subuid_range_configured(username: Option<&str>, uid: u32, subuid: Option<&str>) -> bool {
     let Some(contents) = subuid else {
         return true;
     };
@@ -262,6 +465,35 @@ mod tests {
         assert_eq!(program, "docker");
     }
 
+    #[test]
+    fn detach_keys_args_per_runtime() {
+        let podman = ContainerRuntime {
+            kind: RuntimeKind::Podman,
+            dry_run: false,
+        };
+        let docker = ContainerRuntime {
+            kind: RuntimeKind::Docker,
+            dry_run: false,
+        };
+        assert!(podman.detach_keys_args(None).is_empty());
+        assert_eq!(podman.detach_keys_args(Some("ctrl-x,ctrl-d")), vec!["--detach-keys=ctrl-x,ctrl-d"]);
+        assert_eq!(podman.detach_keys_args(Some("")), vec!["--detach-keys="]);
+        assert!(docker.detach_keys_args(Some("")).is_empty());
+    }
+
+    #[test]
+    fn resolve_detach_keys_prefers_cli_and_validates() {
+        assert_eq!(resolve_detach_keys(None, None).unwrap(), None);
+        assert_eq!(
+            resolve_detach_keys(Some("ctrl-x"), Some("ctrl-]")).unwrap().as_deref(),
+            Some("ctrl-x")
+        );
+        assert_eq!(resolve_detach_keys(None, Some("")).unwrap().as_deref(), Some(""));
+        assert!(resolve_detach_keys(Some("ctrl-xy"), None).is_err());
+        assert!(resolve_detach_keys(Some("ctrl-p,,q"), None).is_err());
+        assert!(validate_detach_keys("ctrl-],q").is_ok());
+    }
+
     #[test]
     fn dry_run_on_uses_echo() {
         let rt = ContainerRuntime {
@@ -347,7 +579,7 @@ mod tests {
             kind: RuntimeKind::Docker,
             dry_run: false,
         };
-        rt.warn_if_rootless_userns_mismatch();
+        rt.warn_if_rootless_userns_mismatch(UsernsMode::Default);
     }
 
     #[test]
@@ -356,7 +588,72 @@ mod tests {
             kind: RuntimeKind::Podman,
             dry_run: true,
         };
-        rt.warn_if_rootless_userns_mismatch();
+        rt.warn_if_rootless_userns_mismatch(UsernsMode::Default);
+    }
+
+    #[test]
+    fn userns_args_empty_for_default_mode() {
+        let rt = ContainerRuntime {
+            kind: RuntimeKind::Podman,
+            dry_run: false,
+        };
+        assert!(rt.userns_args(UsernsMode::Default, Some((1000, 1000))).is_empty());
+    }
+
+    #[test]
+    fn userns_args_maps_onto_image_user_when_known() {
+        if env::var_os("PODMAN_USERNS").is_some() {
+            return;
+        }
+        let rt = ContainerRuntime {
+            kind: RuntimeKind::Podman,
+            dry_run: false,
+        };
+        assert_eq!(
+            rt.userns_args(UsernsMode::KeepId, Some((1001, 1002))),
+            vec!["--userns=keep-id:uid=1001,gid=1002"]
+        );
+        assert_eq!(
+            rt.userns_args(UsernsMode::KeepId, None),
+            vec!["--userns=keep-id"]
+        );
+    }
+
+    #[test]
+    fn userns_args_ignored_on_docker() {
+        let rt = ContainerRuntime {
+            kind: RuntimeKind::Docker,
+            dry_run: false,
+        };
+        assert!(rt.userns_args(UsernsMode::KeepId, Some((1000, 1000))).is_empty());
+    }
+
+    #[test]
+    fn kind_from_version_reads_the_banner() {
+        assert_eq!(kind_from_version("podman version 5.2.0\n"), Some(RuntimeKind::Podman));
+        assert_eq!(kind_from_version("podman-remote version 4.9.3"), Some(RuntimeKind::Podman));
+        assert_eq!(
+            kind_from_version("Docker version 27.3.1, build ce12230"),
+            Some(RuntimeKind::Docker)
+        );
+        assert_eq!(kind_from_version("nerdctl version 1.7.6"), None);
+    }
+
+    #[test]
+    fn configure_rejects_a_binary_that_is_not_a_runtime() {
+        let err = configure(None, Some("/bin/true"), None, None, false).unwrap_err();
+        assert!(format!("{:#}", err).contains("AI_POD_RUNTIME"));
+        let err = configure(None, None, Some("/nonexistent/podman"), None, false).unwrap_err();
+        assert!(format!("{:#}", err).contains("runtime_path"));
+        // Names keep selecting a runtime without touching the binary.
+        assert_eq!(
+            configure(None, Some(" docker "), None, None, false).unwrap(),
+            Some(RuntimeKind::Docker)
+        );
+        assert_eq!(
+            configure(Some(RuntimeKind::Podman), Some("docker"), None, None, false).unwrap(),
+            Some(RuntimeKind::Podman)
+        );
     }
 
     #[test]
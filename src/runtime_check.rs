@@ -0,0 +1,340 @@
+//! Turn a missing or broken container runtime into an actionable message.
+//! Runtime detection reports which binary is absent with install commands
+//! for the host's platform, and commands that need a working engine probe it
+//! first so a stopped podman machine, an unreachable Docker daemon or a
+//! missing sub-UID range is explained up front instead of surfacing as an
+//! opaque failure halfway through a launch.
+
+use colored::Colorize;
+use std::process::{Command, Stdio};
+
+use crate::runtime::{ContainerRuntime, RuntimeKind};
+
+/// Why the runtime can't be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The binary isn't on PATH.
+    NotInstalled(RuntimeKind),
+    /// Podman's VM (macOS/Windows) isn't running, or was never created.
+    MachineStopped { initialised: bool },
+    /// The Docker daemon isn't running.
+    DaemonDown,
+    /// The Docker socket exists but the user may not use it.
+    SocketPermission,
+    /// Rootless Podman can't set up its user namespace.
+    NoSubuid,
+    /// Anything else; carries the engine's own error output.
+    Other { kind: RuntimeKind, stderr: String },
+}
+
+/// The host platform, for picking install commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    MacOs,
+    Fedora,
+    Debian,
+    Arch,
+    OpenSuse,
+    Alpine,
+    OtherLinux,
+    Other,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        match std::env::consts::OS {
+            "macos" => Platform::MacOs,
+            "linux" => Self::from_os_release(&std::fs::read_to_string("/etc/os-release").unwrap_or_default()),
+            _ => Platform::Other,
+        }
+    }
+
+    /// Pick the distro family from `/etc/os-release`'s `ID` and `ID_LIKE`.
+    fn from_os_release(contents: &str) -> Self {
+        let ids: Vec<String> = contents
+            .lines()
+            .filter_map(|l| l.strip_prefix("ID=").or_else(|| l.strip_prefix("ID_LIKE=")))
+            .flat_map(|v| {
+                v.trim_matches('"')
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let has = |id: &str| ids.iter().any(|i| i == id);
+        if has("fedora") || has("rhel") || has("centos") {
+            Platform::Fedora
+        } else if has("debian") || has("ubuntu") {
+            Platform::Debian
+        } else if has("arch") {
+            Platform::Arch
+        } else if has("suse") || has("opensuse") {
+            Platform::OpenSuse
+        } else if has("alpine") {
+            Platform::Alpine
+        } else {
+            Platform::OtherLinux
+        }
+    }
+}
+
+/// Commands (or a pointer) to install `kind` on `platform`.
+pub fn install_steps(kind: RuntimeKind, platform: Platform) -> Vec<String> {
+    match (kind, platform) {
+        (RuntimeKind::Podman, Platform::MacOs) => vec![
+            "brew install podman".into(),
+            "podman machine init".into(),
+            "podman machine start".into(),
+        ],
+        (RuntimeKind::Docker, Platform::MacOs) => {
+            vec!["Install Docker Desktop: https://docs.docker.com/desktop/setup/install/mac-install/".into()]
+        }
+        (RuntimeKind::Podman, Platform::Fedora) => vec!["sudo dnf install podman".into()],
+        (RuntimeKind::Podman, Platform::Debian) => vec!["sudo apt install podman".into()],
+        (RuntimeKind::Podman, Platform::Arch) => vec!["sudo pacman -S podman".into()],
+        (RuntimeKind::Podman, Platform::OpenSuse) => vec!["sudo zypper install podman".into()],
+        (RuntimeKind::Podman, Platform::Alpine) => vec!["sudo apk add podman".into()],
+        (RuntimeKind::Podman, _) => vec!["See https://podman.io/docs/installation".into()],
+        (RuntimeKind::Docker, _) => vec!["See https://docs.docker.com/engine/install/".into()],
+    }
+}
+
+/// Classify a failed `<runtime> info` by its error output.
+pub fn classify(kind: RuntimeKind, stderr: &str) -> Problem {
+    let lower = stderr.to_ascii_lowercase();
+    match kind {
+        RuntimeKind::Podman => {
+            if lower.contains("podman machine init") || lower.contains("no default machine") {
+                Problem::MachineStopped { initialised: false }
+            } else if lower.contains("podman machine start")
+                || (lower.contains("cannot connect to podman") && cfg!(target_os = "macos"))
+            {
+                Problem::MachineStopped { initialised: true }
+            } else if lower.contains("newuidmap")
+                || lower.contains("subuid")
+                || lower.contains("cannot find mappings for user")
+                || lower.contains("insufficient uids or gids")
+            {
+                Problem::NoSubuid
+            } else {
+                Problem::Other {
+                    kind,
+                    stderr: stderr.trim().to_string(),
+                }
+            }
+        }
+        RuntimeKind::Docker => {
+            if lower.contains("permission denied") && lower.contains("docker.sock") {
+                Problem::SocketPermission
+            } else if lower.contains("cannot connect to the docker daemon")
+                || lower.contains("is the docker daemon running")
+            {
+                Problem::DaemonDown
+            } else {
+                Problem::Other {
+                    kind,
+                    stderr: stderr.trim().to_string(),
+                }
+            }
+        }
+    }
+}
+
+impl Problem {
+    /// One-line summary followed by the steps that fix it.
+    pub fn explain(&self, platform: Platform) -> (String, Vec<String>) {
+        match self {
+            Problem::NotInstalled(kind) => (
+                format!("{} is not installed or not on your PATH.", kind.as_str()),
+                install_steps(*kind, platform),
+            ),
+            Problem::MachineStopped { initialised: true } => (
+                "The podman machine is not running.".into(),
+                vec!["podman machine start".into()],
+            ),
+            Problem::MachineStopped { initialised: false } => (
+                "No podman machine has been created yet.".into(),
+                vec!["podman machine init".into(), "podman machine start".into()],
+            ),
+            Problem::DaemonDown => (
+                "The Docker daemon is not running.".into(),
+                if platform == Platform::MacOs {
+                    vec!["Start Docker Desktop".into()]
+                } else {
+                    vec!["sudo systemctl start docker".into()]
+                },
+            ),
+            Problem::SocketPermission => (
+                "Permission denied on the Docker socket.".into(),
+                vec![
+                    "sudo usermod -aG docker $USER".into(),
+                    "Log out and back in for the group change to apply".into(),
+                ],
+            ),
+            Problem::NoSubuid => (
+                "Rootless Podman has no sub-UID/GID range for your user.".into(),
+                vec![
+                    "sudo usermod --add-subuids 100000-165535 --add-subgids 100000-165535 $USER".into(),
+                    "podman system migrate".into(),
+                ],
+            ),
+            Problem::Other { kind, stderr } => (
+                format!("`{} info` failed:\n{}", kind.as_str(), stderr),
+                vec![format!("Run `{} info` to investigate", kind.as_str())],
+            ),
+        }
+    }
+
+    /// The full error text shown to the user.
+    pub fn message(&self, platform: Platform) -> String {
+        let (summary, steps) = self.explain(platform);
+        let mut out = summary;
+        out.push_str("\nTo fix it:");
+        for step in steps {
+            out.push_str("\n  ");
+            out.push_str(&step);
+        }
+        out
+    }
+}
+
+/// The message for "no usable runtime at all": with no preference, both
+/// runtimes are missing and Podman (the preferred one) is suggested.
+pub fn not_found_message(preferred: Option<RuntimeKind>, platform: Platform) -> String {
+    let kind = preferred.unwrap_or(RuntimeKind::Podman);
+    let mut msg = Problem::NotInstalled(kind).message(platform);
+    if preferred.is_none() {
+        msg = format!(
+            "Neither podman nor docker found.\n{}",
+            msg.replacen("podman is not installed or not on your PATH.\n", "", 1)
+        );
+    } else {
+        msg.push_str("\nOr pick the other runtime with --runtime.");
+    }
+    msg.push_str("\nIf it is installed outside your PATH, set AI_POD_RUNTIME to its path (e.g. /opt/homebrew/bin/podman).");
+    msg
+}
+
+/// Check that the engine answers, so launches fail early with a targeted
+/// message. Skipped in dry-run mode and when the Podman API socket already
+/// responded (which proves the service is up).
+pub fn ensure_working(rt: &ContainerRuntime) -> anyhow::Result<()> {
+    if rt.dry_run || crate::podman_api::client(rt).is_some() {
+        return Ok(());
+    }
+    // A field that needs the engine to answer, so the probe is cheap.
+    let format = match rt.kind {
+        RuntimeKind::Podman => "{{.Host.OS}}",
+        RuntimeKind::Docker => "{{.OSType}}",
+    };
+    let output = match Command::new(rt.kind.binary())
+        .args(["info", "--format", format])
+        .stdin(Stdio::null())
+        .output()
+    {
+        Ok(o) => o,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!(Problem::NotInstalled(rt.kind).message(Platform::current()))
+        }
+        Err(e) => anyhow::bail!("Failed to run {} info: {}", rt.cmd(), e),
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    anyhow::bail!(classify(rt.kind, &stderr).message(Platform::current()))
+}
+
+/// Warn (without failing) when rootless Podman has no sub-UID range: single
+/// UID images still run, but most multi-user base images fail to unpack.
+pub fn warn_missing_subuid(rt: &ContainerRuntime) {
+    if rt.kind != RuntimeKind::Podman || rt.dry_run || !cfg!(target_os = "linux") {
+        return;
+    }
+    // SAFETY: `getuid` is always safe to call and cannot fail.
+    let uid = unsafe { libc::getuid() };
+    if uid == 0 {
+        return;
+    }
+    let Ok(subuid) = std::fs::read_to_string("/etc/subuid") else {
+        return;
+    };
+    let user = std::env::var("USER").ok();
+    if crate::runtime::subuid_range_configured(user.as_deref(), uid, Some(&subuid)) {
+        return;
+    }
+    let (summary, steps) = Problem::NoSubuid.explain(Platform::current());
+    eprintln!("{} {}", "warning:".yellow().bold(), summary);
+    for step in steps {
+        eprintln!("  {}", step);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platform_from_os_release_uses_id_like() {
+        assert_eq!(Platform::from_os_release("ID=fedora\n"), Platform::Fedora);
+        assert_eq!(
+            Platform::from_os_release("ID=pop\nID_LIKE=\"ubuntu debian\"\n"),
+            Platform::Debian
+        );
+        assert_eq!(Platform::from_os_release("ID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"\n"), Platform::OpenSuse);
+        assert_eq!(Platform::from_os_release("ID=nixos\n"), Platform::OtherLinux);
+    }
+
+    #[test]
+    fn classify_podman_failures() {
+        assert_eq!(
+            classify(
+                RuntimeKind::Podman,
+                "Error: cannot connect to Podman. Please verify ... run 'podman machine start'"
+            ),
+            Problem::MachineStopped { initialised: true }
+        );
+        assert_eq!(
+            classify(RuntimeKind::Podman, "Error: no default machine found, run `podman machine init`"),
+            Problem::MachineStopped { initialised: false }
+        );
+        assert_eq!(
+            classify(
+                RuntimeKind::Podman,
+                "cannot find mappings for user alice: no subuid ranges found for user \"alice\""
+            ),
+            Problem::NoSubuid
+        );
+        assert!(matches!(classify(RuntimeKind::Podman, "boom"), Problem::Other { .. }));
+    }
+
+    #[test]
+    fn classify_docker_failures() {
+        assert_eq!(
+            classify(
+                RuntimeKind::Docker,
+                "Cannot connect to the Docker daemon at unix:///var/run/docker.sock. Is the docker daemon running?"
+            ),
+            Problem::DaemonDown
+        );
+        assert_eq!(
+            classify(
+                RuntimeKind::Docker,
+                "permission denied while trying to connect to the Docker daemon socket at unix:///var/run/docker.sock"
+            ),
+            Problem::SocketPermission
+        );
+    }
+
+    #[test]
+    fn not_found_message_suggests_platform_install() {
+        let msg = not_found_message(None, Platform::Debian);
+        assert!(msg.starts_with("Neither podman nor docker found."));
+        assert!(msg.contains("sudo apt install podman"));
+        let msg = not_found_message(Some(RuntimeKind::Docker), Platform::MacOs);
+        assert!(msg.contains("docker is not installed"));
+        assert!(msg.contains("Docker Desktop"));
+        assert!(msg.contains("--runtime"));
+        assert!(msg.contains("AI_POD_RUNTIME"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Access log for the shared server: one JSON object per request in
+//! `~/.ai-pod/access.log` (timestamp, peer address, method, path, event,
+//! status, duration), so users on shared machines can audit what has been
+//! hitting the server. Rotated like the event log. API keys and request
+//! bodies are never written.
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::AppState;
+
+pub const ACCESS_LOG_FILE: &str = "access.log";
+
+/// Bodies up to this size are read to name the event; larger ones are
+/// passed through untouched and logged without it.
+const PEEK_LIMIT: usize = 64 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AccessEntry {
+    /// Unix time in seconds.
+    pub ts: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// The notification kind for `/notify_user`, the tool (or JSON-RPC
+    /// method) for `/mcp`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    pub status: u16,
+    pub ms: u64,
+}
+
+pub fn access_log_file(config_dir: &Path) -> PathBuf {
+    config_dir.join(ACCESS_LOG_FILE)
+}
+
+/// What a request body says it is about, for the endpoints where that's
+/// meaningful.
+fn event_of(path: &str, body: &[u8]) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let field = |v: &serde_json::Value| v.as_str().map(str::to_string);
+    match path {
+        "/notify_user" => Some(value.get("event").and_then(field).unwrap_or_else(|| "message".into())),
+        "/mcp" => {
+            let method = value.get("method").and_then(field)?;
+            if method == "tools/call" {
+                value.pointer("/params/name").and_then(field).or(Some(method))
+            } else {
+                Some(method)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Middleware writing one access log entry per request.
+pub async fn log_request(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+
+    let small = request
+        .headers()
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok())
+        .is_some_and(|len| len <= PEEK_LIMIT);
+    let (request, event) = if small && matches!(path.as_str(), "/notify_user" | "/mcp") {
+        let (parts, body) = request.into_parts();
+        match axum::body::to_bytes(body, PEEK_LIMIT).await {
+            Ok(bytes) => {
+                let event = event_of(&path, &bytes);
+                (Request::from_parts(parts, Body::from(bytes)), event)
+            }
+            Err(_) => (Request::from_parts(parts, Body::empty()), None),
+        }
+    } else {
+        (request, None)
+    };
+
+    let response = next.run(request).await;
+    let entry = AccessEntry {
+        ts: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        peer,
+        method,
+        path,
+        event,
+        status: response.status().as_u16(),
+        ms: started.elapsed().as_millis() as u64,
+    };
+    // Best-effort, like the event log: never fail a request over logging.
+    let _ = crate::events::append_json_line(&access_log_file(&state.config_dir), &entry);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_of_names_notifications_and_tools() {
+        assert_eq!(
+            event_of("/notify_user", br#"{"project_id":"p","message":"m","event":"stop"}"#),
+            Some("stop".into())
+        );
+        assert_eq!(event_of("/notify_user", br#"{"project_id":"p","message":"m"}"#), Some("message".into()));
+        assert_eq!(
+            event_of("/mcp", br#"{"method":"tools/call","params":{"name":"run_command"}}"#),
+            Some("run_command".into())
+        );
+        assert_eq!(event_of("/mcp", br#"{"method":"tools/list"}"#), Some("tools/list".into()));
+        assert_eq!(event_of("/mcp", b"not json"), None);
+        assert_eq!(event_of("/commands/run", br#"{"command":"ls"}"#), None);
+    }
+}
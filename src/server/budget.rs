@@ -0,0 +1,332 @@
+//! Budget guard: with `budget.daily_usd` or `budget.session_usd` set,
+//! sessions are launched with Claude Code's OpenTelemetry metrics pointed at
+//! `POST /otel/<project_id>/v1/metrics` on the shared server. Claude Code
+//! reports its own estimate of what each API request cost
+//! (`claude_code.cost.usage`), so no price list is kept here. The server adds
+//! the cost up per session and per local day in `~/.ai-pod/budget.json`.
+//!
+//! When a session's report takes it over a cap, the user gets an `error`
+//! notification and, with `budget.action` set to `pause` or `stop`, the
+//! session's container is paused or stopped. Each session is handled once
+//! per cap (the daily one again the next day).
+//!
+//! Costs are estimates and arrive every 30 seconds, so a session can run a
+//! little past a cap before it is caught. OpenCode doesn't export these
+//! metrics and isn't covered.
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::AppState;
+use crate::config::{BudgetAction, BudgetConfig, GlobalConfig, SessionState};
+
+pub const BUDGET_FILE: &str = "budget.json";
+
+/// The metric Claude Code reports its cost estimate in, in USD.
+const COST_METRIC: &str = "claude_code.cost.usage";
+
+/// Resource attribute carrying the ai-pod session id; Claude Code's own
+/// `session.id` is a different one.
+const SESSION_ATTRIBUTE: &str = "ai_pod.session_id";
+
+/// How often the agent reports, in milliseconds.
+const EXPORT_INTERVAL_MS: u32 = 30_000;
+
+/// Serializes the read-modify-write of the ledger across requests.
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+
+/// `-e` args that make Claude Code report its cost to the shared server,
+/// when a cap is set.
+pub fn env_args(
+    settings: &BudgetConfig,
+    server_url: &str,
+    project_id: &str,
+    session_id: &str,
+    api_key: &str,
+) -> Vec<String> {
+    if !settings.enabled() {
+        return Vec::new();
+    }
+    [
+        ("CLAUDE_CODE_ENABLE_TELEMETRY", "1".to_string()),
+        ("OTEL_METRICS_EXPORTER", "otlp".to_string()),
+        ("OTEL_EXPORTER_OTLP_METRICS_PROTOCOL", "http/json".to_string()),
+        (
+            "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT",
+            format!("{}/otel/{}/v1/metrics", server_url, project_id),
+        ),
+        ("OTEL_EXPORTER_OTLP_METRICS_HEADERS", format!("x-api-key={}", api_key)),
+        ("OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE", "delta".to_string()),
+        ("OTEL_METRIC_EXPORT_INTERVAL", EXPORT_INTERVAL_MS.to_string()),
+        ("OTEL_RESOURCE_ATTRIBUTES", format!("{}={}", SESSION_ATTRIBUTE, session_id)),
+    ]
+    .into_iter()
+    .flat_map(|(name, value)| ["-e".to_string(), format!("{}={}", name, value)])
+    .collect()
+}
+
+/// Spend so far, persisted in `~/.ai-pod/budget.json`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+struct Ledger {
+    /// Local date (`YYYY-MM-DD`) `day_usd` is for.
+    #[serde(default)]
+    day: String,
+    #[serde(default)]
+    day_usd: f64,
+    /// Spend per session id.
+    #[serde(default)]
+    sessions: BTreeMap<String, f64>,
+    /// Sessions already handled for going over the daily cap today.
+    #[serde(default)]
+    over_daily: BTreeSet<String>,
+    /// Sessions already handled for going over the session cap.
+    #[serde(default)]
+    over_session: BTreeSet<String>,
+}
+
+/// A cap a session went over.
+#[derive(Debug, PartialEq)]
+enum Overrun {
+    Daily { spent: f64, cap: f64 },
+    Session { spent: f64, cap: f64 },
+}
+
+impl Ledger {
+    /// Add `usd` to `session_id`'s spend on `today`, and return the caps it
+    /// takes the session over for the first time.
+    fn charge(
+        &mut self,
+        settings: &BudgetConfig,
+        today: &str,
+        session_id: &str,
+        usd: f64,
+        live: impl Fn(&str) -> bool,
+    ) -> Vec<Overrun> {
+        if self.day != today {
+            self.day = today.to_string();
+            self.day_usd = 0.0;
+            self.over_daily.clear();
+            // Sessions that have ended don't need their totals any more.
+            self.sessions.retain(|id, _| live(id));
+            self.over_session.retain(|id| live(id));
+        }
+        self.day_usd += usd;
+        let spent = self.sessions.entry(session_id.to_string()).or_default();
+        *spent += usd;
+        let spent = *spent;
+
+        let mut out = Vec::new();
+        if let Some(cap) = settings.session_usd
+            && spent >= cap
+            && self.over_session.insert(session_id.to_string())
+        {
+            out.push(Overrun::Session { spent, cap });
+        }
+        if let Some(cap) = settings.daily_usd
+            && self.day_usd >= cap
+            && self.over_daily.insert(session_id.to_string())
+        {
+            out.push(Overrun::Daily {
+                spent: self.day_usd,
+                cap,
+            });
+        }
+        out
+    }
+}
+
+fn ledger_file(config_dir: &Path) -> PathBuf {
+    config_dir.join(BUDGET_FILE)
+}
+
+/// Local date as `YYYY-MM-DD`.
+fn today() -> String {
+    crate::local_time::date(crate::local_time::now())
+}
+
+/// Cost reported in an OTLP/JSON metrics export, per ai-pod session id.
+/// Only delta data points are counted; the session sets delta temporality.
+fn session_costs(body: &Value) -> BTreeMap<String, f64> {
+    let mut out = BTreeMap::new();
+    let list = |v: &Value, key: &str| v[key].as_array().cloned().unwrap_or_default();
+    for resource in list(body, "resourceMetrics") {
+        let session = list(&resource["resource"], "attributes")
+            .iter()
+            .find(|a| a["key"] == SESSION_ATTRIBUTE)
+            .and_then(|a| a["value"]["stringValue"].as_str().map(str::to_string));
+        let Some(session) = session else {
+            continue;
+        };
+        for scope in list(&resource, "scopeMetrics") {
+            for metric in list(&scope, "metrics") {
+                let sum = &metric["sum"];
+                // 1 is AGGREGATION_TEMPORALITY_DELTA.
+                if metric["name"] != COST_METRIC || sum["aggregationTemporality"].as_u64() != Some(1) {
+                    continue;
+                }
+                let cost: f64 = list(sum, "dataPoints")
+                    .iter()
+                    .filter_map(|p| p["asDouble"].as_f64())
+                    .filter(|c| c.is_finite() && *c > 0.0)
+                    .sum();
+                *out.entry(session.clone()).or_default() += cost;
+            }
+        }
+    }
+    out
+}
+
+/// Notify about `overrun` and apply `budget.action` to the session.
+async fn enforce(state: &AppState, workspace: &Path, session_id: &str, overrun: &Overrun, action: BudgetAction) {
+    let (what, spent, cap) = match overrun {
+        Overrun::Daily { spent, cap } => ("today's", spent, cap),
+        Overrun::Session { spent, cap } => ("this session's", spent, cap),
+    };
+    let container = crate::workspace::container_name_for(workspace, session_id);
+    let runtime = SessionState::load_from_dir(&state.config_dir, session_id)
+        .map(|s| crate::runtime::ContainerRuntime {
+            kind: s.runtime,
+            dry_run: state.runtime.dry_run,
+        })
+        .unwrap_or_else(|| state.runtime.clone());
+    let verb = match action {
+        BudgetAction::Warn => None,
+        BudgetAction::Pause => Some("pause"),
+        BudgetAction::Stop => Some("stop"),
+    };
+    let applied = match verb {
+        Some(verb) => runtime
+            .async_command()
+            .args([verb, &container])
+            .output()
+            .await
+            .is_ok_and(|o| o.status.success()),
+        None => false,
+    };
+    let outcome = match (action, applied) {
+        (BudgetAction::Pause, true) => format!(
+            "; the session is paused (`{} unpause {}` resumes it)",
+            runtime.kind.binary(),
+            container
+        ),
+        (BudgetAction::Stop, true) => "; the session was stopped".to_string(),
+        (BudgetAction::Warn, _) => String::new(),
+        (_, false) => "; the session could not be paused or stopped".to_string(),
+    };
+    let message = format!(
+        "Estimated spend is ${:.2}, over {} budget of ${:.2}{}",
+        spent, what, cap, outcome
+    );
+    eprintln!("[budget] {}: {}", container, message);
+    super::notify::notify_user(&state.config_dir, workspace, Some(session_id), "error", &message, None);
+}
+
+/// `POST /otel/{project_id}/v1/metrics`: cost reports from sessions
+/// launched with [`env_args`].
+pub async fn otel_metrics_handler(
+    State(state): State<AppState>,
+    AxumPath(project_id): AxumPath<String>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> impl IntoResponse {
+    let provided_key = super::rest::extract_api_key(&headers).to_string();
+    let workspace = match super::rest::authenticate(&state, &project_id, &provided_key).await {
+        Ok(w) => w,
+        Err((status, msg)) => return (status, msg.to_string()).into_response(),
+    };
+    let settings = GlobalConfig::load_from_dir(&state.config_dir).budget;
+    if !settings.enabled() {
+        return Json(serde_json::json!({})).into_response();
+    }
+    let costs = session_costs(&body);
+    let mut overruns = Vec::new();
+    {
+        let _guard = LEDGER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let path = ledger_file(&state.config_dir);
+        let mut ledger: Ledger = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        let today = today();
+        let config_dir = state.config_dir.clone();
+        let live = |id: &str| SessionState::load_from_dir(&config_dir, id).is_some();
+        for (session_id, usd) in costs {
+            // The id names the container below; keep it to what ai-pod generates.
+            if session_id.is_empty() || !session_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+                continue;
+            }
+            for overrun in ledger.charge(&settings, &today, &session_id, usd, live) {
+                overruns.push((session_id.clone(), overrun));
+            }
+        }
+        if let Ok(json) = serde_json::to_string_pretty(&ledger) {
+            let _ = std::fs::write(&path, json);
+        }
+    }
+    let action = settings.action.unwrap_or_default();
+    for (session_id, overrun) in overruns {
+        enforce(&state, &workspace, &session_id, &overrun, action).await;
+    }
+    (StatusCode::OK, Json(serde_json::json!({}))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn costs_are_added_up_per_session_and_day_and_caps_trip_once() {
+        let export = serde_json::json!({"resourceMetrics": [{
+            "resource": {"attributes": [
+                {"key": "service.name", "value": {"stringValue": "claude-code"}},
+                {"key": "ai_pod.session_id", "value": {"stringValue": "s1"}},
+            ]},
+            "scopeMetrics": [{"metrics": [
+                {"name": "claude_code.cost.usage", "unit": "USD", "sum": {"aggregationTemporality": 1, "dataPoints": [
+                    {"asDouble": 0.25, "attributes": [{"key": "model", "value": {"stringValue": "a"}}]},
+                    {"asDouble": 0.5},
+                ]}},
+                {"name": "claude_code.token.usage", "sum": {"aggregationTemporality": 1, "dataPoints": [{"asDouble": 900.0}]}},
+                {"name": "claude_code.cost.usage", "sum": {"aggregationTemporality": 2, "dataPoints": [{"asDouble": 7.0}]}},
+            ]}],
+        }, {
+            "resource": {"attributes": []},
+            "scopeMetrics": [{"metrics": [{"name": "claude_code.cost.usage", "sum": {"aggregationTemporality": 1, "dataPoints": [{"asDouble": 3.0}]}}]}],
+        }]});
+        assert_eq!(session_costs(&export), BTreeMap::from([("s1".to_string(), 0.75)]));
+
+        let settings = BudgetConfig {
+            daily_usd: Some(2.0),
+            session_usd: Some(1.0),
+            action: None,
+        };
+        let live = |id: &str| id != "gone";
+        let mut ledger = Ledger::default();
+        assert!(ledger.charge(&settings, "2026-01-01", "gone", 0.5, live).is_empty());
+        assert!(ledger.charge(&settings, "2026-01-01", "s1", 0.75, live).is_empty());
+        assert_eq!(
+            ledger.charge(&settings, "2026-01-01", "s1", 0.25, live),
+            vec![Overrun::Session { spent: 1.0, cap: 1.0 }]
+        );
+        assert!(ledger.charge(&settings, "2026-01-01", "s1", 0.25, live).is_empty());
+        assert_eq!(
+            ledger.charge(&settings, "2026-01-01", "s2", 0.5, live),
+            vec![Overrun::Daily { spent: 2.25, cap: 2.0 }]
+        );
+
+        // A new day starts the daily total over; the session cap still holds.
+        assert!(ledger.charge(&settings, "2026-01-02", "s1", 0.25, live).is_empty());
+        assert_eq!(ledger.day_usd, 0.25);
+        assert_eq!(ledger.sessions["s1"], 1.5);
+        assert!(!ledger.sessions.contains_key("gone"));
+    }
+}
@@ -0,0 +1,751 @@
+//! Egress proxy: with `network.audit` enabled, containers get
+//! `HTTP_PROXY`/`HTTPS_PROXY` pointing at this proxy, which runs alongside the
+//! shared server and records every destination they contact — host, port and
+//! bytes each way, per session — in `~/.ai-pod/egress.log` (one JSON object
+//! per connection, rotated like the event log). `ai-pod egress` summarizes it.
+//! `network.bandwidth` and `network.max_connections` are enforced here too,
+//! per session, so a dependency install can't saturate a metered link.
+//!
+//! The proxy speaks plain HTTP forwarding and `CONNECT` tunnels. HTTPS is
+//! tunnelled, never decrypted, so only the host name is known. Clients
+//! authenticate with `<project_id>.<session_id>:<api key>` as proxy
+//! credentials, naming a running session of that project; anything else
+//! gets a 407. Tools that ignore the proxy
+//! variables connect directly and aren't recorded.
+//!
+//! The proxy runs on the host, so it refuses destinations that resolve to
+//! the host's loopback, link-local or cloud metadata addresses, which the
+//! container couldn't reach itself, unless `network.local_destinations`
+//! lists them.
+
+use anyhow::Result;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::AppState;
+
+pub const EGRESS_PORT: u16 = 7823;
+pub const EGRESS_LOG_FILE: &str = "egress.log";
+
+/// Request heads larger than this are refused.
+const MAX_HEAD: usize = 16 * 1024;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Bytes relayed per read when a bandwidth cap applies.
+const THROTTLED_CHUNK: usize = 16 * 1024;
+
+/// Parse a rate such as `8mbit`, `500kbit`, `2MB` or `1.5MB/s` into bytes per
+/// second. Units follow `tc`: `bit`/`kbit`/`mbit`/`gbit` are bits,
+/// `B`/`KB`/`MB`/`GB` bytes, all decimal; a bare number is bytes.
+pub(crate) fn parse_rate(rate: &str) -> Result<u64, String> {
+    let s = rate.trim();
+    let s = s.strip_suffix("/s").unwrap_or(s);
+    let split = s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a rate like 8mbit or 500KB", rate))?;
+    let factor = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "bit" => 1.0 / 8.0,
+        "kbit" => 1e3 / 8.0,
+        "mbit" => 1e6 / 8.0,
+        "gbit" => 1e9 / 8.0,
+        other => return Err(format!("unknown rate unit '{}' (expected bit, kbit, mbit, gbit, B, KB, MB or GB)", other)),
+    };
+    let bytes = (number * factor) as u64;
+    if bytes == 0 {
+        return Err(format!("'{}' is below one byte per second", rate));
+    }
+    Ok(bytes)
+}
+
+/// Token bucket shared by a session's connections. It may go into debt by
+/// one chunk; the next caller then waits for it to be paid off.
+pub struct Throttle {
+    rate: u64,
+    state: std::sync::Mutex<(f64, Instant)>,
+}
+
+impl Throttle {
+    fn new(rate: u64) -> Self {
+        Throttle {
+            rate,
+            state: std::sync::Mutex::new((rate as f64, Instant::now())),
+        }
+    }
+
+    /// How long to wait before sending `n` bytes.
+    fn reserve(&self, n: usize) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (tokens, last) = &mut *state;
+        let now = Instant::now();
+        // Allow bursts of up to one second's worth.
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.rate as f64).min(self.rate as f64);
+        *last = now;
+        *tokens -= n as f64;
+        if *tokens < 0.0 {
+            Duration::from_secs_f64(-*tokens / self.rate as f64)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// The limits in force for one session, built from `network` when its
+/// first connection arrives and rebuilt if the config changes.
+pub struct SessionLimits {
+    bandwidth: Option<u64>,
+    max_connections: Option<u32>,
+    throttle: Option<Throttle>,
+    connections: Option<Arc<Semaphore>>,
+}
+
+impl SessionLimits {
+    fn new(bandwidth: Option<u64>, max_connections: Option<u32>) -> Self {
+        SessionLimits {
+            bandwidth,
+            max_connections,
+            throttle: bandwidth.map(Throttle::new),
+            connections: max_connections.map(|n| Arc::new(Semaphore::new(n as usize))),
+        }
+    }
+}
+
+async fn session_limits(state: &AppState, session_id: &str, network: &crate::config::NetworkConfig) -> Arc<SessionLimits> {
+    let bandwidth = network.bandwidth.as_deref().and_then(|r| parse_rate(r).ok());
+    let max_connections = network.max_connections.filter(|n| *n > 0);
+    let mut limits = state.egress_limits.lock().await;
+    match limits.get(session_id) {
+        Some(l) if l.bandwidth == bandwidth && l.max_connections == max_connections => l.clone(),
+        _ => {
+            // Sessions that ended have no record left.
+            limits.retain(|id, _| crate::config::session_state_path(&state.config_dir, id).exists());
+            let l = Arc::new(SessionLimits::new(bandwidth, max_connections));
+            limits.insert(session_id.to_string(), l.clone());
+            l
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EgressEntry {
+    /// Unix time in seconds, when the connection closed.
+    pub ts: u64,
+    pub project_id: String,
+    pub session_id: String,
+    pub host: String,
+    pub port: u16,
+    /// Bytes from the container to the destination.
+    pub sent: u64,
+    /// Bytes from the destination to the container.
+    pub received: u64,
+}
+
+pub fn egress_log_file(config_dir: &Path) -> PathBuf {
+    config_dir.join(EGRESS_LOG_FILE)
+}
+
+/// Entries in the current log, oldest first; unreadable lines are skipped.
+pub fn load(config_dir: &Path) -> Vec<EgressEntry> {
+    std::fs::read_to_string(egress_log_file(config_dir))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+/// The parts of a proxy request head this proxy cares about.
+#[derive(Debug, PartialEq, Eq)]
+struct Head {
+    method: String,
+    target: String,
+    version: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Head {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn parse_head(raw: &[u8]) -> Option<Head> {
+    let text = std::str::from_utf8(raw).ok()?;
+    let mut lines = text.split("\r\n");
+    let mut request = lines.next()?.split(' ');
+    let (method, target, version) = (request.next()?, request.next()?, request.next()?);
+    let headers = lines
+        .filter(|l| !l.is_empty())
+        .filter_map(|l| {
+            let (k, v) = l.split_once(':')?;
+            Some((k.trim().to_string(), v.trim().to_string()))
+        })
+        .collect();
+    Some(Head {
+        method: method.to_string(),
+        target: target.to_string(),
+        version: version.to_string(),
+        headers,
+    })
+}
+
+/// `(project_id, session_id, api_key)` from a `Proxy-Authorization: Basic`
+/// header.
+fn credentials(head: &Head) -> Option<(String, String, String)> {
+    let encoded = head.header("proxy-authorization")?.strip_prefix("Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (user, key) = decoded.split_once(':')?;
+    let (project, session) = user.split_once('.')?;
+    Some((project.to_string(), session.to_string(), key.to_string()))
+}
+
+/// Split `host:port` (IPv6 hosts in brackets), defaulting the port.
+fn host_port(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((h, p)) if !p.contains(']') => (h, p.parse().ok()?),
+        _ => (authority, default_port),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return None;
+    }
+    Some((host.to_ascii_lowercase(), port))
+}
+
+/// The destination of a forwarded `http://` request and the head to send
+/// it: origin-form target, proxy headers dropped, and `Connection: close` so
+/// a kept-alive client can't reuse the connection for another host.
+fn forward_request(head: &Head) -> Option<(String, u16, Vec<u8>)> {
+    let rest = head.target.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = host_port(authority, 80)?;
+    let mut out = format!("{} {} {}\r\n", head.method, path, head.version);
+    for (k, v) in &head.headers {
+        let drop = ["proxy-authorization", "proxy-connection", "connection", "keep-alive"]
+            .iter()
+            .any(|h| k.eq_ignore_ascii_case(h));
+        if !drop {
+            out.push_str(&format!("{}: {}\r\n", k, v));
+        }
+    }
+    out.push_str("Connection: close\r\n\r\n");
+    Some((host, port, out.into_bytes()))
+}
+
+/// Addresses only the host itself should reach: loopback, unspecified,
+/// link-local (which holds most clouds' metadata service) and the other
+/// metadata addresses.
+fn host_only(ip: IpAddr) -> bool {
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    };
+    match ip {
+        IpAddr::V4(v4) => {
+            v4.is_loopback() || v4.is_unspecified() || v4.is_link_local() || v4 == Ipv4Addr::new(100, 100, 100, 200)
+        }
+        IpAddr::V6(v6) => {
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_unicast_link_local()
+                || v6 == Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254)
+        }
+    }
+}
+
+/// Resolve the destination. Host-only addresses are dropped unless
+/// `allowed` names the host or `host:port`; `None` when nothing is left.
+async fn resolve(host: &str, port: u16, allowed: &[String]) -> Option<Vec<SocketAddr>> {
+    let listed = allowed.iter().any(|a| {
+        let a = a.trim().to_ascii_lowercase();
+        a == host || a == format!("{}:{}", host, port)
+    });
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .ok()?
+        .filter(|a| listed || !host_only(a.ip()))
+        .collect();
+    (!addrs.is_empty()).then_some(addrs)
+}
+
+/// Read until the end of the request head. Returns the head and whatever
+/// followed it in the same reads.
+async fn read_head(stream: &mut TcpStream) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buf.split_off(end + 4);
+            return Some((buf, rest));
+        }
+        if buf.len() > MAX_HEAD {
+            return None;
+        }
+    }
+}
+
+async fn reply(stream: &mut TcpStream, status: &str, extra: &str) {
+    let _ = stream
+        .write_all(format!("HTTP/1.1 {}\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n", status, extra).as_bytes())
+        .await;
+}
+
+async fn handle(mut client: TcpStream, state: AppState) {
+    let Some((raw, early)) = read_head(&mut client).await else {
+        return;
+    };
+    let Some(head) = parse_head(&raw) else {
+        reply(&mut client, "400 Bad Request", "").await;
+        return;
+    };
+    // Limits are per session, so the session must be one of the project's:
+    // a made-up id per connection would get a fresh allowance each time.
+    let authorized = match credentials(&head) {
+        Some((project, session, key)) => super::rest::authenticate(&state, &project, &key)
+            .await
+            .ok()
+            .filter(|ws| crate::config::SessionState::load_for(&state.config_dir, &session, ws).is_some())
+            .map(|_| (project, session)),
+        None => None,
+    };
+    let Some((project_id, session_id)) = authorized else {
+        reply(
+            &mut client,
+            "407 Proxy Authentication Required",
+            "Proxy-Authenticate: Basic realm=\"ai-pod\"\r\n",
+        )
+        .await;
+        return;
+    };
+
+    let network = crate::config::GlobalConfig::load_from_dir(&state.config_dir).network;
+    let limits = session_limits(&state, &session_id, &network).await;
+    // Held until the connection closes; over the limit, wait for a slot.
+    let _slot: Option<OwnedSemaphorePermit> = match &limits.connections {
+        Some(sem) => sem.clone().acquire_owned().await.ok(),
+        None => None,
+    };
+
+    let (host, port, preamble) = if head.method.eq_ignore_ascii_case("CONNECT") {
+        match host_port(&head.target, 443) {
+            Some((host, port)) => (host, port, Vec::new()),
+            None => return reply(&mut client, "400 Bad Request", "").await,
+        }
+    } else {
+        match forward_request(&head) {
+            Some(dest) => dest,
+            None => return reply(&mut client, "400 Bad Request", "").await,
+        }
+    };
+
+    // Connect to the addresses checked here, not a second lookup's.
+    let Some(addrs) = resolve(&host, port, &network.local_destinations).await else {
+        reply(&mut client, "403 Forbidden", "").await;
+        return;
+    };
+    let upstream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&addrs[..])).await;
+    let Ok(Ok(mut upstream)) = upstream else {
+        reply(&mut client, "502 Bad Gateway", "").await;
+        return;
+    };
+    if preamble.is_empty() {
+        if client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").await.is_err() {
+            return;
+        }
+    } else if upstream.write_all(&preamble).await.is_err() {
+        return;
+    }
+    let mut sent = preamble.len() as u64;
+    if !early.is_empty() && upstream.write_all(&early).await.is_ok() {
+        sent += early.len() as u64;
+    }
+    let (up, down) = match &limits.throttle {
+        Some(throttle) => {
+            let (mut client_read, mut client_write) = client.into_split();
+            let (mut upstream_read, mut upstream_write) = upstream.into_split();
+            tokio::join!(
+                pump(&mut client_read, &mut upstream_write, throttle),
+                pump(&mut upstream_read, &mut client_write, throttle)
+            )
+        }
+        None => tokio::io::copy_bidirectional(&mut client, &mut upstream)
+            .await
+            .unwrap_or((0, 0)),
+    };
+    if !network.audit {
+        return;
+    }
+
+    let entry = EgressEntry {
+        ts: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        project_id,
+        session_id,
+        host,
+        port,
+        sent: sent + up,
+        received: down,
+    };
+    let _ = crate::events::append_json_line(&egress_log_file(&state.config_dir), &entry);
+}
+
+/// Copy `from` to `to` at the throttle's pace until either side closes,
+/// then close `to` for writing. Returns the bytes copied.
+async fn pump<R, W>(from: &mut R, to: &mut W, throttle: &Throttle) -> u64
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; THROTTLED_CHUNK];
+    let mut total = 0;
+    loop {
+        let n = match from.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        tokio::time::sleep(throttle.reserve(n)).await;
+        if to.write_all(&buf[..n]).await.is_err() {
+            break;
+        }
+        total += n as u64;
+    }
+    let _ = to.shutdown().await;
+    total
+}
+
+/// Accept proxy connections on `listener` until `stop` flips to true.
+pub async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    mut stop: tokio::sync::watch::Receiver<bool>,
+) -> Result<()> {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else { continue };
+                tokio::spawn(handle(stream, state.clone()));
+            }
+            _ = stop.wait_for(|stop| *stop) => return Ok(()),
+        }
+    }
+}
+
+/// Traffic to one destination, summed over connections.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Destination {
+    pub connections: u64,
+    pub sent: u64,
+    pub received: u64,
+}
+
+/// Sum `entries` per `host:port`.
+pub fn summarize<'a>(entries: impl IntoIterator<Item = &'a EgressEntry>) -> BTreeMap<String, Destination> {
+    let mut out: BTreeMap<String, Destination> = BTreeMap::new();
+    for e in entries {
+        let dest = out.entry(format!("{}:{}", e.host, e.port)).or_default();
+        dest.connections += 1;
+        dest.sent += e.sent;
+        dest.received += e.received;
+    }
+    out
+}
+
+/// `ai-pod egress`: destinations contacted from `workspace`'s containers —
+/// in one session (`session`, or the most recent one), or with `all` across
+/// every session still in the log.
+pub fn print_report(config_dir: &Path, workspace: &Path, session: Option<&str>, all: bool) -> Result<()> {
+    use colored::Colorize;
+
+    let project_id = crate::workspace::workspace_hash(workspace);
+    let entries: Vec<EgressEntry> = load(config_dir)
+        .into_iter()
+        .filter(|e| e.project_id == project_id)
+        .collect();
+    let session = match session {
+        Some(s) => Some(s.to_string()),
+        None if all => None,
+        None => entries.last().map(|e| e.session_id.clone()),
+    };
+    let shown: Vec<&EgressEntry> = entries
+        .iter()
+        .filter(|e| session.as_ref().is_none_or(|s| &e.session_id == s))
+        .collect();
+    if shown.is_empty() {
+        println!("No outbound traffic recorded.");
+        if !crate::config::GlobalConfig::load_from_dir(config_dir).network.audit {
+            println!(
+                "{} auditing is off; enable it with `ai-pod config set network.audit true` and relaunch.",
+                "Note:".yellow().bold()
+            );
+        }
+        return Ok(());
+    }
+
+    match &session {
+        Some(s) => println!("{} {}", "Session:".blue().bold(), s),
+        None => println!("{} all sessions", "Sessions:".blue().bold()),
+    }
+    println!();
+    let mut rows: Vec<(String, Destination)> = summarize(shown).into_iter().collect();
+    rows.sort_by_key(|(_, d)| std::cmp::Reverse(d.sent + d.received));
+    println!(
+        "{:<40}  {:>6}  {:>10}  {:>10}",
+        "DESTINATION".bold(),
+        "CONNS".bold(),
+        "SENT".bold(),
+        "RECEIVED".bold()
+    );
+    for (dest, d) in rows {
+        println!(
+            "{:<40}  {:>6}  {:>10}  {:>10}",
+            dest,
+            d.connections,
+            crate::du::format_bytes(d.sent),
+            crate::du::format_bytes(d.received)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{ContainerRuntime, RuntimeKind};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    fn head(raw: &str) -> Head {
+        parse_head(raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parse_head_reads_request_line_and_headers() {
+        let h = head("CONNECT api.anthropic.com:443 HTTP/1.1\r\nHost: api.anthropic.com:443\r\nProxy-Authorization: Basic eA==\r\n\r\n");
+        assert_eq!(h.method, "CONNECT");
+        assert_eq!(h.target, "api.anthropic.com:443");
+        assert_eq!(h.header("proxy-authorization"), Some("Basic eA=="));
+        assert!(parse_head(b"garbage").is_none());
+    }
+
+    #[test]
+    fn credentials_split_project_session_and_key() {
+        let token = base64::engine::general_purpose::STANDARD.encode("abc123.s1:secret:with:colons");
+        let h = head(&format!("CONNECT x:443 HTTP/1.1\r\nProxy-Authorization: Basic {}\r\n\r\n", token));
+        assert_eq!(
+            credentials(&h),
+            Some(("abc123".into(), "s1".into(), "secret:with:colons".into()))
+        );
+        assert_eq!(credentials(&head("CONNECT x:443 HTTP/1.1\r\n\r\n")), None);
+    }
+
+    #[test]
+    fn host_port_handles_defaults_and_ipv6() {
+        assert_eq!(host_port("Example.com:8443", 443), Some(("example.com".into(), 8443)));
+        assert_eq!(host_port("example.com", 80), Some(("example.com".into(), 80)));
+        assert_eq!(host_port("[::1]:8080", 80), Some(("::1".into(), 8080)));
+        assert_eq!(host_port("[::1]", 80), Some(("::1".into(), 80)));
+        assert_eq!(host_port("example.com:http", 80), None);
+    }
+
+    #[test]
+    fn forward_request_rewrites_to_origin_form() {
+        let h = head(
+            "GET http://example.com:8080/a?b=1 HTTP/1.1\r\nHost: example.com:8080\r\nProxy-Authorization: Basic eA==\r\nProxy-Connection: keep-alive\r\nAccept: */*\r\n\r\n",
+        );
+        let (host, port, out) = forward_request(&h).unwrap();
+        assert_eq!((host.as_str(), port), ("example.com", 8080));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "GET /a?b=1 HTTP/1.1\r\nHost: example.com:8080\r\nAccept: */*\r\nConnection: close\r\n\r\n"
+        );
+        assert!(forward_request(&head("GET https://x/ HTTP/1.1\r\n\r\n")).is_none());
+    }
+
+    #[test]
+    fn host_only_covers_loopback_link_local_and_metadata() {
+        for ip in ["127.0.0.1", "::1", "0.0.0.0", "169.254.169.254", "fe80::1", "::ffff:127.0.0.1", "fd00:ec2::254"] {
+            assert!(host_only(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["10.0.0.5", "93.184.216.34", "2606:4700::1111"] {
+            assert!(!host_only(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn parse_rate_understands_tc_units() {
+        assert_eq!(parse_rate("8mbit"), Ok(1_000_000));
+        assert_eq!(parse_rate("500KB"), Ok(500_000));
+        assert_eq!(parse_rate("1.5MB/s"), Ok(1_500_000));
+        assert_eq!(parse_rate("2048"), Ok(2048));
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("10 parsecs").is_err());
+        assert!(parse_rate("1bit").is_err());
+    }
+
+    #[test]
+    fn throttle_allows_a_burst_then_paces() {
+        let throttle = Throttle::new(1000);
+        assert_eq!(throttle.reserve(1000), Duration::ZERO);
+        let wait = throttle.reserve(500);
+        assert!(wait > Duration::from_millis(450) && wait <= Duration::from_millis(500), "{:?}", wait);
+    }
+
+    #[test]
+    fn summarize_sums_per_destination() {
+        let entry = |host: &str, sent, received| EgressEntry {
+            ts: 0,
+            project_id: "p".into(),
+            session_id: "s".into(),
+            host: host.into(),
+            port: 443,
+            sent,
+            received,
+        };
+        let entries = [entry("a.com", 10, 100), entry("b.com", 1, 2), entry("a.com", 5, 50)];
+        let summary = summarize(&entries);
+        assert_eq!(
+            summary["a.com:443"],
+            Destination {
+                connections: 2,
+                sent: 15,
+                received: 150
+            }
+        );
+        assert_eq!(summary.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn proxy_tunnels_and_records_authenticated_connections() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = crate::config::test_config(&dir);
+        std::fs::write(
+            config.config_dir.join("config.json"),
+            r#"{"network": {"audit": true, "bandwidth": "1MB", "max_connections": 2, "local_destinations": ["127.0.0.1"]}}"#,
+        )
+        .unwrap();
+        let mut projects = HashMap::new();
+        projects.insert(
+            "proj".to_string(),
+            super::super::ProjectInfo {
+                workspace: dir.path().to_path_buf(),
+                api_key: "key".into(),
+            },
+        );
+        let state = AppState {
+            projects: Arc::new(Mutex::new(projects)),
+            config_dir: config.config_dir.clone(),
+            approval_lock: Arc::new(Mutex::new(())),
+            commands: Arc::new(Mutex::new(HashMap::new())),
+            runtime: ContainerRuntime {
+                kind: RuntimeKind::Podman,
+                dry_run: true,
+            },
+            keep_alive_until: Arc::new(Mutex::new(std::time::Instant::now())),
+            egress_limits: Arc::new(Mutex::new(HashMap::new())),
+        };
+        crate::config::SessionState {
+            runtime: RuntimeKind::Podman,
+            tmux: None,
+            title: None,
+            workspace: Some(dir.path().to_path_buf()),
+        }
+        .save(&config, "s1")
+        .unwrap();
+
+        // An upstream that answers "pong" to anything.
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_port = upstream.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut s, _) = upstream.accept().await.unwrap();
+            let mut buf = [0u8; 4];
+            s.read_exact(&mut buf).await.unwrap();
+            s.write_all(b"pong").await.unwrap();
+        });
+
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_port = proxy.local_addr().unwrap().port();
+        let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+        tokio::spawn(serve(proxy, state, stop_rx));
+
+        // Wrong key: 407.
+        let mut c = TcpStream::connect(("127.0.0.1", proxy_port)).await.unwrap();
+        let bad = base64::engine::general_purpose::STANDARD.encode("proj.s1:nope");
+        c.write_all(format!("CONNECT 127.0.0.1:{} HTTP/1.1\r\nProxy-Authorization: Basic {}\r\n\r\n", upstream_port, bad).as_bytes())
+            .await
+            .unwrap();
+        let mut resp = String::new();
+        c.read_to_string(&mut resp).await.unwrap();
+        assert!(resp.starts_with("HTTP/1.1 407"));
+
+        // Right key, but a session the project doesn't have: 407.
+        let mut c = TcpStream::connect(("127.0.0.1", proxy_port)).await.unwrap();
+        let unknown = base64::engine::general_purpose::STANDARD.encode("proj.s2:key");
+        c.write_all(format!("CONNECT 127.0.0.1:{} HTTP/1.1\r\nProxy-Authorization: Basic {}\r\n\r\n", upstream_port, unknown).as_bytes())
+            .await
+            .unwrap();
+        let mut resp = String::new();
+        c.read_to_string(&mut resp).await.unwrap();
+        assert!(resp.starts_with("HTTP/1.1 407"));
+
+        // The host's loopback only when listed.
+        let good = base64::engine::general_purpose::STANDARD.encode("proj.s1:key");
+        let mut c = TcpStream::connect(("127.0.0.1", proxy_port)).await.unwrap();
+        c.write_all(format!("CONNECT localhost:{} HTTP/1.1\r\nProxy-Authorization: Basic {}\r\n\r\n", upstream_port, good).as_bytes())
+            .await
+            .unwrap();
+        let mut resp = String::new();
+        c.read_to_string(&mut resp).await.unwrap();
+        assert!(resp.starts_with("HTTP/1.1 403"), "{}", resp);
+
+        let mut c = TcpStream::connect(("127.0.0.1", proxy_port)).await.unwrap();
+        c.write_all(format!("CONNECT 127.0.0.1:{} HTTP/1.1\r\nProxy-Authorization: Basic {}\r\n\r\nping", upstream_port, good).as_bytes())
+            .await
+            .unwrap();
+        let mut resp = String::new();
+        c.read_to_string(&mut resp).await.unwrap();
+        assert_eq!(resp, "HTTP/1.1 200 Connection Established\r\n\r\npong");
+        drop(c);
+
+        let mut entries = Vec::new();
+        for _ in 0..50 {
+            entries = load(&config.config_dir);
+            if !entries.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let _ = stop_tx.send(true);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].session_id, "s1");
+        assert_eq!(entries[0].host, "127.0.0.1");
+        assert_eq!((entries[0].sent, entries[0].received), (4, 4));
+    }
+}
@@ -7,16 +7,31 @@ use std::fs::OpenOptions;
 use std::io::Write;
 use std::os::unix::fs::OpenOptionsExt;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 use crate::config::AppConfig;
 use crate::workspace::workspace_hash;
 
 pub const MCP_PORT: u16 = 7822;
 
+/// Port of the server instance this process uses, set by [`select_port`].
+static PORT: OnceLock<u16> = OnceLock::new();
+
+/// Consecutive crashes after which the supervisor gives up.
+const MAX_RESTARTS: u32 = 5;
+
+/// A server process that ran at least this long resets the crash count.
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// How long `ensure_shared_server` waits for a new server to report its port.
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Shared server state stored in ~/.ai-pod/server.json
 #[derive(Serialize, Deserialize, Default)]
-struct ServerState {
+pub struct ServerState {
+    /// The supervisor (`ai-pod server run`), which owns the server process.
     pub pid: Option<u32>,
     /// Path of the executable that was spawned for this server.
     /// Used to verify (on Linux via /proc/<pid>/exe) that the PID we
@@ -26,6 +41,69 @@ struct ServerState {
     /// prior versions.
     #[serde(default)]
     pub exe_path: Option<String>,
+    /// The `ai-pod serve` process currently listening; changes on restart.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_pid: Option<u32>,
+    /// Port the server bound, written once it is listening.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Kernel boot id at start (Linux). A state file from before a reboot
+    /// is stale no matter which process now has its PID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boot_id: Option<String>,
+    /// Times the supervisor restarted a crashed server process.
+    #[serde(default)]
+    pub restarts: u32,
+}
+
+impl ServerState {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let tmp = path.with_extension("tmp");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)
+            .context("Failed to write server state")?;
+        file.write_all(json.as_bytes())
+            .context("Failed to write server state contents")?;
+        std::fs::rename(&tmp, path).context("Failed to rename server state")?;
+        Ok(())
+    }
+
+    /// Whether the recorded supervisor is still running: same boot, PID
+    /// alive and still our binary.
+    pub fn is_alive(&self) -> bool {
+        if let (Some(recorded), Some(current)) = (&self.boot_id, current_boot_id())
+            && *recorded != current
+        {
+            return false;
+        }
+        self.pid
+            .is_some_and(|pid| is_server_process_alive(pid, self.exe_path.as_deref()))
+    }
+}
+
+fn current_boot_id() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/sys/kernel/random/boot_id")
+            .ok()
+            .map(|s| s.trim().to_string())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
 }
 
 /// Per-project state stored in ~/.ai-pod/{hash}.json
@@ -42,6 +120,44 @@ pub struct ProjectState {
     /// `start_service` requests. See `commands::service_approval_key`.
     #[serde(default)]
     pub allowed_services: Vec<String>,
+    /// Per-project escape hatch: skip the capability, no-new-privileges and
+    /// seccomp/AppArmor options from the global `security` config. Toggled
+    /// with `ai-pod security relax` / `ai-pod security enforce`.
+    #[serde(default)]
+    pub relax_security: bool,
+    /// Extra mounts for this workspace only, applied after the global ones.
+    /// Managed with `ai-pod mount add --project`.
+    #[serde(default)]
+    pub mounts: Vec<crate::config::MountSpec>,
+    /// Workspace-relative paths hidden from the container by overlaying an
+    /// empty tmpfs (directories) or `/dev/null` (files). Managed with
+    /// `ai-pod exclude` / `ai-pod unexclude`.
+    #[serde(default)]
+    pub excluded_paths: Vec<String>,
+    /// Fingerprint of the devcontainer `postCreateCommand` that last ran
+    /// successfully against this workspace's home volume. Cleared when the
+    /// volume is created or reseeded so the setup runs again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_create_done: Option<String>,
+    /// Home volume whose initialisation started but hasn't finished. Set
+    /// before the volume is created and cleared once it's fully seeded; a
+    /// volume still named here on the next launch is recreated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub home_volume_pending: Option<String>,
+    /// ID of the image the home volume was seeded from. When the image
+    /// changes, the home volume is regenerated; the state volume is kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub home_volume_image: Option<String>,
+    /// User the agent runs as in this workspace, over the global `user`.
+    #[serde(default, skip_serializing_if = "crate::config::UserConfig::is_empty")]
+    pub user: crate::config::UserConfig,
+    /// Session entrypoint and command for this workspace, over the global
+    /// `launch`.
+    #[serde(default, skip_serializing_if = "crate::config::LaunchConfig::is_empty")]
+    pub launch: crate::config::LaunchConfig,
+    /// Workspace sync for this workspace, over the global `sync`.
+    #[serde(default, skip_serializing_if = "crate::config::SyncConfig::is_empty")]
+    pub sync: crate::config::SyncConfig,
 }
 
 impl ProjectState {
@@ -112,6 +228,20 @@ impl ProjectState {
         self.masked_directories.retain(|d| d != dir);
     }
 
+    pub fn is_excluded(&self, path: &str) -> bool {
+        self.excluded_paths.iter().any(|p| p == path)
+    }
+
+    pub fn add_excluded(&mut self, path: &str) {
+        if !self.is_excluded(path) {
+            self.excluded_paths.push(path.to_string());
+        }
+    }
+
+    pub fn remove_excluded(&mut self, path: &str) {
+        self.excluded_paths.retain(|p| p != path);
+    }
+
     pub fn is_service_allowed(&self, key: &str) -> bool {
         self.allowed_services.iter().any(|k| k == key)
     }
@@ -184,6 +314,92 @@ fn create_server_log(path: &Path) -> std::io::Result<std::fs::File> {
         .open(path)
 }
 
+/// Per-project state files in the config dir, as `(project_id, path)`.
+/// Every other top-level `*.json` there is a per-project file except
+/// `server` (the shared server state), `config` (the global GlobalConfig)
+/// and `workspaces` (the alias/recent registry).
+pub fn project_state_files(config_dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(config_dir) else {
+        return vec![];
+    };
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if matches!(stem, "server" | "config" | "workspaces") || instance_port(stem).is_some() {
+            continue;
+        }
+        files.push((stem.to_string(), path.clone()));
+    }
+    files.sort();
+    files
+}
+
+/// Use the server instance on `port` for the rest of this process: the one
+/// a workspace is assigned in the registry (see
+/// `registry::server_port`), or the one `ai-pod serve --port` runs. The
+/// first call wins; without one, the default instance on [`MCP_PORT`] is
+/// used.
+pub fn select_port(port: u16) {
+    let _ = PORT.set(port);
+}
+
+/// Port of the server instance this process uses.
+pub fn server_port() -> u16 {
+    PORT.get().copied().unwrap_or(MCP_PORT)
+}
+
+/// The port of a non-default instance's `server-<port>` file stem.
+fn instance_port(stem: &str) -> Option<u16> {
+    stem.strip_prefix("server-")?.parse().ok()
+}
+
+/// `~/.ai-pod/server.<ext>` for the default instance,
+/// `~/.ai-pod/server-<port>.<ext>` for the others.
+fn instance_file(config: &AppConfig, port: u16, ext: &str) -> PathBuf {
+    if port == MCP_PORT {
+        config.config_dir.join(format!("server.{}", ext))
+    } else {
+        config.config_dir.join(format!("server-{}.{}", port, ext))
+    }
+}
+
+/// State file of the selected server instance.
+pub fn server_state_file(config: &AppConfig) -> PathBuf {
+    instance_file(config, server_port(), "json")
+}
+
+/// Log of the selected server instance.
+pub fn server_log_file(config: &AppConfig) -> PathBuf {
+    instance_file(config, server_port(), "log")
+}
+
+/// Ports of the server instances with a state file, default first.
+fn instance_ports(config: &AppConfig) -> Vec<u16> {
+    let mut ports: Vec<u16> = std::fs::read_dir(&config.config_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                return None;
+            }
+            match path.file_stem()?.to_str()? {
+                "server" => Some(MCP_PORT),
+                stem => instance_port(stem),
+            }
+        })
+        .collect();
+    ports.sort_by_key(|p| (*p != MCP_PORT, *p));
+    ports
+}
+
 #[allow(dead_code)]
 pub fn state_file_for(config: &AppConfig, workspace: &Path) -> PathBuf {
     let hash = workspace_hash(workspace);
@@ -195,7 +411,7 @@ pub fn state_file_for(config: &AppConfig, workspace: &Path) -> PathBuf {
 /// timer for the next operation, and any real connectivity problem will
 /// surface on the subsequent authenticated request.
 pub async fn bump_keep_alive() {
-    let url = format!("http://127.0.0.1:{}/keep-alive", MCP_PORT);
+    let url = format!("http://localhost:{}/keep-alive", server_port());
     let _ = reqwest::Client::new()
         .post(&url)
         .timeout(std::time::Duration::from_secs(2))
@@ -203,70 +419,255 @@ pub async fn bump_keep_alive() {
         .await;
 }
 
-/// Ensure the shared server is running. Starts it if not alive.
-pub async fn ensure_shared_server(config: &AppConfig) -> Result<()> {
-    let state_path = config.server_state_file();
-    let state: ServerState = std::fs::read_to_string(&state_path)
-        .ok()
-        .and_then(|s| serde_json::from_str(&s).ok())
-        .unwrap_or_default();
-
-    if let Some(pid) = state.pid
-        && is_server_process_alive(pid, state.exe_path.as_deref())
-    {
-        // Re-arm the inactivity timer so a freshly-arriving CLI command does
-        // not inherit a near-expired timer from the previous run.
-        bump_keep_alive().await;
-        return Ok(());
+/// Delay before restarting the server after `crashes` consecutive crashes:
+/// 1s, 2s, 4s, ... capped at 30s, or `None` once the supervisor should give up.
+pub fn restart_delay(crashes: u32) -> Option<Duration> {
+    if crashes == 0 || crashes > MAX_RESTARTS {
+        return None;
+    }
+    Some(Duration::from_secs((1u64 << (crashes - 1)).min(30)))
+}
+
+/// Supervise the shared server in the foreground (`ai-pod server run`):
+/// run `ai-pod serve` and restart it with backoff when it crashes. Returns
+/// once the server shuts itself down after going idle. A lock on
+/// `server.lock` keeps a second supervisor from starting.
+pub fn supervise(config: &AppConfig) -> Result<()> {
+    let lock_path = instance_file(config, server_port(), "lock");
+    let lock = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .mode(0o600)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+    if crate::launch_lock::flock(&lock, libc::LOCK_EX | libc::LOCK_NB).is_err() {
+        let state = ServerState::load(&server_state_file(config));
+        anyhow::bail!(
+            "The shared server on port {} is already running (PID {})",
+            server_port(),
+            state.pid.map(|p| p.to_string()).unwrap_or_else(|| "unknown".into())
+        );
     }
 
     let exe = std::env::current_exe().context("Failed to get current executable path")?;
-    let log_path = config.config_dir.join("server.log");
+    let state_path = server_state_file(config);
+    let mut restarts = 0;
+    let mut crashes = 0;
+    loop {
+        ServerState {
+            pid: Some(std::process::id()),
+            exe_path: Some(exe.to_string_lossy().to_string()),
+            boot_id: current_boot_id(),
+            restarts,
+            ..Default::default()
+        }
+        .save(&state_path)?;
+
+        let started = Instant::now();
+        let status = Command::new(&exe)
+            .args(["serve", "--port", &server_port().to_string()])
+            .stdin(std::process::Stdio::null())
+            .status()
+            .context("Failed to start the server process")?;
+        if status.success() {
+            eprintln!("Shared server stopped.");
+            break;
+        }
+
+        if started.elapsed() >= STABLE_RUN {
+            crashes = 0;
+        }
+        crashes += 1;
+        let Some(delay) = restart_delay(crashes) else {
+            let _ = std::fs::remove_file(&state_path);
+            anyhow::bail!(
+                "Shared server crashed {} times in a row; giving up. See {}",
+                crashes,
+                server_log_file(config).display()
+            );
+        };
+        eprintln!(
+            "{} server process exited ({}); restarting in {}s",
+            "warning:".yellow().bold(),
+            status,
+            delay.as_secs()
+        );
+        std::thread::sleep(delay);
+        restarts += 1;
+    }
+
+    let _ = std::fs::remove_file(&state_path);
+    drop(lock);
+    Ok(())
+}
+
+/// Called by the server process once it is listening: record its PID and
+/// the bound port in the state file.
+pub fn record_listening(config: &AppConfig, port: u16) -> Result<()> {
+    let path = server_state_file(config);
+    let mut state = ServerState::load(&path);
+    state.worker_pid = Some(std::process::id());
+    state.port = Some(port);
+    state.save(&path)
+}
+
+/// Start `ai-pod server run --foreground` detached from this terminal (own
+/// process group, output to `~/.ai-pod/server.log`, or `server-<port>.log`
+/// for another instance).
+fn spawn_daemon(config: &AppConfig) -> Result<Child> {
+    use std::os::unix::process::CommandExt;
+
+    let exe = std::env::current_exe().context("Failed to get current executable path")?;
+    let log_path = server_log_file(config);
     let log = create_server_log(&log_path).context("Failed to create server log file")?;
     let log_err = log.try_clone()?;
 
-    let child = Command::new(&exe)
-        .args(["serve"])
+    Command::new(&exe)
+        .args(["server", "run", "--foreground", "--port", &server_port().to_string()])
         .stdin(std::process::Stdio::null())
         .stdout(std::process::Stdio::from(log))
         .stderr(std::process::Stdio::from(log_err))
+        .process_group(0)
         .spawn()
-        .context("Failed to spawn shared server")?;
+        .context("Failed to spawn shared server")
+}
+
+/// Wait until a live server has recorded its port, or `child` exits first.
+fn wait_until_listening(config: &AppConfig, child: &mut Child) -> Option<ServerState> {
+    let deadline = Instant::now() + START_TIMEOUT;
+    while Instant::now() < deadline {
+        let state = ServerState::load(&server_state_file(config));
+        if state.port.is_some() && state.is_alive() {
+            return Some(state);
+        }
+        if matches!(child.try_wait(), Ok(Some(_))) {
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    None
+}
 
-    let pid = child.id();
-    let new_state = ServerState {
-        pid: Some(pid),
-        exe_path: Some(exe.to_string_lossy().to_string()),
+/// Start the supervised server in the background (`ai-pod server run
+/// --daemonize`, and on demand from every command that needs it).
+pub fn daemonize(config: &AppConfig) -> Result<()> {
+    let mut child = spawn_daemon(config)?;
+    match wait_until_listening(config, &mut child) {
+        Some(state) => eprintln!(
+            "{} (PID {}, port {})",
+            "Shared server started.".green(),
+            state.pid.unwrap_or(child.id()),
+            state.port.unwrap_or(server_port()),
+        ),
+        None => eprintln!(
+            "{} the shared server (PID {}) hasn't reported a port yet; see {}",
+            "warning:".yellow().bold(),
+            child.id(),
+            server_log_file(config).display()
+        ),
+    }
+    Ok(())
+}
+
+/// Ensure the shared server is running. Starts it if not alive.
+pub async fn ensure_shared_server(config: &AppConfig) -> Result<()> {
+    if ServerState::load(&server_state_file(config)).is_alive() {
+        // Re-arm the inactivity timer so a freshly-arriving CLI command does
+        // not inherit a near-expired timer from the previous run.
+        bump_keep_alive().await;
+        return Ok(());
+    }
+    daemonize(config)
+}
+
+/// `ai-pod server status`: every running instance, the default one first.
+pub fn print_status(config: &AppConfig) {
+    let running: Vec<(u16, ServerState)> = instance_ports(config)
+        .into_iter()
+        .map(|port| (port, ServerState::load(&instance_file(config, port, "json"))))
+        .filter(|(_, state)| state.is_alive())
+        .collect();
+    if running.is_empty() {
+        println!("{}", "Shared server is not running.".yellow());
+        return;
+    }
+    let show = |label: &str, value: Option<String>| {
+        println!("  {:<12} {}", label, value.unwrap_or_else(|| "-".into()));
     };
-    let json = serde_json::to_string_pretty(&new_state)?;
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .mode(0o600)
-        .open(&state_path)
-        .context("Failed to write server state")?;
-    file.write_all(json.as_bytes())
-        .context("Failed to write server state contents")?;
-
-    // Wait briefly for server to start
-    std::thread::sleep(std::time::Duration::from_millis(500));
-
-    eprintln!(
-        "{} (PID {}, port {})",
-        "Shared server started.".green(),
-        pid,
-        MCP_PORT,
+    for (i, (port, state)) in running.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        if *port == MCP_PORT {
+            println!("{}", "Shared server is running.".green());
+        } else {
+            println!("{}", format!("Shared server on port {} is running.", port).green());
+        }
+        show("Supervisor:", state.pid.map(|p| p.to_string()));
+        show("Server:", state.worker_pid.map(|p| p.to_string()));
+        show("Port:", state.port.map(|p| p.to_string()));
+        show("Restarts:", Some(state.restarts.to_string()));
+        show("Log:", Some(instance_file(config, *port, "log").display().to_string()));
+    }
+    show(
+        "Access log:",
+        Some(super::access_log::access_log_file(&config.config_dir).display().to_string()),
     );
+}
 
+/// `ai-pod server stop`: stop the selected instance, or with `all` every
+/// instance.
+pub fn stop_server(config: &AppConfig, all: bool) -> Result<()> {
+    let ports = if all { instance_ports(config) } else { vec![server_port()] };
+    let mut stopped = false;
+    for port in ports {
+        stopped |= stop_instance(config, port);
+    }
+    if !stopped {
+        println!("{}", "Shared server is not running.".yellow());
+    }
     Ok(())
 }
 
+/// Terminate the supervisor of the instance on `port` first so it doesn't
+/// restart the server, then the server itself. Returns whether it was
+/// running.
+fn stop_instance(config: &AppConfig, port: u16) -> bool {
+    let state_path = instance_file(config, port, "json");
+    let state = ServerState::load(&state_path);
+    if !state.is_alive() {
+        let _ = std::fs::remove_file(&state_path);
+        return false;
+    }
+    for pid in [state.pid, state.worker_pid].into_iter().flatten() {
+        unsafe {
+            libc::kill(pid as i32, libc::SIGTERM);
+        }
+    }
+    // Give the server a moment to release the port for a following start.
+    if let Some(worker) = state.worker_pid {
+        let deadline = Instant::now() + START_TIMEOUT;
+        while is_process_alive(worker) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+    let _ = std::fs::remove_file(&state_path);
+    let label = if port == MCP_PORT {
+        "Stopped shared server.".to_string()
+    } else {
+        format!("Stopped shared server on port {}.", port)
+    };
+    println!("{} (PID {})", label.red().bold(), state.pid.unwrap_or_default());
+    true
+}
+
 /// Load or create per-project state (generates api_key on first use).
 pub fn get_or_create_project_state(config: &AppConfig, workspace: &Path) -> Result<ProjectState> {
     let hash = workspace_hash(workspace);
     let state_path = config.project_state_file(&hash);
     let mut state = ProjectState::load(&state_path);
+    crate::collision::check_state(&state_path, &state.workspace, workspace)?;
 
     let changed = if state.api_key.is_empty() {
         state.api_key = uuid::Uuid::new_v4().to_string().replace('-', "");
@@ -288,7 +689,7 @@ pub fn get_or_create_project_state(config: &AppConfig, workspace: &Path) -> Resu
 
 /// Tell the running shared server to rescan config files.
 pub async fn reload_config() -> Result<()> {
-    let url = format!("http://127.0.0.1:{}/reload", MCP_PORT);
+    let url = format!("http://localhost:{}/reload", server_port());
     reqwest::Client::new()
         .post(&url)
         .send()
@@ -314,7 +715,7 @@ fn is_newer_version(server: &str, cli: &str) -> bool {
 
 /// Check that the running server version matches the CLI. Returns Err if CLI is newer.
 pub async fn check_server_version() -> Result<()> {
-    let url = format!("http://127.0.0.1:{}/version", MCP_PORT);
+    let url = format!("http://localhost:{}/version", server_port());
     let resp: serde_json::Value = reqwest::Client::new()
         .get(&url)
         .send()
@@ -344,19 +745,9 @@ pub async fn check_server_version() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::test_config;
     use tempfile::TempDir;
 
-    fn temp_config(dir: &TempDir) -> AppConfig {
-        let home = dir.path().to_path_buf();
-        let config_dir = home.join(".ai-pod");
-        std::fs::create_dir_all(&config_dir).unwrap();
-        AppConfig {
-            runtime_settings: config_dir.join("runtime-settings.json"),
-            config_dir,
-            home_dir: home,
-        }
-    }
-
     #[test]
     fn project_state_default_has_no_api_key() {
         let state = ProjectState::default();
@@ -376,6 +767,15 @@ mod tests {
             ignored_credential_files: vec![],
             masked_directories: vec![],
             allowed_services: vec![],
+            relax_security: false,
+            mounts: vec![],
+            excluded_paths: vec![],
+            post_create_done: None,
+            home_volume_pending: None,
+            home_volume_image: None,
+            user: Default::default(),
+            launch: Default::default(),
+            sync: Default::default(),
         };
         state.save(&path).unwrap();
         let perms = std::fs::metadata(&path).unwrap().permissions();
@@ -400,6 +800,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn restart_delay_backs_off_then_gives_up() {
+        assert_eq!(restart_delay(1), Some(Duration::from_secs(1)));
+        assert_eq!(restart_delay(3), Some(Duration::from_secs(4)));
+        assert_eq!(restart_delay(MAX_RESTARTS), Some(Duration::from_secs(16)));
+        assert_eq!(restart_delay(MAX_RESTARTS + 1), None);
+    }
+
+    #[test]
+    fn server_state_records_listening_port() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        ServerState {
+            pid: Some(std::process::id()),
+            boot_id: current_boot_id(),
+            ..Default::default()
+        }
+        .save(&config.server_state_file())
+        .unwrap();
+        record_listening(&config, 7822).unwrap();
+
+        let state = ServerState::load(&config.server_state_file());
+        assert_eq!(state.port, Some(7822));
+        assert_eq!(state.worker_pid, Some(std::process::id()));
+        assert!(state.is_alive());
+    }
+
+    #[test]
+    fn server_instances_have_their_own_files() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        assert_eq!(instance_file(&config, MCP_PORT, "json"), config.server_state_file());
+        assert_eq!(
+            instance_file(&config, 7830, "log"),
+            config.config_dir.join("server-7830.log")
+        );
+        for name in ["server-7830.json", "server.json", "server-9000.json", "abc123.json", "server-x.json"] {
+            std::fs::write(config.config_dir.join(name), "{}").unwrap();
+        }
+        assert_eq!(instance_ports(&config), vec![MCP_PORT, 7830, 9000]);
+        let stems: Vec<String> = project_state_files(&config.config_dir).into_iter().map(|(s, _)| s).collect();
+        assert_eq!(stems, vec!["abc123", "server-x"]);
+    }
+
+    #[test]
+    fn server_state_from_another_boot_is_stale() {
+        let state = ServerState {
+            pid: Some(std::process::id()),
+            boot_id: Some("not-this-boot".into()),
+            ..Default::default()
+        };
+        assert_eq!(state.is_alive(), current_boot_id().is_none());
+    }
+
+    #[test]
+    fn legacy_server_state_still_loads() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("server.json");
+        std::fs::write(&path, r#"{"pid": 42, "exe_path": "/usr/bin/ai-pod"}"#).unwrap();
+        let state = ServerState::load(&path);
+        assert_eq!(state.pid, Some(42));
+        assert_eq!(state.port, None);
+        assert_eq!(state.restarts, 0);
+    }
+
     #[test]
     fn project_state_round_trips() {
         let dir = TempDir::new().unwrap();
@@ -411,6 +876,15 @@ mod tests {
             ignored_credential_files: vec![],
             masked_directories: vec![],
             allowed_services: vec![],
+            relax_security: false,
+            mounts: vec![],
+            excluded_paths: vec![],
+            post_create_done: None,
+            home_volume_pending: None,
+            home_volume_image: None,
+            user: Default::default(),
+            launch: Default::default(),
+            sync: Default::default(),
         };
         state.save(&path).unwrap();
         let loaded = ProjectState::load(&path);
@@ -494,7 +968,7 @@ mod tests {
     #[test]
     fn state_file_is_under_config_dir() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let path = state_file_for(&config, Path::new("/home/user/myproject"));
         assert!(path.starts_with(&config.config_dir));
         assert!(path.extension().unwrap() == "json");
@@ -503,7 +977,7 @@ mod tests {
     #[test]
     fn get_or_create_generates_api_key() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let workspace = Path::new("/home/user/myproject");
         let state = get_or_create_project_state(&config, workspace).unwrap();
         assert!(!state.api_key.is_empty());
@@ -513,7 +987,7 @@ mod tests {
     #[test]
     fn get_or_create_is_stable() {
         let dir = TempDir::new().unwrap();
-        let config = temp_config(&dir);
+        let config = test_config(&dir);
         let workspace = Path::new("/home/user/myproject");
         let state1 = get_or_create_project_state(&config, workspace).unwrap();
         let state2 = get_or_create_project_state(&config, workspace).unwrap();
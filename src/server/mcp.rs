@@ -478,6 +478,7 @@ mod tests {
             keep_alive_until: Arc::new(Mutex::new(
                 std::time::Instant::now() + std::time::Duration::from_secs(30),
             )),
+            egress_limits: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -492,19 +493,18 @@ mod tests {
     #[test]
     fn session_runtime_reads_persisted_kind_and_keeps_dry_run() {
         let dir = tempfile::TempDir::new().unwrap();
-        let config = crate::config::AppConfig {
-            config_dir: dir.path().to_path_buf(),
-            runtime_settings: dir.path().join("runtime-settings.json"),
-            home_dir: dir.path().to_path_buf(),
-        };
+        let config = crate::config::test_config(&dir);
         crate::config::SessionState {
             runtime: RuntimeKind::Docker,
+            tmux: None,
+            title: None,
+            workspace: None,
         }
         .save(&config, "sess42")
         .unwrap();
 
         // Server runtime is podman, but the session was launched with docker.
-        let state = test_state(dir.path().to_path_buf(), RuntimeKind::Podman);
+        let state = test_state(config.config_dir.clone(), RuntimeKind::Podman);
         let rt = session_runtime(&state, "sess42");
         assert_eq!(rt.kind, RuntimeKind::Docker);
         assert_eq!(rt.dry_run, state.runtime.dry_run);
@@ -714,11 +714,10 @@ async fn handle_tool_call(
         }
         "notify_user" => {
             let msg = args.get("message").and_then(|v| v.as_str()).unwrap_or("");
-            let project_name = workspace
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            notify::send_notification(&format!("ai-pod {}", project_name), msg);
+            if let Err(e) = super::rest::validate_message(msg) {
+                return tool_error(format!("Invalid notification: {}", e));
+            }
+            notify::notify_user(&state.config_dir, workspace, Some(session_id), "message", msg, None);
             tool_text("ok".into())
         }
         "list_allowed_commands" => {
@@ -1,4 +1,7 @@
+pub mod access_log;
+pub mod budget;
 pub mod commands;
+pub mod egress;
 pub mod lifecycle;
 pub mod mcp;
 pub mod notify;
@@ -7,7 +10,7 @@ pub mod runner;
 
 use axum::{
     Json, Router,
-    extract::{Path as AxumPath, Request, State},
+    extract::{DefaultBodyLimit, Path as AxumPath, Request, State},
     http::{StatusCode, header},
     middleware::{self, Next},
     response::{IntoResponse, Response},
@@ -41,6 +44,8 @@ pub struct AppState {
     pub commands: Arc<Mutex<HashMap<(String, String), CommandHandle>>>,
     pub runtime: ContainerRuntime,
     pub keep_alive_until: Arc<Mutex<Instant>>,
+    /// Per-session bandwidth and connection limits of the egress proxy.
+    pub egress_limits: Arc<Mutex<HashMap<String, Arc<egress::SessionLimits>>>>,
 }
 
 async fn health_handler() -> &'static str {
@@ -165,13 +170,18 @@ pub fn build_app(state: AppState) -> Router {
         .route("/version", get(version_handler))
         .route("/keep-alive", post(keep_alive_handler))
         .route("/reload", post(reload_handler))
-        .route("/notify_user", post(rest::notify_user_handler))
+        .route(
+            "/notify_user",
+            post(rest::notify_user_handler).layer(DefaultBodyLimit::max(rest::MAX_NOTIFY_BODY)),
+        )
+        .route("/open_url", post(rest::open_url_handler))
         .route("/list_allowed_commands", post(rest::list_allowed_commands_handler))
         .route("/commands/run", post(rest::run_command_handler))
         .route("/commands/stop", post(rest::stop_command_handler))
         .route("/commands/status", post(rest::command_status_handler))
         .route("/commands/list", post(rest::list_commands_handler))
         .route("/mcp", post(mcp::mcp_handler))
+        .route("/otel/{project_id}/v1/metrics", post(budget::otel_metrics_handler))
         .layer(GovernorLayer::new(governor_conf))
         .layer(middleware::from_fn(add_retry_after_header));
 
@@ -180,6 +190,7 @@ pub fn build_app(state: AppState) -> Router {
         .route("/install/{name}", get(install_script_handler))
         .route("/host-tools", get(host_tools_deprecated_handler))
         .merge(rate_limited)
+        .layer(middleware::from_fn_with_state(state.clone(), access_log::log_request))
         .with_state(state)
 }
 
@@ -286,31 +297,16 @@ async fn sweep_orphan_services_one(rt: &ContainerRuntime) {
 
 async fn reload_handler(State(state): State<AppState>) -> &'static str {
     let mut projects = state.projects.lock().await;
-    if let Ok(entries) = std::fs::read_dir(&state.config_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|e| e.to_str()) != Some("json") {
-                continue;
-            }
-            let stem = match path.file_stem().and_then(|s| s.to_str()) {
-                Some(s) => s.to_string(),
-                None => continue,
-            };
-            // "server" = the shared server state, "config" = the global
-            // GlobalConfig (mounts). Neither is a per-project state file.
-            if stem == "server" || stem == "config" {
-                continue;
-            }
-            let ps = ProjectState::load(&path);
-            if !ps.api_key.is_empty() && !ps.workspace.is_empty() {
-                projects.insert(
-                    stem,
-                    ProjectInfo {
-                        workspace: PathBuf::from(&ps.workspace),
-                        api_key: ps.api_key,
-                    },
-                );
-            }
+    for (stem, path) in lifecycle::project_state_files(&state.config_dir) {
+        let ps = ProjectState::load(&path);
+        if !ps.api_key.is_empty() && !ps.workspace.is_empty() {
+            projects.insert(
+                stem,
+                ProjectInfo {
+                    workspace: PathBuf::from(&ps.workspace),
+                    api_key: ps.api_key,
+                },
+            );
         }
     }
     "reloaded"
@@ -319,31 +315,16 @@ async fn reload_handler(State(state): State<AppState>) -> &'static str {
 pub async fn run_server(port: u16, config: AppConfig, rt: ContainerRuntime) -> anyhow::Result<()> {
     let mut projects: HashMap<String, ProjectInfo> = HashMap::new();
 
-    if let Ok(entries) = std::fs::read_dir(&config.config_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|e| e.to_str()) != Some("json") {
-                continue;
-            }
-            let stem = match path.file_stem().and_then(|s| s.to_str()) {
-                Some(s) => s.to_string(),
-                None => continue,
-            };
-            // "server" = the shared server state, "config" = the global
-            // GlobalConfig (mounts). Neither is a per-project state file.
-            if stem == "server" || stem == "config" {
-                continue;
-            }
-            let state = ProjectState::load(&path);
-            if !state.api_key.is_empty() && !state.workspace.is_empty() {
-                projects.insert(
-                    stem,
-                    ProjectInfo {
-                        workspace: PathBuf::from(&state.workspace),
-                        api_key: state.api_key,
-                    },
-                );
-            }
+    for (stem, path) in lifecycle::project_state_files(&config.config_dir) {
+        let state = ProjectState::load(&path);
+        if !state.api_key.is_empty() && !state.workspace.is_empty() {
+            projects.insert(
+                stem,
+                ProjectInfo {
+                    workspace: PathBuf::from(&state.workspace),
+                    api_key: state.api_key,
+                },
+            );
         }
     }
 
@@ -354,6 +335,7 @@ pub async fn run_server(port: u16, config: AppConfig, rt: ContainerRuntime) -> a
         commands: Arc::new(Mutex::new(HashMap::new())),
         runtime: rt,
         keep_alive_until: Arc::new(Mutex::new(Instant::now() + Duration::from_secs(30))),
+        egress_limits: Arc::new(Mutex::new(HashMap::new())),
     };
 
     // Refresh the update-check cache in the background. The server is long-lived
@@ -398,18 +380,116 @@ pub async fn run_server(port: u16, config: AppConfig, rt: ContainerRuntime) -> a
         }
     });
 
+    let proxy_state = state.clone();
     let app = build_app(state);
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    println!("Shared server listening on {}", addr);
+    let listen = crate::config::GlobalConfig::load_from_dir(&config.config_dir).server.listen;
+    let listeners = bind_listeners(listen, port).await?;
+    for listener in &listeners {
+        println!("Shared server listening on {}", listener.local_addr()?);
+    }
+    if let Err(e) = lifecycle::record_listening(&config, listeners[0].local_addr()?.port()) {
+        eprintln!("Failed to record the server port: {:#}", e);
+    }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(async { shutdown_rx.await.ok(); })
-    .await?;
+    // One shutdown signal for every listener.
+    let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_rx.await.ok();
+        let _ = stop_tx.send(true);
+    });
+    // The egress proxy is only used by containers launched with
+    // `network.audit`; if its port is taken the server runs without it.
+    match bind_listeners(listen, egress::EGRESS_PORT).await {
+        Ok(proxies) => {
+            for listener in proxies {
+                tokio::spawn(egress::serve(listener, proxy_state.clone(), stop_rx.clone()));
+            }
+        }
+        Err(e) => eprintln!("Egress audit proxy not available: {}", e),
+    }
+    let servers = listeners.into_iter().map(|listener| {
+        let mut stop_rx = stop_rx.clone();
+        axum::serve(
+            listener,
+            app.clone().into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move {
+            let _ = stop_rx.wait_for(|stop| *stop).await;
+        })
+        .into_future()
+    });
+    futures_util::future::try_join_all(servers).await?;
 
     Ok(())
 }
+
+/// Bind the server's listeners for `mode`. In dual mode `[::]` usually
+/// accepts IPv4 too (IPv4-mapped addresses), in which case binding
+/// `0.0.0.0` as well fails with `AddrInUse` and is skipped; on hosts without
+/// IPv6 only the IPv4 listener is bound.
+async fn bind_listeners(
+    mode: crate::config::ListenMode,
+    port: u16,
+) -> anyhow::Result<Vec<tokio::net::TcpListener>> {
+    use crate::config::ListenMode;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use tokio::net::TcpListener;
+
+    let v6 = SocketAddr::from((Ipv6Addr::UNSPECIFIED, port));
+    let v4 = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+    match mode {
+        ListenMode::Ipv4 => Ok(vec![TcpListener::bind(v4).await?]),
+        ListenMode::Ipv6 => Ok(vec![TcpListener::bind(v6).await?]),
+        ListenMode::Dual => {
+            let mut listeners = Vec::new();
+            let v6_err = match TcpListener::bind(v6).await {
+                Ok(l) => {
+                    listeners.push(l);
+                    None
+                }
+                Err(e) => Some(e),
+            };
+            // Reuse the port the first listener got (matters for port 0).
+            let v4 = match listeners.first() {
+                Some(l) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, l.local_addr()?.port())),
+                None => v4,
+            };
+            match TcpListener::bind(v4).await {
+                Ok(l) => listeners.push(l),
+                Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && v6_err.is_none() => {}
+                Err(e) => {
+                    if listeners.is_empty() {
+                        return Err(e.into());
+                    }
+                    eprintln!("IPv4 listener not available: {}", e);
+                }
+            }
+            if let Some(e) = v6_err {
+                eprintln!("IPv6 listener not available ({}); serving IPv4 only", e);
+            }
+            Ok(listeners)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ListenMode;
+
+    #[tokio::test]
+    async fn dual_listeners_accept_ipv4() {
+        let listeners = bind_listeners(ListenMode::Dual, 0).await.unwrap();
+        let port = listeners[0].local_addr().unwrap().port();
+        assert!(listeners.iter().all(|l| l.local_addr().unwrap().port() == port));
+        tokio::net::TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn ipv4_mode_binds_only_ipv4() {
+        let listeners = bind_listeners(ListenMode::Ipv4, 0).await.unwrap();
+        assert_eq!(listeners.len(), 1);
+        assert!(listeners[0].local_addr().unwrap().is_ipv4());
+    }
+}
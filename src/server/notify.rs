@@ -1,9 +1,668 @@
-pub fn send_notification(title: &str, message: &str) {
-    if let Err(e) = notify_rust::Notification::new()
-        .summary(title)
-        .body(message)
-        .show()
-    {
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::config::{GlobalConfig, NotifyConfig, NotifyTemplate, SessionState};
+use crate::events::{self, Event, EventKind};
+use crate::plugins;
+
+/// Tell the user about a session through the configured notification
+/// backend; while the session runs, the terminal belongs to the agent.
+pub fn announce(config_dir: &Path, workspace: &Path, kind: &str, message: &str) {
+    let global = GlobalConfig::load_from_dir(config_dir);
+    NotifyBackend::from_config(&global.notify).send(kind, "ai-pod", message, workspace);
+}
+
+/// Where agent notifications go on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifyBackend {
+    /// The desktop notification service (libnotify / Notification Center).
+    Desktop,
+    /// A user-supplied argv from `notify.command`, with placeholders.
+    Command(Vec<String>),
+}
+
+impl NotifyBackend {
+    pub fn from_config(config: &NotifyConfig) -> Self {
+        match &config.command {
+            Some(argv) if !argv.is_empty() => NotifyBackend::Command(argv.clone()),
+            _ => NotifyBackend::Desktop,
+        }
+    }
+
+    /// Show a notification of `kind` (see [`EVENT_KINDS`]).
+    pub fn send(&self, kind: &str, title: &str, message: &str, workspace: &Path) {
+        match self {
+            NotifyBackend::Desktop => send_notification(kind, title, message),
+            NotifyBackend::Command(template) => {
+                let argv = expand_args(template, kind, title, message, workspace);
+                match spawn_command(&argv) {
+                    // Reap in the background; a slow notifier must not hold
+                    // up the agent's tool call.
+                    Ok(mut child) => {
+                        let program = argv[0].clone();
+                        std::thread::spawn(move || match child.wait() {
+                            Ok(status) if !status.success() => {
+                                eprintln!("[notify] {program} exited with {status}")
+                            }
+                            Err(e) => eprintln!("[notify] Failed to wait for {program}: {e}"),
+                            Ok(_) => {}
+                        });
+                    }
+                    Err(e) => eprintln!("[notify] Failed to run {}: {e}", argv[0]),
+                }
+            }
+        }
+    }
+}
+
+/// Substitute `{title}`, `{message}`, `{event}`, `{workspace}` and
+/// `{project_id}` in every argument, in one pass so placeholders inside the
+/// values themselves are left as they are.
+/// The values are passed as separate argv entries, never through a shell.
+fn expand_args(template: &[String], kind: &str, title: &str, message: &str, workspace: &Path) -> Vec<String> {
+    let ws = workspace.to_string_lossy();
+    let project_id = crate::workspace::workspace_hash(workspace);
+    let value = |name: &str| match name {
+        "title" => Some(title),
+        "event" => Some(kind),
+        "message" => Some(message),
+        "workspace" => Some(ws.as_ref()),
+        "project_id" => Some(project_id.as_str()),
+        _ => None,
+    };
+    template
+        .iter()
+        .map(|arg| {
+            let mut out = String::with_capacity(arg.len());
+            let mut rest = arg.as_str();
+            while let Some(start) = rest.find('{') {
+                out.push_str(&rest[..start]);
+                let after = &rest[start + 1..];
+                match after.find('}').and_then(|end| Some((end, value(&after[..end])?))) {
+                    Some((end, v)) => {
+                        out.push_str(v);
+                        rest = &after[end + 1..];
+                    }
+                    None => {
+                        out.push('{');
+                        rest = after;
+                    }
+                }
+            }
+            out.push_str(rest);
+            out
+        })
+        .collect()
+}
+
+fn spawn_command(argv: &[String]) -> std::io::Result<Child> {
+    Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .spawn()
+}
+
+/// How urgently a desktop notification asks for attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Attention {
+    Low,
+    Normal,
+    Critical,
+}
+
+/// Urgency and sound of a desktop notification of `kind`. A finished task
+/// can wait; a permission prompt or an error holds the agent up until
+/// someone looks, so it stays on screen and sounds different. Sounds are
+/// freedesktop sound names on Linux and system sounds on macOS.
+fn desktop_style(kind: &str) -> (Attention, &'static str) {
+    let macos = cfg!(target_os = "macos");
+    match kind {
+        "stop" => (Attention::Low, if macos { "Glass" } else { "complete" }),
+        "permission" => (Attention::Critical, if macos { "Ping" } else { "dialog-warning" }),
+        "error" => (Attention::Critical, if macos { "Basso" } else { "dialog-error" }),
+        _ => (Attention::Normal, if macos { "default" } else { "message-new-instant" }),
+    }
+}
+
+pub fn send_notification(kind: &str, title: &str, message: &str) {
+    let (attention, sound) = desktop_style(kind);
+    let mut notification = notify_rust::Notification::new();
+    notification.summary(title).body(message).sound_name(sound);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    notification.urgency(match attention {
+        Attention::Low => notify_rust::Urgency::Low,
+        Attention::Normal => notify_rust::Urgency::Normal,
+        Attention::Critical => notify_rust::Urgency::Critical,
+    });
+    #[cfg(target_os = "macos")]
+    let _ = attention;
+    if let Err(e) = notification.show() {
         eprintln!("[notify] Failed to send notification: {e}");
     }
 }
+
+/// Window used when `notify.debounce_secs` is unset.
+pub const DEFAULT_DEBOUNCE_SECS: u64 = 2;
+
+/// A notification waiting out the debounce window, with how many arrived
+/// for the same workspace and kind. The latest one is delivered.
+struct Burst {
+    count: usize,
+    latest: Pending,
+}
+
+struct Pending {
+    project_name: String,
+    kind: String,
+    message: String,
+    /// Seconds since the session was launched, when known.
+    duration: Option<u64>,
+    /// The session's label, if it has one.
+    title: Option<String>,
+    event: Event,
+}
+
+/// Bursts keyed by workspace and notification kind.
+static BURSTS: LazyLock<Mutex<HashMap<(PathBuf, String), Burst>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Placeholders `notify.templates` can use.
+pub const TEMPLATE_FIELDS: &[&str] = &[
+    "project",
+    "session_title",
+    "event",
+    "message",
+    "duration",
+    "count",
+    "workspace",
+    "project_id",
+];
+
+/// Built-in body for `count` notifications of `kind` folded into one, e.g.
+/// "3 tasks finished in api".
+fn builtin_summary(kind: &str) -> &'static str {
+    match kind {
+        "stop" => "{count} tasks finished in {project}",
+        "permission" => "{count} permission requests in {project}",
+        "error" => "{count} errors in {project}; latest: {message}",
+        _ => "{count} messages from {project}; latest: {message}",
+    }
+}
+
+/// An error for the first `{name}` in `template` that isn't one of
+/// [`TEMPLATE_FIELDS`].
+pub fn check_template(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + len];
+        if !TEMPLATE_FIELDS.contains(&name) {
+            return Err(format!(
+                "unknown placeholder {{{}}} in \"{}\" (expected one of {})",
+                name,
+                template,
+                TEMPLATE_FIELDS.iter().map(|f| format!("{{{}}}", f)).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    Ok(())
+}
+
+/// Every template error in `config`: unknown kinds and placeholders.
+pub fn template_errors(config: &NotifyConfig) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (kind, t) in &config.templates {
+        if kind != "default" && !EVENT_KINDS.contains(&kind.as_str()) {
+            errors.push(format!(
+                "notify.templates: unknown kind '{}' (expected default, stop, permission, message or error)",
+                kind
+            ));
+        }
+        for text in [&t.title, &t.body, &t.summary].into_iter().flatten() {
+            if let Err(e) = check_template(text) {
+                errors.push(format!("notify.templates.{}: {}", kind, e));
+            }
+        }
+    }
+    errors
+}
+
+/// "45s", "12m", "1h 5m" or "2d 3h".
+pub(crate) fn format_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// Title and body for `pending`, of which `count` arrived within the
+/// debounce window, from the kind's template, the `default` one or the
+/// built-in text.
+fn render(config: &NotifyConfig, workspace: &Path, pending: &Pending, count: usize) -> (String, String) {
+    let pick = |field: fn(&NotifyTemplate) -> &Option<String>| {
+        [pending.kind.as_str(), "default"]
+            .iter()
+            .find_map(|k| config.templates.get(*k).and_then(|t| field(t).clone()))
+    };
+    let title = pick(|t| &t.title).unwrap_or_else(|| match pending.title {
+        Some(_) => "ai-pod {project}: {session_title}".to_string(),
+        None => "ai-pod {project}".to_string(),
+    });
+    let body = if count > 1 {
+        pick(|t| &t.summary).unwrap_or_else(|| builtin_summary(&pending.kind).to_string())
+    } else {
+        pick(|t| &t.body).unwrap_or_else(|| "{message}".to_string())
+    };
+    let values = [
+        ("project", pending.project_name.clone()),
+        ("session_title", pending.title.clone().unwrap_or_default()),
+        ("event", pending.kind.clone()),
+        ("message", pending.message.clone()),
+        ("duration", pending.duration.map(format_duration).unwrap_or_default()),
+        ("count", count.to_string()),
+        ("workspace", workspace.to_string_lossy().into_owned()),
+        ("project_id", crate::workspace::workspace_hash(workspace)),
+    ];
+    (fill(&title, &values), fill(&body, &values))
+}
+
+/// Replace the `{name}`s in `template` with their values in one pass, so
+/// braces in a value (the agent's message) are never expanded themselves.
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let known = after
+            .find('}')
+            .and_then(|end| Some((end, values.iter().find(|(k, _)| *k == &after[..end])?)));
+        match known {
+            Some((end, (_, value))) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Notification kinds `notify.events` can name: the agent's Stop and
+/// PermissionRequest hooks, the `notify_user` MCP tool, and errors ai-pod
+/// reports during a session.
+pub const EVENT_KINDS: &[&str] = &["stop", "permission", "message", "error"];
+
+/// Parse `notify.quiet_hours` (`"HH:MM-HH:MM"`) into minutes after midnight.
+pub fn parse_quiet_hours(s: &str) -> Result<(u32, u32), String> {
+    let minutes = |t: &str| -> Option<u32> {
+        let (h, m) = t.trim().split_once(':')?;
+        let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+        (h < 24 && m < 60).then_some(h * 60 + m)
+    };
+    s.split_once('-')
+        .and_then(|(from, to)| Some((minutes(from)?, minutes(to)?)))
+        .ok_or_else(|| format!("notify.quiet_hours must look like \"22:00-08:00\", got \"{}\"", s))
+}
+
+/// Why a notification is held back under `config`, or `None` to deliver it.
+/// `minute` is the local time of day; `session_age` is how long ago the
+/// session was launched, when known.
+fn suppression(config: &NotifyConfig, kind: &str, minute: u32, session_age: Option<u64>) -> Option<&'static str> {
+    if let Some(Ok((from, to))) = config.quiet_hours.as_deref().map(parse_quiet_hours) {
+        let quiet = if from <= to {
+            (from..to).contains(&minute)
+        } else {
+            minute >= from || minute < to
+        };
+        if quiet {
+            return Some("quiet hours");
+        }
+    }
+    if !config.events.is_empty() && !config.events.iter().any(|e| e == kind) {
+        return Some("filtered by notify.events");
+    }
+    if let (Some(min), Some(age)) = (config.min_session_secs, session_age)
+        && age < min
+    {
+        return Some("session younger than notify.min_session_secs");
+    }
+    None
+}
+
+/// Local time of day in minutes after midnight.
+fn local_minute_of_day() -> u32 {
+    crate::local_time::tm(crate::local_time::now())
+        .map(|tm| (tm.tm_hour * 60 + tm.tm_min) as u32)
+        .unwrap_or(0)
+}
+
+/// Seconds since `session_id` was launched in `workspace`, from its launch
+/// event.
+fn session_age(config_dir: &Path, workspace: &Path, session_id: Option<&str>, now: u64) -> Option<u64> {
+    let id = session_id?;
+    let workspace = workspace.to_string_lossy();
+    events::load(config_dir)
+        .iter()
+        .rev()
+        .find(|e| {
+            e.event == EventKind::Launch
+                && e.session_id.as_deref() == Some(id)
+                && e.workspace.as_deref() == Some(&*workspace)
+        })
+        .map(|e| now.saturating_sub(e.ts))
+}
+
+/// The [`SessionState`] of `session_id` (it comes from the container), if
+/// the session belongs to `workspace`.
+fn session_state(config_dir: &Path, workspace: &Path, session_id: Option<&str>) -> Option<SessionState> {
+    SessionState::load_for(config_dir, session_id?, workspace)
+}
+
+/// Deliver an agent's `notify_user` message (REST or MCP), then record it in
+/// the event log and hand it to the notification plugins in the background.
+/// `session_id` locates the session's tmux pane, if it was launched in tmux;
+/// `kind` is one of `EVENT_KINDS`, checked against the `notify` filters;
+/// `files` are the changed files recorded with a stop notification.
+pub fn notify_user(
+    config_dir: &Path,
+    workspace: &Path,
+    session_id: Option<&str>,
+    kind: &str,
+    message: &str,
+    files: Option<Vec<String>>,
+) {
+    // The container only sends its project id; the server resolved that to
+    // the workspace, so titles name the project even across same-named dirs.
+    let project_name = crate::registry::workspace_label(config_dir, workspace);
+
+    let mut event = Event::new(EventKind::Notification, Some(workspace));
+    event.session_id = session_id.map(str::to_string);
+    event.message = Some(message.to_string());
+    event.files = files;
+    // Recorded even when held back, so `ai-pod notifications` still lists it.
+    events::record(config_dir, &event);
+
+    // Read per notification so `ai-pod config set notify.command ...` applies
+    // without restarting the shared server.
+    let global = GlobalConfig::load_from_dir(config_dir);
+    let age = session_age(config_dir, workspace, session_id, event.ts);
+    if let Some(reason) = suppression(&global.notify, kind, local_minute_of_day(), age) {
+        eprintln!("[notify] Held back {} notification for {} ({})", kind, project_name, reason);
+        return;
+    }
+    let launched = age.map(|age| event.ts.saturating_sub(age));
+    if let Some(reason) = crate::snooze::load(config_dir, workspace).reason(event.ts, launched) {
+        eprintln!("[notify] Held back {} notification for {} ({})", kind, project_name, reason);
+        return;
+    }
+
+    let pending = Pending {
+        project_name,
+        kind: kind.to_string(),
+        message: message.to_string(),
+        duration: age,
+        title: session_state(config_dir, workspace, session_id).and_then(|s| s.title),
+        event,
+    };
+    let window = global.notify.debounce_secs.unwrap_or(DEFAULT_DEBOUNCE_SECS);
+    if window == 0 {
+        deliver(config_dir, workspace, pending, 1);
+        return;
+    }
+
+    // Sub-agents can finish several turns within a second: hold the first
+    // notification for the window and fold any that follow into it.
+    let key = (workspace.to_path_buf(), kind.to_string());
+    {
+        let mut bursts = BURSTS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(burst) = bursts.get_mut(&key) {
+            burst.count += 1;
+            burst.latest = pending;
+            return;
+        }
+        bursts.insert(key.clone(), Burst { count: 1, latest: pending });
+    }
+    let config_dir = config_dir.to_path_buf();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(window)).await;
+        let burst = BURSTS.lock().unwrap_or_else(|e| e.into_inner()).remove(&key);
+        if let Some(Burst { count, latest }) = burst {
+            deliver(&config_dir, &key.0, latest, count);
+        }
+    });
+}
+
+/// Show a notification through the configured backend and the session's
+/// tmux pane, then run the notification plugins. `count` notifications were
+/// folded into `pending`.
+fn deliver(config_dir: &Path, workspace: &Path, pending: Pending, count: usize) {
+    let global = GlobalConfig::load_from_dir(config_dir);
+    let (title, message) = render(&global.notify, workspace, &pending, count);
+    let Pending {
+        project_name,
+        kind,
+        event,
+        ..
+    } = pending;
+    let backend = NotifyBackend::from_config(&global.notify);
+    backend.send(&kind, &title, &message, workspace);
+
+    let tmux_target = session_state(config_dir, workspace, event.session_id.as_deref()).and_then(|s| s.tmux);
+    let config_dir = config_dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if let Some(target) = tmux_target {
+            crate::tmux::notify(&global.tmux, &target, &project_name, &message);
+        }
+        plugins::run_all(&config_dir, plugins::Hook::Notification, &event)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_config_falls_back_to_desktop() {
+        assert_eq!(NotifyBackend::from_config(&NotifyConfig::default()), NotifyBackend::Desktop);
+        let empty = NotifyConfig {
+            command: Some(vec![]),
+            ..Default::default()
+        };
+        assert_eq!(NotifyBackend::from_config(&empty), NotifyBackend::Desktop);
+        let cmd = NotifyConfig {
+            command: Some(vec!["kdeconnect-cli".into(), "--ping-msg".into(), "{message}".into()]),
+            ..Default::default()
+        };
+        assert!(matches!(NotifyBackend::from_config(&cmd), NotifyBackend::Command(_)));
+    }
+
+    #[test]
+    fn expand_args_substitutes_placeholders_per_argument() {
+        let template: Vec<String> = ["notify", "{title}: {message}", "--cwd={workspace}", "$HOME", "{project_id}"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            expand_args(&template, "stop", "ai-pod api", "done; rm -rf /", Path::new("/src/api")),
+            vec![
+                "notify".to_string(),
+                "ai-pod api: done; rm -rf /".into(),
+                "--cwd=/src/api".into(),
+                "$HOME".into(),
+                crate::workspace::workspace_hash(Path::new("/src/api")),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_args_leaves_placeholders_in_values_alone() {
+        let template = vec!["{message} {title}".to_string(), "{nope} {title".to_string()];
+        assert_eq!(
+            expand_args(&template, "stop", "{message}", "see {workspace}", Path::new("/src/api")),
+            vec!["see {workspace} {message}".to_string(), "{nope} {title".into()]
+        );
+    }
+
+    #[test]
+    fn desktop_style_sets_blocking_kinds_apart() {
+        assert_eq!(desktop_style("stop").0, Attention::Low);
+        assert_eq!(desktop_style("message").0, Attention::Normal);
+        assert_eq!(desktop_style("permission").0, Attention::Critical);
+        assert_eq!(desktop_style("error").0, Attention::Critical);
+        assert_ne!(desktop_style("permission").1, desktop_style("stop").1);
+        assert_ne!(desktop_style("error").1, desktop_style("permission").1);
+    }
+
+    #[test]
+    fn parse_quiet_hours_accepts_hh_mm_ranges() {
+        assert_eq!(parse_quiet_hours("22:00-08:00"), Ok((1320, 480)));
+        assert_eq!(parse_quiet_hours(" 9:30 - 17:05 "), Ok((570, 1025)));
+        assert!(parse_quiet_hours("22-08").is_err());
+        assert!(parse_quiet_hours("24:00-08:00").is_err());
+    }
+
+    #[test]
+    fn suppression_applies_quiet_hours_events_and_session_age() {
+        let config = NotifyConfig {
+            quiet_hours: Some("22:00-08:00".into()),
+            events: vec!["stop".into(), "permission".into()],
+            min_session_secs: Some(120),
+            ..Default::default()
+        };
+        let noon = 12 * 60;
+        assert_eq!(suppression(&config, "stop", noon, Some(600)), None);
+        assert_eq!(suppression(&config, "stop", noon, None), None);
+        assert_eq!(suppression(&config, "stop", 23 * 60, Some(600)), Some("quiet hours"));
+        assert_eq!(suppression(&config, "stop", 7 * 60 + 59, Some(600)), Some("quiet hours"));
+        assert_eq!(suppression(&config, "stop", 8 * 60, Some(600)), None);
+        assert!(suppression(&config, "message", noon, Some(600)).is_some());
+        assert!(suppression(&config, "permission", noon, Some(30)).is_some());
+
+        let daytime = NotifyConfig {
+            quiet_hours: Some("12:00-13:00".into()),
+            ..Default::default()
+        };
+        assert!(suppression(&daytime, "message", noon, None).is_some());
+        assert_eq!(suppression(&daytime, "message", 13 * 60, None), None);
+        assert_eq!(suppression(&NotifyConfig::default(), "message", noon, Some(0)), None);
+    }
+
+    fn pending(kind: &str, message: &str) -> Pending {
+        Pending {
+            project_name: "api".into(),
+            kind: kind.into(),
+            message: message.into(),
+            duration: Some(3900),
+            title: None,
+            event: Event::new(EventKind::Notification, None),
+        }
+    }
+
+    #[test]
+    fn burst_message_summarises_by_kind() {
+        let ws = Path::new("/src/api");
+        let body = |kind, count, message| render(&NotifyConfig::default(), ws, &pending(kind, message), count).1;
+        assert_eq!(body("stop", 1, "Task completed"), "Task completed");
+        assert_eq!(body("stop", 3, "Task completed"), "3 tasks finished in api");
+        assert_eq!(body("permission", 2, "Claude needs your approval"), "2 permission requests in api");
+        assert_eq!(body("message", 2, "done"), "2 messages from api; latest: done");
+    }
+
+    #[test]
+    fn render_uses_kind_then_default_templates() {
+        let ws = Path::new("/src/api");
+        let template = |title: Option<&str>, body: Option<&str>| NotifyTemplate {
+            title: title.map(str::to_string),
+            body: body.map(str::to_string),
+            summary: None,
+        };
+        let config = NotifyConfig {
+            templates: [
+                ("default".to_string(), template(Some("[{project}] {event}"), None)),
+                ("stop".to_string(), template(None, Some("Fertig nach {duration}: {message}"))),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&config, ws, &pending("stop", "{count} {x}"), 1),
+            ("[api] stop".to_string(), "Fertig nach 1h 5m: {count} {x}".to_string())
+        );
+        assert_eq!(
+            render(&config, ws, &pending("message", "hi"), 1),
+            ("[api] message".to_string(), "hi".to_string())
+        );
+        assert_eq!(render(&NotifyConfig::default(), ws, &pending("stop", "hi"), 1).0, "ai-pod api");
+        let titled = Pending {
+            title: Some("fix the build".into()),
+            ..pending("stop", "hi")
+        };
+        assert_eq!(render(&NotifyConfig::default(), ws, &titled, 1).0, "ai-pod api: fix the build");
+    }
+
+    #[test]
+    fn template_errors_name_unknown_kinds_and_placeholders() {
+        assert_eq!(
+            check_template("{project}: {message} {}"),
+            Err("unknown placeholder {} in \"{project}: {message} {}\" (expected one of {project}, \
+                 {session_title}, {event}, {message}, {duration}, {count}, {workspace}, {project_id})"
+                .to_string())
+        );
+        assert!(check_template("{duration} {count}").is_ok());
+        let config = NotifyConfig {
+            templates: [
+                ("stopp".to_string(), NotifyTemplate::default()),
+                (
+                    "stop".to_string(),
+                    NotifyTemplate {
+                        summary: Some("{n} done".into()),
+                        ..Default::default()
+                    },
+                ),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let errors = template_errors(&config);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("notify.templates.stop: unknown placeholder {n}"));
+        assert!(errors[1].contains("unknown kind 'stopp'"));
+    }
+
+    #[test]
+    fn format_duration_picks_two_units_at_most() {
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(720), "12m");
+        assert_eq!(format_duration(3900), "1h 5m");
+        assert_eq!(format_duration(2 * 86400 + 3 * 3600 + 59), "2d 3h");
+    }
+
+    #[test]
+    fn spawn_command_runs_argv_without_shell() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let out = dir.path().join("out");
+        let argv = vec![
+            "sh".to_string(),
+            "-c".into(),
+            "printf '%s' \"$1\" > \"$2\"".into(),
+            "sh".into(),
+            "$(whoami)".into(),
+            out.to_string_lossy().to_string(),
+        ];
+        assert!(spawn_command(&argv).unwrap().wait().unwrap().success());
+        assert_eq!(std::fs::read_to_string(out).unwrap(), "$(whoami)");
+    }
+}
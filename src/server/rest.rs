@@ -57,10 +57,63 @@ pub struct ListCommandsResponse2 {
     pub commands: Vec<runner::CommandSummary>,
 }
 
+/// Largest `/notify_user` body accepted; hook payloads are a few hundred bytes.
+pub const MAX_NOTIFY_BODY: usize = 16 * 1024;
+
+/// Longest notification message, in characters.
+pub const MAX_MESSAGE_CHARS: usize = 1000;
+
+/// A hook's notification that arrives this much later than it fired (it was
+/// queued while the server was down) is marked as delayed.
+const DELAYED_NOTICE_SECS: u64 = 60;
+
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct NotifyUserRequest {
     pub project_id: String,
     pub message: String,
+    /// Routes tmux actions to the session's pane; older hooks omit it.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// What triggered the notification; `stop` collects the changed files.
+    #[serde(default)]
+    pub event: Option<String>,
+    /// Unix time the hook fired. Notifications queued while the server was
+    /// unreachable arrive later and say how late they are.
+    #[serde(default)]
+    pub ts: Option<u64>,
+}
+
+/// A message that can go into a desktop notification: non-empty, at most
+/// `MAX_MESSAGE_CHARS`, and no control characters besides newline and tab.
+pub fn validate_message(message: &str) -> Result<(), String> {
+    if message.trim().is_empty() {
+        return Err("message is empty".into());
+    }
+    if message.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(format!("message is longer than {} characters", MAX_MESSAGE_CHARS));
+    }
+    if message.chars().any(|c| c.is_control() && c != '\n' && c != '\t') {
+        return Err("message contains control characters".into());
+    }
+    Ok(())
+}
+
+impl NotifyUserRequest {
+    fn validate(&self) -> Result<(), String> {
+        validate_message(&self.message)?;
+        if let Some(event) = &self.event
+            && !notify::EVENT_KINDS.contains(&event.as_str())
+        {
+            return Err(format!("unknown event '{}'", event));
+        }
+        if let Some(id) = &self.session_id
+            && (id.is_empty() || id.len() > 32 || !id.chars().all(|c| c.is_ascii_alphanumeric()))
+        {
+            return Err("malformed session_id".into());
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize)]
@@ -68,6 +121,15 @@ pub struct NotifyUserResponse {
     pub ok: bool,
 }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenUrlRequest {
+    pub project_id: String,
+    pub url: String,
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct ListAllowedCommandsRequest {
     pub project_id: String,
@@ -78,7 +140,14 @@ pub struct ListAllowedCommandsResponse {
     pub commands: Vec<String>,
 }
 
-fn extract_api_key(headers: &HeaderMap) -> &str {
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub(crate) fn extract_api_key(headers: &HeaderMap) -> &str {
     headers
         .get("x-api-key")
         .and_then(|v| v.to_str().ok())
@@ -211,6 +280,9 @@ pub async fn notify_user_handler(
     headers: HeaderMap,
     Json(req): Json<NotifyUserRequest>,
 ) -> impl IntoResponse {
+    if let Err(msg) = req.validate() {
+        return (StatusCode::BAD_REQUEST, msg).into_response();
+    }
     let provided_key = extract_api_key(&headers).to_string();
 
     let workspace = match authenticate(&state, &req.project_id, &provided_key).await {
@@ -218,13 +290,61 @@ pub async fn notify_user_handler(
         Err((status, msg)) => return (status, msg.to_string()).into_response(),
     };
 
-    let project_name = workspace
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "unknown".to_string());
+    let files = if req.event.as_deref() == Some("stop") {
+        let (config_dir, ws) = (state.config_dir.clone(), workspace.clone());
+        tokio::task::spawn_blocking(move || crate::editor::on_stop(&config_dir, &ws))
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
+    let message = match req.ts.map(|ts| now_secs().saturating_sub(ts)) {
+        Some(late) if late >= DELAYED_NOTICE_SECS => {
+            format!("{} (delayed {})", req.message, notify::format_duration(late))
+        }
+        _ => req.message.clone(),
+    };
+    notify::notify_user(
+        &state.config_dir,
+        &workspace,
+        req.session_id.as_deref(),
+        req.event.as_deref().unwrap_or("message"),
+        &message,
+        files,
+    );
 
-    notify::send_notification(&format!("ai-pod {}", project_name), &req.message);
+    Json(NotifyUserResponse { ok: true }).into_response()
+}
 
+/// `POST /open_url` from the browser shim (see [`crate::browser`]).
+pub async fn open_url_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<OpenUrlRequest>,
+) -> impl IntoResponse {
+    let provided_key = extract_api_key(&headers).to_string();
+    if let Err((status, msg)) = authenticate(&state, &req.project_id, &provided_key).await {
+        return (status, msg.to_string()).into_response();
+    }
+    let global = crate::config::GlobalConfig::load_from_dir(&state.config_dir);
+    if let Err(msg) = crate::browser::check(&req.url, &global.browser) {
+        return (StatusCode::FORBIDDEN, msg).into_response();
+    }
+    if !crate::browser::take_slot(&req.project_id, std::time::Instant::now()) {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            "too many URLs opened in the last minute; hand the URL to the user instead",
+        )
+            .into_response();
+    }
+    let url = req.url.clone();
+    let opened = tokio::task::spawn_blocking(move || crate::browser::open_on_host(&url))
+        .await
+        .unwrap_or(false);
+    if !opened {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "the host could not open a browser").into_response();
+    }
     Json(NotifyUserResponse { ok: true }).into_response()
 }
 
@@ -243,3 +363,39 @@ pub async fn list_allowed_commands_handler(
     let cmds = commands::get_allowed_commands(&state, &workspace);
     Json(ListAllowedCommandsResponse { commands: cmds }).into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(json: serde_json::Value) -> Result<NotifyUserRequest, serde_json::Error> {
+        serde_json::from_value(json)
+    }
+
+    #[test]
+    fn notify_request_rejects_unknown_fields() {
+        assert!(request(serde_json::json!({"project_id": "p", "message": "hi", "extra": 1})).is_err());
+        assert!(request(serde_json::json!({"project_id": "p"})).is_err());
+    }
+
+    #[test]
+    fn notify_request_validates_fields() {
+        let ok = request(serde_json::json!({
+            "project_id": "p", "message": "Task completed", "session_id": "a1b2c3d4", "event": "stop"
+        }))
+        .unwrap();
+        assert!(ok.validate().is_ok());
+
+        let with = |field: &str, value: serde_json::Value| {
+            let mut json = serde_json::json!({"project_id": "p", "message": "hi"});
+            json[field] = value;
+            request(json).unwrap().validate()
+        };
+        assert!(with("message", "  ".into()).is_err());
+        assert!(with("message", "x".repeat(MAX_MESSAGE_CHARS + 1).into()).is_err());
+        assert!(with("message", "line\nnext\ttab".into()).is_ok());
+        assert!(with("message", "bell\u{7}".into()).is_err());
+        assert!(with("event", "shutdown".into()).is_err());
+        assert!(with("session_id", "../x".into()).is_err());
+    }
+}
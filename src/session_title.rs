@@ -0,0 +1,123 @@
+//! Human labels for sessions, so headless jobs and parallel sessions can be
+//! told apart in `ai-pod list`, notifications and the event log. Taken from
+//! `--title`, or from the first words of the prompt of a headless agent run
+//! (`claude -p "..."`, `opencode run "..."`).
+
+/// Container label holding the title.
+pub const LABEL: &str = "ai-pod.title";
+
+/// Words of the prompt kept in a derived title.
+const PROMPT_WORDS: usize = 6;
+
+/// Longest title kept, in characters.
+const MAX_CHARS: usize = 60;
+
+/// `text` on one line, without control characters, cut to [`MAX_CHARS`].
+/// `None` when nothing is left.
+pub fn normalize(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|w| w.chars().filter(|c| !c.is_control()).collect())
+        .collect();
+    let line = words.join(" ");
+    if line.is_empty() {
+        return None;
+    }
+    if line.chars().count() <= MAX_CHARS {
+        return Some(line);
+    }
+    let cut: String = line.chars().take(MAX_CHARS - 1).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
+/// A title from the first words of `prompt`.
+pub fn from_prompt(prompt: &str) -> Option<String> {
+    let mut words = prompt.split_whitespace();
+    let head: Vec<&str> = words.by_ref().take(PROMPT_WORDS).collect();
+    let more = words.next().is_some();
+    let title = normalize(&head.join(" "))?;
+    Some(if more && !title.ends_with('…') {
+        format!("{}…", title)
+    } else {
+        title
+    })
+}
+
+/// The prompt of a headless agent command: the first argument with a space
+/// in it after `claude`'s `-p`/`--print` or `opencode run`, else the first
+/// plain argument after them.
+pub fn prompt_in(command: &str, args: &[String]) -> Option<String> {
+    let start = match command {
+        "claude" => args.iter().position(|a| a == "-p" || a == "--print")? + 1,
+        "opencode" => args.iter().position(|a| a == "run")? + 1,
+        _ => return None,
+    };
+    let rest = &args[start..];
+    rest.iter()
+        .filter(|a| !a.starts_with('-'))
+        .find(|a| a.contains(char::is_whitespace))
+        .or_else(|| rest.iter().find(|a| !a.starts_with('-')))
+        .cloned()
+}
+
+/// The session's title: `explicit` if given, else derived from a headless
+/// agent command's prompt.
+pub fn resolve(explicit: Option<&str>, command: &str, args: &[String]) -> Option<String> {
+    match explicit {
+        Some(title) => normalize(title),
+        None => prompt_in(command, args).as_deref().and_then(from_prompt),
+    }
+}
+
+/// `--label ai-pod.title=<title>`, when there is one.
+pub fn label_args(title: Option<&str>) -> Vec<String> {
+    title
+        .map(|t| vec!["--label".to_string(), format!("{}={}", LABEL, t)])
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn from_prompt_keeps_the_first_words() {
+        assert_eq!(
+            from_prompt("  Make the\nfailing tests in src/api pass again ").as_deref(),
+            Some("Make the failing tests in src/api…")
+        );
+        assert_eq!(from_prompt("fix lint").as_deref(), Some("fix lint"));
+        assert_eq!(from_prompt(" \n"), None);
+    }
+
+    #[test]
+    fn normalize_strips_control_characters_and_caps_length() {
+        assert_eq!(normalize("nightly\x1b]0;x\x07 deps").as_deref(), Some("nightly]0;x deps"));
+        let long = normalize(&"word ".repeat(30)).unwrap();
+        assert_eq!(long.chars().count(), 60);
+        assert!(long.ends_with("word…"));
+    }
+
+    #[test]
+    fn prompt_in_finds_headless_agent_prompts() {
+        let claude = strings(&["--model", "opus", "-p", "--output-format", "json", "review the diff"]);
+        assert_eq!(prompt_in("claude", &claude).as_deref(), Some("review the diff"));
+        assert_eq!(prompt_in("claude", &strings(&["-p", "lint"])).as_deref(), Some("lint"));
+        assert_eq!(prompt_in("opencode", &strings(&["run", "bump deps"])).as_deref(), Some("bump deps"));
+        assert_eq!(prompt_in("claude", &strings(&["review the diff"])), None);
+        assert_eq!(prompt_in("make", &strings(&["-p", "test"])), None);
+    }
+
+    #[test]
+    fn resolve_prefers_the_explicit_title() {
+        let args = strings(&["-p", "fix the build"]);
+        assert_eq!(resolve(Some(" nightly "), "claude", &args).as_deref(), Some("nightly"));
+        assert_eq!(resolve(None, "claude", &args).as_deref(), Some("fix the build"));
+        assert_eq!(label_args(Some("fix")), strings(&["--label", "ai-pod.title=fix"]));
+        assert!(label_args(None).is_empty());
+    }
+}
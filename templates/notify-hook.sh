@@ -0,0 +1,50 @@
+# ai-pod notification hook, seeded into the home volume as
+# ~/.ai-pod/notify.sh and run by Claude Code's hooks:
+#
+#   sh ~/.ai-pod/notify.sh EVENT MESSAGE   post a notification
+#   sh ~/.ai-pod/notify.sh --flush         only send what is queued
+#
+# While the ai-pod server is unreachable, notifications are appended to
+# ~/.ai-pod/notify-queue (one JSON payload per line, newest 100 kept) and
+# sent, oldest first, by the next hook that gets through. Always exits 0 so
+# a missing server never fails the agent's hook.
+
+queue="$HOME/.ai-pod/notify-queue"
+max_queued=100
+
+# Fails only when the server can't be reached. A payload the server answers
+# with an HTTP error (curl's exit 22) is dropped rather than retried forever.
+post() {
+    curl -fsS --connect-timeout 2 --max-time 5 -X POST \
+        -H "X-Api-Key: $AI_POD_API_KEY" -H 'Content-Type: application/json' \
+        -d "$1" "$AI_POD_SERVER_URL/notify_user" >/dev/null 2>&1
+    rc=$?
+    [ "$rc" -eq 0 ] || [ "$rc" -eq 22 ]
+}
+
+mkdir -p "${queue%/*}" 2>/dev/null
+
+# Send the queue first so notifications arrive in order. After the first
+# failure the rest are put back untried, so a down server costs one timeout.
+if [ -s "$queue" ]; then
+    sending="$queue.$$"
+    mv "$queue" "$sending" 2>/dev/null || exit 0
+    down=
+    while IFS= read -r payload; do
+        if [ -n "$down" ] || ! post "$payload"; then
+            down=1
+            printf '%s\n' "$payload" >>"$queue"
+        fi
+    done <"$sending"
+    rm -f "$sending"
+fi
+
+[ "$1" = "--flush" ] && exit 0
+
+payload=$(printf '{"project_id":"%s","session_id":"%s","event":"%s","message":"%s","ts":%s}' \
+    "$AI_POD_PROJECT_ID" "$AI_POD_SESSION_ID" "$1" "$2" "$(date +%s)")
+if [ -n "$down" ] || ! post "$payload"; then
+    printf '%s\n' "$payload" >>"$queue"
+    tail -n "$max_queued" "$queue" >"$queue.tmp" 2>/dev/null && mv "$queue.tmp" "$queue"
+fi
+exit 0
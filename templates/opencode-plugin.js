@@ -2,6 +2,7 @@ export const AiPodPlugin = async ({ project }) => {
   const url = (process.env.AI_POD_SERVER_URL || "").replace(/\/+$/, "");
   const apiKey = process.env.AI_POD_API_KEY || "";
   const projectId = process.env.AI_POD_PROJECT_ID || "";
+  const sessionId = process.env.AI_POD_SESSION_ID || "";
   return {
     "session.idle": async () => {
       if (!url || !apiKey || !projectId) return;
@@ -15,6 +16,7 @@ export const AiPodPlugin = async ({ project }) => {
           },
           body: JSON.stringify({
             project_id: projectId,
+            session_id: sessionId || undefined,
             message: `${title}: Task completed`,
           }),
         });
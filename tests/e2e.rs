@@ -413,7 +413,12 @@ fn e2e_clean_container_removes_all() {
 
     // Production clean_container should remove both
     let (_cfg_dir, cfg) = make_test_config();
-    container::clean_container(&rt, &cfg, ws.path()).unwrap();
+    let opts = container::CleanOptions {
+        containers: true,
+        volumes: true,
+        image: false,
+    };
+    container::clean_container(&rt, &cfg, ws.path(), opts).unwrap();
 
     assert!(
         !container::volume_exists(&rt, &vol).unwrap(),
@@ -0,0 +1,88 @@
+//! Integration test for input hardening on `/notify_user`: oversized bodies,
+//! non-JSON content types and invalid payloads are rejected with a 4xx
+//! before the request is authenticated or delivered.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use ai_pod::runtime::{ContainerRuntime, RuntimeKind};
+use ai_pod::server::{AppState, build_app};
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+fn make_state(config_dir: &std::path::Path) -> AppState {
+    AppState {
+        projects: Arc::new(Mutex::new(HashMap::new())),
+        config_dir: config_dir.to_path_buf(),
+        approval_lock: Arc::new(Mutex::new(())),
+        commands: Arc::new(Mutex::new(HashMap::new())),
+        runtime: ContainerRuntime {
+            kind: RuntimeKind::Podman,
+            dry_run: false,
+        },
+        keep_alive_until: Arc::new(Mutex::new(
+            std::time::Instant::now() + std::time::Duration::from_secs(30),
+        )),
+        egress_limits: Arc::new(Mutex::new(HashMap::new())),
+    }
+}
+
+#[tokio::test]
+async fn notify_rejects_malformed_requests() {
+    let dir = TempDir::new().unwrap();
+    let app = build_app(make_state(dir.path()));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/notify_user", addr);
+    let post_json = |body: String| {
+        client
+            .post(&url)
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+    };
+
+    let big = serde_json::json!({"project_id": "p", "message": "x".repeat(64 * 1024)});
+    assert_eq!(post_json(big.to_string()).await.unwrap().status(), 413);
+
+    let resp = client
+        .post(&url)
+        .header("content-type", "text/plain")
+        .body(r#"{"project_id":"p","message":"hi"}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 415);
+
+    let unknown = serde_json::json!({"project_id": "p", "message": "hi", "html": "<b>"});
+    assert_eq!(post_json(unknown.to_string()).await.unwrap().status(), 422);
+
+    let bad_event = serde_json::json!({"project_id": "p", "message": "hi", "event": "rm"});
+    assert_eq!(post_json(bad_event.to_string()).await.unwrap().status(), 400);
+
+    // Well-formed, so it reaches authentication.
+    let ok = serde_json::json!({"project_id": "p", "message": "hi", "event": "stop"});
+    assert_eq!(post_json(ok.to_string()).await.unwrap().status(), 404);
+
+    // Every request above is in the access log, the last one with its event.
+    let log = std::fs::read_to_string(dir.path().join("access.log")).unwrap();
+    let entries: Vec<serde_json::Value> = log.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(entries.len(), 5);
+    assert_eq!(entries[0]["status"], 413);
+    let last = &entries[4];
+    assert_eq!(last["path"], "/notify_user");
+    assert_eq!(last["event"], "stop");
+    assert_eq!(last["status"], 404);
+    assert_eq!(last["peer"], "127.0.0.1");
+}
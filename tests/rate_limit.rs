@@ -27,6 +27,7 @@ fn make_state(config_dir: &std::path::Path) -> AppState {
         keep_alive_until: Arc::new(Mutex::new(
             std::time::Instant::now() + std::time::Duration::from_secs(30),
         )),
+        egress_limits: Arc::new(Mutex::new(HashMap::new())),
     }
 }
 